@@ -0,0 +1,89 @@
+//! getProgramAccounts filters
+//!
+//! Memcmp filters on the account discriminator and the fixed-offset key
+//! fields of StakingPool and UserStake. No `dataSize` filter is used:
+//! legacy accounts are smaller than the current layout but keep the same
+//! offsets.
+
+use chiefstaker::state::{
+    StakingPool, UserStake, POOL_DISCRIMINATOR, USER_STAKE_DISCRIMINATOR,
+};
+use solana_rpc_client_api::filter::{Memcmp, RpcFilterType};
+use solana_sdk::pubkey::Pubkey;
+
+fn memcmp(offset: usize, bytes: &[u8]) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(offset, bytes))
+}
+
+/// Every staking pool
+pub fn pools() -> Vec<RpcFilterType> {
+    vec![memcmp(0, &POOL_DISCRIMINATOR)]
+}
+
+/// Every pool of `mint` (pool 0 and any created with InitializePoolWithId)
+pub fn pools_by_mint(mint: &Pubkey) -> Vec<RpcFilterType> {
+    vec![
+        memcmp(0, &POOL_DISCRIMINATOR),
+        memcmp(StakingPool::MINT_OFFSET, mint.as_ref()),
+    ]
+}
+
+/// Every pool administered by `authority`. Renounced pools have the
+/// default pubkey as authority.
+pub fn pools_by_authority(authority: &Pubkey) -> Vec<RpcFilterType> {
+    vec![
+        memcmp(0, &POOL_DISCRIMINATOR),
+        memcmp(StakingPool::AUTHORITY_OFFSET, authority.as_ref()),
+    ]
+}
+
+/// Every position in `pool`, including emptied ones not yet closed
+pub fn stakes_by_pool(pool: &Pubkey) -> Vec<RpcFilterType> {
+    vec![
+        memcmp(0, &USER_STAKE_DISCRIMINATOR),
+        memcmp(UserStake::POOL_OFFSET, pool.as_ref()),
+    ]
+}
+
+/// Every position held by `owner`, across pools
+pub fn stakes_by_owner(owner: &Pubkey) -> Vec<RpcFilterType> {
+    vec![
+        memcmp(0, &USER_STAKE_DISCRIMINATOR),
+        memcmp(UserStake::OWNER_OFFSET, owner.as_ref()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filters_match_accounts() {
+        let (mint, authority, owner) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let pool = StakingPool::new(mint, Pubkey::new_unique(), Pubkey::new_unique(), authority, 86_400, 0, 255);
+        let pool_key = Pubkey::new_unique();
+        let stake = UserStake::new(owner, pool_key, 1, 0, 0, 255, 0);
+        let pool_data = borsh::to_vec(&pool).unwrap();
+        let stake_data = borsh::to_vec(&stake).unwrap();
+
+        let matches = |filters: Vec<RpcFilterType>, data: &[u8]| {
+            filters.iter().all(|filter| match filter {
+                RpcFilterType::Memcmp(memcmp) => memcmp.bytes_match(data),
+                _ => false,
+            })
+        };
+        assert!(matches(pools(), &pool_data));
+        assert!(matches(pools_by_mint(&mint), &pool_data));
+        assert!(matches(pools_by_authority(&authority), &pool_data));
+        assert!(matches(stakes_by_pool(&pool_key), &stake_data));
+        assert!(matches(stakes_by_owner(&owner), &stake_data));
+
+        // Wrong key or wrong account type
+        assert!(!matches(pools_by_mint(&authority), &pool_data));
+        assert!(!matches(stakes_by_owner(&owner), &pool_data));
+        assert!(!matches(pools(), &stake_data));
+
+        // Legacy pools keep the same offsets
+        assert!(matches(pools_by_authority(&authority), &pool_data[..StakingPool::LEGACY_LEN]));
+    }
+}
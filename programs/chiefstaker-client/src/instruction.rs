@@ -0,0 +1,174 @@
+//! Instruction builders
+//!
+//! Data is packed with the sighash discriminator
+//! ([`StakingInstruction::pack`]).
+
+use chiefstaker::state::{StakingPool, UserStake};
+use chiefstaker_interface::{instruction::StakingInstruction, pda};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+
+/// Authority instruction accounts: pool, authority, then system program and
+/// log for pools with an authority log (the authority pays for its growth)
+fn authority_accounts(
+    pool: &Pubkey,
+    authority: &Pubkey,
+    authority_log: Option<&Pubkey>,
+) -> Vec<AccountMeta> {
+    let mut accounts = vec![AccountMeta::new(*pool, false)];
+    match authority_log {
+        Some(log) => accounts.extend([
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new(*log, false),
+        ]),
+        None => accounts.push(AccountMeta::new_readonly(*authority, true)),
+    }
+    accounts
+}
+
+/// UpdatePoolSettings; `None` fields are left unchanged.
+/// Pass `authority_log` for pools with an authority log.
+pub fn update_pool_settings(
+    pool: &Pubkey,
+    authority: &Pubkey,
+    authority_log: Option<&Pubkey>,
+    min_stake_amount: Option<u64>,
+    lock_duration_seconds: Option<u64>,
+    unstake_cooldown_seconds: Option<u64>,
+) -> Instruction {
+    Instruction {
+        program_id: chiefstaker_interface::ID,
+        accounts: authority_accounts(pool, authority, authority_log),
+        data: StakingInstruction::UpdatePoolSettings {
+            min_stake_amount,
+            lock_duration_seconds,
+            unstake_cooldown_seconds,
+        }
+        .pack(),
+    }
+}
+
+/// TransferAuthority; `Pubkey::default()` renounces authority.
+/// Pass `authority_log` for pools with an authority log.
+pub fn transfer_authority(
+    pool: &Pubkey,
+    authority: &Pubkey,
+    authority_log: Option<&Pubkey>,
+    new_authority: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: chiefstaker_interface::ID,
+        accounts: authority_accounts(pool, authority, authority_log),
+        data: StakingInstruction::TransferAuthority {
+            new_authority: *new_authority,
+        }
+        .pack(),
+    }
+}
+
+/// RenounceAuthorityUnsafe: renounce even with a lock or cooldown above
+/// 7 days. Pass `authority_log` for pools with an authority log.
+pub fn renounce_authority_unsafe(
+    pool: &Pubkey,
+    authority: &Pubkey,
+    authority_log: Option<&Pubkey>,
+) -> Instruction {
+    Instruction {
+        program_id: chiefstaker_interface::ID,
+        accounts: authority_accounts(pool, authority, authority_log),
+        data: StakingInstruction::RenounceAuthorityUnsafe.pack(),
+    }
+}
+
+/// Stake. `pool_state` decides the conditional accounts: receipt pools pass
+/// the receipt mint and `user_receipt_token` (required there), pools with a
+/// rent sponsor its vault. `position` is the user's existing stake, if any,
+/// whose weight history is then passed. A new position passes the pool
+/// metadata when `with_metadata` is set, so member_count counts it (the
+/// program skips the count silently without it); [`crate::rpc::stake`]
+/// looks all of this up.
+#[allow(clippy::too_many_arguments)]
+pub fn stake(
+    pool: &Pubkey,
+    pool_state: &StakingPool,
+    user: &Pubkey,
+    user_token: &Pubkey,
+    user_receipt_token: Option<&Pubkey>,
+    position: Option<&UserStake>,
+    with_metadata: bool,
+    amount: u64,
+) -> Instruction {
+    let (user_stake, _) = pda::find_stake_address(pool, user);
+    let mut accounts = vec![
+        AccountMeta::new(*pool, false),
+        AccountMeta::new(user_stake, false),
+        AccountMeta::new(pool_state.token_vault, false),
+        AccountMeta::new(*user_token, false),
+        AccountMeta::new_readonly(pool_state.mint, false),
+        AccountMeta::new(*user, true),
+        AccountMeta::new_readonly(system_program::ID, false),
+        AccountMeta::new_readonly(spl_token_2022::id(), false),
+    ];
+    if pool_state.has_receipt_mint() {
+        accounts.push(AccountMeta::new(pool_state.receipt_mint, false));
+        if let Some(user_receipt_token) = user_receipt_token {
+            accounts.push(AccountMeta::new(*user_receipt_token, false));
+        }
+    }
+    if pool_state.has_rent_sponsor {
+        accounts.push(AccountMeta::new(pda::find_rent_sponsor_address(pool).0, false));
+    }
+    match position {
+        Some(position) => {
+            if position.has_weight_history {
+                let (history, _) = pda::find_weight_history_address(pool, user);
+                accounts.push(AccountMeta::new(history, false));
+            }
+        }
+        None => {
+            if with_metadata {
+                accounts.push(AccountMeta::new(pda::find_metadata_address(pool).0, false));
+            }
+        }
+    }
+    Instruction {
+        program_id: chiefstaker_interface::ID,
+        accounts,
+        data: StakingInstruction::Stake { amount }.pack(),
+    }
+}
+
+/// CloseStakeAccount for `owner`'s emptied `position`. Sponsored positions
+/// return their rent to the pool's rent sponsor vault. `with_metadata`
+/// passes the pool metadata so member_count drops (the program skips the
+/// count silently without it); [`crate::rpc::close_stake_account`] looks
+/// this up.
+pub fn close_stake_account(
+    pool: &Pubkey,
+    pool_state: &StakingPool,
+    position: &UserStake,
+    owner: &Pubkey,
+    with_metadata: bool,
+) -> Instruction {
+    let (user_stake, _) = pda::find_stake_address(pool, owner);
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*pool, false),
+        AccountMeta::new(user_stake, false),
+        AccountMeta::new(*owner, true),
+    ];
+    if position.sponsored_rent > 0 && pool_state.has_rent_sponsor {
+        accounts.push(AccountMeta::new(pda::find_rent_sponsor_address(pool).0, false));
+    }
+    if with_metadata {
+        accounts.push(AccountMeta::new(pda::find_metadata_address(pool).0, false));
+    }
+    Instruction {
+        program_id: chiefstaker_interface::ID,
+        accounts,
+        data: StakingInstruction::CloseStakeAccount.pack(),
+    }
+}
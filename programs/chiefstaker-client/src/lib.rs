@@ -0,0 +1,16 @@
+//! ChiefStaker client
+//!
+//! Instruction builders and transaction assembly for off-chain callers,
+//! including durable-nonce transactions that cold-storage authorities can
+//! sign offline and hand back as base64, and builders that look up their
+//! optional accounts over RPC.
+
+pub mod filters;
+pub mod instruction;
+pub mod offline;
+pub mod rpc;
+
+pub use chiefstaker_interface as interface;
+
+/// Off-chain dry-run of a reward deposit against a pool snapshot.
+pub use chiefstaker::math::{simulate_distribution, DistributionSimulation};
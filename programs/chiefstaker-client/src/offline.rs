@@ -0,0 +1,157 @@
+//! Durable-nonce transactions for offline signing
+//!
+//! An online machine reads the nonce account and assembles the transaction
+//! with [`nonce_transaction`], then exports it with [`to_base64`]. Each
+//! offline signer imports it with [`from_base64`], calls [`partial_sign`]
+//! and exports it again; once [`missing_signers`] is empty the transaction
+//! can be broadcast. The stored nonce stays valid until it is advanced, so
+//! signing isn't bound to the ~2 minute blockhash lifetime.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    message::Message,
+    nonce::state::{State, Versions},
+    pubkey::Pubkey,
+    signer::{Signer, SignerError},
+    system_instruction,
+    transaction::Transaction,
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum OfflineError {
+    #[error("Account is not an initialized nonce account")]
+    InvalidNonceAccount,
+
+    #[error("Transaction encoding failed: {0}")]
+    Encoding(#[from] bincode::Error),
+
+    #[error("Invalid base64: {0}")]
+    Base64(#[from] base64::DecodeError),
+
+    #[error("Signing failed: {0}")]
+    Signer(#[from] SignerError),
+}
+
+/// Durable nonce stored in a nonce account's data
+pub fn nonce_blockhash(nonce_account_data: &[u8]) -> Result<Hash, OfflineError> {
+    let versions: Versions =
+        bincode::deserialize(nonce_account_data).map_err(|_| OfflineError::InvalidNonceAccount)?;
+    match versions.state() {
+        State::Initialized(data) => Ok(data.blockhash()),
+        State::Uninitialized => Err(OfflineError::InvalidNonceAccount),
+    }
+}
+
+/// Unsigned transaction that advances `nonce_account` first and uses
+/// `nonce` (see [`nonce_blockhash`]) as its recent blockhash
+pub fn nonce_transaction(
+    instructions: &[Instruction],
+    fee_payer: &Pubkey,
+    nonce_account: &Pubkey,
+    nonce_authority: &Pubkey,
+    nonce: Hash,
+) -> Transaction {
+    let mut all = Vec::with_capacity(instructions.len() + 1);
+    all.push(system_instruction::advance_nonce_account(
+        nonce_account,
+        nonce_authority,
+    ));
+    all.extend_from_slice(instructions);
+    Transaction::new_unsigned(Message::new_with_blockhash(&all, Some(fee_payer), &nonce))
+}
+
+/// Add the signatures of `signers`, leaving other signatures untouched.
+/// Fails if a signer is not a required signer of the transaction.
+pub fn partial_sign<T: Signer>(
+    transaction: &mut Transaction,
+    signers: &[&T],
+) -> Result<(), OfflineError> {
+    let blockhash = transaction.message.recent_blockhash;
+    transaction.try_partial_sign(signers, blockhash)?;
+    Ok(())
+}
+
+/// Required signers whose signature is still missing
+pub fn missing_signers(transaction: &Transaction) -> Vec<Pubkey> {
+    let required = transaction.message.header.num_required_signatures as usize;
+    transaction
+        .message
+        .account_keys
+        .iter()
+        .zip(&transaction.signatures)
+        .take(required)
+        .filter(|(_, signature)| **signature == Default::default())
+        .map(|(key, _)| *key)
+        .collect()
+}
+
+/// Wire-format transaction as base64 (what `sendTransaction` accepts)
+pub fn to_base64(transaction: &Transaction) -> Result<String, OfflineError> {
+    Ok(STANDARD.encode(bincode::serialize(transaction)?))
+}
+
+/// Parse a transaction exported with [`to_base64`]
+pub fn from_base64(encoded: &str) -> Result<Transaction, OfflineError> {
+    Ok(bincode::deserialize(&STANDARD.decode(encoded.trim())?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::transfer_authority;
+    use solana_sdk::{
+        nonce::state::{Data, DurableNonce},
+        signature::Keypair,
+    };
+
+    #[test]
+    fn test_offline_round_trip() {
+        let fee_payer = Keypair::new();
+        let authority = Keypair::new();
+        let nonce_account = Pubkey::new_unique();
+
+        // Nonce as read from the nonce account
+        let durable = DurableNonce::from_blockhash(&Hash::new_unique());
+        let data = Data::new(fee_payer.pubkey(), durable, 5_000);
+        let account_data =
+            bincode::serialize(&Versions::new(State::Initialized(data))).unwrap();
+        let nonce = nonce_blockhash(&account_data).unwrap();
+        assert_eq!(nonce, *durable.as_hash());
+        assert!(nonce_blockhash(&[0u8; 4]).is_err());
+
+        let ix = transfer_authority(
+            &Pubkey::new_unique(),
+            &authority.pubkey(),
+            None,
+            &Pubkey::new_unique(),
+        );
+        let tx = nonce_transaction(
+            &[ix],
+            &fee_payer.pubkey(),
+            &nonce_account,
+            &fee_payer.pubkey(),
+            nonce,
+        );
+        assert_eq!(tx.message.recent_blockhash, nonce);
+        assert_eq!(
+            missing_signers(&tx),
+            vec![fee_payer.pubkey(), authority.pubkey()]
+        );
+
+        // Cold authority signs first, fee payer later on another machine
+        let mut tx = from_base64(&to_base64(&tx).unwrap()).unwrap();
+        partial_sign(&mut tx, &[&authority]).unwrap();
+        assert_eq!(missing_signers(&tx), vec![fee_payer.pubkey()]);
+
+        let mut tx = from_base64(&to_base64(&tx).unwrap()).unwrap();
+        partial_sign(&mut tx, &[&fee_payer]).unwrap();
+        assert!(missing_signers(&tx).is_empty());
+        tx.verify().unwrap();
+
+        // Strangers can't sign
+        assert!(partial_sign(&mut tx, &[&Keypair::new()]).is_err());
+    }
+}
@@ -0,0 +1,248 @@
+//! Instruction builders that look up their optional accounts over RPC
+//!
+//! The pool, the caller's position and the pool's PoolMetadata are read in
+//! one getMultipleAccounts call, so accounts the program only uses when
+//! passed (like the metadata behind member_count) are included whenever
+//! they exist.
+
+use borsh::BorshDeserialize;
+use chiefstaker::state::{StakingPool, UserStake};
+use chiefstaker_interface::pda;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_rpc_client_api::client_error::Error as ClientError;
+use solana_sdk::{account::Account, instruction::Instruction, pubkey::Pubkey};
+use thiserror::Error;
+
+use crate::instruction;
+
+#[derive(Error, Debug)]
+pub enum RpcBuildError {
+    #[error("RPC client: {0}")]
+    Rpc(Box<ClientError>),
+
+    #[error("Account {0} does not exist")]
+    AccountNotFound(Pubkey),
+
+    #[error("Account {0} is not an initialized ChiefStaker account")]
+    InvalidAccount(Pubkey),
+}
+
+impl From<ClientError> for RpcBuildError {
+    fn from(e: ClientError) -> Self {
+        RpcBuildError::Rpc(Box::new(e))
+    }
+}
+
+/// A pool as seen by one owner
+#[derive(Debug, Clone)]
+pub struct PoolSnapshot {
+    pub pool: StakingPool,
+
+    /// The owner's stake account, if it exists
+    pub position: Option<UserStake>,
+
+    /// Whether the pool has a PoolMetadata account
+    pub has_metadata: bool,
+}
+
+/// Read `pool`, `owner`'s position in it and whether it has metadata
+pub async fn fetch_pool_snapshot(
+    client: &RpcClient,
+    pool: &Pubkey,
+    owner: &Pubkey,
+) -> Result<PoolSnapshot, RpcBuildError> {
+    let (user_stake, _) = pda::find_stake_address(pool, owner);
+    let (metadata, _) = pda::find_metadata_address(pool);
+    let accounts = client
+        .get_multiple_accounts_with_commitment(&[*pool, user_stake, metadata], client.commitment())
+        .await?
+        .value;
+    decode_snapshot(pool, &user_stake, &accounts)
+}
+
+/// Decode the accounts fetched by [`fetch_pool_snapshot`]: pool, user
+/// stake, metadata. Accounts not owned by the program count as missing.
+fn decode_snapshot(
+    pool: &Pubkey,
+    user_stake: &Pubkey,
+    accounts: &[Option<Account>],
+) -> Result<PoolSnapshot, RpcBuildError> {
+    let owned = |i: usize| {
+        accounts
+            .get(i)
+            .and_then(Option::as_ref)
+            .filter(|account| {
+                account.owner == chiefstaker_interface::ID && !account.data.is_empty()
+            })
+    };
+
+    let pool_account = owned(0).ok_or(RpcBuildError::AccountNotFound(*pool))?;
+    let pool_state = StakingPool::try_from_slice(&pool_account.data)
+        .ok()
+        .filter(StakingPool::is_initialized)
+        .ok_or(RpcBuildError::InvalidAccount(*pool))?;
+
+    let position = match owned(1) {
+        Some(account) => Some(
+            UserStake::try_from_slice(&account.data)
+                .ok()
+                .filter(UserStake::is_initialized)
+                .ok_or(RpcBuildError::InvalidAccount(*user_stake))?,
+        ),
+        None => None,
+    };
+
+    Ok(PoolSnapshot {
+        pool: pool_state,
+        position,
+        has_metadata: owned(2).is_some(),
+    })
+}
+
+/// [`instruction::stake`] with its conditional accounts looked up.
+/// `user_receipt_token` is required on receipt pools.
+pub async fn stake(
+    client: &RpcClient,
+    pool: &Pubkey,
+    user: &Pubkey,
+    user_token: &Pubkey,
+    user_receipt_token: Option<&Pubkey>,
+    amount: u64,
+) -> Result<Instruction, RpcBuildError> {
+    let snapshot = fetch_pool_snapshot(client, pool, user).await?;
+    Ok(instruction::stake(
+        pool,
+        &snapshot.pool,
+        user,
+        user_token,
+        user_receipt_token,
+        snapshot.position.as_ref(),
+        snapshot.has_metadata,
+        amount,
+    ))
+}
+
+/// [`instruction::close_stake_account`] with its conditional accounts
+/// looked up
+pub async fn close_stake_account(
+    client: &RpcClient,
+    pool: &Pubkey,
+    owner: &Pubkey,
+) -> Result<Instruction, RpcBuildError> {
+    let snapshot = fetch_pool_snapshot(client, pool, owner).await?;
+    let position = snapshot
+        .position
+        .ok_or_else(|| RpcBuildError::AccountNotFound(pda::find_stake_address(pool, owner).0))?;
+    Ok(instruction::close_stake_account(
+        pool,
+        &snapshot.pool,
+        &position,
+        owner,
+        snapshot.has_metadata,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn program_account(data: Vec<u8>) -> Option<Account> {
+        Some(Account {
+            lamports: 1,
+            data,
+            owner: chiefstaker_interface::ID,
+            executable: false,
+            rent_epoch: 0,
+        })
+    }
+
+    #[test]
+    fn test_metadata_included_when_present() {
+        let mint = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        let user_token = Pubkey::new_unique();
+        let (pool, _) = pda::find_pool_address(&mint, 0);
+        let (user_stake, _) = pda::find_stake_address(&pool, &user);
+        let (metadata, _) = pda::find_metadata_address(&pool);
+        let (history, _) = pda::find_weight_history_address(&pool, &user);
+        let pool_state = StakingPool::new(
+            mint,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            86_400,
+            0,
+            255,
+        );
+        let pool_data = borsh::to_vec(&pool_state).unwrap();
+
+        // New position on a pool with metadata: the metadata is passed last
+        let accounts = [program_account(pool_data.clone()), None, program_account(vec![1])];
+        let snapshot = decode_snapshot(&pool, &user_stake, &accounts).unwrap();
+        assert!(snapshot.position.is_none() && snapshot.has_metadata);
+        let ix =
+            instruction::stake(&pool, &snapshot.pool, &user, &user_token, None, None, true, 10);
+        assert_eq!(ix.accounts.len(), 9);
+        assert_eq!(ix.accounts[8].pubkey, metadata);
+        assert!(ix.accounts[8].is_writable);
+
+        // Existing positions pass their weight history instead (the
+        // program only counts new members)
+        let mut position = UserStake::new(user, pool, 10, 0, 0, 255, 0);
+        position.has_weight_history = true;
+        let accounts = [
+            program_account(pool_data.clone()),
+            program_account(borsh::to_vec(&position).unwrap()),
+            program_account(vec![1]),
+        ];
+        let snapshot = decode_snapshot(&pool, &user_stake, &accounts).unwrap();
+        let ix = instruction::stake(
+            &pool,
+            &snapshot.pool,
+            &user,
+            &user_token,
+            None,
+            snapshot.position.as_ref(),
+            snapshot.has_metadata,
+            10,
+        );
+        assert_eq!(ix.accounts.len(), 9);
+        assert_eq!(ix.accounts[8].pubkey, history);
+
+        let ix = instruction::close_stake_account(
+            &pool,
+            &snapshot.pool,
+            &position,
+            &user,
+            snapshot.has_metadata,
+        );
+        assert_eq!(ix.accounts.len(), 4);
+        assert_eq!(ix.accounts[3].pubkey, metadata);
+
+        // No metadata account, or one not owned by the program: nothing extra
+        let mut foreign = program_account(vec![1]);
+        foreign.as_mut().unwrap().owner = Pubkey::new_unique();
+        for metadata_account in [None, foreign] {
+            let accounts = [program_account(pool_data.clone()), None, metadata_account];
+            let snapshot = decode_snapshot(&pool, &user_stake, &accounts).unwrap();
+            assert!(!snapshot.has_metadata);
+            let ix = instruction::stake(
+                &pool,
+                &snapshot.pool,
+                &user,
+                &user_token,
+                None,
+                None,
+                snapshot.has_metadata,
+                10,
+            );
+            assert_eq!(ix.accounts.len(), 8);
+        }
+
+        // The pool itself must exist
+        assert!(matches!(
+            decode_snapshot(&pool, &user_stake, &[None, None, None]),
+            Err(RpcBuildError::AccountNotFound(key)) if key == pool
+        ));
+    }
+}
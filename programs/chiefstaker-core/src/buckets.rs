@@ -0,0 +1,393 @@
+//! Age-bucketed weighting
+//!
+//! An alternative to the continuous `1 - e^(-age/τ)` weight: stake is
+//! tracked in discrete age buckets of `bucket_seconds` each, and every
+//! bucket has a fixed weight. Rolling the buckets forward (once per elapsed
+//! bucket period, by a crank or by any operation) moves each cohort one
+//! bucket older; the last bucket holds all saturated stake. There is no
+//! exponential, so no overflow bound and no rebase, at the cost of weights
+//! that step once per period instead of growing continuously.
+//!
+//! Rewards use one accumulator `C` (WAD lamports per unit of weight, weight
+//! being `amount * weight_bps`) plus its value at the start of every epoch
+//! (`epoch_acc`). A cohort that entered at epoch `e` spent epoch `e + k` in
+//! bucket `k`, so its reward per token only needs the `K` epoch starts
+//! `e ..= e + K - 1`: claims and unstakes cost O(K) whatever the age.
+//!
+//! On chain a pool selects this engine at initialization
+//! (InitializeBucketedPool); the epoch starts live in paged history
+//! accounts, so the free functions below take them from a callback.
+
+use crate::error::MathError;
+use crate::math::{U256, WAD};
+
+/// Largest number of age buckets a schedule may have
+pub const MAX_AGE_BUCKETS: usize = 16;
+
+/// Weight of a bucket when it counts fully (100%)
+pub const FULL_WEIGHT_BPS: u16 = 10_000;
+
+/// Bucket period and per-bucket weights (youngest first)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgeBucketSchedule {
+    /// Length of one age bucket in seconds
+    pub bucket_seconds: u64,
+    /// Weight of each bucket in bps; the last one applies to all older stake
+    pub weights_bps: Vec<u16>,
+}
+
+impl AgeBucketSchedule {
+    /// A schedule is valid with a non-zero period, 1..=MAX_AGE_BUCKETS
+    /// non-decreasing weights, and a last weight in 1..=FULL_WEIGHT_BPS
+    pub fn is_valid(&self) -> bool {
+        self.bucket_seconds > 0
+            && (1..=MAX_AGE_BUCKETS).contains(&self.weights_bps.len())
+            && self.weights_bps.windows(2).all(|w| w[0] <= w[1])
+            && self
+                .weights_bps
+                .last()
+                .is_some_and(|&w| w > 0 && w <= FULL_WEIGHT_BPS)
+    }
+
+    /// Number of buckets (K)
+    pub fn len(&self) -> usize {
+        self.weights_bps.len()
+    }
+
+    /// Whether the schedule has no buckets (never true for a valid one)
+    pub fn is_empty(&self) -> bool {
+        self.weights_bps.is_empty()
+    }
+}
+
+/// A position: `amount` tokens that entered at `entry_epoch`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BucketPosition {
+    pub amount: u64,
+    pub entry_epoch: u64,
+    /// Reward per token (WAD, weight-scaled) already paid out
+    pub paid_per_token: u128,
+}
+
+/// Pool state of the age-bucketed engine
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BucketedPool {
+    pub schedule: AgeBucketSchedule,
+    /// Start of epoch 0
+    pub start_time: i64,
+    /// Current epoch (bucket periods rolled since start_time)
+    pub epoch: u64,
+    /// Staked amount per age bucket, youngest first
+    pub amounts: Vec<u64>,
+    /// Accumulated WAD lamports per unit of weight
+    pub acc_per_weight: u128,
+    /// acc_per_weight at the start of each epoch (index = epoch)
+    pub epoch_acc: Vec<u128>,
+}
+
+impl BucketedPool {
+    /// Empty pool whose epoch 0 starts at `start_time`. Invalid schedules
+    /// fail with InvalidTau: the schedule takes τ's place.
+    pub fn new(schedule: AgeBucketSchedule, start_time: i64) -> Result<Self, MathError> {
+        if !schedule.is_valid() {
+            return Err(MathError::InvalidTau);
+        }
+        let amounts = vec![0; schedule.len()];
+        Ok(Self {
+            schedule,
+            start_time,
+            epoch: 0,
+            amounts,
+            acc_per_weight: 0,
+            epoch_acc: vec![0],
+        })
+    }
+
+    /// Epoch containing `time`
+    pub fn epoch_at(&self, time: i64) -> u64 {
+        let elapsed = time.saturating_sub(self.start_time).max(0) as u64;
+        elapsed / self.schedule.bucket_seconds
+    }
+
+    /// Roll the buckets forward to the epoch containing `now`. Returns the
+    /// number of epochs rolled.
+    pub fn roll(&mut self, now: i64) -> u64 {
+        let target = self.epoch_at(now);
+        let rolled = target.saturating_sub(self.epoch);
+        roll_amounts(&mut self.amounts, rolled);
+        for _ in 0..rolled {
+            self.epoch_acc.push(self.acc_per_weight);
+        }
+        self.epoch = self.epoch.max(target);
+        rolled
+    }
+
+    /// Total weight: sum of bucket amounts times their weight (bps)
+    pub fn total_weight(&self) -> u128 {
+        total_weight(&self.amounts, &self.schedule.weights_bps)
+    }
+
+    /// Bucket a position is in now
+    pub fn bucket_of(&self, position: &BucketPosition) -> usize {
+        let age = self.epoch.saturating_sub(position.entry_epoch);
+        (age as usize).min(self.amounts.len() - 1)
+    }
+
+    /// Distribute `lamports` over the current weight. Returns false, leaving
+    /// the rewards undistributed, when nothing is staked.
+    pub fn distribute(&mut self, lamports: u64) -> Result<bool, MathError> {
+        let total_weight = self.total_weight();
+        if total_weight == 0 {
+            return Ok(false);
+        }
+        self.acc_per_weight = self
+            .acc_per_weight
+            .checked_add(acc_increment(lamports, total_weight)?)
+            .ok_or(MathError::Overflow)?;
+        Ok(true)
+    }
+
+    /// Reward per token (WAD, weight-scaled) earned since `entry_epoch` by
+    /// a cohort that entered then
+    pub fn earned_per_token(&self, entry_epoch: u64) -> Result<u128, MathError> {
+        earned_per_token(
+            &self.schedule.weights_bps,
+            entry_epoch,
+            self.epoch,
+            self.acc_per_weight,
+            |epoch| self.epoch_acc[epoch as usize],
+        )
+    }
+
+    /// Lamports `position` could claim now (rounded down)
+    pub fn pending(&self, position: &BucketPosition) -> Result<u64, MathError> {
+        let earned = self.earned_per_token(position.entry_epoch)?;
+        let unpaid = earned
+            .checked_sub(position.paid_per_token)
+            .ok_or(MathError::Underflow)?;
+        let lamports =
+            U256::from(position.amount) * U256::from_u128(unpaid) / U256::from_u128(WAD);
+        lamports
+            .to_u128()
+            .and_then(|v| u64::try_from(v).ok())
+            .ok_or(MathError::Overflow)
+    }
+
+    /// Open a position of `amount` at `now` in the youngest bucket
+    pub fn stake(&mut self, amount: u64, now: i64) -> Result<BucketPosition, MathError> {
+        self.roll(now);
+        self.amounts[0] = self.amounts[0]
+            .checked_add(amount)
+            .ok_or(MathError::Overflow)?;
+        // Nothing earned before now: already distributed rewards of this
+        // epoch count as paid
+        Ok(BucketPosition {
+            amount,
+            entry_epoch: self.epoch,
+            paid_per_token: self.earned_per_token(self.epoch)?,
+        })
+    }
+
+    /// Pay out the pending rewards of `position` at `now`
+    pub fn claim(&mut self, position: &mut BucketPosition, now: i64) -> Result<u64, MathError> {
+        self.roll(now);
+        let pending = self.pending(position)?;
+        position.paid_per_token = self.earned_per_token(position.entry_epoch)?;
+        Ok(pending)
+    }
+
+    /// Remove `amount` from `position` at `now`, paying out its pending
+    /// rewards first. Returns the rewards paid.
+    pub fn unstake(
+        &mut self,
+        position: &mut BucketPosition,
+        amount: u64,
+        now: i64,
+    ) -> Result<u64, MathError> {
+        let rewards = self.claim(position, now)?;
+        let bucket = self.bucket_of(position);
+        position.amount = position
+            .amount
+            .checked_sub(amount)
+            .ok_or(MathError::Underflow)?;
+        self.amounts[bucket] = self.amounts[bucket]
+            .checked_sub(amount)
+            .ok_or(MathError::Underflow)?;
+        Ok(rewards)
+    }
+}
+
+/// Move every cohort `epochs` buckets older, youngest first; the last
+/// bucket holds all saturated stake. After `amounts.len()` rolls every
+/// bucket but the last is empty, so longer rolls stop there.
+pub fn roll_amounts(amounts: &mut [u64], epochs: u64) {
+    let last = amounts.len().saturating_sub(1);
+    if last == 0 {
+        return;
+    }
+    for _ in 0..epochs.min(amounts.len() as u64) {
+        amounts[last] = amounts[last].saturating_add(amounts[last - 1]);
+        amounts.copy_within(0..last - 1, 1);
+        amounts[0] = 0;
+    }
+}
+
+/// Sum of bucket amounts times their weight (bps)
+pub fn total_weight(amounts: &[u64], weights_bps: &[u16]) -> u128 {
+    amounts
+        .iter()
+        .zip(weights_bps)
+        .map(|(&amount, &w)| amount as u128 * w as u128)
+        .sum()
+}
+
+/// Accumulator increase distributing `lamports` over a non-zero
+/// `total_weight` (rounded down, so payouts never exceed the distribution)
+pub fn acc_increment(lamports: u64, total_weight: u128) -> Result<u128, MathError> {
+    Ok((lamports as u128)
+        .checked_mul(WAD)
+        .ok_or(MathError::Overflow)?
+        / total_weight)
+}
+
+/// Reward per token (WAD, weight-scaled) earned by a cohort that entered at
+/// `entry_epoch`, with the pool at `epoch` and `acc_per_weight`.
+/// `epoch_start(e)` gives the accumulator at the start of epoch `e` for the
+/// epochs up to `epoch`; only `entry_epoch ..= entry_epoch + K - 1` are
+/// asked for, so callers can keep the history paged.
+pub fn earned_per_token(
+    weights_bps: &[u16],
+    entry_epoch: u64,
+    epoch: u64,
+    acc_per_weight: u128,
+    epoch_start: impl Fn(u64) -> u128,
+) -> Result<u128, MathError> {
+    let acc_at = |e: u64| if e <= epoch { epoch_start(e) } else { acc_per_weight };
+    let last = weights_bps.len() as u64 - 1;
+    let mut earned = 0u128;
+    for (k, &w) in weights_bps.iter().enumerate() {
+        let start = acc_at(entry_epoch + k as u64);
+        let end = if k as u64 == last {
+            acc_per_weight
+        } else {
+            acc_at(entry_epoch + k as u64 + 1)
+        };
+        let part = (w as u128)
+            .checked_mul(end.checked_sub(start).ok_or(MathError::Underflow)?)
+            .ok_or(MathError::Overflow)?;
+        earned = earned.checked_add(part).ok_or(MathError::Overflow)?;
+    }
+    Ok(earned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 86400;
+
+    fn schedule() -> AgeBucketSchedule {
+        AgeBucketSchedule {
+            bucket_seconds: DAY as u64,
+            weights_bps: vec![2_500, 5_000, 10_000],
+        }
+    }
+
+    #[test]
+    fn test_schedule_validation() {
+        assert!(schedule().is_valid());
+        let invalid = [
+            AgeBucketSchedule { bucket_seconds: 0, ..schedule() },
+            AgeBucketSchedule { bucket_seconds: 1, weights_bps: vec![] },
+            AgeBucketSchedule { bucket_seconds: 1, weights_bps: vec![5_000, 2_500] },
+            AgeBucketSchedule { bucket_seconds: 1, weights_bps: vec![0] },
+            AgeBucketSchedule { bucket_seconds: 1, weights_bps: vec![10_001] },
+            AgeBucketSchedule { bucket_seconds: 1, weights_bps: vec![1; MAX_AGE_BUCKETS + 1] },
+        ];
+        for schedule in invalid {
+            assert!(!schedule.is_valid());
+            assert_eq!(BucketedPool::new(schedule, 0), Err(MathError::InvalidTau));
+        }
+    }
+
+    #[test]
+    fn test_roll_moves_cohorts() {
+        let mut pool = BucketedPool::new(schedule(), 0).unwrap();
+        let young = pool.stake(100, 0).unwrap();
+        assert_eq!(pool.roll(DAY - 1), 0);
+        assert_eq!(pool.roll(DAY), 1);
+        pool.stake(40, DAY).unwrap();
+        assert_eq!(pool.amounts, vec![40, 100, 0]);
+        assert_eq!(pool.bucket_of(&young), 1);
+
+        // Late rolls saturate everything and still record each epoch start
+        assert_eq!(pool.roll(10 * DAY), 9);
+        assert_eq!(pool.amounts, vec![0, 0, 140]);
+        assert_eq!(pool.epoch_acc.len(), 11);
+        assert_eq!(pool.total_weight(), 140 * 10_000);
+    }
+
+    #[test]
+    fn test_rewards_follow_bucket_weights() {
+        let mut pool = BucketedPool::new(schedule(), 0).unwrap();
+        let mut old = pool.stake(1_000, 0).unwrap();
+        pool.roll(2 * DAY);
+        let mut new = pool.stake(1_000, 2 * DAY).unwrap();
+
+        // Rewards distributed before a stake don't reach it
+        pool.distribute(500).unwrap();
+        let mut late = pool.stake(1_000, 2 * DAY + 1).unwrap();
+        assert_eq!(pool.pending(&late).unwrap(), 0);
+        assert_eq!(pool.unstake(&mut late, 1_000, 2 * DAY + 1).unwrap(), 0);
+        assert_eq!(pool.claim(&mut old, 2 * DAY + 1).unwrap(), 400);
+        assert_eq!(pool.claim(&mut new, 2 * DAY + 1).unwrap(), 100);
+
+        // Saturated (10000 bps) vs youngest (2500 bps): 4:1
+        assert!(pool.distribute(1_000_000).unwrap());
+        assert_eq!(pool.pending(&old).unwrap(), 800_000);
+        assert_eq!(pool.pending(&new).unwrap(), 200_000);
+
+        // A day later the new position is in bucket 1 (5000 bps): 2:1
+        pool.roll(3 * DAY);
+        pool.distribute(300_000).unwrap();
+        assert_eq!(pool.claim(&mut old, 3 * DAY).unwrap(), 1_000_000);
+        assert_eq!(pool.pending(&old).unwrap(), 0);
+
+        // Claimed long after, the new position still gets each epoch's share
+        pool.roll(20 * DAY);
+        pool.distribute(100_000).unwrap();
+        assert_eq!(pool.pending(&old).unwrap(), 50_000);
+        assert_eq!(pool.unstake(&mut new, 1_000, 21 * DAY).unwrap(), 350_000);
+        assert_eq!(pool.amounts, vec![0, 0, 1_000]);
+
+        // Nobody left at the rewards but the old position
+        pool.unstake(&mut old, 1_000, 21 * DAY).unwrap();
+        assert!(!pool.distribute(1).unwrap());
+    }
+
+    #[test]
+    fn test_earned_reads_entry_window_only() {
+        let mut pool = BucketedPool::new(schedule(), 0).unwrap();
+        let position = pool.stake(1_000, 0).unwrap();
+        for day in 1..=30 {
+            pool.roll(day * DAY);
+            pool.distribute(1_000).unwrap();
+        }
+
+        // A cohort that entered at epoch 5 only needs epoch starts 5..=7
+        let weights = &pool.schedule.weights_bps;
+        let earned = earned_per_token(weights, 5, pool.epoch, pool.acc_per_weight, |e| {
+            assert!((5..=7).contains(&e), "epoch {} read", e);
+            pool.epoch_acc[e as usize]
+        })
+        .unwrap();
+        assert_eq!(earned, pool.earned_per_token(5).unwrap());
+        assert_eq!(pool.pending(&position).unwrap(), 30_000);
+
+        let mut amounts = [7, 5, 3];
+        roll_amounts(&mut amounts, 1);
+        assert_eq!(amounts, [0, 7, 8]);
+        roll_amounts(&mut amounts, 100);
+        assert_eq!(amounts, [0, 0, 15]);
+    }
+}
@@ -0,0 +1,33 @@
+//! Arithmetic errors
+//!
+//! Codes match the StakingError variants of the same meaning, so a failure
+//! reports the same `custom program error` on-chain and off-chain.
+
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum MathError {
+    #[error("Math overflow")]
+    Overflow = 12,
+
+    #[error("Math underflow")]
+    Underflow = 13,
+
+    #[error("Invalid tau value")]
+    InvalidTau = 15,
+}
+
+impl MathError {
+    /// Numeric error code, equal to the matching StakingError code
+    pub const fn code(self) -> u32 {
+        self as u32
+    }
+}
+
+#[cfg(feature = "program")]
+impl From<MathError> for solana_program::program_error::ProgramError {
+    fn from(e: MathError) -> Self {
+        solana_program::program_error::ProgramError::Custom(e.code())
+    }
+}
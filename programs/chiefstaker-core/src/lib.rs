@@ -0,0 +1,12 @@
+//! ChiefStaker core arithmetic
+//!
+//! Fixed-point math and reward accounting of the ChiefStaker program, with
+//! no Solana dependencies unless the `program` feature is enabled. Web
+//! frontends compile it to wasm32-unknown-unknown to compute weights and
+//! pending rewards with the exact on-chain arithmetic.
+
+pub mod buckets;
+pub mod error;
+pub mod math;
+pub mod simulate;
+pub mod state;
@@ -0,0 +1,411 @@
+//! Reward simulation
+//!
+//! Replays a sequence of stakes, reward deposits and claims against the
+//! program's exact fixed-point accounting, for tokenomics modeling and for
+//! comparing candidate τ values before a pool is created. Only the core
+//! reward path is modeled: no protocol fee, bootstrap period, lock, claim
+//! vesting, burn or unstake. A deposit made while nothing is staked stays
+//! undistributed until a deposit finds stake, as SyncRewards would leave it.
+
+use crate::error::MathError;
+use crate::math::{
+    calculate_total_weighted_stake, calculate_user_weighted_stake, exp_neg_time_ratio,
+    exp_time_ratio, wad_mul, wad_mul_u256, MAX_EXP_INPUT, U256, WAD,
+};
+use crate::state::{
+    credit_ray_accumulator, pending_rewards_wad, rebase_exp_start_factor, RoundingMode,
+};
+
+/// One simulated operation. Stakers are identified by index; the first
+/// stake of an index opens its position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimEvent {
+    /// `staker` stakes `amount` tokens (opening or topping up)
+    Stake { staker: usize, amount: u64 },
+    /// `lamports` of rewards are deposited and distributed
+    Deposit { lamports: u64 },
+    /// `staker` claims their pending rewards
+    Claim { staker: usize },
+}
+
+/// An event at a unix timestamp. Steps must be in time order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimStep {
+    pub time: i64,
+    pub event: SimEvent,
+}
+
+/// A simulated position, with the same fields as its UserStake
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct SimPosition {
+    opened: bool,
+    amount: u64,
+    exp_start_factor: u128,
+    base_time_snapshot: i64,
+    reward_debt: u128,
+    claimed_rewards_wad: u128,
+    total_claimed: u64,
+}
+
+/// A position after a step
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PositionBalance {
+    pub amount: u64,
+    /// WAD-scaled weighted stake
+    pub weighted_stake: u128,
+    /// What a claim would pay now
+    pub pending_lamports: u64,
+    /// Lamports claimed so far
+    pub total_claimed: u64,
+}
+
+/// Pool and position balances after a step
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepResult {
+    pub time: i64,
+    /// Lamports the step's claim paid (0 for other events)
+    pub claimed_lamports: u64,
+    pub total_staked: u128,
+    /// WAD-scaled total weighted stake
+    pub total_weighted_stake: u128,
+    pub acc_reward_per_weighted_share: u128,
+    /// Reward lamports held by the pool (distributed or not)
+    pub pool_lamports: u64,
+    /// Deposited lamports not yet distributed (nothing staked)
+    pub undistributed_lamports: u64,
+    /// Positions by staker index
+    pub positions: Vec<PositionBalance>,
+}
+
+/// Pool state under simulation
+#[derive(Debug, Clone)]
+pub struct RewardSimulator {
+    tau_seconds: u64,
+    rounding: RoundingMode,
+    base_time: i64,
+    total_staked: u128,
+    sum_stake_exp: U256,
+    acc_reward_per_weighted_share: u128,
+    acc_rps_ray_remainder: u64,
+    rounding_reserve_wad: u128,
+    pool_lamports: u64,
+    undistributed_lamports: u64,
+    positions: Vec<SimPosition>,
+}
+
+impl RewardSimulator {
+    /// Empty pool created at `start_time`
+    pub fn new(tau_seconds: u64, start_time: i64, rounding: RoundingMode) -> Result<Self, MathError> {
+        if tau_seconds == 0 {
+            return Err(MathError::InvalidTau);
+        }
+        Ok(Self {
+            tau_seconds,
+            rounding,
+            base_time: start_time,
+            total_staked: 0,
+            sum_stake_exp: U256::from_u128(0),
+            acc_reward_per_weighted_share: 0,
+            acc_rps_ray_remainder: 0,
+            rounding_reserve_wad: 0,
+            pool_lamports: 0,
+            undistributed_lamports: 0,
+            positions: Vec::new(),
+        })
+    }
+
+    /// Apply `step` and return the balances after it
+    pub fn apply(&mut self, step: &SimStep) -> Result<StepResult, MathError> {
+        let mut claimed_lamports = 0;
+        match step.event {
+            SimEvent::Stake { staker, amount } => self.stake(staker, amount, step.time)?,
+            SimEvent::Deposit { lamports } => self.deposit(lamports)?,
+            SimEvent::Claim { staker } => claimed_lamports = self.claim(staker, step.time)?,
+        }
+        let mut result = self.balances(step.time)?;
+        result.claimed_lamports = claimed_lamports;
+        Ok(result)
+    }
+
+    /// Balances at `time` without changing anything
+    pub fn balances(&self, time: i64) -> Result<StepResult, MathError> {
+        let positions = self
+            .positions
+            .iter()
+            .map(|position| {
+                let mut position = position.clone();
+                self.sync(&mut position)?;
+                let weighted_stake = self.weighted_stake(&position, time)?;
+                let pending = self.pending_wad(&position, weighted_stake)?;
+                Ok(PositionBalance {
+                    amount: position.amount,
+                    weighted_stake,
+                    pending_lamports: self
+                        .rounding
+                        .to_lamports_funded(pending, self.rounding_reserve_wad)
+                        .min(u64::MAX as u128) as u64,
+                    total_claimed: position.total_claimed,
+                })
+            })
+            .collect::<Result<Vec<_>, MathError>>()?;
+        Ok(StepResult {
+            time,
+            claimed_lamports: 0,
+            total_staked: self.total_staked,
+            total_weighted_stake: calculate_total_weighted_stake(
+                self.total_staked,
+                &self.sum_stake_exp,
+                time,
+                self.base_time,
+                self.tau_seconds,
+            )?,
+            acc_reward_per_weighted_share: self.acc_reward_per_weighted_share,
+            pool_lamports: self.pool_lamports,
+            undistributed_lamports: self.undistributed_lamports,
+            positions,
+        })
+    }
+
+    /// Rebase as StakingPool::rebase_if_needed does
+    fn rebase_if_needed(&mut self, time: i64) -> Result<(), MathError> {
+        let time_delta = time.saturating_sub(self.base_time);
+        if time_delta <= 0 {
+            return Ok(());
+        }
+        let exp_input = (time_delta as u128)
+            .checked_mul(WAD)
+            .ok_or(MathError::Overflow)?
+            / self.tau_seconds as u128;
+        if !self.sum_stake_exp.needs_rebase() && exp_input <= MAX_EXP_INPUT {
+            return Ok(());
+        }
+        let decay_factor = exp_neg_time_ratio(time_delta, self.tau_seconds)?;
+        self.sum_stake_exp = wad_mul_u256(self.sum_stake_exp, U256::from_u128(decay_factor))?;
+        self.base_time = time;
+        Ok(())
+    }
+
+    /// Catch a position up with rebases, as UserStake::sync_to_pool does
+    fn sync(&self, position: &mut SimPosition) -> Result<(), MathError> {
+        if position.base_time_snapshot != self.base_time {
+            position.exp_start_factor = rebase_exp_start_factor(
+                position.exp_start_factor,
+                position.base_time_snapshot,
+                self.base_time,
+                self.tau_seconds,
+            )?;
+            position.base_time_snapshot = self.base_time;
+        }
+        Ok(())
+    }
+
+    fn weighted_stake(&self, position: &SimPosition, time: i64) -> Result<u128, MathError> {
+        calculate_user_weighted_stake(
+            position.amount,
+            position.exp_start_factor,
+            time,
+            self.base_time,
+            self.tau_seconds,
+        )
+    }
+
+    fn pending_wad(&self, position: &SimPosition, weighted_stake: u128) -> Result<u128, MathError> {
+        pending_rewards_wad(
+            position.amount,
+            position.reward_debt,
+            position.claimed_rewards_wad,
+            weighted_stake,
+            self.acc_reward_per_weighted_share,
+        )
+    }
+
+    /// Stake or StakeOnBehalf: open_position or add_to_position
+    fn stake(&mut self, staker: usize, amount: u64, time: i64) -> Result<(), MathError> {
+        self.rebase_if_needed(time)?;
+        if staker >= self.positions.len() {
+            self.positions.resize(staker + 1, SimPosition::default());
+        }
+        let mut position = self.positions[staker].clone();
+        let amount_wad = (amount as u128).checked_mul(WAD).ok_or(MathError::Overflow)?;
+
+        if !position.opened {
+            position.opened = true;
+            position.exp_start_factor = exp_time_ratio(time - self.base_time, self.tau_seconds)?;
+            position.base_time_snapshot = self.base_time;
+        } else {
+            self.sync(&mut position)?;
+        }
+
+        // New tokens keep the position's maturity and snapshot the
+        // accumulator so they can't reach earlier rewards
+        let contribution = wad_mul(amount_wad, position.exp_start_factor)?;
+        self.sum_stake_exp = self
+            .sum_stake_exp
+            .checked_add(U256::from_u128(contribution))
+            .ok_or(MathError::Overflow)?;
+        position.reward_debt = position
+            .reward_debt
+            .checked_add(wad_mul(amount_wad, self.acc_reward_per_weighted_share)?)
+            .ok_or(MathError::Overflow)?;
+        position.amount = position.amount.checked_add(amount).ok_or(MathError::Overflow)?;
+        self.total_staked = self
+            .total_staked
+            .checked_add(amount as u128)
+            .ok_or(MathError::Overflow)?;
+
+        self.positions[staker] = position;
+        Ok(())
+    }
+
+    /// DepositRewards: credit the accumulator at full weight
+    fn deposit(&mut self, lamports: u64) -> Result<(), MathError> {
+        self.pool_lamports = self.pool_lamports.checked_add(lamports).ok_or(MathError::Overflow)?;
+        self.undistributed_lamports = self.undistributed_lamports.saturating_add(lamports);
+        if self.total_staked == 0 {
+            return Ok(());
+        }
+        let amount_wad = (self.undistributed_lamports as u128)
+            .checked_mul(WAD)
+            .ok_or(MathError::Overflow)?;
+        credit_ray_accumulator(
+            &mut self.acc_reward_per_weighted_share,
+            &mut self.acc_rps_ray_remainder,
+            amount_wad,
+            self.total_staked,
+        )?;
+        self.undistributed_lamports = 0;
+        Ok(())
+    }
+
+    /// ClaimRewards: pay pending rewards, capped at the pool's balance.
+    /// Returns the lamports paid.
+    fn claim(&mut self, staker: usize, time: i64) -> Result<u64, MathError> {
+        // Best effort, as on-chain: claims never depend on the rebase
+        let mut rebased = self.clone();
+        if rebased.rebase_if_needed(time).is_ok() {
+            *self = rebased;
+        }
+        let Some(mut position) = self.positions.get(staker).cloned() else {
+            return Ok(0);
+        };
+        self.sync(&mut position)?;
+        let weighted_stake = self.weighted_stake(&position, time)?;
+        let pending = self.pending_wad(&position, weighted_stake)?;
+        let pending_lamports = self.rounding.to_lamports_funded(pending, self.rounding_reserve_wad);
+        let paid = pending_lamports.min(self.pool_lamports as u128) as u64;
+        let settled = self.rounding.settle(pending, paid as u128, &mut self.rounding_reserve_wad);
+
+        position.claimed_rewards_wad = position
+            .claimed_rewards_wad
+            .checked_add(settled)
+            .ok_or(MathError::Overflow)?;
+        position.total_claimed = position.total_claimed.saturating_add(paid);
+        self.pool_lamports -= paid;
+        self.positions[staker] = position;
+        Ok(paid)
+    }
+}
+
+/// Replay `steps` on a new pool created at `start_time` and return the
+/// balances after each step
+pub fn simulate(
+    tau_seconds: u64,
+    start_time: i64,
+    rounding: RoundingMode,
+    steps: &[SimStep],
+) -> Result<Vec<StepResult>, MathError> {
+    let mut simulator = RewardSimulator::new(tau_seconds, start_time, rounding)?;
+    steps.iter().map(|step| simulator.apply(step)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::calculate_weight;
+
+    const TAU: u64 = 1_000;
+
+    fn step(time: i64, event: SimEvent) -> SimStep {
+        SimStep { time, event }
+    }
+
+    #[test]
+    fn test_simulate_single_staker() {
+        let results = simulate(
+            TAU,
+            0,
+            RoundingMode::Floor,
+            &[
+                step(0, SimEvent::Stake { staker: 0, amount: 1_000 }),
+                step(TAU as i64, SimEvent::Deposit { lamports: 1_000_000 }),
+                step(TAU as i64, SimEvent::Claim { staker: 0 }),
+                step(TAU as i64, SimEvent::Claim { staker: 0 }),
+            ],
+        )
+        .unwrap();
+
+        // At τ the position earns its ~63% weight share of the deposit
+        let weight = calculate_weight(1_000, TAU as i64, TAU).unwrap();
+        let expected = (weight * 1_000) / WAD;
+        let deposited = &results[1];
+        assert_eq!(deposited.positions[0].weighted_stake, weight);
+        assert!(deposited.positions[0].pending_lamports.abs_diff(expected as u64) <= 1);
+        assert!((631_000..633_000).contains(&deposited.positions[0].pending_lamports));
+
+        let claimed = &results[2];
+        assert_eq!(claimed.claimed_lamports, deposited.positions[0].pending_lamports);
+        assert_eq!(claimed.positions[0].pending_lamports, 0);
+        assert_eq!(claimed.pool_lamports, 1_000_000 - claimed.claimed_lamports);
+        // A second claim at the same time pays nothing
+        assert_eq!(results[3].claimed_lamports, 0);
+    }
+
+    #[test]
+    fn test_simulate_deposit_schedule() {
+        let mut simulator = RewardSimulator::new(TAU, 0, RoundingMode::Floor).unwrap();
+        // Rewards before any stake wait for stakers
+        let early = simulator.apply(&step(0, SimEvent::Deposit { lamports: 500 })).unwrap();
+        assert_eq!(early.undistributed_lamports, 500);
+        assert_eq!(early.acc_reward_per_weighted_share, 0);
+
+        simulator.apply(&step(0, SimEvent::Stake { staker: 0, amount: 100 })).unwrap();
+        simulator.apply(&step(0, SimEvent::Stake { staker: 1, amount: 100 })).unwrap();
+        let late = simulator.apply(&step(10 * TAU as i64, SimEvent::Deposit { lamports: 1_500 })).unwrap();
+        assert_eq!(late.undistributed_lamports, 0);
+        // Equal, fully matured stakes split everything (less rounding)
+        assert_eq!(late.positions[0], late.positions[1]);
+        assert!((999..=1_000).contains(&late.positions[0].pending_lamports));
+
+        // A late top-up doesn't reach rewards deposited before it
+        simulator.apply(&step(10 * TAU as i64, SimEvent::Stake { staker: 1, amount: 100 })).unwrap();
+        let after = simulator.balances(10 * TAU as i64).unwrap();
+        assert_eq!(after.positions[1].pending_lamports, late.positions[1].pending_lamports);
+        assert_eq!(after.total_staked, 300);
+    }
+
+    #[test]
+    fn test_simulate_across_rebase() {
+        // Far past MAX_EXP_INPUT: the pool rebases and positions catch up
+        let late = 50 * TAU as i64;
+        let results = simulate(
+            TAU,
+            0,
+            RoundingMode::HalfUp,
+            &[
+                step(0, SimEvent::Stake { staker: 0, amount: 1_000 }),
+                step(late, SimEvent::Stake { staker: 1, amount: 1_000 }),
+                step(late + 3 * TAU as i64, SimEvent::Deposit { lamports: 2_000 }),
+            ],
+        )
+        .unwrap();
+        let balances = &results[2];
+        assert!(balances.positions[0].pending_lamports > balances.positions[1].pending_lamports);
+        assert!(balances.positions[1].pending_lamports > 900);
+        assert!(balances.total_weighted_stake <= 2_000 * WAD);
+
+        assert_eq!(
+            RewardSimulator::new(0, 0, RoundingMode::Floor).unwrap_err(),
+            MathError::InvalidTau
+        );
+    }
+}
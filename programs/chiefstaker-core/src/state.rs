@@ -0,0 +1,361 @@
+//! Reward accounting on account fields
+//!
+//! The program's StakingPool and UserStake methods delegate to these
+//! functions, so values computed from fetched accounts match what a claim
+//! would pay at the same time.
+
+use crate::error::MathError;
+use crate::math::{exp_neg_time_ratio, wad_div, wad_mul, RAY_PER_WAD, U256, WAD};
+
+/// How a pool rounds WAD-scaled reward entitlements to whole lamports when
+/// paying them out. Under Floor the unpaid fraction stays owed to the
+/// position. The nearest modes settle the entitlement exactly and move the
+/// rounding difference through the pool's rounding reserve: round-downs
+/// add to it, and round-ups are only paid when it can fund them, so payouts
+/// never exceed what was distributed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum RoundingMode {
+    /// Always down, toward the pool
+    Floor = 0,
+    /// To nearest, halves up
+    HalfUp = 1,
+    /// To nearest, halves to even (banker's rounding)
+    HalfEven = 2,
+}
+
+impl RoundingMode {
+    /// Mode stored as `value`, if any
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Floor),
+            1 => Some(Self::HalfUp),
+            2 => Some(Self::HalfEven),
+            _ => None,
+        }
+    }
+
+    /// Whole lamports paid for `wad` (WAD-scaled lamports)
+    pub fn to_lamports(self, wad: u128) -> u128 {
+        let (lamports, rem) = (wad / WAD, wad % WAD);
+        let half = WAD / 2;
+        let round_up = match self {
+            Self::Floor => false,
+            Self::HalfUp => rem >= half,
+            Self::HalfEven => rem > half || (rem == half && lamports % 2 == 1),
+        };
+        lamports + round_up as u128
+    }
+
+    /// Whole lamports paid for `wad` when round-ups must be funded from
+    /// `reserve_wad` (the rounding reserve): a round-up it can't cover pays
+    /// the floor instead
+    pub fn to_lamports_funded(self, wad: u128, reserve_wad: u128) -> u128 {
+        let lamports = self.to_lamports(wad);
+        let round_up_wad = lamports.saturating_mul(WAD).saturating_sub(wad);
+        if round_up_wad > reserve_wad {
+            wad / WAD
+        } else {
+            lamports
+        }
+    }
+
+    /// Settle a payout of `paid` lamports for `pending` (WAD-scaled)
+    /// rewards. Returns the WAD-scaled rewards it settles: under Floor, or
+    /// for a payout capped below [`Self::to_lamports_funded`], what was paid
+    /// (the rest stays owed); otherwise all of `pending`, with the rounding
+    /// difference added to or drawn from `reserve_wad`.
+    pub fn settle(self, pending: u128, paid: u128, reserve_wad: &mut u128) -> u128 {
+        let paid_wad = paid.saturating_mul(WAD);
+        if self == Self::Floor || paid != self.to_lamports_funded(pending, *reserve_wad) {
+            return paid_wad;
+        }
+        if paid_wad > pending {
+            *reserve_wad -= paid_wad - pending;
+        } else {
+            *reserve_wad += pending - paid_wad;
+        }
+        pending
+    }
+}
+
+/// Re-express `exp_start_factor` (relative to `from_base_time`) relative to
+/// the later `to_base_time`, as a pool rebase does for its sum
+pub fn rebase_exp_start_factor(
+    exp_start_factor: u128,
+    from_base_time: i64,
+    to_base_time: i64,
+    tau: u64,
+) -> Result<u128, MathError> {
+    let delta = to_base_time.saturating_sub(from_base_time);
+    if delta <= 0 {
+        return Ok(exp_start_factor);
+    }
+    let adjustment = exp_neg_time_ratio(delta, tau)?;
+    wad_mul(exp_start_factor, adjustment)
+}
+
+/// WAD-scaled rewards earned but not yet claimed by a position of `amount`
+/// tokens, given its current weighted stake:
+/// user_weighted * (acc_rps - reward_debt / amount) - claimed_rewards_wad.
+/// Only meaningful while amount > 0 (residuals live in reward_debt).
+pub fn pending_rewards_wad(
+    amount: u64,
+    reward_debt: u128,
+    claimed_rewards_wad: u128,
+    user_weighted: u128,
+    acc_reward_per_weighted_share: u128,
+) -> Result<u128, MathError> {
+    if amount == 0 || user_weighted == 0 {
+        return Ok(0);
+    }
+    // snapshot = reward_debt / (amount * WAD)
+    let amount_wad = (amount as u128)
+        .checked_mul(WAD)
+        .ok_or(MathError::Overflow)?;
+    let snapshot = wad_div(reward_debt, amount_wad)?;
+    let delta_rps = acc_reward_per_weighted_share.saturating_sub(snapshot);
+    let full_entitlement = wad_mul(user_weighted, delta_rps)?;
+    Ok(full_entitlement.saturating_sub(claimed_rewards_wad))
+}
+
+/// Lamports a claim would pay: pending rewards of an open position, or the
+/// residual kept in `reward_debt` once it is fully unstaked, rounded with
+/// the pool's mode and its rounding reserve
+pub fn pending_rewards_lamports(
+    rounding: RoundingMode,
+    rounding_reserve_wad: u128,
+    amount: u64,
+    reward_debt: u128,
+    claimed_rewards_wad: u128,
+    user_weighted: u128,
+    acc_reward_per_weighted_share: u128,
+) -> Result<u64, MathError> {
+    let pending_wad = if amount == 0 {
+        reward_debt
+    } else {
+        pending_rewards_wad(
+            amount,
+            reward_debt,
+            claimed_rewards_wad,
+            user_weighted,
+            acc_reward_per_weighted_share,
+        )?
+    };
+    Ok(rounding
+        .to_lamports_funded(pending_wad, rounding_reserve_wad)
+        .min(u64::MAX as u128) as u64)
+}
+
+/// Credit `amount_wad` to a reward accumulator kept at RAY scale, split
+/// into its WAD digits (`acc_wad`, the acc_reward_per_weighted_share
+/// positions settle against) and the 9 below them (`acc_ray_remainder`):
+/// acc += amount_wad / total_staked. Carrying the extra digits means many
+/// small credits to a large total_staked add up instead of each flooring
+/// to WAD. Returns the WAD-scaled part of `amount_wad` the accumulator
+/// can't hold (dust).
+///
+/// The pair is a RAY accumulator stored as quotient and remainder by
+/// RAY_PER_WAD: every credit recombines them in U256 and splits the sum
+/// again, so `acc_ray_remainder` stays below 10^9 and always fits its u64,
+/// and `acc_wad` only overflows where a u128 WAD accumulator would. That
+/// gives existing pools RAY precision without a U256 field or a migration.
+pub fn credit_ray_accumulator(
+    acc_wad: &mut u128,
+    acc_ray_remainder: &mut u64,
+    amount_wad: u128,
+    total_staked: u128,
+) -> Result<u128, MathError> {
+    if total_staked == 0 {
+        return Err(MathError::Overflow);
+    }
+    let ray_per_wad = U256::from_u128(RAY_PER_WAD);
+    let total_staked = U256::from_u128(total_staked);
+    let amount_ray = U256::from_u128(amount_wad) * ray_per_wad;
+    let reward_per_share_ray = amount_ray / total_staked;
+
+    let acc_ray = (U256::from_u128(*acc_wad) * ray_per_wad + U256::from_u128(*acc_ray_remainder as u128))
+        .checked_add(reward_per_share_ray)
+        .ok_or(MathError::Overflow)?;
+    *acc_wad = (acc_ray / ray_per_wad).to_u128().ok_or(MathError::Overflow)?;
+    *acc_ray_remainder = (acc_ray % ray_per_wad).low_u64();
+
+    let dust_ray = amount_ray - reward_per_share_ray * total_staked;
+    Ok((dust_ray / ray_per_wad).low_u128())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{calculate_user_weighted_stake, exp_time_ratio};
+
+    #[test]
+    fn test_rebase_exp_start_factor() {
+        let tau = 1_000;
+        let esf = exp_time_ratio(3_000, tau).unwrap();
+
+        // Moving the base forward leaves the weight unchanged (up to exp rounding)
+        let rebased = rebase_exp_start_factor(esf, 0, 2_000, tau).unwrap();
+        let before = calculate_user_weighted_stake(1_000, esf, 4_000, 0, tau).unwrap();
+        let after = calculate_user_weighted_stake(1_000, rebased, 4_000, 2_000, tau).unwrap();
+        assert!(before.abs_diff(after) < before / 100_000);
+
+        // Same or earlier base: unchanged
+        assert_eq!(rebase_exp_start_factor(esf, 2_000, 2_000, tau).unwrap(), esf);
+        assert_eq!(rebase_exp_start_factor(esf, 2_000, 1_000, tau).unwrap(), esf);
+    }
+
+    #[test]
+    fn test_credit_ray_accumulator() {
+        // 3 tokens staked, 1 lamport: 1/3 per token, carried to 27 digits
+        let (mut acc, mut rem) = (0u128, 0u64);
+        let dust = credit_ray_accumulator(&mut acc, &mut rem, WAD, 3).unwrap();
+        assert_eq!((acc, rem, dust), (333_333_333_333_333_333, 333_333_333, 0));
+        credit_ray_accumulator(&mut acc, &mut rem, 2 * WAD, 3).unwrap();
+        assert_eq!((acc, rem), (999_999_999_999_999_999, 999_999_999));
+
+        assert_eq!(credit_ray_accumulator(&mut acc, &mut rem, WAD, 0), Err(MathError::Overflow));
+    }
+
+    #[test]
+    fn test_ray_accumulator_remainder_bounded() {
+        // Many deposits of varied sizes: the remainder never leaves
+        // [0, RAY_PER_WAD), and the pair always equals the exact RAY sum
+        let totals = [1u128, 3, 7_000_000_000_000, 200_000_000_000_000_000_000, u64::MAX as u128];
+        for total_staked in totals {
+            let (mut acc, mut rem) = (0u128, 0u64);
+            let mut exact = U256::from_u128(0);
+            for i in 1..=2_000u128 {
+                let amount_wad = (i * 7_919 % 1_000_003) * WAD / 1_000 + i;
+                let before = U256::from_u128(acc) * U256::from_u128(RAY_PER_WAD)
+                    + U256::from_u128(rem as u128);
+                credit_ray_accumulator(&mut acc, &mut rem, amount_wad, total_staked).unwrap();
+                assert!((rem as u128) < RAY_PER_WAD);
+
+                let after = U256::from_u128(acc) * U256::from_u128(RAY_PER_WAD)
+                    + U256::from_u128(rem as u128);
+                let credited = U256::from_u128(amount_wad) * U256::from_u128(RAY_PER_WAD)
+                    / U256::from_u128(total_staked);
+                assert_eq!(after, before + credited);
+                exact += credited;
+            }
+            assert_eq!(
+                U256::from_u128(acc) * U256::from_u128(RAY_PER_WAD) + U256::from_u128(rem as u128),
+                exact
+            );
+        }
+
+        // Even an out-of-range stored remainder is carried, not overflowed
+        let (mut acc, mut rem) = (5u128, u64::MAX);
+        credit_ray_accumulator(&mut acc, &mut rem, WAD, 1).unwrap();
+        assert_eq!(acc, 5 + WAD + u64::MAX as u128 / RAY_PER_WAD);
+        assert_eq!(rem as u128, u64::MAX as u128 % RAY_PER_WAD);
+    }
+
+    #[test]
+    fn test_ray_accumulator_rounding_loss() {
+        // 2 * 10^11 tokens of 9 decimals staked, 1,000 deposits of 10
+        // lamports. Each deposit is 0.05 WAD units per token.
+        let total_staked = 200_000_000_000_000_000_000u128;
+        let amount_wad = 10 * WAD;
+
+        // WAD accumulator: every deposit floors to 0 and all of it is dust
+        let mut wad_acc = 0u128;
+        let mut wad_dust = 0u128;
+        for _ in 0..1_000 {
+            let rps = wad_div(amount_wad, total_staked * WAD).unwrap();
+            wad_acc += rps;
+            wad_dust += amount_wad - wad_mul(rps, total_staked * WAD).unwrap();
+        }
+        assert_eq!(wad_acc, 0);
+        assert_eq!(wad_dust, 1_000 * amount_wad);
+
+        // RAY accumulator: exact, nothing left as dust
+        let (mut acc, mut rem) = (0u128, 0u64);
+        let mut dust = 0u128;
+        for _ in 0..1_000 {
+            dust += credit_ray_accumulator(&mut acc, &mut rem, amount_wad, total_staked).unwrap();
+        }
+        assert_eq!((acc, rem, dust), (50, 0, 0));
+
+        // Uneven shares: the WAD accumulator loses up to a unit per deposit,
+        // the RAY one under a unit overall
+        let total_staked = 7_000_000_000_000_000_000u128;
+        let amount_wad = 3 * WAD;
+        let mut wad_acc = 0u128;
+        let (mut acc, mut rem) = (0u128, 0u64);
+        for _ in 0..1_000 {
+            wad_acc += wad_div(amount_wad, total_staked * WAD).unwrap();
+            credit_ray_accumulator(&mut acc, &mut rem, amount_wad, total_staked).unwrap();
+        }
+        let exact = 3_000 * WAD / total_staked;
+        assert_eq!(exact, 428);
+        assert_eq!(wad_acc, 0);
+        assert_eq!(acc, 428);
+    }
+
+    #[test]
+    fn test_pending_rewards() {
+        // Staked at acc_rps = 2, now 5: 3 lamports per weighted token
+        let amount = 100u64;
+        let reward_debt = 100 * WAD * 2;
+        let weighted = 50 * WAD;
+        assert_eq!(
+            pending_rewards_wad(amount, reward_debt, 0, weighted, 5 * WAD).unwrap(),
+            150 * WAD
+        );
+        assert_eq!(
+            pending_rewards_wad(amount, reward_debt, 40 * WAD, weighted, 5 * WAD).unwrap(),
+            110 * WAD
+        );
+        assert_eq!(pending_rewards_wad(amount, reward_debt, 0, 0, 5 * WAD).unwrap(), 0);
+
+        // Rounded with the pool's mode; residuals once fully unstaked
+        let half = WAD / 2;
+        assert_eq!(
+            pending_rewards_lamports(RoundingMode::HalfUp, half, 0, 7 * WAD + half, 0, 0, 0)
+                .unwrap(),
+            8
+        );
+        assert_eq!(
+            pending_rewards_lamports(RoundingMode::Floor, half, 0, 7 * WAD + half, 0, 0, 0)
+                .unwrap(),
+            7
+        );
+        let floor = RoundingMode::Floor;
+        assert_eq!(
+            pending_rewards_lamports(floor, 0, amount, reward_debt, 0, weighted, 5 * WAD).unwrap(),
+            150
+        );
+        // No reserve to fund the round-up
+        assert_eq!(
+            pending_rewards_lamports(RoundingMode::HalfUp, 0, 0, 7 * WAD + half, 0, 0, 0).unwrap(),
+            7
+        );
+    }
+
+    #[test]
+    fn test_rounding_reserve() {
+        let mut reserve = 0u128;
+
+        // Round-ups wait for the reserve; round-downs fund it
+        let pending = 2 * WAD + WAD * 6 / 10;
+        assert_eq!(RoundingMode::HalfUp.to_lamports_funded(pending, reserve), 2);
+        assert_eq!(RoundingMode::HalfUp.settle(pending, 2, &mut reserve), pending);
+        assert_eq!(reserve, WAD * 6 / 10);
+        assert_eq!(RoundingMode::HalfUp.to_lamports_funded(pending, reserve), 3);
+        assert_eq!(RoundingMode::HalfUp.settle(pending, 3, &mut reserve), pending);
+        assert_eq!(reserve, WAD * 2 / 10);
+        assert_eq!(RoundingMode::HalfEven.settle(WAD + WAD / 10, 1, &mut reserve), WAD + WAD / 10);
+        assert_eq!(reserve, WAD * 3 / 10);
+
+        // A payout capped below the rounded amount settles what it paid
+        assert_eq!(RoundingMode::HalfUp.settle(5 * WAD + WAD / 10, 4, &mut reserve), 4 * WAD);
+        assert_eq!(reserve, WAD * 3 / 10);
+
+        // Floor leaves the fraction owed and never touches the reserve
+        assert_eq!(RoundingMode::Floor.to_lamports_funded(WAD - 1, u128::MAX), 0);
+        assert_eq!(RoundingMode::Floor.settle(2 * WAD + WAD / 2, 2, &mut reserve), 2 * WAD);
+        assert_eq!(reserve, WAD * 3 / 10);
+    }
+}
@@ -0,0 +1,866 @@
+//! Account indices for each instruction
+//!
+//! `FIXED_LEN` is the number of required leading accounts; conditional and
+//! optional accounts follow in the documented order. TakeFeeOwnership is
+//! not listed: it is a one-off pump.fun migration, not a CPI target.
+
+/// InitializePool / InitializePoolWithId / InitializePoolIfNeeded /
+/// InitializePoolWithAuthority / InitializePoolBeforeMint
+///
+/// Optional: receipt mint at `FIXED_LEN`
+pub mod initialize_pool {
+    pub const POOL: usize = 0;
+    pub const MINT: usize = 1;
+    pub const TOKEN_VAULT: usize = 2;
+    pub const AUTHORITY: usize = 3;
+    pub const SYSTEM_PROGRAM: usize = 4;
+    pub const TOKEN_PROGRAM: usize = 5;
+    pub const RENT_SYSVAR: usize = 6;
+    pub const FIXED_LEN: usize = 7;
+}
+
+/// Stake
+///
+/// Then: receipt mint + receipt token account (receipt pools), rent sponsor
+/// vault (pools with a rent sponsor), weight history (positions with
+/// history), optional metadata
+pub mod stake {
+    pub const POOL: usize = 0;
+    pub const USER_STAKE: usize = 1;
+    pub const TOKEN_VAULT: usize = 2;
+    pub const USER_TOKEN: usize = 3;
+    pub const MINT: usize = 4;
+    pub const USER: usize = 5;
+    pub const SYSTEM_PROGRAM: usize = 6;
+    pub const TOKEN_PROGRAM: usize = 7;
+    pub const FIXED_LEN: usize = 8;
+}
+
+/// Unstake / UnstakeAll / CompleteUnstake
+///
+/// Then: receipt mint + receipt token account (receipt pools), fee destination
+/// (pools with an unstake fee), weight history (positions with history),
+/// payout split + its destinations (positions with a split) or else reward
+/// recipient (positions with a declared recipient), lock schedule
+/// (Unstake/UnstakeAll only, positions with lock tranches), guardian (signer, positions
+/// with a guardian), memo program (user token accounts requiring memos),
+/// optional system program
+pub mod unstake {
+    pub const POOL: usize = 0;
+    pub const USER_STAKE: usize = 1;
+    pub const TOKEN_VAULT: usize = 2;
+    pub const USER_TOKEN: usize = 3;
+    pub const MINT: usize = 4;
+    pub const USER: usize = 5;
+    pub const TOKEN_PROGRAM: usize = 6;
+    pub const FIXED_LEN: usize = 7;
+}
+
+/// ClaimRewards
+///
+/// Then: payout split + its destinations (positions with a split) or else
+/// reward recipient (positions with a declared recipient), gas rebate vault
+/// (pools with claim rebates), burn destination (pools with a claim burn),
+/// optional system program (legacy account reallocation)
+pub mod claim_rewards {
+    pub const POOL: usize = 0;
+    pub const USER_STAKE: usize = 1;
+    pub const USER: usize = 2;
+    pub const FIXED_LEN: usize = 3;
+}
+
+/// ClaimRewardsWrapped
+///
+/// Then: gas rebate vault (pools with claim rebates), burn destination
+/// (pools with a claim burn), optional system program (legacy account
+/// reallocation)
+pub mod claim_rewards_wrapped {
+    pub const POOL: usize = 0;
+    pub const USER_STAKE: usize = 1;
+    pub const USER: usize = 2;
+    pub const WSOL_ACCOUNT: usize = 3;
+    pub const TOKEN_PROGRAM: usize = 4;
+    pub const FIXED_LEN: usize = 5;
+}
+
+/// DepositRewards
+///
+/// Then: depositor allowlist (pools with an allowlist), matching vault (pools
+/// with reward matching), current distribution history page (pools with a
+/// distribution history), depositor record (pools that track depositors),
+/// instructions sysvar (optional unless the pool sets stake_guard_required)
+pub mod deposit_rewards {
+    pub const POOL: usize = 0;
+    pub const DEPOSITOR: usize = 1;
+    pub const SYSTEM_PROGRAM: usize = 2;
+    pub const FIXED_LEN: usize = 3;
+}
+
+/// SyncPool / SweepDust / CheckSyncNeeded / PreviewRecoverStranded
+pub mod sync_pool {
+    pub const POOL: usize = 0;
+    pub const FIXED_LEN: usize = 1;
+}
+
+/// SyncRewards
+///
+/// Then: matching vault (pools with reward matching), current distribution
+/// history page (pools with a distribution history), instructions sysvar
+/// (optional unless the pool sets stake_guard_required)
+pub mod sync_rewards {
+    pub const POOL: usize = 0;
+    pub const FIXED_LEN: usize = 1;
+}
+
+/// UpdatePoolSettings / TransferAuthority / SetRewardsPaused / SetRefundPolicy /
+/// SetUnstakeRequestExpiry / SetRoundingMode / SetLockBoost / SetBootstrapPeriod /
+/// SetMinSyncInterval / SetClaimVesting / RenounceAuthorityUnsafe
+///
+/// Optional: system program (required on pools with an authority log), then
+/// the authority log (pools with an authority log)
+pub mod update_pool_settings {
+    pub const POOL: usize = 0;
+    pub const AUTHORITY: usize = 1;
+    pub const FIXED_LEN: usize = 2;
+}
+
+/// RequestUnstake / RequestUnstakeAll / CancelUnstakeRequest / CloseStakeAccount /
+/// SetRewardRecipient / SkimStakeAccountLamports
+///
+/// Then (RequestUnstake/RequestUnstakeAll): lock schedule (positions with lock tranches)
+///
+/// Then (CloseStakeAccount): rent sponsor vault (sponsored accounts of pools
+/// with a rent sponsor)
+///
+/// Optional: system program (all but CloseStakeAccount and
+/// SkimStakeAccountLamports),
+/// pool metadata (CloseStakeAccount)
+pub mod request_unstake {
+    pub const POOL: usize = 0;
+    pub const USER_STAKE: usize = 1;
+    pub const USER: usize = 2;
+    pub const FIXED_LEN: usize = 3;
+}
+
+/// SetDistributionThreshold
+///
+/// Optional: system program (legacy pool reallocation; required on pools with
+/// an authority log), then the authority log (pools with an authority log)
+pub mod set_distribution_threshold {
+    pub const POOL: usize = 0;
+    pub const AUTHORITY: usize = 1;
+    pub const MINT: usize = 2;
+    pub const FIXED_LEN: usize = 3;
+}
+
+/// SetUnstakeFee
+///
+/// Optional: system program (legacy pool reallocation; required on pools with
+/// an authority log), then the authority log (pools with an authority log)
+pub mod set_unstake_fee {
+    pub const POOL: usize = 0;
+    pub const AUTHORITY: usize = 1;
+    pub const FEE_DESTINATION: usize = 2;
+    pub const FIXED_LEN: usize = 3;
+}
+
+/// RefundUndistributed
+pub mod refund_undistributed {
+    pub const POOL: usize = 0;
+    pub const REFUND_ADDRESS: usize = 1;
+    pub const FIXED_LEN: usize = 2;
+}
+
+/// SetPoolMetadata
+pub mod set_pool_metadata {
+    pub const POOL: usize = 0;
+    pub const METADATA: usize = 1;
+    pub const MINT: usize = 2;
+    pub const PAYER: usize = 3;
+    pub const SYSTEM_PROGRAM: usize = 4;
+    pub const FIXED_LEN: usize = 5;
+}
+
+/// StakeOnBehalf
+///
+/// Then: receipt mint + beneficiary receipt token account (receipt pools),
+/// weight history (positions with history), optional metadata
+pub mod stake_on_behalf {
+    pub const POOL: usize = 0;
+    pub const BENEFICIARY_STAKE: usize = 1;
+    pub const TOKEN_VAULT: usize = 2;
+    pub const STAKER_TOKEN: usize = 3;
+    pub const MINT: usize = 4;
+    pub const STAKER: usize = 5;
+    pub const BENEFICIARY: usize = 6;
+    pub const SYSTEM_PROGRAM: usize = 7;
+    pub const TOKEN_PROGRAM: usize = 8;
+    pub const FIXED_LEN: usize = 9;
+}
+
+/// GetUserPosition
+pub mod get_user_position {
+    pub const POOL: usize = 0;
+    pub const USER_STAKE: usize = 1;
+    pub const FIXED_LEN: usize = 2;
+}
+
+/// InitWeightHistory
+pub mod init_weight_history {
+    pub const POOL: usize = 0;
+    pub const USER_STAKE: usize = 1;
+    pub const WEIGHT_HISTORY: usize = 2;
+    pub const USER: usize = 3;
+    pub const SYSTEM_PROGRAM: usize = 4;
+    pub const FIXED_LEN: usize = 5;
+}
+
+/// GetAverageWeightedStake
+pub mod get_average_weighted_stake {
+    pub const POOL: usize = 0;
+    pub const WEIGHT_HISTORY: usize = 1;
+    pub const FIXED_LEN: usize = 2;
+}
+
+/// GetUserStats
+pub mod get_user_stats {
+    pub const POOL: usize = 0;
+    pub const USER_STAKE: usize = 1;
+    pub const FIXED_LEN: usize = 2;
+}
+
+/// StakeLocked
+///
+/// Then: receipt mint + beneficiary receipt token account (receipt pools),
+/// weight history (positions with history), optional metadata
+pub mod stake_locked {
+    pub const POOL: usize = 0;
+    pub const BENEFICIARY_STAKE: usize = 1;
+    pub const TOKEN_VAULT: usize = 2;
+    pub const STAKER_TOKEN: usize = 3;
+    pub const MINT: usize = 4;
+    pub const STAKER: usize = 5;
+    pub const BENEFICIARY: usize = 6;
+    pub const SYSTEM_PROGRAM: usize = 7;
+    pub const TOKEN_PROGRAM: usize = 8;
+    pub const LOCK_SCHEDULE: usize = 9;
+    pub const FIXED_LEN: usize = 10;
+}
+
+/// InitAuthorityLog
+pub mod init_authority_log {
+    pub const POOL: usize = 0;
+    pub const AUTHORITY_LOG: usize = 1;
+    pub const PAYER: usize = 2;
+    pub const SYSTEM_PROGRAM: usize = 3;
+    pub const FIXED_LEN: usize = 4;
+}
+
+/// DepositRewardsSplit
+///
+/// Then: depositor allowlist (pools with an allowlist), depositor record
+/// (pools that track depositors)
+pub mod deposit_rewards_split {
+    pub const POOL: usize = 0;
+    pub const PENDING_DISTRIBUTIONS: usize = 1;
+    pub const DEPOSITOR: usize = 2;
+    pub const SYSTEM_PROGRAM: usize = 3;
+    pub const FIXED_LEN: usize = 4;
+}
+
+/// ReleasePendingDistributions
+///
+/// Then: SyncRewards' accounts after the pool (matching vault, distribution
+/// history page, instructions sysvar)
+pub mod release_pending_distributions {
+    pub const POOL: usize = 0;
+    pub const PENDING_DISTRIBUTIONS: usize = 1;
+    pub const FIXED_LEN: usize = 2;
+}
+
+/// AssertPoolHealthy
+pub mod assert_pool_healthy {
+    pub const POOL: usize = 0;
+    pub const TOKEN_VAULT: usize = 1;
+    pub const FIXED_LEN: usize = 2;
+}
+
+/// SetPayoutSplit
+pub mod set_payout_split {
+    pub const POOL: usize = 0;
+    pub const USER_STAKE: usize = 1;
+    pub const USER: usize = 2;
+    pub const PAYOUT_SPLIT: usize = 3;
+    pub const SYSTEM_PROGRAM: usize = 4;
+    pub const FIXED_LEN: usize = 5;
+}
+
+/// MigrateStake
+///
+/// Then: source weight history (positions with history), payout split + its
+/// destinations (positions with a split) or else reward recipient (positions
+/// with a declared recipient), source lock schedule (positions with lock
+/// tranches), guardian (signer, positions with a guardian), target weight
+/// history (existing target positions with history)
+pub mod migrate_stake {
+    pub const SOURCE_POOL: usize = 0;
+    pub const SOURCE_STAKE: usize = 1;
+    pub const SOURCE_VAULT: usize = 2;
+    pub const TARGET_POOL: usize = 3;
+    pub const TARGET_STAKE: usize = 4;
+    pub const TARGET_VAULT: usize = 5;
+    pub const MINT: usize = 6;
+    pub const USER: usize = 7;
+    pub const SYSTEM_PROGRAM: usize = 8;
+    pub const TOKEN_PROGRAM: usize = 9;
+    pub const FIXED_LEN: usize = 10;
+}
+
+/// InitProgramConfig
+pub mod init_program_config {
+    pub const PROGRAM_CONFIG: usize = 0;
+    pub const DEPLOYER: usize = 1;
+    pub const PROGRAM_DATA: usize = 2;
+    pub const SYSTEM_PROGRAM: usize = 3;
+    pub const FIXED_LEN: usize = 4;
+}
+
+/// AssertUpgradeAuthority
+pub mod assert_upgrade_authority {
+    pub const PROGRAM_CONFIG: usize = 0;
+    pub const PROGRAM_DATA: usize = 1;
+    pub const FIXED_LEN: usize = 2;
+}
+
+/// UpdateProgramConfig
+pub mod update_program_config {
+    pub const PROGRAM_CONFIG: usize = 0;
+    pub const ADMIN: usize = 1;
+    pub const FIXED_LEN: usize = 2;
+}
+
+/// CollectProtocolFees
+///
+/// Optional: system program (legacy pool reallocation)
+pub mod collect_protocol_fees {
+    pub const POOL: usize = 0;
+    pub const PROGRAM_CONFIG: usize = 1;
+    pub const FEE_TREASURY: usize = 2;
+    pub const PAYER: usize = 3;
+    pub const FIXED_LEN: usize = 4;
+}
+
+/// RecountRewardDebt
+///
+/// Trailing: user stake accounts of the pool, ascending by key
+pub mod recount_reward_debt {
+    pub const POOL: usize = 0;
+    pub const DEBT_RECOUNT: usize = 1;
+    pub const PAYER: usize = 2;
+    pub const SYSTEM_PROGRAM: usize = 3;
+    pub const FIXED_LEN: usize = 4;
+}
+
+/// SetDepositorAllowlist
+///
+/// Then: authority log (pools with an authority log)
+pub mod set_depositor_allowlist {
+    pub const POOL: usize = 0;
+    pub const AUTHORITY: usize = 1;
+    pub const DEPOSITOR_ALLOWLIST: usize = 2;
+    pub const SYSTEM_PROGRAM: usize = 3;
+    pub const FIXED_LEN: usize = 4;
+}
+
+/// ExtendLock
+///
+/// Then: weight history (positions with history), optional system program
+/// (legacy account reallocation)
+pub mod extend_lock {
+    pub const POOL: usize = 0;
+    pub const USER_STAKE: usize = 1;
+    pub const USER: usize = 2;
+    pub const FIXED_LEN: usize = 3;
+}
+
+/// SetMaturityBonus
+///
+/// Then: authority log (pools with an authority log)
+pub mod set_maturity_bonus {
+    pub const POOL: usize = 0;
+    pub const AUTHORITY: usize = 1;
+    pub const BONUS_BUDGET: usize = 2;
+    pub const SYSTEM_PROGRAM: usize = 3;
+    pub const FIXED_LEN: usize = 4;
+}
+
+/// ClaimMaturityBonus
+///
+/// Then: optional system program (legacy account reallocation)
+pub mod claim_maturity_bonus {
+    pub const POOL: usize = 0;
+    pub const USER_STAKE: usize = 1;
+    pub const USER: usize = 2;
+    pub const BONUS_BUDGET: usize = 3;
+    pub const FIXED_LEN: usize = 4;
+}
+
+/// SetGuardian
+///
+/// Then: current guardian (signer, positions with a guardian), optional
+/// system program (legacy account reallocation)
+pub mod set_guardian {
+    pub const POOL: usize = 0;
+    pub const USER_STAKE: usize = 1;
+    pub const USER: usize = 2;
+    pub const FIXED_LEN: usize = 3;
+}
+
+/// ClearGuardian
+///
+/// Then: optional system program (legacy account reallocation)
+pub mod clear_guardian {
+    pub const POOL: usize = 0;
+    pub const USER_STAKE: usize = 1;
+    pub const USER: usize = 2;
+    pub const GUARDIAN: usize = 3;
+    pub const FIXED_LEN: usize = 4;
+}
+
+/// SetRewardMatching
+///
+/// Then: authority log (pools with an authority log)
+pub mod set_reward_matching {
+    pub const POOL: usize = 0;
+    pub const AUTHORITY: usize = 1;
+    pub const MATCHING_VAULT: usize = 2;
+    pub const SYSTEM_PROGRAM: usize = 3;
+    pub const FIXED_LEN: usize = 4;
+}
+
+/// InitCompressionTree
+///
+/// Then: authority log (pools with an authority log)
+pub mod init_compression_tree {
+    pub const POOL: usize = 0;
+    pub const AUTHORITY: usize = 1;
+    pub const MERKLE_TREE: usize = 2;
+    pub const COMPRESSION_PROGRAM: usize = 3;
+    pub const NOOP_PROGRAM: usize = 4;
+    pub const SYSTEM_PROGRAM: usize = 5;
+    pub const FIXED_LEN: usize = 6;
+}
+
+/// StakeCompressed / DecompressStake
+///
+/// Then (DecompressStake): proof nodes, leaf to root (minus the canopy)
+pub mod stake_compressed {
+    pub const POOL: usize = 0;
+    pub const USER_STAKE: usize = 1;
+    pub const USER: usize = 2;
+    pub const MERKLE_TREE: usize = 3;
+    pub const COMPRESSION_PROGRAM: usize = 4;
+    pub const NOOP_PROGRAM: usize = 5;
+    pub const SYSTEM_PROGRAM: usize = 6;
+    pub const FIXED_LEN: usize = 7;
+}
+
+/// SetRentSponsor
+///
+/// Then: authority log (pools with an authority log)
+pub mod set_rent_sponsor {
+    pub const POOL: usize = 0;
+    pub const AUTHORITY: usize = 1;
+    pub const SPONSOR_VAULT: usize = 2;
+    pub const SYSTEM_PROGRAM: usize = 3;
+    pub const FIXED_LEN: usize = 4;
+}
+
+/// SetWeightTiers
+///
+/// Then: authority log (pools with an authority log)
+pub mod set_weight_tiers {
+    pub const POOL: usize = 0;
+    pub const AUTHORITY: usize = 1;
+    pub const WEIGHT_TIERS: usize = 2;
+    pub const SYSTEM_PROGRAM: usize = 3;
+    pub const FIXED_LEN: usize = 4;
+}
+
+/// GetWeightTier
+pub mod get_weight_tier {
+    pub const POOL: usize = 0;
+    pub const USER_STAKE: usize = 1;
+    pub const WEIGHT_TIERS: usize = 2;
+    pub const FIXED_LEN: usize = 3;
+}
+
+/// SetPriceOracle
+///
+/// Then: authority log (pools with an authority log)
+pub mod set_price_oracle {
+    pub const POOL: usize = 0;
+    pub const AUTHORITY: usize = 1;
+    /// The system program removes the oracle
+    pub const PRICE_ACCOUNT: usize = 2;
+    pub const SYSTEM_PROGRAM: usize = 3;
+    pub const FIXED_LEN: usize = 4;
+}
+
+/// GetPoolStats
+///
+/// Then: price account (pools with a price oracle), optional SOL/USD price
+/// account
+pub mod get_pool_stats {
+    pub const POOL: usize = 0;
+    pub const MINT: usize = 1;
+    pub const FIXED_LEN: usize = 2;
+}
+
+/// SetClaimRebate
+///
+/// Then: authority log (pools with an authority log)
+pub mod set_claim_rebate {
+    pub const POOL: usize = 0;
+    pub const AUTHORITY: usize = 1;
+    pub const GAS_REBATE_VAULT: usize = 2;
+    pub const SYSTEM_PROGRAM: usize = 3;
+    pub const FIXED_LEN: usize = 4;
+}
+
+/// FundGasRebates
+pub mod fund_gas_rebates {
+    pub const POOL: usize = 0;
+    pub const GAS_REBATE_VAULT: usize = 1;
+    pub const FUNDER: usize = 2;
+    pub const SYSTEM_PROGRAM: usize = 3;
+    pub const FIXED_LEN: usize = 4;
+}
+
+/// SetClaimBurn
+///
+/// Optional: system program (legacy pool reallocation; required on pools with
+/// an authority log), then the authority log (pools with an authority log)
+pub mod set_claim_burn {
+    pub const POOL: usize = 0;
+    pub const AUTHORITY: usize = 1;
+    pub const BURN_DESTINATION: usize = 2;
+    pub const FIXED_LEN: usize = 3;
+}
+
+/// TokenizePosition
+pub mod tokenize_position {
+    pub const POOL: usize = 0;
+    pub const USER_STAKE: usize = 1;
+    pub const USER: usize = 2;
+    pub const POSITION_MINT: usize = 3;
+    pub const TOKEN_ACCOUNT: usize = 4;
+    pub const TOKEN_PROGRAM: usize = 5;
+    pub const ASSOCIATED_TOKEN_PROGRAM: usize = 6;
+    pub const SYSTEM_PROGRAM: usize = 7;
+    pub const FIXED_LEN: usize = 8;
+}
+
+/// RedeemPosition
+pub mod redeem_position {
+    pub const POOL: usize = 0;
+    pub const USER_STAKE: usize = 1;
+    pub const HOLDER: usize = 2;
+    pub const HOLDER_STAKE: usize = 3;
+    pub const POSITION_MINT: usize = 4;
+    pub const TOKEN_ACCOUNT: usize = 5;
+    pub const TOKEN_PROGRAM: usize = 6;
+    pub const SYSTEM_PROGRAM: usize = 7;
+    pub const FIXED_LEN: usize = 8;
+}
+
+/// SetResidualsFirst
+///
+/// Optional: system program (legacy pool reallocation; required on pools with
+/// an authority log), then the authority log (pools with an authority log)
+pub mod set_residuals_first {
+    pub const POOL: usize = 0;
+    pub const AUTHORITY: usize = 1;
+    pub const FIXED_LEN: usize = 2;
+}
+
+/// ClaimWithSession
+///
+/// Then: payout split + its destinations (positions with a split) or else
+/// reward recipient (positions with a declared recipient), gas rebate vault
+/// (pools with claim rebates), burn destination (pools with a claim burn),
+/// optional system program (legacy account reallocation)
+pub mod claim_with_session {
+    pub const POOL: usize = 0;
+    pub const USER_STAKE: usize = 1;
+    pub const OWNER: usize = 2;
+    pub const SESSION_KEY: usize = 3;
+    pub const INSTRUCTIONS_SYSVAR: usize = 4;
+    pub const FIXED_LEN: usize = 5;
+}
+
+/// SetMinClaimLamports
+///
+/// Optional: system program (legacy pool reallocation; required on pools with
+/// an authority log), then the authority log (pools with an authority log)
+pub mod set_min_claim_lamports {
+    pub const POOL: usize = 0;
+    pub const AUTHORITY: usize = 1;
+    pub const FIXED_LEN: usize = 2;
+}
+
+/// InitDistributionHistory
+///
+/// Then: the authority log (pools with an authority log)
+pub mod init_distribution_history {
+    pub const POOL: usize = 0;
+    pub const AUTHORITY: usize = 1;
+    pub const PAGE: usize = 2;
+    pub const SYSTEM_PROGRAM: usize = 3;
+    pub const FIXED_LEN: usize = 4;
+}
+
+/// AddDistributionHistoryPage
+pub mod add_distribution_history_page {
+    pub const POOL: usize = 0;
+    pub const PAGE: usize = 1;
+    pub const PAYER: usize = 2;
+    pub const SYSTEM_PROGRAM: usize = 3;
+    pub const FIXED_LEN: usize = 4;
+}
+
+/// ClosePoolMetadata
+///
+/// Optional: system program (required on pools with an authority log), then
+/// the authority log (pools with an authority log)
+pub mod close_pool_metadata {
+    pub const POOL: usize = 0;
+    pub const AUTHORITY: usize = 1;
+    pub const METADATA: usize = 2;
+    pub const FIXED_LEN: usize = 3;
+}
+
+/// CloseStakeAccountArchived
+///
+/// Then: rent sponsor vault (sponsored accounts of pools with a rent sponsor)
+///
+/// Optional: pool metadata
+pub mod close_stake_account_archived {
+    pub const POOL: usize = 0;
+    pub const USER_STAKE: usize = 1;
+    pub const USER: usize = 2;
+    pub const ARCHIVE: usize = 3;
+    pub const SYSTEM_PROGRAM: usize = 4;
+    pub const FIXED_LEN: usize = 5;
+}
+
+/// CloseArchived
+pub mod close_archived {
+    pub const ARCHIVE: usize = 0;
+    pub const OWNER: usize = 1;
+    pub const FIXED_LEN: usize = 2;
+}
+
+/// SetDepositorTracking
+///
+/// Optional: system program (legacy pool reallocation; required on pools with
+/// an authority log), then the authority log (pools with an authority log)
+pub mod set_depositor_tracking {
+    pub const POOL: usize = 0;
+    pub const AUTHORITY: usize = 1;
+    pub const FIXED_LEN: usize = 2;
+}
+
+/// SetRebaseThreshold
+///
+/// Optional: system program (legacy pool reallocation; required on pools with
+/// an authority log), then the authority log (pools with an authority log)
+pub mod set_rebase_threshold {
+    pub const POOL: usize = 0;
+    pub const AUTHORITY: usize = 1;
+    pub const FIXED_LEN: usize = 2;
+}
+
+/// ExitPool
+///
+/// Then: receipt mint + user receipt token (receipt pools), fee destination
+/// (pools with an unstake fee), weight history (positions with history),
+/// payout split + its destinations or else reward recipient, lock schedule
+/// (positions with lock tranches), guardian (signer, positions with a
+/// guardian), memo program (user token accounts requiring memos), gas
+/// rebate vault (pools with claim rebates), burn destination
+/// (pools with a claim burn), rent sponsor vault (sponsored accounts of pools
+/// with a rent sponsor), optional pool metadata
+pub mod exit_pool {
+    pub const POOL: usize = 0;
+    pub const USER_STAKE: usize = 1;
+    pub const TOKEN_VAULT: usize = 2;
+    pub const USER_TOKEN: usize = 3;
+    pub const MINT: usize = 4;
+    pub const USER: usize = 5;
+    pub const TOKEN_PROGRAM: usize = 6;
+    pub const SYSTEM_PROGRAM: usize = 7;
+    pub const FIXED_LEN: usize = 8;
+}
+
+/// MigrateVault
+///
+/// New vault: ["token_vault", pool, vault_generation + 1 (u32 LE)]. Then the
+/// authority log (pools with an authority log)
+pub mod migrate_vault {
+    pub const POOL: usize = 0;
+    pub const MINT: usize = 1;
+    pub const OLD_VAULT: usize = 2;
+    pub const NEW_VAULT: usize = 3;
+    pub const AUTHORITY: usize = 4;
+    pub const SYSTEM_PROGRAM: usize = 5;
+    pub const TOKEN_PROGRAM: usize = 6;
+    pub const FIXED_LEN: usize = 7;
+}
+
+/// SnapshotVotingWeights
+///
+/// Then: user stake accounts, by increasing owner
+pub mod snapshot_voting_weights {
+    pub const POOL: usize = 0;
+    pub const SNAPSHOT: usize = 1;
+    pub const PAGE: usize = 2;
+    pub const PAYER: usize = 3;
+    pub const SYSTEM_PROGRAM: usize = 4;
+    pub const FIXED_LEN: usize = 5;
+}
+
+/// SetLockExemptAmount
+///
+/// Optional: system program (legacy pool reallocation; required on pools with
+/// an authority log), then the authority log (pools with an authority log)
+pub mod set_lock_exempt_amount {
+    pub const POOL: usize = 0;
+    pub const AUTHORITY: usize = 1;
+    pub const FIXED_LEN: usize = 2;
+}
+
+/// ReconcileSyncedLamports
+pub mod reconcile_synced_lamports {
+    pub const POOL: usize = 0;
+    pub const FIXED_LEN: usize = 1;
+}
+
+/// SetStakeFrozen
+///
+/// Optional: system program (legacy stake reallocation; required on pools
+/// with an authority log), then the authority log (pools with an authority log)
+pub mod set_stake_frozen {
+    pub const POOL: usize = 0;
+    pub const USER_STAKE: usize = 1;
+    pub const AUTHORITY: usize = 2;
+    pub const FIXED_LEN: usize = 3;
+}
+
+/// ClonePoolConfig
+///
+/// Optional: source pool metadata, then the new pool's metadata (both or
+/// neither)
+pub mod clone_pool_config {
+    pub const POOL: usize = 0;
+    pub const MINT: usize = 1;
+    pub const TOKEN_VAULT: usize = 2;
+    pub const AUTHORITY: usize = 3;
+    pub const SYSTEM_PROGRAM: usize = 4;
+    pub const TOKEN_PROGRAM: usize = 5;
+    pub const RENT_SYSVAR: usize = 6;
+    pub const SOURCE_POOL: usize = 7;
+    pub const FIXED_LEN: usize = 8;
+}
+
+/// SetStakeGuardRequired
+///
+/// Optional: system program (legacy pool reallocation; required on pools with
+/// an authority log), then the authority log (pools with an authority log)
+pub mod set_stake_guard_required {
+    pub const POOL: usize = 0;
+    pub const AUTHORITY: usize = 1;
+    pub const FIXED_LEN: usize = 2;
+}
+
+/// InitializeBucketedPool
+pub mod initialize_bucketed_pool {
+    pub const POOL: usize = 0;
+    pub const MINT: usize = 1;
+    pub const TOKEN_VAULT: usize = 2;
+    pub const AUTHORITY: usize = 3;
+    pub const SYSTEM_PROGRAM: usize = 4;
+    pub const TOKEN_PROGRAM: usize = 5;
+    pub const RENT_SYSVAR: usize = 6;
+    pub const AGE_BUCKETS: usize = 7;
+    pub const HISTORY_PAGE: usize = 8;
+    pub const FIXED_LEN: usize = 9;
+}
+
+/// RollAgeBuckets
+pub mod roll_age_buckets {
+    pub const POOL: usize = 0;
+    pub const AGE_BUCKETS: usize = 1;
+    pub const HISTORY_PAGE: usize = 2;
+    pub const PAYER: usize = 3;
+    pub const SYSTEM_PROGRAM: usize = 4;
+    pub const FIXED_LEN: usize = 5;
+}
+
+/// Stake on a bucketed pool
+pub mod bucketed_stake {
+    pub const POOL: usize = 0;
+    pub const USER_STAKE: usize = 1;
+    pub const TOKEN_VAULT: usize = 2;
+    pub const USER_TOKEN: usize = 3;
+    pub const MINT: usize = 4;
+    pub const USER: usize = 5;
+    pub const SYSTEM_PROGRAM: usize = 6;
+    pub const TOKEN_PROGRAM: usize = 7;
+    pub const AGE_BUCKETS: usize = 8;
+    pub const FIXED_LEN: usize = 9;
+}
+
+/// Unstake / UnstakeAll on a bucketed pool
+///
+/// Then: the age bucket history pages of the position's first epochs
+/// (positions older than the current epoch)
+pub mod bucketed_unstake {
+    pub const POOL: usize = 0;
+    pub const USER_STAKE: usize = 1;
+    pub const TOKEN_VAULT: usize = 2;
+    pub const USER_TOKEN: usize = 3;
+    pub const MINT: usize = 4;
+    pub const USER: usize = 5;
+    pub const TOKEN_PROGRAM: usize = 6;
+    pub const AGE_BUCKETS: usize = 7;
+    pub const FIXED_LEN: usize = 8;
+}
+
+/// ClaimRewards on a bucketed pool
+///
+/// Then: the age bucket history pages of the position's first epochs
+/// (positions older than the current epoch)
+pub mod bucketed_claim_rewards {
+    pub const POOL: usize = 0;
+    pub const USER_STAKE: usize = 1;
+    pub const USER: usize = 2;
+    pub const AGE_BUCKETS: usize = 3;
+    pub const FIXED_LEN: usize = 4;
+}
+
+/// DepositRewards on a bucketed pool
+pub mod bucketed_deposit_rewards {
+    pub const POOL: usize = 0;
+    pub const DEPOSITOR: usize = 1;
+    pub const SYSTEM_PROGRAM: usize = 2;
+    pub const AGE_BUCKETS: usize = 3;
+    pub const FIXED_LEN: usize = 4;
+}
+
+/// SyncRewards on a bucketed pool
+pub mod bucketed_sync_rewards {
+    pub const POOL: usize = 0;
+    pub const AGE_BUCKETS: usize = 1;
+    pub const FIXED_LEN: usize = 2;
+}
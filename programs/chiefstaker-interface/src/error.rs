@@ -0,0 +1,521 @@
+//! Program error codes
+//!
+//! Codes are explicitly numbered and must never be reordered or reused:
+//! clients map `custom program error: 0x..` back to a variant by value.
+//! Errors that have useful numbers attached (seconds left on a lock,
+//! lamports missing, ...) additionally publish an [`ErrorContext`] through
+//! return data so simulations can render an actionable message.
+
+use chiefstaker_core::error::MathError;
+use solana_program::{program::set_return_data, program_error::ProgramError};
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum StakingError {
+    #[error("Invalid instruction data")]
+    InvalidInstruction = 0,
+
+    #[error("Account already initialized")]
+    AlreadyInitialized = 1,
+
+    #[error("Account not initialized")]
+    NotInitialized = 2,
+
+    #[error("Invalid pool mint")]
+    InvalidPoolMint = 3,
+
+    #[error("Invalid token vault")]
+    InvalidTokenVault = 4,
+
+    /// UNUSED: Retained for ABI stability (error code numbering).
+    #[error("Invalid reward vault")]
+    InvalidRewardVault = 5,
+
+    #[error("Invalid authority")]
+    InvalidAuthority = 6,
+
+    #[error("Invalid owner")]
+    InvalidOwner = 7,
+
+    #[error("Invalid pool")]
+    InvalidPool = 8,
+
+    #[error("Invalid PDA")]
+    InvalidPDA = 9,
+
+    #[error("Insufficient stake balance")]
+    InsufficientStakeBalance = 10,
+
+    #[error("Insufficient reward balance")]
+    InsufficientRewardBalance = 11,
+
+    #[error("Math overflow")]
+    MathOverflow = 12,
+
+    #[error("Math underflow")]
+    MathUnderflow = 13,
+
+    #[error("Zero amount not allowed")]
+    ZeroAmount = 14,
+
+    #[error("Invalid tau value")]
+    InvalidTau = 15,
+
+    #[error("Pool requires sync before operation")]
+    PoolRequiresSync = 16,
+
+    #[error("Invalid mint - must be Token 2022")]
+    InvalidMintProgram = 17,
+
+    #[error("Missing required signer")]
+    MissingRequiredSigner = 18,
+
+    #[error("Account data too small")]
+    AccountDataTooSmall = 19,
+
+    #[error("Invalid account owner")]
+    InvalidAccountOwner = 20,
+
+    #[error("Stake amount below pool minimum")]
+    BelowMinimumStake = 21,
+
+    #[error("Stake is locked - lock duration has not elapsed")]
+    StakeLocked = 22,
+
+    #[error("Unstake cooldown period has not elapsed")]
+    CooldownNotElapsed = 23,
+
+    #[error("Pool requires RequestUnstake flow, not direct Unstake")]
+    CooldownRequired = 24,
+
+    #[error("No pending unstake request")]
+    NoPendingUnstakeRequest = 25,
+
+    #[error("Must cancel existing unstake request first")]
+    PendingUnstakeRequestExists = 26,
+
+    #[error("Authority has been renounced")]
+    AuthorityRenounced = 27,
+
+    #[error("Pool has no cooldown configured - use direct Unstake instead")]
+    CooldownNotConfigured = 28,
+
+    #[error("Setting value exceeds maximum allowed")]
+    SettingExceedsMaximum = 29,
+
+    #[error("User stake account still has balance or pending requests")]
+    AccountNotEmpty = 30,
+
+    #[error("Invalid Token 2022 program")]
+    InvalidTokenProgram = 31,
+
+    #[error("Token mint has a dangerous extension (PermanentDelegate, TransferHook, etc.)")]
+    UnsupportedMintExtension = 32,
+
+    #[error("System program required for legacy account reallocation")]
+    MissingSystemProgram = 33,
+
+    #[error("New total_reward_debt exceeds maximum accumulated rewards")]
+    RewardDebtExceedsBound = 34,
+
+    #[error("A stake into this pool earlier in the transaction blocks reward distribution")]
+    StakeBeforeDistribution = 35,
+
+    #[error("Stake and unstake of the same position in one slot is not allowed")]
+    SameSlotAction = 36,
+
+    #[error("Receipt mint does not match the pool's receipt mint")]
+    InvalidReceiptMint = 37,
+
+    #[error("Requested period starts before the oldest retained checkpoint")]
+    HistoryWindowExceeded = 38,
+
+    #[error("Reward recipient does not match the stake account")]
+    InvalidRewardRecipient = 39,
+
+    #[error("Account is not a native (wrapped SOL) token account")]
+    InvalidWrappedSolAccount = 40,
+
+    #[error("Pool has no refund policy")]
+    RefundPolicyNotSet = 41,
+
+    #[error("Refund account does not match the pool's refund address")]
+    InvalidRefundAddress = 42,
+
+    #[error("Undistributed SOL has not been idle long enough to refund")]
+    RefundNotDue = 43,
+
+    #[error("Pool has stakers; undistributed SOL can still be distributed")]
+    PoolHasStakers = 44,
+
+    #[error("Accounting invariant violated (strict-invariants builds only)")]
+    InvariantViolation = 45,
+
+    #[error("Fee destination does not match the pool's unstake fee destination")]
+    InvalidFeeDestination = 46,
+
+    #[error("Amount exceeds the stake not held by lock tranches")]
+    InsufficientUnlockedStake = 47,
+
+    #[error("Lock schedule has no room for another tranche")]
+    LockScheduleFull = 48,
+
+    #[error("Unlock time must be in the future")]
+    InvalidUnlockTime = 49,
+
+    #[error("Distribution schedule must be non-empty and sum to 10000 bps")]
+    InvalidDistributionSchedule = 50,
+
+    #[error("Pending distributions have no room for more tranches")]
+    PendingDistributionsFull = 51,
+
+    #[error("Unstake request expired after its grace window")]
+    UnstakeRequestExpired = 52,
+
+    #[error("Payout split must have 1-4 distinct destinations summing to 10000 bps")]
+    InvalidPayoutSplit = 53,
+
+    #[error("Stake can't migrate between these pools")]
+    MigrationNotAllowed = 54,
+
+    #[error("Program upgrade authority doesn't match the program config")]
+    UpgradeAuthorityMismatch = 55,
+
+    #[error("Fee treasury is missing or does not match the program config")]
+    InvalidFeeTreasury = 56,
+
+    #[error("Reward debt recount is out of sync with the pool; restart from cursor 0")]
+    RecountOutOfSync = 57,
+
+    #[error("Depositor is not on the pool's depositor allowlist")]
+    DepositorNotAllowed = 58,
+
+    #[error("Depositor allowlist must have 1-8 distinct depositors")]
+    InvalidDepositorAllowlist = 59,
+
+    #[error("This pool does not grant a weight boost for extending locks")]
+    LockBoostDisabled = 60,
+
+    #[error("This pool does not pay a maturity bonus")]
+    MaturityBonusDisabled = 61,
+
+    #[error("Stake has not reached maturity (3 tau) yet")]
+    StakeNotMature = 62,
+
+    #[error("Maturity bonus already paid for this position")]
+    MaturityBonusAlreadyPaid = 63,
+
+    #[error("Bonus budget cannot cover the maturity bonus")]
+    InsufficientBonusBudget = 64,
+
+    #[error("Position has a guardian, which must co-sign")]
+    GuardianSignatureRequired = 65,
+
+    #[error("Guardian removal is still timelocked")]
+    GuardianRemovalTimelocked = 66,
+
+    #[error("Invalid guardian")]
+    InvalidGuardian = 67,
+
+    #[error("Pool has no compression tree")]
+    CompressionNotEnabled = 68,
+
+    #[error("Invalid compression tree or compression program")]
+    InvalidCompressionTree = 69,
+
+    #[error("Position can't be compressed (empty, pending request, or companion accounts)")]
+    PositionNotCompressible = 70,
+
+    #[error("Weight tiers must have 1-8 increasing, nonzero thresholds")]
+    InvalidWeightTiers = 71,
+
+    #[error("Invalid price oracle (unsupported owner, layout or price)")]
+    InvalidOracle = 72,
+
+    #[error("Burn destination must be the incinerator")]
+    InvalidBurnDestination = 73,
+
+    #[error("Position is held as an NFT; redeem it first")]
+    PositionTokenized = 74,
+
+    #[error("Position cannot be tokenized")]
+    PositionNotTokenizable = 75,
+
+    #[error("Mint is not the position's NFT mint")]
+    InvalidPositionMint = 76,
+
+    #[error("No Ed25519 instruction signs this claim session")]
+    InvalidSessionProof = 77,
+
+    #[error("Claim session has expired")]
+    SessionExpired = 78,
+
+    #[error("Lock duration or unstake cooldown too long to renounce authority")]
+    UnsafeRenounceSettings = 79,
+
+    #[error("Clock is behind the pool's recorded time")]
+    ClockWentBackwards = 80,
+
+    #[error("Snapshot entries must be in strictly increasing owner order")]
+    SnapshotOutOfOrder = 81,
+
+    #[error("Destination token account requires memos; pass the memo program")]
+    MemoProgramRequired = 82,
+
+    #[error("Position's rewards are frozen by the pool authority")]
+    PositionFrozen = 83,
+
+    #[error("Mint has no supply; only its mint authority can create a pool for it")]
+    MintHasNoSupply = 84,
+
+    #[error("Pool requires the instructions sysvar after the distribution accounts")]
+    InstructionsSysvarRequired = 85,
+
+    #[error("Instruction not supported by the pool's weight engine")]
+    UnsupportedWeightEngine = 86,
+
+    #[error("Age buckets must be rolled to the current epoch first")]
+    AgeBucketsNotRolled = 87,
+
+    #[error("Bucketed positions can only be topped up in the epoch they entered")]
+    AgedPositionTopUp = 88,
+}
+
+impl StakingError {
+    /// All variants in code order (index == error code)
+    pub const ALL: [StakingError; 89] = [
+        StakingError::InvalidInstruction,
+        StakingError::AlreadyInitialized,
+        StakingError::NotInitialized,
+        StakingError::InvalidPoolMint,
+        StakingError::InvalidTokenVault,
+        StakingError::InvalidRewardVault,
+        StakingError::InvalidAuthority,
+        StakingError::InvalidOwner,
+        StakingError::InvalidPool,
+        StakingError::InvalidPDA,
+        StakingError::InsufficientStakeBalance,
+        StakingError::InsufficientRewardBalance,
+        StakingError::MathOverflow,
+        StakingError::MathUnderflow,
+        StakingError::ZeroAmount,
+        StakingError::InvalidTau,
+        StakingError::PoolRequiresSync,
+        StakingError::InvalidMintProgram,
+        StakingError::MissingRequiredSigner,
+        StakingError::AccountDataTooSmall,
+        StakingError::InvalidAccountOwner,
+        StakingError::BelowMinimumStake,
+        StakingError::StakeLocked,
+        StakingError::CooldownNotElapsed,
+        StakingError::CooldownRequired,
+        StakingError::NoPendingUnstakeRequest,
+        StakingError::PendingUnstakeRequestExists,
+        StakingError::AuthorityRenounced,
+        StakingError::CooldownNotConfigured,
+        StakingError::SettingExceedsMaximum,
+        StakingError::AccountNotEmpty,
+        StakingError::InvalidTokenProgram,
+        StakingError::UnsupportedMintExtension,
+        StakingError::MissingSystemProgram,
+        StakingError::RewardDebtExceedsBound,
+        StakingError::StakeBeforeDistribution,
+        StakingError::SameSlotAction,
+        StakingError::InvalidReceiptMint,
+        StakingError::HistoryWindowExceeded,
+        StakingError::InvalidRewardRecipient,
+        StakingError::InvalidWrappedSolAccount,
+        StakingError::RefundPolicyNotSet,
+        StakingError::InvalidRefundAddress,
+        StakingError::RefundNotDue,
+        StakingError::PoolHasStakers,
+        StakingError::InvariantViolation,
+        StakingError::InvalidFeeDestination,
+        StakingError::InsufficientUnlockedStake,
+        StakingError::LockScheduleFull,
+        StakingError::InvalidUnlockTime,
+        StakingError::InvalidDistributionSchedule,
+        StakingError::PendingDistributionsFull,
+        StakingError::UnstakeRequestExpired,
+        StakingError::InvalidPayoutSplit,
+        StakingError::MigrationNotAllowed,
+        StakingError::UpgradeAuthorityMismatch,
+        StakingError::InvalidFeeTreasury,
+        StakingError::RecountOutOfSync,
+        StakingError::DepositorNotAllowed,
+        StakingError::InvalidDepositorAllowlist,
+        StakingError::LockBoostDisabled,
+        StakingError::MaturityBonusDisabled,
+        StakingError::StakeNotMature,
+        StakingError::MaturityBonusAlreadyPaid,
+        StakingError::InsufficientBonusBudget,
+        StakingError::GuardianSignatureRequired,
+        StakingError::GuardianRemovalTimelocked,
+        StakingError::InvalidGuardian,
+        StakingError::CompressionNotEnabled,
+        StakingError::InvalidCompressionTree,
+        StakingError::PositionNotCompressible,
+        StakingError::InvalidWeightTiers,
+        StakingError::InvalidOracle,
+        StakingError::InvalidBurnDestination,
+        StakingError::PositionTokenized,
+        StakingError::PositionNotTokenizable,
+        StakingError::InvalidPositionMint,
+        StakingError::InvalidSessionProof,
+        StakingError::SessionExpired,
+        StakingError::UnsafeRenounceSettings,
+        StakingError::ClockWentBackwards,
+        StakingError::SnapshotOutOfOrder,
+        StakingError::MemoProgramRequired,
+        StakingError::PositionFrozen,
+        StakingError::MintHasNoSupply,
+        StakingError::InstructionsSysvarRequired,
+        StakingError::UnsupportedWeightEngine,
+        StakingError::AgeBucketsNotRolled,
+        StakingError::AgedPositionTopUp,
+    ];
+
+    /// Numeric error code as reported in `ProgramError::Custom`
+    pub const fn code(self) -> u32 {
+        self as u32
+    }
+
+    /// Publish `context` as return data and convert into a `ProgramError`.
+    ///
+    /// Usage: `return Err(StakingError::StakeLocked.with_context(ErrorContext::RemainingSeconds(n)));`
+    pub fn with_context(self, context: ErrorContext) -> ProgramError {
+        set_return_data(&context.encode(self));
+        self.into()
+    }
+}
+
+impl TryFrom<u32> for StakingError {
+    type Error = ProgramError;
+
+    fn try_from(code: u32) -> Result<Self, Self::Error> {
+        StakingError::ALL
+            .get(code as usize)
+            .copied()
+            .ok_or(ProgramError::InvalidArgument)
+    }
+}
+
+impl From<MathError> for StakingError {
+    fn from(e: MathError) -> Self {
+        match e {
+            MathError::Overflow => StakingError::MathOverflow,
+            MathError::Underflow => StakingError::MathUnderflow,
+            MathError::InvalidTau => StakingError::InvalidTau,
+        }
+    }
+}
+
+impl From<StakingError> for ProgramError {
+    fn from(e: StakingError) -> Self {
+        ProgramError::Custom(e.code())
+    }
+}
+
+/// Machine-readable detail attached to a failed instruction via return data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorContext {
+    /// Seconds left until a lock or cooldown elapses
+    RemainingSeconds(u64),
+    /// Lamports missing to satisfy the request in full
+    LamportsShort(u64),
+    /// Smallest amount that would have been accepted
+    MinimumAmount(u64),
+    /// Largest amount or value that would have been accepted
+    MaximumAmount(u64),
+    /// Amount currently available (e.g. staked balance)
+    AvailableAmount(u64),
+}
+
+/// sha256("error:ErrorContext")[..8]
+pub const ERROR_CONTEXT_DISCRIMINATOR: [u8; 8] = [0xd0, 0x72, 0x0c, 0x82, 0xad, 0xc9, 0x3a, 0xa8];
+
+impl ErrorContext {
+    /// Size of the encoded context in bytes
+    pub const LEN: usize = 8 + 4 + 1 + 8;
+
+    fn kind(&self) -> (u8, u64) {
+        match *self {
+            ErrorContext::RemainingSeconds(v) => (0, v),
+            ErrorContext::LamportsShort(v) => (1, v),
+            ErrorContext::MinimumAmount(v) => (2, v),
+            ErrorContext::MaximumAmount(v) => (3, v),
+            ErrorContext::AvailableAmount(v) => (4, v),
+        }
+    }
+
+    /// Encode as return data.
+    ///
+    /// Layout: 8 discriminator + 4 error code (LE) + 1 kind + 8 value (LE)
+    pub fn encode(&self, error: StakingError) -> [u8; Self::LEN] {
+        let (kind, value) = self.kind();
+        let mut data = [0u8; Self::LEN];
+        data[..8].copy_from_slice(&ERROR_CONTEXT_DISCRIMINATOR);
+        data[8..12].copy_from_slice(&error.code().to_le_bytes());
+        data[12] = kind;
+        data[13..21].copy_from_slice(&value.to_le_bytes());
+        data
+    }
+
+    /// Decode return data produced by [`StakingError::with_context`].
+    /// Returns None if the data is not an error context.
+    pub fn decode(data: &[u8]) -> Option<(StakingError, ErrorContext)> {
+        if data.len() != Self::LEN || data[..8] != ERROR_CONTEXT_DISCRIMINATOR {
+            return None;
+        }
+        let code = u32::from_le_bytes(data[8..12].try_into().ok()?);
+        let error = StakingError::try_from(code).ok()?;
+        let value = u64::from_le_bytes(data[13..21].try_into().ok()?);
+        let context = match data[12] {
+            0 => ErrorContext::RemainingSeconds(value),
+            1 => ErrorContext::LamportsShort(value),
+            2 => ErrorContext::MinimumAmount(value),
+            3 => ErrorContext::MaximumAmount(value),
+            4 => ErrorContext::AvailableAmount(value),
+            _ => return None,
+        };
+        Some((error, context))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_codes_stable() {
+        assert_eq!(StakingError::InvalidInstruction.code(), 0);
+        assert_eq!(StakingError::PoolRequiresSync.code(), 16);
+        assert_eq!(StakingError::CooldownNotElapsed.code(), 23);
+        assert_eq!(StakingError::RewardDebtExceedsBound.code(), 34);
+        for (i, e) in StakingError::ALL.iter().enumerate() {
+            assert_eq!(e.code() as usize, i);
+            assert_eq!(StakingError::try_from(i as u32).unwrap(), *e);
+        }
+        assert!(StakingError::try_from(StakingError::ALL.len() as u32).is_err());
+
+        // chiefstaker-core reports math failures under the same codes
+        for e in [MathError::Overflow, MathError::Underflow, MathError::InvalidTau] {
+            assert_eq!(StakingError::from(e).code(), e.code());
+        }
+    }
+
+    #[test]
+    fn test_error_context_roundtrip() {
+        let ctx = ErrorContext::RemainingSeconds(3_600);
+        let data = ctx.encode(StakingError::CooldownNotElapsed);
+        assert_eq!(
+            ErrorContext::decode(&data),
+            Some((StakingError::CooldownNotElapsed, ctx))
+        );
+        assert_eq!(ErrorContext::decode(&data[..ErrorContext::LEN - 1]), None);
+        let mut bad = data;
+        bad[12] = 0xff;
+        assert_eq!(ErrorContext::decode(&bad), None);
+    }
+}
@@ -0,0 +1,1837 @@
+//! Program instructions
+//!
+//! Borsh-encoded behind an 8-byte Anchor-style sighash discriminator
+//! (`sha256("global:<snake_case_name>")[..8]`, see [`StakingInstruction::pack`]).
+//! The legacy layout, where the first byte is the Borsh variant index, is
+//! still accepted by [`StakingInstruction::unpack`], so variants are only
+//! ever appended. Stake, Unstake and ClaimRewards also have a compact
+//! layout for transactions batching many of them: a one-byte tag
+//! (`COMPACT_*`) followed by the amount in as few little-endian bytes as it
+//! needs ([`StakingInstruction::pack_compact`]). Account orders are mirrored
+//! as index constants in [`crate::accounts`].
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+/// Program instructions
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub enum StakingInstruction {
+    /// Initialize a new staking pool for a Token 2022 mint
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account (PDA: ["pool", mint])
+    /// 1. `[]` Token mint (Token 2022)
+    /// 2. `[writable]` Token vault (PDA: ["token_vault", pool])
+    /// 3. `[writable, signer]` Authority/payer
+    /// 4. `[]` System program
+    /// 5. `[]` Token 2022 program
+    /// 6. `[]` Rent sysvar
+    /// 7. `[writable]` (optional) Receipt mint (PDA: ["receipt_mint", pool])
+    InitializePool {
+        /// Time constant in seconds (e.g., 2592000 for 30 days)
+        tau_seconds: u64,
+    },
+
+    /// Stake tokens into the pool
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable]` User stake account (PDA: ["stake", pool, owner])
+    /// 2. `[writable]` Token vault
+    /// 3. `[writable]` User token account
+    /// 4. `[]` Token mint
+    /// 5. `[writable, signer]` User/owner
+    /// 6. `[]` System program
+    /// 7. `[]` Token 2022 program
+    /// 8. `[writable]` Receipt mint (receipt pools only)
+    /// 9. `[writable]` User receipt token account (receipt pools only)
+    /// 10. `[writable]` Rent sponsor vault (pools with a rent sponsor only)
+    /// 11. `[writable]` Weight history (existing positions with history only)
+    /// 12. `[writable]` (optional) Pool metadata (new positions; member count)
+    ///
+    /// Bucketed pools take accounts 0-7, then 8. `[writable]` Age buckets
+    Stake {
+        /// Amount of tokens to stake
+        amount: u64,
+    },
+
+    /// Unstake tokens from the pool
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable]` User stake account
+    /// 2. `[writable]` Token vault
+    /// 3. `[writable]` User token account
+    /// 4. `[]` Token mint
+    /// 5. `[writable, signer]` User/owner
+    /// 6. `[]` Token 2022 program
+    /// 7. `[writable]` Receipt mint (receipt pools only)
+    /// 8. `[writable]` User receipt token account (receipt pools only, burned from)
+    /// 9. `[writable]` Unstake fee destination (pools with an unstake fee only)
+    /// 10. `[writable]` Weight history (positions with history only)
+    /// 11. `[writable]` Reward recipient (positions with a declared recipient only)
+    /// 12. `[]` Lock schedule (positions with lock tranches only)
+    /// 13. `[signer]` Guardian (positions with a guardian only)
+    ///
+    /// Bucketed pools take accounts 0-6, then 7. `[writable]` Age buckets,
+    ///    then `[]` the age bucket history pages holding the position's first
+    ///    bucket-count epoch starts up to the current epoch (one, or two
+    ///    across a page boundary; none for positions of the current epoch)
+    Unstake {
+        /// Amount of tokens to unstake
+        amount: u64,
+    },
+
+    /// Claim accumulated SOL rewards
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account (holds SOL rewards)
+    /// 1. `[writable]` User stake account
+    /// 2. `[writable, signer]` User/owner
+    /// 3. `[writable]` Reward recipient (positions with a declared recipient only)
+    /// 4. `[writable]` Gas rebate vault (pools with claim rebates only)
+    /// 5. `[writable]` Burn destination (pools with a claim burn only)
+    ///
+    /// Bucketed pools take accounts 0-2, then 3. `[]` Age buckets,
+    ///    then `[]` the age bucket history pages holding the position's first
+    ///    bucket-count epoch starts up to the current epoch (one, or two
+    ///    across a page boundary; none for positions of the current epoch)
+    ClaimRewards,
+
+    /// Deposit SOL rewards into the pool (permissionless)
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account (receives SOL)
+    /// 1. `[writable, signer]` Depositor
+    /// 2. `[]` System program
+    /// 3. `[]` Depositor allowlist (pools with an allowlist only)
+    /// 4. `[writable]` Matching vault (pools with reward matching only)
+    /// 5. `[writable]` Current distribution history page (pools with a distribution history
+    ///    only; created at the depositor's expense when missing)
+    /// 6. `[writable]` Depositor record (pools that track depositors only; created at the
+    ///    depositor's expense on first use)
+    /// 7. `[]` (optional) Instructions sysvar — rejects if a Stake into this pool precedes it;
+    ///    required on pools with stake_guard_required
+    ///
+    /// Bucketed pools take accounts 0-2, then 3. `[writable]` Age buckets
+    DepositRewards {
+        /// Amount of lamports to deposit
+        amount: u64,
+    },
+
+    /// Sync/rebase the pool to prevent overflow (permissionless crank). A
+    /// no-op until sum_stake_exp nears overflow or (now - base_time) / tau
+    /// passes the pool's rebase threshold.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    SyncPool,
+
+    /// Sync rewards sent directly to the pool (permissionless crank)
+    /// Use this when SOL is sent directly to the pool PDA (e.g., from pump.fun)
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable]` Matching vault (pools with reward matching only)
+    /// 2. `[writable]` Current distribution history page (pools with a distribution history
+    ///    only; distributions are deferred while it doesn't exist)
+    /// 3. `[]` (optional) Instructions sysvar, required on pools with stake_guard_required —
+    ///    rejects if a Stake into this pool precedes it; system transfers into the pool earlier in the transaction are attributed in the
+    ///    PoolSynced event and distributed even below min_distribution_stake
+    ///
+    /// Bucketed pools take account 0, then 1. `[writable]` Age buckets
+    SyncRewards,
+
+    /// Update pool settings (authority only)
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[signer]` Authority (`[writable]` on pools with an authority log)
+    /// 2. `[]` System program (pools with an authority log only)
+    /// 3. `[writable]` Authority log (pools with an authority log only)
+    UpdatePoolSettings {
+        min_stake_amount: Option<u64>,
+        lock_duration_seconds: Option<u64>,
+        unstake_cooldown_seconds: Option<u64>,
+    },
+
+    /// Transfer pool authority to a new address
+    /// Setting to Pubkey::default() renounces authority (irreversible). A
+    /// renounce is refused while lock_duration or unstake_cooldown exceeds 7
+    /// days; RenounceAuthorityUnsafe overrides that.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[signer]` Current authority (`[writable]` on pools with an authority log)
+    /// 2. `[]` System program (pools with an authority log only)
+    /// 3. `[writable]` Authority log (pools with an authority log only)
+    TransferAuthority {
+        new_authority: Pubkey,
+    },
+
+    /// Request unstake - starts cooldown period (tokens remain staked)
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable]` User stake account
+    /// 2. `[signer]` User/owner
+    /// 3. `[]` Lock schedule (positions with lock tranches only)
+    RequestUnstake {
+        amount: u64,
+    },
+
+    /// Complete unstake after cooldown elapsed
+    ///
+    /// Accounts (same as Unstake):
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable]` User stake account
+    /// 2. `[writable]` Token vault
+    /// 3. `[writable]` User token account
+    /// 4. `[]` Token mint
+    /// 5. `[writable, signer]` User/owner
+    /// 6. `[]` Token 2022 program
+    /// 7. `[writable]` Receipt mint (receipt pools only)
+    /// 8. `[writable]` User receipt token account (receipt pools only, burned from)
+    /// 9. `[writable]` Unstake fee destination (pools with an unstake fee only)
+    /// 10. `[writable]` Weight history (positions with history only)
+    /// 11. `[writable]` Reward recipient (positions with a declared recipient only)
+    /// 12. `[signer]` Guardian (positions with a guardian only)
+    CompleteUnstake,
+
+    /// Cancel a pending unstake request
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    /// 1. `[writable]` User stake account
+    /// 2. `[signer]` User/owner
+    CancelUnstakeRequest,
+
+    /// Close a zero-balance user stake account to reclaim rent
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    /// 1. `[writable]` User stake account
+    /// 2. `[writable, signer]` User/owner
+    /// 3. `[writable]` Rent sponsor vault (sponsored accounts of pools with a
+    ///    rent sponsor only)
+    /// 4. `[writable]` (optional) Pool metadata (member count)
+    CloseStakeAccount,
+
+    /// DEPRECATED: Slot 13 reserved for ABI compatibility (was FixTotalRewardDebt,
+    /// superseded by RecountRewardDebt). Always returns InvalidInstruction.
+    DeprecatedFixTotalRewardDebt {
+        _unused: u128,
+    },
+
+    /// Set (create or update) pool metadata for explorer display (permissionless)
+    ///
+    /// Derives name from Token 2022 mint metadata extension, tags are fixed.
+    /// Creates the metadata PDA if it doesn't exist.
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    /// 1. `[writable]` Metadata PDA (["metadata", pool])
+    /// 2. `[]` Token mint
+    /// 3. `[writable, signer]` Payer
+    /// 4. `[]` System program
+    SetPoolMetadata,
+
+    /// Take fee ownership: set pool as sole fee recipient and revoke authority (permissionless)
+    ///
+    /// Prerequisite: fee sharing authority must already be transferred to the pool PDA.
+    /// This instruction sets the pool PDA as the sole shareholder (100% / 10000 bps)
+    /// and then revokes the fee sharing authority, making it irreversible.
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account (PDA: ["pool", mint] for pool_id 0,
+    ///    ["pool", mint, pool_id (u64 LE)] otherwise)
+    /// 1. `[]` Token mint
+    /// 2. `[]` pfee program
+    /// 3. `[]` pfee event authority
+    /// 4. `[]` pump global
+    /// 5. `[writable]` sharing config
+    /// 6. `[]` bonding curve
+    /// 7. `[writable]` pump creator vault
+    /// 8. `[]` system program
+    /// 9. `[]` pump program
+    /// 10. `[]` pump event authority
+    /// 11. `[]` pump AMM program
+    /// 12. `[]` AMM event authority
+    /// 13. `[]` wSOL mint
+    /// 14. `[]` token program
+    /// 15. `[]` associated token program
+    /// 16. `[writable]` coin creator vault authority
+    /// 17. `[writable]` coin creator vault ATA
+    TakeFeeOwnership,
+
+    /// Stake tokens on behalf of another user (beneficiary)
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable]` Beneficiary stake account (PDA: ["stake", pool, beneficiary])
+    /// 2. `[writable]` Token vault
+    /// 3. `[writable]` Staker's token account
+    /// 4. `[]` Token mint
+    /// 5. `[writable, signer]` Staker (payer + token source)
+    /// 6. `[writable]` Beneficiary (receives position + auto-claimed rewards)
+    /// 7. `[]` System program
+    /// 8. `[]` Token 2022 program
+    /// 9. `[writable]` Receipt mint (receipt pools only)
+    /// 10. `[writable]` Beneficiary's receipt token account (receipt pools only)
+    StakeOnBehalf {
+        amount: u64,
+    },
+
+    /// Pause or resume reward distribution (authority only)
+    ///
+    /// While paused, deposited/synced SOL stays undistributed; claims of
+    /// already-accrued rewards still work.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable, signer]` Authority
+    /// 2. `[]` (optional) System program (legacy pool reallocation; required
+    ///    on pools with an authority log)
+    /// 3. `[writable]` Authority log (pools with an authority log only)
+    SetRewardsPaused {
+        paused: bool,
+    },
+
+    /// Fold accumulated rounding dust back into the reward accumulator
+    /// (permissionless crank)
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    SweepDust,
+
+    /// Return a Borsh-encoded `UserPosition` summary via return data
+    /// (read-only, intended for simulation)
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    /// 1. `[]` User stake account
+    GetUserPosition,
+
+    /// Return a one-byte `SYNC_FLAG_*` bitflag via return data telling
+    /// keepers whether SyncPool/SyncRewards are due (read-only, for simulation)
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    CheckSyncNeeded {
+        /// Set SYNC_FLAG_UNDISTRIBUTED when undistributed lamports exceed this
+        min_undistributed_lamports: u64,
+    },
+
+    /// Initialize an additional staking pool for a mint, identified by
+    /// `pool_id` (pool_id 0 is equivalent to InitializePool)
+    ///
+    /// Accounts: same as InitializePool, with the pool account at
+    /// PDA ["pool", mint, pool_id (u64 LE)]
+    InitializePoolWithId {
+        /// Time constant in seconds (e.g., 604800 for 7 days)
+        tau_seconds: u64,
+        /// Pool index for the mint
+        pool_id: u64,
+    },
+
+    /// Create (or re-link) the caller's WeightHistory checkpoint log.
+    /// Afterwards Stake/StakeOnBehalf/Unstake/CompleteUnstake of the position
+    /// must pass the history account right after any receipt accounts.
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    /// 1. `[writable]` User stake account
+    /// 2. `[writable]` Weight history (PDA: ["weight_history", pool, owner])
+    /// 3. `[writable, signer]` User/owner
+    /// 4. `[]` System program
+    InitWeightHistory {
+        /// Number of retained checkpoints (1..=256)
+        capacity: u16,
+    },
+
+    /// Return the average weighted stake (u128 LE) of a position over
+    /// [from, to] via return data (read-only)
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    /// 1. `[]` Weight history
+    GetAverageWeightedStake {
+        from: i64,
+        to: i64,
+    },
+
+    /// Declare the account that receives the position's reward lamports
+    /// (for PDA owners invoking via CPI). Pubkey::default() pays the owner.
+    /// Afterwards ClaimRewards/Unstake/CompleteUnstake must pass the
+    /// recipient right after any weight history account.
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    /// 1. `[writable]` User stake account
+    /// 2. `[writable, signer]` User/owner
+    /// 3. `[]` (optional) System program (legacy account reallocation)
+    SetRewardRecipient {
+        recipient: Pubkey,
+    },
+
+    /// Claim accumulated SOL rewards as wrapped SOL into a native token
+    /// account (the pool credits the lamports and syncs the account)
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account (holds SOL rewards)
+    /// 1. `[writable]` User stake account
+    /// 2. `[writable, signer]` User/owner
+    /// 3. `[writable]` Native (wSOL) token account
+    /// 4. `[]` Token program (SPL Token or Token 2022)
+    /// 5. `[writable]` Gas rebate vault (pools with claim rebates only)
+    /// 6. `[writable]` Burn destination (pools with a claim burn only)
+    /// 7. `[]` (optional) System program (legacy account reallocation)
+    ClaimRewardsWrapped,
+
+    /// Configure refunds of SOL left undistributed in a pool without
+    /// stakers (authority only). Zero `refund_after_seconds` disables.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable, signer]` Authority
+    /// 2. `[]` (optional) System program (legacy pool reallocation; required
+    ///    on pools with an authority log)
+    /// 3. `[writable]` Authority log (pools with an authority log only)
+    SetRefundPolicy {
+        refund_address: Pubkey,
+        refund_after_seconds: u64,
+    },
+
+    /// Return SOL idle for at least `refund_after_seconds` in a pool without
+    /// stakers to its refund address (permissionless crank; the first call
+    /// starts the idle timer if it isn't running)
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable]` Refund address
+    RefundUndistributed,
+
+    /// Set the minimum total_staked (raw token units, at most the mint
+    /// supply) below which deposits and syncs stay undistributed
+    /// (authority only)
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable, signer]` Authority
+    /// 2. `[]` Token mint
+    /// 3. `[]` (optional) System program (legacy pool reallocation; required
+    ///    on pools with an authority log)
+    /// 4. `[writable]` Authority log (pools with an authority log only)
+    SetDistributionThreshold {
+        min_total_staked: u64,
+    },
+
+    /// Set the unstake fee (authority only): `fee_bps` of unstaked tokens,
+    /// at most 500, go to the fee destination. Afterwards Unstake and
+    /// CompleteUnstake must pass the destination right after any receipt
+    /// accounts.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable, signer]` Authority
+    /// 2. `[]` Fee destination token account (pool mint)
+    /// 3. `[]` (optional) System program (legacy pool reallocation; required
+    ///    on pools with an authority log)
+    /// 4. `[writable]` Authority log (pools with an authority log only)
+    SetUnstakeFee {
+        fee_bps: u16,
+    },
+
+    /// Return a Borsh-encoded `UserStats` summary (lifetime deposits,
+    /// withdrawals, claimed rewards and realized reward rate) via return
+    /// data (read-only, intended for simulation)
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    /// 1. `[]` User stake account
+    GetUserStats,
+
+    /// Stake tokens on behalf of a beneficiary and lock them until
+    /// `unlock_time` as a new tranche of the beneficiary's lock schedule.
+    /// Locked tokens keep earning rewards, but Unstake and RequestUnstake
+    /// only release the stake not held by unexpired tranches. Unlocked
+    /// tranches are pruned when a new one is added.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable]` Beneficiary stake account (PDA: ["stake", pool, beneficiary])
+    /// 2. `[writable]` Token vault
+    /// 3. `[writable]` Staker's token account
+    /// 4. `[]` Token mint
+    /// 5. `[writable, signer]` Staker — pays rent, provides tokens
+    /// 6. `[]` Beneficiary
+    /// 7. `[]` System program
+    /// 8. `[]` Token 2022 program
+    /// 9. `[writable]` Lock schedule (PDA: ["lock_schedule", pool, beneficiary])
+    /// 10. `[writable]` Receipt mint (receipt pools only)
+    /// 11. `[writable]` Beneficiary's receipt token account (receipt pools only)
+    StakeLocked {
+        amount: u64,
+        unlock_time: i64,
+    },
+
+    /// Create the pool's append-only AuthorityLog (permissionless, payer
+    /// funds rent). Afterwards every authority instruction must pass the
+    /// system program and the log, and appends each changed setting with
+    /// its old and new value; the authority pays for the log's growth.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable]` Authority log (PDA: ["authority_log", pool])
+    /// 2. `[writable, signer]` Payer
+    /// 3. `[]` System program
+    InitAuthorityLog,
+
+    /// Return a Borsh-encoded `StrandedPreview` via return data: synced
+    /// lamports, the upper bound still owed to stakers and residual
+    /// claimants, and the stranded remainder owed to nobody (read-only,
+    /// intended for simulation)
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    PreviewRecoverStranded,
+
+    /// Stage a SOL deposit as timed tranches (permissionless). Each
+    /// `(offset_seconds, bps)` entry releases `bps` of `amount` once
+    /// `offset_seconds` have passed; the shares must sum to 10000. The SOL
+    /// is held by the pool's PendingDistributions account (created or grown
+    /// as needed, depositor funds rent) until ReleasePendingDistributions.
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    /// 1. `[writable]` Pending distributions (PDA: ["pending_distributions", pool])
+    /// 2. `[writable, signer]` Depositor
+    /// 3. `[]` System program
+    /// 4. `[]` Depositor allowlist (pools with an allowlist only)
+    /// 5. `[writable]` Depositor record (pools that track depositors only)
+    DepositRewardsSplit {
+        amount: u64,
+        schedule: Vec<(u64, u16)>,
+    },
+
+    /// Move every due tranche of the pool's PendingDistributions into the
+    /// pool and distribute it as SyncRewards would (permissionless crank)
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable]` Pending distributions
+    /// 2. Then SyncRewards' accounts after the pool: matching vault, distribution
+    ///    history page (where the pool has them), instructions sysvar (optional
+    ///    unless the pool sets stake_guard_required)
+    ReleasePendingDistributions,
+
+    /// Return a Borsh-encoded `PoolHealth` via return data: a bitmask of
+    /// violated invariants (vault below total_staked, synced lamports above
+    /// the pool's balance, reward debt above what total_staked accumulated)
+    /// plus the balances checked (read-only, intended for simulation)
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    /// 1. `[]` Token vault
+    AssertPoolHealthy,
+
+    /// Set the unstake request grace window (authority only): after its
+    /// cooldown elapses a request can be completed for `expiry_seconds`, then
+    /// it lapses and the stake resumes normal status. 0 = never expires.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable, signer]` Authority
+    /// 2. `[]` (optional) System program (legacy pool reallocation; required
+    ///    on pools with an authority log)
+    /// 3. `[writable]` Authority log (pools with an authority log only)
+    SetUnstakeRequestExpiry {
+        expiry_seconds: u64,
+    },
+
+    /// Split the position's reward payouts across up to 4 `(recipient, bps)`
+    /// destinations; the shares must sum to 10000. Claims and unstakes then
+    /// pass the split account and its destinations in order. An empty list
+    /// removes the split and refunds its rent.
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    /// 1. `[writable]` User stake account
+    /// 2. `[writable, signer]` User/owner (pays rent)
+    /// 3. `[writable]` Payout split (PDA: ["payout_split", pool, owner])
+    /// 4. `[]` System program
+    SetPayoutSplit {
+        destinations: Vec<(Pubkey, u16)>,
+    },
+
+    /// Move the whole position to another pool of the same mint in one
+    /// step, with a fresh exp_start_factor there. Source lock checks apply
+    /// and its pending rewards are paid out as on Unstake. The target must
+    /// not issue receipts or have a shorter cooldown or lower unstake fee.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Source pool account
+    /// 1. `[writable]` Source user stake account
+    /// 2. `[writable]` Source token vault
+    /// 3. `[writable]` Target pool account
+    /// 4. `[writable]` Target user stake account (PDA: ["stake", target_pool, owner])
+    /// 5. `[writable]` Target token vault
+    /// 6. `[]` Token mint
+    /// 7. `[writable, signer]` User/owner (pays rent)
+    /// 8. `[]` System program
+    /// 9. `[]` Token 2022 program
+    /// 10. `[writable]` Source weight history (positions with history only)
+    /// 11. Payout split + destinations or reward recipient (as on Unstake)
+    /// 12. `[]` Source lock schedule (positions with lock tranches only)
+    /// 13. `[signer]` Guardian (positions with a guardian only)
+    /// 14. `[writable]` Target weight history (existing target positions with history only)
+    MigrateStake {
+        target_pool: Pubkey,
+    },
+
+    /// Create the program-wide ProgramConfig (once, current upgrade
+    /// authority only), recording the expected upgrade authority. The signer
+    /// becomes the config admin.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Program config (PDA: ["program_config"])
+    /// 1. `[writable, signer]` Current upgrade authority (pays rent)
+    /// 2. `[]` ProgramData account of this program
+    /// 3. `[]` System program
+    InitProgramConfig {
+        upgrade_authority: Pubkey,
+    },
+
+    /// Fail with UpgradeAuthorityMismatch unless the program's upgrade
+    /// authority is the one recorded in the ProgramConfig (read-only).
+    ///
+    /// Accounts:
+    /// 0. `[]` Program config (PDA: ["program_config"])
+    /// 1. `[]` ProgramData account of this program
+    AssertUpgradeAuthority,
+
+    /// Change ProgramConfig settings (config admin only): admin, expected
+    /// upgrade authority, protocol fee (at most 1000 bps) and fee treasury.
+    /// `None` leaves a setting unchanged.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Program config (PDA: ["program_config"])
+    /// 1. `[signer]` Config admin
+    UpdateProgramConfig {
+        admin: Option<Pubkey>,
+        upgrade_authority: Option<Pubkey>,
+        protocol_fee_bps: Option<u16>,
+        fee_treasury: Option<Pubkey>,
+    },
+
+    /// Send a pool's owed protocol fees to the fee treasury and refresh its
+    /// protocol fee snapshot from the ProgramConfig (permissionless).
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[]` Program config (PDA: ["program_config"])
+    /// 2. `[writable]` Fee treasury (must match the program config)
+    /// 3. `[writable, signer]` Payer (legacy pool reallocation)
+    /// 4. `[]` (optional) System program (legacy pool reallocation)
+    CollectProtocolFees,
+
+    /// Recount the pool's total_reward_debt from its stake accounts, one
+    /// page at a time, committing when every staked token is covered
+    /// (permissionless). Pages list stake accounts in ascending key order.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable]` Debt recount scratch (PDA: ["debt_recount", pool])
+    /// 2. `[writable, signer]` Payer (scratch rent, legacy pool reallocation)
+    /// 3. `[]` System program
+    /// 4. `[]` User stake accounts of the pool (up to 24)
+    RecountRewardDebt {
+        /// Stake accounts already counted (0 = start over)
+        cursor: u64,
+    },
+
+    /// Set how claims and unstakes round WAD-scaled rewards to whole
+    /// lamports (authority only): 0 = floor (toward the pool), 1 = half up,
+    /// 2 = half even (banker's). Round-ups are only paid when the pool's
+    /// rounding reserve, filled by round-downs, covers them.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable, signer]` Authority
+    /// 2. `[]` (optional) System program (legacy pool reallocation; required
+    ///    on pools with an authority log)
+    /// 3. `[writable]` Authority log (pools with an authority log only)
+    SetRoundingMode {
+        mode: u8,
+    },
+
+    /// Restrict DepositRewards and DepositRewardsSplit to up to 8 depositor
+    /// keys, e.g. a fee router (authority only). An empty list removes the
+    /// allowlist, refunds its rent to the authority and makes deposits
+    /// permissionless again. SOL sent directly to the pool and picked up by
+    /// SyncRewards is not affected.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable, signer]` Authority (pays rent)
+    /// 2. `[writable]` Depositor allowlist (PDA: ["depositor_allowlist", pool])
+    /// 3. `[]` System program
+    /// 4. `[writable]` Authority log (pools with an authority log only)
+    SetDepositorAllowlist {
+        depositors: Vec<Pubkey>,
+    },
+
+    /// Lock the position for `additional_seconds` more (counted from the
+    /// later of now and its current lock end, at most 365 days ahead) in
+    /// exchange for treating the stake as older by `additional_seconds *
+    /// lock_boost_bps / 10000`, so it approaches full weight sooner. Fails
+    /// with LockBoostDisabled on pools without a lock boost.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable]` User stake account
+    /// 2. `[writable, signer]` User/owner
+    /// 3. `[writable]` Weight history (positions with history only)
+    /// 4. `[]` (optional) System program (legacy account reallocation)
+    ExtendLock {
+        additional_seconds: u64,
+    },
+
+    /// Set the age bonus ExtendLock grants, in basis points of the added
+    /// lock time (authority only, at most 10000; 0 disables ExtendLock).
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable, signer]` Authority
+    /// 2. `[]` (optional) System program (legacy pool reallocation; required
+    ///    on pools with an authority log)
+    /// 3. `[writable]` Authority log (pools with an authority log only)
+    SetLockBoost {
+        boost_bps: u16,
+    },
+
+    /// Set the one-time maturity bonus, in basis points of the position's
+    /// lifetime claimed rewards (authority only, at most 10000). A non-zero
+    /// value creates the bonus budget PDA, which the authority funds with
+    /// plain SOL transfers. 0 disables the bonus, closes the budget and
+    /// refunds what is left in it to the authority.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable, signer]` Authority (pays rent, receives refunds)
+    /// 2. `[writable]` Bonus budget (PDA: ["bonus_budget", pool])
+    /// 3. `[]` System program
+    /// 4. `[writable]` Authority log (pools with an authority log only)
+    SetMaturityBonus {
+        bonus_bps: u16,
+    },
+
+    /// Collect the maturity bonus once the stake is 3 tau old:
+    /// `total_rewards_claimed * maturity_bonus_bps / 10000` lamports from
+    /// the bonus budget to the owner. Paid once per position; fails with
+    /// InsufficientBonusBudget (nothing is marked paid) if the budget is
+    /// short.
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    /// 1. `[writable]` User stake account
+    /// 2. `[writable, signer]` User/owner
+    /// 3. `[writable]` Bonus budget (PDA: ["bonus_budget", pool])
+    /// 4. `[]` (optional) System program (legacy account reallocation)
+    ClaimMaturityBonus,
+
+    /// Give the position a guardian that must co-sign Unstake,
+    /// CompleteUnstake and MigrateStake (claims stay single-signature).
+    /// Replacing an existing guardian needs its co-signature; setting one
+    /// cancels any pending ClearGuardian timelock.
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    /// 1. `[writable]` User stake account
+    /// 2. `[writable, signer]` User/owner
+    /// 3. `[signer]` Current guardian (positions with a guardian only)
+    /// 4. `[]` (optional) System program (legacy account reallocation)
+    SetGuardian {
+        guardian: Pubkey,
+    },
+
+    /// Remove the position's guardian. With the guardian's co-signature it
+    /// is removed at once; otherwise the first call starts a 7-day timelock
+    /// and a call after it expires removes the guardian.
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    /// 1. `[writable]` User stake account
+    /// 2. `[writable, signer]` User/owner
+    /// 3. `[]` Guardian (signer to remove immediately)
+    /// 4. `[]` (optional) System program (legacy account reallocation)
+    ClearGuardian,
+
+    /// Set the bootstrap window (authority only, at most 30 days): positions
+    /// opened within `period_seconds` of pool creation are weighted as if
+    /// staked at creation. 0 disables it.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable, signer]` Authority
+    /// 2. `[]` (optional) System program (legacy pool reallocation; required
+    ///    on pools with an authority log)
+    /// 3. `[writable]` Authority log (pools with an authority log only)
+    SetBootstrapPeriod {
+        period_seconds: u64,
+    },
+
+    /// Match reward distributions from an authority-funded vault (authority
+    /// only, at most 10000). Each DepositRewards or SyncRewards that
+    /// distributes also distributes `match_bps` of that amount from the
+    /// MatchingVault PDA, until the vault runs dry. A non-zero value creates
+    /// the vault, which the authority funds with plain SOL transfers. 0
+    /// stops matching, closes the vault and refunds what is left to the
+    /// authority.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable, signer]` Authority (pays rent, receives refunds)
+    /// 2. `[writable]` Matching vault (PDA: ["matching_vault", pool])
+    /// 3. `[]` System program
+    /// 4. `[writable]` Authority log (pools with an authority log only)
+    SetRewardMatching {
+        match_bps: u16,
+    },
+
+    /// Initialize a pool like InitializePoolWithId, but succeed without
+    /// changes if the pool already exists for the same mint, pool_id and
+    /// tau (and the same receipt mint, when one is passed). Lets deployment
+    /// scripts and factory programs retry safely. A pool that exists with
+    /// other parameters fails with AlreadyInitialized.
+    ///
+    /// Accounts: same as InitializePool, with the pool account at the
+    /// InitializePoolWithId address
+    InitializePoolIfNeeded {
+        /// Time constant in seconds
+        tau_seconds: u64,
+        /// Pool index for the mint (0 for the original pool)
+        pool_id: u64,
+    },
+
+    /// Initialize pool 0 of a mint with `authority` as pool authority while
+    /// the signer only pays rent. Lets launchpads fund pools from a service
+    /// wallet and hand administration to the project (e.g. a multisig or a
+    /// PDA) from the start. `authority` must not be the default pubkey.
+    ///
+    /// Accounts: same as InitializePool, with account 3 as payer only
+    InitializePoolWithAuthority {
+        /// Time constant in seconds
+        tau_seconds: u64,
+        /// Pool authority
+        authority: Pubkey,
+    },
+
+    /// Unstake the whole position, reading the staked amount at execution
+    /// time instead of taking a client-supplied amount that may be stale.
+    ///
+    /// Accounts: same as Unstake
+    UnstakeAll,
+
+    /// Request unstake of the whole position, reading the staked amount at
+    /// execution time instead of taking a client-supplied amount.
+    ///
+    /// Accounts: same as RequestUnstake
+    RequestUnstakeAll,
+
+    /// Attach a concurrent merkle tree (spl-account-compression) to the
+    /// pool so positions can be compressed. The tree account is allocated
+    /// beforehand, owned by the compression program; the pool PDA becomes
+    /// its authority. Set once (authority only).
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable, signer]` Authority
+    /// 2. `[writable]` Merkle tree
+    /// 3. `[]` spl-account-compression program
+    /// 4. `[]` spl-noop program
+    /// 5. `[]` System program
+    /// 6. `[writable]` Authority log (pools with an authority log only)
+    InitCompressionTree {
+        /// Tree depth (capacity 2^max_depth positions)
+        max_depth: u32,
+        /// Concurrent changes per slot the tree tolerates
+        max_buffer_size: u32,
+    },
+
+    /// Move the position into the pool's compression tree and close its
+    /// stake account, refunding the rent. The tokens stay staked and keep
+    /// earning; the position is decompressed to claim, add or unstake. The
+    /// full position is logged in a StakeCompressed event.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable]` User stake account (closed)
+    /// 2. `[writable, signer]` User/owner
+    /// 3. `[writable]` Merkle tree
+    /// 4. `[]` spl-account-compression program
+    /// 5. `[]` spl-noop program
+    /// 6. `[]` System program
+    StakeCompressed,
+
+    /// Restore a compressed position to its stake account (owner pays rent)
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable]` User stake account (must not exist)
+    /// 2. `[writable, signer]` User/owner
+    /// 3. `[writable]` Merkle tree
+    /// 4. `[]` spl-account-compression program
+    /// 5. `[]` spl-noop program
+    /// 6. `[]` System program
+    /// 7. `[]` Proof nodes, leaf to root (minus the tree's canopy)
+    DecompressStake {
+        /// Tree root the proof was built against
+        root: [u8; 32],
+        /// Leaf index from the StakeCompressed event
+        leaf_index: u32,
+        /// borsh(UserStake) from the StakeCompressed event
+        position: Vec<u8>,
+    },
+
+    /// Enable or disable rent sponsorship: new stake accounts get their rent
+    /// from an authority-funded RentSponsorVault, and return it on close.
+    /// Disabling closes the vault and refunds the authority (authority only).
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable, signer]` Authority
+    /// 2. `[writable]` Rent sponsor vault (PDA: ["rent_sponsor", pool])
+    /// 3. `[]` System program
+    /// 4. `[writable]` Authority log (pools with an authority log only)
+    SetRentSponsor {
+        enabled: bool,
+    },
+
+    /// Set the minimum interval between reward distributions (authority
+    /// only, at most 1 day): DepositRewards and SyncRewards within
+    /// `interval_seconds` of the last distribution leave the SOL pending.
+    /// 0 disables it.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable, signer]` Authority
+    /// 2. `[]` (optional) System program (legacy pool reallocation; required
+    ///    on pools with an authority log)
+    /// 3. `[writable]` Authority log (pools with an authority log only)
+    SetMinSyncInterval {
+        interval_seconds: u64,
+    },
+
+    /// Withdraw lamports above the rent-exempt minimum from the caller's
+    /// stake account (e.g. SOL sent to the PDA), without closing it
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    /// 1. `[writable]` User stake account
+    /// 2. `[writable, signer]` User/owner
+    SkimStakeAccountLamports,
+
+    /// Set the weighted stake thresholds (raw token units, 1-8, increasing)
+    /// behind GetWeightTier; an empty list removes them (authority only)
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    /// 1. `[writable, signer]` Authority
+    /// 2. `[writable]` Weight tiers (PDA: ["weight_tiers", pool])
+    /// 3. `[]` System program
+    /// 4. `[writable]` Authority log (pools with an authority log only)
+    SetWeightTiers {
+        thresholds: Vec<u64>,
+    },
+
+    /// Return a Borsh-encoded `WeightTier` (tier index, tier count, weighted
+    /// stake and next threshold) for a position via return data (read-only,
+    /// intended for simulation by partner programs)
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    /// 1. `[]` User stake account
+    /// 2. `[]` Weight tiers (PDA: ["weight_tiers", pool])
+    GetWeightTier,
+
+    /// Set how long each distribution takes to vest to stakers (authority
+    /// only, at most 30 days): claims see distributions vest linearly over
+    /// `vesting_seconds`. 0 makes new distributions claimable at once.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable, signer]` Authority
+    /// 2. `[]` (optional) System program (legacy pool reallocation; required
+    ///    on pools with an authority log)
+    /// 3. `[writable]` Authority log (pools with an authority log only)
+    SetClaimVesting {
+        vesting_seconds: u64,
+    },
+
+    /// Return the program's input limits (tau bounds, setting caps,
+    /// MAX_EXP_INPUT) and version as Borsh-encoded return data, so UIs
+    /// validate against the deployed program. Read-only.
+    ///
+    /// Accounts: none
+    GetProgramLimits,
+
+    /// Return the program version and feature bitmap as Borsh-encoded
+    /// return data, so clients can gate newer instructions on what a
+    /// cluster has deployed. Read-only.
+    ///
+    /// Accounts: none
+    GetVersion,
+
+    /// Register the Pyth or Switchboard price account for the pool mint,
+    /// used by GetPoolStats for USD figures (authority only). Passing the
+    /// system program as the price account removes the oracle.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable, signer]` Authority
+    /// 2. `[]` Price account, or the system program to remove the oracle
+    /// 3. `[]` (optional) System program (legacy pool reallocation; required
+    ///    on pools with an authority log)
+    /// 4. `[writable]` Authority log (pools with an authority log only)
+    SetPriceOracle,
+
+    /// Return pool statistics (total staked, lifetime reward rate, and TVL
+    /// and reward APY in USD on pools with a price oracle) as Borsh-encoded
+    /// return data. Read-only.
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    /// 1. `[]` Token mint
+    /// 2. `[]` Price account (pools with a price oracle only)
+    /// 3. `[]` (optional) SOL/USD price account, for the APY
+    GetPoolStats,
+
+    /// Pay claimers a fixed lamport rebate from a dedicated vault (authority
+    /// only, at most 100000 lamports). Every ClaimRewards or
+    /// ClaimRewardsWrapped paying at least `rebate_lamports` also pays the
+    /// owner `rebate_lamports` from the GasRebateVault PDA, while the vault
+    /// can cover it. A non-zero value creates the vault, which anyone funds
+    /// with FundGasRebates. 0 stops rebates, closes the vault and refunds
+    /// what is left to the authority.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable, signer]` Authority (pays rent, receives refunds)
+    /// 2. `[writable]` Gas rebate vault (PDA: ["gas_rebate", pool])
+    /// 3. `[]` System program
+    /// 4. `[writable]` Authority log (pools with an authority log only)
+    SetClaimRebate {
+        /// Lamports paid per claim (0 = disabled)
+        rebate_lamports: u64,
+    },
+
+    /// Fund a pool's claim gas rebates (permissionless). The lamports go to
+    /// the GasRebateVault, never to stakers.
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    /// 1. `[writable]` Gas rebate vault (PDA: ["gas_rebate", pool])
+    /// 2. `[writable, signer]` Funder
+    /// 3. `[]` System program
+    FundGasRebates {
+        /// Lamports to add to the vault
+        amount: u64,
+    },
+
+    /// Send a share of each reward claim to the incinerator instead of the
+    /// claimer (authority only, at most 1000 bps). The position is charged
+    /// the whole claim. 0 disables the burn.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable, signer]` Authority
+    /// 2. `[]` Burn destination: the incinerator (ignored when disabling)
+    /// 3. `[]` (optional) System program (legacy pool reallocation; required
+    ///    on pools with an authority log)
+    /// 4. `[writable]` Authority log (pools with an authority log only)
+    SetClaimBurn {
+        /// Share of each claim burned, in basis points (0 = disabled)
+        burn_bps: u16,
+    },
+
+    /// Mint a Token 2022 NFT for the position (metadata: pool, amount,
+    /// stake time) and freeze the position: owner instructions fail with
+    /// PositionTokenized until the NFT's holder redeems it. Positions with
+    /// a pending request, companion accounts, guardian, reward recipient,
+    /// sponsored rent or vesting rewards, and receipt pools, are refused.
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    /// 1. `[writable]` User stake account
+    /// 2. `[writable, signer]` User/owner (pays rent)
+    /// 3. `[writable]` Position NFT mint (PDA: ["position_nft", user_stake])
+    /// 4. `[writable]` Owner's associated token account for the NFT
+    /// 5. `[]` Token 2022 program
+    /// 6. `[]` Associated token account program
+    /// 7. `[]` System program
+    TokenizePosition,
+
+    /// Burn a position NFT and give the position to its holder, moving it
+    /// to the holder's stake account (which must not exist) unless the
+    /// holder is the owner. The old stake account and the NFT mint are
+    /// closed to the holder.
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    /// 1. `[writable]` Tokenized user stake account
+    /// 2. `[writable, signer]` NFT holder (pays rent for the new account)
+    /// 3. `[writable]` Holder's user stake account (account 1 again when
+    ///    the holder is the owner)
+    /// 4. `[writable]` Position NFT mint
+    /// 5. `[writable]` Holder's token account holding the NFT
+    /// 6. `[]` Token 2022 program
+    /// 7. `[]` System program
+    RedeemPosition,
+
+    /// Cover residual claimants before growing the accumulator (authority
+    /// only): while enabled, each distribution first reserves lamports for
+    /// total_residual_unpaid, which only residual claims can spend.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable, signer]` Authority
+    /// 2. `[]` (optional) System program (legacy pool reallocation; required
+    ///    on pools with an authority log)
+    /// 3. `[writable]` Authority log (pools with an authority log only)
+    SetResidualsFirst {
+        enabled: bool,
+    },
+
+    /// Claim accumulated SOL rewards with a session key: the owner signed
+    /// off-chain (chiefstaker::session::session_message) that the session key
+    /// may claim on this pool until `expires_at`, and an Ed25519 program
+    /// instruction earlier in the transaction verifies that signature.
+    /// Rewards go where ClaimRewards sends them, never to the session key.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account (holds SOL rewards)
+    /// 1. `[writable]` User stake account
+    /// 2. `[writable]` Owner (receives the rewards and any gas rebate)
+    /// 3. `[writable, signer]` Session key (pays legacy account reallocation)
+    /// 4. `[]` Instructions sysvar
+    /// 5. `[]` Payout split, then `[writable]` each of its destinations
+    ///    (positions with a payout split only); otherwise `[writable]`
+    ///    Reward recipient (positions with a declared recipient only)
+    /// 6. `[writable]` Gas rebate vault (pools with claim rebates only)
+    /// 7. `[writable]` Burn destination (pools with a claim burn only)
+    /// 8. `[]` (optional) System program (legacy account reallocation)
+    ClaimWithSession {
+        /// Session expiry (unix timestamp) the owner signed
+        expires_at: i64,
+    },
+
+    /// Set the smallest claim paid out (authority only): claims with less
+    /// pending are deferred and keep accruing. Residual claims are exempt.
+    /// 0 removes the minimum.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable, signer]` Authority
+    /// 2. `[]` (optional) System program (legacy pool reallocation; required
+    ///    on pools with an authority log)
+    /// 3. `[writable]` Authority log (pools with an authority log only)
+    SetMinClaimLamports {
+        /// Minimum claim in lamports (at most 0.1 SOL)
+        min_claim_lamports: u64,
+    },
+
+    /// Start checkpointing distributions (authority only): creates history
+    /// page 0; every later Deposit/SyncRewards distribution appends
+    /// (timestamp, acc_reward_per_weighted_share, lamports) to the current
+    /// page. Can't be turned off.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable, signer]` Authority (pays rent)
+    /// 2. `[writable]` Distribution history page 0
+    /// 3. `[]` System program
+    /// 4. `[writable]` Authority log (pools with an authority log only)
+    InitDistributionHistory,
+
+    /// Create the distribution history page receiving the next checkpoint
+    /// (permissionless). SyncRewards defers distributions while it is
+    /// missing. A no-op if it already exists.
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    /// 1. `[writable]` Current distribution history page
+    /// 2. `[writable, signer]` Payer
+    /// 3. `[]` System program
+    AddDistributionHistoryPage,
+
+    /// Close the pool metadata account and return its rent to the authority
+    /// (authority only). Works on corrupted metadata; SetPoolMetadata
+    /// recreates the account afterwards, with member_count reset to 0.
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    /// 1. `[writable, signer]` Authority (receives the rent)
+    /// 2. `[writable]` Metadata PDA (["metadata", pool])
+    /// 3. `[]` (optional) System program (required on pools with an
+    ///    authority log)
+    /// 4. `[writable]` Authority log (pools with an authority log only)
+    ClosePoolMetadata,
+
+    /// Close a zero-balance user stake account like CloseStakeAccount, but
+    /// first record its lifetime totals and any forgiven sub-lamport reward
+    /// residual in the owner's stake archive (created on first use, owner
+    /// pays rent). Later archived closes accumulate into the same archive.
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    /// 1. `[writable]` User stake account
+    /// 2. `[writable, signer]` User/owner (pays archive rent, receives
+    ///    stake rent)
+    /// 3. `[writable]` Stake archive (PDA: ["stake_archive", pool, owner])
+    /// 4. `[]` System program
+    /// 5. `[writable]` Rent sponsor vault (sponsored accounts of pools with a
+    ///    rent sponsor only)
+    /// 6. `[writable]` (optional) Pool metadata (member count)
+    CloseStakeAccountArchived,
+
+    /// Close the owner's stake archive, returning its rent to the owner
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Stake archive
+    /// 1. `[writable, signer]` Owner (receives rent)
+    CloseArchived,
+
+    /// Turn depositor tracking on or off (authority only): while enabled,
+    /// DepositRewards and DepositRewardsSplit add each deposit to the
+    /// depositor's DepositorRecord (PDA: ["depositor_record", pool,
+    /// depositor]), created at the depositor's expense on first use.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable, signer]` Authority
+    /// 2. `[]` (optional) System program (legacy pool reallocation; required
+    ///    on pools with an authority log)
+    /// 3. `[writable]` Authority log (pools with an authority log only)
+    SetDepositorTracking {
+        enabled: bool,
+    },
+
+    /// Set how far (now - base_time) / tau may grow, in basis points of
+    /// tau, before SyncPool rebases (authority only). At most 420000
+    /// (MAX_EXP_INPUT); 0 restores the default (REBASE_WARN_EXP_INPUT).
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable, signer]` Authority
+    /// 2. `[]` (optional) System program (legacy pool reallocation; required
+    ///    on pools with an authority log)
+    /// 3. `[writable]` Authority log (pools with an authority log only)
+    SetRebaseThreshold {
+        rebase_threshold_bps: u32,
+    },
+
+    /// Leave the pool in one instruction: complete the pending unstake
+    /// request (or unstake everything, as UnstakeAll does), claim any
+    /// residual rewards, and close the stake account. Fails as a whole if
+    /// the account can't be closed afterwards.
+    ///
+    /// Accounts:
+    /// 0-6. Same as Unstake
+    /// 7. `[]` System program
+    /// 8. Then the conditional accounts of Unstake, in order (receipt
+    ///    accounts, fee destination, weight history, payee accounts, lock
+    ///    schedule, guardian), those of ClaimRewards (gas rebate vault, burn
+    ///    destination), the rent sponsor vault as CloseStakeAccount takes it,
+    ///    and optional pool metadata
+    ExitPool,
+
+    /// Replace the pool's token vault with one sized for the account
+    /// extensions its mint requires now, moving the whole balance and
+    /// closing the old vault (authority only). The new vault is the PDA
+    /// ["token_vault", pool, vault_generation + 1 (u32 LE)].
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[]` Token mint
+    /// 2. `[writable]` Current token vault
+    /// 3. `[writable]` New token vault
+    /// 4. `[writable, signer]` Authority (pays the new vault's rent,
+    ///    receives the old vault's)
+    /// 5. `[]` System program
+    /// 6. `[]` Token 2022 program
+    /// 7. `[writable]` Authority log (pools with an authority log only)
+    MigrateVault,
+
+    /// Write the current weighted stake of each given position into the
+    /// proposal's voting snapshot (permissionless crank). The snapshot and
+    /// its pages are created as needed, funded by the payer. Stake accounts
+    /// must be in strictly increasing owner order across calls; a call
+    /// writes at most the current page's remaining room.
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    /// 1. `[writable]` Voting snapshot (PDA: ["voting_snapshot", pool,
+    ///    proposal_id (u64 LE)])
+    /// 2. `[writable]` Current snapshot page (PDA: ["voting_snapshot_page",
+    ///    snapshot, page (u32 LE)])
+    /// 3. `[writable, signer]` Payer
+    /// 4. `[]` System program
+    /// 5. `[]` User stake accounts, by increasing owner
+    SnapshotVotingWeights {
+        /// Caller-chosen proposal identifier
+        proposal_id: u64,
+    },
+
+    /// Set the principal the pool lock leaves free (authority only):
+    /// lock_duration_seconds only holds the part of a position above
+    /// `lock_exempt_amount` tokens. 0 locks whole positions.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable, signer]` Authority
+    /// 2. `[]` (optional) System program (legacy pool reallocation; required
+    ///    on pools with an authority log)
+    /// 3. `[writable]` Authority log (pools with an authority log only)
+    SetLockExemptAmount {
+        lock_exempt_amount: u64,
+    },
+
+    /// Lower last_synced_lamports to the pool's balance above rent if it
+    /// drifted past it (permissionless). Never raises it, so no lamports
+    /// become rewards; the change is reported in a SyncedLamportsReconciled
+    /// event.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    ReconcileSyncedLamports,
+
+    /// Freeze or unfreeze a position's rewards (authority only, while the
+    /// pool has one). A frozen position can't claim, stake or migrate;
+    /// unstaking returns principal and keeps rewards owed until unfrozen.
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    /// 1. `[writable]` User stake account
+    /// 2. `[writable, signer]` Authority
+    /// 3. `[]` (optional) System program (legacy stake reallocation; required
+    ///    on pools with an authority log)
+    /// 4. `[writable]` Authority log (pools with an authority log only)
+    SetStakeFrozen {
+        frozen: bool,
+    },
+
+    /// Initialize an additional pool for a mint (like InitializePoolWithId)
+    /// with its settings copied from `source_pool`, an existing pool of the
+    /// same mint: min stake, lock and lock-exempt amount, unstake cooldown
+    /// and request expiry, unstake fee and claim burn, and the PoolMetadata
+    /// name, tags and url. For moving stakers to a new tau without
+    /// re-entering every parameter. Signed by the source pool's authority,
+    /// which also becomes the new pool's authority.
+    ///
+    /// Accounts:
+    /// 0-6. Same as InitializePool, with the pool account at the
+    ///    InitializePoolWithId address and account 3 the source pool's
+    ///    authority
+    /// 7. `[]` Source pool
+    /// 8. `[]` (optional) Source pool metadata (PDA: ["metadata", source_pool])
+    /// 9. `[writable]` New pool metadata (PDA: ["metadata", pool]; with 8 only)
+    ClonePoolConfig {
+        source_pool: Pubkey,
+        /// Time constant in seconds for the new pool
+        tau_seconds: u64,
+        /// Pool index for the mint
+        pool_id: u64,
+    },
+
+    /// Require the instructions sysvar on DepositRewards and SyncRewards
+    /// (authority only). While required, distributions without it fail
+    /// with InstructionsSysvarRequired, so the sandwich guard against a
+    /// stake earlier in the same transaction can't be skipped.
+    ///
+    /// Accounts:
+    /// 0. `[writable]` Pool account
+    /// 1. `[writable, signer]` Authority
+    /// 2. `[]` (optional) System program (legacy pool reallocation; required
+    ///    on pools with an authority log)
+    /// 3. `[writable]` Authority log (pools with an authority log only)
+    SetStakeGuardRequired {
+        required: bool,
+    },
+
+    /// Initialize a pool weighted by age bucket instead of the exponential:
+    /// stake moves one bucket older every `bucket_seconds` and earns the
+    /// weight (bps) of its bucket, the last weight applying to all older
+    /// stake. The pool supports Stake, Unstake, UnstakeAll, ClaimRewards,
+    /// DepositRewards, SyncRewards, RollAgeBuckets and CollectProtocolFees;
+    /// other instructions fail with UnsupportedWeightEngine. The pool's
+    /// tau_seconds is set to `bucket_seconds * weights_bps.len()` and
+    /// bounded like tau.
+    ///
+    /// Accounts:
+    /// 0-6. Same as InitializePool, with the pool account at the
+    ///    InitializePoolWithId address
+    /// 7. `[writable]` Age buckets (PDA: ["age_buckets", pool])
+    /// 8. `[writable]` Age bucket history page 0
+    ///    (PDA: ["age_bucket_history", pool, 0u32 LE])
+    InitializeBucketedPool {
+        /// Pool index for the mint
+        pool_id: u64,
+        /// Length of one age bucket in seconds
+        bucket_seconds: u64,
+        /// Non-decreasing weight of each bucket in bps, youngest first
+        /// (1 to 16 buckets, the last in 1..=10000)
+        weights_bps: Vec<u16>,
+    },
+
+    /// Roll a bucketed pool's age buckets towards the current epoch
+    /// (permissionless crank), recording each epoch start in the history.
+    /// Rolls at most to the end of the passed history page; stakes and
+    /// distributions wait until the pool is rolled to the current epoch.
+    ///
+    /// Accounts:
+    /// 0. `[]` Pool account
+    /// 1. `[writable]` Age buckets
+    /// 2. `[writable]` Age bucket history page of the next epoch; created
+    ///    at the payer's expense when it doesn't exist yet
+    /// 3. `[writable, signer]` Payer
+    /// 4. `[]` System program
+    RollAgeBuckets,
+
+    /// Renounce pool authority even while lock_duration or unstake_cooldown
+    /// exceeds the 7-day bound TransferAuthority enforces on renounces: the
+    /// explicit opt-in to freezing those settings (irreversible)
+    ///
+    /// Accounts: same as TransferAuthority
+    RenounceAuthorityUnsafe,
+
+    /// Initialize a pool like InitializePoolWithId for a mint with no
+    /// supply yet, e.g. ahead of a launch: the explicit opt-in to skipping
+    /// the nonzero-supply check. Account 3 must sign and be the mint's
+    /// mint authority; it becomes the pool authority.
+    ///
+    /// Accounts: same as InitializePool, with the pool account at the
+    /// InitializePoolWithId address
+    InitializePoolBeforeMint {
+        /// Time constant in seconds (e.g., 604800 for 7 days)
+        tau_seconds: u64,
+        /// Pool index for the mint
+        pool_id: u64,
+    },
+}
+
+/// Anchor-style instruction names, indexed by Borsh variant index.
+/// Slot 13 keeps the name of the removed stranded-rewards recovery.
+pub const INSTRUCTION_NAMES: [&str; 99] = [
+    "initialize_pool",
+    "stake",
+    "unstake",
+    "claim_rewards",
+    "deposit_rewards",
+    "sync_pool",
+    "sync_rewards",
+    "update_pool_settings",
+    "transfer_authority",
+    "request_unstake",
+    "complete_unstake",
+    "cancel_unstake_request",
+    "close_stake_account",
+    "recover_stranded_rewards",
+    "set_pool_metadata",
+    "take_fee_ownership",
+    "stake_on_behalf",
+    "set_rewards_paused",
+    "sweep_dust",
+    "get_user_position",
+    "check_sync_needed",
+    "initialize_pool_with_id",
+    "init_weight_history",
+    "get_average_weighted_stake",
+    "set_reward_recipient",
+    "claim_rewards_wrapped",
+    "set_refund_policy",
+    "refund_undistributed",
+    "set_distribution_threshold",
+    "set_unstake_fee",
+    "get_user_stats",
+    "stake_locked",
+    "init_authority_log",
+    "preview_recover_stranded",
+    "deposit_rewards_split",
+    "release_pending_distributions",
+    "assert_pool_healthy",
+    "set_unstake_request_expiry",
+    "set_payout_split",
+    "migrate_stake",
+    "init_program_config",
+    "assert_upgrade_authority",
+    "update_program_config",
+    "collect_protocol_fees",
+    "recount_reward_debt",
+    "set_rounding_mode",
+    "set_depositor_allowlist",
+    "extend_lock",
+    "set_lock_boost",
+    "set_maturity_bonus",
+    "claim_maturity_bonus",
+    "set_guardian",
+    "clear_guardian",
+    "set_bootstrap_period",
+    "set_reward_matching",
+    "initialize_pool_if_needed",
+    "initialize_pool_with_authority",
+    "unstake_all",
+    "request_unstake_all",
+    "init_compression_tree",
+    "stake_compressed",
+    "decompress_stake",
+    "set_rent_sponsor",
+    "set_min_sync_interval",
+    "skim_stake_account_lamports",
+    "set_weight_tiers",
+    "get_weight_tier",
+    "set_claim_vesting",
+    "get_program_limits",
+    "get_version",
+    "set_price_oracle",
+    "get_pool_stats",
+    "set_claim_rebate",
+    "fund_gas_rebates",
+    "set_claim_burn",
+    "tokenize_position",
+    "redeem_position",
+    "set_residuals_first",
+    "claim_with_session",
+    "set_min_claim_lamports",
+    "init_distribution_history",
+    "add_distribution_history_page",
+    "close_pool_metadata",
+    "close_stake_account_archived",
+    "close_archived",
+    "set_depositor_tracking",
+    "set_rebase_threshold",
+    "exit_pool",
+    "migrate_vault",
+    "snapshot_voting_weights",
+    "set_lock_exempt_amount",
+    "reconcile_synced_lamports",
+    "set_stake_frozen",
+    "clone_pool_config",
+    "set_stake_guard_required",
+    "initialize_bucketed_pool",
+    "roll_age_buckets",
+    "renounce_authority_unsafe",
+    "initialize_pool_before_mint",
+];
+
+/// `sha256("global:<name>")[..8]` for each entry of [`INSTRUCTION_NAMES`]
+pub const INSTRUCTION_SIGHASHES: [[u8; 8]; 99] = [
+    [95, 180, 10, 172, 84, 174, 232, 40], // initialize_pool
+    [206, 176, 202, 18, 200, 209, 179, 108], // stake
+    [90, 95, 107, 42, 205, 124, 50, 225], // unstake
+    [4, 144, 132, 71, 116, 23, 151, 80], // claim_rewards
+    [52, 249, 112, 72, 206, 161, 196, 1], // deposit_rewards
+    [219, 251, 215, 251, 44, 37, 108, 102], // sync_pool
+    [18, 47, 240, 255, 79, 191, 165, 54], // sync_rewards
+    [222, 34, 66, 86, 117, 205, 101, 49], // update_pool_settings
+    [48, 169, 76, 72, 229, 180, 55, 161], // transfer_authority
+    [44, 154, 110, 253, 160, 202, 54, 34], // request_unstake
+    [79, 98, 40, 241, 100, 30, 25, 234], // complete_unstake
+    [146, 92, 252, 229, 122, 129, 37, 141], // cancel_unstake_request
+    [246, 236, 59, 167, 115, 135, 122, 12], // close_stake_account
+    [34, 234, 7, 70, 16, 95, 223, 188], // recover_stranded_rewards
+    [52, 196, 44, 60, 201, 162, 55, 8], // set_pool_metadata
+    [178, 27, 77, 157, 167, 230, 154, 46], // take_fee_ownership
+    [27, 199, 245, 201, 127, 32, 99, 182], // stake_on_behalf
+    [237, 177, 240, 194, 143, 95, 101, 221], // set_rewards_paused
+    [9, 49, 242, 88, 156, 84, 109, 15], // sweep_dust
+    [134, 165, 10, 74, 46, 176, 252, 181], // get_user_position
+    [235, 84, 23, 176, 171, 9, 172, 183], // check_sync_needed
+    [79, 138, 18, 4, 59, 56, 237, 250], // initialize_pool_with_id
+    [54, 254, 50, 119, 116, 200, 219, 219], // init_weight_history
+    [78, 140, 15, 9, 118, 98, 221, 7], // get_average_weighted_stake
+    [156, 177, 158, 16, 16, 167, 80, 227], // set_reward_recipient
+    [199, 240, 197, 137, 135, 133, 233, 102], // claim_rewards_wrapped
+    [53, 4, 81, 208, 106, 61, 14, 124], // set_refund_policy
+    [23, 99, 37, 247, 166, 103, 127, 50], // refund_undistributed
+    [21, 225, 16, 225, 254, 14, 84, 74], // set_distribution_threshold
+    [134, 98, 159, 1, 216, 25, 5, 172], // set_unstake_fee
+    [38, 55, 50, 132, 115, 127, 50, 32], // get_user_stats
+    [165, 31, 139, 41, 64, 105, 46, 13], // stake_locked
+    [154, 215, 34, 12, 144, 0, 56, 223], // init_authority_log
+    [255, 61, 69, 159, 184, 87, 126, 127], // preview_recover_stranded
+    [63, 166, 5, 89, 181, 243, 4, 15], // deposit_rewards_split
+    [199, 80, 206, 195, 156, 237, 212, 104], // release_pending_distributions
+    [226, 109, 20, 92, 77, 27, 160, 113], // assert_pool_healthy
+    [16, 198, 25, 231, 234, 226, 143, 168], // set_unstake_request_expiry
+    [29, 114, 116, 98, 50, 70, 185, 21], // set_payout_split
+    [178, 5, 26, 85, 56, 20, 153, 160], // migrate_stake
+    [185, 54, 237, 229, 219, 179, 109, 20], // init_program_config
+    [249, 217, 121, 182, 173, 166, 5, 245], // assert_upgrade_authority
+    [214, 3, 187, 98, 170, 106, 33, 45], // update_program_config
+    [22, 67, 23, 98, 150, 178, 70, 220], // collect_protocol_fees
+    [163, 94, 107, 117, 167, 190, 196, 81], // recount_reward_debt
+    [39, 213, 102, 69, 25, 187, 47, 106], // set_rounding_mode
+    [33, 105, 70, 32, 245, 15, 216, 57], // set_depositor_allowlist
+    [68, 151, 140, 144, 139, 122, 118, 170], // extend_lock
+    [160, 9, 113, 216, 111, 120, 180, 181], // set_lock_boost
+    [106, 205, 29, 204, 250, 62, 157, 173], // set_maturity_bonus
+    [48, 16, 51, 14, 115, 156, 2, 234], // claim_maturity_bonus
+    [147, 243, 50, 121, 154, 164, 50, 30], // set_guardian
+    [61, 110, 254, 187, 25, 162, 145, 159], // clear_guardian
+    [2, 158, 29, 97, 36, 101, 162, 126], // set_bootstrap_period
+    [85, 174, 131, 166, 222, 245, 166, 173], // set_reward_matching
+    [122, 118, 48, 192, 123, 60, 176, 64], // initialize_pool_if_needed
+    [196, 99, 162, 88, 254, 240, 176, 77], // initialize_pool_with_authority
+    [246, 100, 226, 167, 229, 44, 86, 204], // unstake_all
+    [99, 213, 62, 43, 202, 40, 213, 13], // request_unstake_all
+    [155, 98, 251, 90, 21, 187, 173, 115], // init_compression_tree
+    [183, 89, 1, 86, 125, 131, 209, 204], // stake_compressed
+    [131, 117, 4, 124, 9, 95, 18, 238], // decompress_stake
+    [116, 2, 98, 27, 72, 168, 162, 240], // set_rent_sponsor
+    [115, 192, 220, 180, 77, 159, 0, 18], // set_min_sync_interval
+    [163, 209, 43, 204, 83, 217, 60, 25], // skim_stake_account_lamports
+    [62, 222, 45, 238, 73, 126, 157, 157], // set_weight_tiers
+    [23, 37, 229, 63, 111, 88, 35, 224], // get_weight_tier
+    [110, 0, 33, 54, 109, 247, 82, 114], // set_claim_vesting
+    [234, 63, 18, 159, 246, 174, 66, 141], // get_program_limits
+    [168, 85, 244, 45, 81, 56, 130, 50], // get_version
+    [189, 75, 36, 144, 201, 68, 8, 248], // set_price_oracle
+    [119, 221, 164, 30, 10, 6, 255, 128], // get_pool_stats
+    [191, 25, 103, 38, 82, 102, 25, 167], // set_claim_rebate
+    [49, 230, 248, 209, 35, 120, 162, 24], // fund_gas_rebates
+    [47, 218, 196, 30, 98, 45, 178, 250], // set_claim_burn
+    [167, 82, 237, 13, 157, 135, 59, 138], // tokenize_position
+    [51, 138, 49, 229, 33, 216, 19, 97], // redeem_position
+    [62, 89, 4, 209, 117, 1, 206, 235], // set_residuals_first
+    [129, 210, 238, 76, 63, 134, 2, 167], // claim_with_session
+    [138, 191, 237, 88, 214, 159, 189, 68], // set_min_claim_lamports
+    [112, 172, 220, 155, 239, 154, 35, 64], // init_distribution_history
+    [23, 63, 208, 69, 102, 204, 72, 156], // add_distribution_history_page
+    [69, 169, 56, 94, 202, 242, 237, 72], // close_pool_metadata
+    [161, 16, 88, 158, 90, 137, 6, 225], // close_stake_account_archived
+    [82, 150, 119, 196, 104, 31, 1, 192], // close_archived
+    [53, 61, 106, 176, 64, 175, 68, 99], // set_depositor_tracking
+    [54, 215, 94, 230, 135, 73, 88, 138], // set_rebase_threshold
+    [6, 156, 87, 187, 195, 101, 222, 143], // exit_pool
+    [139, 151, 25, 211, 120, 164, 24, 215], // migrate_vault
+    [184, 179, 64, 201, 133, 145, 78, 118], // snapshot_voting_weights
+    [50, 89, 255, 10, 85, 250, 147, 151], // set_lock_exempt_amount
+    [75, 216, 91, 188, 235, 242, 106, 90], // reconcile_synced_lamports
+    [205, 136, 230, 159, 236, 211, 164, 112], // set_stake_frozen
+    [193, 242, 238, 22, 150, 147, 22, 88], // clone_pool_config
+    [34, 242, 237, 11, 4, 118, 121, 232], // set_stake_guard_required
+    [128, 196, 186, 197, 72, 174, 228, 98], // initialize_bucketed_pool
+    [31, 125, 102, 27, 159, 7, 162, 189], // roll_age_buckets
+    [102, 207, 210, 29, 205, 91, 23, 98], // renounce_authority_unsafe
+    [93, 25, 5, 162, 255, 231, 59, 62], // initialize_pool_before_mint
+];
+
+/// Compact layout tag of Stake. Compact tags sit above every variant index
+/// and are never the first byte of a sighash, so all three layouts decode
+/// unambiguously.
+pub const COMPACT_STAKE: u8 = 0xf0;
+/// Compact layout tag of Unstake
+pub const COMPACT_UNSTAKE: u8 = 0xf1;
+/// Compact layout tag of ClaimRewards
+pub const COMPACT_CLAIM_REWARDS: u8 = 0xf2;
+
+/// `value` as little-endian bytes without its trailing zero bytes
+fn minimal_le(value: u64) -> Vec<u8> {
+    let bytes = value.to_le_bytes();
+    let len = 8 - (value.leading_zeros() / 8) as usize;
+    bytes[..len].to_vec()
+}
+
+/// Inverse of `minimal_le`: up to 8 little-endian bytes
+fn read_minimal_le(bytes: &[u8]) -> Option<u64> {
+    if bytes.len() > 8 {
+        return None;
+    }
+    let mut buf = [0u8; 8];
+    buf[..bytes.len()].copy_from_slice(bytes);
+    Some(u64::from_le_bytes(buf))
+}
+
+impl StakingInstruction {
+    /// Borsh variant index of this instruction
+    pub fn variant_index(&self) -> u8 {
+        // Borsh writes the variant index as the first byte
+        borsh::to_vec(self).map(|data| data[0]).unwrap_or_default()
+    }
+
+    /// 8-byte Anchor-style discriminator of this instruction
+    pub fn sighash(&self) -> [u8; 8] {
+        INSTRUCTION_SIGHASHES[self.variant_index() as usize]
+    }
+
+    /// Encode as the sighash discriminator followed by the Borsh-encoded fields
+    pub fn pack(&self) -> Vec<u8> {
+        let data = borsh::to_vec(self).expect("instruction serialization cannot fail");
+        let mut packed = Vec::with_capacity(8 + data.len() - 1);
+        packed.extend_from_slice(&INSTRUCTION_SIGHASHES[data[0] as usize]);
+        packed.extend_from_slice(&data[1..]);
+        packed
+    }
+
+    /// Encode in the compact layout: a `COMPACT_*` tag, then the amount in
+    /// minimal little-endian bytes (none for ClaimRewards or a zero
+    /// amount). None for instructions without a compact layout.
+    pub fn pack_compact(&self) -> Option<Vec<u8>> {
+        let (tag, amount) = match self {
+            StakingInstruction::Stake { amount } => (COMPACT_STAKE, *amount),
+            StakingInstruction::Unstake { amount } => (COMPACT_UNSTAKE, *amount),
+            StakingInstruction::ClaimRewards => return Some(vec![COMPACT_CLAIM_REWARDS]),
+            _ => return None,
+        };
+        let mut packed = vec![tag];
+        packed.extend_from_slice(&minimal_le(amount));
+        Some(packed)
+    }
+
+    /// Decode any layout. A leading sighash wins only if the remaining
+    /// bytes decode exactly as that instruction's fields; then a compact
+    /// tag; otherwise the data is read as the legacy u8 variant index
+    /// layout.
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        if let Some(instruction) = Self::unpack_sighash(data) {
+            return Ok(instruction);
+        }
+        if let Some(instruction) = Self::unpack_compact(data) {
+            return Ok(instruction);
+        }
+        Self::try_from_slice(data).map_err(|_| ProgramError::InvalidInstructionData)
+    }
+
+    fn unpack_compact(data: &[u8]) -> Option<Self> {
+        let (tag, fields) = data.split_first()?;
+        match *tag {
+            COMPACT_STAKE => Some(StakingInstruction::Stake {
+                amount: read_minimal_le(fields)?,
+            }),
+            COMPACT_UNSTAKE => Some(StakingInstruction::Unstake {
+                amount: read_minimal_le(fields)?,
+            }),
+            COMPACT_CLAIM_REWARDS if fields.is_empty() => Some(StakingInstruction::ClaimRewards),
+            _ => None,
+        }
+    }
+
+    fn unpack_sighash(data: &[u8]) -> Option<Self> {
+        let (discriminator, fields) = data.split_first_chunk::<8>()?;
+        let index = INSTRUCTION_SIGHASHES
+            .iter()
+            .position(|sighash| sighash == discriminator)?;
+        let mut legacy = Vec::with_capacity(1 + fields.len());
+        legacy.push(index as u8);
+        legacy.extend_from_slice(fields);
+        Self::try_from_slice(&legacy).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::hash::hashv;
+
+    #[test]
+    fn test_sighashes_match_names() {
+        for (name, sighash) in INSTRUCTION_NAMES.iter().zip(INSTRUCTION_SIGHASHES.iter()) {
+            let hash = hashv(&[b"global:", name.as_bytes()]);
+            assert_eq!(&hash.to_bytes()[..8], sighash, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_pack_unpack_both_layouts() {
+        let instruction = StakingInstruction::StakeLocked {
+            amount: 1_000_000,
+            unlock_time: 1_700_000_000,
+        };
+        let packed = instruction.pack();
+        assert_eq!(packed[..8], INSTRUCTION_SIGHASHES[31]);
+        assert_eq!(instruction.sighash(), INSTRUCTION_SIGHASHES[31]);
+
+        let legacy = borsh::to_vec(&instruction).unwrap();
+        for data in [packed, legacy] {
+            match StakingInstruction::unpack(&data).unwrap() {
+                StakingInstruction::StakeLocked { amount, unlock_time } => {
+                    assert_eq!(amount, 1_000_000);
+                    assert_eq!(unlock_time, 1_700_000_000);
+                }
+                other => panic!("Wrong instruction type: {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_compact_layout() {
+        // Tags never collide with a variant index or a sighash's first byte
+        for tag in [COMPACT_STAKE, COMPACT_UNSTAKE, COMPACT_CLAIM_REWARDS] {
+            assert!(tag as usize >= INSTRUCTION_NAMES.len());
+            assert!(INSTRUCTION_SIGHASHES.iter().all(|sighash| sighash[0] != tag));
+        }
+
+        let stake = StakingInstruction::Stake { amount: 1_000_000_000 };
+        let packed = stake.pack_compact().unwrap();
+        assert_eq!(packed, vec![COMPACT_STAKE, 0x00, 0xca, 0x9a, 0x3b]);
+        assert!(matches!(
+            StakingInstruction::unpack(&packed).unwrap(),
+            StakingInstruction::Stake { amount: 1_000_000_000 }
+        ));
+
+        for amount in [0, 1, 255, 256, u64::MAX] {
+            let packed = StakingInstruction::Unstake { amount }.pack_compact().unwrap();
+            assert_eq!(packed.len(), 1 + minimal_le(amount).len());
+            match StakingInstruction::unpack(&packed).unwrap() {
+                StakingInstruction::Unstake { amount: decoded } => assert_eq!(decoded, amount),
+                other => panic!("Wrong instruction type: {:?}", other),
+            }
+        }
+        assert_eq!(minimal_le(0), Vec::<u8>::new());
+        assert_eq!(minimal_le(u64::MAX).len(), 8);
+
+        let claim = StakingInstruction::ClaimRewards.pack_compact().unwrap();
+        assert_eq!(claim, vec![COMPACT_CLAIM_REWARDS]);
+        assert!(matches!(
+            StakingInstruction::unpack(&claim).unwrap(),
+            StakingInstruction::ClaimRewards
+        ));
+
+        // Oversized amounts, ClaimRewards with fields and other instructions
+        assert!(StakingInstruction::unpack(&[COMPACT_STAKE; 10]).is_err());
+        assert!(StakingInstruction::unpack(&[COMPACT_CLAIM_REWARDS, 0]).is_err());
+        assert!(StakingInstruction::DepositRewards { amount: 1 }.pack_compact().is_none());
+    }
+
+    #[test]
+    fn test_unpack_legacy_colliding_first_byte() {
+        // claim_rewards' sighash starts with 4 (DepositRewards); a legacy
+        // DepositRewards must still decode as such
+        assert_eq!(INSTRUCTION_SIGHASHES[3][0], 4);
+        let legacy = borsh::to_vec(&StakingInstruction::DepositRewards { amount: 42 }).unwrap();
+        assert!(matches!(
+            StakingInstruction::unpack(&legacy).unwrap(),
+            StakingInstruction::DepositRewards { amount: 42 }
+        ));
+        // Trailing bytes that fit neither layout are rejected
+        let mut data = INSTRUCTION_SIGHASHES[3].to_vec();
+        data.extend_from_slice(&[0, 0]);
+        assert!(StakingInstruction::unpack(&data).is_err());
+    }
+
+    #[test]
+    fn test_unpack_legacy_transfer_authority() {
+        // Deployed clients send variant 8 and the new authority, nothing more
+        let new_authority = Pubkey::new_unique();
+        let mut data = vec![8];
+        data.extend_from_slice(new_authority.as_ref());
+        assert_eq!(data.len(), 33);
+        match StakingInstruction::unpack(&data).unwrap() {
+            StakingInstruction::TransferAuthority { new_authority: decoded } => {
+                assert_eq!(decoded, new_authority)
+            }
+            other => panic!("Wrong instruction type: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,13 @@
+//! ChiefStaker interface
+//!
+//! Instruction enum, account orders, PDA seeds and error codes of the
+//! ChiefStaker program, without its entrypoint or processing code, so other
+//! on-chain programs can CPI into it with minimal dependencies.
+
+pub mod accounts;
+pub mod error;
+pub mod instruction;
+pub mod pda;
+
+// Declare program ID - matches target/deploy/chiefstaker-keypair.json
+solana_program::declare_id!("3Ecf8gyRURyrBtGHS1XAVXyQik5PqgDch4VkxrH4ECcr");
@@ -0,0 +1,201 @@
+//! PDA seeds and address derivation for ChiefStaker accounts
+
+use solana_program::pubkey::Pubkey;
+
+/// Seed prefixes for PDAs
+pub const POOL_SEED: &[u8] = b"pool";
+pub const STAKE_SEED: &[u8] = b"stake";
+pub const TOKEN_VAULT_SEED: &[u8] = b"token_vault";
+pub const METADATA_SEED: &[u8] = b"metadata";
+pub const RECEIPT_MINT_SEED: &[u8] = b"receipt_mint";
+pub const WEIGHT_HISTORY_SEED: &[u8] = b"weight_history";
+pub const LOCK_SCHEDULE_SEED: &[u8] = b"lock_schedule";
+pub const AUTHORITY_LOG_SEED: &[u8] = b"authority_log";
+pub const PENDING_DISTRIBUTIONS_SEED: &[u8] = b"pending_distributions";
+pub const PAYOUT_SPLIT_SEED: &[u8] = b"payout_split";
+pub const PROGRAM_CONFIG_SEED: &[u8] = b"program_config";
+pub const DEBT_RECOUNT_SEED: &[u8] = b"debt_recount";
+pub const DEPOSITOR_ALLOWLIST_SEED: &[u8] = b"depositor_allowlist";
+pub const BONUS_BUDGET_SEED: &[u8] = b"bonus_budget";
+pub const MATCHING_VAULT_SEED: &[u8] = b"matching_vault";
+pub const RENT_SPONSOR_SEED: &[u8] = b"rent_sponsor";
+pub const WEIGHT_TIERS_SEED: &[u8] = b"weight_tiers";
+pub const GAS_REBATE_SEED: &[u8] = b"gas_rebate";
+pub const POSITION_NFT_SEED: &[u8] = b"position_nft";
+pub const DISTRIBUTION_HISTORY_SEED: &[u8] = b"distribution_history";
+pub const STAKE_ARCHIVE_SEED: &[u8] = b"stake_archive";
+pub const DEPOSITOR_RECORD_SEED: &[u8] = b"depositor_record";
+pub const VOTING_SNAPSHOT_SEED: &[u8] = b"voting_snapshot";
+pub const VOTING_SNAPSHOT_PAGE_SEED: &[u8] = b"voting_snapshot_page";
+pub const AGE_BUCKETS_SEED: &[u8] = b"age_buckets";
+pub const AGE_BUCKET_HISTORY_SEED: &[u8] = b"age_bucket_history";
+
+/// Pool `pool_id` of `mint`: ["pool", mint] for pool 0,
+/// ["pool", mint, pool_id (u64 LE)] otherwise
+pub fn find_pool_address(mint: &Pubkey, pool_id: u64) -> (Pubkey, u8) {
+    if pool_id == 0 {
+        return Pubkey::find_program_address(&[POOL_SEED, mint.as_ref()], &crate::ID);
+    }
+    Pubkey::find_program_address(
+        &[POOL_SEED, mint.as_ref(), &pool_id.to_le_bytes()],
+        &crate::ID,
+    )
+}
+
+/// User stake account: ["stake", pool, owner]
+pub fn find_stake_address(pool: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[STAKE_SEED, pool.as_ref(), owner.as_ref()], &crate::ID)
+}
+
+/// Token vault: ["token_vault", pool]
+pub fn find_token_vault_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TOKEN_VAULT_SEED, pool.as_ref()], &crate::ID)
+}
+
+/// Token vault after MigrateVault: ["token_vault", pool, generation (u32 LE)]
+/// (generation 0 is the original vault)
+pub fn find_migrated_token_vault_address(pool: &Pubkey, generation: u32) -> (Pubkey, u8) {
+    if generation == 0 {
+        return find_token_vault_address(pool);
+    }
+    Pubkey::find_program_address(
+        &[TOKEN_VAULT_SEED, pool.as_ref(), &generation.to_le_bytes()],
+        &crate::ID,
+    )
+}
+
+/// Pool metadata: ["metadata", pool]
+pub fn find_metadata_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[METADATA_SEED, pool.as_ref()], &crate::ID)
+}
+
+/// Receipt mint: ["receipt_mint", pool]
+pub fn find_receipt_mint_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[RECEIPT_MINT_SEED, pool.as_ref()], &crate::ID)
+}
+
+/// Weight history: ["weight_history", pool, owner]
+pub fn find_weight_history_address(pool: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[WEIGHT_HISTORY_SEED, pool.as_ref(), owner.as_ref()],
+        &crate::ID,
+    )
+}
+
+/// Lock schedule: ["lock_schedule", pool, owner]
+pub fn find_lock_schedule_address(pool: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[LOCK_SCHEDULE_SEED, pool.as_ref(), owner.as_ref()],
+        &crate::ID,
+    )
+}
+
+/// Authority log: ["authority_log", pool]
+pub fn find_authority_log_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[AUTHORITY_LOG_SEED, pool.as_ref()], &crate::ID)
+}
+
+/// Pending distributions: ["pending_distributions", pool]
+pub fn find_pending_distributions_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PENDING_DISTRIBUTIONS_SEED, pool.as_ref()], &crate::ID)
+}
+
+/// Payout split: ["payout_split", pool, owner]
+pub fn find_payout_split_address(pool: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[PAYOUT_SPLIT_SEED, pool.as_ref(), owner.as_ref()],
+        &crate::ID,
+    )
+}
+
+/// Program config: ["program_config"]
+pub fn find_program_config_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROGRAM_CONFIG_SEED], &crate::ID)
+}
+
+/// Reward debt recount scratch: ["debt_recount", pool]
+pub fn find_debt_recount_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[DEBT_RECOUNT_SEED, pool.as_ref()], &crate::ID)
+}
+
+/// Depositor allowlist: ["depositor_allowlist", pool]
+pub fn find_depositor_allowlist_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[DEPOSITOR_ALLOWLIST_SEED, pool.as_ref()], &crate::ID)
+}
+
+/// Maturity bonus budget: ["bonus_budget", pool]
+pub fn find_bonus_budget_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[BONUS_BUDGET_SEED, pool.as_ref()], &crate::ID)
+}
+
+/// Reward matching vault: ["matching_vault", pool]
+pub fn find_matching_vault_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[MATCHING_VAULT_SEED, pool.as_ref()], &crate::ID)
+}
+
+/// Rent sponsor vault: ["rent_sponsor", pool]
+pub fn find_rent_sponsor_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[RENT_SPONSOR_SEED, pool.as_ref()], &crate::ID)
+}
+
+/// Weight tier thresholds: ["weight_tiers", pool]
+pub fn find_weight_tiers_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[WEIGHT_TIERS_SEED, pool.as_ref()], &crate::ID)
+}
+
+/// Claim gas rebate vault: ["gas_rebate", pool]
+pub fn find_gas_rebate_vault_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[GAS_REBATE_SEED, pool.as_ref()], &crate::ID)
+}
+
+/// Position NFT mint of a tokenized stake: ["position_nft", user_stake]
+pub fn find_position_mint_address(user_stake: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[POSITION_NFT_SEED, user_stake.as_ref()], &crate::ID)
+}
+
+/// Distribution history page `page`: ["distribution_history", pool, page (u32 LE)]
+pub fn find_distribution_history_address(pool: &Pubkey, page: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[DISTRIBUTION_HISTORY_SEED, pool.as_ref(), &page.to_le_bytes()],
+        &crate::ID,
+    )
+}
+
+/// Archive of `owner`'s closed positions in `pool`: ["stake_archive", pool, owner]
+pub fn find_stake_archive_address(pool: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[STAKE_ARCHIVE_SEED, pool.as_ref(), owner.as_ref()], &crate::ID)
+}
+
+/// Reward contributions of `depositor` to `pool`: ["depositor_record", pool, depositor]
+pub fn find_depositor_record_address(pool: &Pubkey, depositor: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[DEPOSITOR_RECORD_SEED, pool.as_ref(), depositor.as_ref()], &crate::ID)
+}
+
+/// Voting snapshot of `proposal_id` in `pool`: ["voting_snapshot", pool, proposal_id (u64 LE)]
+pub fn find_voting_snapshot_address(pool: &Pubkey, proposal_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[VOTING_SNAPSHOT_SEED, pool.as_ref(), &proposal_id.to_le_bytes()],
+        &crate::ID,
+    )
+}
+
+/// Voting snapshot page `page`: ["voting_snapshot_page", snapshot, page (u32 LE)]
+pub fn find_voting_snapshot_page_address(snapshot: &Pubkey, page: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[VOTING_SNAPSHOT_PAGE_SEED, snapshot.as_ref(), &page.to_le_bytes()],
+        &crate::ID,
+    )
+}
+
+/// Age-bucket state of a bucketed pool: ["age_buckets", pool]
+pub fn find_age_buckets_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[AGE_BUCKETS_SEED, pool.as_ref()], &crate::ID)
+}
+
+/// Age-bucket history page `page`: ["age_bucket_history", pool, page (u32 LE)]
+pub fn find_age_bucket_history_address(pool: &Pubkey, page: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[AGE_BUCKET_HISTORY_SEED, pool.as_ref(), &page.to_le_bytes()],
+        &crate::ID,
+    )
+}
@@ -0,0 +1,157 @@
+//! Backends the setup helpers run against
+
+use solana_banks_client::{BanksClient, BanksClientError};
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_rpc_client_api::client_error::Error as ClientError;
+use solana_sdk::{
+    account::{from_account, Account},
+    clock::Clock,
+    hash::Hash,
+    instruction::Instruction,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    sysvar,
+    transaction::Transaction,
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum TestUtilsError {
+    #[error("Banks client: {0}")]
+    Banks(#[from] BanksClientError),
+
+    #[error("RPC client: {0}")]
+    Rpc(Box<ClientError>),
+
+    #[error("Instruction building failed: {0}")]
+    Program(#[from] ProgramError),
+
+    #[error("Clock sysvar unavailable")]
+    MissingClock,
+
+    #[cfg(feature = "program-test")]
+    #[error("Warp failed: {0}")]
+    Warp(#[from] solana_program_test::ProgramTestError),
+
+    #[cfg(feature = "program-test")]
+    #[error("Blockhash refresh failed: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl From<ClientError> for TestUtilsError {
+    fn from(e: ClientError) -> Self {
+        TestUtilsError::Rpc(Box::new(e))
+    }
+}
+
+/// Minimal cluster access needed to set up pools and stakers
+#[allow(async_fn_in_trait)]
+pub trait TestClient {
+    /// Blockhash to sign the next transaction with
+    async fn latest_blockhash(&mut self) -> Result<Hash, TestUtilsError>;
+
+    /// Submit `transaction` and wait until it is confirmed
+    async fn process(&mut self, transaction: Transaction) -> Result<(), TestUtilsError>;
+
+    /// Account at `address`, if it exists
+    async fn account(&mut self, address: &Pubkey) -> Result<Option<Account>, TestUtilsError>;
+
+    /// Rent-exempt minimum for `len` bytes of data
+    async fn minimum_balance(&mut self, len: usize) -> Result<u64, TestUtilsError>;
+
+    /// Give `to` `lamports`: a faucet airdrop on RPC, a transfer from
+    /// `payer` on a bank
+    async fn airdrop(
+        &mut self,
+        payer: &Keypair,
+        to: &Pubkey,
+        lamports: u64,
+    ) -> Result<(), TestUtilsError>;
+
+    /// Current Clock sysvar
+    async fn clock(&mut self) -> Result<Clock, TestUtilsError> {
+        let account = self
+            .account(&sysvar::clock::ID)
+            .await?
+            .ok_or(TestUtilsError::MissingClock)?;
+        from_account(&account).ok_or(TestUtilsError::MissingClock)
+    }
+
+    /// Sign `instructions` with `payer` (fee payer) and `signers`, then process
+    async fn send(
+        &mut self,
+        instructions: &[Instruction],
+        payer: &Keypair,
+        signers: &[&Keypair],
+    ) -> Result<(), TestUtilsError> {
+        let blockhash = self.latest_blockhash().await?;
+        let mut keys: Vec<&Keypair> = vec![payer];
+        keys.extend_from_slice(signers);
+        let transaction =
+            Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), &keys, blockhash);
+        self.process(transaction).await
+    }
+}
+
+impl TestClient for BanksClient {
+    async fn latest_blockhash(&mut self) -> Result<Hash, TestUtilsError> {
+        Ok(self.get_latest_blockhash().await?)
+    }
+
+    async fn process(&mut self, transaction: Transaction) -> Result<(), TestUtilsError> {
+        Ok(self.process_transaction(transaction).await?)
+    }
+
+    async fn account(&mut self, address: &Pubkey) -> Result<Option<Account>, TestUtilsError> {
+        Ok(self.get_account(*address).await?)
+    }
+
+    async fn minimum_balance(&mut self, len: usize) -> Result<u64, TestUtilsError> {
+        Ok(self.get_rent().await?.minimum_balance(len))
+    }
+
+    async fn airdrop(
+        &mut self,
+        payer: &Keypair,
+        to: &Pubkey,
+        lamports: u64,
+    ) -> Result<(), TestUtilsError> {
+        let transfer = system_instruction::transfer(&payer.pubkey(), to, lamports);
+        self.send(&[transfer], payer, &[]).await
+    }
+}
+
+impl TestClient for RpcClient {
+    async fn latest_blockhash(&mut self) -> Result<Hash, TestUtilsError> {
+        Ok(self.get_latest_blockhash().await?)
+    }
+
+    async fn process(&mut self, transaction: Transaction) -> Result<(), TestUtilsError> {
+        self.send_and_confirm_transaction(&transaction).await?;
+        Ok(())
+    }
+
+    async fn account(&mut self, address: &Pubkey) -> Result<Option<Account>, TestUtilsError> {
+        Ok(self
+            .get_account_with_commitment(address, self.commitment())
+            .await?
+            .value)
+    }
+
+    async fn minimum_balance(&mut self, len: usize) -> Result<u64, TestUtilsError> {
+        Ok(self.get_minimum_balance_for_rent_exemption(len).await?)
+    }
+
+    async fn airdrop(
+        &mut self,
+        _payer: &Keypair,
+        to: &Pubkey,
+        lamports: u64,
+    ) -> Result<(), TestUtilsError> {
+        let signature = self.request_airdrop(to, lamports).await?;
+        self.poll_for_signature(&signature).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+//! Moving the cluster clock
+
+use std::time::Duration;
+
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::clock::Clock;
+
+use crate::client::{TestClient, TestUtilsError};
+
+/// Jump a program-test bank one slot and `seconds` ahead, refreshing the
+/// context's blockhash. Returns the new clock.
+#[cfg(feature = "program-test")]
+pub async fn warp_forward(
+    context: &mut solana_program_test::ProgramTestContext,
+    seconds: i64,
+) -> Result<Clock, TestUtilsError> {
+    let mut clock: Clock = context.banks_client.get_sysvar().await?;
+    context.warp_to_slot(clock.slot + 1)?;
+    clock.slot += 1;
+    clock.unix_timestamp += seconds;
+    context.set_sysvar(&clock);
+    context.last_blockhash = context.get_new_latest_blockhash().await?;
+    Ok(clock)
+}
+
+/// A running validator can't be warped: poll its clock every `poll` until
+/// it reaches `unix_timestamp`. Returns the clock that did.
+pub async fn wait_until(
+    client: &mut RpcClient,
+    unix_timestamp: i64,
+    poll: Duration,
+) -> Result<Clock, TestUtilsError> {
+    loop {
+        let clock = client.clock().await?;
+        if clock.unix_timestamp >= unix_timestamp {
+            return Ok(clock);
+        }
+        tokio::time::sleep(poll).await;
+    }
+}
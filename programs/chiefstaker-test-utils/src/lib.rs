@@ -0,0 +1,22 @@
+//! ChiefStaker test utilities
+//!
+//! Setup helpers for integration tests of programs and tools built on
+//! ChiefStaker. They run against anything implementing [`TestClient`]: a
+//! `BanksClient` (solana-program-test) or a nonblocking `RpcClient` pointed
+//! at `solana-test-validator`.
+//!
+//! ```ignore
+//! let mint = Keypair::new();
+//! create_mint_with_metadata(&mut client, &payer, &mint, 6, "Chief", "CHIEF", "").await?;
+//! let pool = initialize_pool(&mut client, &payer, &mint.pubkey(), 86_400).await?;
+//! let stakers = fund_stakers(&mut client, &payer, &pool, 4, LAMPORTS_PER_SOL, 1_000_000).await?;
+//! warp_forward(&mut context, 86_400).await?; // with the `program-test` feature
+//! ```
+
+pub mod client;
+pub mod clock;
+pub mod setup;
+
+pub use client::{TestClient, TestUtilsError};
+pub use clock::*;
+pub use setup::*;
@@ -0,0 +1,206 @@
+//! Mint, pool and staker setup
+
+use chiefstaker_interface::{
+    instruction::StakingInstruction,
+    pda::{find_pool_address, find_stake_address, find_token_vault_address},
+    ID,
+};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction, system_program, sysvar,
+};
+use spl_token_2022::{
+    extension::{metadata_pointer, ExtensionType},
+    state::{Account as TokenAccount, Mint},
+};
+use spl_token_metadata_interface::state::TokenMetadata;
+
+use crate::client::{TestClient, TestUtilsError};
+
+/// Addresses of a pool created by [`initialize_pool`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolSetup {
+    pub mint: Pubkey,
+    pub pool: Pubkey,
+    pub token_vault: Pubkey,
+}
+
+/// A funded staker created by [`fund_stakers`]
+#[derive(Debug)]
+pub struct Staker {
+    pub keypair: Keypair,
+    /// Token 2022 account holding the staker's tokens
+    pub token_account: Pubkey,
+    /// UserStake PDA in the pool the staker was funded for
+    pub stake: Pubkey,
+}
+
+/// Create a Token 2022 mint with a metadata pointer to itself and embedded
+/// token metadata. `payer` is mint authority and metadata update authority.
+#[allow(clippy::too_many_arguments)]
+pub async fn create_mint_with_metadata<C: TestClient>(
+    client: &mut C,
+    payer: &Keypair,
+    mint: &Keypair,
+    decimals: u8,
+    name: &str,
+    symbol: &str,
+    uri: &str,
+) -> Result<(), TestUtilsError> {
+    let authority = payer.pubkey();
+    let mint_key = mint.pubkey();
+    let token_program = spl_token_2022::id();
+
+    // The account starts at the pointer-only size; initializing metadata
+    // grows it, so fund the final size up front
+    let space = ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::MetadataPointer])?;
+    let metadata = TokenMetadata {
+        mint: mint_key,
+        name: name.to_string(),
+        symbol: symbol.to_string(),
+        uri: uri.to_string(),
+        ..Default::default()
+    };
+    let lamports = client.minimum_balance(space + metadata.tlv_size_of()?).await?;
+
+    let instructions = [
+        system_instruction::create_account(&authority, &mint_key, lamports, space as u64, &token_program),
+        metadata_pointer::instruction::initialize(
+            &token_program,
+            &mint_key,
+            Some(authority),
+            Some(mint_key),
+        )?,
+        spl_token_2022::instruction::initialize_mint2(&token_program, &mint_key, &authority, None, decimals)?,
+        spl_token_metadata_interface::instruction::initialize(
+            &token_program,
+            &mint_key,
+            &authority,
+            &mint_key,
+            &authority,
+            metadata.name,
+            metadata.symbol,
+            metadata.uri,
+        ),
+    ];
+    client.send(&instructions, payer, &[mint]).await
+}
+
+/// InitializePool for pool id 0 of `mint`, with `authority` as authority/payer
+pub fn initialize_pool_instruction(mint: &Pubkey, authority: &Pubkey, tau_seconds: u64) -> Instruction {
+    let (pool, _) = find_pool_address(mint, 0);
+    let (token_vault, _) = find_token_vault_address(&pool);
+    Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(pool, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(token_vault, false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(spl_token_2022::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::ID, false),
+        ],
+        data: StakingInstruction::InitializePool { tau_seconds }.pack(),
+    }
+}
+
+/// Initialize pool id 0 of `mint` with `payer` as authority
+pub async fn initialize_pool<C: TestClient>(
+    client: &mut C,
+    payer: &Keypair,
+    mint: &Pubkey,
+    tau_seconds: u64,
+) -> Result<PoolSetup, TestUtilsError> {
+    let instruction = initialize_pool_instruction(mint, &payer.pubkey(), tau_seconds);
+    client.send(&[instruction], payer, &[]).await?;
+
+    let (pool, _) = find_pool_address(mint, 0);
+    let (token_vault, _) = find_token_vault_address(&pool);
+    Ok(PoolSetup {
+        mint: *mint,
+        pool,
+        token_vault,
+    })
+}
+
+/// Create `count` stakers for `pool`, each given `lamports` of SOL and a
+/// Token 2022 account holding `tokens` freshly minted by `payer` (the mint
+/// authority)
+pub async fn fund_stakers<C: TestClient>(
+    client: &mut C,
+    payer: &Keypair,
+    pool: &PoolSetup,
+    count: usize,
+    lamports: u64,
+    tokens: u64,
+) -> Result<Vec<Staker>, TestUtilsError> {
+    let token_program = spl_token_2022::id();
+    let account_rent = client.minimum_balance(TokenAccount::LEN).await?;
+
+    let mut stakers = Vec::with_capacity(count);
+    for _ in 0..count {
+        let keypair = Keypair::new();
+        let token_account = Keypair::new();
+        client.airdrop(payer, &keypair.pubkey(), lamports).await?;
+
+        let instructions = [
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &token_account.pubkey(),
+                account_rent,
+                TokenAccount::LEN as u64,
+                &token_program,
+            ),
+            spl_token_2022::instruction::initialize_account3(
+                &token_program,
+                &token_account.pubkey(),
+                &pool.mint,
+                &keypair.pubkey(),
+            )?,
+            spl_token_2022::instruction::mint_to(
+                &token_program,
+                &pool.mint,
+                &token_account.pubkey(),
+                &payer.pubkey(),
+                &[],
+                tokens,
+            )?,
+        ];
+        client.send(&instructions, payer, &[&token_account]).await?;
+
+        let (stake, _) = find_stake_address(&pool.pool, &keypair.pubkey());
+        stakers.push(Staker {
+            keypair,
+            token_account: token_account.pubkey(),
+            stake,
+        });
+    }
+    Ok(stakers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chiefstaker_interface::accounts::initialize_pool as accounts;
+
+    #[test]
+    fn test_initialize_pool_instruction() {
+        let mint = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let ix = initialize_pool_instruction(&mint, &authority, 3_600);
+
+        assert_eq!(ix.accounts.len(), accounts::FIXED_LEN);
+        let (pool, _) = find_pool_address(&mint, 0);
+        assert_eq!(ix.accounts[accounts::POOL].pubkey, pool);
+        assert_eq!(ix.accounts[accounts::TOKEN_VAULT].pubkey, find_token_vault_address(&pool).0);
+        assert!(ix.accounts[accounts::AUTHORITY].is_signer);
+        assert!(matches!(
+            StakingInstruction::unpack(&ix.data).unwrap(),
+            StakingInstruction::InitializePool { tau_seconds: 3_600 }
+        ));
+    }
+}
@@ -0,0 +1,335 @@
+//! Account handling of the age-bucketed weight engine
+//!
+//! Pools created by InitializeBucketedPool keep their buckets in an
+//! AgeBuckets account and the accumulator at every epoch start in
+//! AgeBucketHistory pages; the math is chiefstaker_core::buckets. A
+//! position is `amount` tokens that entered at the epoch of its stake_time:
+//! its entitlement is `amount * earned_per_token` (WAD lamports), and
+//! claimed_rewards_wad counts what was already paid, as on exponential
+//! pools.
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar::Sysvar,
+};
+
+use chiefstaker_core::buckets;
+
+use crate::{
+    error::StakingError,
+    instructions::create_lamport_vault,
+    math::U256,
+    state::{
+        AgeBucketHistory, AgeBuckets, StakingPool, UserStake, WeightEngine,
+        AGE_BUCKET_HISTORY_DISCRIMINATOR, AGE_BUCKET_HISTORY_SEED, POOL_DISCRIMINATOR,
+    },
+};
+
+/// Whether `accounts` start with a pool of the AgeBuckets engine. Reads two
+/// fields in place, so the entrypoint can route an instruction before its
+/// handler loads the pool.
+pub fn is_bucketed_pool(program_id: &Pubkey, accounts: &[AccountInfo]) -> bool {
+    let Some(pool_info) = accounts.first() else {
+        return false;
+    };
+    if pool_info.owner != program_id {
+        return false;
+    }
+    let Ok(data) = pool_info.try_borrow_data() else {
+        return false;
+    };
+    data.starts_with(&POOL_DISCRIMINATOR)
+        && data.get(StakingPool::WEIGHT_ENGINE_OFFSET) == Some(&(WeightEngine::AgeBuckets as u8))
+}
+
+/// Load the age buckets of the pool at `pool_key`: owned by the program,
+/// initialized, of that pool, and at its ["age_buckets", pool] PDA.
+pub fn load_age_buckets(
+    program_id: &Pubkey,
+    buckets_info: &AccountInfo,
+    pool_key: &Pubkey,
+) -> Result<AgeBuckets, ProgramError> {
+    if buckets_info.owner != program_id {
+        return Err(StakingError::InvalidAccountOwner.into());
+    }
+    let buckets = AgeBuckets::try_from_slice(&buckets_info.try_borrow_data()?)?;
+    if !buckets.is_initialized() {
+        return Err(StakingError::NotInitialized.into());
+    }
+    if buckets.pool != *pool_key {
+        return Err(StakingError::InvalidPool.into());
+    }
+    let (expected_buckets, _) = AgeBuckets::derive_pda(pool_key, program_id);
+    if *buckets_info.key != expected_buckets {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    Ok(buckets)
+}
+
+/// Validate `page_info` as history page `page` of the pool at `pool_key`.
+/// Returns its header, or None if the page hasn't been created yet.
+pub fn load_history_page(
+    program_id: &Pubkey,
+    page_info: &AccountInfo,
+    pool_key: &Pubkey,
+    page: u32,
+) -> Result<Option<AgeBucketHistory>, ProgramError> {
+    let (expected_page, _) = AgeBucketHistory::derive_pda(pool_key, page, program_id);
+    if *page_info.key != expected_page {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    if page_info.owner != program_id {
+        // Not created yet (possibly pre-funded by a transfer)
+        return Ok(None);
+    }
+    let data = page_info.try_borrow_data()?;
+    let header = data
+        .get(..AgeBucketHistory::HEADER_LEN)
+        .ok_or(StakingError::AccountDataTooSmall)?;
+    let history = AgeBucketHistory::try_from_slice(header)?;
+    if !history.is_initialized() {
+        return Err(StakingError::NotInitialized.into());
+    }
+    Ok(Some(history))
+}
+
+/// Create history page `page` of the pool at `pool_key` (payer funds rent)
+pub fn create_history_page<'a>(
+    program_id: &Pubkey,
+    page_info: &AccountInfo<'a>,
+    pool_key: &Pubkey,
+    page: u32,
+    payer_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+) -> Result<AgeBucketHistory, ProgramError> {
+    let (expected_page, bump) = AgeBucketHistory::derive_pda(pool_key, page, program_id);
+    if *page_info.key != expected_page {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    let page_bytes = page.to_le_bytes();
+    let seeds: &[&[u8]] = &[AGE_BUCKET_HISTORY_SEED, pool_key.as_ref(), &page_bytes, &[bump]];
+    create_lamport_vault(
+        program_id,
+        payer_info,
+        page_info,
+        system_program_info,
+        seeds,
+        AgeBucketHistory::LEN,
+    )?;
+
+    let history = AgeBucketHistory {
+        discriminator: AGE_BUCKET_HISTORY_DISCRIMINATOR,
+        pool: *pool_key,
+        page,
+        count: 0,
+        bump,
+    };
+    borsh::to_writer(&mut &mut page_info.try_borrow_mut_data()?[..], &history)?;
+    Ok(history)
+}
+
+/// Roll `buckets` one epoch at a time towards `target`, appending each
+/// epoch start to `history` (the page of epoch `buckets.epoch + 1`, with
+/// data `page_data`). Stops at the end of the page. Returns the epochs
+/// rolled.
+pub fn roll(
+    buckets: &mut AgeBuckets,
+    history: &mut AgeBucketHistory,
+    page_data: &mut [u8],
+    target: u64,
+) -> Result<u64, ProgramError> {
+    let page_end = (history.page as u64 + 1) * AgeBucketHistory::CAPACITY as u64 - 1;
+    let mut rolled = 0;
+    while buckets.epoch < target.min(page_end) {
+        buckets.roll_one();
+        history.push(page_data, buckets.epoch_acc)?;
+        rolled += 1;
+    }
+    Ok(rolled)
+}
+
+/// Epoch a position entered at. Stakes only land in a pool rolled to the
+/// current epoch, so this is the epoch of its stake_time.
+pub fn entry_epoch(buckets: &AgeBuckets, user_stake: &UserStake) -> u64 {
+    buckets.epoch_at(user_stake.stake_time)
+}
+
+/// Take from `iter` the history pages with the epoch starts a position
+/// that entered at `entry_epoch` needs: its first bucket_count epochs,
+/// before the current one (whose start is AgeBuckets::epoch_acc). That is
+/// one page, two across a page boundary, and none for positions of the
+/// current epoch. Returns the starts, from `entry_epoch` on.
+pub fn next_epoch_starts<'a, 'b, I>(
+    program_id: &Pubkey,
+    pool_key: &Pubkey,
+    buckets: &AgeBuckets,
+    entry_epoch: u64,
+    iter: &mut I,
+) -> Result<Vec<u128>, ProgramError>
+where
+    'a: 'b,
+    I: Iterator<Item = &'b AccountInfo<'a>>,
+{
+    if entry_epoch >= buckets.epoch {
+        return Ok(Vec::new());
+    }
+    let last = (entry_epoch + buckets.bucket_count as u64 - 1).min(buckets.epoch - 1);
+    let mut starts = Vec::with_capacity((last - entry_epoch + 1) as usize);
+    for page in AgeBucketHistory::page_of(entry_epoch)..=AgeBucketHistory::page_of(last) {
+        let page_info = next_account_info(iter)?;
+        let history = load_history_page(program_id, page_info, pool_key, page)?
+            .ok_or(StakingError::NotInitialized)?;
+        let data = page_info.try_borrow_data()?;
+        let first = entry_epoch + starts.len() as u64;
+        let page_last = last.min((page as u64 + 1) * AgeBucketHistory::CAPACITY as u64 - 1);
+        for epoch in first..=page_last {
+            starts.push(history.get(&data, epoch).ok_or(StakingError::NotInitialized)?);
+        }
+    }
+    Ok(starts)
+}
+
+/// Reward per token (WAD, weight-scaled) a position that entered at
+/// `entry_epoch` has earned, with `starts` from [`next_epoch_starts`]
+pub fn earned_per_token(
+    buckets: &AgeBuckets,
+    entry_epoch: u64,
+    starts: &[u128],
+) -> Result<u128, StakingError> {
+    Ok(buckets::earned_per_token(
+        buckets.weights(),
+        entry_epoch,
+        buckets.epoch,
+        buckets.acc_per_weight,
+        |epoch| {
+            if epoch == buckets.epoch {
+                buckets.epoch_acc
+            } else {
+                starts[(epoch - entry_epoch) as usize]
+            }
+        },
+    )?)
+}
+
+/// WAD lamports `amount` tokens are entitled to at `earned` per token
+pub fn entitlement_wad(amount: u64, earned: u128) -> Result<u128, StakingError> {
+    (U256::from(amount) * U256::from_u128(earned))
+        .to_u128()
+        .ok_or(StakingError::MathOverflow)
+}
+
+/// Distribute the pool's new lamports (above last_synced_lamports) over
+/// the bucket weights, less the protocol fee. They stay pending while the
+/// buckets aren't rolled to the epoch of `current_time`, since they would
+/// otherwise accrue to an older epoch's weights, or while nothing is
+/// staked. Returns the lamports distributed.
+pub fn distribute_new_rewards(
+    pool: &mut StakingPool,
+    buckets: &mut AgeBuckets,
+    pool_info: &AccountInfo,
+    current_time: i64,
+) -> Result<u64, ProgramError> {
+    let rent_exempt_minimum = Rent::get()?.minimum_balance(pool_info.data_len());
+    let available = pool_info.lamports().saturating_sub(rent_exempt_minimum);
+    let new_rewards = available.saturating_sub(pool.last_synced_lamports);
+    if new_rewards == 0 || !buckets.is_rolled(current_time) || buckets.total_weight() == 0 {
+        return Ok(0);
+    }
+
+    // The protocol fee stays in the pool, owed to the treasury
+    let fee = pool.protocol_fee(new_rewards);
+    pool.protocol_fees_owed = pool
+        .protocol_fees_owed
+        .checked_add(fee)
+        .ok_or(StakingError::MathOverflow)?;
+    buckets.distribute(new_rewards - fee)?;
+
+    pool.last_synced_lamports = available;
+    pool.last_update_time = current_time;
+    pool.last_distribution_time = current_time;
+    Ok(new_rewards)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::WAD;
+    use chiefstaker_core::buckets::AgeBucketSchedule;
+
+    const DAY: i64 = 86_400;
+
+    fn test_buckets() -> AgeBuckets {
+        let schedule = AgeBucketSchedule {
+            bucket_seconds: DAY as u64,
+            weights_bps: vec![0, 5_000, 10_000],
+        };
+        AgeBuckets::new(Pubkey::new_unique(), &schedule, 0, 255)
+    }
+
+    fn test_page(page: u32) -> (AgeBucketHistory, Vec<u8>) {
+        let history = AgeBucketHistory {
+            discriminator: AGE_BUCKET_HISTORY_DISCRIMINATOR,
+            pool: Pubkey::new_unique(),
+            page,
+            count: 0,
+            bump: 255,
+        };
+        let mut data = vec![0u8; AgeBucketHistory::LEN];
+        borsh::to_writer(&mut &mut data[..], &history).unwrap();
+        (history, data)
+    }
+
+    #[test]
+    fn test_roll_stops_at_page_end() {
+        let mut buckets = test_buckets();
+        let (mut page0, mut data0) = test_page(0);
+        page0.push(&mut data0, 0).unwrap();
+        buckets.amounts[0] = 100;
+        buckets.acc_per_weight = 7;
+
+        assert_eq!(roll(&mut buckets, &mut page0, &mut data0, 1).unwrap(), 1);
+        assert_eq!(buckets.amounts[..3], [0, 100, 0]);
+        assert_eq!((buckets.epoch, buckets.epoch_acc), (1, 7));
+        assert_eq!(page0.get(&data0, 1), Some(7));
+
+        // A late crank fills the page, then needs the next one
+        assert_eq!(roll(&mut buckets, &mut page0, &mut data0, 500).unwrap(), 62);
+        assert_eq!(buckets.epoch, 63);
+        assert_eq!(page0.count, AgeBucketHistory::CAPACITY);
+        assert_eq!(buckets.amounts[..3], [0, 0, 100]);
+        assert!(!buckets.is_rolled(500 * DAY));
+
+        let (mut page1, mut data1) = test_page(1);
+        assert_eq!(roll(&mut buckets, &mut page1, &mut data1, 70).unwrap(), 7);
+        assert_eq!(page1.get(&data1, 64), Some(7));
+        assert_eq!(page1.get(&data1, 71), None);
+        assert!(buckets.is_rolled(70 * DAY + 1));
+    }
+
+    #[test]
+    fn test_earned_per_token() {
+        let mut buckets = test_buckets();
+        buckets.amounts[0] = 1_000;
+
+        // Bucket 0 has no weight: a fresh stake can't catch a distribution
+        assert!(!buckets.distribute(1_000).unwrap());
+        assert_eq!(earned_per_token(&buckets, 0, &[]).unwrap(), 0);
+
+        // Epoch 1: half weight, so 1_000 tokens earn all of 500 lamports
+        buckets.roll_one();
+        assert!(buckets.distribute(500).unwrap());
+        let earned = earned_per_token(&buckets, 0, &[0]).unwrap();
+        assert_eq!(entitlement_wad(1_000, earned).unwrap(), 500 * WAD);
+
+        // Epoch 2: full weight; the epoch-1 start comes from the history
+        let epoch1_start = buckets.epoch_acc;
+        buckets.roll_one();
+        buckets.distribute(300).unwrap();
+        let earned = earned_per_token(&buckets, 0, &[0, epoch1_start]).unwrap();
+        assert_eq!(entitlement_wad(1_000, earned).unwrap(), 800 * WAD);
+    }
+}
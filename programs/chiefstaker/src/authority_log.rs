@@ -0,0 +1,80 @@
+//! Authority action log for pools with an AuthorityLog account
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program::invoke,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    error::StakingError,
+    state::{AuthorityLog, AuthorityLogEntry, StakingPool},
+};
+
+/// Take the AuthorityLog account from `iter` if the pool keeps one.
+/// Pools without a log consume nothing.
+pub fn next_authority_log<'a, 'b, I>(
+    pool: &StakingPool,
+    iter: &mut I,
+) -> Result<Option<&'b AccountInfo<'a>>, ProgramError>
+where
+    I: Iterator<Item = &'b AccountInfo<'a>>,
+{
+    if !pool.has_authority_log {
+        return Ok(None);
+    }
+    next_account_info(iter).map(Some)
+}
+
+/// Validate `log_info` as the pool's AuthorityLog and append `entries`,
+/// growing the account first (the authority pays the rent)
+pub fn record_authority_actions<'a>(
+    program_id: &Pubkey,
+    log_info: &AccountInfo<'a>,
+    pool_key: &Pubkey,
+    authority_info: &AccountInfo<'a>,
+    system_program_info: Option<&AccountInfo<'a>>,
+    entries: &[AuthorityLogEntry],
+) -> ProgramResult {
+    if log_info.owner != program_id {
+        return Err(StakingError::InvalidAccountOwner.into());
+    }
+    let (expected_log, _) = AuthorityLog::derive_pda(pool_key, program_id);
+    if *log_info.key != expected_log {
+        return Err(StakingError::InvalidPDA.into());
+    }
+
+    let mut log = {
+        let data = log_info.try_borrow_data()?;
+        let header = data
+            .get(..AuthorityLog::HEADER_LEN)
+            .ok_or(StakingError::AccountDataTooSmall)?;
+        AuthorityLog::try_from_slice(header)?
+    };
+    if !log.is_initialized() {
+        return Err(StakingError::NotInitialized.into());
+    }
+
+    let space = AuthorityLog::space(log.count.saturating_add(entries.len() as u64));
+    if log_info.data_len() < space {
+        let rent_delta = Rent::get()?
+            .minimum_balance(space)
+            .saturating_sub(log_info.lamports());
+        if rent_delta > 0 {
+            let sys_prog = system_program_info.ok_or(StakingError::MissingSystemProgram)?;
+            invoke(
+                &system_instruction::transfer(authority_info.key, log_info.key, rent_delta),
+                &[authority_info.clone(), log_info.clone(), sys_prog.clone()],
+            )?;
+        }
+        log_info.realloc(space, false)?;
+    }
+
+    log.append(&mut log_info.try_borrow_mut_data()?, entries)
+}
@@ -0,0 +1,178 @@
+//! Compressed positions in an spl-account-compression merkle tree
+//!
+//! A compressed position is a UserStake stored as a leaf instead of an
+//! account: leaf = keccak(borsh(UserStake)), over the bytes as serialized
+//! at compression so later UserStake fields don't orphan older leaves
+//! (they deserialize with defaults). The pool PDA is the tree
+//! authority, so only this program can append or replace leaves. The
+//! account-compression program is invoked through hand-built instructions
+//! (Anchor layout) to avoid pulling Anchor into the program.
+
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    keccak,
+    pubkey,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::StakingError,
+    state::StakingPool,
+};
+
+/// spl-account-compression program
+pub const ACCOUNT_COMPRESSION_ID: Pubkey = pubkey!("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK");
+
+/// spl-noop program (changelog sink for the compression program)
+pub const NOOP_ID: Pubkey = pubkey!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+
+/// sha256("global:init_empty_merkle_tree")[..8]
+const INIT_EMPTY_MERKLE_TREE_DISCRIMINATOR: [u8; 8] = [191, 11, 119, 7, 180, 107, 220, 110];
+
+/// sha256("global:append")[..8]
+const APPEND_DISCRIMINATOR: [u8; 8] = [149, 120, 18, 222, 236, 225, 88, 203];
+
+/// sha256("global:replace_leaf")[..8]
+const REPLACE_LEAF_DISCRIMINATOR: [u8; 8] = [204, 165, 76, 100, 73, 147, 0, 128];
+
+/// Leaf value of an emptied (decompressed) position
+pub const EMPTY_LEAF: [u8; 32] = [0u8; 32];
+
+/// Leaf hash of a compressed position, given its borsh bytes
+pub fn compressed_leaf(position: &[u8]) -> [u8; 32] {
+    keccak::hashv(&[position]).to_bytes()
+}
+
+/// Check the tree, compression program and noop accounts against the pool
+pub fn check_tree_accounts(
+    pool: &StakingPool,
+    tree_info: &AccountInfo,
+    compression_program_info: &AccountInfo,
+    noop_info: &AccountInfo,
+) -> ProgramResult {
+    if !pool.has_compression_tree() {
+        return Err(StakingError::CompressionNotEnabled.into());
+    }
+    if *compression_program_info.key != ACCOUNT_COMPRESSION_ID {
+        return Err(StakingError::InvalidCompressionTree.into());
+    }
+    if *noop_info.key != NOOP_ID {
+        return Err(StakingError::InvalidCompressionTree.into());
+    }
+    if *tree_info.key != pool.compression_tree || *tree_info.owner != ACCOUNT_COMPRESSION_ID {
+        return Err(StakingError::InvalidCompressionTree.into());
+    }
+    Ok(())
+}
+
+fn tree_accounts(tree: &Pubkey, authority: &Pubkey) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(*tree, false),
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new_readonly(NOOP_ID, false),
+    ]
+}
+
+/// `init_empty_merkle_tree` for a tree account already allocated (zeroed,
+/// owned by the compression program) for `max_depth` / `max_buffer_size`
+pub fn init_empty_merkle_tree(
+    tree: &Pubkey,
+    authority: &Pubkey,
+    max_depth: u32,
+    max_buffer_size: u32,
+) -> Instruction {
+    let mut data = Vec::with_capacity(16);
+    data.extend_from_slice(&INIT_EMPTY_MERKLE_TREE_DISCRIMINATOR);
+    data.extend_from_slice(&max_depth.to_le_bytes());
+    data.extend_from_slice(&max_buffer_size.to_le_bytes());
+    Instruction {
+        program_id: ACCOUNT_COMPRESSION_ID,
+        accounts: tree_accounts(tree, authority),
+        data,
+    }
+}
+
+/// `append` a leaf at the tree's next index
+pub fn append(tree: &Pubkey, authority: &Pubkey, leaf: [u8; 32]) -> Instruction {
+    let mut data = Vec::with_capacity(40);
+    data.extend_from_slice(&APPEND_DISCRIMINATOR);
+    data.extend_from_slice(&leaf);
+    Instruction {
+        program_id: ACCOUNT_COMPRESSION_ID,
+        accounts: tree_accounts(tree, authority),
+        data,
+    }
+}
+
+/// `replace_leaf` at `index`, proving `previous_leaf` against `root` with
+/// the `proof` nodes (leaf to root, minus any canopy)
+pub fn replace_leaf(
+    tree: &Pubkey,
+    authority: &Pubkey,
+    root: [u8; 32],
+    previous_leaf: [u8; 32],
+    new_leaf: [u8; 32],
+    index: u32,
+    proof: &[Pubkey],
+) -> Instruction {
+    let mut data = Vec::with_capacity(108);
+    data.extend_from_slice(&REPLACE_LEAF_DISCRIMINATOR);
+    data.extend_from_slice(&root);
+    data.extend_from_slice(&previous_leaf);
+    data.extend_from_slice(&new_leaf);
+    data.extend_from_slice(&index.to_le_bytes());
+    let mut accounts = tree_accounts(tree, authority);
+    accounts.extend(proof.iter().map(|node| AccountMeta::new_readonly(*node, false)));
+    Instruction {
+        program_id: ACCOUNT_COMPRESSION_ID,
+        accounts,
+        data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::state::UserStake;
+    use borsh::BorshDeserialize;
+
+    #[test]
+    fn test_compressed_leaf() {
+        let stake = UserStake::new(Pubkey::new_unique(), Pubkey::new_unique(), 100, 0, 1, 255, 0);
+        let bytes = borsh::to_vec(&stake).unwrap();
+        let leaf = compressed_leaf(&bytes);
+        assert_ne!(leaf, EMPTY_LEAF);
+
+        // Any field change yields another leaf
+        let mut other = stake.clone();
+        other.reward_debt = 1;
+        assert_ne!(leaf, compressed_leaf(&borsh::to_vec(&other).unwrap()));
+        let mut other = stake;
+        other.owner = Pubkey::new_unique();
+        assert_ne!(leaf, compressed_leaf(&borsh::to_vec(&other).unwrap()));
+
+        // A position compressed before the last UserStake field existed
+        // still deserializes; its leaf is over the shorter bytes
+        let short = &bytes[..bytes.len() - 8];
+        let restored = UserStake::try_from_slice(short).unwrap();
+        assert_eq!(restored.amount, 100);
+        assert_eq!(restored.sponsored_rent, 0);
+    }
+
+    #[test]
+    fn test_replace_leaf_layout() {
+        let tree = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let proof = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let ix = replace_leaf(&tree, &authority, [1; 32], [2; 32], EMPTY_LEAF, 7, &proof);
+        assert_eq!(ix.data.len(), 108);
+        assert_eq!(ix.data[104..], 7u32.to_le_bytes());
+        assert_eq!(ix.accounts.len(), 5);
+        assert!(ix.accounts[1].is_signer);
+        assert_eq!(ix.accounts[4].pubkey, proof[1]);
+        assert_eq!(append(&tree, &authority, [3; 32]).data.len(), 40);
+    }
+}
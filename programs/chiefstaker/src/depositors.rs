@@ -0,0 +1,112 @@
+//! Depositor checks for pools with a DepositorAllowlist account, and
+//! contribution records for pools that track depositors
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::StakingError,
+    instructions::create_lamport_vault,
+    state::{DepositorAllowlist, DepositorRecord, StakingPool, DEPOSITOR_RECORD_SEED},
+};
+
+/// Take the DepositorAllowlist account from `iter` if the pool keeps one and
+/// fail with DepositorNotAllowed unless `depositor` is on it. Pools without
+/// an allowlist consume nothing.
+pub fn check_depositor<'a: 'b, 'b, I>(
+    program_id: &Pubkey,
+    pool_key: &Pubkey,
+    pool: &StakingPool,
+    depositor: &Pubkey,
+    iter: &mut I,
+) -> ProgramResult
+where
+    I: Iterator<Item = &'b AccountInfo<'a>>,
+{
+    if !pool.has_depositor_allowlist {
+        return Ok(());
+    }
+    let allowlist_info = next_account_info(iter)?;
+    if allowlist_info.owner != program_id {
+        return Err(StakingError::InvalidAccountOwner.into());
+    }
+    let (expected_allowlist, _) = DepositorAllowlist::derive_pda(pool_key, program_id);
+    if *allowlist_info.key != expected_allowlist {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    let allowlist = DepositorAllowlist::try_from_slice(&allowlist_info.try_borrow_data()?)?;
+    if !allowlist.is_initialized() {
+        return Err(StakingError::NotInitialized.into());
+    }
+    if !allowlist.contains(depositor) {
+        return Err(StakingError::DepositorNotAllowed.into());
+    }
+    Ok(())
+}
+
+/// Take the depositor's DepositorRecord from `iter` if the pool tracks
+/// depositors. Pools without tracking consume nothing.
+pub fn next_depositor_record<'a, 'b, I>(
+    pool: &StakingPool,
+    iter: &mut I,
+) -> Result<Option<&'b AccountInfo<'a>>, ProgramError>
+where
+    I: Iterator<Item = &'b AccountInfo<'a>>,
+{
+    if !pool.track_depositors {
+        return Ok(None);
+    }
+    next_account_info(iter).map(Some)
+}
+
+/// Add a deposit of `amount` at `current_time` to the record at
+/// `record_info`, creating it on the depositor's first deposit (depositor
+/// funds rent)
+pub fn record_deposit<'a>(
+    program_id: &Pubkey,
+    record_info: &AccountInfo<'a>,
+    pool_key: &Pubkey,
+    depositor_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    amount: u64,
+    current_time: i64,
+) -> ProgramResult {
+    let (expected_record, bump) =
+        DepositorRecord::derive_pda(pool_key, depositor_info.key, program_id);
+    if *record_info.key != expected_record {
+        return Err(StakingError::InvalidPDA.into());
+    }
+
+    let mut record = if record_info.owner == program_id {
+        let record = DepositorRecord::try_from_slice(&record_info.try_borrow_data()?)?;
+        if !record.is_initialized() {
+            return Err(StakingError::NotInitialized.into());
+        }
+        record
+    } else {
+        let seeds: &[&[u8]] = &[
+            DEPOSITOR_RECORD_SEED,
+            pool_key.as_ref(),
+            depositor_info.key.as_ref(),
+            &[bump],
+        ];
+        create_lamport_vault(
+            program_id,
+            depositor_info,
+            record_info,
+            system_program_info,
+            seeds,
+            DepositorRecord::LEN,
+        )?;
+        DepositorRecord::new(*pool_key, *depositor_info.key, bump)
+    };
+
+    record.record(amount, current_time);
+    borsh::to_writer(&mut &mut record_info.try_borrow_mut_data()?[..], &record)?;
+    Ok(())
+}
@@ -0,0 +1,116 @@
+//! Distribution checkpoints for pools with DistributionHistory pages
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::StakingError,
+    instructions::create_lamport_vault,
+    state::{
+        DistributionCheckpoint, DistributionHistory, StakingPool, DISTRIBUTION_HISTORY_DISCRIMINATOR,
+        DISTRIBUTION_HISTORY_SEED,
+    },
+};
+
+/// Take the current DistributionHistory page from `iter` if the pool keeps
+/// a history. Pools without one consume nothing.
+pub fn next_distribution_history<'a, 'b, I>(
+    pool: &StakingPool,
+    iter: &mut I,
+) -> Result<Option<&'b AccountInfo<'a>>, ProgramError>
+where
+    I: Iterator<Item = &'b AccountInfo<'a>>,
+{
+    if !pool.has_distribution_history {
+        return Ok(None);
+    }
+    next_account_info(iter).map(Some)
+}
+
+/// Validate `page_info` as the page receiving the pool's next checkpoint.
+/// Returns its header, or None if the page hasn't been created yet.
+pub fn load_history_page(
+    program_id: &Pubkey,
+    page_info: &AccountInfo,
+    pool_key: &Pubkey,
+    pool: &StakingPool,
+) -> Result<Option<DistributionHistory>, ProgramError> {
+    let page = DistributionHistory::page_of(pool.distribution_checkpoints);
+    let (expected_page, _) = DistributionHistory::derive_pda(pool_key, page, program_id);
+    if *page_info.key != expected_page {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    if page_info.owner != program_id {
+        // Not created yet (possibly pre-funded by a transfer)
+        return Ok(None);
+    }
+    let data = page_info.try_borrow_data()?;
+    let header = data
+        .get(..DistributionHistory::HEADER_LEN)
+        .ok_or(StakingError::AccountDataTooSmall)?;
+    let history = DistributionHistory::try_from_slice(header)?;
+    if !history.is_initialized() {
+        return Err(StakingError::NotInitialized.into());
+    }
+    Ok(Some(history))
+}
+
+/// Create page `page` of the pool's history at `page_info` (payer funds rent)
+pub fn create_history_page<'a>(
+    program_id: &Pubkey,
+    page_info: &AccountInfo<'a>,
+    pool_key: &Pubkey,
+    page: u32,
+    payer_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+) -> Result<DistributionHistory, ProgramError> {
+    let (expected_page, bump) = DistributionHistory::derive_pda(pool_key, page, program_id);
+    if *page_info.key != expected_page {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    let page_bytes = page.to_le_bytes();
+    let seeds: &[&[u8]] = &[DISTRIBUTION_HISTORY_SEED, pool_key.as_ref(), &page_bytes, &[bump]];
+    create_lamport_vault(
+        program_id,
+        payer_info,
+        page_info,
+        system_program_info,
+        seeds,
+        DistributionHistory::LEN,
+    )?;
+
+    let history = DistributionHistory {
+        discriminator: DISTRIBUTION_HISTORY_DISCRIMINATOR,
+        pool: *pool_key,
+        page,
+        count: 0,
+        bump,
+    };
+    borsh::to_writer(&mut &mut page_info.try_borrow_mut_data()?[..], &history)?;
+    Ok(history)
+}
+
+/// Append the pool's accumulator after a distribution of `lamports` at
+/// `current_time` to `history` (the page loaded from `page_info`) and count
+/// it in the pool. The caller saves the pool.
+pub fn record_distribution(
+    page_info: &AccountInfo,
+    history: &mut DistributionHistory,
+    pool: &mut StakingPool,
+    current_time: i64,
+    lamports: u64,
+) -> ProgramResult {
+    let checkpoint = DistributionCheckpoint {
+        timestamp: current_time,
+        acc_reward_per_weighted_share: pool.acc_reward_per_weighted_share,
+        lamports,
+    };
+    history.push(&mut page_info.try_borrow_mut_data()?, &checkpoint)?;
+    pool.distribution_checkpoints = pool.distribution_checkpoints.saturating_add(1);
+    Ok(())
+}
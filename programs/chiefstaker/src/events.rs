@@ -2,14 +2,46 @@
 
 use solana_program::{log::sol_log_data, pubkey::Pubkey};
 
+use crate::{
+    math::U256,
+    version::{PROGRAM_FEATURES, PROGRAM_VERSION},
+};
+
 /// sha256("event:RewardPayout")[..8]
 pub const REWARD_PAYOUT_DISCRIMINATOR: [u8; 8] = [0x9b, 0x22, 0x27, 0xc0, 0x5f, 0x1b, 0x8e, 0x4d];
 
+/// sha256("event:PoolSynced")[..8]
+pub const POOL_SYNCED_DISCRIMINATOR: [u8; 8] = [0x12, 0x44, 0x6f, 0xf0, 0xce, 0xca, 0xef, 0xce];
+
+/// sha256("event:PoolRebased")[..8]
+pub const POOL_REBASED_DISCRIMINATOR: [u8; 8] = [0x59, 0x1e, 0xa5, 0x39, 0x8b, 0x3f, 0x2b, 0x6c];
+
+/// sha256("event:StakeCompressed")[..8]
+pub const STAKE_COMPRESSED_DISCRIMINATOR: [u8; 8] =
+    [0xf8, 0xa1, 0x43, 0xb7, 0xbb, 0x6e, 0x41, 0xc6];
+
+/// sha256("event:PoolInitialized")[..8]
+pub const POOL_INITIALIZED_DISCRIMINATOR: [u8; 8] =
+    [0x64, 0x76, 0xad, 0x57, 0x0c, 0xc6, 0xfe, 0xe5];
+
+/// sha256("event:RewardBurned")[..8]
+pub const REWARD_BURNED_DISCRIMINATOR: [u8; 8] =
+    [0x2a, 0xe4, 0xfb, 0xa3, 0x72, 0x01, 0x5a, 0x3a];
+
+/// sha256("event:SyncedLamportsReconciled")[..8]
+pub const SYNCED_LAMPORTS_RECONCILED_DISCRIMINATOR: [u8; 8] =
+    [0x1a, 0x6a, 0x3a, 0x94, 0xda, 0x4c, 0xcc, 0x4c];
+
+/// sha256("event:StakeFreezeSet")[..8]
+pub const STAKE_FREEZE_SET_DISCRIMINATOR: [u8; 8] =
+    [0x34, 0xc2, 0x9b, 0x4d, 0xc8, 0x6b, 0xda, 0x3e];
+
 #[repr(u8)]
 pub enum RewardPayoutType {
     Claim = 0,
     Unstake = 1,
     AutoClaimStake = 2,
+    ClaimWrapped = 3,
 }
 
 /// Emit a structured RewardPayout event (81 bytes).
@@ -29,3 +61,149 @@ pub fn emit_reward_payout(
     data[80] = payout_type as u8;
     sol_log_data(&[&data]);
 }
+
+/// Emit a structured RewardBurned event (112 bytes) for the share of a
+/// claim sent to the pool's burn destination. The RewardPayout event of
+/// the same claim carries only what the claimer received.
+///
+/// Layout: 8 discriminator + 32 pool + 32 user + 32 destination + 8 amount
+pub fn emit_reward_burned(
+    pool: &Pubkey,
+    user: &Pubkey,
+    destination: &Pubkey,
+    amount_lamports: u64,
+) {
+    let mut data = [0u8; 112];
+    data[..8].copy_from_slice(&REWARD_BURNED_DISCRIMINATOR);
+    data[8..40].copy_from_slice(pool.as_ref());
+    data[40..72].copy_from_slice(user.as_ref());
+    data[72..104].copy_from_slice(destination.as_ref());
+    data[104..112].copy_from_slice(&amount_lamports.to_le_bytes());
+    sol_log_data(&[&data]);
+}
+
+/// Emit a structured StakeCompressed event carrying the full compressed
+/// position, which DecompressStake must present again (its account no
+/// longer exists to be read).
+///
+/// Layout: 8 discriminator + 32 pool + 4 leaf index + borsh(UserStake)
+pub fn emit_stake_compressed(pool: &Pubkey, leaf_index: u32, position: &[u8]) {
+    let mut data = Vec::with_capacity(44 + position.len());
+    data.extend_from_slice(&STAKE_COMPRESSED_DISCRIMINATOR);
+    data.extend_from_slice(pool.as_ref());
+    data.extend_from_slice(&leaf_index.to_le_bytes());
+    data.extend_from_slice(position);
+    sol_log_data(&[&data]);
+}
+
+/// Emit a structured PoolSynced event (128 bytes) for rewards distributed by
+/// SyncRewards. `depositor` is the sender of a direct transfer earlier in
+/// the transaction, or the default pubkey if none was detected.
+///
+/// Layout: 8 discriminator + 32 pool + 8 lamports distributed + 8 protocol
+/// fee + 16 acc_rps before + 16 acc_rps after + 8 base_time + 32 depositor
+pub fn emit_pool_synced(
+    pool: &Pubkey,
+    lamports: u64,
+    protocol_fee: u64,
+    old_acc_rps: u128,
+    new_acc_rps: u128,
+    base_time: i64,
+    depositor: &Pubkey,
+) {
+    let mut data = [0u8; 128];
+    data[..8].copy_from_slice(&POOL_SYNCED_DISCRIMINATOR);
+    data[8..40].copy_from_slice(pool.as_ref());
+    data[40..48].copy_from_slice(&lamports.to_le_bytes());
+    data[48..56].copy_from_slice(&protocol_fee.to_le_bytes());
+    data[56..72].copy_from_slice(&old_acc_rps.to_le_bytes());
+    data[72..88].copy_from_slice(&new_acc_rps.to_le_bytes());
+    data[88..96].copy_from_slice(&base_time.to_le_bytes());
+    data[96..128].copy_from_slice(depositor.as_ref());
+    sol_log_data(&[&data]);
+}
+
+/// Emit a structured PoolRebased event (136 bytes) for a SyncPool rebase.
+///
+/// Layout: 8 discriminator + 32 pool + 8 old base_time + 8 new base_time +
+/// 16 decay factor (WAD) + 32 old sum_stake_exp + 32 new sum_stake_exp
+pub fn emit_pool_rebased(
+    pool: &Pubkey,
+    old_base_time: i64,
+    new_base_time: i64,
+    decay_factor: u128,
+    old_sum_stake_exp: &U256,
+    new_sum_stake_exp: &U256,
+) {
+    let mut data = [0u8; 136];
+    data[..8].copy_from_slice(&POOL_REBASED_DISCRIMINATOR);
+    data[8..40].copy_from_slice(pool.as_ref());
+    data[40..48].copy_from_slice(&old_base_time.to_le_bytes());
+    data[48..56].copy_from_slice(&new_base_time.to_le_bytes());
+    data[56..72].copy_from_slice(&decay_factor.to_le_bytes());
+    data[72..104].copy_from_slice(&old_sum_stake_exp.to_le_bytes());
+    data[104..136].copy_from_slice(&new_sum_stake_exp.to_le_bytes());
+    sol_log_data(&[&data]);
+}
+
+/// Emit a structured PoolInitialized event (134 bytes) carrying the version
+/// and feature bitmap of the program that created the pool.
+///
+/// Layout: 8 discriminator + 32 pool + 32 mint + 32 authority + 8 pool_id +
+/// 8 tau_seconds + 6 version (3 x u16) + 8 features
+pub fn emit_pool_initialized(
+    pool: &Pubkey,
+    mint: &Pubkey,
+    authority: &Pubkey,
+    pool_id: u64,
+    tau_seconds: u64,
+) {
+    let mut data = [0u8; 134];
+    data[..8].copy_from_slice(&POOL_INITIALIZED_DISCRIMINATOR);
+    data[8..40].copy_from_slice(pool.as_ref());
+    data[40..72].copy_from_slice(mint.as_ref());
+    data[72..104].copy_from_slice(authority.as_ref());
+    data[104..112].copy_from_slice(&pool_id.to_le_bytes());
+    data[112..120].copy_from_slice(&tau_seconds.to_le_bytes());
+    for (i, part) in PROGRAM_VERSION.iter().enumerate() {
+        data[120 + 2 * i..122 + 2 * i].copy_from_slice(&part.to_le_bytes());
+    }
+    data[126..134].copy_from_slice(&PROGRAM_FEATURES.to_le_bytes());
+    sol_log_data(&[&data]);
+}
+
+/// Emit a structured SyncedLamportsReconciled event (64 bytes) for a
+/// ReconcileSyncedLamports that lowered last_synced_lamports.
+///
+/// Layout: 8 discriminator + 32 pool + 8 old last_synced_lamports + 8 new
+/// last_synced_lamports + 8 lamports removed
+pub fn emit_synced_lamports_reconciled(pool: &Pubkey, old: u64, new: u64) {
+    let mut data = [0u8; 64];
+    data[..8].copy_from_slice(&SYNCED_LAMPORTS_RECONCILED_DISCRIMINATOR);
+    data[8..40].copy_from_slice(pool.as_ref());
+    data[40..48].copy_from_slice(&old.to_le_bytes());
+    data[48..56].copy_from_slice(&new.to_le_bytes());
+    data[56..64].copy_from_slice(&(old - new).to_le_bytes());
+    sol_log_data(&[&data]);
+}
+
+/// Emit a structured StakeFreezeSet event (113 bytes) for a SetStakeFrozen.
+///
+/// Layout: 8 discriminator + 32 pool + 32 user stake + 32 owner + 1 frozen +
+/// 8 timestamp
+pub fn emit_stake_freeze_set(
+    pool: &Pubkey,
+    user_stake: &Pubkey,
+    owner: &Pubkey,
+    frozen: bool,
+    timestamp: i64,
+) {
+    let mut data = [0u8; 113];
+    data[..8].copy_from_slice(&STAKE_FREEZE_SET_DISCRIMINATOR);
+    data[8..40].copy_from_slice(pool.as_ref());
+    data[40..72].copy_from_slice(user_stake.as_ref());
+    data[72..104].copy_from_slice(owner.as_ref());
+    data[104] = frozen as u8;
+    data[105..113].copy_from_slice(&timestamp.to_le_bytes());
+    sol_log_data(&[&data]);
+}
@@ -0,0 +1,24 @@
+//! Guardian co-signature for positions with a guardian set
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+};
+
+use crate::{error::StakingError, state::UserStake};
+
+/// Take the guardian from `iter` and require its signature if the position
+/// has one. Positions without a guardian consume nothing.
+pub fn check_guardian_signature<'a: 'b, 'b, I>(user_stake: &UserStake, iter: &mut I) -> ProgramResult
+where
+    I: Iterator<Item = &'b AccountInfo<'a>>,
+{
+    if !user_stake.has_guardian() {
+        return Ok(());
+    }
+    let guardian_info = next_account_info(iter)?;
+    if *guardian_info.key != user_stake.guardian || !guardian_info.is_signer {
+        return Err(StakingError::GuardianSignatureRequired.into());
+    }
+    Ok(())
+}
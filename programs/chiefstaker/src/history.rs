@@ -0,0 +1,82 @@
+//! Weighted-stake checkpoints for positions with a WeightHistory account
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::StakingError,
+    math::calculate_user_weighted_stake,
+    state::{StakingPool, UserStake, WeightCheckpoint, WeightHistory},
+};
+
+/// Take the WeightHistory account from `iter` if the position has one.
+/// Positions without history consume nothing.
+pub fn next_weight_history<'a, 'b, I>(
+    user_stake: &UserStake,
+    iter: &mut I,
+) -> Result<Option<&'b AccountInfo<'a>>, ProgramError>
+where
+    I: Iterator<Item = &'b AccountInfo<'a>>,
+{
+    if !user_stake.has_weight_history {
+        return Ok(None);
+    }
+    next_account_info(iter).map(Some)
+}
+
+/// Checkpoint of `user_stake` at `current_time` (user_stake must already be
+/// synced to the pool's base_time)
+pub fn checkpoint_of(
+    pool: &StakingPool,
+    user_stake: &UserStake,
+    current_time: i64,
+) -> Result<WeightCheckpoint, StakingError> {
+    let weighted_stake = calculate_user_weighted_stake(
+        user_stake.amount,
+        user_stake.exp_start_factor,
+        current_time,
+        pool.base_time,
+        pool.tau_seconds,
+    )?;
+    Ok(WeightCheckpoint {
+        timestamp: current_time,
+        amount: user_stake.amount,
+        weighted_stake,
+    })
+}
+
+/// Validate `history_info` as the position's WeightHistory and append the
+/// position's state at `current_time`
+pub fn record_checkpoint(
+    program_id: &Pubkey,
+    history_info: &AccountInfo,
+    pool_key: &Pubkey,
+    pool: &StakingPool,
+    user_stake: &UserStake,
+    current_time: i64,
+) -> ProgramResult {
+    if history_info.owner != program_id {
+        return Err(StakingError::InvalidAccountOwner.into());
+    }
+    let (expected_history, _) = WeightHistory::derive_pda(pool_key, &user_stake.owner, program_id);
+    if *history_info.key != expected_history {
+        return Err(StakingError::InvalidPDA.into());
+    }
+
+    let mut data = history_info.try_borrow_mut_data()?;
+    let header = data
+        .get(..WeightHistory::HEADER_LEN)
+        .ok_or(StakingError::AccountDataTooSmall)?;
+    let mut history = WeightHistory::try_from_slice(header)?;
+    if !history.is_initialized() {
+        return Err(StakingError::NotInitialized.into());
+    }
+
+    let checkpoint = checkpoint_of(pool, user_stake, current_time)?;
+    history.push(&mut data, &checkpoint)
+}
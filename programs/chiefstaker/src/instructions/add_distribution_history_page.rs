@@ -0,0 +1,69 @@
+//! Add distribution history page instruction (permissionless)
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    distribution_history::{create_history_page, load_history_page},
+    error::StakingError,
+    loaders::load_pool,
+    state::DistributionHistory,
+};
+
+/// Create the page that receives the pool's next distribution checkpoint.
+/// SyncRewards can't pay for pages, so once a page fills up it defers
+/// distributions until someone (anyone) creates the next one; Deposit
+/// creates it itself at the depositor's expense. A no-op if the page
+/// already exists.
+///
+/// Accounts:
+/// 0. `[]` Pool account
+/// 1. `[writable]` Current distribution history page
+///    (PDA: ["distribution_history", pool, page u32 LE])
+/// 2. `[writable, signer]` Payer (pays rent)
+/// 3. `[]` System program
+pub fn process_add_distribution_history_page(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let page_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Validate payer is signer
+    if !payer_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let pool = load_pool(program_id, pool_info)?;
+
+    if !pool.has_distribution_history {
+        return Err(StakingError::NotInitialized.into());
+    }
+
+    let page = DistributionHistory::page_of(pool.distribution_checkpoints);
+    if load_history_page(program_id, page_info, pool_info.key, &pool)?.is_some() {
+        msg!("Distribution history page {} already exists", page);
+        return Ok(());
+    }
+    create_history_page(
+        program_id,
+        page_info,
+        pool_info.key,
+        page,
+        payer_info,
+        system_program_info,
+    )?;
+
+    msg!("Distribution history page {} created", page);
+
+    Ok(())
+}
@@ -0,0 +1,134 @@
+//! Read-only pool health check for monitors, returned through return data
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program::set_return_data,
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar::Sysvar,
+};
+use spl_token_2022::extension::StateWithExtensions;
+
+use crate::{
+    error::StakingError,
+    loaders::load_pool,
+    math::U256,
+    state::StakingPool,
+};
+
+/// Token vault holds less than total_staked
+pub const HEALTH_VAULT_UNDERFUNDED: u32 = 1 << 0;
+/// last_synced_lamports exceeds the pool's lamports above rent
+pub const HEALTH_SYNCED_EXCEEDS_BALANCE: u32 = 1 << 1;
+/// total_reward_debt exceeds total_staked * acc_reward_per_weighted_share
+pub const HEALTH_REWARD_DEBT_EXCEEDS_ACCUMULATED: u32 = 1 << 2;
+
+/// Pool health report, Borsh-encoded into return data
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PoolHealth {
+    /// Bitmask of `HEALTH_*` violations; 0 when healthy
+    pub violations: u32,
+    /// Token vault balance (raw units)
+    pub vault_amount: u64,
+    /// Pool lamports above the rent-exempt minimum
+    pub available_lamports: u64,
+}
+
+/// Check the pool's balance invariants and publish a [`PoolHealth`] via
+/// `set_return_data`. Violations are reported, not raised, so a monitor gets
+/// every failing check from a single simulation. Does not modify any account.
+///
+/// Accounts:
+/// 0. `[]` Pool account
+/// 1. `[]` Token vault
+pub fn process_assert_pool_healthy(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let token_vault_info = next_account_info(account_info_iter)?;
+
+    // Load and validate pool
+    let pool = load_pool(program_id, pool_info)?;
+
+    // Verify token vault
+    if *token_vault_info.key != pool.token_vault {
+        return Err(StakingError::InvalidTokenVault.into());
+    }
+    let vault_amount = {
+        let data = token_vault_info.try_borrow_data()?;
+        StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?
+            .base
+            .amount
+    };
+
+    let rent_exempt_minimum = Rent::get()?.minimum_balance(pool_info.data_len());
+    let available_lamports = pool_info.lamports().saturating_sub(rent_exempt_minimum);
+
+    let health = pool_health(&pool, vault_amount, available_lamports);
+    set_return_data(&borsh::to_vec(&health)?);
+
+    Ok(())
+}
+
+/// Evaluate every health check against the given balances
+pub fn pool_health(pool: &StakingPool, vault_amount: u64, available_lamports: u64) -> PoolHealth {
+    let mut violations = 0;
+    if (vault_amount as u128) < pool.total_staked {
+        violations |= HEALTH_VAULT_UNDERFUNDED;
+    }
+    if pool.last_synced_lamports > available_lamports {
+        violations |= HEALTH_SYNCED_EXCEEDS_BALANCE;
+    }
+    // reward_debt = amount * acc_rps at stake time, so the pool total can't
+    // exceed what total_staked has accumulated at the current acc_rps
+    let accumulated = U256::from_u128(pool.total_staked)
+        .checked_mul(U256::from_u128(pool.acc_reward_per_weighted_share));
+    if accumulated.is_some_and(|accumulated| U256::from_u128(pool.total_reward_debt) > accumulated) {
+        violations |= HEALTH_REWARD_DEBT_EXCEEDS_ACCUMULATED;
+    }
+    PoolHealth {
+        violations,
+        vault_amount,
+        available_lamports,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::WAD;
+
+    #[test]
+    fn test_pool_health() {
+        let mut pool = StakingPool::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            2592000,
+            0,
+            255,
+        );
+        pool.total_staked = 1_000;
+        pool.acc_reward_per_weighted_share = WAD;
+        pool.total_reward_debt = 1_000 * WAD;
+        pool.last_synced_lamports = 500;
+        assert_eq!(pool_health(&pool, 1_000, 500).violations, 0);
+
+        assert_eq!(pool_health(&pool, 999, 500).violations, HEALTH_VAULT_UNDERFUNDED);
+        assert_eq!(pool_health(&pool, 1_000, 499).violations, HEALTH_SYNCED_EXCEEDS_BALANCE);
+
+        pool.total_reward_debt += 1;
+        assert_eq!(
+            pool_health(&pool, 0, 0).violations,
+            HEALTH_VAULT_UNDERFUNDED
+                | HEALTH_SYNCED_EXCEEDS_BALANCE
+                | HEALTH_REWARD_DEBT_EXCEEDS_ACCUMULATED
+        );
+    }
+}
@@ -0,0 +1,56 @@
+//! Read-only check of the program's upgrade authority
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::StakingError,
+    state::ProgramConfig,
+    upgrade::current_upgrade_authority,
+};
+
+/// Fail with UpgradeAuthorityMismatch unless the program's current upgrade
+/// authority is the one recorded in the ProgramConfig. Meant to be
+/// simulated by monitors or prepended to transactions that should only
+/// land while the deploy key is under the advertised governance.
+///
+/// Accounts:
+/// 0. `[]` Program config (PDA: ["program_config"])
+/// 1. `[]` ProgramData account of this program
+pub fn process_assert_upgrade_authority(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let config_info = next_account_info(account_info_iter)?;
+    let programdata_info = next_account_info(account_info_iter)?;
+
+    // Load and validate config
+    if config_info.owner != program_id {
+        return Err(StakingError::InvalidAccountOwner.into());
+    }
+    let (expected_config, _) = ProgramConfig::derive_pda(program_id);
+    if *config_info.key != expected_config {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    let config = ProgramConfig::try_from_slice(&config_info.try_borrow_data()?)?;
+    if !config.is_initialized() {
+        return Err(StakingError::NotInitialized.into());
+    }
+
+    let current = current_upgrade_authority(program_id, programdata_info)?;
+    if current != config.upgrade_authority {
+        msg!("Upgrade authority is {}, expected {}", current, config.upgrade_authority);
+        return Err(StakingError::UpgradeAuthorityMismatch.into());
+    }
+
+    msg!("Upgrade authority matches {}", current);
+
+    Ok(())
+}
@@ -0,0 +1,541 @@
+//! Stake, unstake, claim and reward instructions of AgeBuckets pools
+//!
+//! The entrypoint routes Stake, Unstake, UnstakeAll, ClaimRewards,
+//! DepositRewards and SyncRewards here when the pool was created by
+//! InitializeBucketedPool. Their account lists end with the pool's age
+//! buckets (and, for payouts, the history pages of the position's epochs)
+//! instead of the exponential pools' optional accounts.
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+use spl_token_2022::extension::StateWithExtensions;
+
+use crate::{
+    age_buckets::{
+        distribute_new_rewards, earned_per_token, entitlement_wad, entry_epoch,
+        load_age_buckets, next_epoch_starts,
+    },
+    error::{ErrorContext, StakingError},
+    events::{emit_reward_payout, RewardPayoutType},
+    loaders::{load_bucketed_pool, load_user_stake},
+    math::WAD,
+    sponsor::create_stake_account,
+    state::{StakeAction, StakingPool, UserStake, STAKE_SEED},
+    time::pool_time,
+};
+
+use super::add_total_staked;
+
+/// Pay what a position is owed at `earned` per token, as far as the pool's
+/// claimable lamports go. Settles the position and pool accounting and
+/// returns the lamports to transfer.
+fn settle_payout(
+    pool: &mut StakingPool,
+    user_stake: &mut UserStake,
+    pool_info: &AccountInfo,
+    earned: u128,
+) -> Result<u64, ProgramError> {
+    let entitled = entitlement_wad(user_stake.amount, earned)?;
+    let pending = entitled.saturating_sub(user_stake.claimed_rewards_wad);
+
+    let rent_exempt_minimum = Rent::get()?.minimum_balance(pool_info.data_len());
+    let available = pool.claimable_lamports(pool_info.lamports(), rent_exempt_minimum, false);
+    let paid = (pending / WAD).min(available as u128) as u64;
+
+    user_stake.claimed_rewards_wad = user_stake
+        .claimed_rewards_wad
+        .checked_add(paid as u128 * WAD)
+        .ok_or(StakingError::MathOverflow)?;
+    user_stake.total_rewards_claimed = user_stake.total_rewards_claimed.saturating_add(paid);
+    pool.last_synced_lamports = pool.last_synced_lamports.saturating_sub(paid);
+    Ok(paid)
+}
+
+/// Stake tokens into an AgeBuckets pool. The stake enters the current
+/// epoch's bucket, so the buckets must be rolled to it (RollAgeBuckets).
+/// All of a position's tokens share one age: a position can only be topped
+/// up in the epoch it entered, or once fully unstaked.
+///
+/// Accounts:
+/// 0-7. Same as Stake
+/// 8. `[writable]` Age buckets (PDA: ["age_buckets", pool])
+pub fn process_stake_bucketed(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+) -> ProgramResult {
+    if amount == 0 {
+        return Err(StakingError::ZeroAmount.into());
+    }
+
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let user_stake_info = next_account_info(account_info_iter)?;
+    let token_vault_info = next_account_info(account_info_iter)?;
+    let user_token_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let user_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let buckets_info = next_account_info(account_info_iter)?;
+
+    // Validate Token 2022 program
+    if *token_program_info.key != spl_token_2022::id() {
+        return Err(StakingError::InvalidTokenProgram.into());
+    }
+
+    // Validate user is signer
+    if !user_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    let mut pool = load_bucketed_pool(program_id, pool_info)?;
+
+    // Verify mint matches pool
+    if pool.mint != *mint_info.key {
+        return Err(StakingError::InvalidPoolMint.into());
+    }
+
+    // Verify token vault
+    if pool.token_vault != *token_vault_info.key {
+        return Err(StakingError::InvalidTokenVault.into());
+    }
+
+    let mut buckets = load_age_buckets(program_id, buckets_info, pool_info.key)?;
+
+    // Verify user stake PDA
+    let (expected_stake, stake_bump) =
+        UserStake::derive_pda(pool_info.key, user_info.key, program_id);
+    if *user_stake_info.key != expected_stake {
+        return Err(StakingError::InvalidPDA.into());
+    }
+
+    let clock = Clock::get()?;
+    let current_time = pool_time(&pool)?;
+
+    // Stake entering before a roll would land in an older epoch's bucket
+    if !buckets.is_rolled(current_time) {
+        return Err(StakingError::AgeBucketsNotRolled.into());
+    }
+
+    // What the current epoch's entrants have earned so far is not theirs
+    let earned = earned_per_token(&buckets, buckets.epoch, &[])?;
+
+    let mut user_stake = if user_stake_info.data_is_empty() {
+        // Check minimum stake amount
+        if pool.min_stake_amount > 0 && amount < pool.min_stake_amount {
+            return Err(StakingError::BelowMinimumStake
+                .with_context(ErrorContext::MinimumAmount(pool.min_stake_amount)));
+        }
+
+        let stake_seeds: &[&[u8]] = &[
+            STAKE_SEED,
+            pool_info.key.as_ref(),
+            user_info.key.as_ref(),
+            &[stake_bump],
+        ];
+        create_stake_account(
+            program_id,
+            user_info,
+            user_stake_info,
+            system_program_info,
+            None,
+            stake_seeds,
+        )?;
+
+        let mut user_stake = UserStake::new(
+            *user_info.key,
+            *pool_info.key,
+            amount,
+            current_time,
+            0,
+            stake_bump,
+            pool.base_time,
+        );
+        user_stake.record_action(StakeAction::Stake, clock.slot)?;
+        user_stake.claimed_rewards_wad = entitlement_wad(amount, earned)?;
+        user_stake
+    } else {
+        // Realloc legacy accounts to current size (payer = user)
+        UserStake::maybe_realloc(user_stake_info, user_info, Some(system_program_info))?;
+
+        let mut user_stake =
+            load_user_stake(program_id, user_stake_info, pool_info.key, user_info.key)?;
+        user_stake.check_not_frozen()?;
+
+        // Block re-staking in the same slot as an unstake
+        user_stake.record_action(StakeAction::Stake, clock.slot)?;
+
+        if user_stake.amount == 0 {
+            // A fully unstaked position (paid out in full) starts over
+            user_stake.stake_time = current_time;
+            user_stake.claimed_rewards_wad = 0;
+        } else if entry_epoch(&buckets, &user_stake) != buckets.epoch {
+            return Err(StakingError::AgedPositionTopUp.into());
+        }
+
+        // Check minimum stake amount on new total
+        let new_total = user_stake
+            .amount
+            .checked_add(amount)
+            .ok_or(StakingError::MathOverflow)?;
+        if pool.min_stake_amount > 0 && new_total < pool.min_stake_amount {
+            let min_top_up = pool.min_stake_amount - user_stake.amount;
+            return Err(StakingError::BelowMinimumStake
+                .with_context(ErrorContext::MinimumAmount(min_top_up)));
+        }
+
+        user_stake.claimed_rewards_wad = user_stake
+            .claimed_rewards_wad
+            .checked_add(entitlement_wad(amount, earned)?)
+            .ok_or(StakingError::MathOverflow)?;
+        user_stake.amount = new_total;
+        user_stake.last_stake_time = current_time;
+        user_stake.total_deposited = user_stake.total_deposited.saturating_add(amount);
+        user_stake
+    };
+
+    buckets.amounts[0] = buckets.amounts[0]
+        .checked_add(amount)
+        .ok_or(StakingError::MathOverflow)?;
+    add_total_staked(&mut pool, amount)?;
+
+    // Write every account before the token CPI
+    user_stake.save(user_stake_info)?;
+    pool.save(pool_info)?;
+    buckets.save(buckets_info)?;
+
+    // Transfer tokens from user to vault
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+    let decimals = mint.base.decimals;
+    drop(mint_data);
+
+    invoke(
+        &spl_token_2022::instruction::transfer_checked(
+            &spl_token_2022::id(),
+            user_token_info.key,
+            mint_info.key,
+            token_vault_info.key,
+            user_info.key,
+            &[],
+            amount,
+            decimals,
+        )?,
+        &[
+            user_token_info.clone(),
+            mint_info.clone(),
+            token_vault_info.clone(),
+            user_info.clone(),
+        ],
+    )?;
+
+    msg!("Staked {} tokens in age bucket epoch {}", amount, buckets.epoch);
+
+    Ok(())
+}
+
+/// Unstake tokens from an AgeBuckets pool, paying the position's rewards.
+/// `amount` None (UnstakeAll) unstakes the whole position as of execution.
+/// The buckets need not be rolled.
+///
+/// Accounts:
+/// 0-6. Same as Unstake
+/// 7. `[writable]` Age buckets
+/// 8. `[]` Age bucket history pages of the position's first epochs
+///    (none in the epoch it entered; two across a page boundary)
+pub fn process_unstake_bucketed(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: Option<u64>,
+) -> ProgramResult {
+    if amount == Some(0) {
+        return Err(StakingError::ZeroAmount.into());
+    }
+
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let user_stake_info = next_account_info(account_info_iter)?;
+    let token_vault_info = next_account_info(account_info_iter)?;
+    let user_token_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let user_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let buckets_info = next_account_info(account_info_iter)?;
+
+    // Validate Token 2022 program
+    if *token_program_info.key != spl_token_2022::id() {
+        return Err(StakingError::InvalidTokenProgram.into());
+    }
+
+    // Validate user is signer
+    if !user_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    let mut pool = load_bucketed_pool(program_id, pool_info)?;
+
+    // Verify mint matches pool
+    if pool.mint != *mint_info.key {
+        return Err(StakingError::InvalidPoolMint.into());
+    }
+
+    // Verify token vault
+    if pool.token_vault != *token_vault_info.key {
+        return Err(StakingError::InvalidTokenVault.into());
+    }
+
+    let mut user_stake =
+        load_user_stake(program_id, user_stake_info, pool_info.key, user_info.key)?;
+    let mut buckets = load_age_buckets(program_id, buckets_info, pool_info.key)?;
+
+    // UnstakeAll takes whatever is staked now, not a client-cached amount
+    let amount = amount.unwrap_or(user_stake.amount);
+    if amount == 0 {
+        return Err(StakingError::ZeroAmount.into());
+    }
+
+    // Check sufficient balance
+    if user_stake.amount < amount {
+        return Err(StakingError::InsufficientStakeBalance
+            .with_context(ErrorContext::AvailableAmount(user_stake.amount)));
+    }
+
+    let clock = Clock::get()?;
+
+    // Block unstaking in the same slot as a stake
+    user_stake.record_action(StakeAction::Unstake, clock.slot)?;
+
+    let entry = entry_epoch(&buckets, &user_stake);
+    let starts = next_epoch_starts(program_id, pool_info.key, &buckets, entry, account_info_iter)?;
+    let earned = earned_per_token(&buckets, entry, &starts)?;
+
+    // Pay out first; the remaining stake keeps only what couldn't be paid
+    let reward = settle_payout(&mut pool, &mut user_stake, pool_info, earned)?;
+    let unpaid = entitlement_wad(user_stake.amount, earned)?
+        .saturating_sub(user_stake.claimed_rewards_wad);
+
+    let bucket = buckets.bucket_of(entry);
+    buckets.amounts[bucket] = buckets.amounts[bucket]
+        .checked_sub(amount)
+        .ok_or(StakingError::MathUnderflow)?;
+    pool.total_staked = pool
+        .total_staked
+        .checked_sub(amount as u128)
+        .ok_or(StakingError::MathUnderflow)?;
+
+    user_stake.amount -= amount;
+    user_stake.total_withdrawn = user_stake.total_withdrawn.saturating_add(amount);
+    // Sub-lamport dust beyond what the remaining stake is entitled to is
+    // forfeited with the tokens
+    user_stake.claimed_rewards_wad =
+        entitlement_wad(user_stake.amount, earned)?.saturating_sub(unpaid);
+
+    // Save states (before CPI)
+    pool.save(pool_info)?;
+    user_stake.save(user_stake_info)?;
+    buckets.save(buckets_info)?;
+
+    // Transfer tokens from vault to user (CPI)
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+    let decimals = mint.base.decimals;
+    drop(mint_data);
+
+    let pool_id_bytes = pool.pool_id.to_le_bytes();
+    let pool_bump = [pool.bump];
+    let pool_seeds: &[&[u8]] = &pool.signer_seeds(&pool_id_bytes, &pool_bump);
+
+    invoke_signed(
+        &spl_token_2022::instruction::transfer_checked(
+            &spl_token_2022::id(),
+            token_vault_info.key,
+            mint_info.key,
+            user_token_info.key,
+            pool_info.key,
+            &[],
+            amount,
+            decimals,
+        )?,
+        &[
+            token_vault_info.clone(),
+            mint_info.clone(),
+            user_token_info.clone(),
+            pool_info.clone(),
+        ],
+        &[pool_seeds],
+    )?;
+
+    // Transfer SOL rewards AFTER token CPI to avoid CPI balance check failure
+    if reward > 0 {
+        **pool_info.try_borrow_mut_lamports()? -= reward;
+        **user_info.try_borrow_mut_lamports()? += reward;
+        msg!("Claimed {} lamports in rewards", reward);
+        emit_reward_payout(pool_info.key, user_info.key, reward, RewardPayoutType::Unstake);
+    }
+
+    msg!("Unstaked {} tokens", amount);
+
+    Ok(())
+}
+
+/// Claim a position's rewards from an AgeBuckets pool. The buckets need
+/// not be rolled.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable]` User stake account
+/// 2. `[writable, signer]` User/owner
+/// 3. `[]` Age buckets
+/// 4. `[]` Age bucket history pages of the position's first epochs
+///    (none in the epoch it entered; two across a page boundary)
+pub fn process_claim_bucketed(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let user_stake_info = next_account_info(account_info_iter)?;
+    let user_info = next_account_info(account_info_iter)?;
+    let buckets_info = next_account_info(account_info_iter)?;
+
+    // Validate user is signer
+    if !user_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    let mut pool = load_bucketed_pool(program_id, pool_info)?;
+    let mut user_stake =
+        load_user_stake(program_id, user_stake_info, pool_info.key, user_info.key)?;
+    user_stake.check_not_frozen()?;
+    let buckets = load_age_buckets(program_id, buckets_info, pool_info.key)?;
+
+    if user_stake.amount == 0 {
+        msg!("No rewards to claim");
+        return Ok(());
+    }
+
+    let entry = entry_epoch(&buckets, &user_stake);
+    let starts = next_epoch_starts(program_id, pool_info.key, &buckets, entry, account_info_iter)?;
+    let earned = earned_per_token(&buckets, entry, &starts)?;
+
+    let paid = settle_payout(&mut pool, &mut user_stake, pool_info, earned)?;
+    if paid == 0 {
+        msg!("No pending rewards to claim");
+        return Ok(());
+    }
+
+    pool.save(pool_info)?;
+    user_stake.save(user_stake_info)?;
+
+    **pool_info.try_borrow_mut_lamports()? -= paid;
+    **user_info.try_borrow_mut_lamports()? += paid;
+
+    msg!("Claimed {} lamports in rewards", paid);
+    emit_reward_payout(pool_info.key, user_info.key, paid, RewardPayoutType::Claim);
+
+    Ok(())
+}
+
+/// Deposit SOL rewards into an AgeBuckets pool (permissionless). They are
+/// distributed over the bucket weights once the buckets are rolled to the
+/// current epoch and hold any weight; until then they stay pending.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account (receives SOL)
+/// 1. `[writable, signer]` Depositor
+/// 2. `[]` System program
+/// 3. `[writable]` Age buckets
+pub fn process_deposit_bucketed(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+) -> ProgramResult {
+    if amount == 0 {
+        return Err(StakingError::ZeroAmount.into());
+    }
+
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let depositor_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let buckets_info = next_account_info(account_info_iter)?;
+
+    // Validate depositor is signer
+    if !depositor_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    let mut pool = load_bucketed_pool(program_id, pool_info)?;
+    let mut buckets = load_age_buckets(program_id, buckets_info, pool_info.key)?;
+
+    invoke(
+        &system_instruction::transfer(depositor_info.key, pool_info.key, amount),
+        &[
+            depositor_info.clone(),
+            pool_info.clone(),
+            system_program_info.clone(),
+        ],
+    )?;
+
+    let current_time = pool_time(&pool)?;
+    let distributed = distribute_new_rewards(&mut pool, &mut buckets, pool_info, current_time)?;
+    if distributed == 0 {
+        msg!("Deposited {} lamports (pending distribution)", amount);
+        return Ok(());
+    }
+
+    pool.save(pool_info)?;
+    buckets.save(buckets_info)?;
+
+    msg!("Deposited {} lamports, distributed {}", amount, distributed);
+
+    Ok(())
+}
+
+/// Distribute SOL sent directly to an AgeBuckets pool (permissionless
+/// crank), under the same conditions as a deposit.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable]` Age buckets
+pub fn process_sync_bucketed(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let buckets_info = next_account_info(account_info_iter)?;
+
+    let mut pool = load_bucketed_pool(program_id, pool_info)?;
+    let mut buckets = load_age_buckets(program_id, buckets_info, pool_info.key)?;
+
+    let current_time = pool_time(&pool)?;
+    let distributed = distribute_new_rewards(&mut pool, &mut buckets, pool_info, current_time)?;
+    if distributed == 0 {
+        msg!("No rewards to distribute");
+        return Ok(());
+    }
+
+    pool.save(pool_info)?;
+    buckets.save(buckets_info)?;
+
+    msg!("Distributed {} lamports over the age buckets", distributed);
+
+    Ok(())
+}
@@ -1,6 +1,5 @@
 //! Cancel unstake request instruction
 
-use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
@@ -10,7 +9,9 @@ use solana_program::{
 
 use crate::{
     error::StakingError,
-    state::{StakingPool, UserStake},
+    loaders::{load_pool, load_user_stake},
+    state::UserStake,
+    time::pool_time,
 };
 
 /// Cancel a pending unstake request
@@ -35,19 +36,7 @@ pub fn process_cancel_unstake_request(
     }
 
     // Load and validate pool
-    if pool_info.owner != program_id {
-        return Err(StakingError::InvalidAccountOwner.into());
-    }
-    let pool = StakingPool::try_from_slice(&pool_info.try_borrow_data()?)?;
-    if !pool.is_initialized() {
-        return Err(StakingError::NotInitialized.into());
-    }
-
-    // Verify pool PDA
-    let (expected_pool, _) = StakingPool::derive_pda(&pool.mint, program_id);
-    if *pool_info.key != expected_pool {
-        return Err(StakingError::InvalidPDA.into());
-    }
+    let pool = load_pool(program_id, pool_info)?;
 
     // Realloc legacy accounts to current size (payer = user)
     // System program is optional trailing account, only needed for legacy accounts
@@ -55,34 +44,14 @@ pub fn process_cancel_unstake_request(
     UserStake::maybe_realloc(user_stake_info, user_info, system_program_info)?;
 
     // Load and validate user stake
-    if user_stake_info.owner != program_id {
-        return Err(StakingError::InvalidAccountOwner.into());
-    }
-    let mut user_stake = UserStake::try_from_slice(&user_stake_info.try_borrow_data()?)?;
-    if !user_stake.is_initialized() {
-        return Err(StakingError::NotInitialized.into());
-    }
-
-    // Verify ownership
-    if user_stake.owner != *user_info.key {
-        return Err(StakingError::InvalidOwner.into());
-    }
-    if user_stake.pool != *pool_info.key {
-        return Err(StakingError::InvalidPool.into());
-    }
-
-    // Verify user stake PDA
-    let (expected_stake, _) =
-        UserStake::derive_pda(pool_info.key, user_info.key, program_id);
-    if *user_stake_info.key != expected_stake {
-        return Err(StakingError::InvalidPDA.into());
-    }
+    let mut user_stake =
+        load_user_stake(program_id, user_stake_info, pool_info.key, user_info.key)?;
 
     // Lazily adjust exp_start_factor if pool has been rebased
     user_stake.sync_to_pool(&pool)?;
 
-    // Check there is a pending request
-    if !user_stake.has_pending_unstake_request() {
+    // Check there is a pending request (a lapsed one no longer counts)
+    if !user_stake.has_pending_unstake_request(&pool, pool_time(&pool)?) {
         return Err(StakingError::NoPendingUnstakeRequest.into());
     }
 
@@ -93,8 +62,7 @@ pub fn process_cancel_unstake_request(
     user_stake.unstake_request_time = 0;
 
     // Save user stake
-    let mut stake_data = user_stake_info.try_borrow_mut_data()?;
-    user_stake.serialize(&mut &mut stake_data[..])?;
+    user_stake.save(user_stake_info)?;
 
     msg!("Cancelled unstake request for {} tokens", cancelled_amount);
 
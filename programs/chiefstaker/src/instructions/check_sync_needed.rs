@@ -0,0 +1,70 @@
+//! Keeper poll: report which cranks a pool needs, via return data
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program::set_return_data,
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar::Sysvar,
+};
+
+use crate::{error::StakingError, loaders::load_pool, state::StakingPool, time::pool_time};
+
+/// sum_stake_exp is above REBASE_THRESHOLD; SyncPool is due
+pub const SYNC_FLAG_NEEDS_REBASE: u8 = 1 << 0;
+/// (now - base_time) / tau is past the pool's rebase threshold (by default
+/// near MAX_EXP_INPUT); SyncPool is due
+pub const SYNC_FLAG_RATIO_NEAR_LIMIT: u8 = 1 << 1;
+/// Undistributed lamports exceed the caller's threshold; SyncRewards
+pub const SYNC_FLAG_UNDISTRIBUTED: u8 = 1 << 2;
+
+/// Return a one-byte bitflag (`SYNC_FLAG_*`) describing pending maintenance.
+/// Intended for simulation; does not modify any account.
+///
+/// Accounts:
+/// 0. `[]` Pool account
+pub fn process_check_sync_needed(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    min_undistributed_lamports: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let pool_info = next_account_info(account_info_iter)?;
+
+    // Load and validate pool
+    let pool = load_pool(program_id, pool_info)?;
+
+    let rent_exempt_minimum = Rent::get()?.minimum_balance(pool_info.data_len());
+    let undistributed = pool.undistributed_lamports(pool_info.lamports(), rent_exempt_minimum);
+
+    let flags = sync_flags(
+        &pool,
+        pool_time(&pool)?,
+        undistributed,
+        min_undistributed_lamports,
+    )?;
+    set_return_data(&[flags]);
+
+    Ok(())
+}
+
+/// Compute the `SYNC_FLAG_*` bitflag at `current_time`
+pub fn sync_flags(
+    pool: &StakingPool,
+    current_time: i64,
+    undistributed_lamports: u64,
+    min_undistributed_lamports: u64,
+) -> Result<u8, StakingError> {
+    let mut flags = 0;
+    if pool.get_sum_stake_exp().needs_rebase() {
+        flags |= SYNC_FLAG_NEEDS_REBASE;
+    }
+    if pool.exp_input_wad(current_time)? > pool.rebase_threshold_wad() {
+        flags |= SYNC_FLAG_RATIO_NEAR_LIMIT;
+    }
+    if undistributed_lamports > min_undistributed_lamports {
+        flags |= SYNC_FLAG_UNDISTRIBUTED;
+    }
+    Ok(flags)
+}
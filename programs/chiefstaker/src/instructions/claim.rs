@@ -1,92 +1,214 @@
 //! Claim rewards instruction
 
-use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
-    clock::Clock,
     entrypoint::ProgramResult,
     msg,
+    program::invoke,
+    program_error::ProgramError,
     pubkey::Pubkey,
     rent::Rent,
     sysvar::Sysvar,
 };
 
+use spl_token_2022::extension::StateWithExtensions;
+
 use crate::{
-    error::StakingError,
-    events::{emit_reward_payout, RewardPayoutType},
-    math::{calculate_user_weighted_stake, wad_div, wad_mul, WAD},
+    error::{ErrorContext, StakingError},
+    events::{emit_reward_burned, emit_reward_payout, RewardPayoutType},
+    loaders::{load_pool, load_user_stake},
+    math::{calculate_user_weighted_stake, WAD},
+    rebate::{next_gas_rebate_vault, pay_gas_rebate},
+    recipient::{next_reward_payee, RewardPayee},
+    session::verify_claim_session,
     state::{StakingPool, UserStake},
+    time::pool_time,
 };
 
-/// Claim accumulated SOL rewards
+/// Claim accumulated SOL rewards. On pools with claim vesting, only the
+/// vested part of each distribution (and of the position's vesting
+/// fragment) is claimable.
 ///
 /// Accounts:
 /// 0. `[writable]` Pool account (holds SOL rewards)
 /// 1. `[writable]` User stake account
 /// 2. `[writable, signer]` User/owner
+/// 3. `[]` Payout split, then `[writable]` each of its destinations in
+///    order (positions with a payout split only); otherwise
+///    `[writable]` Reward recipient (positions with a declared recipient only)
+/// 4. `[writable]` Gas rebate vault (pools with claim rebates only); pays
+///    the owner a fixed rebate on claims of at least that size
+/// 5. `[writable]` Burn destination (pools with a claim burn only); takes
+///    claim_burn_bps of the claim
+/// 6. `[]` (optional) System program (legacy account reallocation)
 pub fn process_claim_rewards(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
+) -> ProgramResult {
+    claim_rewards(program_id, accounts, false, None)
+}
+
+/// Claim accumulated SOL rewards as wrapped SOL: the pool credits the
+/// lamports to a native token account and syncs it, for owners that can't
+/// accept raw lamport credits. Any declared reward recipient or payout
+/// split is not used.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account (holds SOL rewards)
+/// 1. `[writable]` User stake account
+/// 2. `[writable, signer]` User/owner
+/// 3. `[writable]` Native (wSOL) token account receiving the rewards
+/// 4. `[]` Token program owning it (SPL Token or Token 2022)
+/// 5. `[writable]` Gas rebate vault (pools with claim rebates only); the
+///    rebate goes to the owner as plain lamports
+/// 6. `[writable]` Burn destination (pools with a claim burn only)
+/// 7. `[]` (optional) System program (legacy account reallocation)
+pub fn process_claim_rewards_wrapped(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    claim_rewards(program_id, accounts, true, None)
+}
+
+/// Claim accumulated SOL rewards with a session key the owner delegated
+/// to off-chain (see crate::session): the session key signs instead of the
+/// owner, and an Ed25519 program instruction earlier in the transaction
+/// must verify the owner's signature of the session message. Rewards are
+/// paid exactly as ClaimRewards pays them, so a leaked session key can only
+/// trigger claims to the owner's own destinations, until `expires_at`.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account (holds SOL rewards)
+/// 1. `[writable]` User stake account
+/// 2. `[writable]` Owner (receives the rewards and any gas rebate)
+/// 3. `[writable, signer]` Session key (pays legacy account reallocation)
+/// 4. `[]` Instructions sysvar
+/// 5. `[]` Payout split, then `[writable]` each of its destinations in
+///    order (positions with a payout split only); otherwise
+///    `[writable]` Reward recipient (positions with a declared recipient only)
+/// 6. `[writable]` Gas rebate vault (pools with claim rebates only)
+/// 7. `[writable]` Burn destination (pools with a claim burn only)
+/// 8. `[]` (optional) System program (legacy account reallocation)
+pub fn process_claim_with_session(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    expires_at: i64,
+) -> ProgramResult {
+    claim_rewards(program_id, accounts, false, Some(expires_at))
+}
+
+/// Check that `wsol_info` is a native token account of `token_program_info`
+fn validate_wsol_account(wsol_info: &AccountInfo, token_program_info: &AccountInfo) -> ProgramResult {
+    spl_token_2022::check_spl_token_program_account(token_program_info.key)?;
+    if wsol_info.owner != token_program_info.key {
+        return Err(StakingError::InvalidAccountOwner.into());
+    }
+    let data = wsol_info.try_borrow_data()?;
+    let account = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?;
+    if account.base.is_native.is_none() {
+        return Err(StakingError::InvalidWrappedSolAccount.into());
+    }
+    Ok(())
+}
+
+/// Take the burn destination from `iter` if the pool burns a share of
+/// claims. Pools without a claim burn consume nothing.
+pub fn next_burn_destination<'a, 'b, I>(
+    pool: &StakingPool,
+    iter: &mut I,
+) -> Result<Option<&'b AccountInfo<'a>>, ProgramError>
+where
+    I: Iterator<Item = &'b AccountInfo<'a>>,
+{
+    if !pool.has_claim_burn() {
+        return Ok(None);
+    }
+    let destination_info = next_account_info(iter)?;
+    if *destination_info.key != pool.claim_burn_destination {
+        return Err(StakingError::InvalidBurnDestination.into());
+    }
+    Ok(Some(destination_info))
+}
+
+/// Shared claim path. `session_expires_at` is set for session claims, whose
+/// session key signs (and pays) in place of the owner.
+fn claim_rewards(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    wrap: bool,
+    session_expires_at: Option<i64>,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
 
     let pool_info = next_account_info(account_info_iter)?;
     let user_stake_info = next_account_info(account_info_iter)?;
     let user_info = next_account_info(account_info_iter)?;
+    let (signer_info, session) = match session_expires_at {
+        Some(expires_at) => {
+            let session_key_info = next_account_info(account_info_iter)?;
+            let instructions_sysvar_info = next_account_info(account_info_iter)?;
+            (session_key_info, Some((instructions_sysvar_info, expires_at)))
+        }
+        None => (user_info, None),
+    };
 
-    // Validate user is signer
-    if !user_info.is_signer {
+    // Validate user (or session key) is signer
+    if !signer_info.is_signer {
         return Err(StakingError::MissingRequiredSigner.into());
     }
 
     // Load and validate pool
-    if pool_info.owner != program_id {
-        return Err(StakingError::InvalidAccountOwner.into());
-    }
-    let mut pool = StakingPool::try_from_slice(&pool_info.try_borrow_data()?)?;
-    if !pool.is_initialized() {
-        return Err(StakingError::NotInitialized.into());
-    }
+    let mut pool = load_pool(program_id, pool_info)?;
 
-    // Verify pool PDA
-    let (expected_pool, _) = StakingPool::derive_pda(&pool.mint, program_id);
-    if *pool_info.key != expected_pool {
-        return Err(StakingError::InvalidPDA.into());
-    }
+    // Rebase inline if sum_stake_exp is near overflow (saved with the pool
+    // below). Best effort: earned rewards stay claimable even if it fails.
+    pool.rebase_if_possible(pool_time(&pool)?);
 
-    // Check if pool needs rebasing
-    if pool.get_sum_stake_exp().needs_rebase() {
-        return Err(StakingError::PoolRequiresSync.into());
+    // Load and validate user stake
+    let mut user_stake =
+        load_user_stake(program_id, user_stake_info, pool_info.key, user_info.key)?;
+    user_stake.check_not_frozen()?;
+
+    // Session claims need the owner's signed delegation to the session key
+    if let Some((instructions_sysvar_info, expires_at)) = session {
+        verify_claim_session(
+            instructions_sysvar_info,
+            pool_info.key,
+            user_info.key,
+            signer_info.key,
+            expires_at,
+            pool_time(&pool)?,
+        )?;
     }
 
-    // Realloc legacy accounts to current size (payer = user)
+    // Rewards go to the wSOL account when wrapping, otherwise to the
+    // payout split or declared recipient, if any, instead of the owner
+    let (payee, wrapped) = if wrap {
+        let wsol_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        validate_wsol_account(wsol_info, token_program_info)?;
+        (RewardPayee::Single(wsol_info), Some((wsol_info, token_program_info)))
+    } else {
+        let payee = next_reward_payee(program_id, &user_stake, user_info, account_info_iter)?;
+        (payee, None)
+    };
+
+    // Pools with claim rebates must pass their rebate vault
+    let rebate_vault_info =
+        next_gas_rebate_vault(program_id, pool_info.key, &pool, account_info_iter)?;
+
+    // Pools with a claim burn must pass the burn destination
+    let burn_destination_info = next_burn_destination(&pool, account_info_iter)?;
+
+    // Realloc legacy accounts to current size (payer = user or session key)
     // System program is optional trailing account, only needed for legacy accounts
     let system_program_info = account_info_iter.next();
-    UserStake::maybe_realloc(user_stake_info, user_info, system_program_info)?;
+    UserStake::maybe_realloc(user_stake_info, signer_info, system_program_info)?;
 
-    // Load and validate user stake
-    if user_stake_info.owner != program_id {
-        return Err(StakingError::InvalidAccountOwner.into());
-    }
-    let mut user_stake = UserStake::try_from_slice(&user_stake_info.try_borrow_data()?)?;
-    if !user_stake.is_initialized() {
-        return Err(StakingError::NotInitialized.into());
-    }
+    let current_time = pool_time(&pool)?;
 
-    // Verify ownership
-    if user_stake.owner != *user_info.key {
-        return Err(StakingError::InvalidOwner.into());
-    }
-    if user_stake.pool != *pool_info.key {
-        return Err(StakingError::InvalidPool.into());
-    }
-
-    // Verify user stake PDA
-    let (expected_stake, _) =
-        UserStake::derive_pda(pool_info.key, user_info.key, program_id);
-    if *user_stake_info.key != expected_stake {
-        return Err(StakingError::InvalidPDA.into());
-    }
+    // Rewards left vesting by an unstake are paid alongside either path
+    let vested_fragment = user_stake.vested_rewards_wad(current_time);
 
     // Handle two claim paths:
     // 1. amount > 0: normal claim using snapshot-delta formula
@@ -94,7 +216,7 @@ pub fn process_claim_rewards(
     //    (when pool lacked SOL at unstake time, unpaid rewards are stored in reward_debt)
     let (pending, is_residual_claim) = if user_stake.amount == 0 {
         // Post-full-unstake: reward_debt stores unclaimed WAD-scaled rewards
-        if user_stake.reward_debt == 0 {
+        if user_stake.reward_debt == 0 && vested_fragment == 0 {
             msg!("No rewards to claim");
             return Ok(());
         }
@@ -105,9 +227,6 @@ pub fn process_claim_rewards(
         // Lazily adjust exp_start_factor if pool has been rebased
         user_stake.sync_to_pool(&pool)?;
 
-        let clock = Clock::get()?;
-        let current_time = clock.unix_timestamp;
-
         // Calculate user's current weighted stake
         let user_weighted = calculate_user_weighted_stake(
             user_stake.amount,
@@ -116,64 +235,100 @@ pub fn process_claim_rewards(
             pool.base_time,
             pool.tau_seconds,
         )?;
-        if user_weighted == 0 {
+        if user_weighted == 0 && vested_fragment == 0 {
             msg!("No rewards to claim (stake too new)");
             return Ok(());
         }
 
-        // Full entitlement: user_weighted * (acc_rps - snapshot)
-        // where snapshot = reward_debt / (amount * WAD)
-        let amount_wad = (user_stake.amount as u128)
-            .checked_mul(WAD)
-            .ok_or(StakingError::MathOverflow)?;
-        let snapshot = wad_div(user_stake.reward_debt, amount_wad)?;
-        let delta_rps = pool.acc_reward_per_weighted_share.saturating_sub(snapshot);
-        let full_entitlement = wad_mul(user_weighted, delta_rps)?;
-
-        // Subtract already-claimed amount to get pending (frequency-independent)
-        let p = full_entitlement.saturating_sub(user_stake.claimed_rewards_wad);
+        // Full (vested) entitlement minus already-claimed amount (frequency-independent)
+        let p = user_stake
+            .pending_rewards_wad(user_weighted, pool.vested_acc_rps(current_time))?;
 
-        if p == 0 {
+        if p == 0 && vested_fragment == 0 {
             msg!("No pending rewards to claim");
             return Ok(());
         }
         (p, false)
     };
 
-    // Convert from WAD-scaled to lamports
-    let pending_lamports = pending / WAD;
+    // Convert from WAD-scaled to lamports using the pool's rounding mode.
+    // Round-ups are funded by the pool's rounding reserve, so payouts never
+    // exceed what was distributed.
+    let owed = pending.saturating_add(vested_fragment);
+    let pending_lamports = pool.payout_lamports(owed);
 
     if pending_lamports == 0 {
         msg!("Pending rewards too small to claim");
         return Ok(());
     }
 
+    // Below the pool's minimum claim: leave the rewards accruing
+    if pool.defers_claim(pending_lamports, is_residual_claim) {
+        msg!(
+            "Pending rewards below the {} lamport minimum claim",
+            pool.min_claim_lamports
+        );
+        return Ok(());
+    }
+
     // Check pool has sufficient balance (keep rent-exempt minimum)
     let rent = Rent::get()?;
     let rent_exempt_minimum = rent.minimum_balance(pool_info.data_len());
     let pool_lamports = pool_info.lamports();
 
-    let available_rewards = pool_lamports.saturating_sub(rent_exempt_minimum);
+    // Protocol fees owed to the treasury are not rewards, and lamports
+    // reserved for residual claimants only pay residual claims
+    let available_rewards =
+        pool.claimable_lamports(pool_lamports, rent_exempt_minimum, is_residual_claim);
 
     if available_rewards == 0 {
-        return Err(StakingError::InsufficientRewardBalance.into());
+        let short = pending_lamports.min(u64::MAX as u128) as u64;
+        return Err(StakingError::InsufficientRewardBalance
+            .with_context(ErrorContext::LamportsShort(short)));
     }
 
-    let transfer_amount = pending_lamports.min(available_rewards as u128) as u64;
+    // The fragment is only released when it can be paid in full; otherwise
+    // the claim pays what it can of the position's other rewards
+    let (transfer_amount, fragment_paid, paid_for) =
+        if pending_lamports <= available_rewards as u128 {
+            (pending_lamports as u64, user_stake.release_vested_rewards(current_time), owed)
+        } else {
+            let own_lamports = pool.payout_lamports(pending);
+            (own_lamports.min(available_rewards as u128) as u64, 0, pending)
+        };
+    if transfer_amount == 0 {
+        let short = pending_lamports.min(u64::MAX as u128) as u64;
+        return Err(StakingError::InsufficientRewardBalance
+            .with_context(ErrorContext::LamportsShort(short)));
+    }
+    // Fragment lamports are tracked with the residuals
+    pool.pay_residual((fragment_paid / WAD) as u64);
+
+    // The burned share leaves the pool with the rest of the claim: the
+    // position is charged the whole amount
+    let burned = match burn_destination_info {
+        Some(_) => pool.claim_burn(transfer_amount),
+        None => 0,
+    };
+    let paid_amount = transfer_amount - burned;
 
-    // Transfer SOL from pool to user
+    // Transfer SOL from pool to user (or their split / declared recipient / wSOL account)
     **pool_info.try_borrow_mut_lamports()? -= transfer_amount;
-    **user_info.try_borrow_mut_lamports()? += transfer_amount;
+    payee.credit(paid_amount)?;
+    if let (Some(destination_info), true) = (burn_destination_info, burned > 0) {
+        **destination_info.try_borrow_mut_lamports()? += burned;
+    }
 
-    let paid_wad = (transfer_amount as u128)
-        .checked_mul(WAD)
-        .ok_or(StakingError::MathOverflow)?;
+    // Rewards the payout settles, net of the released fragment
+    let paid_wad = pool
+        .settle_payout(paid_for, transfer_amount)
+        .saturating_sub(fragment_paid);
 
     if is_residual_claim {
         // Residual claim (amount==0): reward_debt IS the unclaimed amount, so subtract
         user_stake.reward_debt = user_stake.reward_debt.saturating_sub(paid_wad);
         // Residual debts are tracked in total_residual_unpaid (not total_reward_debt)
-        pool.total_residual_unpaid = pool.total_residual_unpaid.saturating_sub(transfer_amount);
+        pool.pay_residual((paid_wad / WAD) as u64);
     } else {
         // Track cumulative claimed amount (no snapshot reset).
         // Snapshot stays fixed so weight maturation isn't forfeited on claim.
@@ -190,15 +345,25 @@ pub fn process_claim_rewards(
     user_stake.total_rewards_claimed = user_stake.total_rewards_claimed.saturating_add(transfer_amount);
 
     // Save user stake
-    {
-        let mut stake_data = user_stake_info.try_borrow_mut_data()?;
-        user_stake.serialize(&mut &mut stake_data[..])?;
-    }
+    user_stake.save(user_stake_info)?;
 
     // Save pool state
-    {
-        let mut pool_data = pool_info.try_borrow_mut_data()?;
-        pool.serialize(&mut &mut pool_data[..])?;
+    pool.save(pool_info)?;
+
+    // Wrapped claim: bring the token amount in line with the credited lamports
+    if let Some((wsol_info, token_program_info)) = wrapped {
+        invoke(
+            &spl_token_2022::instruction::sync_native(token_program_info.key, wsol_info.key)?,
+            std::slice::from_ref(wsol_info),
+        )?;
+    }
+
+    // The rebate comes from its own vault, never from staker rewards
+    if let Some(vault_info) = rebate_vault_info {
+        let rebate = pay_gas_rebate(vault_info, user_info, paid_amount)?;
+        if rebate > 0 {
+            msg!("Gas rebate: {} lamports", rebate);
+        }
     }
 
     if is_residual_claim {
@@ -206,8 +371,19 @@ pub fn process_claim_rewards(
     } else {
         msg!("Claimed {} lamports in rewards", transfer_amount);
     }
+    if burned > 0 {
+        msg!("Burned {} lamports, paid {}", burned, paid_amount);
+    }
 
-    emit_reward_payout(pool_info.key, user_info.key, transfer_amount, RewardPayoutType::Claim);
+    let payout_type = if wrap {
+        RewardPayoutType::ClaimWrapped
+    } else {
+        RewardPayoutType::Claim
+    };
+    emit_reward_payout(pool_info.key, user_info.key, paid_amount, payout_type);
+    if let (Some(destination_info), true) = (burn_destination_info, burned > 0) {
+        emit_reward_burned(pool_info.key, user_info.key, destination_info.key, burned);
+    }
 
     Ok(())
 }
@@ -0,0 +1,115 @@
+//! Claim maturity bonus instruction
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    error::{ErrorContext, StakingError},
+    loaders::{load_pool, load_user_stake},
+    state::{BonusBudget, UserStake},
+    time::pool_time,
+};
+
+/// Pay the position's one-time maturity bonus: once the stake is 3 tau old,
+/// `total_rewards_claimed * maturity_bonus_bps / 10000` lamports move from
+/// the bonus budget to the owner. Claim pending rewards first; only what
+/// has been claimed counts. If the budget is short the call fails and the
+/// bonus can be claimed once it is topped up.
+///
+/// Accounts:
+/// 0. `[]` Pool account
+/// 1. `[writable]` User stake account
+/// 2. `[writable, signer]` User/owner
+/// 3. `[writable]` Bonus budget (PDA: ["bonus_budget", pool])
+/// 4. `[]` (optional) System program (legacy account reallocation)
+pub fn process_claim_maturity_bonus(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let user_stake_info = next_account_info(account_info_iter)?;
+    let user_info = next_account_info(account_info_iter)?;
+    let budget_info = next_account_info(account_info_iter)?;
+    let system_program_info = account_info_iter.next();
+
+    // Validate user is signer
+    if !user_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let pool = load_pool(program_id, pool_info)?;
+
+    if pool.maturity_bonus_bps == 0 {
+        return Err(StakingError::MaturityBonusDisabled.into());
+    }
+
+    // Load and validate user stake
+    let mut user_stake =
+        load_user_stake(program_id, user_stake_info, pool_info.key, user_info.key)?;
+    user_stake.check_not_frozen()?;
+
+    // Load and validate bonus budget
+    let (expected_budget, _) = BonusBudget::derive_pda(pool_info.key, program_id);
+    if *budget_info.key != expected_budget {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    if budget_info.owner != program_id {
+        return Err(StakingError::InvalidAccountOwner.into());
+    }
+    let mut budget = BonusBudget::try_from_slice(&budget_info.try_borrow_data()?)?;
+    if !budget.is_initialized() {
+        return Err(StakingError::NotInitialized.into());
+    }
+
+    if user_stake.maturity_bonus_paid {
+        return Err(StakingError::MaturityBonusAlreadyPaid.into());
+    }
+    if user_stake.amount == 0 {
+        return Err(StakingError::InsufficientStakeBalance
+            .with_context(ErrorContext::AvailableAmount(0)));
+    }
+
+    let current_time = pool_time(&pool)?;
+    let remaining = user_stake.maturity_remaining_seconds(&pool, current_time);
+    if remaining > 0 {
+        return Err(StakingError::StakeNotMature.with_context(ErrorContext::RemainingSeconds(remaining)));
+    }
+
+    let bonus = user_stake.maturity_bonus(pool.maturity_bonus_bps);
+    if bonus == 0 {
+        return Err(StakingError::ZeroAmount.into());
+    }
+
+    // Everything above the budget's rent-exempt minimum is available
+    let rent = Rent::get()?;
+    let available = budget_info
+        .lamports()
+        .saturating_sub(rent.minimum_balance(BonusBudget::LEN));
+    if bonus > available {
+        return Err(StakingError::InsufficientBonusBudget
+            .with_context(ErrorContext::LamportsShort(bonus - available)));
+    }
+
+    // Realloc legacy accounts to current size (payer = user)
+    UserStake::maybe_realloc(user_stake_info, user_info, system_program_info)?;
+
+    **budget_info.try_borrow_mut_lamports()? -= bonus;
+    **user_info.try_borrow_mut_lamports()? += bonus;
+
+    budget.total_paid = budget.total_paid.saturating_add(bonus);
+    budget.serialize(&mut &mut budget_info.try_borrow_mut_data()?[..])?;
+
+    user_stake.maturity_bonus_paid = true;
+    user_stake.save(user_stake_info)?;
+
+    msg!("Paid maturity bonus of {} lamports", bonus);
+
+    Ok(())
+}
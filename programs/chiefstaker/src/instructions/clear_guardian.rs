@@ -0,0 +1,78 @@
+//! Clear guardian instruction
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::{ErrorContext, StakingError},
+    loaders::{load_pool, load_user_stake},
+    state::UserStake,
+    time::pool_time,
+};
+
+/// Remove the position's guardian. If the guardian co-signs it is removed
+/// at once. Otherwise the first call starts a GUARDIAN_REMOVAL_DELAY_SECONDS
+/// timelock (the guardian keeps co-signing until then, and can cancel it
+/// with SetGuardian), and a call after the timelock removes the guardian.
+///
+/// Accounts:
+/// 0. `[]` Pool account
+/// 1. `[writable]` User stake account
+/// 2. `[writable, signer]` User/owner
+/// 3. `[]` Guardian (signer to remove immediately)
+/// 4. `[]` (optional) System program (legacy account reallocation)
+pub fn process_clear_guardian(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let user_stake_info = next_account_info(account_info_iter)?;
+    let user_info = next_account_info(account_info_iter)?;
+    let guardian_info = next_account_info(account_info_iter)?;
+
+    // Validate user is signer
+    if !user_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let pool = load_pool(program_id, pool_info)?;
+
+    // Load and validate user stake
+    let mut user_stake =
+        load_user_stake(program_id, user_stake_info, pool_info.key, user_info.key)?;
+
+    if !user_stake.has_guardian() || *guardian_info.key != user_stake.guardian {
+        return Err(StakingError::InvalidGuardian.into());
+    }
+
+    // Realloc legacy accounts to current size (payer = user)
+    let system_program_info = account_info_iter.next();
+    UserStake::maybe_realloc(user_stake_info, user_info, system_program_info)?;
+
+    let current_time = pool_time(&pool)?;
+    if guardian_info.is_signer {
+        msg!("Guardian {} removed with its consent", user_stake.guardian);
+        user_stake.guardian = Pubkey::default();
+        user_stake.guardian_removal_time = 0;
+    } else if user_stake.guardian_removal_time == 0 {
+        user_stake.guardian_removal_time =
+            current_time.saturating_add(UserStake::GUARDIAN_REMOVAL_DELAY_SECONDS);
+        msg!("Guardian removal possible after {}", user_stake.guardian_removal_time);
+    } else if current_time >= user_stake.guardian_removal_time {
+        msg!("Guardian {} removed after timelock", user_stake.guardian);
+        user_stake.guardian = Pubkey::default();
+        user_stake.guardian_removal_time = 0;
+    } else {
+        let remaining = user_stake.guardian_removal_time.saturating_sub(current_time) as u64;
+        return Err(StakingError::GuardianRemovalTimelocked
+            .with_context(ErrorContext::RemainingSeconds(remaining)));
+    }
+
+    user_stake.save(user_stake_info)?;
+
+    Ok(())
+}
@@ -0,0 +1,206 @@
+//! Initialize a pool with another pool's settings (source authority only)
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::StakingError,
+    loaders::load_pool,
+    state::{PoolMetadata, StakingPool, METADATA_SEED},
+};
+
+use super::{create_lamport_vault, process_initialize_pool};
+
+/// Initialize pool `pool_id` of the source pool's mint with tau_seconds and
+/// the source pool's settings, e.g. to move stakers to a new tau. The new
+/// pool starts empty: only settings carried by pool fields alone are
+/// copied. Features backed by their own accounts (authority log, receipt
+/// mint, sponsor and bonus vaults, ...) are set up separately.
+///
+/// Accounts:
+/// 0-6. Same as InitializePool, with the pool account at
+///    ["pool", mint, pool_id (u64 LE)] and account 3 the source pool's
+///    authority (pays rent, becomes the new pool's authority)
+/// 7. `[]` Source pool
+/// 8. `[]` (optional) Source pool metadata (PDA: ["metadata", source_pool])
+/// 9. `[writable]` New pool metadata (PDA: ["metadata", pool]; with 8 only)
+pub fn process_clone_pool_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    source_pool: &Pubkey,
+    tau_seconds: u64,
+    pool_id: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let _token_vault_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let _token_program_info = next_account_info(account_info_iter)?;
+    let _rent_sysvar_info = next_account_info(account_info_iter)?;
+    let source_pool_info = next_account_info(account_info_iter)?;
+
+    // Validate authority is signer
+    if !authority_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate source pool
+    if source_pool_info.key != source_pool {
+        return Err(StakingError::InvalidPool.into());
+    }
+    let source = load_pool(program_id, source_pool_info)?;
+    if source.mint != *mint_info.key {
+        return Err(StakingError::InvalidPoolMint.into());
+    }
+
+    // Check authority is not renounced
+    if source.is_authority_renounced() {
+        return Err(StakingError::AuthorityRenounced.into());
+    }
+
+    // Verify authority
+    if source.authority != *authority_info.key {
+        return Err(StakingError::InvalidAuthority.into());
+    }
+
+    // Validate the source metadata before creating anything
+    let source_metadata = match account_info_iter.next() {
+        Some(source_metadata_info) => {
+            let (expected_metadata, _) = PoolMetadata::derive_pda(source_pool, program_id);
+            if *source_metadata_info.key != expected_metadata {
+                return Err(StakingError::InvalidPDA.into());
+            }
+            if source_metadata_info.owner != program_id {
+                return Err(StakingError::InvalidAccountOwner.into());
+            }
+            let metadata = PoolMetadata::try_from_slice(&source_metadata_info.try_borrow_data()?)?;
+            if !metadata.is_initialized() {
+                return Err(StakingError::NotInitialized.into());
+            }
+            if metadata.pool != *source_pool {
+                return Err(StakingError::InvalidPool.into());
+            }
+            Some((metadata, next_account_info(account_info_iter)?))
+        }
+        None => None,
+    };
+
+    // Create the pool and vault, without a receipt mint
+    process_initialize_pool(program_id, &accounts[..7], tau_seconds, pool_id, None, false)?;
+
+    let mut pool = load_pool(program_id, pool_info)?;
+    copy_pool_settings(&mut pool, &source);
+    pool.save(pool_info)?;
+
+    if let Some((mut metadata, metadata_info)) = source_metadata {
+        let (expected_metadata, metadata_bump) =
+            PoolMetadata::derive_pda(pool_info.key, program_id);
+        if *metadata_info.key != expected_metadata {
+            return Err(StakingError::InvalidPDA.into());
+        }
+        let seeds: &[&[u8]] = &[METADATA_SEED, pool_info.key.as_ref(), &[metadata_bump]];
+        create_lamport_vault(
+            program_id,
+            authority_info,
+            metadata_info,
+            system_program_info,
+            seeds,
+            PoolMetadata::LEN,
+        )?;
+
+        metadata.pool = *pool_info.key;
+        metadata.member_count = 0;
+        metadata.bump = metadata_bump;
+        metadata.serialize(&mut &mut metadata_info.try_borrow_mut_data()?[..])?;
+    }
+
+    msg!(
+        "Cloned settings of pool {} (tau={}s) into pool {} (tau={}s)",
+        source.pool_id,
+        source.tau_seconds,
+        pool_id,
+        tau_seconds
+    );
+
+    Ok(())
+}
+
+/// Copy the settings ClonePoolConfig carries over from `source`
+fn copy_pool_settings(pool: &mut StakingPool, source: &StakingPool) {
+    pool.min_stake_amount = source.min_stake_amount;
+    pool.lock_duration_seconds = source.lock_duration_seconds;
+    pool.lock_exempt_amount = source.lock_exempt_amount;
+    pool.unstake_cooldown_seconds = source.unstake_cooldown_seconds;
+    pool.unstake_request_expiry_seconds = source.unstake_request_expiry_seconds;
+    pool.unstake_fee_bps = source.unstake_fee_bps;
+    pool.unstake_fee_destination = source.unstake_fee_destination;
+    pool.claim_burn_bps = source.claim_burn_bps;
+    pool.claim_burn_destination = source.claim_burn_destination;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_pool_settings() {
+        let mint = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let mut source = StakingPool::new(
+            mint,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            authority,
+            86_400,
+            1_000,
+            255,
+        );
+        source.min_stake_amount = 10;
+        source.lock_duration_seconds = 3_600;
+        source.lock_exempt_amount = 5;
+        source.unstake_cooldown_seconds = 600;
+        source.unstake_request_expiry_seconds = 1_200;
+        source.unstake_fee_bps = 50;
+        source.unstake_fee_destination = Pubkey::new_unique();
+        source.claim_burn_bps = 25;
+        source.claim_burn_destination = Pubkey::new_unique();
+        source.total_staked = 1_000;
+        source.rewards_paused = true;
+
+        let mut pool = StakingPool::new(
+            mint,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            authority,
+            604_800,
+            2_000,
+            254,
+        );
+        pool.pool_id = 1;
+        copy_pool_settings(&mut pool, &source);
+
+        assert_eq!(pool.min_stake_amount, 10);
+        assert_eq!(pool.lock_duration_seconds, 3_600);
+        assert_eq!(pool.lock_exempt_amount, 5);
+        assert_eq!(pool.unstake_cooldown_seconds, 600);
+        assert_eq!(pool.unstake_request_expiry_seconds, 1_200);
+        assert_eq!(pool.unstake_fee_bps, 50);
+        assert_eq!(pool.unstake_fee_destination, source.unstake_fee_destination);
+        assert_eq!(pool.claim_burn_bps, 25);
+        assert_eq!(pool.claim_burn_destination, source.claim_burn_destination);
+
+        // Identity, tau, balances and state stay the new pool's own
+        assert_eq!((pool.pool_id, pool.tau_seconds, pool.bump), (1, 604_800, 254));
+        assert_eq!(pool.base_time, 2_000);
+        assert_eq!(pool.total_staked, 0);
+        assert!(!pool.rewards_paused);
+    }
+}
@@ -0,0 +1,67 @@
+//! Close a stake archive to reclaim rent
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{error::StakingError, state::StakeArchive};
+
+/// Close the owner's stake archive, returning its rent to the owner.
+/// The pool isn't needed: archives outlive their positions and may
+/// outlive the pool.
+///
+/// Accounts:
+/// 0. `[writable]` Stake archive (PDA: ["stake_archive", pool, owner])
+/// 1. `[writable, signer]` Owner (receives rent)
+pub fn process_close_archived(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let archive_info = next_account_info(account_info_iter)?;
+    let owner_info = next_account_info(account_info_iter)?;
+
+    // Validate owner is signer
+    if !owner_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate archive
+    if archive_info.owner != program_id {
+        return Err(StakingError::InvalidAccountOwner.into());
+    }
+    let archive = StakeArchive::try_from_slice(&archive_info.try_borrow_data()?)?;
+    if !archive.is_initialized() {
+        return Err(StakingError::NotInitialized.into());
+    }
+    if archive.owner != *owner_info.key {
+        return Err(StakingError::InvalidOwner.into());
+    }
+
+    // Verify archive PDA
+    let (expected_archive, _) = StakeArchive::derive_pda(&archive.pool, owner_info.key, program_id);
+    if *archive_info.key != expected_archive {
+        return Err(StakingError::InvalidPDA.into());
+    }
+
+    // Transfer all lamports from the archive to the owner
+    let archive_lamports = archive_info.lamports();
+    **archive_info.try_borrow_mut_lamports()? = 0;
+    **owner_info.try_borrow_mut_lamports()? += archive_lamports;
+
+    // Zero out the account data so it can't be re-read as a valid archive
+    archive_info.try_borrow_mut_data()?.fill(0);
+
+    msg!(
+        "Closed stake archive of {} positions, returned {} lamports",
+        archive.closures,
+        archive_lamports
+    );
+
+    Ok(())
+}
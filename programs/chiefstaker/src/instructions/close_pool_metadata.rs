@@ -0,0 +1,99 @@
+//! Close pool metadata account (authority only)
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+    system_program,
+};
+
+use crate::{
+    authority_log::{next_authority_log, record_authority_actions},
+    error::StakingError,
+    loaders::load_pool,
+    state::{AuthorityAction, AuthorityLogEntry, PoolMetadata},
+    time::pool_time,
+};
+
+/// Close the pool's metadata account and return its rent to the authority.
+/// The account's contents are not read, so corrupted metadata can be
+/// closed too. The account is handed back to the system program, so
+/// SetPoolMetadata (even later in the same transaction) recreates it;
+/// member_count then restarts at 0.
+///
+/// Accounts:
+/// 0. `[]` Pool account
+/// 1. `[writable, signer]` Authority (receives the rent)
+/// 2. `[writable]` Metadata PDA (["metadata", pool])
+/// 3. `[]` (optional) System program, required on pools with an authority log
+/// 4. `[writable]` Authority log (pools with an authority log only)
+pub fn process_close_pool_metadata(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let metadata_info = next_account_info(account_info_iter)?;
+    let system_program_info = account_info_iter.next();
+
+    // Validate authority is signer
+    if !authority_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let pool = load_pool(program_id, pool_info)?;
+
+    // Check authority is not renounced
+    if pool.is_authority_renounced() {
+        return Err(StakingError::AuthorityRenounced.into());
+    }
+
+    // Verify authority
+    if pool.authority != *authority_info.key {
+        return Err(StakingError::InvalidAuthority.into());
+    }
+
+    // Verify metadata PDA
+    let (expected_metadata, _) = PoolMetadata::derive_pda(pool_info.key, program_id);
+    if *metadata_info.key != expected_metadata {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    if metadata_info.owner != program_id {
+        return Err(StakingError::InvalidAccountOwner.into());
+    }
+
+    // Pools with an authority log must pass it (after the system program)
+    let log_info = next_authority_log(&pool, account_info_iter)?;
+    if let Some(log_info) = log_info {
+        let entry = AuthorityLogEntry::pubkey(
+            pool_time(&pool)?,
+            *authority_info.key,
+            AuthorityAction::PoolMetadata,
+            *metadata_info.key,
+            Pubkey::default(),
+        );
+        record_authority_actions(program_id, log_info, pool_info.key, authority_info, system_program_info, &[entry])?;
+    }
+
+    // Transfer all lamports from the metadata account to the authority
+    let metadata_lamports = metadata_info.lamports();
+    **metadata_info.try_borrow_mut_lamports()? = 0;
+    **authority_info.try_borrow_mut_lamports()? += metadata_lamports;
+
+    // Zero and release the account so it can be recreated right away
+    metadata_info.try_borrow_mut_data()?.fill(0);
+    metadata_info.realloc(0, false)?;
+    metadata_info.assign(&system_program::id());
+
+    msg!(
+        "Closed pool metadata of {}, returned {} lamports",
+        pool_info.key,
+        metadata_lamports
+    );
+
+    Ok(())
+}
@@ -8,27 +8,73 @@ use solana_program::{
     pubkey::Pubkey,
 };
 
+use super::create_lamport_vault;
+
 use crate::{
     error::StakingError,
+    loaders::{load_pool, load_user_stake},
     math::WAD,
-    state::{PoolMetadata, StakingPool, UserStake},
+    sponsor::{next_rent_sponsor, return_sponsored_rent},
+    state::{PoolMetadata, StakeArchive, UserStake, STAKE_ARCHIVE_SEED},
+    time::pool_time,
 };
 
 /// Close a zero-balance user stake account, returning rent to the user.
+/// Rent paid by the pool's rent sponsor goes back to its vault instead
+/// (or to the user, if the pool no longer has a sponsor).
 ///
 /// Accounts:
 /// 0. `[]` Pool account
 /// 1. `[writable]` User stake account (PDA: ["stake", pool, owner])
 /// 2. `[writable, signer]` User/owner (receives rent)
+/// 3. `[writable]` Rent sponsor vault (sponsored accounts of pools with a
+///    rent sponsor only)
+/// 4. `[writable]` (optional) Pool metadata (member count)
 pub fn process_close_stake_account(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
+) -> ProgramResult {
+    close_stake_account(program_id, accounts, false)
+}
+
+/// Close a zero-balance user stake account like CloseStakeAccount, first
+/// adding its lifetime totals and forgiven sub-lamport residual to the
+/// owner's stake archive. The owner pays the archive's rent the first time.
+///
+/// Accounts:
+/// 0. `[]` Pool account
+/// 1. `[writable]` User stake account (PDA: ["stake", pool, owner])
+/// 2. `[writable, signer]` User/owner (pays archive rent, receives rent)
+/// 3. `[writable]` Stake archive (PDA: ["stake_archive", pool, owner])
+/// 4. `[]` System program
+/// 5. `[writable]` Rent sponsor vault (sponsored accounts of pools with a
+///    rent sponsor only)
+/// 6. `[writable]` (optional) Pool metadata (member count)
+pub fn process_close_stake_account_archived(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    close_stake_account(program_id, accounts, true)
+}
+
+fn close_stake_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    archived: bool,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
 
     let pool_info = next_account_info(account_info_iter)?;
     let user_stake_info = next_account_info(account_info_iter)?;
     let user_info = next_account_info(account_info_iter)?;
+    let archive_accounts = if archived {
+        Some((
+            next_account_info(account_info_iter)?,
+            next_account_info(account_info_iter)?,
+        ))
+    } else {
+        None
+    };
 
     // Validate user is signer
     if !user_info.is_signer {
@@ -36,60 +82,55 @@ pub fn process_close_stake_account(
     }
 
     // Load and validate pool
-    if pool_info.owner != program_id {
-        return Err(StakingError::InvalidAccountOwner.into());
-    }
-    let pool = StakingPool::try_from_slice(&pool_info.try_borrow_data()?)?;
-    if !pool.is_initialized() {
-        return Err(StakingError::NotInitialized.into());
-    }
-
-    // Verify pool PDA
-    let (expected_pool, _) = StakingPool::derive_pda(&pool.mint, program_id);
-    if *pool_info.key != expected_pool {
-        return Err(StakingError::InvalidPDA.into());
-    }
+    let pool = load_pool(program_id, pool_info)?;
+    let current_time = pool_time(&pool)?;
 
     // Load and validate user stake
-    if user_stake_info.owner != program_id {
-        return Err(StakingError::InvalidAccountOwner.into());
-    }
-    let user_stake = UserStake::try_from_slice(&user_stake_info.try_borrow_data()?)?;
-    if !user_stake.is_initialized() {
-        return Err(StakingError::NotInitialized.into());
-    }
-
-    // Verify ownership
-    if user_stake.owner != *user_info.key {
-        return Err(StakingError::InvalidOwner.into());
-    }
-    if user_stake.pool != *pool_info.key {
-        return Err(StakingError::InvalidPool.into());
-    }
-
-    // Verify user stake PDA
-    let (expected_stake, _) =
-        UserStake::derive_pda(pool_info.key, user_info.key, program_id);
-    if *user_stake_info.key != expected_stake {
-        return Err(StakingError::InvalidPDA.into());
-    }
+    let user_stake = load_user_stake(program_id, user_stake_info, pool_info.key, user_info.key)?;
 
     // Account must be empty: no staked tokens, no pending unstake request,
-    // and no residual unclaimed rewards worth >= 1 lamport.
+    // and no residual unclaimed or vesting rewards worth >= 1 lamport.
     // reward_debt stores unclaimed WAD-scaled rewards after a full unstake
     // when the pool lacked SOL. Sub-WAD dust (< 1 lamport) is forgiven
     // to prevent permanent lock of the account.
     if user_stake.amount > 0
-        || user_stake.has_pending_unstake_request()
+        || user_stake.has_pending_unstake_request(&pool, current_time)
         || user_stake.reward_debt / WAD > 0
+        || user_stake.vesting_rewards_wad / WAD > 0
     {
         return Err(StakingError::AccountNotEmpty.into());
     }
 
+    // Record the position before its data is erased
+    if let Some((archive_info, system_program_info)) = archive_accounts {
+        archive_stake(
+            program_id,
+            pool_info.key,
+            &user_stake,
+            current_time,
+            user_info,
+            archive_info,
+            system_program_info,
+        )?;
+    }
+
+    // Sponsored rent returns to the sponsor vault
+    let sponsor_info = if user_stake.sponsored_rent > 0 {
+        next_rent_sponsor(program_id, pool_info.key, &pool, account_info_iter)?
+    } else {
+        None
+    };
+
     // Transfer all lamports from stake account to user (closes the account)
     let stake_lamports = user_stake_info.lamports();
+    let returned = match sponsor_info {
+        Some(vault_info) => {
+            return_sponsored_rent(vault_info, user_stake.sponsored_rent, stake_lamports)?
+        }
+        None => 0,
+    };
     **user_stake_info.try_borrow_mut_lamports()? = 0;
-    **user_info.try_borrow_mut_lamports()? += stake_lamports;
+    **user_info.try_borrow_mut_lamports()? += stake_lamports - returned;
 
     // Zero out the account data so it can't be re-read as a valid stake
     let mut stake_data = user_stake_info.try_borrow_mut_data()?;
@@ -112,7 +153,57 @@ pub fn process_close_stake_account(
         }
     }
 
-    msg!("Closed user stake account, returned {} lamports", stake_lamports);
+    if returned > 0 {
+        msg!("Returned {} lamports of sponsored rent", returned);
+    }
+    msg!(
+        "Closed user stake account, returned {} lamports",
+        stake_lamports - returned
+    );
+
+    Ok(())
+}
+
+/// Add `user_stake` to the owner's archive, creating it if needed
+fn archive_stake<'a>(
+    program_id: &Pubkey,
+    pool_key: &Pubkey,
+    user_stake: &UserStake,
+    current_time: i64,
+    user_info: &AccountInfo<'a>,
+    archive_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    let (expected_archive, bump) = StakeArchive::derive_pda(pool_key, user_info.key, program_id);
+    if *archive_info.key != expected_archive {
+        return Err(StakingError::InvalidPDA.into());
+    }
 
+    let mut archive = if archive_info.owner == program_id {
+        let archive = StakeArchive::try_from_slice(&archive_info.try_borrow_data()?)?;
+        if !archive.is_initialized() {
+            return Err(StakingError::NotInitialized.into());
+        }
+        archive
+    } else {
+        let seeds: &[&[u8]] = &[STAKE_ARCHIVE_SEED, pool_key.as_ref(), user_info.key.as_ref(), &[bump]];
+        create_lamport_vault(
+            program_id,
+            user_info,
+            archive_info,
+            system_program_info,
+            seeds,
+            StakeArchive::LEN,
+        )?;
+        StakeArchive::new(*pool_key, *user_info.key, bump)
+    };
+
+    archive.record(user_stake, current_time);
+    borsh::to_writer(&mut &mut archive_info.try_borrow_mut_data()?[..], &archive)?;
+    msg!(
+        "Archived position {} (forgiven {} reward WAD)",
+        archive.closures,
+        user_stake.reward_debt + user_stake.vesting_rewards_wad,
+    );
     Ok(())
 }
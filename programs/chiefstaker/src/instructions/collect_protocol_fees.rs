@@ -0,0 +1,85 @@
+//! Collect a pool's protocol fees and refresh its fee snapshot
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::StakingError,
+    loaders::load_pool_any_engine,
+    state::{ProgramConfig, StakingPool},
+};
+
+/// Send the pool's owed protocol fees to the config's fee treasury and
+/// copy the current protocol fee into the pool (permissionless crank).
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[]` Program config (PDA: ["program_config"])
+/// 2. `[writable]` Fee treasury (must match the program config)
+/// 3. `[writable, signer]` Payer (legacy pool reallocation)
+/// 4. `[]` (optional) System program (legacy pool reallocation)
+pub fn process_collect_protocol_fees(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+    let treasury_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+
+    // Validate payer is signer
+    if !payer_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool (fees accrue on pools of either engine)
+    let mut pool = load_pool_any_engine(program_id, pool_info)?;
+
+    // Load and validate config
+    if config_info.owner != program_id {
+        return Err(StakingError::InvalidAccountOwner.into());
+    }
+    let (expected_config, _) = ProgramConfig::derive_pda(program_id);
+    if *config_info.key != expected_config {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    let config = ProgramConfig::try_from_slice(&config_info.try_borrow_data()?)?;
+    if !config.is_initialized() {
+        return Err(StakingError::NotInitialized.into());
+    }
+
+    // Verify treasury
+    if config.fee_treasury == Pubkey::default() || *treasury_info.key != config.fee_treasury {
+        return Err(StakingError::InvalidFeeTreasury.into());
+    }
+
+    // Grow legacy pools so the fee fields fit (payer funds rent)
+    let system_program_info = account_info_iter.next();
+    StakingPool::maybe_realloc(pool_info, payer_info, system_program_info)?;
+
+    let collected = pool.protocol_fees_owed;
+    if collected > 0 {
+        **pool_info.try_borrow_mut_lamports()? -= collected;
+        **treasury_info.try_borrow_mut_lamports()? += collected;
+        pool.last_synced_lamports = pool.last_synced_lamports.saturating_sub(collected);
+        pool.protocol_fees_owed = 0;
+    }
+
+    pool.protocol_fee_bps = config.protocol_fee_bps;
+    pool.save(pool_info)?;
+
+    msg!(
+        "Collected {} lamports in protocol fees, pool fee now {} bps",
+        collected,
+        pool.protocol_fee_bps
+    );
+
+    Ok(())
+}
@@ -1,21 +1,24 @@
 //! Complete unstake instruction (after cooldown period has elapsed)
 
-use borsh::BorshDeserialize;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
-    clock::Clock,
     entrypoint::ProgramResult,
     pubkey::Pubkey,
-    sysvar::Sysvar,
 };
 
 use crate::{
-    error::StakingError,
-    state::{StakingPool, UserStake},
+    error::{ErrorContext, StakingError},
+    guardian::check_guardian_signature,
+    history::{next_weight_history, record_checkpoint},
+    loaders::{load_pool, load_user_stake},
+    memo::next_memo_program,
+    receipt::{burn_receipt, next_receipt_accounts},
+    recipient::next_reward_payee,
+    time::pool_time,
 };
 use spl_token_2022;
 
-use super::unstake::execute_unstake;
+use super::unstake::{execute_unstake, next_fee_destination};
 
 /// Complete unstake after cooldown has elapsed
 ///
@@ -27,6 +30,16 @@ use super::unstake::execute_unstake;
 /// 4. `[]` Token mint
 /// 5. `[writable, signer]` User/owner
 /// 6. `[]` Token 2022 program
+/// 7. `[writable]` Receipt mint (receipt pools only)
+/// 8. `[writable]` User receipt token account (receipt pools only, burned from)
+/// 9. `[writable]` Unstake fee destination (pools with an unstake fee only)
+/// 10. `[writable]` Weight history (positions with history only)
+/// 11. `[]` Payout split, then `[writable]` each of its destinations in
+///     order (positions with a payout split only); otherwise `[writable]`
+///     Reward recipient (positions with a declared recipient only)
+/// 12. `[signer]` Guardian (positions with a guardian only)
+/// 13. `[]` Memo program (user token accounts requiring memos only)
+/// 14. `[]` (optional) System program (legacy account reallocation)
 pub fn process_complete_unstake(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -52,24 +65,10 @@ pub fn process_complete_unstake(
     }
 
     // Load and validate pool
-    if pool_info.owner != program_id {
-        return Err(StakingError::InvalidAccountOwner.into());
-    }
-    let mut pool = StakingPool::try_from_slice(&pool_info.try_borrow_data()?)?;
-    if !pool.is_initialized() {
-        return Err(StakingError::NotInitialized.into());
-    }
-
-    // Verify pool PDA
-    let (expected_pool, _) = StakingPool::derive_pda(&pool.mint, program_id);
-    if *pool_info.key != expected_pool {
-        return Err(StakingError::InvalidPDA.into());
-    }
+    let mut pool = load_pool(program_id, pool_info)?;
 
-    // Check if pool needs rebasing
-    if pool.get_sum_stake_exp().needs_rebase() {
-        return Err(StakingError::PoolRequiresSync.into());
-    }
+    // Rebase inline if sum_stake_exp is near overflow (saved with the pool below)
+    pool.rebase_if_needed(pool_time(&pool)?)?;
 
     // Verify mint matches pool
     if pool.mint != *mint_info.key {
@@ -82,40 +81,26 @@ pub fn process_complete_unstake(
     }
 
     // Load and validate user stake
-    if user_stake_info.owner != program_id {
-        return Err(StakingError::InvalidAccountOwner.into());
-    }
-    let mut user_stake = UserStake::try_from_slice(&user_stake_info.try_borrow_data()?)?;
-    if !user_stake.is_initialized() {
-        return Err(StakingError::NotInitialized.into());
-    }
-
-    // Verify ownership
-    if user_stake.owner != *user_info.key {
-        return Err(StakingError::InvalidOwner.into());
-    }
-    if user_stake.pool != *pool_info.key {
-        return Err(StakingError::InvalidPool.into());
-    }
-
-    // Verify user stake PDA
-    let (expected_stake, _) =
-        UserStake::derive_pda(pool_info.key, user_info.key, program_id);
-    if *user_stake_info.key != expected_stake {
-        return Err(StakingError::InvalidPDA.into());
-    }
+    let mut user_stake =
+        load_user_stake(program_id, user_stake_info, pool_info.key, user_info.key)?;
 
     // Check there is a pending request
-    if !user_stake.has_pending_unstake_request() {
+    if user_stake.unstake_request_amount == 0 {
         return Err(StakingError::NoPendingUnstakeRequest.into());
     }
 
     // Check cooldown has elapsed
-    let clock = Clock::get()?;
-    let current_time = clock.unix_timestamp;
+    let current_time = pool_time(&pool)?;
     let elapsed = current_time.saturating_sub(user_stake.unstake_request_time).max(0) as u64;
     if elapsed < pool.unstake_cooldown_seconds {
-        return Err(StakingError::CooldownNotElapsed.into());
+        let remaining = pool.unstake_cooldown_seconds - elapsed;
+        return Err(StakingError::CooldownNotElapsed
+            .with_context(ErrorContext::RemainingSeconds(remaining)));
+    }
+
+    // ...and the grace window hasn't: a lapsed request must be made again
+    if user_stake.unstake_request_expired(&pool, current_time) {
+        return Err(StakingError::UnstakeRequestExpired.into());
     }
 
     // Lazily adjust exp_start_factor if pool has been rebased
@@ -127,6 +112,26 @@ pub fn process_complete_unstake(
     user_stake.unstake_request_amount = 0;
     user_stake.unstake_request_time = 0;
 
+    // Receipt pools: burn the matching receipt tokens before releasing stake
+    if let Some(receipt) = next_receipt_accounts(&pool, account_info_iter)? {
+        burn_receipt(&receipt, user_info, amount)?;
+    }
+
+    // Pools with an unstake fee must pass the fee destination
+    let fee_destination_info = next_fee_destination(&pool, account_info_iter)?;
+
+    // Positions with a weight history must pass it
+    let history_info = next_weight_history(&user_stake, account_info_iter)?;
+
+    // Rewards go to the payout split or declared recipient, if any, instead of the owner
+    let payee = next_reward_payee(program_id, &user_stake, user_info, account_info_iter)?;
+
+    // Guarded positions need the guardian to co-sign
+    check_guardian_signature(&user_stake, account_info_iter)?;
+
+    // User token accounts requiring memos must pass the memo program
+    let memo_program_info = next_memo_program(user_token_info, account_info_iter)?;
+
     // Optional trailing system program for legacy account reallocation
     let system_program_info = account_info_iter.next();
 
@@ -141,8 +146,17 @@ pub fn process_complete_unstake(
         user_token_info,
         mint_info,
         user_info,
+        &payee,
+        fee_destination_info,
+        memo_program_info,
         amount,
         current_time,
         system_program_info,
-    )
+    )?;
+
+    if let Some(history_info) = history_info {
+        record_checkpoint(program_id, history_info, pool_info.key, &pool, &user_stake, current_time)?;
+    }
+
+    Ok(())
 }
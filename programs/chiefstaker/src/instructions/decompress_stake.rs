@@ -0,0 +1,157 @@
+//! Decompress stake instruction - restore a compressed position's account
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    compression::{check_tree_accounts, compressed_leaf, replace_leaf, EMPTY_LEAF},
+    error::StakingError,
+    loaders::load_pool,
+    state::{StakingPool, UserStake, STAKE_SEED},
+};
+
+/// Restore a compressed position to its stake account. `position` is the
+/// UserStake from the StakeCompressed event; the compression program checks
+/// it against `root` with the proof and empties the leaf, so a position
+/// decompresses once. The owner pays the account rent again. The stake
+/// account must not exist (unstake and close a newer position first).
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable]` User stake account (PDA: ["stake", pool, owner], created)
+/// 2. `[writable, signer]` User/owner (pays rent)
+/// 3. `[writable]` Merkle tree (pool's compression tree)
+/// 4. `[]` spl-account-compression program
+/// 5. `[]` spl-noop program
+/// 6. `[]` System program
+/// 7. `[]` Proof nodes, leaf to root (minus the tree's canopy)
+pub fn process_decompress_stake(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    root: [u8; 32],
+    leaf_index: u32,
+    position: &[u8],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let user_stake_info = next_account_info(account_info_iter)?;
+    let user_info = next_account_info(account_info_iter)?;
+    let tree_info = next_account_info(account_info_iter)?;
+    let compression_program_info = next_account_info(account_info_iter)?;
+    let noop_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let proof_infos = account_info_iter.as_slice();
+
+    // Validate user is signer
+    if !user_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    check_tree_accounts(&pool, tree_info, compression_program_info, noop_info)?;
+
+    // The presented position must be a stake of this owner in this pool
+    let mut user_stake = UserStake::try_from_slice(position)?;
+    if !user_stake.is_initialized() {
+        return Err(StakingError::NotInitialized.into());
+    }
+    user_stake.check_owner(user_info.key)?;
+    if user_stake.pool != *pool_info.key {
+        return Err(StakingError::InvalidPool.into());
+    }
+
+    // Verify user stake PDA
+    let (expected_stake, stake_bump) =
+        UserStake::derive_pda(pool_info.key, user_info.key, program_id);
+    if *user_stake_info.key != expected_stake {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    if !user_stake_info.data_is_empty() {
+        return Err(StakingError::AccountNotEmpty.into());
+    }
+
+    // Prove and empty the leaf (fails unless the position is in the tree)
+    let pool_id_bytes = pool.pool_id.to_le_bytes();
+    let pool_bump = [pool.bump];
+    let pool_seeds: &[&[u8]] = &pool.signer_seeds(&pool_id_bytes, &pool_bump);
+    let proof: Vec<Pubkey> = proof_infos.iter().map(|info| *info.key).collect();
+    let mut cpi_accounts = vec![
+        tree_info.clone(),
+        pool_info.clone(),
+        noop_info.clone(),
+        compression_program_info.clone(),
+    ];
+    cpi_accounts.extend(proof_infos.iter().cloned());
+    invoke_signed(
+        &replace_leaf(
+            tree_info.key,
+            pool_info.key,
+            root,
+            compressed_leaf(position),
+            EMPTY_LEAF,
+            leaf_index,
+            &proof,
+        ),
+        &cpi_accounts,
+        &[pool_seeds],
+    )?;
+
+    // Recreate the stake account
+    let rent = Rent::get()?;
+    let stake_seeds = &[
+        STAKE_SEED,
+        pool_info.key.as_ref(),
+        user_info.key.as_ref(),
+        &[stake_bump],
+    ];
+    invoke_signed(
+        &system_instruction::create_account(
+            user_info.key,
+            user_stake_info.key,
+            rent.minimum_balance(UserStake::LEN),
+            UserStake::LEN as u64,
+            program_id,
+        ),
+        &[
+            user_info.clone(),
+            user_stake_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[stake_seeds],
+    )?;
+    user_stake.bump = stake_bump;
+    user_stake.save(user_stake_info)?;
+
+    // Grow legacy pools before writing (payer = user)
+    StakingPool::maybe_realloc(pool_info, user_info, Some(system_program_info))?;
+
+    pool.compressed_staked = pool
+        .compressed_staked
+        .checked_sub(user_stake.amount as u128)
+        .ok_or(StakingError::MathUnderflow)?;
+    pool.compressed_reward_debt = pool
+        .compressed_reward_debt
+        .checked_sub(user_stake.reward_debt)
+        .ok_or(StakingError::MathUnderflow)?;
+    pool.save(pool_info)?;
+
+    msg!(
+        "Decompressed leaf {}: {} staked tokens",
+        leaf_index,
+        user_stake.amount
+    );
+
+    Ok(())
+}
@@ -1,9 +1,7 @@
 //! Deposit rewards instruction
 
-use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
-    clock::Clock,
     entrypoint::ProgramResult,
     msg,
     program::invoke,
@@ -14,18 +12,36 @@ use solana_program::{
 };
 
 use crate::{
+    depositors::{check_depositor, next_depositor_record, record_deposit},
+    distribution_history::{
+        create_history_page, load_history_page, next_distribution_history, record_distribution,
+    },
     error::StakingError,
-    math::{wad_div, WAD},
-    state::StakingPool,
+    introspection::{next_instructions_sysvar, reject_prior_stake},
+    loaders::load_pool,
+    matching::{draw_match, next_matching_vault},
+    state::{DistributionHistory, StakingPool},
+    time::pool_time,
 };
 
 /// Deposit SOL rewards into the pool
-/// Anyone can call this (permissionless)
+/// Anyone can call this (permissionless), unless the pool keeps a depositor
+/// allowlist. Within min_sync_interval_seconds of the last distribution
+/// the deposit is accepted but left pending.
 ///
 /// Accounts:
 /// 0. `[writable]` Pool account (receives SOL)
 /// 1. `[writable, signer]` Depositor
 /// 2. `[]` System program
+/// 3. `[]` Depositor allowlist (pools with an allowlist only)
+/// 4. `[writable]` Matching vault (pools with reward matching only)
+/// 5. `[writable]` Current distribution history page (pools with a
+///    distribution history only); created, at the depositor's expense,
+///    when a distribution needs it
+/// 6. `[writable]` Depositor record (pools that track depositors only);
+///    created, at the depositor's expense, on their first deposit
+/// 7. `[]` (optional) Instructions sysvar; required on pools with
+///    stake_guard_required
 pub fn process_deposit_rewards(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -47,35 +63,38 @@ pub fn process_deposit_rewards(
     }
 
     // Load and validate pool
-    if pool_info.owner != program_id {
-        return Err(StakingError::InvalidAccountOwner.into());
-    }
-    let mut pool = StakingPool::try_from_slice(&pool_info.try_borrow_data()?)?;
-    if !pool.is_initialized() {
-        return Err(StakingError::NotInitialized.into());
-    }
+    let mut pool = load_pool(program_id, pool_info)?;
 
-    // Verify pool PDA
-    let (expected_pool, _) = StakingPool::derive_pda(&pool.mint, program_id);
-    if *pool_info.key != expected_pool {
-        return Err(StakingError::InvalidPDA.into());
-    }
+    // Pools with an allowlist must pass it, and it must list the depositor
+    check_depositor(program_id, pool_info.key, &pool, depositor_info.key, account_info_iter)?;
+
+    // Pools with reward matching must pass their matching vault
+    let vault_info = next_matching_vault(program_id, pool_info.key, &pool, account_info_iter)?;
+
+    // Pools with a distribution history must pass the current page
+    let history_info = next_distribution_history(&pool, account_info_iter)?;
 
-    let clock = Clock::get()?;
-    let current_time = clock.unix_timestamp;
+    // Pools that track depositors must pass the depositor's record
+    let record_info = next_depositor_record(&pool, account_info_iter)?;
+
+    // Pools with stake_guard_required must pass the instructions sysvar
+    let instructions_sysvar = next_instructions_sysvar(&pool, account_info_iter)?;
+
+    let current_time = pool_time(&pool)?;
+
+    // Grow legacy pools so new accounting fields fit (payer = depositor)
+    StakingPool::maybe_realloc(pool_info, depositor_info, Some(system_program_info))?;
 
     let rent = Rent::get()?;
     let rent_exempt_minimum = rent.minimum_balance(pool_info.data_len());
 
-    // Denominator: total_staked * WAD (max weight, not time-varying)
-    let total_staked_wad = (pool.total_staked as u128)
-        .checked_mul(WAD)
-        .ok_or(StakingError::MathOverflow)?;
-
-    if total_staked_wad == 0 {
-        // No stakers to distribute to.
+    let too_soon = pool.distribution_too_soon(current_time);
+    if pool.below_distribution_threshold() || pool.rewards_paused || too_soon {
+        // No stakers (or less than min_distribution_stake) to distribute to,
+        // distribution is paused, or the last one was too recent.
         // Accept the deposit but do NOT update last_synced_lamports so the
-        // rewards remain pending and will be distributed once someone stakes.
+        // rewards remain pending and will be distributed once someone stakes
+        // (or distribution resumes).
         invoke(
             &system_instruction::transfer(depositor_info.key, pool_info.key, amount),
             &[
@@ -84,33 +103,81 @@ pub fn process_deposit_rewards(
                 system_program_info.clone(),
             ],
         )?;
-
-        msg!(
-            "Deposited {} lamports (deferred - no stakers)",
-            amount,
-        );
+        if let Some(record_info) = record_info {
+            record_deposit(
+                program_id,
+                record_info,
+                pool_info.key,
+                depositor_info,
+                system_program_info,
+                amount,
+                current_time,
+            )?;
+        }
+
+        if !pool.rewards_paused && pool.below_distribution_threshold() {
+            pool.mark_undistributed(current_time);
+        }
+        pool.record_undistributed(pool_info, &rent);
+        pool.save(pool_info)?;
+
+        if pool.rewards_paused {
+            msg!("Deposited {} lamports (deferred - rewards paused)", amount);
+        } else if pool.below_distribution_threshold() {
+            msg!("Deposited {} lamports (deferred - stake below threshold)", amount);
+        } else {
+            msg!("Deposited {} lamports (deferred - minimum sync interval)", amount);
+        }
         return Ok(());
     }
 
+    // Sandwich guard: refuse to distribute if this transaction already
+    // staked into the pool
+    if let Some(instructions_sysvar) = instructions_sysvar {
+        reject_prior_stake(instructions_sysvar, program_id, pool_info.key)?;
+    }
+
+    // Every distribution is checkpointed; the depositor creates a missing page
+    let history = match history_info {
+        Some(page_info) => {
+            let history = match load_history_page(program_id, page_info, pool_info.key, &pool)? {
+                Some(history) => history,
+                None => create_history_page(
+                    program_id,
+                    page_info,
+                    pool_info.key,
+                    DistributionHistory::page_of(pool.distribution_checkpoints),
+                    depositor_info,
+                    system_program_info,
+                )?,
+            };
+            Some((page_info, history))
+        }
+        None => None,
+    };
+
     // Include any previously undistributed rewards alongside this deposit.
     let current_available = pool_info.lamports().saturating_sub(rent_exempt_minimum);
     let undistributed = current_available.saturating_sub(pool.last_synced_lamports);
     let total_new_rewards = amount.saturating_add(undistributed);
 
-    // Calculate reward per share using max weight denominator
-    // reward_per_share = total_new_rewards * WAD / (total_staked * WAD)
-    let amount_wad = (total_new_rewards as u128)
-        .checked_mul(WAD)
-        .ok_or(StakingError::MathOverflow)?;
-    let reward_per_share = wad_div(amount_wad, total_staked_wad)?;
+    // Matching vaults add their share of the distribution (moved in directly,
+    // so it is counted by the lamports-based sync below)
+    let matched = match vault_info {
+        Some(vault_info) => draw_match(vault_info, pool_info, total_new_rewards)?,
+        None => 0,
+    };
+    let total_new_rewards = total_new_rewards.saturating_add(matched);
 
-    // Update accumulator
-    pool.acc_reward_per_weighted_share = pool
-        .acc_reward_per_weighted_share
-        .checked_add(reward_per_share)
-        .ok_or(StakingError::MathOverflow)?;
+    // Calculate reward per share using max weight denominator and update
+    // the accumulator: reward_per_share = total_new_rewards * WAD / (total_staked * WAD)
+    let reward_per_share = pool.distribute_rewards(total_new_rewards)?;
+    pool.vest_distribution(current_time, reward_per_share);
+    pool.record_reward_rate(current_time, reward_per_share)?;
 
     pool.last_update_time = current_time;
+    pool.last_distribution_time = current_time;
+    pool.undistributed_since = 0;
 
     // Transfer SOL from depositor to pool (before serialization so lamports() is updated)
     invoke(
@@ -121,20 +188,33 @@ pub fn process_deposit_rewards(
             system_program_info.clone(),
         ],
     )?;
+    if let Some(record_info) = record_info {
+        record_deposit(
+            program_id,
+            record_info,
+            pool_info.key,
+            depositor_info,
+            system_program_info,
+            amount,
+            current_time,
+        )?;
+    }
 
     // Update last_synced_lamports so sync_rewards doesn't double-count
     pool.last_synced_lamports = pool_info.lamports().saturating_sub(rent_exempt_minimum);
+    pool.record_undistributed(pool_info, &rent);
+    if let Some((page_info, mut history)) = history {
+        record_distribution(page_info, &mut history, &mut pool, current_time, total_new_rewards)?;
+    }
 
     // Save pool state
-    {
-        let mut pool_data = pool_info.try_borrow_mut_data()?;
-        pool.serialize(&mut &mut pool_data[..])?;
-    }
+    pool.save(pool_info)?;
 
     msg!(
-        "Deposited {} lamports (distributed {} total), total_staked: {}, reward_per_share: {}",
+        "Deposited {} lamports (distributed {} total, {} matched), total_staked: {}, reward_per_share: {}",
         amount,
         total_new_rewards,
+        matched,
         pool.total_staked,
         reward_per_share
     );
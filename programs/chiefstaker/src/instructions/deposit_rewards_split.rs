@@ -0,0 +1,181 @@
+//! Deposit rewards staged for timed release
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    depositors::{check_depositor, next_depositor_record, record_deposit},
+    error::{ErrorContext, StakingError},
+    loaders::load_pool,
+    state::{
+        PendingDistributions, PENDING_DISTRIBUTIONS_DISCRIMINATOR,
+        PENDING_DISTRIBUTIONS_SEED,
+    },
+    time::pool_time,
+};
+
+/// Stage a SOL deposit as timed tranches in the pool's PendingDistributions.
+/// Anyone can call this (permissionless) unless the pool keeps a depositor
+/// allowlist; ReleasePendingDistributions later moves each tranche into the
+/// pool once it is due.
+///
+/// Accounts:
+/// 0. `[]` Pool account
+/// 1. `[writable]` Pending distributions (PDA: ["pending_distributions", pool])
+/// 2. `[writable, signer]` Depositor
+/// 3. `[]` System program
+/// 4. `[]` Depositor allowlist (pools with an allowlist only)
+/// 5. `[writable]` Depositor record (pools that track depositors only);
+///    created, at the depositor's expense, on their first deposit
+pub fn process_deposit_rewards_split(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    schedule: Vec<(u64, u16)>,
+) -> ProgramResult {
+    if amount == 0 {
+        return Err(StakingError::ZeroAmount.into());
+    }
+
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let pending_info = next_account_info(account_info_iter)?;
+    let depositor_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Validate depositor is signer
+    if !depositor_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let pool = load_pool(program_id, pool_info)?;
+
+    // Pools with an allowlist must pass it, and it must list the depositor
+    check_depositor(program_id, pool_info.key, &pool, depositor_info.key, account_info_iter)?;
+
+    // Pools that track depositors must pass the depositor's record
+    let record_info = next_depositor_record(&pool, account_info_iter)?;
+
+    let current_time = pool_time(&pool)?;
+    let new_tranches = PendingDistributions::split_schedule(amount, &schedule, current_time)?;
+
+    // Verify pending distributions PDA
+    let (expected_pending, pending_bump) =
+        PendingDistributions::derive_pda(pool_info.key, program_id);
+    if *pending_info.key != expected_pending {
+        return Err(StakingError::InvalidPDA.into());
+    }
+
+    let rent = Rent::get()?;
+
+    let (mut pending, mut tranches) = if pending_info.data_is_empty() {
+        let space = PendingDistributions::space(new_tranches.len());
+        let pending_seeds = &[
+            PENDING_DISTRIBUTIONS_SEED,
+            pool_info.key.as_ref(),
+            &[pending_bump],
+        ];
+        invoke_signed(
+            &system_instruction::create_account(
+                depositor_info.key,
+                pending_info.key,
+                rent.minimum_balance(space),
+                space as u64,
+                program_id,
+            ),
+            &[
+                depositor_info.clone(),
+                pending_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[pending_seeds],
+        )?;
+
+        let pending = PendingDistributions {
+            discriminator: PENDING_DISTRIBUTIONS_DISCRIMINATOR,
+            pool: *pool_info.key,
+            count: 0,
+            bump: pending_bump,
+        };
+        (pending, Vec::new())
+    } else {
+        if pending_info.owner != program_id {
+            return Err(StakingError::InvalidAccountOwner.into());
+        }
+        let data = pending_info.try_borrow_data()?;
+        let header = data
+            .get(..PendingDistributions::HEADER_LEN)
+            .ok_or(StakingError::AccountDataTooSmall)?;
+        let pending = PendingDistributions::try_from_slice(header)?;
+        if !pending.is_initialized() {
+            return Err(StakingError::NotInitialized.into());
+        }
+        let tranches = pending.tranches(&data)?;
+        (pending, tranches)
+    };
+
+    tranches.extend_from_slice(&new_tranches);
+    if tranches.len() > PendingDistributions::MAX_TRANCHES {
+        return Err(StakingError::PendingDistributionsFull.with_context(
+            ErrorContext::MaximumAmount(PendingDistributions::MAX_TRANCHES as u64),
+        ));
+    }
+
+    // Grow the account if the tranches don't fit; the staged lamports sit on
+    // top of the larger rent-exempt minimum
+    let space = PendingDistributions::space(tranches.len());
+    let rent_delta = if pending_info.data_len() < space {
+        rent.minimum_balance(space)
+            .saturating_sub(rent.minimum_balance(pending_info.data_len()))
+    } else {
+        0
+    };
+    invoke(
+        &system_instruction::transfer(
+            depositor_info.key,
+            pending_info.key,
+            amount.checked_add(rent_delta).ok_or(StakingError::MathOverflow)?,
+        ),
+        &[
+            depositor_info.clone(),
+            pending_info.clone(),
+            system_program_info.clone(),
+        ],
+    )?;
+    if pending_info.data_len() < space {
+        pending_info.realloc(space, false)?;
+    }
+
+    pending.write(&mut pending_info.try_borrow_mut_data()?, &tranches)?;
+    if let Some(record_info) = record_info {
+        record_deposit(
+            program_id,
+            record_info,
+            pool_info.key,
+            depositor_info,
+            system_program_info,
+            amount,
+            current_time,
+        )?;
+    }
+
+    msg!(
+        "Staged {} lamports in {} tranches ({} pending)",
+        amount,
+        new_tranches.len(),
+        tranches.len()
+    );
+
+    Ok(())
+}
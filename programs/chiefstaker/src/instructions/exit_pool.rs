@@ -0,0 +1,148 @@
+//! Exit pool instruction - unstake, claim and close in one step
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::StakingError,
+    guardian::check_guardian_signature,
+    history::next_weight_history,
+    lock::next_lock_schedule,
+    memo::next_memo_program,
+    rebate::next_gas_rebate_vault,
+    receipt::next_receipt_accounts,
+    recipient::next_reward_payee,
+    sponsor::next_rent_sponsor,
+    state::{StakingPool, UserStake},
+    time::pool_time,
+};
+
+use super::{
+    claim::next_burn_destination, process_claim_rewards, process_close_stake_account,
+    process_complete_unstake, process_unstake, unstake::next_fee_destination,
+};
+
+/// Leave the pool in one instruction: complete the position's pending
+/// unstake request (or, without one, unstake everything as UnstakeAll
+/// does), claim whatever residual rewards the pool can pay, and close the
+/// stake account. Each step runs the same checks as its own instruction,
+/// and the exit fails as a whole if the account can't be closed (a
+/// cooldown pool without a completable request, a request for part of the
+/// stake, an unpaid residual, or rewards still vesting).
+///
+/// Every conditional account is passed whenever its pool or position
+/// flag is set, whether or not its step has anything to do.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable]` User stake account
+/// 2. `[writable]` Token vault
+/// 3. `[writable]` User token account
+/// 4. `[]` Token mint
+/// 5. `[writable, signer]` User/owner
+/// 6. `[]` Token 2022 program
+/// 7. `[]` System program
+/// 8. `[writable]` Receipt mint (receipt pools only)
+/// 9. `[writable]` User receipt token account (receipt pools only, burned from)
+/// 10. `[writable]` Unstake fee destination (pools with an unstake fee only)
+/// 11. `[writable]` Weight history (positions with history only)
+/// 12. `[]` Payout split, then `[writable]` each of its destinations in
+///     order (positions with a payout split only); otherwise `[writable]`
+///     Reward recipient (positions with a declared recipient only)
+/// 13. `[]` Lock schedule (positions with lock tranches only)
+/// 14. `[signer]` Guardian (positions with a guardian only)
+/// 15. `[]` Memo program (user token accounts requiring memos only)
+/// 16. `[writable]` Gas rebate vault (pools with claim rebates only)
+/// 17. `[writable]` Burn destination (pools with a claim burn only)
+/// 18. `[writable]` Rent sponsor vault (sponsored accounts of pools with a
+///     rent sponsor only)
+/// 19. `[writable]` (optional) Pool metadata (member count)
+pub fn process_exit_pool(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let user_stake_info = next_account_info(account_info_iter)?;
+    let _token_vault_info = next_account_info(account_info_iter)?;
+    let user_token_info = next_account_info(account_info_iter)?;
+    let _mint_info = next_account_info(account_info_iter)?;
+    let user_info = next_account_info(account_info_iter)?;
+    let _token_program_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // The flags deciding which accounts follow; each step validates the
+    // pool and position in full
+    if pool_info.owner != program_id || user_stake_info.owner != program_id {
+        return Err(StakingError::InvalidAccountOwner.into());
+    }
+    let pool = StakingPool::try_from_slice(&pool_info.try_borrow_data()?)?;
+    if !pool.is_initialized() {
+        return Err(StakingError::NotInitialized.into());
+    }
+    let user_stake = UserStake::try_from_slice(&user_stake_info.try_borrow_data()?)?;
+    if !user_stake.is_initialized() {
+        return Err(StakingError::NotInitialized.into());
+    }
+    let completes_request = user_stake.has_pending_unstake_request(&pool, pool_time(&pool)?);
+
+    // Split the conditional accounts between the three steps
+    let rest = account_info_iter.as_slice();
+    let iter = &mut rest.iter();
+    next_receipt_accounts(&pool, iter)?;
+    next_fee_destination(&pool, iter)?;
+    next_weight_history(&user_stake, iter)?;
+    let payee_start = rest.len() - iter.len();
+    next_reward_payee(program_id, &user_stake, user_info, iter)?;
+    let payee_end = rest.len() - iter.len();
+    next_lock_schedule(&user_stake, iter)?;
+    let lock_end = rest.len() - iter.len();
+    check_guardian_signature(&user_stake, iter)?;
+    next_memo_program(user_token_info, iter)?;
+    let unstake_end = rest.len() - iter.len();
+    next_gas_rebate_vault(program_id, pool_info.key, &pool, iter)?;
+    next_burn_destination(&pool, iter)?;
+    let claim_end = rest.len() - iter.len();
+    if user_stake.sponsored_rent > 0 {
+        next_rent_sponsor(program_id, pool_info.key, &pool, iter)?;
+    }
+
+    // 1. Unstake: CompleteUnstake takes no lock schedule (the lock was
+    //    checked when the request was made)
+    if completes_request || user_stake.amount > 0 {
+        let mut unstake_accounts: Vec<AccountInfo> = accounts[..7].to_vec();
+        if completes_request {
+            unstake_accounts.extend_from_slice(&rest[..payee_end]);
+            unstake_accounts.extend_from_slice(&rest[lock_end..unstake_end]);
+        } else {
+            unstake_accounts.extend_from_slice(&rest[..unstake_end]);
+        }
+        unstake_accounts.push(system_program_info.clone());
+        if completes_request {
+            process_complete_unstake(program_id, &unstake_accounts)?;
+        } else {
+            process_unstake(program_id, &unstake_accounts, None)?;
+        }
+    }
+
+    // 2. Claim: after a full unstake only residual rewards are left
+    let mut claim_accounts = vec![pool_info.clone(), user_stake_info.clone(), user_info.clone()];
+    claim_accounts.extend_from_slice(&rest[payee_start..payee_end]);
+    claim_accounts.extend_from_slice(&rest[unstake_end..claim_end]);
+    claim_accounts.push(system_program_info.clone());
+    process_claim_rewards(program_id, &claim_accounts)?;
+
+    // 3. Close: rent sponsor vault and optional metadata
+    let mut close_accounts = vec![pool_info.clone(), user_stake_info.clone(), user_info.clone()];
+    close_accounts.extend_from_slice(&rest[claim_end..]);
+    process_close_stake_account(program_id, &close_accounts)?;
+
+    msg!("Exited pool");
+    Ok(())
+}
@@ -0,0 +1,117 @@
+//! Extend lock instruction - trade a longer lock for an older stake
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::{ErrorContext, StakingError},
+    history::{next_weight_history, record_checkpoint},
+    loaders::{load_pool, load_user_stake},
+    state::UserStake,
+    time::pool_time,
+};
+
+/// Furthest ahead a position can be locked (matches the pool lock cap)
+const MAX_LOCK_AHEAD_SECONDS: u64 = 365 * 24 * 60 * 60;
+
+/// Lock the position for `additional_seconds` more, counted from the later
+/// of now and its current lock end. In exchange the stake is treated as
+/// `additional_seconds * lock_boost_bps / 10000` seconds older, so its
+/// weight approaches the maximum sooner. Rewards already accrued at full
+/// weight vest at the new maturity.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable]` User stake account
+/// 2. `[writable, signer]` User/owner
+/// 3. `[writable]` Weight history (positions with history only)
+/// 4. `[]` (optional) System program (legacy account reallocation)
+pub fn process_extend_lock(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    additional_seconds: u64,
+) -> ProgramResult {
+    if additional_seconds == 0 {
+        return Err(StakingError::ZeroAmount.into());
+    }
+
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let user_stake_info = next_account_info(account_info_iter)?;
+    let user_info = next_account_info(account_info_iter)?;
+
+    // Validate user is signer
+    if !user_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    if pool.lock_boost_bps == 0 {
+        return Err(StakingError::LockBoostDisabled.into());
+    }
+
+    let current_time = pool_time(&pool)?;
+
+    // Rebase inline if sum_stake_exp is near overflow (saved with the pool below)
+    pool.rebase_if_needed(current_time)?;
+
+    // Load and validate user stake
+    let mut user_stake =
+        load_user_stake(program_id, user_stake_info, pool_info.key, user_info.key)?;
+
+    if user_stake.amount == 0 {
+        return Err(StakingError::InsufficientStakeBalance
+            .with_context(ErrorContext::AvailableAmount(0)));
+    }
+
+    // Tokens on their way out don't get a boost
+    if user_stake.has_pending_unstake_request(&pool, current_time) {
+        return Err(StakingError::PendingUnstakeRequestExists.into());
+    }
+
+    // New lock end: extend from the current lock end, or from now if unlocked
+    let lock_remaining = user_stake.lock_remaining_seconds(&pool, current_time);
+    let lock_ahead = lock_remaining.saturating_add(additional_seconds);
+    if lock_ahead > MAX_LOCK_AHEAD_SECONDS {
+        return Err(StakingError::SettingExceedsMaximum.with_context(ErrorContext::MaximumAmount(
+            MAX_LOCK_AHEAD_SECONDS.saturating_sub(lock_remaining),
+        )));
+    }
+    user_stake.lock_until = current_time.saturating_add(lock_ahead as i64);
+
+    // Positions with a weight history must pass it
+    let history_info = next_weight_history(&user_stake, account_info_iter)?;
+
+    // Realloc legacy accounts to current size (payer = user)
+    let system_program_info = account_info_iter.next();
+    UserStake::maybe_realloc(user_stake_info, user_info, system_program_info)?;
+
+    // Lazily adjust exp_start_factor if pool has been rebased
+    user_stake.sync_to_pool(&pool)?;
+
+    let bonus_seconds =
+        (additional_seconds as u128 * pool.lock_boost_bps as u128 / 10_000) as u64;
+    user_stake.apply_age_bonus(&mut pool, bonus_seconds)?;
+
+    if let Some(history_info) = history_info {
+        record_checkpoint(program_id, history_info, pool_info.key, &pool, &user_stake, current_time)?;
+    }
+
+    user_stake.save(user_stake_info)?;
+    pool.save(pool_info)?;
+
+    msg!(
+        "Lock extended to {} ({} seconds older)",
+        user_stake.lock_until,
+        bonus_seconds
+    );
+
+    Ok(())
+}
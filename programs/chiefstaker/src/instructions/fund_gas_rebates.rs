@@ -0,0 +1,77 @@
+//! Fund gas rebates instruction
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    pubkey::Pubkey,
+    system_instruction,
+};
+
+use crate::{
+    error::StakingError,
+    loaders::load_pool,
+    rebate::next_gas_rebate_vault,
+    state::GasRebateVault,
+};
+
+/// Add lamports to a pool's GasRebateVault, which pays claim rebates
+/// (SetClaimRebate). Anyone can call this. Funding is tracked apart from
+/// rewards: it never reaches the pool and is never distributed to stakers.
+///
+/// Accounts:
+/// 0. `[]` Pool account
+/// 1. `[writable]` Gas rebate vault (PDA: ["gas_rebate", pool])
+/// 2. `[writable, signer]` Funder
+/// 3. `[]` System program
+pub fn process_fund_gas_rebates(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+) -> ProgramResult {
+    if amount == 0 {
+        return Err(StakingError::ZeroAmount.into());
+    }
+
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+
+    // Load and validate pool
+    let pool = load_pool(program_id, pool_info)?;
+
+    // Only pools with claim rebates enabled have a vault to fund
+    let vault_info = next_gas_rebate_vault(program_id, pool_info.key, &pool, account_info_iter)?
+        .ok_or(StakingError::NotInitialized)?;
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    if !funder_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    invoke(
+        &system_instruction::transfer(funder_info.key, vault_info.key, amount),
+        &[
+            funder_info.clone(),
+            vault_info.clone(),
+            system_program_info.clone(),
+        ],
+    )?;
+
+    let mut vault = GasRebateVault::try_from_slice(&vault_info.try_borrow_data()?)?;
+    vault.total_funded = vault.total_funded.saturating_add(amount);
+    vault.serialize(&mut &mut vault_info.try_borrow_mut_data()?[..])?;
+
+    msg!(
+        "Funded gas rebates with {} lamports (total funded: {}, rebated: {})",
+        amount,
+        vault.total_funded,
+        vault.total_rebated
+    );
+
+    Ok(())
+}
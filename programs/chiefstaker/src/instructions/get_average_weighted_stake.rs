@@ -0,0 +1,55 @@
+//! Average weighted stake of a position over a period, via return data
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program::set_return_data,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::StakingError,
+    loaders::load_pool,
+    state::WeightHistory,
+};
+
+/// Return the time-weighted average weighted stake (u128 LE, WAD-scaled)
+/// of a position over [from, to], computed from its WeightHistory.
+/// Read-only; other programs can CPI into it and read the return data.
+///
+/// Accounts:
+/// 0. `[]` Pool account
+/// 1. `[]` Weight history
+pub fn process_get_average_weighted_stake(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    from: i64,
+    to: i64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let history_info = next_account_info(account_info_iter)?;
+
+    // Load and validate pool
+    let pool = load_pool(program_id, pool_info)?;
+
+    // Load and validate history
+    if history_info.owner != program_id {
+        return Err(StakingError::InvalidAccountOwner.into());
+    }
+    let data = history_info.try_borrow_data()?;
+    let history = WeightHistory::deserialize(&mut &data[..])?;
+    if !history.is_initialized() {
+        return Err(StakingError::NotInitialized.into());
+    }
+    if history.pool != *pool_info.key {
+        return Err(StakingError::InvalidPool.into());
+    }
+
+    let average = history.average_weighted_stake(&data, pool.tau_seconds, from, to)?;
+    set_return_data(&average.to_le_bytes());
+
+    Ok(())
+}
@@ -0,0 +1,229 @@
+//! Read-only pool statistics, returned through return data
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program::set_return_data,
+    pubkey::Pubkey,
+};
+use spl_token_2022::extension::StateWithExtensions;
+
+use crate::{
+    error::StakingError,
+    loaders::load_pool,
+    math::U256,
+    oracle::{read_price, usd_apy_bps, usd_value_wad, OraclePrice},
+    state::StakingPool,
+    time::pool_time,
+};
+
+use super::get_user_stats::SECONDS_PER_YEAR;
+
+/// Pool-wide statistics, Borsh-encoded into return data. USD fields are 0
+/// when the pool has no price oracle (or, for the APY, no SOL price was
+/// passed).
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PoolStats {
+    /// Total staked tokens (raw units)
+    pub total_staked: u128,
+    /// Seconds since the pool was created
+    pub elapsed_seconds: u64,
+    /// Lifetime reward rate of a fully matured position: lamports per raw
+    /// token unit per year, WAD-scaled. 0 before any time has passed.
+    pub reward_rate_wad: u128,
+    /// Token price from the pool's oracle (USD per whole token, WAD-scaled)
+    pub token_price_wad: u128,
+    /// Publish time of the token price
+    pub token_price_time: i64,
+    /// SOL price from the optional SOL/USD account (USD per SOL, WAD-scaled)
+    pub sol_price_wad: u128,
+    /// Publish time of the SOL price
+    pub sol_price_time: i64,
+    /// Value of the staked tokens (USD, WAD-scaled)
+    pub tvl_usd_wad: u128,
+    /// Reward APY in USD terms (basis points) at reward_rate_wad
+    pub reward_apy_bps: u64,
+    /// Lamports not yet credited to the accumulator, as of the last
+    /// deposit, sync or refund (StakingPool::undistributed_lamports)
+    pub undistributed_lamports: u64,
+    /// Trailing reward rate of a fully matured position: 7-day moving
+    /// average of lamports per raw token unit per day, WAD-scaled
+    /// (StakingPool::trailing_reward_rate)
+    pub trailing_reward_rate_wad: u128,
+    /// Reward APY in USD terms (basis points) at the trailing reward rate
+    pub trailing_apy_bps: u64,
+}
+
+/// Compute pool statistics and publish them via `set_return_data`, with
+/// TVL and reward APY in USD on pools with a price oracle (SetPriceOracle).
+/// Prices are reported with their publish times; callers judge staleness.
+/// Intended for simulation; does not modify any account.
+///
+/// Accounts:
+/// 0. `[]` Pool account
+/// 1. `[]` Token mint
+/// 2. `[]` Price account (pools with a price oracle only)
+/// 3. `[]` (optional) SOL/USD price account (Pyth or Switchboard), for
+///    the APY
+pub fn process_get_pool_stats(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+
+    // Load and validate pool
+    let pool = load_pool(program_id, pool_info)?;
+
+    // Verify mint matches pool
+    if pool.mint != *mint_info.key {
+        return Err(StakingError::InvalidPoolMint.into());
+    }
+    let decimals = {
+        let mint_data = mint_info.try_borrow_data()?;
+        StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?
+            .base
+            .decimals
+    };
+
+    // Pools with a price oracle must pass it; the SOL price may follow
+    let (token_price, sol_price) = if pool.has_price_oracle() {
+        let oracle_info = next_account_info(account_info_iter)?;
+        if *oracle_info.key != pool.price_oracle {
+            return Err(StakingError::InvalidOracle.into());
+        }
+        let token_price = read_price(oracle_info)?;
+        let sol_price = account_info_iter.next().map(read_price).transpose()?;
+        (Some(token_price), sol_price)
+    } else {
+        (None, None)
+    };
+
+    let stats = pool_stats(
+        &pool,
+        decimals,
+        token_price,
+        sol_price,
+        pool_time(&pool)?,
+    )?;
+    set_return_data(&borsh::to_vec(&stats)?);
+
+    Ok(())
+}
+
+/// Build the statistics at `current_time` from already validated prices
+pub fn pool_stats(
+    pool: &StakingPool,
+    decimals: u8,
+    token_price: Option<OraclePrice>,
+    sol_price: Option<OraclePrice>,
+    current_time: i64,
+) -> Result<PoolStats, StakingError> {
+    let elapsed_seconds = current_time.saturating_sub(pool.creation_time()).max(0) as u64;
+    let reward_rate_wad = if elapsed_seconds == 0 {
+        0
+    } else {
+        let numerator = U256::from_u128(pool.acc_reward_per_weighted_share)
+            * U256::from(SECONDS_PER_YEAR);
+        (numerator / U256::from(elapsed_seconds))
+            .to_u128()
+            .unwrap_or(u128::MAX)
+    };
+
+    let trailing_reward_rate_wad = pool.trailing_reward_rate(current_time)?;
+
+    let (token_price_wad, token_price_time) =
+        token_price.map_or((0, 0), |price| (price.price_wad, price.publish_time));
+    let (sol_price_wad, sol_price_time) =
+        sol_price.map_or((0, 0), |price| (price.price_wad, price.publish_time));
+
+    let tvl_usd_wad = usd_value_wad(pool.total_staked, decimals, token_price_wad)
+        .ok_or(StakingError::MathOverflow)?;
+    let reward_apy_bps = if token_price_wad == 0 || sol_price_wad == 0 {
+        0
+    } else {
+        usd_apy_bps(reward_rate_wad, decimals, sol_price_wad, token_price_wad)
+            .unwrap_or(u64::MAX)
+    };
+    let trailing_apy_bps = if token_price_wad == 0 || sol_price_wad == 0 {
+        0
+    } else {
+        let yearly = trailing_reward_rate_wad.saturating_mul(365);
+        usd_apy_bps(yearly, decimals, sol_price_wad, token_price_wad).unwrap_or(u64::MAX)
+    };
+
+    Ok(PoolStats {
+        total_staked: pool.total_staked,
+        elapsed_seconds,
+        reward_rate_wad,
+        token_price_wad,
+        token_price_time,
+        sol_price_wad,
+        sol_price_time,
+        tvl_usd_wad,
+        reward_apy_bps,
+        undistributed_lamports: pool.undistributed_lamports,
+        trailing_reward_rate_wad,
+        trailing_apy_bps,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::WAD;
+
+    #[test]
+    fn test_pool_stats() {
+        let mut pool = StakingPool::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            3_600,
+            0,
+            255,
+        );
+        pool.total_staked = 1_000_000_000; // 1,000 tokens at 6 decimals
+        // 10 lamports per raw unit over half a year
+        pool.acc_reward_per_weighted_share = 10 * WAD;
+        pool.undistributed_lamports = 42;
+        let half_year = (SECONDS_PER_YEAR / 2) as i64;
+
+        // No oracle: no USD figures
+        let stats = pool_stats(&pool, 6, None, None, half_year).unwrap();
+        assert_eq!(stats.reward_rate_wad, 20 * WAD);
+        assert_eq!(stats.tvl_usd_wad, 0);
+        assert_eq!(stats.reward_apy_bps, 0);
+        assert_eq!(stats.undistributed_lamports, 42);
+        assert_eq!(stats.trailing_reward_rate_wad, 0);
+
+        // Token at $3, SOL at $150: 0.02 SOL ($3) per $3 token a year
+        let token = OraclePrice { price_wad: 3 * WAD, publish_time: 10 };
+        let sol = OraclePrice { price_wad: 150 * WAD, publish_time: 20 };
+        let stats = pool_stats(&pool, 6, Some(token), Some(sol), half_year).unwrap();
+        assert_eq!(stats.tvl_usd_wad, 3_000 * WAD);
+        assert_eq!(stats.reward_apy_bps, 10_000);
+        assert_eq!((stats.token_price_time, stats.sol_price_time), (10, 20));
+
+        // The same yearly rate, paid daily over the last weeks
+        let mut time = half_year - 8 * 7 * 86_400;
+        while time < half_year {
+            time += 86_400;
+            pool.record_reward_rate(time, 20 * WAD / 365).unwrap();
+        }
+        // (read half a day after the last payout, between two steps)
+        let stats = pool_stats(&pool, 6, Some(token), Some(sol), half_year + 43_200).unwrap();
+        assert!(stats.trailing_apy_bps.abs_diff(10_000) < 100);
+
+        // Without the SOL price only the TVL is known
+        let stats = pool_stats(&pool, 6, Some(token), None, half_year).unwrap();
+        assert_eq!(stats.tvl_usd_wad, 3_000 * WAD);
+        assert_eq!(stats.reward_apy_bps, 0);
+
+        assert_eq!(pool_stats(&pool, 6, None, None, 0).unwrap().reward_rate_wad, 0);
+    }
+}
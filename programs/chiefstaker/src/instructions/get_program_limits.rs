@@ -0,0 +1,98 @@
+//! Read-only program limits, returned through return data
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{entrypoint::ProgramResult, program::set_return_data};
+
+use crate::{
+    math::MAX_EXP_INPUT,
+    state::{ProgramConfig, StakingPool},
+    version::PROGRAM_VERSION,
+};
+
+use super::{
+    initialize::{MAX_TAU_SECONDS, MIN_TAU_SECONDS},
+    update_settings::{MAX_LOCK_DURATION_SECONDS, MAX_MIN_STAKE_AMOUNT, MAX_UNSTAKE_COOLDOWN_SECONDS},
+};
+
+/// Bounds the deployed program enforces on instruction inputs,
+/// Borsh-encoded into return data. Fields may be appended in later
+/// versions; decoders should tolerate trailing bytes.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ProgramLimits {
+    /// Program version (major, minor, patch)
+    pub version: [u16; 3],
+    /// Smallest tau accepted by InitializePool
+    pub min_tau_seconds: u64,
+    /// Largest tau accepted by InitializePool
+    pub max_tau_seconds: u64,
+    /// Largest lock_duration_seconds accepted by UpdatePoolSettings
+    pub max_lock_duration_seconds: u64,
+    /// Largest unstake_cooldown_seconds accepted by UpdatePoolSettings
+    pub max_unstake_cooldown_seconds: u64,
+    /// Largest min_stake_amount accepted by UpdatePoolSettings (raw units).
+    /// The distribution threshold has no fixed cap: SetDistributionThreshold
+    /// bounds it by the mint's current supply.
+    pub max_min_stake_amount: u64,
+    /// Largest unstake fee (basis points)
+    pub max_unstake_fee_bps: u16,
+    /// Largest lock boost (basis points)
+    pub max_lock_boost_bps: u16,
+    /// Largest maturity bonus (basis points)
+    pub max_maturity_bonus_bps: u16,
+    /// Largest reward match (basis points)
+    pub max_match_bps: u16,
+    /// Largest protocol fee (basis points)
+    pub max_protocol_fee_bps: u16,
+    /// Largest bootstrap period
+    pub max_bootstrap_period_seconds: u64,
+    /// Largest minimum interval between distributions
+    pub max_min_sync_interval_seconds: u64,
+    /// Largest claim vesting period
+    pub max_claim_vesting_seconds: u64,
+    /// Largest (now - base_time) / tau (WAD) before stakes need a SyncPool
+    pub max_exp_input: u128,
+}
+
+/// Current limits of this program build
+pub fn program_limits() -> ProgramLimits {
+    ProgramLimits {
+        version: PROGRAM_VERSION,
+        min_tau_seconds: MIN_TAU_SECONDS,
+        max_tau_seconds: MAX_TAU_SECONDS,
+        max_lock_duration_seconds: MAX_LOCK_DURATION_SECONDS,
+        max_unstake_cooldown_seconds: MAX_UNSTAKE_COOLDOWN_SECONDS,
+        max_min_stake_amount: MAX_MIN_STAKE_AMOUNT,
+        max_unstake_fee_bps: StakingPool::MAX_UNSTAKE_FEE_BPS,
+        max_lock_boost_bps: StakingPool::MAX_LOCK_BOOST_BPS,
+        max_maturity_bonus_bps: StakingPool::MAX_MATURITY_BONUS_BPS,
+        max_match_bps: StakingPool::MAX_MATCH_BPS,
+        max_protocol_fee_bps: ProgramConfig::MAX_PROTOCOL_FEE_BPS,
+        max_bootstrap_period_seconds: StakingPool::MAX_BOOTSTRAP_PERIOD_SECONDS,
+        max_min_sync_interval_seconds: StakingPool::MAX_MIN_SYNC_INTERVAL_SECONDS,
+        max_claim_vesting_seconds: StakingPool::MAX_CLAIM_VESTING_SECONDS,
+        max_exp_input: MAX_EXP_INPUT,
+    }
+}
+
+/// Publish the program's input limits and version via `set_return_data`, so
+/// UIs validate against the deployed program instead of hardcoded values.
+/// Takes no accounts and does not modify anything.
+pub fn process_get_program_limits() -> ProgramResult {
+    set_return_data(&borsh::to_vec(&program_limits())?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_program_limits() {
+        let limits = program_limits();
+        assert_eq!(limits.version, PROGRAM_VERSION);
+        assert!(limits.min_tau_seconds < limits.max_tau_seconds);
+
+        let bytes = borsh::to_vec(&limits).unwrap();
+        assert_eq!(ProgramLimits::try_from_slice(&bytes).unwrap(), limits);
+    }
+}
@@ -0,0 +1,160 @@
+//! Read-only position summary returned through return data
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program::set_return_data,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::StakingError,
+    loaders::{load_pool, load_pool_stake},
+    math::{calculate_total_weighted_stake, calculate_user_weighted_stake, wad_div, WAD},
+    state::{StakingPool, UserStake},
+    time::pool_time,
+};
+
+/// Snapshot of a single stake position, Borsh-encoded into return data.
+///
+/// WAD-scaled fields use 1e18 = 1.0 (or 1 token for weighted stakes).
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct UserPosition {
+    /// Staked token amount (raw units)
+    pub amount: u64,
+    /// Current weighted stake (WAD-scaled)
+    pub weighted_stake: u128,
+    /// Share of the pool's total weighted stake (WAD-scaled fraction)
+    pub pool_share_wad: u128,
+    /// Maturity 1 - e^(-age/tau) (WAD-scaled fraction)
+    pub maturity_wad: u128,
+    /// Claimable rewards in lamports (including residual rewards)
+    pub pending_rewards: u64,
+    /// Seconds until the lock period elapses (0 if unlocked)
+    pub lock_remaining_seconds: u64,
+    /// Amount in a pending unstake request (0 if none or lapsed)
+    pub unstake_request_amount: u64,
+    /// Seconds until the pending unstake request can complete (0 if ready or none)
+    pub cooldown_remaining_seconds: u64,
+    /// Projected weighted stake one tau from now (WAD-scaled)
+    pub projected_weighted_stake: u128,
+}
+
+/// Compute a position summary and publish it via `set_return_data`.
+/// Intended for simulation; does not modify any account.
+///
+/// Accounts:
+/// 0. `[]` Pool account
+/// 1. `[]` User stake account
+pub fn process_get_user_position(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let user_stake_info = next_account_info(account_info_iter)?;
+
+    // Load and validate pool
+    let pool = load_pool(program_id, pool_info)?;
+
+    // Load and validate user stake
+    let mut user_stake = load_pool_stake(program_id, user_stake_info, pool_info.key)?;
+
+    let position = user_position(&pool, &mut user_stake, pool_time(&pool)?)?;
+    set_return_data(&borsh::to_vec(&position)?);
+
+    Ok(())
+}
+
+/// Build the position summary at `current_time`. `user_stake` is synced
+/// to the pool's base_time in memory only.
+pub fn user_position(
+    pool: &StakingPool,
+    user_stake: &mut UserStake,
+    current_time: i64,
+) -> Result<UserPosition, StakingError> {
+    user_stake.sync_to_pool(pool)?;
+
+    let weighted_stake = calculate_user_weighted_stake(
+        user_stake.amount,
+        user_stake.exp_start_factor,
+        current_time,
+        pool.base_time,
+        pool.tau_seconds,
+    )?;
+    let projected_weighted_stake = calculate_user_weighted_stake(
+        user_stake.amount,
+        user_stake.exp_start_factor,
+        current_time.saturating_add(pool.tau_seconds as i64),
+        pool.base_time,
+        pool.tau_seconds,
+    )?;
+
+    let total_weighted = calculate_total_weighted_stake(
+        pool.total_staked,
+        &pool.get_sum_stake_exp(),
+        current_time,
+        pool.base_time,
+        pool.tau_seconds,
+    )?;
+    let pool_share_wad = if total_weighted == 0 {
+        0
+    } else {
+        wad_div(weighted_stake, total_weighted)?.min(WAD)
+    };
+
+    let maturity_wad = if user_stake.amount == 0 {
+        0
+    } else {
+        let amount_wad = (user_stake.amount as u128)
+            .checked_mul(WAD)
+            .ok_or(StakingError::MathOverflow)?;
+        wad_div(weighted_stake, amount_wad)?.min(WAD)
+    };
+
+    // Claimable rewards as ClaimRewards pays them: residuals after a full
+    // unstake live in reward_debt, and on pools with claim vesting only the
+    // vested part of distributions and of the vesting fragment counts
+    let pending_wad = if user_stake.amount == 0 {
+        user_stake.reward_debt
+    } else {
+        user_stake.pending_rewards_wad(weighted_stake, pool.vested_acc_rps(current_time))?
+    };
+    let pending_rewards = pool
+        .payout_lamports(pending_wad.saturating_add(user_stake.vested_rewards_wad(current_time)))
+        .min(u64::MAX as u128) as u64;
+
+    let lock_remaining_seconds = if user_stake.amount > 0 {
+        user_stake.lock_remaining_seconds(pool, current_time)
+    } else {
+        0
+    };
+
+    let has_pending_request = user_stake.has_pending_unstake_request(pool, current_time);
+    let cooldown_remaining_seconds = if has_pending_request {
+        let elapsed = current_time
+            .saturating_sub(user_stake.unstake_request_time)
+            .max(0) as u64;
+        pool.unstake_cooldown_seconds.saturating_sub(elapsed)
+    } else {
+        0
+    };
+
+    Ok(UserPosition {
+        amount: user_stake.amount,
+        weighted_stake,
+        pool_share_wad,
+        maturity_wad,
+        pending_rewards,
+        lock_remaining_seconds,
+        unstake_request_amount: if has_pending_request {
+            user_stake.unstake_request_amount
+        } else {
+            0
+        },
+        cooldown_remaining_seconds,
+        projected_weighted_stake,
+    })
+}
@@ -0,0 +1,129 @@
+//! Read-only lifetime statistics for a stake position, returned through return data
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program::set_return_data,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::StakingError,
+    instructions::user_position,
+    loaders::{load_pool, load_pool_stake},
+    math::{U256, WAD},
+    state::{StakingPool, UserStake},
+    time::pool_time,
+};
+
+/// Seconds in a 365-day year, used to annualize the realized reward rate
+pub const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+
+/// Lifetime statistics for a single stake position, Borsh-encoded into return data.
+///
+/// Deposit/withdraw totals only cover activity since the account was upgraded
+/// to track them; `total_rewards_claimed` covers the whole position history.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct UserStats {
+    /// Unix timestamp when the position was opened
+    pub first_stake_time: i64,
+    /// Seconds since the position was opened
+    pub elapsed_seconds: u64,
+    /// Currently staked token amount (raw units)
+    pub amount: u64,
+    /// Lifetime tokens staked (raw units)
+    pub total_deposited: u64,
+    /// Lifetime tokens unstaked, before any unstake fee (raw units)
+    pub total_withdrawn: u64,
+    /// Lifetime SOL rewards claimed (lamports)
+    pub total_rewards_claimed: u64,
+    /// Claimable rewards in lamports (including residual rewards)
+    pub pending_rewards: u64,
+    /// Realized rate: total_rewards_claimed per staked token per year,
+    /// WAD-scaled lamports per raw token unit. Multiply by the token/SOL
+    /// price ratio to get a percentage APR. 0 with no stake or no elapsed time.
+    pub realized_apr_wad: u128,
+}
+
+/// Compute lifetime statistics and publish them via `set_return_data`.
+/// Intended for simulation; does not modify any account.
+///
+/// Accounts:
+/// 0. `[]` Pool account
+/// 1. `[]` User stake account
+pub fn process_get_user_stats(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let user_stake_info = next_account_info(account_info_iter)?;
+
+    // Load and validate pool
+    let pool = load_pool(program_id, pool_info)?;
+
+    // Load and validate user stake
+    let mut user_stake = load_pool_stake(program_id, user_stake_info, pool_info.key)?;
+
+    let stats = user_stats(&pool, &mut user_stake, pool_time(&pool)?)?;
+    set_return_data(&borsh::to_vec(&stats)?);
+
+    Ok(())
+}
+
+/// Build the statistics at `current_time`. `user_stake` is synced to the
+/// pool's base_time in memory only.
+pub fn user_stats(
+    pool: &StakingPool,
+    user_stake: &mut UserStake,
+    current_time: i64,
+) -> Result<UserStats, StakingError> {
+    let pending_rewards = user_position(pool, user_stake, current_time)?.pending_rewards;
+
+    let first_stake_time = user_stake.effective_first_stake_time();
+    let elapsed_seconds = current_time.saturating_sub(first_stake_time).max(0) as u64;
+
+    Ok(UserStats {
+        first_stake_time,
+        elapsed_seconds,
+        amount: user_stake.amount,
+        total_deposited: user_stake.total_deposited,
+        total_withdrawn: user_stake.total_withdrawn,
+        total_rewards_claimed: user_stake.total_rewards_claimed,
+        pending_rewards,
+        realized_apr_wad: realized_apr_wad(
+            user_stake.total_rewards_claimed,
+            user_stake.amount,
+            elapsed_seconds,
+        ),
+    })
+}
+
+/// claimed * WAD * SECONDS_PER_YEAR / (amount * elapsed), saturating at u128::MAX
+fn realized_apr_wad(claimed: u64, amount: u64, elapsed_seconds: u64) -> u128 {
+    if amount == 0 || elapsed_seconds == 0 {
+        return 0;
+    }
+    // claimed * WAD * year fits comfortably in U256, as does amount * elapsed
+    let numerator = U256::from_u128(claimed as u128 * WAD) * U256::from(SECONDS_PER_YEAR);
+    let denominator = U256::from(amount) * U256::from(elapsed_seconds);
+    (numerator / denominator).to_u128().unwrap_or(u128::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_realized_apr_wad() {
+        // 1 lamport per token over one year
+        assert_eq!(realized_apr_wad(1_000, 1_000, SECONDS_PER_YEAR), WAD);
+        // Same rewards over half a year annualize to twice the rate
+        assert_eq!(realized_apr_wad(1_000, 1_000, SECONDS_PER_YEAR / 2), 2 * WAD);
+        assert_eq!(realized_apr_wad(1_000, 0, SECONDS_PER_YEAR), 0);
+        assert_eq!(realized_apr_wad(1_000, 1_000, 0), 0);
+        assert_eq!(realized_apr_wad(u64::MAX, 1, 1), u128::MAX);
+    }
+}
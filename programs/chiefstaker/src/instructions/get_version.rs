@@ -0,0 +1,28 @@
+//! Read-only program version, returned through return data
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{entrypoint::ProgramResult, program::set_return_data};
+
+use crate::version::{PROGRAM_FEATURES, PROGRAM_VERSION};
+
+/// Version and feature bitmap of the deployed program, Borsh-encoded into
+/// return data
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ProgramVersion {
+    /// Program version (major, minor, patch)
+    pub version: [u16; 3],
+    /// Supported features (`version::FEATURE_*` bits)
+    pub features: u64,
+}
+
+/// Publish the program's version and feature bitmap via `set_return_data`,
+/// so clients can check a cluster's deployment before using newer
+/// instructions. Takes no accounts and does not modify anything.
+pub fn process_get_version() -> ProgramResult {
+    let version = ProgramVersion {
+        version: PROGRAM_VERSION,
+        features: PROGRAM_FEATURES,
+    };
+    set_return_data(&borsh::to_vec(&version)?);
+    Ok(())
+}
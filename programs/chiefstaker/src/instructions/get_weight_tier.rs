@@ -0,0 +1,102 @@
+//! Read-only weight tier of a stake position, returned through return data
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program::set_return_data,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::StakingError,
+    loaders::{load_pool, load_pool_stake},
+    math::{calculate_user_weighted_stake, WAD},
+    state::{StakingPool, UserStake, WeightTiers},
+    time::pool_time,
+};
+
+/// A position's tier under the pool's WeightTiers, Borsh-encoded into return data
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WeightTier {
+    /// Number of thresholds the weighted stake reaches (0 = below all)
+    pub tier: u8,
+    /// Number of thresholds the pool configures (the highest tier)
+    pub tier_count: u8,
+    /// Current weighted stake (raw token units)
+    pub weighted_stake: u64,
+    /// Threshold of the next tier (0 at the highest tier)
+    pub next_threshold: u64,
+}
+
+/// Map the position's current weighted stake onto the pool's tiers and
+/// publish the result via `set_return_data`, so partner programs can grant
+/// fee discounts from a single simulation. Does not modify any account.
+///
+/// Accounts:
+/// 0. `[]` Pool account
+/// 1. `[]` User stake account
+/// 2. `[]` Weight tiers (PDA: ["weight_tiers", pool])
+pub fn process_get_weight_tier(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let user_stake_info = next_account_info(account_info_iter)?;
+    let tiers_info = next_account_info(account_info_iter)?;
+
+    // Load and validate pool
+    let pool = load_pool(program_id, pool_info)?;
+
+    // Load and validate user stake
+    let mut user_stake = load_pool_stake(program_id, user_stake_info, pool_info.key)?;
+
+    // Load and validate weight tiers
+    let (expected_tiers, _) = WeightTiers::derive_pda(pool_info.key, program_id);
+    if *tiers_info.key != expected_tiers {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    if tiers_info.owner != program_id {
+        return Err(StakingError::InvalidAccountOwner.into());
+    }
+    let tiers = WeightTiers::try_from_slice(&tiers_info.try_borrow_data()?)?;
+    if !tiers.is_initialized() {
+        return Err(StakingError::NotInitialized.into());
+    }
+
+    let tier = weight_tier(&pool, &mut user_stake, &tiers, pool_time(&pool)?)?;
+    set_return_data(&borsh::to_vec(&tier)?);
+
+    Ok(())
+}
+
+/// Build the tier at `current_time`. `user_stake` is synced to the pool's
+/// base_time in memory only.
+pub fn weight_tier(
+    pool: &StakingPool,
+    user_stake: &mut UserStake,
+    tiers: &WeightTiers,
+    current_time: i64,
+) -> Result<WeightTier, StakingError> {
+    user_stake.sync_to_pool(pool)?;
+
+    let weighted_stake_wad = calculate_user_weighted_stake(
+        user_stake.amount,
+        user_stake.exp_start_factor,
+        current_time,
+        pool.base_time,
+        pool.tau_seconds,
+    )?;
+    // Weighted stake never exceeds the staked amount, so it fits in u64
+    let weighted_stake = (weighted_stake_wad / WAD).min(user_stake.amount as u128) as u64;
+
+    let tier = tiers.tier_of(weighted_stake);
+    Ok(WeightTier {
+        tier,
+        tier_count: tiers.count,
+        weighted_stake,
+        next_threshold: tiers.active().get(tier as usize).copied().unwrap_or(0),
+    })
+}
@@ -0,0 +1,94 @@
+//! Create the authority action log of a pool
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    error::StakingError,
+    loaders::load_pool,
+    state::{AuthorityLog, StakingPool, AUTHORITY_LOG_DISCRIMINATOR, AUTHORITY_LOG_SEED},
+};
+
+/// Create an AuthorityLog for the pool (permissionless, payer funds rent).
+/// From then on every authority instruction must pass the log, which
+/// records each changed setting with its old and new value. Once created
+/// the log can't be detached from the pool.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable]` Authority log (PDA: ["authority_log", pool])
+/// 2. `[writable, signer]` Payer
+/// 3. `[]` System program
+pub fn process_init_authority_log(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let log_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Validate payer is signer
+    if !payer_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    if pool.has_authority_log || !log_info.data_is_empty() {
+        return Err(StakingError::AlreadyInitialized.into());
+    }
+
+    // Derive and verify log PDA
+    let (expected_log, log_bump) = AuthorityLog::derive_pda(pool_info.key, program_id);
+    if *log_info.key != expected_log {
+        return Err(StakingError::InvalidPDA.into());
+    }
+
+    let space = AuthorityLog::space(0);
+    let log_seeds = &[AUTHORITY_LOG_SEED, pool_info.key.as_ref(), &[log_bump]];
+    invoke_signed(
+        &system_instruction::create_account(
+            payer_info.key,
+            log_info.key,
+            Rent::get()?.minimum_balance(space),
+            space as u64,
+            program_id,
+        ),
+        &[
+            payer_info.clone(),
+            log_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[log_seeds],
+    )?;
+
+    let mut log = AuthorityLog {
+        discriminator: AUTHORITY_LOG_DISCRIMINATOR,
+        pool: *pool_info.key,
+        count: 0,
+        bump: log_bump,
+    };
+    log.append(&mut log_info.try_borrow_mut_data()?, &[])?;
+
+    // Legacy pools have no room for the flag (payer funds the growth)
+    StakingPool::maybe_realloc(pool_info, payer_info, Some(system_program_info))?;
+
+    pool.has_authority_log = true;
+    pool.save(pool_info)?;
+
+    msg!("Authority log created");
+
+    Ok(())
+}
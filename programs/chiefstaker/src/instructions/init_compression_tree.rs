@@ -0,0 +1,121 @@
+//! Init compression tree instruction (authority only)
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    authority_log::{next_authority_log, record_authority_actions},
+    compression::{check_tree_accounts, init_empty_merkle_tree},
+    error::StakingError,
+    loaders::load_pool,
+    state::{AuthorityAction, AuthorityLogEntry, StakingPool},
+    time::pool_time,
+};
+
+/// Attach a concurrent merkle tree to the pool so positions can be
+/// compressed with StakeCompressed. The tree account must already be
+/// allocated for `max_depth` / `max_buffer_size` (plus any canopy), zeroed
+/// and owned by the spl-account-compression program; the pool PDA becomes
+/// its authority. A pool's tree is set once.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable, signer]` Authority (pays legacy pool reallocation)
+/// 2. `[writable]` Merkle tree
+/// 3. `[]` spl-account-compression program
+/// 4. `[]` spl-noop program
+/// 5. `[]` System program
+/// 6. `[writable]` Authority log (pools with an authority log only)
+pub fn process_init_compression_tree(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    max_depth: u32,
+    max_buffer_size: u32,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let tree_info = next_account_info(account_info_iter)?;
+    let compression_program_info = next_account_info(account_info_iter)?;
+    let noop_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Validate authority is signer
+    if !authority_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    // Check authority is not renounced
+    if pool.is_authority_renounced() {
+        return Err(StakingError::AuthorityRenounced.into());
+    }
+
+    // Verify authority
+    if pool.authority != *authority_info.key {
+        return Err(StakingError::InvalidAuthority.into());
+    }
+
+    if pool.has_compression_tree() {
+        return Err(StakingError::AlreadyInitialized.into());
+    }
+    pool.compression_tree = *tree_info.key;
+    check_tree_accounts(&pool, tree_info, compression_program_info, noop_info)?;
+
+    // Pools with an authority log must pass it (after the system program)
+    let log_info = next_authority_log(&pool, account_info_iter)?;
+
+    // Legacy pools have no room for the tree (payer = authority)
+    StakingPool::maybe_realloc(pool_info, authority_info, Some(system_program_info))?;
+
+    if let Some(log_info) = log_info {
+        let entry = AuthorityLogEntry::pubkey(
+            pool_time(&pool)?,
+            *authority_info.key,
+            AuthorityAction::CompressionTree,
+            Pubkey::default(),
+            *tree_info.key,
+        );
+        record_authority_actions(
+            program_id,
+            log_info,
+            pool_info.key,
+            authority_info,
+            Some(system_program_info),
+            &[entry],
+        )?;
+    }
+
+    pool.save(pool_info)?;
+
+    let pool_id_bytes = pool.pool_id.to_le_bytes();
+    let pool_bump = [pool.bump];
+    let pool_seeds: &[&[u8]] = &pool.signer_seeds(&pool_id_bytes, &pool_bump);
+    invoke_signed(
+        &init_empty_merkle_tree(tree_info.key, pool_info.key, max_depth, max_buffer_size),
+        &[
+            tree_info.clone(),
+            pool_info.clone(),
+            noop_info.clone(),
+            compression_program_info.clone(),
+        ],
+        &[pool_seeds],
+    )?;
+
+    msg!(
+        "Compression tree {} set (depth {}, buffer {})",
+        tree_info.key,
+        max_depth,
+        max_buffer_size
+    );
+
+    Ok(())
+}
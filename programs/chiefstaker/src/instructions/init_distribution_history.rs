@@ -0,0 +1,109 @@
+//! Init distribution history instruction (authority only)
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    authority_log::{next_authority_log, record_authority_actions},
+    distribution_history::create_history_page,
+    error::StakingError,
+    loaders::load_pool,
+    state::{AuthorityAction, AuthorityLogEntry, DistributionHistory, StakingPool},
+    time::pool_time,
+};
+
+/// Start checkpointing the pool's distributions: create the first
+/// DistributionHistory page and, from then on, record (timestamp,
+/// acc_reward_per_weighted_share, lamports) after every Deposit and
+/// SyncRewards distribution. Off-chain indexers can then read the
+/// accumulator at any past distribution without replaying transactions.
+///
+/// Pages hold DistributionHistory::CAPACITY checkpoints each; the next one
+/// is created by the distributing depositor or by AddDistributionHistoryPage.
+/// A pool's history can't be turned off.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable, signer]` Authority (pays rent)
+/// 2. `[writable]` Distribution history page 0
+///    (PDA: ["distribution_history", pool, 0u32 LE])
+/// 3. `[]` System program
+/// 4. `[writable]` Authority log (pools with an authority log only)
+pub fn process_init_distribution_history(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let page_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Validate authority is signer
+    if !authority_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    // Check authority is not renounced
+    if pool.is_authority_renounced() {
+        return Err(StakingError::AuthorityRenounced.into());
+    }
+
+    // Verify authority
+    if pool.authority != *authority_info.key {
+        return Err(StakingError::InvalidAuthority.into());
+    }
+
+    if pool.has_distribution_history {
+        return Err(StakingError::AlreadyInitialized.into());
+    }
+
+    // Pools with an authority log must pass it (after the system program)
+    let log_info = next_authority_log(&pool, account_info_iter)?;
+
+    // Legacy pools have no room for the history fields (payer = authority)
+    StakingPool::maybe_realloc(pool_info, authority_info, Some(system_program_info))?;
+
+    let page = DistributionHistory::page_of(pool.distribution_checkpoints);
+    create_history_page(
+        program_id,
+        page_info,
+        pool_info.key,
+        page,
+        authority_info,
+        system_program_info,
+    )?;
+
+    if let Some(log_info) = log_info {
+        let entry = AuthorityLogEntry::integer(
+            pool_time(&pool)?,
+            *authority_info.key,
+            AuthorityAction::DistributionHistory,
+            0,
+            1,
+        );
+        record_authority_actions(
+            program_id,
+            log_info,
+            pool_info.key,
+            authority_info,
+            Some(system_program_info),
+            &[entry],
+        )?;
+    }
+
+    pool.has_distribution_history = true;
+    pool.save(pool_info)?;
+
+    msg!("Distribution history started at page {}", page);
+
+    Ok(())
+}
@@ -0,0 +1,92 @@
+//! Create the program-wide config account
+
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    error::StakingError,
+    state::{ProgramConfig, PROGRAM_CONFIG_DISCRIMINATOR, PROGRAM_CONFIG_SEED},
+    upgrade::current_upgrade_authority,
+};
+
+/// Create the ProgramConfig (once). Only the program's current upgrade
+/// authority can do this; it becomes the config admin and records
+/// `upgrade_authority` as the key the deploy authority is expected to be
+/// (e.g. a governance multisig it is about to be handed to).
+///
+/// Accounts:
+/// 0. `[writable]` Program config (PDA: ["program_config"])
+/// 1. `[writable, signer]` Current upgrade authority (pays rent)
+/// 2. `[]` ProgramData account of this program
+/// 3. `[]` System program
+pub fn process_init_program_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    upgrade_authority: Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let config_info = next_account_info(account_info_iter)?;
+    let deployer_info = next_account_info(account_info_iter)?;
+    let programdata_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Validate deployer is signer
+    if !deployer_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Only the deploy key can claim the config
+    if current_upgrade_authority(program_id, programdata_info)? != *deployer_info.key {
+        return Err(StakingError::InvalidAuthority.into());
+    }
+
+    // Verify config PDA
+    let (expected_config, config_bump) = ProgramConfig::derive_pda(program_id);
+    if *config_info.key != expected_config {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    if !config_info.data_is_empty() {
+        return Err(StakingError::AlreadyInitialized.into());
+    }
+
+    let rent = Rent::get()?;
+    invoke_signed(
+        &system_instruction::create_account(
+            deployer_info.key,
+            config_info.key,
+            rent.minimum_balance(ProgramConfig::LEN),
+            ProgramConfig::LEN as u64,
+            program_id,
+        ),
+        &[
+            deployer_info.clone(),
+            config_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[&[PROGRAM_CONFIG_SEED, &[config_bump]]],
+    )?;
+
+    let config = ProgramConfig {
+        discriminator: PROGRAM_CONFIG_DISCRIMINATOR,
+        admin: *deployer_info.key,
+        upgrade_authority,
+        bump: config_bump,
+        protocol_fee_bps: 0,
+        fee_treasury: Pubkey::default(),
+    };
+    config.serialize(&mut &mut config_info.try_borrow_mut_data()?[..])?;
+
+    msg!("Program config created, expected upgrade authority {}", upgrade_authority);
+
+    Ok(())
+}
@@ -0,0 +1,146 @@
+//! Create (or re-link) the weighted-stake history of a position
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    error::{ErrorContext, StakingError},
+    history::checkpoint_of,
+    loaders::{load_pool, load_user_stake},
+    state::{
+        UserStake, WeightHistory, WEIGHT_HISTORY_DISCRIMINATOR, WEIGHT_HISTORY_SEED,
+    },
+    time::pool_time,
+};
+
+/// Create a WeightHistory for the caller's position and record the current
+/// state as its first checkpoint. From then on every stake/unstake of the
+/// position must pass the history account, so the log has no gaps.
+///
+/// If the history already exists (e.g. the stake account was closed and
+/// re-created), it is re-linked and `capacity` is ignored.
+///
+/// Accounts:
+/// 0. `[]` Pool account
+/// 1. `[writable]` User stake account
+/// 2. `[writable]` Weight history (PDA: ["weight_history", pool, owner])
+/// 3. `[writable, signer]` User/owner (pays rent)
+/// 4. `[]` System program
+pub fn process_init_weight_history(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    capacity: u16,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let user_stake_info = next_account_info(account_info_iter)?;
+    let history_info = next_account_info(account_info_iter)?;
+    let user_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Validate user is signer
+    if !user_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let pool = load_pool(program_id, pool_info)?;
+
+    // Realloc legacy accounts to current size (payer = user)
+    UserStake::maybe_realloc(user_stake_info, user_info, Some(system_program_info))?;
+
+    // Load and validate user stake
+    let mut user_stake =
+        load_user_stake(program_id, user_stake_info, pool_info.key, user_info.key)?;
+
+    if user_stake.has_weight_history {
+        return Err(StakingError::AlreadyInitialized.into());
+    }
+
+    // Derive and verify history PDA
+    let (expected_history, history_bump) =
+        WeightHistory::derive_pda(pool_info.key, user_info.key, program_id);
+    if *history_info.key != expected_history {
+        return Err(StakingError::InvalidPDA.into());
+    }
+
+    let mut history = if history_info.data_is_empty() {
+        if capacity == 0 {
+            return Err(StakingError::ZeroAmount.into());
+        }
+        if capacity > WeightHistory::MAX_CAPACITY {
+            return Err(StakingError::SettingExceedsMaximum
+                .with_context(ErrorContext::MaximumAmount(WeightHistory::MAX_CAPACITY as u64)));
+        }
+
+        // Account doesn't exist — create it
+        let space = WeightHistory::space(capacity);
+        let history_rent = Rent::get()?.minimum_balance(space);
+        let history_seeds = &[
+            WEIGHT_HISTORY_SEED,
+            pool_info.key.as_ref(),
+            user_info.key.as_ref(),
+            &[history_bump],
+        ];
+
+        invoke_signed(
+            &system_instruction::create_account(
+                user_info.key,
+                history_info.key,
+                history_rent,
+                space as u64,
+                program_id,
+            ),
+            &[
+                user_info.clone(),
+                history_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[history_seeds],
+        )?;
+
+        WeightHistory {
+            discriminator: WEIGHT_HISTORY_DISCRIMINATOR,
+            pool: *pool_info.key,
+            owner: *user_info.key,
+            capacity,
+            count: 0,
+            bump: history_bump,
+        }
+    } else {
+        if history_info.owner != program_id {
+            return Err(StakingError::InvalidAccountOwner.into());
+        }
+        let existing = WeightHistory::deserialize(&mut &history_info.try_borrow_data()?[..])?;
+        if !existing.is_initialized() {
+            return Err(StakingError::NotInitialized.into());
+        }
+        existing
+    };
+
+    // First checkpoint: the position as it stands now
+    user_stake.sync_to_pool(&pool)?;
+    let checkpoint = checkpoint_of(&pool, &user_stake, pool_time(&pool)?)?;
+    history.push(&mut history_info.try_borrow_mut_data()?, &checkpoint)?;
+
+    user_stake.has_weight_history = true;
+    user_stake.save(user_stake_info)?;
+
+    msg!(
+        "Weight history linked (capacity {}, {} checkpoints)",
+        history.capacity,
+        history.count
+    );
+
+    Ok(())
+}
@@ -1,15 +1,18 @@
 //! Initialize a staking pool for a Token 2022 mint
 
-use borsh::BorshSerialize;
+use borsh::BorshDeserialize;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     clock::Clock,
     entrypoint::ProgramResult,
     msg,
     program::invoke_signed,
+    program_error::ProgramError,
+    program_option::COption,
     pubkey::Pubkey,
     rent::Rent,
     system_instruction,
+    system_program,
     sysvar::Sysvar,
 };
 use spl_token_2022::{
@@ -23,24 +26,54 @@ use spl_token_2022::{
 };
 
 use crate::{
-    error::StakingError,
+    error::{ErrorContext, StakingError},
+    events::emit_pool_initialized,
+    receipt::create_receipt_mint,
     state::{StakingPool, POOL_SEED, TOKEN_VAULT_SEED},
 };
 
+/// Minimum tau: 1 hour. Shorter taus make weights effectively instant, so
+/// a stake timed just before a deposit would earn like a long-term one.
+#[cfg(not(feature = "short-tau"))]
+pub const MIN_TAU_SECONDS: u64 = 60 * 60;
+
+/// Minimum tau of `short-tau` builds: 60 seconds, so dedicated localnet
+/// tests can watch positions mature in real time. Never deploy this build.
+#[cfg(feature = "short-tau")]
+pub const MIN_TAU_SECONDS: u64 = 60;
+
+/// Maximum tau: ~10 years, so weights eventually mature.
+pub const MAX_TAU_SECONDS: u64 = 10 * 365 * 24 * 60 * 60;
+
 /// Initialize a new staking pool
 ///
+/// `pool_id` 0 is the original pool for the mint; other ids create
+/// additional pools (e.g. a different tau) for the same mint. `authority`
+/// overrides the pool authority, which defaults to the payer.
+/// `allow_zero_supply` (InitializePoolBeforeMint) accepts a mint with no
+/// supply when the signer is its mint authority.
+///
 /// Accounts:
-/// 0. `[writable]` Pool account (PDA: ["pool", mint])
+/// 0. `[writable]` Pool account (PDA: ["pool", mint] for pool_id 0,
+///    ["pool", mint, pool_id (u64 LE)] otherwise)
 /// 1. `[]` Token mint (Token 2022)
 /// 2. `[writable]` Token vault (PDA: ["token_vault", pool])
-/// 3. `[writable, signer]` Authority/payer
+/// 3. `[writable, signer]` Authority/payer (payer only with `authority`)
 /// 4. `[]` System program
 /// 5. `[]` Token 2022 program
 /// 6. `[]` Rent sysvar
+/// 7. `[writable]` (optional) Receipt mint (PDA: ["receipt_mint", pool]);
+///    when present the pool mints 1:1 receipt tokens on stake
+///
+/// Fails with AlreadyInitialized if the pool or token vault account
+/// already exists (InitializePoolIfNeeded accepts an existing pool).
 pub fn process_initialize_pool(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     tau_seconds: u64,
+    pool_id: u64,
+    authority: Option<Pubkey>,
+    allow_zero_supply: bool,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
 
@@ -51,6 +84,7 @@ pub fn process_initialize_pool(
     let system_program_info = next_account_info(account_info_iter)?;
     let token_program_info = next_account_info(account_info_iter)?;
     let rent_sysvar_info = next_account_info(account_info_iter)?;
+    let receipt_mint_info = account_info_iter.next();
 
     // Validate Token 2022 program
     if *token_program_info.key != spl_token_2022::id() {
@@ -62,12 +96,13 @@ pub fn process_initialize_pool(
         return Err(StakingError::MissingRequiredSigner.into());
     }
 
-    // Validate tau_seconds (min 60s to prevent near-instant maturation,
-    // max ~10 years to ensure weights eventually mature)
-    const MIN_TAU_SECONDS: u64 = 60;
-    const MAX_TAU_SECONDS: u64 = 10 * 365 * 24 * 60 * 60; // ~10 years
-    if tau_seconds < MIN_TAU_SECONDS || tau_seconds > MAX_TAU_SECONDS {
-        return Err(StakingError::InvalidTau.into());
+    // Validate tau_seconds
+    check_tau(tau_seconds)?;
+
+    // The default pubkey marks a renounced authority
+    let authority = authority.unwrap_or(*authority_info.key);
+    if authority == Pubkey::default() {
+        return Err(StakingError::InvalidAuthority.into());
     }
 
     // Verify mint is a Token 2022 mint
@@ -78,6 +113,7 @@ pub fn process_initialize_pool(
     // Verify mint is valid by trying to unpack it
     let mint_data = mint_info.try_borrow_data()?;
     let mint_state = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+    let decimals = mint_state.base.decimals;
 
     // Reject mints with transfer fee extension — fee-on-transfer tokens
     // would cause total_staked to diverge from actual vault balance,
@@ -103,9 +139,12 @@ pub fn process_initialize_pool(
         return Err(StakingError::UnsupportedMintExtension.into());
     }
 
+    // Reject unminted tokens unless their mint authority opts in
+    check_mint_supply(&mint_state.base, authority_info.key, allow_zero_supply)?;
+
     // Derive and verify pool PDA
     let (expected_pool, pool_bump) =
-        Pubkey::find_program_address(&[POOL_SEED, mint_info.key.as_ref()], program_id);
+        StakingPool::derive_pda_with_id(mint_info.key, pool_id, program_id);
     if *pool_info.key != expected_pool {
         return Err(StakingError::InvalidPDA.into());
     }
@@ -117,11 +156,27 @@ pub fn process_initialize_pool(
         return Err(StakingError::InvalidPDA.into());
     }
 
+    // Refuse reruns up front instead of failing inside create_account
+    if !pool_info.data_is_empty() || *pool_info.owner != system_program::id() {
+        msg!("Pool {} already exists", pool_info.key);
+        return Err(StakingError::AlreadyInitialized.into());
+    }
+    if !token_vault_info.data_is_empty() || *token_vault_info.owner != system_program::id() {
+        msg!("Token vault {} already exists", token_vault_info.key);
+        return Err(StakingError::AlreadyInitialized.into());
+    }
+
     let rent = Rent::from_account_info(rent_sysvar_info)?;
     let clock = Clock::get()?;
 
     // Create pool account
-    let pool_seeds = &[POOL_SEED, mint_info.key.as_ref(), &[pool_bump]];
+    let pool_id_bytes = pool_id.to_le_bytes();
+    let bump = [pool_bump];
+    let mut pool_seeds: Vec<&[u8]> = vec![POOL_SEED, mint_info.key.as_ref()];
+    if pool_id != 0 {
+        pool_seeds.push(&pool_id_bytes);
+    }
+    pool_seeds.push(&bump);
     let pool_rent = rent.minimum_balance(StakingPool::LEN);
 
     invoke_signed(
@@ -137,7 +192,7 @@ pub fn process_initialize_pool(
             pool_info.clone(),
             system_program_info.clone(),
         ],
-        &[pool_seeds],
+        &[&pool_seeds],
     )?;
 
     // Create token vault account (Token 2022 account)
@@ -178,22 +233,198 @@ pub fn process_initialize_pool(
     )?;
 
     // Initialize pool state
-    let pool = StakingPool::new(
+    let mut pool = StakingPool::new(
         *mint_info.key,
         *token_vault_info.key,
         *pool_info.key, // Reward vault is the pool itself (stores SOL as lamports)
-        *authority_info.key,
+        authority,
         tau_seconds,
         clock.unix_timestamp,
         pool_bump,
     );
+    pool.pool_id = pool_id;
+
+    // Optional receipt mint, created while the pool has no stakers so that
+    // receipt supply always equals total_staked
+    if let Some(receipt_mint_info) = receipt_mint_info {
+        create_receipt_mint(
+            program_id,
+            pool_info,
+            receipt_mint_info,
+            authority_info,
+            system_program_info,
+            &rent,
+            decimals,
+        )?;
+        pool.receipt_mint = *receipt_mint_info.key;
+        msg!("Receipt mint: {}", receipt_mint_info.key);
+    }
 
     // Serialize pool state
-    let mut pool_data = pool_info.try_borrow_mut_data()?;
-    pool.serialize(&mut &mut pool_data[..])?;
+    pool.save(pool_info)?;
+
+    emit_pool_initialized(pool_info.key, mint_info.key, &authority, pool_id, tau_seconds);
 
-    msg!("Initialized staking pool for mint {}", mint_info.key);
+    msg!("Initialized staking pool {} for mint {}", pool_id, mint_info.key);
     msg!("Tau: {} seconds", tau_seconds);
+    if authority != *authority_info.key {
+        msg!("Authority: {}", authority);
+    }
 
     Ok(())
 }
+
+/// Initialize a pool unless it already exists with the same parameters
+///
+/// An existing pool is accepted when its mint, pool_id and tau match and,
+/// if a receipt mint account is passed, it is the pool's receipt mint;
+/// nothing is created or charged in that case. Other existing pools fail
+/// with AlreadyInitialized.
+///
+/// Accounts: same as process_initialize_pool
+pub fn process_initialize_pool_if_needed(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    tau_seconds: u64,
+    pool_id: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+
+    if pool_info.data_is_empty() {
+        return process_initialize_pool(program_id, accounts, tau_seconds, pool_id, None, false);
+    }
+
+    // Load and validate existing pool
+    if pool_info.owner != program_id {
+        return Err(StakingError::InvalidAccountOwner.into());
+    }
+    let pool = StakingPool::try_from_slice(&pool_info.try_borrow_data()?)?;
+    if !pool.is_initialized() {
+        return Err(StakingError::NotInitialized.into());
+    }
+
+    // Verify pool PDA
+    let (expected_pool, _) = StakingPool::derive_pda_with_id(mint_info.key, pool_id, program_id);
+    if *pool_info.key != expected_pool {
+        return Err(StakingError::InvalidPDA.into());
+    }
+
+    // The optional receipt mint follows the five remaining fixed accounts
+    let receipt_mint_info = account_info_iter.nth(5);
+    let receipt_matches = match receipt_mint_info {
+        Some(receipt_mint_info) => pool.receipt_mint == *receipt_mint_info.key,
+        None => !pool.has_receipt_mint(),
+    };
+    if pool.mint != *mint_info.key
+        || pool.pool_id != pool_id
+        || pool.tau_seconds != tau_seconds
+        || !receipt_matches
+        || pool.uses_age_buckets()
+    {
+        msg!(
+            "Pool {} exists with different parameters (tau={}s)",
+            pool.pool_id,
+            pool.tau_seconds
+        );
+        return Err(StakingError::AlreadyInitialized.into());
+    }
+
+    msg!("Staking pool {} for mint {} already initialized", pool_id, mint_info.key);
+
+    Ok(())
+}
+
+/// Check tau_seconds is within [MIN_TAU_SECONDS, MAX_TAU_SECONDS]; the
+/// error carries the violated bound
+fn check_tau(tau_seconds: u64) -> Result<(), ProgramError> {
+    if tau_seconds < MIN_TAU_SECONDS {
+        msg!("Tau {}s is below the minimum of {}s", tau_seconds, MIN_TAU_SECONDS);
+        return Err(StakingError::InvalidTau
+            .with_context(ErrorContext::MinimumAmount(MIN_TAU_SECONDS)));
+    }
+    if tau_seconds > MAX_TAU_SECONDS {
+        msg!("Tau {}s is above the maximum of {}s", tau_seconds, MAX_TAU_SECONDS);
+        return Err(StakingError::InvalidTau
+            .with_context(ErrorContext::MaximumAmount(MAX_TAU_SECONDS)));
+    }
+    Ok(())
+}
+
+/// A mint with no supply yet has no holders to stake, so a pool for it is
+/// most likely a mistake or squatting. Its mint authority may still create
+/// one ahead of the first mint (e.g. a launch) with `allow_zero_supply`,
+/// and only as the signer.
+fn check_mint_supply(
+    mint: &Mint,
+    signer: &Pubkey,
+    allow_zero_supply: bool,
+) -> Result<(), StakingError> {
+    if mint.supply > 0 {
+        return Ok(());
+    }
+    if !allow_zero_supply {
+        msg!("Mint has no supply; its mint authority can use InitializePoolBeforeMint");
+        return Err(StakingError::MintHasNoSupply);
+    }
+    match mint.mint_authority {
+        COption::Some(mint_authority) if mint_authority == *signer => Ok(()),
+        _ => {
+            msg!("Mint has no supply and the signer is not its mint authority");
+            Err(StakingError::MintHasNoSupply)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_initialize_checks() {
+        assert!(check_tau(MIN_TAU_SECONDS).is_ok());
+        assert!(check_tau(MAX_TAU_SECONDS).is_ok());
+        assert_eq!(
+            check_tau(MIN_TAU_SECONDS - 1).unwrap_err(),
+            StakingError::InvalidTau.into()
+        );
+        assert_eq!(
+            check_tau(MAX_TAU_SECONDS + 1).unwrap_err(),
+            StakingError::InvalidTau.into()
+        );
+        #[cfg(not(feature = "short-tau"))]
+        assert!(check_tau(60).is_err());
+
+        let (signer, mint_authority) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut mint = Mint {
+            mint_authority: COption::Some(mint_authority),
+            supply: 0,
+            decimals: 6,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        };
+        assert_eq!(
+            check_mint_supply(&mint, &signer, true),
+            Err(StakingError::MintHasNoSupply)
+        );
+        // The mint authority may create a pool before the first mint, but
+        // only through the explicit opt-in
+        assert!(check_mint_supply(&mint, &mint_authority, true).is_ok());
+        assert_eq!(
+            check_mint_supply(&mint, &mint_authority, false),
+            Err(StakingError::MintHasNoSupply)
+        );
+
+        // Without a mint authority, no supply can ever exist
+        mint.mint_authority = COption::None;
+        assert_eq!(
+            check_mint_supply(&mint, &signer, true),
+            Err(StakingError::MintHasNoSupply)
+        );
+
+        mint.supply = 1;
+        assert!(check_mint_supply(&mint, &signer, false).is_ok());
+    }
+}
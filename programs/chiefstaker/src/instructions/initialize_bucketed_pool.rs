@@ -0,0 +1,114 @@
+//! Initialize a pool weighted by age bucket
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use chiefstaker_core::buckets::AgeBucketSchedule;
+
+use crate::{
+    age_buckets::create_history_page,
+    error::StakingError,
+    loaders::load_pool,
+    state::{AgeBuckets, WeightEngine, AGE_BUCKETS_SEED},
+    time::pool_time,
+};
+
+use super::{create_lamport_vault, process_initialize_pool};
+
+/// Initialize pool `pool_id` with the AgeBuckets weight engine: stake earns
+/// the weight of its age bucket, `weights_bps[k]` during its k-th period of
+/// `bucket_seconds`, and the last weight from then on. A first weight of 0
+/// keeps stakes made just before a distribution from sharing in it.
+///
+/// The pool is created like InitializePool, with tau_seconds standing for
+/// the time to full weight (`bucket_seconds * weights_bps.len()`, bounded
+/// like tau), then switched to the engine. Epoch 0 starts now; its start is
+/// the first entry of history page 0.
+///
+/// Accounts:
+/// 0-6. Same as InitializePool, with the pool account at
+///    ["pool", mint, pool_id (u64 LE)] (["pool", mint] for pool_id 0)
+/// 7. `[writable]` Age buckets (PDA: ["age_buckets", pool])
+/// 8. `[writable]` Age bucket history page 0
+///    (PDA: ["age_bucket_history", pool, 0u32 LE])
+pub fn process_initialize_bucketed_pool(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    pool_id: u64,
+    bucket_seconds: u64,
+    weights_bps: Vec<u16>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let _mint_info = next_account_info(account_info_iter)?;
+    let _token_vault_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let _token_program_info = next_account_info(account_info_iter)?;
+    let _rent_sysvar_info = next_account_info(account_info_iter)?;
+    let buckets_info = next_account_info(account_info_iter)?;
+    let page_info = next_account_info(account_info_iter)?;
+
+    // The schedule takes tau's place
+    let schedule = AgeBucketSchedule {
+        bucket_seconds,
+        weights_bps,
+    };
+    if !schedule.is_valid() {
+        return Err(StakingError::InvalidTau.into());
+    }
+    let tau_seconds = bucket_seconds
+        .checked_mul(schedule.len() as u64)
+        .ok_or(StakingError::InvalidTau)?;
+
+    // Validate the age buckets PDA before creating anything
+    let (expected_buckets, buckets_bump) = AgeBuckets::derive_pda(pool_info.key, program_id);
+    if *buckets_info.key != expected_buckets {
+        return Err(StakingError::InvalidPDA.into());
+    }
+
+    // Create the pool and vault, without a receipt mint
+    process_initialize_pool(program_id, &accounts[..7], tau_seconds, pool_id, None, false)?;
+
+    let mut pool = load_pool(program_id, pool_info)?;
+    let start_time = pool_time(&pool)?;
+    pool.weight_engine = WeightEngine::AgeBuckets as u8;
+    pool.save(pool_info)?;
+
+    let seeds: &[&[u8]] = &[AGE_BUCKETS_SEED, pool_info.key.as_ref(), &[buckets_bump]];
+    create_lamport_vault(
+        program_id,
+        authority_info,
+        buckets_info,
+        system_program_info,
+        seeds,
+        AgeBuckets::LEN,
+    )?;
+    let buckets = AgeBuckets::new(*pool_info.key, &schedule, start_time, buckets_bump);
+    buckets.save(buckets_info)?;
+
+    // Epoch 0 starts with nothing distributed
+    let mut history = create_history_page(
+        program_id,
+        page_info,
+        pool_info.key,
+        0,
+        authority_info,
+        system_program_info,
+    )?;
+    history.push(&mut page_info.try_borrow_mut_data()?, buckets.epoch_acc)?;
+
+    msg!(
+        "Age buckets: {} x {}s, weights {:?} bps",
+        schedule.len(),
+        bucket_seconds,
+        schedule.weights_bps
+    );
+
+    Ok(())
+}
@@ -0,0 +1,389 @@
+//! Migrate a stake position to another pool of the same mint
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+use spl_token_2022::extension::StateWithExtensions;
+
+use crate::{
+    error::{ErrorContext, StakingError},
+    events::{emit_reward_payout, RewardPayoutType},
+    guardian::check_guardian_signature,
+    history::{next_weight_history, record_checkpoint},
+    instructions::settle_unstake,
+    loaders::{load_pool, load_user_stake},
+    lock::{check_unlocked, next_lock_schedule},
+    math::{wad_mul, U256, WAD},
+    recipient::next_reward_payee,
+    state::{StakeAction, StakingPool, UserStake, STAKE_SEED},
+    time::clamp_time,
+};
+
+/// Check that a position may move from `source` to `target`: both pools
+/// share a mint, neither issues receipts, and the target's exit terms
+/// (cooldown, unstake fee) are at least as strict as the source's.
+pub fn check_migration(source: &StakingPool, target: &StakingPool) -> Result<(), StakingError> {
+    if source.mint != target.mint
+        || source.has_receipt_mint()
+        || target.has_receipt_mint()
+        || target.unstake_cooldown_seconds < source.unstake_cooldown_seconds
+        || target.unstake_fee_bps < source.unstake_fee_bps
+    {
+        return Err(StakingError::MigrationNotAllowed);
+    }
+    Ok(())
+}
+
+/// Move the whole position into `target_pool` in one step. Source-side
+/// checks match Unstake (lock duration, lock tranches, pending request,
+/// same-slot guard) and its pending rewards are paid out as on Unstake.
+/// The tokens move vault to vault and the target position starts fresh:
+/// new exp_start_factor and lock period. The target position must not exist
+/// or be empty. Lock tranches don't carry over.
+///
+/// Accounts:
+/// 0. `[writable]` Source pool account
+/// 1. `[writable]` Source user stake account
+/// 2. `[writable]` Source token vault
+/// 3. `[writable]` Target pool account
+/// 4. `[writable]` Target user stake account (PDA: ["stake", target_pool, owner])
+/// 5. `[writable]` Target token vault
+/// 6. `[]` Token mint
+/// 7. `[writable, signer]` User/owner (pays rent)
+/// 8. `[]` System program
+/// 9. `[]` Token 2022 program
+/// 10. `[writable]` Source weight history (positions with history only)
+/// 11. `[]` Payout split, then `[writable]` each of its destinations in
+///     order (positions with a payout split only); otherwise `[writable]`
+///     Reward recipient (positions with a declared recipient only)
+/// 12. `[]` Source lock schedule (positions with lock tranches only)
+/// 13. `[signer]` Guardian (positions with a guardian only)
+/// 14. `[writable]` Target weight history (existing target positions with history only)
+pub fn process_migrate_stake(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    target_pool: Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let source_pool_info = next_account_info(account_info_iter)?;
+    let source_stake_info = next_account_info(account_info_iter)?;
+    let source_vault_info = next_account_info(account_info_iter)?;
+    let target_pool_info = next_account_info(account_info_iter)?;
+    let target_stake_info = next_account_info(account_info_iter)?;
+    let target_vault_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let user_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    // Validate Token 2022 program
+    if *token_program_info.key != spl_token_2022::id() {
+        return Err(StakingError::InvalidTokenProgram.into());
+    }
+
+    // Validate user is signer
+    if !user_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    if *target_pool_info.key != target_pool || target_pool == *source_pool_info.key {
+        return Err(StakingError::InvalidPool.into());
+    }
+
+    // Load and validate both pools
+    let mut source = load_pool(program_id, source_pool_info)?;
+    let mut target = load_pool(program_id, target_pool_info)?;
+    check_migration(&source, &target)?;
+
+    // Verify mint and token vaults
+    if source.mint != *mint_info.key {
+        return Err(StakingError::InvalidPoolMint.into());
+    }
+    if source.token_vault != *source_vault_info.key
+        || target.token_vault != *target_vault_info.key
+    {
+        return Err(StakingError::InvalidTokenVault.into());
+    }
+
+    let clock = Clock::get()?;
+    // Both pools' recorded times bound the clock
+    let current_time =
+        clamp_time(&source, clock.unix_timestamp)?.max(clamp_time(&target, clock.unix_timestamp)?);
+
+    // Rebase inline if sum_stake_exp is near overflow (saved with the pools below)
+    source.rebase_if_needed(current_time)?;
+    target.rebase_if_needed(current_time)?;
+
+    // Load and validate source stake
+    let mut user_stake =
+        load_user_stake(program_id, source_stake_info, source_pool_info.key, user_info.key)?;
+    user_stake.check_not_frozen()?;
+
+    let amount = user_stake.amount;
+    if amount == 0 {
+        return Err(StakingError::ZeroAmount.into());
+    }
+
+    // Block if pending unstake request
+    if user_stake.has_pending_unstake_request(&source, current_time) {
+        return Err(StakingError::PendingUnstakeRequestExists.into());
+    }
+    user_stake.clear_expired_unstake_request(&source, current_time);
+
+    // Lazily adjust exp_start_factor if pool has been rebased
+    user_stake.sync_to_pool(&source)?;
+
+    // Block migrating in the same slot as a stake
+    user_stake.record_action(StakeAction::Unstake, clock.slot)?;
+
+    // Check lock duration (pool lock on recent deposits, and any ExtendLock)
+    user_stake.check_lock(&source, amount, current_time)?;
+
+    // Minimum stake applies on the target
+    if target.min_stake_amount > 0 && amount < target.min_stake_amount {
+        return Err(StakingError::BelowMinimumStake
+            .with_context(ErrorContext::MinimumAmount(target.min_stake_amount)));
+    }
+
+    // Positions with a weight history must pass it
+    let source_history_info = next_weight_history(&user_stake, account_info_iter)?;
+
+    // Rewards go to the payout split or declared recipient, if any, instead of the owner
+    let payee = next_reward_payee(program_id, &user_stake, user_info, account_info_iter)?;
+
+    // Positions with lock tranches can only migrate once they are all unlocked
+    if let Some(schedule_info) = next_lock_schedule(&user_stake, account_info_iter)? {
+        check_unlocked(
+            program_id,
+            schedule_info,
+            source_pool_info.key,
+            &user_stake,
+            amount,
+            current_time,
+        )?;
+    }
+
+    // Guarded positions need the guardian to co-sign
+    check_guardian_signature(&user_stake, account_info_iter)?;
+
+    // Settle the source side as a full unstake
+    let reward_transfer_amount =
+        settle_unstake(&mut source, &mut user_stake, source_pool_info, amount, current_time)?;
+
+    // Open the target position with a fresh exp_start_factor (target pool
+    // creation time while its bootstrap window is open)
+    let (stake_time, exp_start_factor) = target.new_stake_start(current_time)?;
+    let (expected_target_stake, target_bump) =
+        UserStake::derive_pda(target_pool_info.key, user_info.key, program_id);
+    if *target_stake_info.key != expected_target_stake {
+        return Err(StakingError::InvalidPDA.into());
+    }
+
+    let mut target_stake = if target_stake_info.data_is_empty() {
+        let stake_seeds = &[
+            STAKE_SEED,
+            target_pool_info.key.as_ref(),
+            user_info.key.as_ref(),
+            &[target_bump],
+        ];
+        invoke_signed(
+            &system_instruction::create_account(
+                user_info.key,
+                target_stake_info.key,
+                Rent::get()?.minimum_balance(UserStake::LEN),
+                UserStake::LEN as u64,
+                program_id,
+            ),
+            &[
+                user_info.clone(),
+                target_stake_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[stake_seeds],
+        )?;
+
+        let mut target_stake = UserStake::new(
+            *user_info.key,
+            *target_pool_info.key,
+            amount,
+            current_time,
+            exp_start_factor,
+            target_bump,
+            target.base_time,
+        );
+        target_stake.stake_time = stake_time;
+        target_stake
+    } else {
+        // Realloc legacy accounts to current size (payer = user)
+        UserStake::maybe_realloc(target_stake_info, user_info, Some(system_program_info))?;
+
+        if target_stake_info.owner != program_id {
+            return Err(StakingError::InvalidAccountOwner.into());
+        }
+        let mut target_stake = UserStake::try_from_slice(&target_stake_info.try_borrow_data()?)?;
+        if !target_stake.is_initialized() {
+            return Err(StakingError::NotInitialized.into());
+        }
+        if target_stake.pool != *target_pool_info.key {
+            return Err(StakingError::InvalidOwner.into());
+        }
+        target_stake.check_owner(user_info.key)?;
+
+        // Only an empty position can take a fresh exp_start_factor
+        if target_stake.amount > 0
+            || target_stake.has_pending_unstake_request(&target, current_time)
+            || target_stake.reward_debt / WAD > 0
+        {
+            return Err(StakingError::AccountNotEmpty.into());
+        }
+
+        target_stake.record_deposit(&target, amount, current_time);
+        target_stake.amount = amount;
+        target_stake.stake_time = stake_time;
+        target_stake.last_stake_time = current_time;
+        target_stake.exp_start_factor = exp_start_factor;
+        target_stake.base_time_snapshot = target.base_time;
+        target_stake.claimed_rewards_wad = 0;
+        target_stake.unstake_request_amount = 0;
+        target_stake.unstake_request_time = 0;
+        target_stake.total_deposited = target_stake.total_deposited.saturating_add(amount);
+        target_stake
+    };
+    target_stake.record_action(StakeAction::Stake, clock.slot)?;
+
+    // Set reward_debt using max weight (amount * WAD) to prevent accessing prior rewards
+    let amount_wad = (amount as u128)
+        .checked_mul(WAD)
+        .ok_or(StakingError::MathOverflow)?;
+    target_stake.reward_debt = wad_mul(amount_wad, target.acc_reward_per_weighted_share)?;
+    target.total_reward_debt = target
+        .total_reward_debt
+        .checked_add(target_stake.reward_debt)
+        .ok_or(StakingError::MathOverflow)?;
+
+    // Update target sum_stake_exp and total staked
+    let stake_contribution = wad_mul(amount_wad, exp_start_factor)?;
+    let new_sum = target
+        .get_sum_stake_exp()
+        .checked_add(U256::from_u128(stake_contribution))
+        .ok_or(StakingError::MathOverflow)?;
+    target.set_sum_stake_exp(new_sum);
+
+    let was_below_threshold = target.below_distribution_threshold();
+    target.total_staked = target
+        .total_staked
+        .checked_add(amount as u128)
+        .ok_or(StakingError::MathOverflow)?;
+    if was_below_threshold && !target.below_distribution_threshold() {
+        target.undistributed_since = 0;
+    }
+
+    // Existing target positions with a weight history must pass it
+    if let Some(history_info) = next_weight_history(&target_stake, account_info_iter)? {
+        record_checkpoint(program_id, history_info, target_pool_info.key, &target, &target_stake, current_time)?;
+    }
+    if let Some(history_info) = source_history_info {
+        record_checkpoint(program_id, history_info, source_pool_info.key, &source, &user_stake, current_time)?;
+    }
+
+    // Realloc legacy accounts to current size (payer = user)
+    UserStake::maybe_realloc(source_stake_info, user_info, Some(system_program_info))?;
+
+    // Save states (before CPI — source pool data includes pre-updated last_synced_lamports)
+    source.save(source_pool_info)?;
+    target.save(target_pool_info)?;
+    user_stake.save(source_stake_info)?;
+    target_stake.save(target_stake_info)?;
+
+    // Move tokens from the source vault to the target vault
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+    let decimals = mint.base.decimals;
+    drop(mint_data);
+
+    let pool_id_bytes = source.pool_id.to_le_bytes();
+    let pool_bump = [source.bump];
+    let pool_seeds: &[&[u8]] = &source.signer_seeds(&pool_id_bytes, &pool_bump);
+
+    invoke_signed(
+        &spl_token_2022::instruction::transfer_checked(
+            &spl_token_2022::id(),
+            source_vault_info.key,
+            mint_info.key,
+            target_vault_info.key,
+            source_pool_info.key,
+            &[],
+            amount,
+            decimals,
+        )?,
+        &[
+            source_vault_info.clone(),
+            mint_info.clone(),
+            target_vault_info.clone(),
+            source_pool_info.clone(),
+        ],
+        &[pool_seeds],
+    )?;
+
+    // Transfer SOL rewards AFTER token CPI to avoid CPI balance check failure
+    // (source_pool_info is a CPI account but the payee accounts are not)
+    if reward_transfer_amount > 0 {
+        **source_pool_info.try_borrow_mut_lamports()? -= reward_transfer_amount;
+        payee.credit(reward_transfer_amount)?;
+        msg!("Claimed {} lamports in rewards", reward_transfer_amount);
+        emit_reward_payout(source_pool_info.key, user_info.key, reward_transfer_amount, RewardPayoutType::Unstake);
+    }
+
+    msg!("Migrated {} tokens to pool {}", amount, target_pool);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_migration() {
+        let mint = Pubkey::new_unique();
+        let pool = |cooldown: u64, fee_bps: u16| {
+            let mut pool = StakingPool::new(
+                mint,
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                86_400,
+                0,
+                255,
+            );
+            pool.unstake_cooldown_seconds = cooldown;
+            pool.unstake_fee_bps = fee_bps;
+            pool
+        };
+
+        assert_eq!(check_migration(&pool(0, 0), &pool(0, 0)), Ok(()));
+        assert_eq!(check_migration(&pool(3_600, 50), &pool(7_200, 100)), Ok(()));
+
+        // Migrating must not shorten the cooldown or skip the unstake fee
+        assert_eq!(check_migration(&pool(3_600, 0), &pool(0, 0)), Err(StakingError::MigrationNotAllowed));
+        assert_eq!(check_migration(&pool(0, 100), &pool(0, 50)), Err(StakingError::MigrationNotAllowed));
+
+        let mut other_mint = pool(0, 0);
+        other_mint.mint = Pubkey::new_unique();
+        assert_eq!(check_migration(&pool(0, 0), &other_mint), Err(StakingError::MigrationNotAllowed));
+
+        let mut receipts = pool(0, 0);
+        receipts.receipt_mint = Pubkey::new_unique();
+        assert_eq!(check_migration(&receipts, &pool(0, 0)), Err(StakingError::MigrationNotAllowed));
+    }
+}
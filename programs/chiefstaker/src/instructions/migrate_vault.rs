@@ -0,0 +1,233 @@
+//! Move the pool's tokens to a new vault (authority only)
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    system_program,
+    sysvar::Sysvar,
+};
+use spl_token_2022::{
+    extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+    state::{Account, Mint},
+};
+
+use crate::{
+    authority_log::{next_authority_log, record_authority_actions},
+    error::StakingError,
+    loaders::load_pool,
+    state::{AuthorityAction, AuthorityLogEntry, StakingPool, TOKEN_VAULT_SEED},
+    time::pool_time,
+};
+
+/// Replace the pool's token vault with one sized for the account
+/// extensions the mint requires now. Token 2022 sizes an account for its
+/// mint's extensions when it is initialized, so a vault created before the
+/// mint gained extensions that need account space can't be used. The new
+/// vault is created at the next generation's PDA, the whole balance moves
+/// to it, the old vault is closed, and pool.token_vault is updated.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[]` Token mint
+/// 2. `[writable]` Current token vault (pool.token_vault)
+/// 3. `[writable]` New token vault (PDA: ["token_vault", pool,
+///    vault_generation + 1 (u32 LE)])
+/// 4. `[writable, signer]` Authority (pays the new vault's rent, receives
+///    the old vault's)
+/// 5. `[]` System program
+/// 6. `[]` Token 2022 program
+/// 7. `[writable]` Authority log (pools with an authority log only)
+pub fn process_migrate_vault(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let old_vault_info = next_account_info(account_info_iter)?;
+    let new_vault_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    // Validate Token 2022 program
+    if *token_program_info.key != spl_token_2022::id() {
+        return Err(StakingError::InvalidTokenProgram.into());
+    }
+
+    // Validate authority is signer
+    if !authority_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    // Check authority is not renounced
+    if pool.is_authority_renounced() {
+        return Err(StakingError::AuthorityRenounced.into());
+    }
+
+    // Verify authority
+    if pool.authority != *authority_info.key {
+        return Err(StakingError::InvalidAuthority.into());
+    }
+
+    // Verify mint and current vault
+    if pool.mint != *mint_info.key {
+        return Err(StakingError::InvalidPoolMint.into());
+    }
+    if pool.token_vault != *old_vault_info.key {
+        return Err(StakingError::InvalidTokenVault.into());
+    }
+
+    // Pools with an authority log must pass it
+    let log_info = next_authority_log(&pool, account_info_iter)?;
+
+    // Derive and verify the next generation's vault
+    let generation = pool
+        .vault_generation
+        .checked_add(1)
+        .ok_or(StakingError::MathOverflow)?;
+    let (expected_vault, vault_bump) =
+        StakingPool::derive_token_vault_pda_for_generation(pool_info.key, generation, program_id);
+    if *new_vault_info.key != expected_vault {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    if !new_vault_info.data_is_empty() || *new_vault_info.owner != system_program::id() {
+        msg!("Token vault {} already exists", new_vault_info.key);
+        return Err(StakingError::AlreadyInitialized.into());
+    }
+
+    // Size the new vault for the account extensions the mint requires
+    let (decimals, vault_size) = {
+        let mint_data = mint_info.try_borrow_data()?;
+        let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+        let required =
+            ExtensionType::get_required_init_account_extensions(&mint.get_extension_types()?);
+        (
+            mint.base.decimals,
+            ExtensionType::try_calculate_account_len::<Account>(&required)?,
+        )
+    };
+
+    // Legacy pools have no room for vault_generation (payer = authority)
+    StakingPool::maybe_realloc(pool_info, authority_info, Some(system_program_info))?;
+
+    // Create and initialize the new vault, owned by the pool
+    let generation_bytes = generation.to_le_bytes();
+    let vault_seeds: &[&[u8]] =
+        &[TOKEN_VAULT_SEED, pool_info.key.as_ref(), &generation_bytes, &[vault_bump]];
+    invoke_signed(
+        &system_instruction::create_account(
+            authority_info.key,
+            new_vault_info.key,
+            Rent::get()?.minimum_balance(vault_size),
+            vault_size as u64,
+            &spl_token_2022::id(),
+        ),
+        &[
+            authority_info.clone(),
+            new_vault_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[vault_seeds],
+    )?;
+    invoke_signed(
+        &spl_token_2022::instruction::initialize_account3(
+            &spl_token_2022::id(),
+            new_vault_info.key,
+            mint_info.key,
+            pool_info.key,
+        )?,
+        &[new_vault_info.clone(), mint_info.clone()],
+        &[vault_seeds],
+    )?;
+
+    // Move the whole balance (staked tokens plus anything sent directly)
+    let balance = {
+        let vault_data = old_vault_info.try_borrow_data()?;
+        StateWithExtensions::<Account>::unpack(&vault_data)?.base.amount
+    };
+
+    let pool_id_bytes = pool.pool_id.to_le_bytes();
+    let pool_bump = [pool.bump];
+    let pool_seeds: &[&[u8]] = &pool.signer_seeds(&pool_id_bytes, &pool_bump);
+
+    if balance > 0 {
+        invoke_signed(
+            &spl_token_2022::instruction::transfer_checked(
+                &spl_token_2022::id(),
+                old_vault_info.key,
+                mint_info.key,
+                new_vault_info.key,
+                pool_info.key,
+                &[],
+                balance,
+                decimals,
+            )?,
+            &[
+                old_vault_info.clone(),
+                mint_info.clone(),
+                new_vault_info.clone(),
+                pool_info.clone(),
+            ],
+            &[pool_seeds],
+        )?;
+    }
+
+    // Close the old vault, returning its rent to the authority
+    invoke_signed(
+        &spl_token_2022::instruction::close_account(
+            &spl_token_2022::id(),
+            old_vault_info.key,
+            authority_info.key,
+            pool_info.key,
+            &[],
+        )?,
+        &[
+            old_vault_info.clone(),
+            authority_info.clone(),
+            pool_info.clone(),
+        ],
+        &[pool_seeds],
+    )?;
+
+    if let Some(log_info) = log_info {
+        let entry = AuthorityLogEntry::pubkey(
+            pool_time(&pool)?,
+            *authority_info.key,
+            AuthorityAction::VaultMigration,
+            pool.token_vault,
+            *new_vault_info.key,
+        );
+        record_authority_actions(
+            program_id,
+            log_info,
+            pool_info.key,
+            authority_info,
+            Some(system_program_info),
+            &[entry],
+        )?;
+    }
+
+    pool.token_vault = *new_vault_info.key;
+    pool.vault_generation = generation;
+    pool.save(pool_info)?;
+
+    msg!(
+        "Migrated {} tokens to vault {} (generation {}, {} bytes)",
+        balance,
+        new_vault_info.key,
+        generation,
+        vault_size
+    );
+
+    Ok(())
+}
@@ -16,6 +16,79 @@ pub mod close_stake;
 pub mod set_metadata;
 pub mod take_fee_ownership;
 pub mod stake_on_behalf;
+pub mod set_rewards_paused;
+pub mod sweep_dust;
+pub mod get_user_position;
+pub mod check_sync_needed;
+pub mod init_weight_history;
+pub mod get_average_weighted_stake;
+pub mod set_reward_recipient;
+pub mod set_refund_policy;
+pub mod refund_undistributed;
+pub mod set_distribution_threshold;
+pub mod set_unstake_fee;
+pub mod get_user_stats;
+pub mod stake_locked;
+pub mod init_authority_log;
+pub mod preview_recover_stranded;
+pub mod deposit_rewards_split;
+pub mod release_pending_distributions;
+pub mod assert_pool_healthy;
+pub mod set_unstake_request_expiry;
+pub mod set_payout_split;
+pub mod migrate_stake;
+pub mod init_program_config;
+pub mod assert_upgrade_authority;
+pub mod update_program_config;
+pub mod collect_protocol_fees;
+pub mod recount_reward_debt;
+pub mod set_rounding_mode;
+pub mod set_depositor_allowlist;
+pub mod extend_lock;
+pub mod set_lock_boost;
+pub mod set_maturity_bonus;
+pub mod claim_maturity_bonus;
+pub mod set_guardian;
+pub mod clear_guardian;
+pub mod set_bootstrap_period;
+pub mod set_reward_matching;
+pub mod init_compression_tree;
+pub mod stake_compressed;
+pub mod decompress_stake;
+pub mod set_rent_sponsor;
+pub mod set_min_sync_interval;
+pub mod skim_stake_lamports;
+pub mod set_weight_tiers;
+pub mod get_weight_tier;
+pub mod set_claim_vesting;
+pub mod get_program_limits;
+pub mod get_version;
+pub mod set_price_oracle;
+pub mod get_pool_stats;
+pub mod set_claim_rebate;
+pub mod fund_gas_rebates;
+pub mod set_claim_burn;
+pub mod tokenize_position;
+pub mod redeem_position;
+pub mod set_residuals_first;
+pub mod set_min_claim_lamports;
+pub mod init_distribution_history;
+pub mod add_distribution_history_page;
+pub mod close_pool_metadata;
+pub mod close_archived;
+pub mod set_depositor_tracking;
+pub mod set_rebase_threshold;
+pub mod exit_pool;
+pub mod migrate_vault;
+pub mod snapshot_voting_weights;
+pub mod set_lock_exempt_amount;
+pub mod reconcile_synced_lamports;
+pub mod set_stake_frozen;
+pub mod clone_pool_config;
+pub mod set_stake_guard_required;
+pub mod initialize_bucketed_pool;
+pub mod roll_age_buckets;
+pub mod bucketed;
 
 pub use initialize::*;
 pub use stake::*;
@@ -33,3 +106,76 @@ pub use close_stake::*;
 pub use set_metadata::*;
 pub use take_fee_ownership::*;
 pub use stake_on_behalf::*;
+pub use set_rewards_paused::*;
+pub use sweep_dust::*;
+pub use get_user_position::*;
+pub use check_sync_needed::*;
+pub use init_weight_history::*;
+pub use get_average_weighted_stake::*;
+pub use set_reward_recipient::*;
+pub use set_refund_policy::*;
+pub use refund_undistributed::*;
+pub use set_distribution_threshold::*;
+pub use set_unstake_fee::*;
+pub use get_user_stats::*;
+pub use stake_locked::*;
+pub use init_authority_log::*;
+pub use preview_recover_stranded::*;
+pub use deposit_rewards_split::*;
+pub use release_pending_distributions::*;
+pub use assert_pool_healthy::*;
+pub use set_unstake_request_expiry::*;
+pub use set_payout_split::*;
+pub use migrate_stake::*;
+pub use init_program_config::*;
+pub use assert_upgrade_authority::*;
+pub use update_program_config::*;
+pub use collect_protocol_fees::*;
+pub use recount_reward_debt::*;
+pub use set_rounding_mode::*;
+pub use set_depositor_allowlist::*;
+pub use extend_lock::*;
+pub use set_lock_boost::*;
+pub use set_maturity_bonus::*;
+pub use claim_maturity_bonus::*;
+pub use set_guardian::*;
+pub use clear_guardian::*;
+pub use set_bootstrap_period::*;
+pub use set_reward_matching::*;
+pub use init_compression_tree::*;
+pub use stake_compressed::*;
+pub use decompress_stake::*;
+pub use set_rent_sponsor::*;
+pub use set_min_sync_interval::*;
+pub use skim_stake_lamports::*;
+pub use set_weight_tiers::*;
+pub use get_weight_tier::*;
+pub use set_claim_vesting::*;
+pub use get_program_limits::*;
+pub use get_version::*;
+pub use set_price_oracle::*;
+pub use get_pool_stats::*;
+pub use set_claim_rebate::*;
+pub use fund_gas_rebates::*;
+pub use set_claim_burn::*;
+pub use tokenize_position::*;
+pub use redeem_position::*;
+pub use set_residuals_first::*;
+pub use set_min_claim_lamports::*;
+pub use init_distribution_history::*;
+pub use add_distribution_history_page::*;
+pub use close_pool_metadata::*;
+pub use close_archived::*;
+pub use set_depositor_tracking::*;
+pub use set_rebase_threshold::*;
+pub use exit_pool::*;
+pub use migrate_vault::*;
+pub use snapshot_voting_weights::*;
+pub use set_lock_exempt_amount::*;
+pub use reconcile_synced_lamports::*;
+pub use set_stake_frozen::*;
+pub use clone_pool_config::*;
+pub use set_stake_guard_required::*;
+pub use initialize_bucketed_pool::*;
+pub use roll_age_buckets::*;
+pub use bucketed::*;
@@ -0,0 +1,53 @@
+//! Read-only estimate of stranded rewards, returned through return data
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program::set_return_data,
+    pubkey::Pubkey,
+};
+
+use crate::loaders::load_pool;
+
+/// Breakdown of the pool's synced lamports, Borsh-encoded into return data.
+/// All values in lamports.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct StrandedPreview {
+    /// Lamports already credited to the reward accumulator
+    pub last_synced_lamports: u64,
+    /// Upper bound on what active stakers can still claim
+    pub max_active_obligation: u64,
+    /// Owed to fully unstaked users who couldn't be paid in full
+    pub residual_unpaid: u64,
+    /// Synced lamports owed to nobody, net of protocol fees owed and the
+    /// residual and rounding reserves (lower bound)
+    pub stranded_lamports: u64,
+}
+
+/// Compute the stranded-rewards estimate and publish it via `set_return_data`.
+/// Intended for simulation; does not modify any account. Uses pool state
+/// only, so it is cheap enough for indexers to poll.
+///
+/// Accounts:
+/// 0. `[]` Pool account
+pub fn process_preview_recover_stranded(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let pool_info = next_account_info(account_info_iter)?;
+
+    // Load and validate pool
+    let pool = load_pool(program_id, pool_info)?;
+
+    let preview = StrandedPreview {
+        last_synced_lamports: pool.last_synced_lamports,
+        max_active_obligation: pool.max_active_obligation()?,
+        residual_unpaid: pool.total_residual_unpaid,
+        stranded_lamports: pool.stranded_lamports()?,
+    };
+    set_return_data(&borsh::to_vec(&preview)?);
+
+    Ok(())
+}
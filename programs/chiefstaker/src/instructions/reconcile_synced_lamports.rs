@@ -0,0 +1,60 @@
+//! Reconcile last_synced_lamports with the pool balance
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar::Sysvar,
+};
+
+use crate::{error::StakingError, events::emit_synced_lamports_reconciled, loaders::load_pool};
+
+/// Lower last_synced_lamports to the pool's balance above rent when it has
+/// drifted past it, e.g. after lamports changed or the rent-exempt minimum
+/// grew through a path that skipped the bookkeeping. While it exceeds the
+/// balance, SyncRewards sees no new rewards until deposits make up the
+/// difference. It is never raised, so no lamports become rewards. This is
+/// a permissionless crank that anyone can call.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+pub fn process_reconcile_synced_lamports(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let pool_info = next_account_info(account_info_iter)?;
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    let rent = Rent::get()?;
+    let pool_lamports = pool_info.lamports();
+    let rent_exempt_minimum = rent.minimum_balance(pool_info.data_len());
+    let old = pool.last_synced_lamports;
+    let undistributed = pool.undistributed_lamports(pool_lamports, rent_exempt_minimum);
+    let removed = pool.reconcile_synced_lamports(pool_lamports, rent_exempt_minimum);
+    if removed == 0 {
+        msg!("last_synced_lamports in step with the pool balance");
+        return Ok(());
+    }
+
+    // Reconciling must not turn any lamports into new rewards
+    if pool.undistributed_lamports(pool_lamports, rent_exempt_minimum) != undistributed {
+        return Err(StakingError::InvariantViolation.into());
+    }
+    pool.record_undistributed(pool_info, &rent);
+    pool.save(pool_info)?;
+
+    emit_synced_lamports_reconciled(pool_info.key, old, pool.last_synced_lamports);
+    msg!(
+        "Reconciled last_synced_lamports: {} -> {} ({} lamports of drift)",
+        old,
+        pool.last_synced_lamports,
+        removed
+    );
+
+    Ok(())
+}
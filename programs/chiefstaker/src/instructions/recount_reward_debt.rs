@@ -0,0 +1,169 @@
+//! Paged recount of a pool's total_reward_debt
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    error::StakingError,
+    loaders::load_pool,
+    state::{
+        DebtRecount, StakingPool, UserStake, DEBT_RECOUNT_DISCRIMINATOR, DEBT_RECOUNT_SEED,
+        STAKE_SEED,
+    },
+};
+
+/// Recount the pool's total_reward_debt from its stake accounts, one page
+/// at a time (permissionless).
+///
+/// Cursor 0 (re)starts the recount against the pool's current totals; each
+/// later page must pass the cursor the previous page left in the scratch
+/// account, with stake accounts in strictly ascending key order. Once the
+/// counted stakes cover total_staked, the summed reward_debt replaces
+/// total_reward_debt and the scratch account is reset. A stake or unstake
+/// in the pool between pages invalidates the recount (restart from 0).
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable]` Debt recount scratch (PDA: ["debt_recount", pool])
+/// 2. `[writable, signer]` Payer (scratch rent, legacy pool reallocation)
+/// 3. `[]` System program
+/// 4. `[]` User stake accounts of the pool (up to DebtRecount::MAX_PAGE)
+pub fn process_recount_reward_debt(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    cursor: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let recount_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let stake_infos = account_info_iter.as_slice();
+
+    // Validate payer is signer
+    if !payer_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    if stake_infos.len() > DebtRecount::MAX_PAGE {
+        return Err(StakingError::SettingExceedsMaximum.into());
+    }
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    // Verify recount PDA
+    let (expected_recount, recount_bump) = DebtRecount::derive_pda(pool_info.key, program_id);
+    if *recount_info.key != expected_recount {
+        return Err(StakingError::InvalidPDA.into());
+    }
+
+    let mut recount = if recount_info.data_is_empty() {
+        let rent = Rent::get()?;
+        let recount_seeds = &[DEBT_RECOUNT_SEED, pool_info.key.as_ref(), &[recount_bump]];
+        invoke_signed(
+            &system_instruction::create_account(
+                payer_info.key,
+                recount_info.key,
+                rent.minimum_balance(DebtRecount::LEN),
+                DebtRecount::LEN as u64,
+                program_id,
+            ),
+            &[
+                payer_info.clone(),
+                recount_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[recount_seeds],
+        )?;
+
+        DebtRecount {
+            discriminator: DEBT_RECOUNT_DISCRIMINATOR,
+            pool: *pool_info.key,
+            bump: recount_bump,
+            cursor: 0,
+            last_key: Pubkey::default(),
+            counted_staked: 0,
+            counted_debt: 0,
+            snapshot_staked: 0,
+            snapshot_debt: 0,
+        }
+    } else {
+        if recount_info.owner != program_id {
+            return Err(StakingError::InvalidAccountOwner.into());
+        }
+        let recount = DebtRecount::try_from_slice(&recount_info.try_borrow_data()?)?;
+        if !recount.is_initialized() {
+            return Err(StakingError::NotInitialized.into());
+        }
+        recount
+    };
+
+    if cursor == 0 {
+        recount.restart(&pool);
+    } else if cursor != recount.cursor {
+        msg!("Recount is at cursor {}, page passed {}", recount.cursor, cursor);
+        return Err(StakingError::RecountOutOfSync.into());
+    }
+
+    for stake_info in stake_infos {
+        if stake_info.owner != program_id {
+            return Err(StakingError::InvalidAccountOwner.into());
+        }
+        let stake = UserStake::try_from_slice(&stake_info.try_borrow_data()?)?;
+        if !stake.is_initialized() {
+            return Err(StakingError::NotInitialized.into());
+        }
+        if stake.pool != *pool_info.key {
+            return Err(StakingError::InvalidPool.into());
+        }
+
+        // Verify user stake PDA (stored bump avoids a bump search per account)
+        let expected_stake = Pubkey::create_program_address(
+            &[STAKE_SEED, pool_info.key.as_ref(), stake.owner.as_ref(), &[stake.bump]],
+            program_id,
+        )
+        .map_err(|_| StakingError::InvalidPDA)?;
+        if *stake_info.key != expected_stake {
+            return Err(StakingError::InvalidPDA.into());
+        }
+
+        recount.count(stake_info.key, &stake)?;
+    }
+
+    if recount.is_complete(&pool)? {
+        // Grow legacy pools before writing (payer funds rent)
+        StakingPool::maybe_realloc(pool_info, payer_info, Some(system_program_info))?;
+
+        msg!(
+            "Recounted total_reward_debt over {} stakes: {} -> {}",
+            recount.cursor,
+            pool.total_reward_debt,
+            recount.counted_debt
+        );
+        pool.total_reward_debt = recount.counted_debt;
+        pool.save(pool_info)?;
+        recount.restart(&pool);
+    } else {
+        msg!(
+            "Recount at cursor {}: {} of {} staked counted",
+            recount.cursor,
+            recount.counted_staked,
+            pool.total_staked
+        );
+    }
+
+    recount.serialize(&mut &mut recount_info.try_borrow_mut_data()?[..])?;
+
+    Ok(())
+}
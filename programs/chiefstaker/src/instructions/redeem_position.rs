@@ -0,0 +1,124 @@
+//! Redeem position instruction
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::StakingError,
+    loaders::{load_pool, load_pool_stake},
+    position_nft::burn_position_nft,
+    state::{UserStake, STAKE_SEED},
+};
+
+use super::create_lamport_vault;
+
+/// Burn a position NFT (TokenizePosition) and give its holder the
+/// position. When the holder isn't the position's owner, the position
+/// moves to the holder's stake account, which must not exist yet, and the
+/// old account is closed to the holder. Pending rewards move with it. The
+/// holder can then claim, unstake or top up like any owner.
+///
+/// Accounts:
+/// 0. `[]` Pool account
+/// 1. `[writable]` Tokenized user stake account
+/// 2. `[writable, signer]` NFT holder (pays rent for the new stake account)
+/// 3. `[writable]` Holder's user stake account (PDA: ["stake", pool,
+///    holder]); account 1 again when the holder is the owner
+/// 4. `[writable]` Position NFT mint (closed, rent to the holder)
+/// 5. `[writable]` Holder's token account holding the NFT
+/// 6. `[]` Token 2022 program
+/// 7. `[]` System program
+pub fn process_redeem_position(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let user_stake_info = next_account_info(account_info_iter)?;
+    let holder_info = next_account_info(account_info_iter)?;
+    let holder_stake_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let token_account_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Validate holder is signer
+    if !holder_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    if *token_program_info.key != spl_token_2022::id() {
+        return Err(StakingError::InvalidTokenProgram.into());
+    }
+
+    // Load and validate pool
+    let pool = load_pool(program_id, pool_info)?;
+
+    // Load and validate the tokenized stake
+    let mut user_stake = load_pool_stake(program_id, user_stake_info, pool_info.key)?;
+    if !user_stake.is_tokenized() || user_stake.position_mint != *mint_info.key {
+        return Err(StakingError::InvalidPositionMint.into());
+    }
+
+    // Token 2022 checks that the holder owns the NFT
+    burn_position_nft(&pool, pool_info, mint_info, holder_info, token_account_info)?;
+    user_stake.position_mint = Pubkey::default();
+
+    if *holder_info.key == user_stake.owner {
+        if holder_stake_info.key != user_stake_info.key {
+            return Err(StakingError::InvalidPDA.into());
+        }
+        user_stake.save(user_stake_info)?;
+        msg!("Redeemed position NFT {} by its owner", mint_info.key);
+        return Ok(());
+    }
+
+    // Move the position to the holder's stake account
+    let (expected_holder_stake, holder_stake_bump) =
+        UserStake::derive_pda(pool_info.key, holder_info.key, program_id);
+    if *holder_stake_info.key != expected_holder_stake {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    if !holder_stake_info.data_is_empty() {
+        return Err(StakingError::AccountNotEmpty.into());
+    }
+    let stake_seeds: &[&[u8]] = &[
+        STAKE_SEED,
+        pool_info.key.as_ref(),
+        holder_info.key.as_ref(),
+        &[holder_stake_bump],
+    ];
+    create_lamport_vault(
+        program_id,
+        holder_info,
+        holder_stake_info,
+        system_program_info,
+        stake_seeds,
+        UserStake::LEN,
+    )?;
+    let previous_owner = user_stake.owner;
+    user_stake.owner = *holder_info.key;
+    user_stake.bump = holder_stake_bump;
+    user_stake.save(holder_stake_info)?;
+
+    // Close the old account to the holder
+    let stake_lamports = user_stake_info.lamports();
+    **user_stake_info.try_borrow_mut_lamports()? = 0;
+    **holder_info.try_borrow_mut_lamports()? += stake_lamports;
+    user_stake_info.try_borrow_mut_data()?.fill(0);
+
+    msg!(
+        "Redeemed position NFT {}: {} tokens moved from {} to {}",
+        mint_info.key,
+        user_stake.amount,
+        previous_owner,
+        holder_info.key
+    );
+
+    Ok(())
+}
@@ -0,0 +1,88 @@
+//! Return idle undistributed SOL to the pool's refund address (permissionless)
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    error::{ErrorContext, StakingError},
+    loaders::load_pool,
+    time::pool_time,
+};
+
+/// Send SOL that has sat undistributed for lack of stake (no stakers, or
+/// total_staked below min_distribution_stake) for at least
+/// `refund_after_seconds` to the pool's refund address.
+///
+/// The idle timer starts when a deposit or sync is deferred for lack of
+/// stake; if it isn't running yet this call starts it. It resets on any
+/// distribution and when a stake lifts the pool to the threshold. Lamports
+/// already owed to past stakers (last_synced_lamports) are never refunded.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable]` Refund address
+pub fn process_refund_undistributed(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let refund_info = next_account_info(account_info_iter)?;
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    if !pool.has_refund_policy() {
+        return Err(StakingError::RefundPolicyNotSet.into());
+    }
+    if *refund_info.key != pool.refund_address {
+        return Err(StakingError::InvalidRefundAddress.into());
+    }
+
+    // With enough stake the SOL can be distributed by SyncRewards instead
+    if !pool.below_distribution_threshold() {
+        return Err(StakingError::PoolHasStakers.into());
+    }
+
+    let current_time = pool_time(&pool)?;
+    let rent = Rent::get()?;
+    let rent_exempt_minimum = rent.minimum_balance(pool_info.data_len());
+    let undistributed = pool.undistributed_lamports(pool_info.lamports(), rent_exempt_minimum);
+
+    if undistributed == 0 {
+        msg!("No undistributed SOL to refund");
+        return Ok(());
+    }
+
+    if pool.mark_undistributed(current_time) {
+        pool.record_undistributed(pool_info, &rent);
+        pool.save(pool_info)?;
+        msg!("{} lamports idle; refund timer started", undistributed);
+        return Ok(());
+    }
+
+    let idle = current_time.saturating_sub(pool.undistributed_since).max(0) as u64;
+    if idle < pool.refund_after_seconds {
+        let remaining = pool.refund_after_seconds - idle;
+        return Err(StakingError::RefundNotDue
+            .with_context(ErrorContext::RemainingSeconds(remaining)));
+    }
+
+    **pool_info.try_borrow_mut_lamports()? -= undistributed;
+    **refund_info.try_borrow_mut_lamports()? += undistributed;
+
+    pool.undistributed_since = 0;
+    pool.record_undistributed(pool_info, &rent);
+    pool.save(pool_info)?;
+
+    msg!("Refunded {} idle lamports to {}", undistributed, refund_info.key);
+
+    Ok(())
+}
@@ -0,0 +1,96 @@
+//! Release due tranches of staged rewards into the pool
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::StakingError,
+    instructions::process_sync_rewards,
+    loaders::load_pool,
+    state::PendingDistributions,
+    time::pool_time,
+};
+
+/// Move every due tranche from the pool's PendingDistributions into the
+/// pool, then distribute the pool's new lamports as SyncRewards does
+/// (deferred while rewards are paused or stake is below the threshold).
+/// This is a permissionless crank that anyone can call.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable]` Pending distributions
+/// 2. Then the accounts SyncRewards takes after the pool: matching vault
+///    (pools with reward matching), current distribution history page
+///    (pools with a distribution history), instructions sysvar (optional
+///    unless the pool sets stake_guard_required)
+pub fn process_release_pending_distributions(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let pending_info = next_account_info(account_info_iter)?;
+
+    // Load and validate pool
+    let pool = load_pool(program_id, pool_info)?;
+
+    // Load and validate pending distributions
+    if pending_info.owner != program_id {
+        return Err(StakingError::InvalidAccountOwner.into());
+    }
+    let (expected_pending, _) = PendingDistributions::derive_pda(pool_info.key, program_id);
+    if *pending_info.key != expected_pending {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    let mut pending = {
+        let data = pending_info.try_borrow_data()?;
+        let header = data
+            .get(..PendingDistributions::HEADER_LEN)
+            .ok_or(StakingError::AccountDataTooSmall)?;
+        PendingDistributions::try_from_slice(header)?
+    };
+    if !pending.is_initialized() {
+        return Err(StakingError::NotInitialized.into());
+    }
+
+    let current_time = pool_time(&pool)?;
+    let tranches = pending.tranches(&pending_info.try_borrow_data()?)?;
+    let (released, kept) = PendingDistributions::take_due(&tranches, current_time);
+
+    if released == 0 {
+        msg!("No pending distributions due ({} pending)", kept.len());
+        return Ok(());
+    }
+
+    pending.write(&mut pending_info.try_borrow_mut_data()?, &kept)?;
+
+    // Both accounts are program-owned, so lamports move directly
+    **pending_info.try_borrow_mut_lamports()? = pending_info
+        .lamports()
+        .checked_sub(released)
+        .ok_or(StakingError::InsufficientRewardBalance)?;
+    **pool_info.try_borrow_mut_lamports()? = pool_info
+        .lamports()
+        .checked_add(released)
+        .ok_or(StakingError::MathOverflow)?;
+
+    msg!(
+        "Released {} lamports from {} tranches ({} pending)",
+        released,
+        tranches.len() - kept.len(),
+        kept.len()
+    );
+
+    // Distribute through the SyncRewards path, forwarding the remaining
+    // accounts (matching vault, history page, instructions sysvar)
+    let sync_accounts: Vec<AccountInfo> = core::iter::once(pool_info.clone())
+        .chain(account_info_iter.cloned())
+        .collect();
+    process_sync_rewards(program_id, &sync_accounts)
+}
@@ -1,6 +1,5 @@
 //! Request unstake instruction (starts cooldown period)
 
-use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     clock::Clock,
@@ -11,22 +10,28 @@ use solana_program::{
 };
 
 use crate::{
-    error::StakingError,
-    state::{StakingPool, UserStake},
+    error::{ErrorContext, StakingError},
+    loaders::{load_pool, load_user_stake},
+    lock::{check_unlocked, next_lock_schedule},
+    state::{StakeAction, UserStake},
+    time::pool_time,
 };
 
 /// Request unstake - starts cooldown period. Tokens remain staked and earn rewards.
+/// `amount` None (RequestUnstakeAll) requests the whole position as of execution.
 ///
 /// Accounts:
 /// 0. `[writable]` Pool account
 /// 1. `[writable]` User stake account
 /// 2. `[signer]` User/owner
+/// 3. `[]` Lock schedule (positions with lock tranches only)
+/// 4. `[]` (optional) System program (legacy account reallocation)
 pub fn process_request_unstake(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    amount: u64,
+    amount: Option<u64>,
 ) -> ProgramResult {
-    if amount == 0 {
+    if amount == Some(0) {
         return Err(StakingError::ZeroAmount.into());
     }
 
@@ -42,82 +47,60 @@ pub fn process_request_unstake(
     }
 
     // Load and validate pool
-    if pool_info.owner != program_id {
-        return Err(StakingError::InvalidAccountOwner.into());
-    }
-    let pool = StakingPool::try_from_slice(&pool_info.try_borrow_data()?)?;
-    if !pool.is_initialized() {
-        return Err(StakingError::NotInitialized.into());
-    }
+    let mut pool = load_pool(program_id, pool_info)?;
 
-    // Verify pool PDA
-    let (expected_pool, _) = StakingPool::derive_pda(&pool.mint, program_id);
-    if *pool_info.key != expected_pool {
-        return Err(StakingError::InvalidPDA.into());
-    }
-
-    // Check if pool needs rebasing
-    if pool.get_sum_stake_exp().needs_rebase() {
-        return Err(StakingError::PoolRequiresSync.into());
-    }
+    // Rebase inline if sum_stake_exp is near overflow
+    let rebased = pool.rebase_if_needed(pool_time(&pool)?)?;
 
     // Require cooldown to be configured; otherwise use direct Unstake
     if pool.unstake_cooldown_seconds == 0 {
         return Err(StakingError::CooldownNotConfigured.into());
     }
 
+    // Load and validate user stake
+    let mut user_stake =
+        load_user_stake(program_id, user_stake_info, pool_info.key, user_info.key)?;
+
+    // Positions with lock tranches pass their schedule before the system program
+    let lock_schedule_info = next_lock_schedule(&user_stake, account_info_iter)?;
+
     // Realloc legacy accounts to current size (payer = user)
     // System program is optional trailing account, only needed for legacy accounts
     let system_program_info = account_info_iter.next();
     UserStake::maybe_realloc(user_stake_info, user_info, system_program_info)?;
 
-    // Load and validate user stake
-    if user_stake_info.owner != program_id {
-        return Err(StakingError::InvalidAccountOwner.into());
-    }
-    let mut user_stake = UserStake::try_from_slice(&user_stake_info.try_borrow_data()?)?;
-    if !user_stake.is_initialized() {
-        return Err(StakingError::NotInitialized.into());
-    }
-
-    // Verify ownership
-    if user_stake.owner != *user_info.key {
-        return Err(StakingError::InvalidOwner.into());
-    }
-    if user_stake.pool != *pool_info.key {
-        return Err(StakingError::InvalidPool.into());
-    }
-
-    // Verify user stake PDA
-    let (expected_stake, _) =
-        UserStake::derive_pda(pool_info.key, user_info.key, program_id);
-    if *user_stake_info.key != expected_stake {
-        return Err(StakingError::InvalidPDA.into());
-    }
+    let clock = Clock::get()?;
+    let current_time = pool_time(&pool)?;
 
-    // Check no existing pending request
-    if user_stake.has_pending_unstake_request() {
+    // Check no existing pending request (a lapsed one is replaced)
+    if user_stake.has_pending_unstake_request(&pool, current_time) {
         return Err(StakingError::PendingUnstakeRequestExists.into());
     }
 
     // Lazily adjust exp_start_factor if pool has been rebased
     user_stake.sync_to_pool(&pool)?;
 
+    // RequestUnstakeAll takes whatever is staked now, not a client-cached amount
+    let amount = amount.unwrap_or(user_stake.amount);
+    if amount == 0 {
+        return Err(StakingError::ZeroAmount.into());
+    }
+
     // Check sufficient balance
     if user_stake.amount < amount {
-        return Err(StakingError::InsufficientStakeBalance.into());
+        return Err(StakingError::InsufficientStakeBalance
+            .with_context(ErrorContext::AvailableAmount(user_stake.amount)));
     }
 
-    let clock = Clock::get()?;
-    let current_time = clock.unix_timestamp;
-
-    // Check lock duration has elapsed
-    if pool.lock_duration_seconds > 0 {
-        let last_stake = user_stake.effective_last_stake_time();
-        let elapsed = current_time.saturating_sub(last_stake).max(0) as u64;
-        if elapsed < pool.lock_duration_seconds {
-            return Err(StakingError::StakeLocked.into());
-        }
+    // Block requesting an unstake in the same slot as a stake
+    user_stake.record_action(StakeAction::Unstake, clock.slot)?;
+
+    // Check lock duration (pool lock on recent deposits, and any ExtendLock)
+    user_stake.check_lock(&pool, amount, current_time)?;
+
+    // Only the unlocked balance can be requested
+    if let Some(schedule_info) = lock_schedule_info {
+        check_unlocked(program_id, schedule_info, pool_info.key, &user_stake, amount, current_time)?;
     }
 
     // Set unstake request fields
@@ -125,8 +108,12 @@ pub fn process_request_unstake(
     user_stake.unstake_request_time = current_time;
 
     // Save user stake
-    let mut stake_data = user_stake_info.try_borrow_mut_data()?;
-    user_stake.serialize(&mut &mut stake_data[..])?;
+    user_stake.save(user_stake_info)?;
+
+    // The user stake is now synced to the rebased base_time; persist it
+    if rebased {
+        pool.save(pool_info)?;
+    }
 
     msg!(
         "Unstake request created for {} tokens, cooldown {} seconds",
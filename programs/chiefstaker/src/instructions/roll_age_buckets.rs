@@ -0,0 +1,82 @@
+//! Roll a bucketed pool's age buckets (permissionless crank)
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    age_buckets::{create_history_page, load_age_buckets, load_history_page, roll},
+    error::StakingError,
+    loaders::load_bucketed_pool,
+    state::AgeBucketHistory,
+    time::pool_time,
+};
+
+/// Roll the age buckets towards the epoch of the current time, recording
+/// each epoch start in the history. One call rolls at most to the end of
+/// the history page of the next epoch; after a long pause, callers repeat
+/// it with the following pages until the pool is rolled.
+///
+/// Accounts:
+/// 0. `[]` Pool account
+/// 1. `[writable]` Age buckets
+/// 2. `[writable]` Age bucket history page of the next epoch
+///    (PDA: ["age_bucket_history", pool, page (u32 LE)]); created at the
+///    payer's expense when it doesn't exist yet
+/// 3. `[writable, signer]` Payer
+/// 4. `[]` System program
+pub fn process_roll_age_buckets(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let buckets_info = next_account_info(account_info_iter)?;
+    let page_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Validate payer is signer
+    if !payer_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    let pool = load_bucketed_pool(program_id, pool_info)?;
+    let mut buckets = load_age_buckets(program_id, buckets_info, pool_info.key)?;
+
+    let current_time = pool_time(&pool)?;
+    let target = buckets.epoch_at(current_time);
+    if buckets.epoch >= target {
+        msg!("Age buckets already at epoch {}", buckets.epoch);
+        return Ok(());
+    }
+
+    let page = AgeBucketHistory::page_of(buckets.epoch + 1);
+    let mut history = match load_history_page(program_id, page_info, pool_info.key, page)? {
+        Some(history) => history,
+        None => create_history_page(
+            program_id,
+            page_info,
+            pool_info.key,
+            page,
+            payer_info,
+            system_program_info,
+        )?,
+    };
+
+    let rolled = roll(&mut buckets, &mut history, &mut page_info.try_borrow_mut_data()?, target)?;
+    buckets.save(buckets_info)?;
+
+    msg!(
+        "Rolled {} epochs to epoch {} (current epoch {})",
+        rolled,
+        buckets.epoch,
+        target
+    );
+
+    Ok(())
+}
@@ -0,0 +1,91 @@
+//! Set bootstrap period instruction (authority only)
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    authority_log::{next_authority_log, record_authority_actions},
+    error::{ErrorContext, StakingError},
+    loaders::load_pool,
+    state::{AuthorityAction, AuthorityLogEntry, StakingPool},
+    time::pool_time,
+};
+
+/// Set `bootstrap_period_seconds`: positions opened within that long of pool
+/// creation get the exp_start_factor of a stake made at creation, so the
+/// community's early stakers aren't behind whoever staked in the first
+/// block. At most MAX_BOOTSTRAP_PERIOD_SECONDS; 0 disables it. Positions
+/// already opened keep their start time.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable, signer]` Authority (pays rent if a legacy pool must grow)
+/// 2. `[]` (optional) System program, required for legacy pool reallocation
+///    and pools with an authority log
+/// 3. `[writable]` Authority log (pools with an authority log only)
+pub fn process_set_bootstrap_period(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    period_seconds: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let system_program_info = account_info_iter.next();
+
+    // Validate authority is signer
+    if !authority_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    // Check authority is not renounced
+    if pool.is_authority_renounced() {
+        return Err(StakingError::AuthorityRenounced.into());
+    }
+
+    // Verify authority
+    if pool.authority != *authority_info.key {
+        return Err(StakingError::InvalidAuthority.into());
+    }
+
+    if period_seconds > StakingPool::MAX_BOOTSTRAP_PERIOD_SECONDS {
+        return Err(StakingError::SettingExceedsMaximum
+            .with_context(ErrorContext::MaximumAmount(StakingPool::MAX_BOOTSTRAP_PERIOD_SECONDS)));
+    }
+
+    // Pools with an authority log must pass it (after the system program)
+    let log_info = next_authority_log(&pool, account_info_iter)?;
+
+    // Legacy pools have no room for the period (payer = authority)
+    StakingPool::maybe_realloc(pool_info, authority_info, system_program_info)?;
+
+    if let Some(log_info) = log_info {
+        let entry = AuthorityLogEntry::integer(
+            pool_time(&pool)?,
+            *authority_info.key,
+            AuthorityAction::BootstrapPeriod,
+            pool.bootstrap_period_seconds,
+            period_seconds,
+        );
+        record_authority_actions(program_id, log_info, pool_info.key, authority_info, system_program_info, &[entry])?;
+    }
+
+    pool.bootstrap_period_seconds = period_seconds;
+    pool.save(pool_info)?;
+
+    msg!(
+        "Bootstrap period set to {}s (until {})",
+        period_seconds,
+        pool.creation_time().saturating_add(period_seconds as i64)
+    );
+
+    Ok(())
+}
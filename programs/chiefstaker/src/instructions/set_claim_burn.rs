@@ -0,0 +1,149 @@
+//! Set the claim burn (authority only)
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    incinerator, msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    authority_log::{next_authority_log, record_authority_actions},
+    error::{ErrorContext, StakingError},
+    loaders::load_pool,
+    state::{AuthorityAction, AuthorityLogEntry, StakingPool},
+    time::pool_time,
+};
+
+/// Set the share of each reward claim sent to the incinerator, whose
+/// lamports are destroyed, instead of the claimer. Any other destination
+/// would let the authority route part of every claim to itself.
+/// `burn_bps` is capped at StakingPool::MAX_CLAIM_BURN_BPS; 0 disables the
+/// burn.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable, signer]` Authority (pays rent if a legacy pool must grow)
+/// 2. `[]` Burn destination: the incinerator (ignored when disabling)
+/// 3. `[]` (optional) System program, required for legacy pool reallocation
+///    and pools with an authority log
+/// 4. `[writable]` Authority log (pools with an authority log only)
+pub fn process_set_claim_burn(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    burn_bps: u16,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let system_program_info = account_info_iter.next();
+
+    // Validate authority is signer
+    if !authority_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    // Check authority is not renounced
+    if pool.is_authority_renounced() {
+        return Err(StakingError::AuthorityRenounced.into());
+    }
+
+    // Verify authority
+    if pool.authority != *authority_info.key {
+        return Err(StakingError::InvalidAuthority.into());
+    }
+
+    // Pools with an authority log must pass it (after the system program)
+    let log_info = next_authority_log(&pool, account_info_iter)?;
+
+    if burn_bps > StakingPool::MAX_CLAIM_BURN_BPS {
+        return Err(StakingError::SettingExceedsMaximum.with_context(
+            ErrorContext::MaximumAmount(StakingPool::MAX_CLAIM_BURN_BPS as u64),
+        ));
+    }
+
+    let destination = if burn_bps == 0 {
+        Pubkey::default()
+    } else {
+        check_burn_destination(destination_info)?;
+        *destination_info.key
+    };
+
+    // Legacy pools have no room for the burn (payer = authority)
+    StakingPool::maybe_realloc(pool_info, authority_info, system_program_info)?;
+
+    if let Some(log_info) = log_info {
+        let now = pool_time(&pool)?;
+        let entries = [
+            AuthorityLogEntry::integer(
+                now,
+                *authority_info.key,
+                AuthorityAction::ClaimBurnBps,
+                pool.claim_burn_bps as u64,
+                burn_bps as u64,
+            ),
+            AuthorityLogEntry::pubkey(
+                now,
+                *authority_info.key,
+                AuthorityAction::ClaimBurnDestination,
+                pool.claim_burn_destination,
+                destination,
+            ),
+        ];
+        record_authority_actions(program_id, log_info, pool_info.key, authority_info, system_program_info, &entries)?;
+    }
+
+    pool.claim_burn_bps = burn_bps;
+    pool.claim_burn_destination = destination;
+    pool.save(pool_info)?;
+
+    if burn_bps == 0 {
+        msg!("Claim burn disabled");
+    } else {
+        msg!("Claim burn set to {} bps, sent to {}", burn_bps, destination);
+    }
+
+    Ok(())
+}
+
+/// Check `destination_info` can receive burned claims: only the incinerator,
+/// which is emptied every slot and always accepts lamports
+fn check_burn_destination(destination_info: &AccountInfo) -> Result<(), StakingError> {
+    if *destination_info.key != incinerator::id() {
+        return Err(StakingError::InvalidBurnDestination);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::system_program;
+
+    #[test]
+    fn test_check_burn_destination() {
+        let owner = system_program::id();
+        let mut lamports = 1_000_000_000u64;
+        let mut data = vec![];
+
+        // A funded wallet would let the authority collect the "burn"
+        let wallet = Pubkey::new_unique();
+        let wallet_info =
+            AccountInfo::new(&wallet, false, true, &mut lamports, &mut data, &owner, false, 0);
+        assert_eq!(
+            check_burn_destination(&wallet_info).unwrap_err(),
+            StakingError::InvalidBurnDestination
+        );
+
+        let mut lamports = 0u64;
+        let incinerator = incinerator::id();
+        let incinerator_info =
+            AccountInfo::new(&incinerator, false, true, &mut lamports, &mut data, &owner, false, 0);
+        assert!(check_burn_destination(&incinerator_info).is_ok());
+    }
+}
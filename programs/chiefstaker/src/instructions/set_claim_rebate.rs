@@ -0,0 +1,162 @@
+//! Set claim rebate instruction (authority only)
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    authority_log::{next_authority_log, record_authority_actions},
+    error::{ErrorContext, StakingError},
+    loaders::load_pool,
+    state::{
+        AuthorityAction, AuthorityLogEntry, GasRebateVault, StakingPool,
+        GAS_REBATE_DISCRIMINATOR, GAS_REBATE_SEED,
+    },
+    time::pool_time,
+};
+
+use super::set_maturity_bonus::create_lamport_vault;
+
+/// Set the claim gas rebate: every ClaimRewards / ClaimRewardsWrapped
+/// paying at least `rebate_lamports` also sends the owner `rebate_lamports`
+/// from the GasRebateVault PDA, for as long as the vault holds enough, so
+/// claims cost users nothing. Enabling rebates creates the vault (anyone
+/// then funds it with FundGasRebates). Setting 0 disables rebates, closes
+/// the vault and refunds its remaining lamports, whoever funded them, to
+/// the authority.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable, signer]` Authority (pays rent, receives refunds)
+/// 2. `[writable]` Gas rebate vault (PDA: ["gas_rebate", pool])
+/// 3. `[]` System program
+/// 4. `[writable]` Authority log (pools with an authority log only)
+pub fn process_set_claim_rebate(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    rebate_lamports: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Validate authority is signer
+    if !authority_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    // Check authority is not renounced
+    if pool.is_authority_renounced() {
+        return Err(StakingError::AuthorityRenounced.into());
+    }
+
+    // Verify authority
+    if pool.authority != *authority_info.key {
+        return Err(StakingError::InvalidAuthority.into());
+    }
+
+    if rebate_lamports > StakingPool::MAX_CLAIM_REBATE_LAMPORTS {
+        return Err(StakingError::SettingExceedsMaximum
+            .with_context(ErrorContext::MaximumAmount(StakingPool::MAX_CLAIM_REBATE_LAMPORTS)));
+    }
+
+    // Verify gas rebate vault PDA
+    let (expected_vault, vault_bump) = GasRebateVault::derive_pda(pool_info.key, program_id);
+    if *vault_info.key != expected_vault {
+        return Err(StakingError::InvalidPDA.into());
+    }
+
+    // Pools with an authority log must pass it (after the system program)
+    let log_info = next_authority_log(&pool, account_info_iter)?;
+
+    // Legacy pools have no room for the rebate flag (payer = authority)
+    StakingPool::maybe_realloc(pool_info, authority_info, Some(system_program_info))?;
+
+    let existing = if vault_info.data_is_empty() {
+        None
+    } else {
+        if vault_info.owner != program_id {
+            return Err(StakingError::InvalidAccountOwner.into());
+        }
+        let vault = GasRebateVault::try_from_slice(&vault_info.try_borrow_data()?)?;
+        if !vault.is_initialized() {
+            return Err(StakingError::NotInitialized.into());
+        }
+        Some(vault)
+    };
+    let old_rebate_lamports = existing.as_ref().map_or(0, |vault| vault.rebate_lamports);
+
+    if rebate_lamports == 0 {
+        // Disable: refund the vault (rent included) and zero it out
+        if existing.is_some() {
+            let vault_lamports = vault_info.lamports();
+            **vault_info.try_borrow_mut_lamports()? = 0;
+            **authority_info.try_borrow_mut_lamports()? += vault_lamports;
+            vault_info.try_borrow_mut_data()?.fill(0);
+            msg!("Gas rebate vault closed, refunded {} lamports", vault_lamports);
+        }
+    } else {
+        let mut vault = match existing {
+            Some(vault) => vault,
+            None => {
+                // Lamports sent ahead of creation are kept for rebates
+                let vault_seeds: &[&[u8]] =
+                    &[GAS_REBATE_SEED, pool_info.key.as_ref(), &[vault_bump]];
+                create_lamport_vault(
+                    program_id,
+                    authority_info,
+                    vault_info,
+                    system_program_info,
+                    vault_seeds,
+                    GasRebateVault::LEN,
+                )?;
+                GasRebateVault {
+                    discriminator: GAS_REBATE_DISCRIMINATOR,
+                    pool: *pool_info.key,
+                    bump: vault_bump,
+                    rebate_lamports: 0,
+                    total_funded: 0,
+                    total_rebated: 0,
+                    rebates_paid: 0,
+                }
+            }
+        };
+        vault.rebate_lamports = rebate_lamports;
+        vault.serialize(&mut &mut vault_info.try_borrow_mut_data()?[..])?;
+    }
+
+    if let Some(log_info) = log_info {
+        let entry = AuthorityLogEntry::integer(
+            pool_time(&pool)?,
+            *authority_info.key,
+            AuthorityAction::ClaimRebate,
+            old_rebate_lamports,
+            rebate_lamports,
+        );
+        record_authority_actions(
+            program_id,
+            log_info,
+            pool_info.key,
+            authority_info,
+            Some(system_program_info),
+            &[entry],
+        )?;
+    }
+
+    pool.has_gas_rebate_vault = rebate_lamports > 0;
+    pool.save(pool_info)?;
+
+    msg!("Claim gas rebate set to {} lamports", rebate_lamports);
+
+    Ok(())
+}
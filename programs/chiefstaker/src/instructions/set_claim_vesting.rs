@@ -0,0 +1,88 @@
+//! Set claim vesting instruction (authority only)
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    authority_log::{next_authority_log, record_authority_actions},
+    error::{ErrorContext, StakingError},
+    loaders::load_pool,
+    state::{AuthorityAction, AuthorityLogEntry, StakingPool},
+    time::pool_time,
+};
+
+/// Set `claim_vesting_seconds`: each later distribution vests to stakers
+/// linearly over that long instead of being claimable at once, so nobody
+/// can claim and dump right after a large deposit. Rewards still vesting
+/// when a position unstakes keep vesting on the position. At most
+/// MAX_CLAIM_VESTING_SECONDS; 0 makes new distributions instant again
+/// (a running schedule still completes).
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable, signer]` Authority (pays rent if a legacy pool must grow)
+/// 2. `[]` (optional) System program, required for legacy pool reallocation
+///    and pools with an authority log
+/// 3. `[writable]` Authority log (pools with an authority log only)
+pub fn process_set_claim_vesting(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    vesting_seconds: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let system_program_info = account_info_iter.next();
+
+    // Validate authority is signer
+    if !authority_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    // Check authority is not renounced
+    if pool.is_authority_renounced() {
+        return Err(StakingError::AuthorityRenounced.into());
+    }
+
+    // Verify authority
+    if pool.authority != *authority_info.key {
+        return Err(StakingError::InvalidAuthority.into());
+    }
+
+    if vesting_seconds > StakingPool::MAX_CLAIM_VESTING_SECONDS {
+        return Err(StakingError::SettingExceedsMaximum
+            .with_context(ErrorContext::MaximumAmount(StakingPool::MAX_CLAIM_VESTING_SECONDS)));
+    }
+
+    // Pools with an authority log must pass it (after the system program)
+    let log_info = next_authority_log(&pool, account_info_iter)?;
+
+    // Legacy pools have no room for the vesting fields (payer = authority)
+    StakingPool::maybe_realloc(pool_info, authority_info, system_program_info)?;
+
+    if let Some(log_info) = log_info {
+        let entry = AuthorityLogEntry::integer(
+            pool_time(&pool)?,
+            *authority_info.key,
+            AuthorityAction::ClaimVesting,
+            pool.claim_vesting_seconds,
+            vesting_seconds,
+        );
+        record_authority_actions(program_id, log_info, pool_info.key, authority_info, system_program_info, &[entry])?;
+    }
+
+    pool.claim_vesting_seconds = vesting_seconds;
+    pool.save(pool_info)?;
+
+    msg!("Claim vesting set to {}s", vesting_seconds);
+
+    Ok(())
+}
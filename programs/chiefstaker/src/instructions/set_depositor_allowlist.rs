@@ -0,0 +1,168 @@
+//! Set depositor allowlist instruction (authority only)
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    authority_log::{next_authority_log, record_authority_actions},
+    error::StakingError,
+    loaders::load_pool,
+    state::{
+        AuthorityAction, AuthorityLogEntry, DepositorAllowlist, StakingPool,
+        DEPOSITOR_ALLOWLIST_DISCRIMINATOR, DEPOSITOR_ALLOWLIST_SEED,
+    },
+    time::pool_time,
+};
+
+/// Restrict DepositRewards and DepositRewardsSplit to up to 8 depositors
+/// (e.g. the project's fee router). An empty list removes the allowlist,
+/// refunds its rent to the authority and makes deposits permissionless again.
+/// SOL sent straight to the pool PDA and synced with SyncRewards is not
+/// affected.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable, signer]` Authority (pays rent)
+/// 2. `[writable]` Depositor allowlist (PDA: ["depositor_allowlist", pool])
+/// 3. `[]` System program
+/// 4. `[writable]` Authority log (pools with an authority log only)
+pub fn process_set_depositor_allowlist(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    depositors: Vec<Pubkey>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let allowlist_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Validate authority is signer
+    if !authority_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    // Check authority is not renounced
+    if pool.is_authority_renounced() {
+        return Err(StakingError::AuthorityRenounced.into());
+    }
+
+    // Verify authority
+    if pool.authority != *authority_info.key {
+        return Err(StakingError::InvalidAuthority.into());
+    }
+
+    // Verify depositor allowlist PDA
+    let (expected_allowlist, allowlist_bump) =
+        DepositorAllowlist::derive_pda(pool_info.key, program_id);
+    if *allowlist_info.key != expected_allowlist {
+        return Err(StakingError::InvalidPDA.into());
+    }
+
+    // Pools with an authority log must pass it (after the system program)
+    let log_info = next_authority_log(&pool, account_info_iter)?;
+
+    // Legacy pools have no room for the allowlist flag (payer = authority)
+    StakingPool::maybe_realloc(pool_info, authority_info, Some(system_program_info))?;
+
+    let existing = if allowlist_info.data_is_empty() {
+        None
+    } else {
+        if allowlist_info.owner != program_id {
+            return Err(StakingError::InvalidAccountOwner.into());
+        }
+        let allowlist = DepositorAllowlist::try_from_slice(&allowlist_info.try_borrow_data()?)?;
+        if !allowlist.is_initialized() {
+            return Err(StakingError::NotInitialized.into());
+        }
+        Some(allowlist)
+    };
+    let old_count = existing.as_ref().map_or(0, |allowlist| allowlist.count);
+
+    if depositors.is_empty() {
+        // Remove the allowlist: refund its rent and zero it out
+        if existing.is_some() {
+            let allowlist_lamports = allowlist_info.lamports();
+            **allowlist_info.try_borrow_mut_lamports()? = 0;
+            **authority_info.try_borrow_mut_lamports()? += allowlist_lamports;
+            allowlist_info.try_borrow_mut_data()?.fill(0);
+        }
+    } else {
+        let mut allowlist = existing.unwrap_or(DepositorAllowlist {
+            discriminator: DEPOSITOR_ALLOWLIST_DISCRIMINATOR,
+            pool: *pool_info.key,
+            count: 0,
+            bump: allowlist_bump,
+            depositors: Default::default(),
+        });
+        allowlist.set_depositors(&depositors)?;
+
+        if allowlist_info.data_is_empty() {
+            let rent = Rent::get()?;
+            let allowlist_seeds = &[
+                DEPOSITOR_ALLOWLIST_SEED,
+                pool_info.key.as_ref(),
+                &[allowlist_bump],
+            ];
+            invoke_signed(
+                &system_instruction::create_account(
+                    authority_info.key,
+                    allowlist_info.key,
+                    rent.minimum_balance(DepositorAllowlist::LEN),
+                    DepositorAllowlist::LEN as u64,
+                    program_id,
+                ),
+                &[
+                    authority_info.clone(),
+                    allowlist_info.clone(),
+                    system_program_info.clone(),
+                ],
+                &[allowlist_seeds],
+            )?;
+        }
+
+        allowlist.serialize(&mut &mut allowlist_info.try_borrow_mut_data()?[..])?;
+    }
+
+    if let Some(log_info) = log_info {
+        let entry = AuthorityLogEntry::integer(
+            pool_time(&pool)?,
+            *authority_info.key,
+            AuthorityAction::DepositorAllowlist,
+            old_count as u64,
+            depositors.len() as u64,
+        );
+        record_authority_actions(
+            program_id,
+            log_info,
+            pool_info.key,
+            authority_info,
+            Some(system_program_info),
+            &[entry],
+        )?;
+    }
+
+    pool.has_depositor_allowlist = !depositors.is_empty();
+    pool.save(pool_info)?;
+
+    if depositors.is_empty() {
+        msg!("Depositor allowlist removed (deposits are permissionless)");
+    } else {
+        msg!("Depositor allowlist set to {} depositors", depositors.len());
+    }
+
+    Ok(())
+}
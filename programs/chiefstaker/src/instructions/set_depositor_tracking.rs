@@ -0,0 +1,83 @@
+//! Turn depositor contribution tracking on or off (authority only)
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    authority_log::{next_authority_log, record_authority_actions},
+    error::StakingError,
+    loaders::load_pool,
+    state::{AuthorityAction, AuthorityLogEntry, StakingPool},
+    time::pool_time,
+};
+
+/// Turn depositor tracking on or off.
+///
+/// While on, DepositRewards and DepositRewardsSplit take the depositor's
+/// DepositorRecord and add each deposit to it, creating the record on a
+/// depositor's first deposit. Turning tracking off leaves existing records
+/// in place; turning it back on resumes them.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable, signer]` Authority (pays rent if a legacy pool must grow)
+/// 2. `[]` (optional) System program, required for legacy pool reallocation
+///    and pools with an authority log
+/// 3. `[writable]` Authority log (pools with an authority log only)
+pub fn process_set_depositor_tracking(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    enabled: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let system_program_info = account_info_iter.next();
+
+    // Validate authority is signer
+    if !authority_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    // Check authority is not renounced
+    if pool.is_authority_renounced() {
+        return Err(StakingError::AuthorityRenounced.into());
+    }
+
+    // Verify authority
+    if pool.authority != *authority_info.key {
+        return Err(StakingError::InvalidAuthority.into());
+    }
+
+    // Pools with an authority log must pass it (after the system program)
+    let log_info = next_authority_log(&pool, account_info_iter)?;
+
+    // Legacy pools have no room for the flag (payer = authority)
+    StakingPool::maybe_realloc(pool_info, authority_info, system_program_info)?;
+
+    if let Some(log_info) = log_info {
+        let entry = AuthorityLogEntry::integer(
+            pool_time(&pool)?,
+            *authority_info.key,
+            AuthorityAction::DepositorTracking,
+            pool.track_depositors as u64,
+            enabled as u64,
+        );
+        record_authority_actions(program_id, log_info, pool_info.key, authority_info, system_program_info, &[entry])?;
+    }
+
+    pool.track_depositors = enabled;
+    pool.save(pool_info)?;
+
+    msg!("Depositor tracking {}", if enabled { "enabled" } else { "disabled" });
+
+    Ok(())
+}
@@ -0,0 +1,102 @@
+//! Set the minimum total stake for reward distribution (authority only)
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+use spl_token_2022::extension::StateWithExtensions;
+
+use crate::{
+    authority_log::{next_authority_log, record_authority_actions},
+    error::{ErrorContext, StakingError},
+    loaders::load_pool,
+    state::{AuthorityAction, AuthorityLogEntry, StakingPool},
+    time::pool_time,
+};
+
+/// Set `min_distribution_stake`: while total_staked is below it, deposits
+/// and syncs leave SOL undistributed. Expressed in raw token units, so it
+/// scales with the mint's decimals. Capped at the mint's current supply,
+/// since a larger threshold could never be met.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable, signer]` Authority (pays rent if a legacy pool must grow)
+/// 2. `[]` Token mint
+/// 3. `[]` (optional) System program, required for legacy pool reallocation
+///    and pools with an authority log
+/// 4. `[writable]` Authority log (pools with an authority log only)
+pub fn process_set_distribution_threshold(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    min_total_staked: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let system_program_info = account_info_iter.next();
+
+    // Validate authority is signer
+    if !authority_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    // Check authority is not renounced
+    if pool.is_authority_renounced() {
+        return Err(StakingError::AuthorityRenounced.into());
+    }
+
+    // Verify authority
+    if pool.authority != *authority_info.key {
+        return Err(StakingError::InvalidAuthority.into());
+    }
+
+    // Pools with an authority log must pass it (after the system program)
+    let log_info = next_authority_log(&pool, account_info_iter)?;
+
+    // Validate mint and bound the threshold by its supply
+    if *mint_info.key != pool.mint {
+        return Err(StakingError::InvalidPoolMint.into());
+    }
+    if mint_info.owner != &spl_token_2022::id() {
+        return Err(StakingError::InvalidAccountOwner.into());
+    }
+    let supply = {
+        let mint_data = mint_info.try_borrow_data()?;
+        StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?
+            .base
+            .supply
+    };
+    if min_total_staked > supply {
+        return Err(StakingError::SettingExceedsMaximum
+            .with_context(ErrorContext::MaximumAmount(supply)));
+    }
+
+    // Legacy pools have no room for the threshold (payer = authority)
+    StakingPool::maybe_realloc(pool_info, authority_info, system_program_info)?;
+
+    if let Some(log_info) = log_info {
+        let entry = AuthorityLogEntry::integer(
+            pool_time(&pool)?,
+            *authority_info.key,
+            AuthorityAction::DistributionThreshold,
+            pool.min_distribution_stake,
+            min_total_staked,
+        );
+        record_authority_actions(program_id, log_info, pool_info.key, authority_info, system_program_info, &[entry])?;
+    }
+
+    pool.min_distribution_stake = min_total_staked;
+    pool.save(pool_info)?;
+
+    msg!("Minimum stake for distribution set to {}", min_total_staked);
+
+    Ok(())
+}
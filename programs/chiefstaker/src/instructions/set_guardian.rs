@@ -0,0 +1,70 @@
+//! Set guardian instruction
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::StakingError,
+    guardian::check_guardian_signature,
+    loaders::{load_pool, load_user_stake},
+    state::UserStake,
+};
+
+/// Give the position a guardian whose co-signature Unstake, CompleteUnstake
+/// and MigrateStake then require. Claims stay single-signature. Replacing
+/// an existing guardian needs its co-signature, and any pending
+/// ClearGuardian timelock is cancelled.
+///
+/// Accounts:
+/// 0. `[]` Pool account
+/// 1. `[writable]` User stake account
+/// 2. `[writable, signer]` User/owner
+/// 3. `[signer]` Current guardian (positions with a guardian only)
+/// 4. `[]` (optional) System program (legacy account reallocation)
+pub fn process_set_guardian(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    guardian: Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let user_stake_info = next_account_info(account_info_iter)?;
+    let user_info = next_account_info(account_info_iter)?;
+
+    // Validate user is signer
+    if !user_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let _pool = load_pool(program_id, pool_info)?;
+
+    // Load and validate user stake
+    let mut user_stake =
+        load_user_stake(program_id, user_stake_info, pool_info.key, user_info.key)?;
+
+    // A guardian must be a second key
+    if guardian == Pubkey::default() || guardian == user_stake.owner {
+        return Err(StakingError::InvalidGuardian.into());
+    }
+
+    // The current guardian, if any, must agree to its replacement
+    check_guardian_signature(&user_stake, account_info_iter)?;
+
+    // Realloc legacy accounts to current size (payer = user)
+    let system_program_info = account_info_iter.next();
+    UserStake::maybe_realloc(user_stake_info, user_info, system_program_info)?;
+
+    user_stake.guardian = guardian;
+    user_stake.guardian_removal_time = 0;
+    user_stake.save(user_stake_info)?;
+
+    msg!("Guardian set to {}", guardian);
+
+    Ok(())
+}
@@ -0,0 +1,86 @@
+//! Set the age bonus ExtendLock grants (authority only)
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    authority_log::{next_authority_log, record_authority_actions},
+    error::{ErrorContext, StakingError},
+    loaders::load_pool,
+    state::{AuthorityAction, AuthorityLogEntry, StakingPool},
+    time::pool_time,
+};
+
+/// Set `lock_boost_bps`: each second a staker adds with ExtendLock makes the
+/// position `lock_boost_bps / 10000` seconds older. At most
+/// MAX_LOCK_BOOST_BPS; 0 disables ExtendLock. Locks already extended keep
+/// the bonus they were granted.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable, signer]` Authority (pays rent if a legacy pool must grow)
+/// 2. `[]` (optional) System program, required for legacy pool reallocation
+///    and pools with an authority log
+/// 3. `[writable]` Authority log (pools with an authority log only)
+pub fn process_set_lock_boost(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    boost_bps: u16,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let system_program_info = account_info_iter.next();
+
+    // Validate authority is signer
+    if !authority_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    // Check authority is not renounced
+    if pool.is_authority_renounced() {
+        return Err(StakingError::AuthorityRenounced.into());
+    }
+
+    // Verify authority
+    if pool.authority != *authority_info.key {
+        return Err(StakingError::InvalidAuthority.into());
+    }
+
+    if boost_bps > StakingPool::MAX_LOCK_BOOST_BPS {
+        return Err(StakingError::SettingExceedsMaximum
+            .with_context(ErrorContext::MaximumAmount(StakingPool::MAX_LOCK_BOOST_BPS as u64)));
+    }
+
+    // Pools with an authority log must pass it (after the system program)
+    let log_info = next_authority_log(&pool, account_info_iter)?;
+
+    // Legacy pools have no room for the boost (payer = authority)
+    StakingPool::maybe_realloc(pool_info, authority_info, system_program_info)?;
+
+    if let Some(log_info) = log_info {
+        let entry = AuthorityLogEntry::integer(
+            pool_time(&pool)?,
+            *authority_info.key,
+            AuthorityAction::LockBoostBps,
+            pool.lock_boost_bps as u64,
+            boost_bps as u64,
+        );
+        record_authority_actions(program_id, log_info, pool_info.key, authority_info, system_program_info, &[entry])?;
+    }
+
+    pool.lock_boost_bps = boost_bps;
+    pool.save(pool_info)?;
+
+    msg!("Lock boost set to {} bps", boost_bps);
+
+    Ok(())
+}
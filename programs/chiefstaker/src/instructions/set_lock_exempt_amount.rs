@@ -0,0 +1,86 @@
+//! Set the lock-exempt principal (authority only)
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    authority_log::{next_authority_log, record_authority_actions},
+    error::StakingError,
+    loaders::load_pool,
+    state::{AuthorityAction, AuthorityLogEntry, StakingPool},
+    time::pool_time,
+};
+
+/// Set how much of each position the pool lock leaves free. The pool's
+/// lock_duration_seconds only holds principal above `lock_exempt_amount`
+/// (in token base units), so small positions can always unstake while
+/// large ones stay subject to the lock. Positions' own ExtendLock and
+/// lock tranches are unaffected. 0 locks whole positions again.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable, signer]` Authority (pays rent if a legacy pool must grow)
+/// 2. `[]` (optional) System program, required for legacy pool reallocation
+///    and pools with an authority log
+/// 3. `[writable]` Authority log (pools with an authority log only)
+pub fn process_set_lock_exempt_amount(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    lock_exempt_amount: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let system_program_info = account_info_iter.next();
+
+    // Validate authority is signer
+    if !authority_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    // Check authority is not renounced
+    if pool.is_authority_renounced() {
+        return Err(StakingError::AuthorityRenounced.into());
+    }
+
+    // Verify authority
+    if pool.authority != *authority_info.key {
+        return Err(StakingError::InvalidAuthority.into());
+    }
+
+    // Pools with an authority log must pass it (after the system program)
+    let log_info = next_authority_log(&pool, account_info_iter)?;
+
+    // Legacy pools have no room for the amount (payer = authority)
+    StakingPool::maybe_realloc(pool_info, authority_info, system_program_info)?;
+
+    if let Some(log_info) = log_info {
+        let entry = AuthorityLogEntry::integer(
+            pool_time(&pool)?,
+            *authority_info.key,
+            AuthorityAction::LockExemptAmount,
+            pool.lock_exempt_amount,
+            lock_exempt_amount,
+        );
+        record_authority_actions(program_id, log_info, pool_info.key, authority_info, system_program_info, &[entry])?;
+    }
+
+    pool.lock_exempt_amount = lock_exempt_amount;
+    pool.save(pool_info)?;
+
+    if lock_exempt_amount == 0 {
+        msg!("Pool lock holds whole positions");
+    } else {
+        msg!("Pool lock holds principal above {} tokens", lock_exempt_amount);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,192 @@
+//! Set maturity bonus instruction (authority only)
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    authority_log::{next_authority_log, record_authority_actions},
+    error::{ErrorContext, StakingError},
+    loaders::load_pool,
+    state::{
+        AuthorityAction, AuthorityLogEntry, BonusBudget, StakingPool, BONUS_BUDGET_DISCRIMINATOR,
+        BONUS_BUDGET_SEED,
+    },
+    time::pool_time,
+};
+
+/// Set `maturity_bonus_bps`: positions that reach 3 tau of age can claim
+/// that share of their lifetime claimed rewards once, paid from the
+/// BonusBudget PDA. Enabling the bonus creates the budget (the authority
+/// then funds it with plain SOL transfers). Setting 0 disables the bonus,
+/// closes the budget and refunds its remaining lamports to the authority.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable, signer]` Authority (pays rent, receives refunds)
+/// 2. `[writable]` Bonus budget (PDA: ["bonus_budget", pool])
+/// 3. `[]` System program
+/// 4. `[writable]` Authority log (pools with an authority log only)
+pub fn process_set_maturity_bonus(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    bonus_bps: u16,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let budget_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Validate authority is signer
+    if !authority_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    // Check authority is not renounced
+    if pool.is_authority_renounced() {
+        return Err(StakingError::AuthorityRenounced.into());
+    }
+
+    // Verify authority
+    if pool.authority != *authority_info.key {
+        return Err(StakingError::InvalidAuthority.into());
+    }
+
+    if bonus_bps > StakingPool::MAX_MATURITY_BONUS_BPS {
+        return Err(StakingError::SettingExceedsMaximum.with_context(ErrorContext::MaximumAmount(
+            StakingPool::MAX_MATURITY_BONUS_BPS as u64,
+        )));
+    }
+
+    // Verify bonus budget PDA
+    let (expected_budget, budget_bump) = BonusBudget::derive_pda(pool_info.key, program_id);
+    if *budget_info.key != expected_budget {
+        return Err(StakingError::InvalidPDA.into());
+    }
+
+    // Pools with an authority log must pass it (after the system program)
+    let log_info = next_authority_log(&pool, account_info_iter)?;
+
+    // Legacy pools have no room for the bonus (payer = authority)
+    StakingPool::maybe_realloc(pool_info, authority_info, Some(system_program_info))?;
+
+    if !budget_info.data_is_empty() {
+        if budget_info.owner != program_id {
+            return Err(StakingError::InvalidAccountOwner.into());
+        }
+        let budget = BonusBudget::try_from_slice(&budget_info.try_borrow_data()?)?;
+        if !budget.is_initialized() {
+            return Err(StakingError::NotInitialized.into());
+        }
+    }
+
+    if bonus_bps == 0 {
+        // Disable: refund the budget (rent included) and zero it out
+        if !budget_info.data_is_empty() {
+            let budget_lamports = budget_info.lamports();
+            **budget_info.try_borrow_mut_lamports()? = 0;
+            **authority_info.try_borrow_mut_lamports()? += budget_lamports;
+            budget_info.try_borrow_mut_data()?.fill(0);
+            msg!("Bonus budget closed, refunded {} lamports", budget_lamports);
+        }
+    } else if budget_info.data_is_empty() {
+        // Lamports sent ahead of creation are kept as budget
+        let budget_seeds: &[&[u8]] = &[BONUS_BUDGET_SEED, pool_info.key.as_ref(), &[budget_bump]];
+        create_lamport_vault(
+            program_id,
+            authority_info,
+            budget_info,
+            system_program_info,
+            budget_seeds,
+            BonusBudget::LEN,
+        )?;
+
+        let budget = BonusBudget {
+            discriminator: BONUS_BUDGET_DISCRIMINATOR,
+            pool: *pool_info.key,
+            bump: budget_bump,
+            total_paid: 0,
+        };
+        budget.serialize(&mut &mut budget_info.try_borrow_mut_data()?[..])?;
+    }
+
+    if let Some(log_info) = log_info {
+        let entry = AuthorityLogEntry::integer(
+            pool_time(&pool)?,
+            *authority_info.key,
+            AuthorityAction::MaturityBonusBps,
+            pool.maturity_bonus_bps as u64,
+            bonus_bps as u64,
+        );
+        record_authority_actions(
+            program_id,
+            log_info,
+            pool_info.key,
+            authority_info,
+            Some(system_program_info),
+            &[entry],
+        )?;
+    }
+
+    pool.maturity_bonus_bps = bonus_bps;
+    pool.save(pool_info)?;
+
+    msg!("Maturity bonus set to {} bps", bonus_bps);
+
+    Ok(())
+}
+
+/// Create the program-owned PDA `account` with `len` bytes, paid by
+/// `payer`. Lamports already sent to the address are kept (they would make
+/// create_account fail), so vaults funded by plain transfers can't be
+/// blocked by a transfer ahead of their creation.
+pub fn create_lamport_vault<'a>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    seeds: &[&[u8]],
+    len: usize,
+) -> ProgramResult {
+    let required = Rent::get()?
+        .minimum_balance(len)
+        .saturating_sub(account.lamports());
+    if account.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(payer.key, account.key, required, len as u64, program_id),
+            &[payer.clone(), account.clone(), system_program.clone()],
+            &[seeds],
+        );
+    }
+
+    // create_account refuses funded addresses: top up, allocate, assign
+    if required > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, required),
+            &[payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(account.key, len as u64),
+        &[account.clone(), system_program.clone()],
+        &[seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(account.key, program_id),
+        &[account.clone(), system_program.clone()],
+        &[seeds],
+    )
+}
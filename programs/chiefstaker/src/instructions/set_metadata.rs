@@ -17,13 +17,14 @@ use spl_token_metadata_interface::state::TokenMetadata;
 
 use crate::{
     error::StakingError,
-    state::{PoolMetadata, StakingPool, METADATA_DISCRIMINATOR, METADATA_SEED},
+    loaders::load_pool,
+    state::{PoolMetadata, METADATA_DISCRIMINATOR, METADATA_SEED},
 };
 
 const NAME_SUFFIX: &str = " Staking Pool";
 const TAG_STAKING_POOL: &str = "#stakingpool";
 const TAG_CHIEFSTAKER: &str = "#chiefstaker";
-const URL_PREFIX: &str = "https://labs.chiefpussy.com/staking/";
+pub const URL_PREFIX: &str = "https://labs.chiefpussy.com/staking/";
 
 /// Set pool metadata. Permissionless, no instruction args.
 ///
@@ -56,19 +57,7 @@ pub fn process_set_pool_metadata(
     }
 
     // Load and validate pool
-    if pool_info.owner != program_id {
-        return Err(StakingError::InvalidAccountOwner.into());
-    }
-    let pool = StakingPool::try_from_slice(&pool_info.try_borrow_data()?)?;
-    if !pool.is_initialized() {
-        return Err(StakingError::NotInitialized.into());
-    }
-
-    // Verify pool PDA
-    let (expected_pool, _) = StakingPool::derive_pda(&pool.mint, program_id);
-    if *pool_info.key != expected_pool {
-        return Err(StakingError::InvalidPDA.into());
-    }
+    let pool = load_pool(program_id, pool_info)?;
 
     // Verify mint matches pool
     if pool.mint != *mint_info.key {
@@ -0,0 +1,92 @@
+//! Set the minimum claim (authority only)
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    authority_log::{next_authority_log, record_authority_actions},
+    error::{ErrorContext, StakingError},
+    loaders::load_pool,
+    state::{AuthorityAction, AuthorityLogEntry, StakingPool},
+    time::pool_time,
+};
+
+/// Set the smallest claim the pool pays out. ClaimRewards (and its
+/// wrapped and session variants) with less pending defer instead of
+/// transferring dust: nothing moves, and the rewards keep accruing to the
+/// position until they reach the minimum. Residual claims of fully
+/// unstaked positions are always paid. `min_claim_lamports` is capped at
+/// StakingPool::MAX_MIN_CLAIM_LAMPORTS; 0 removes the minimum.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable, signer]` Authority (pays rent if a legacy pool must grow)
+/// 2. `[]` (optional) System program, required for legacy pool reallocation
+///    and pools with an authority log
+/// 3. `[writable]` Authority log (pools with an authority log only)
+pub fn process_set_min_claim_lamports(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    min_claim_lamports: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let system_program_info = account_info_iter.next();
+
+    // Validate authority is signer
+    if !authority_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    // Check authority is not renounced
+    if pool.is_authority_renounced() {
+        return Err(StakingError::AuthorityRenounced.into());
+    }
+
+    // Verify authority
+    if pool.authority != *authority_info.key {
+        return Err(StakingError::InvalidAuthority.into());
+    }
+
+    // Pools with an authority log must pass it (after the system program)
+    let log_info = next_authority_log(&pool, account_info_iter)?;
+
+    if min_claim_lamports > StakingPool::MAX_MIN_CLAIM_LAMPORTS {
+        return Err(StakingError::SettingExceedsMaximum
+            .with_context(ErrorContext::MaximumAmount(StakingPool::MAX_MIN_CLAIM_LAMPORTS)));
+    }
+
+    // Legacy pools have no room for the minimum (payer = authority)
+    StakingPool::maybe_realloc(pool_info, authority_info, system_program_info)?;
+
+    if let Some(log_info) = log_info {
+        let entry = AuthorityLogEntry::integer(
+            pool_time(&pool)?,
+            *authority_info.key,
+            AuthorityAction::MinClaimLamports,
+            pool.min_claim_lamports,
+            min_claim_lamports,
+        );
+        record_authority_actions(program_id, log_info, pool_info.key, authority_info, system_program_info, &[entry])?;
+    }
+
+    pool.min_claim_lamports = min_claim_lamports;
+    pool.save(pool_info)?;
+
+    if min_claim_lamports == 0 {
+        msg!("Minimum claim removed");
+    } else {
+        msg!("Claims below {} lamports are deferred", min_claim_lamports);
+    }
+
+    Ok(())
+}
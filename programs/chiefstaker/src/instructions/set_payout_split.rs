@@ -0,0 +1,145 @@
+//! Set payout split instruction
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    error::StakingError,
+    loaders::{load_pool, load_user_stake},
+    state::{PayoutSplit, UserStake, PAYOUT_SPLIT_DISCRIMINATOR, PAYOUT_SPLIT_SEED},
+};
+
+/// Split this position's reward payouts across up to 4 destinations by bps
+/// share. Claims and unstakes then credit each destination its share instead
+/// of the owner or declared recipient. An empty list removes the split and
+/// refunds the split account's rent to the owner.
+///
+/// Accounts:
+/// 0. `[]` Pool account
+/// 1. `[writable]` User stake account
+/// 2. `[writable, signer]` User/owner (pays rent)
+/// 3. `[writable]` Payout split (PDA: ["payout_split", pool, owner])
+/// 4. `[]` System program
+pub fn process_set_payout_split(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    destinations: Vec<(Pubkey, u16)>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let user_stake_info = next_account_info(account_info_iter)?;
+    let user_info = next_account_info(account_info_iter)?;
+    let split_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Validate user is signer
+    if !user_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let _pool = load_pool(program_id, pool_info)?;
+
+    // Load and validate user stake
+    let mut user_stake =
+        load_user_stake(program_id, user_stake_info, pool_info.key, user_info.key)?;
+
+    // Verify payout split PDA
+    let (expected_split, split_bump) =
+        PayoutSplit::derive_pda(pool_info.key, user_info.key, program_id);
+    if *split_info.key != expected_split {
+        return Err(StakingError::InvalidPDA.into());
+    }
+
+    // Paying rewards back into program accounts would strand them
+    if destinations.iter().any(|(recipient, _)| {
+        recipient == pool_info.key || recipient == user_stake_info.key || recipient == split_info.key
+    }) {
+        return Err(StakingError::InvalidRewardRecipient.into());
+    }
+
+    // Realloc legacy accounts to current size (payer = user)
+    UserStake::maybe_realloc(user_stake_info, user_info, Some(system_program_info))?;
+
+    if destinations.is_empty() {
+        // Remove the split: refund the split account's rent and zero it out
+        if !split_info.data_is_empty() {
+            if split_info.owner != program_id {
+                return Err(StakingError::InvalidAccountOwner.into());
+            }
+            let split_lamports = split_info.lamports();
+            **split_info.try_borrow_mut_lamports()? = 0;
+            **user_info.try_borrow_mut_lamports()? += split_lamports;
+            split_info.try_borrow_mut_data()?.fill(0);
+        }
+
+        user_stake.has_payout_split = false;
+        user_stake.save(user_stake_info)?;
+
+        msg!("Payout split removed (rewards paid to owner or declared recipient)");
+        return Ok(());
+    }
+
+    let mut split = if split_info.data_is_empty() {
+        let rent = Rent::get()?;
+        let split_seeds = &[
+            PAYOUT_SPLIT_SEED,
+            pool_info.key.as_ref(),
+            user_info.key.as_ref(),
+            &[split_bump],
+        ];
+        invoke_signed(
+            &system_instruction::create_account(
+                user_info.key,
+                split_info.key,
+                rent.minimum_balance(PayoutSplit::LEN),
+                PayoutSplit::LEN as u64,
+                program_id,
+            ),
+            &[
+                user_info.clone(),
+                split_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[split_seeds],
+        )?;
+
+        PayoutSplit {
+            discriminator: PAYOUT_SPLIT_DISCRIMINATOR,
+            pool: *pool_info.key,
+            owner: *user_info.key,
+            count: 0,
+            bump: split_bump,
+            destinations: Default::default(),
+        }
+    } else {
+        if split_info.owner != program_id {
+            return Err(StakingError::InvalidAccountOwner.into());
+        }
+        let split = PayoutSplit::try_from_slice(&split_info.try_borrow_data()?)?;
+        if !split.is_initialized() {
+            return Err(StakingError::NotInitialized.into());
+        }
+        split
+    };
+
+    split.set_destinations(&destinations)?;
+    split.serialize(&mut &mut split_info.try_borrow_mut_data()?[..])?;
+
+    user_stake.has_payout_split = true;
+    user_stake.save(user_stake_info)?;
+
+    msg!("Payout split set across {} destinations", destinations.len());
+
+    Ok(())
+}
@@ -0,0 +1,99 @@
+//! Set price oracle instruction (authority only)
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    authority_log::{next_authority_log, record_authority_actions},
+    error::StakingError,
+    loaders::load_pool,
+    oracle::{is_oracle_program, read_price},
+    state::{AuthorityAction, AuthorityLogEntry, StakingPool},
+    time::pool_time,
+};
+
+/// Register the price account GetPoolStats reads to report TVL and APY in
+/// USD. It must be a Pyth PriceUpdateV2 or Switchboard On-Demand feed
+/// account that currently holds a valid price. Passing the system program
+/// (the default pubkey) as the price account removes the oracle.
+///
+/// Only the owner program is checked: the authority is trusted to pick the
+/// feed for the pool mint.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable, signer]` Authority (pays rent if a legacy pool must grow)
+/// 2. `[]` Price account, or the system program to remove the oracle
+/// 3. `[]` (optional) System program, required for legacy pool reallocation
+///    and pools with an authority log
+/// 4. `[writable]` Authority log (pools with an authority log only)
+pub fn process_set_price_oracle(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let oracle_info = next_account_info(account_info_iter)?;
+    let system_program_info = account_info_iter.next();
+
+    // Validate authority is signer
+    if !authority_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    // Check authority is not renounced
+    if pool.is_authority_renounced() {
+        return Err(StakingError::AuthorityRenounced.into());
+    }
+
+    // Verify authority
+    if pool.authority != *authority_info.key {
+        return Err(StakingError::InvalidAuthority.into());
+    }
+
+    // Validate the price account (the default pubkey clears the oracle)
+    let oracle = *oracle_info.key;
+    if oracle != Pubkey::default() {
+        if !is_oracle_program(oracle_info.owner) {
+            return Err(StakingError::InvalidOracle.into());
+        }
+        read_price(oracle_info)?;
+    }
+
+    // Pools with an authority log must pass it (after the system program)
+    let log_info = next_authority_log(&pool, account_info_iter)?;
+
+    // Legacy pools have no room for the oracle (payer = authority)
+    StakingPool::maybe_realloc(pool_info, authority_info, system_program_info)?;
+
+    if let Some(log_info) = log_info {
+        let entry = AuthorityLogEntry::pubkey(
+            pool_time(&pool)?,
+            *authority_info.key,
+            AuthorityAction::PriceOracle,
+            pool.price_oracle,
+            oracle,
+        );
+        record_authority_actions(program_id, log_info, pool_info.key, authority_info, system_program_info, &[entry])?;
+    }
+
+    pool.price_oracle = oracle;
+    pool.save(pool_info)?;
+
+    if oracle == Pubkey::default() {
+        msg!("Price oracle removed");
+    } else {
+        msg!("Price oracle set to {}", oracle);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,92 @@
+//! Set the SyncPool rebase threshold (authority only)
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    authority_log::{next_authority_log, record_authority_actions},
+    error::{ErrorContext, StakingError},
+    loaders::load_pool,
+    state::{AuthorityAction, AuthorityLogEntry, StakingPool},
+    time::pool_time,
+};
+
+/// Set how far (now - base_time) / tau may grow, in basis points of tau,
+/// before SyncPool rebases. Fewer rebases compound less rounding into
+/// sum_stake_exp; stakes still rebase in place past MAX_EXP_INPUT, and
+/// SyncPool always rebases a sum_stake_exp near overflow.
+/// `rebase_threshold_bps` is capped at StakingPool::MAX_REBASE_THRESHOLD_BPS;
+/// 0 restores the default (REBASE_WARN_EXP_INPUT).
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable, signer]` Authority (pays rent if a legacy pool must grow)
+/// 2. `[]` (optional) System program, required for legacy pool reallocation
+///    and pools with an authority log
+/// 3. `[writable]` Authority log (pools with an authority log only)
+pub fn process_set_rebase_threshold(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    rebase_threshold_bps: u32,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let system_program_info = account_info_iter.next();
+
+    // Validate authority is signer
+    if !authority_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    // Check authority is not renounced
+    if pool.is_authority_renounced() {
+        return Err(StakingError::AuthorityRenounced.into());
+    }
+
+    // Verify authority
+    if pool.authority != *authority_info.key {
+        return Err(StakingError::InvalidAuthority.into());
+    }
+
+    // Pools with an authority log must pass it (after the system program)
+    let log_info = next_authority_log(&pool, account_info_iter)?;
+
+    if rebase_threshold_bps > StakingPool::MAX_REBASE_THRESHOLD_BPS {
+        return Err(StakingError::SettingExceedsMaximum.with_context(
+            ErrorContext::MaximumAmount(StakingPool::MAX_REBASE_THRESHOLD_BPS as u64),
+        ));
+    }
+
+    // Legacy pools have no room for the threshold (payer = authority)
+    StakingPool::maybe_realloc(pool_info, authority_info, system_program_info)?;
+
+    if let Some(log_info) = log_info {
+        let entry = AuthorityLogEntry::integer(
+            pool_time(&pool)?,
+            *authority_info.key,
+            AuthorityAction::RebaseThreshold,
+            pool.rebase_threshold_bps as u64,
+            rebase_threshold_bps as u64,
+        );
+        record_authority_actions(program_id, log_info, pool_info.key, authority_info, system_program_info, &[entry])?;
+    }
+
+    pool.rebase_threshold_bps = rebase_threshold_bps;
+    pool.save(pool_info)?;
+
+    msg!(
+        "SyncPool rebases past {} (WAD) time/tau",
+        pool.rebase_threshold_wad()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+//! Configure refunds of idle undistributed SOL (authority only)
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    authority_log::{next_authority_log, record_authority_actions},
+    error::StakingError,
+    loaders::load_pool,
+    state::{AuthorityAction, AuthorityLogEntry, StakingPool},
+    time::pool_time,
+};
+
+/// Set where and after how long SOL left undistributed in a pool without
+/// stakers can be returned via RefundUndistributed. A zero
+/// `refund_after_seconds` or default `refund_address` disables refunds.
+/// Changing the policy restarts the idle timer.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable, signer]` Authority (pays rent if a legacy pool must grow)
+/// 2. `[]` (optional) System program, required for legacy pool reallocation
+///    and pools with an authority log
+/// 3. `[writable]` Authority log (pools with an authority log only)
+pub fn process_set_refund_policy(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    refund_address: Pubkey,
+    refund_after_seconds: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let system_program_info = account_info_iter.next();
+
+    // Validate authority is signer
+    if !authority_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    // Check authority is not renounced
+    if pool.is_authority_renounced() {
+        return Err(StakingError::AuthorityRenounced.into());
+    }
+
+    // Verify authority
+    if pool.authority != *authority_info.key {
+        return Err(StakingError::InvalidAuthority.into());
+    }
+
+    // Pools with an authority log must pass it (after the system program)
+    let log_info = next_authority_log(&pool, account_info_iter)?;
+
+    // Refunding into the pool itself would loop the SOL back as rewards
+    if refund_address == *pool_info.key {
+        return Err(StakingError::InvalidRefundAddress.into());
+    }
+
+    // Legacy pools have no room for the policy (payer = authority)
+    StakingPool::maybe_realloc(pool_info, authority_info, system_program_info)?;
+
+    if let Some(log_info) = log_info {
+        let now = pool_time(&pool)?;
+        let entries = [
+            AuthorityLogEntry::pubkey(
+                now,
+                *authority_info.key,
+                AuthorityAction::RefundAddress,
+                pool.refund_address,
+                refund_address,
+            ),
+            AuthorityLogEntry::integer(
+                now,
+                *authority_info.key,
+                AuthorityAction::RefundAfterSeconds,
+                pool.refund_after_seconds,
+                refund_after_seconds,
+            ),
+        ];
+        record_authority_actions(program_id, log_info, pool_info.key, authority_info, system_program_info, &entries)?;
+    }
+
+    pool.refund_address = refund_address;
+    pool.refund_after_seconds = refund_after_seconds;
+    pool.undistributed_since = 0;
+    pool.save(pool_info)?;
+
+    if pool.has_refund_policy() {
+        msg!(
+            "Idle SOL refundable to {} after {} seconds",
+            refund_address,
+            refund_after_seconds
+        );
+    } else {
+        msg!("Idle SOL refunds disabled");
+    }
+
+    Ok(())
+}
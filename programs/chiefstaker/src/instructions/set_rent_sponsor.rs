@@ -0,0 +1,144 @@
+//! Set rent sponsor instruction (authority only)
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    authority_log::{next_authority_log, record_authority_actions},
+    error::StakingError,
+    loaders::load_pool,
+    state::{
+        AuthorityAction, AuthorityLogEntry, RentSponsorVault, StakingPool,
+        RENT_SPONSOR_DISCRIMINATOR, RENT_SPONSOR_SEED,
+    },
+    time::pool_time,
+};
+
+use super::set_maturity_bonus::create_lamport_vault;
+
+/// Enable or disable rent sponsorship. Enabling creates the RentSponsorVault
+/// PDA (the authority then funds it with plain SOL transfers); while it
+/// holds enough, Stake takes a new stake account's rent from it instead of
+/// the user, and CloseStakeAccount returns that rent to it. Disabling closes
+/// the vault and refunds its lamports to the authority; accounts it
+/// sponsored then return their rent to their owners on close.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable, signer]` Authority (pays rent, receives refunds)
+/// 2. `[writable]` Rent sponsor vault (PDA: ["rent_sponsor", pool])
+/// 3. `[]` System program
+/// 4. `[writable]` Authority log (pools with an authority log only)
+pub fn process_set_rent_sponsor(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    enabled: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Validate authority is signer
+    if !authority_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    // Check authority is not renounced
+    if pool.is_authority_renounced() {
+        return Err(StakingError::AuthorityRenounced.into());
+    }
+
+    // Verify authority
+    if pool.authority != *authority_info.key {
+        return Err(StakingError::InvalidAuthority.into());
+    }
+
+    // Verify rent sponsor vault PDA
+    let (expected_vault, vault_bump) = RentSponsorVault::derive_pda(pool_info.key, program_id);
+    if *vault_info.key != expected_vault {
+        return Err(StakingError::InvalidPDA.into());
+    }
+
+    // Pools with an authority log must pass it (after the system program)
+    let log_info = next_authority_log(&pool, account_info_iter)?;
+
+    // Legacy pools have no room for the sponsor flag (payer = authority)
+    StakingPool::maybe_realloc(pool_info, authority_info, Some(system_program_info))?;
+
+    let exists = if vault_info.data_is_empty() {
+        false
+    } else {
+        if vault_info.owner != program_id {
+            return Err(StakingError::InvalidAccountOwner.into());
+        }
+        let vault = RentSponsorVault::try_from_slice(&vault_info.try_borrow_data()?)?;
+        if !vault.is_initialized() {
+            return Err(StakingError::NotInitialized.into());
+        }
+        true
+    };
+
+    if enabled && !exists {
+        // Lamports sent ahead of creation are kept for sponsoring
+        let vault_seeds: &[&[u8]] = &[RENT_SPONSOR_SEED, pool_info.key.as_ref(), &[vault_bump]];
+        create_lamport_vault(
+            program_id,
+            authority_info,
+            vault_info,
+            system_program_info,
+            vault_seeds,
+            RentSponsorVault::LEN,
+        )?;
+        let vault = RentSponsorVault {
+            discriminator: RENT_SPONSOR_DISCRIMINATOR,
+            pool: *pool_info.key,
+            bump: vault_bump,
+            sponsored_accounts: 0,
+            sponsored_lamports: 0,
+        };
+        vault.serialize(&mut &mut vault_info.try_borrow_mut_data()?[..])?;
+    } else if !enabled && exists {
+        // Disable: refund the vault (rent included) and zero it out
+        let vault_lamports = vault_info.lamports();
+        **vault_info.try_borrow_mut_lamports()? = 0;
+        **authority_info.try_borrow_mut_lamports()? += vault_lamports;
+        vault_info.try_borrow_mut_data()?.fill(0);
+        msg!("Rent sponsor vault closed, refunded {} lamports", vault_lamports);
+    }
+
+    if let Some(log_info) = log_info {
+        let entry = AuthorityLogEntry::integer(
+            pool_time(&pool)?,
+            *authority_info.key,
+            AuthorityAction::RentSponsor,
+            pool.has_rent_sponsor as u64,
+            enabled as u64,
+        );
+        record_authority_actions(
+            program_id,
+            log_info,
+            pool_info.key,
+            authority_info,
+            Some(system_program_info),
+            &[entry],
+        )?;
+    }
+
+    pool.has_rent_sponsor = enabled;
+    pool.save(pool_info)?;
+
+    msg!("Rent sponsorship {}", if enabled { "enabled" } else { "disabled" });
+
+    Ok(())
+}
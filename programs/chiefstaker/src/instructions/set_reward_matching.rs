@@ -0,0 +1,160 @@
+//! Set reward matching instruction (authority only)
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    authority_log::{next_authority_log, record_authority_actions},
+    error::{ErrorContext, StakingError},
+    loaders::load_pool,
+    state::{
+        AuthorityAction, AuthorityLogEntry, MatchingVault, StakingPool,
+        MATCHING_VAULT_DISCRIMINATOR, MATCHING_VAULT_SEED,
+    },
+    time::pool_time,
+};
+
+use super::set_maturity_bonus::create_lamport_vault;
+
+/// Set the reward match rate: every DepositRewards and SyncRewards
+/// distribution is topped up by `match_bps / 10000` of its size from the
+/// MatchingVault PDA, for as long as the vault holds lamports. Enabling
+/// matching creates the vault (the authority then funds it with plain SOL
+/// transfers). Setting 0 disables matching, closes the vault and refunds its
+/// remaining lamports to the authority.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable, signer]` Authority (pays rent, receives refunds)
+/// 2. `[writable]` Matching vault (PDA: ["matching_vault", pool])
+/// 3. `[]` System program
+/// 4. `[writable]` Authority log (pools with an authority log only)
+pub fn process_set_reward_matching(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    match_bps: u16,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Validate authority is signer
+    if !authority_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    // Check authority is not renounced
+    if pool.is_authority_renounced() {
+        return Err(StakingError::AuthorityRenounced.into());
+    }
+
+    // Verify authority
+    if pool.authority != *authority_info.key {
+        return Err(StakingError::InvalidAuthority.into());
+    }
+
+    if match_bps > StakingPool::MAX_MATCH_BPS {
+        return Err(StakingError::SettingExceedsMaximum.with_context(ErrorContext::MaximumAmount(
+            StakingPool::MAX_MATCH_BPS as u64,
+        )));
+    }
+
+    // Verify matching vault PDA
+    let (expected_vault, vault_bump) = MatchingVault::derive_pda(pool_info.key, program_id);
+    if *vault_info.key != expected_vault {
+        return Err(StakingError::InvalidPDA.into());
+    }
+
+    // Pools with an authority log must pass it (after the system program)
+    let log_info = next_authority_log(&pool, account_info_iter)?;
+
+    // Legacy pools have no room for the matching flag (payer = authority)
+    StakingPool::maybe_realloc(pool_info, authority_info, Some(system_program_info))?;
+
+    let existing = if vault_info.data_is_empty() {
+        None
+    } else {
+        if vault_info.owner != program_id {
+            return Err(StakingError::InvalidAccountOwner.into());
+        }
+        let vault = MatchingVault::try_from_slice(&vault_info.try_borrow_data()?)?;
+        if !vault.is_initialized() {
+            return Err(StakingError::NotInitialized.into());
+        }
+        Some(vault)
+    };
+    let old_match_bps = existing.as_ref().map_or(0, |vault| vault.match_bps);
+
+    if match_bps == 0 {
+        // Disable: refund the vault (rent included) and zero it out
+        if existing.is_some() {
+            let vault_lamports = vault_info.lamports();
+            **vault_info.try_borrow_mut_lamports()? = 0;
+            **authority_info.try_borrow_mut_lamports()? += vault_lamports;
+            vault_info.try_borrow_mut_data()?.fill(0);
+            msg!("Matching vault closed, refunded {} lamports", vault_lamports);
+        }
+    } else {
+        let mut vault = match existing {
+            Some(vault) => vault,
+            None => {
+                // Lamports sent ahead of creation are kept for matching
+                let vault_seeds: &[&[u8]] =
+                    &[MATCHING_VAULT_SEED, pool_info.key.as_ref(), &[vault_bump]];
+                create_lamport_vault(
+                    program_id,
+                    authority_info,
+                    vault_info,
+                    system_program_info,
+                    vault_seeds,
+                    MatchingVault::LEN,
+                )?;
+                MatchingVault {
+                    discriminator: MATCHING_VAULT_DISCRIMINATOR,
+                    pool: *pool_info.key,
+                    bump: vault_bump,
+                    match_bps: 0,
+                    total_matched: 0,
+                }
+            }
+        };
+        vault.match_bps = match_bps;
+        vault.serialize(&mut &mut vault_info.try_borrow_mut_data()?[..])?;
+    }
+
+    if let Some(log_info) = log_info {
+        let entry = AuthorityLogEntry::integer(
+            pool_time(&pool)?,
+            *authority_info.key,
+            AuthorityAction::MatchBps,
+            old_match_bps as u64,
+            match_bps as u64,
+        );
+        record_authority_actions(
+            program_id,
+            log_info,
+            pool_info.key,
+            authority_info,
+            Some(system_program_info),
+            &[entry],
+        )?;
+    }
+
+    pool.has_matching_vault = match_bps > 0;
+    pool.save(pool_info)?;
+
+    msg!("Reward matching set to {} bps", match_bps);
+
+    Ok(())
+}
@@ -0,0 +1,67 @@
+//! Set reward recipient instruction
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::StakingError,
+    loaders::{load_pool, load_user_stake},
+    state::UserStake,
+};
+
+/// Declare the account that receives this position's reward lamports.
+/// Meant for program-owned (PDA) owners signing via CPI. Setting
+/// Pubkey::default() pays the owner again.
+///
+/// Accounts:
+/// 0. `[]` Pool account
+/// 1. `[writable]` User stake account
+/// 2. `[writable, signer]` User/owner (pays realloc rent)
+/// 3. `[]` (optional) System program (legacy account reallocation)
+pub fn process_set_reward_recipient(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    recipient: Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let user_stake_info = next_account_info(account_info_iter)?;
+    let user_info = next_account_info(account_info_iter)?;
+
+    // Validate user is signer
+    if !user_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let _pool = load_pool(program_id, pool_info)?;
+
+    // Load and validate user stake
+    let mut user_stake =
+        load_user_stake(program_id, user_stake_info, pool_info.key, user_info.key)?;
+
+    // Paying rewards back into the pool or the stake account would strand them
+    if recipient == *pool_info.key || recipient == *user_stake_info.key {
+        return Err(StakingError::InvalidRewardRecipient.into());
+    }
+
+    // Realloc legacy accounts to current size (payer = user)
+    let system_program_info = account_info_iter.next();
+    UserStake::maybe_realloc(user_stake_info, user_info, system_program_info)?;
+
+    user_stake.reward_recipient = recipient;
+    user_stake.save(user_stake_info)?;
+
+    if recipient == Pubkey::default() {
+        msg!("Reward recipient cleared (rewards paid to owner)");
+    } else {
+        msg!("Reward recipient set to {}", recipient);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,84 @@
+//! Set how reward payouts round to whole lamports (authority only)
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    authority_log::{next_authority_log, record_authority_actions},
+    error::StakingError,
+    loaders::load_pool,
+    state::{AuthorityAction, AuthorityLogEntry, RoundingMode, StakingPool},
+    time::pool_time,
+};
+
+/// Set `rounding_mode`, the rounding claims and unstakes apply when paying
+/// WAD-scaled rewards out in whole lamports: 0 = floor (toward the pool),
+/// 1 = half up, 2 = half even. Applies from the next payout. Under the
+/// nearest modes, round-ups are funded from rounding_reserve_wad, which
+/// round-downs fill; a round-up it can't cover pays the floor.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable, signer]` Authority (pays rent if a legacy pool must grow)
+/// 2. `[]` (optional) System program, required for legacy pool reallocation
+///    and pools with an authority log
+/// 3. `[writable]` Authority log (pools with an authority log only)
+pub fn process_set_rounding_mode(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mode: u8,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let system_program_info = account_info_iter.next();
+
+    // Validate authority is signer
+    if !authority_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    // Check authority is not renounced
+    if pool.is_authority_renounced() {
+        return Err(StakingError::AuthorityRenounced.into());
+    }
+
+    // Verify authority
+    if pool.authority != *authority_info.key {
+        return Err(StakingError::InvalidAuthority.into());
+    }
+
+    let rounding = RoundingMode::from_u8(mode).ok_or(StakingError::SettingExceedsMaximum)?;
+
+    // Pools with an authority log must pass it (after the system program)
+    let log_info = next_authority_log(&pool, account_info_iter)?;
+
+    // Legacy pools have no room for the mode (payer = authority)
+    StakingPool::maybe_realloc(pool_info, authority_info, system_program_info)?;
+
+    if let Some(log_info) = log_info {
+        let entry = AuthorityLogEntry::integer(
+            pool_time(&pool)?,
+            *authority_info.key,
+            AuthorityAction::RoundingMode,
+            pool.rounding_mode as u64,
+            mode as u64,
+        );
+        record_authority_actions(program_id, log_info, pool_info.key, authority_info, system_program_info, &[entry])?;
+    }
+
+    pool.rounding_mode = mode;
+    pool.save(pool_info)?;
+
+    msg!("Reward rounding set to {:?}", rounding);
+
+    Ok(())
+}
@@ -0,0 +1,102 @@
+//! Freeze or unfreeze a position's rewards (authority only)
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    authority_log::{next_authority_log, record_authority_actions},
+    error::StakingError,
+    events::emit_stake_freeze_set,
+    loaders::{load_pool, load_pool_stake},
+    state::{AuthorityAction, AuthorityLogEntry, UserStake},
+    time::pool_time,
+};
+
+/// Set whether a position's rewards are frozen, e.g. for a sanctioned
+/// owner. A frozen position can't claim, take new stake or migrate, but
+/// its owner can always unstake: principal is returned and rewards stay
+/// owed to the position until it is unfrozen. Only available while the
+/// pool has an authority.
+///
+/// Accounts:
+/// 0. `[]` Pool account
+/// 1. `[writable]` User stake account
+/// 2. `[writable, signer]` Authority (pays rent if a legacy stake must grow)
+/// 3. `[]` (optional) System program, required for legacy stake reallocation
+///    and pools with an authority log
+/// 4. `[writable]` Authority log (pools with an authority log only)
+pub fn process_set_stake_frozen(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    frozen: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let user_stake_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let system_program_info = account_info_iter.next();
+
+    // Validate authority is signer
+    if !authority_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let pool = load_pool(program_id, pool_info)?;
+
+    // Check authority is not renounced
+    if pool.is_authority_renounced() {
+        return Err(StakingError::AuthorityRenounced.into());
+    }
+
+    // Verify authority
+    if pool.authority != *authority_info.key {
+        return Err(StakingError::InvalidAuthority.into());
+    }
+
+    // Load and validate user stake
+    let mut user_stake = load_pool_stake(program_id, user_stake_info, pool_info.key)?;
+
+    // Pools with an authority log must pass it (after the system program)
+    let log_info = next_authority_log(&pool, account_info_iter)?;
+
+    // Legacy accounts have no room for the flag (payer = authority)
+    UserStake::maybe_realloc(user_stake_info, authority_info, system_program_info)?;
+
+    let current_time = pool_time(&pool)?;
+    if let Some(log_info) = log_info {
+        let value = |frozen: bool| {
+            if frozen {
+                *user_stake_info.key
+            } else {
+                Pubkey::default()
+            }
+        };
+        let entry = AuthorityLogEntry::pubkey(
+            current_time,
+            *authority_info.key,
+            AuthorityAction::StakeFreeze,
+            value(user_stake.rewards_frozen),
+            value(frozen),
+        );
+        record_authority_actions(program_id, log_info, pool_info.key, authority_info, system_program_info, &[entry])?;
+    }
+
+    user_stake.rewards_frozen = frozen;
+    user_stake.save(user_stake_info)?;
+
+    emit_stake_freeze_set(pool_info.key, user_stake_info.key, &user_stake.owner, frozen, current_time);
+
+    if frozen {
+        msg!("Rewards of position {} frozen", user_stake_info.key);
+    } else {
+        msg!("Rewards of position {} unfrozen", user_stake_info.key);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,122 @@
+//! Set the unstake fee (authority only)
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+use spl_token_2022::extension::StateWithExtensions;
+
+use crate::{
+    authority_log::{next_authority_log, record_authority_actions},
+    error::{ErrorContext, StakingError},
+    loaders::load_pool,
+    state::{AuthorityAction, AuthorityLogEntry, StakingPool},
+    time::pool_time,
+};
+
+/// Set the share of unstaked tokens sent to a fee token account instead of
+/// the user (e.g. to fund protocol-owned liquidity). `fee_bps` is capped at
+/// StakingPool::MAX_UNSTAKE_FEE_BPS; 0 disables the fee.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable, signer]` Authority (pays rent if a legacy pool must grow)
+/// 2. `[]` Fee destination token account (pool mint; ignored when disabling)
+/// 3. `[]` (optional) System program, required for legacy pool reallocation
+///    and pools with an authority log
+/// 4. `[writable]` Authority log (pools with an authority log only)
+pub fn process_set_unstake_fee(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    fee_bps: u16,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let fee_destination_info = next_account_info(account_info_iter)?;
+    let system_program_info = account_info_iter.next();
+
+    // Validate authority is signer
+    if !authority_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    // Check authority is not renounced
+    if pool.is_authority_renounced() {
+        return Err(StakingError::AuthorityRenounced.into());
+    }
+
+    // Verify authority
+    if pool.authority != *authority_info.key {
+        return Err(StakingError::InvalidAuthority.into());
+    }
+
+    // Pools with an authority log must pass it (after the system program)
+    let log_info = next_authority_log(&pool, account_info_iter)?;
+
+    if fee_bps > StakingPool::MAX_UNSTAKE_FEE_BPS {
+        return Err(StakingError::SettingExceedsMaximum.with_context(
+            ErrorContext::MaximumAmount(StakingPool::MAX_UNSTAKE_FEE_BPS as u64),
+        ));
+    }
+
+    let fee_destination = if fee_bps == 0 {
+        Pubkey::default()
+    } else {
+        // Must be a Token 2022 account of the pool mint, and not the vault
+        if fee_destination_info.owner != &spl_token_2022::id() {
+            return Err(StakingError::InvalidAccountOwner.into());
+        }
+        if *fee_destination_info.key == pool.token_vault {
+            return Err(StakingError::InvalidFeeDestination.into());
+        }
+        let data = fee_destination_info.try_borrow_data()?;
+        let account = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?;
+        if account.base.mint != pool.mint {
+            return Err(StakingError::InvalidPoolMint.into());
+        }
+        *fee_destination_info.key
+    };
+
+    // Legacy pools have no room for the fee (payer = authority)
+    StakingPool::maybe_realloc(pool_info, authority_info, system_program_info)?;
+
+    if let Some(log_info) = log_info {
+        let now = pool_time(&pool)?;
+        let entries = [
+            AuthorityLogEntry::integer(
+                now,
+                *authority_info.key,
+                AuthorityAction::UnstakeFeeBps,
+                pool.unstake_fee_bps as u64,
+                fee_bps as u64,
+            ),
+            AuthorityLogEntry::pubkey(
+                now,
+                *authority_info.key,
+                AuthorityAction::UnstakeFeeDestination,
+                pool.unstake_fee_destination,
+                fee_destination,
+            ),
+        ];
+        record_authority_actions(program_id, log_info, pool_info.key, authority_info, system_program_info, &entries)?;
+    }
+
+    pool.unstake_fee_bps = fee_bps;
+    pool.unstake_fee_destination = fee_destination;
+    pool.save(pool_info)?;
+
+    if fee_bps == 0 {
+        msg!("Unstake fee disabled");
+    } else {
+        msg!("Unstake fee set to {} bps, paid to {}", fee_bps, fee_destination);
+    }
+
+    Ok(())
+}
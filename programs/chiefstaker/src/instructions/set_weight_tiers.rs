@@ -0,0 +1,161 @@
+//! Set weight tiers instruction (authority only)
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    authority_log::{next_authority_log, record_authority_actions},
+    error::StakingError,
+    loaders::load_pool,
+    state::{
+        AuthorityAction, AuthorityLogEntry, WeightTiers,
+        WEIGHT_TIERS_DISCRIMINATOR, WEIGHT_TIERS_SEED,
+    },
+    time::pool_time,
+};
+
+/// Set up to 8 weighted stake thresholds (raw token units, increasing) that
+/// GetWeightTier maps positions onto, for partners granting fee discounts
+/// to long-term stakers. An empty list removes the tiers and refunds their
+/// rent to the authority. Staking and rewards are not affected.
+///
+/// Accounts:
+/// 0. `[]` Pool account
+/// 1. `[writable, signer]` Authority (pays rent)
+/// 2. `[writable]` Weight tiers (PDA: ["weight_tiers", pool])
+/// 3. `[]` System program
+/// 4. `[writable]` Authority log (pools with an authority log only)
+pub fn process_set_weight_tiers(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    thresholds: Vec<u64>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let tiers_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Validate authority is signer
+    if !authority_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let pool = load_pool(program_id, pool_info)?;
+
+    // Check authority is not renounced
+    if pool.is_authority_renounced() {
+        return Err(StakingError::AuthorityRenounced.into());
+    }
+
+    // Verify authority
+    if pool.authority != *authority_info.key {
+        return Err(StakingError::InvalidAuthority.into());
+    }
+
+    // Verify weight tiers PDA
+    let (expected_tiers, tiers_bump) =
+        WeightTiers::derive_pda(pool_info.key, program_id);
+    if *tiers_info.key != expected_tiers {
+        return Err(StakingError::InvalidPDA.into());
+    }
+
+    // Pools with an authority log must pass it (after the system program)
+    let log_info = next_authority_log(&pool, account_info_iter)?;
+
+    let existing = if tiers_info.data_is_empty() {
+        None
+    } else {
+        if tiers_info.owner != program_id {
+            return Err(StakingError::InvalidAccountOwner.into());
+        }
+        let tiers = WeightTiers::try_from_slice(&tiers_info.try_borrow_data()?)?;
+        if !tiers.is_initialized() {
+            return Err(StakingError::NotInitialized.into());
+        }
+        Some(tiers)
+    };
+    let old_count = existing.as_ref().map_or(0, |tiers| tiers.count);
+
+    if thresholds.is_empty() {
+        // Remove the tiers: refund their rent and zero them out
+        if existing.is_some() {
+            let tiers_lamports = tiers_info.lamports();
+            **tiers_info.try_borrow_mut_lamports()? = 0;
+            **authority_info.try_borrow_mut_lamports()? += tiers_lamports;
+            tiers_info.try_borrow_mut_data()?.fill(0);
+        }
+    } else {
+        let mut tiers = existing.unwrap_or(WeightTiers {
+            discriminator: WEIGHT_TIERS_DISCRIMINATOR,
+            pool: *pool_info.key,
+            count: 0,
+            bump: tiers_bump,
+            thresholds: Default::default(),
+        });
+        tiers.set_thresholds(&thresholds)?;
+
+        if tiers_info.data_is_empty() {
+            let rent = Rent::get()?;
+            let tiers_seeds = &[
+                WEIGHT_TIERS_SEED,
+                pool_info.key.as_ref(),
+                &[tiers_bump],
+            ];
+            invoke_signed(
+                &system_instruction::create_account(
+                    authority_info.key,
+                    tiers_info.key,
+                    rent.minimum_balance(WeightTiers::LEN),
+                    WeightTiers::LEN as u64,
+                    program_id,
+                ),
+                &[
+                    authority_info.clone(),
+                    tiers_info.clone(),
+                    system_program_info.clone(),
+                ],
+                &[tiers_seeds],
+            )?;
+        }
+
+        tiers.serialize(&mut &mut tiers_info.try_borrow_mut_data()?[..])?;
+    }
+
+    if let Some(log_info) = log_info {
+        let entry = AuthorityLogEntry::integer(
+            pool_time(&pool)?,
+            *authority_info.key,
+            AuthorityAction::WeightTiers,
+            old_count as u64,
+            thresholds.len() as u64,
+        );
+        record_authority_actions(
+            program_id,
+            log_info,
+            pool_info.key,
+            authority_info,
+            Some(system_program_info),
+            &[entry],
+        )?;
+    }
+
+    if thresholds.is_empty() {
+        msg!("Weight tiers removed");
+    } else {
+        msg!("Weight tiers set: {:?}", thresholds);
+    }
+
+    Ok(())
+}
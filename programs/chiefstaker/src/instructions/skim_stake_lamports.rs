@@ -0,0 +1,92 @@
+//! Skim stake account lamports instruction - withdraw SOL sent to a stake PDA
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    error::StakingError,
+    loaders::{load_pool, load_user_stake},
+};
+
+/// Lamports of a stake account above the rent-exempt minimum for its size
+pub fn skimmable_lamports(lamports: u64, data_len: usize, rent: &Rent) -> u64 {
+    lamports.saturating_sub(rent.minimum_balance(data_len))
+}
+
+/// Move lamports above the rent-exempt minimum out of the caller's stake
+/// account, leaving the position untouched. Anyone can send SOL to the PDA;
+/// CloseStakeAccount returns it too, but this recovers it without unstaking.
+/// The rent itself (including any sponsored share) stays in the account.
+///
+/// Accounts:
+/// 0. `[]` Pool account
+/// 1. `[writable]` User stake account (PDA: ["stake", pool, owner])
+/// 2. `[writable, signer]` User/owner (receives the excess)
+pub fn process_skim_stake_account_lamports(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let user_stake_info = next_account_info(account_info_iter)?;
+    let user_info = next_account_info(account_info_iter)?;
+
+    // Validate user is signer
+    if !user_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let _pool = load_pool(program_id, pool_info)?;
+
+    // Load and validate user stake
+    let _user_stake = load_user_stake(program_id, user_stake_info, pool_info.key, user_info.key)?;
+
+    // Legacy accounts keep the (smaller) minimum for their current size
+    let excess = skimmable_lamports(
+        user_stake_info.lamports(),
+        user_stake_info.data_len(),
+        &Rent::get()?,
+    );
+    if excess == 0 {
+        msg!("No excess lamports to skim");
+        return Ok(());
+    }
+
+    **user_stake_info.try_borrow_mut_lamports()? -= excess;
+    **user_info.try_borrow_mut_lamports()? += excess;
+
+    msg!("Skimmed {} excess lamports from stake account", excess);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::UserStake;
+
+    #[test]
+    fn test_skimmable_lamports() {
+        let rent = Rent::default();
+        let minimum = rent.minimum_balance(UserStake::LEN);
+        assert_eq!(skimmable_lamports(minimum, UserStake::LEN, &rent), 0);
+        assert_eq!(skimmable_lamports(minimum + 1_000, UserStake::LEN, &rent), 1_000);
+        // Underfunded accounts have nothing to skim
+        assert_eq!(skimmable_lamports(minimum - 1, UserStake::LEN, &rent), 0);
+
+        // Legacy accounts only need rent for their own size
+        let legacy_minimum = rent.minimum_balance(UserStake::LEGACY_LEN);
+        assert_eq!(
+            skimmable_lamports(minimum, UserStake::LEGACY_LEN, &rent),
+            minimum - legacy_minimum
+        );
+    }
+}
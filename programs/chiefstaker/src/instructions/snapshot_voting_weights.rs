@@ -0,0 +1,167 @@
+//! Snapshot stakers' weighted stakes for an off-protocol vote
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::StakingError,
+    loaders::{load_pool, load_pool_stake},
+    math::calculate_user_weighted_stake,
+    state::{
+        VotingSnapshot, VotingSnapshotPage, VotingWeight,
+        VOTING_SNAPSHOT_PAGE_DISCRIMINATOR, VOTING_SNAPSHOT_PAGE_SEED, VOTING_SNAPSHOT_SEED,
+    },
+    time::pool_time,
+};
+
+use super::create_lamport_vault;
+
+/// Write the current weighted stake of each given position into the
+/// proposal's VotingSnapshot, creating the snapshot and its current page
+/// as needed (the payer funds rent). This is a permissionless crank:
+/// stake accounts must be passed in strictly increasing owner order,
+/// continuing after the snapshot's last entry, so no staker is counted
+/// twice; positions with nothing staked are skipped. A call writes at most
+/// the current page's remaining room (VotingSnapshotPage::CAPACITY per page).
+///
+/// Accounts:
+/// 0. `[]` Pool account
+/// 1. `[writable]` Voting snapshot (PDA: ["voting_snapshot", pool, proposal_id (u64 LE)])
+/// 2. `[writable]` Current snapshot page (PDA: ["voting_snapshot_page",
+///    snapshot, entries / CAPACITY (u32 LE)])
+/// 3. `[writable, signer]` Payer
+/// 4. `[]` System program
+/// 5. `[]` User stake accounts, by increasing owner
+pub fn process_snapshot_voting_weights(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    proposal_id: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let snapshot_info = next_account_info(account_info_iter)?;
+    let page_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Validate payer is signer
+    if !payer_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let pool = load_pool(program_id, pool_info)?;
+
+    let current_time = pool_time(&pool)?;
+
+    // Load the snapshot, creating it on the first call
+    let (expected_snapshot, snapshot_bump) =
+        VotingSnapshot::derive_pda(pool_info.key, proposal_id, program_id);
+    if *snapshot_info.key != expected_snapshot {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    let mut snapshot = if snapshot_info.owner != program_id {
+        let proposal_bytes = proposal_id.to_le_bytes();
+        let seeds: &[&[u8]] =
+            &[VOTING_SNAPSHOT_SEED, pool_info.key.as_ref(), &proposal_bytes, &[snapshot_bump]];
+        create_lamport_vault(
+            program_id,
+            payer_info,
+            snapshot_info,
+            system_program_info,
+            seeds,
+            VotingSnapshot::LEN,
+        )?;
+        VotingSnapshot::new(*pool_info.key, proposal_id, current_time, snapshot_bump)
+    } else {
+        let snapshot = VotingSnapshot::try_from_slice(&snapshot_info.try_borrow_data()?)?;
+        if !snapshot.is_initialized() {
+            return Err(StakingError::NotInitialized.into());
+        }
+        snapshot
+    };
+
+    // Load the page receiving the next entry, creating it when first written
+    let page_index = snapshot.current_page();
+    let (expected_page, page_bump) =
+        VotingSnapshotPage::derive_pda(snapshot_info.key, page_index, program_id);
+    if *page_info.key != expected_page {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    let mut page = if page_info.owner != program_id {
+        let page_bytes = page_index.to_le_bytes();
+        let seeds: &[&[u8]] =
+            &[VOTING_SNAPSHOT_PAGE_SEED, snapshot_info.key.as_ref(), &page_bytes, &[page_bump]];
+        create_lamport_vault(
+            program_id,
+            payer_info,
+            page_info,
+            system_program_info,
+            seeds,
+            VotingSnapshotPage::LEN,
+        )?;
+        VotingSnapshotPage {
+            discriminator: VOTING_SNAPSHOT_PAGE_DISCRIMINATOR,
+            snapshot: *snapshot_info.key,
+            page: page_index,
+            count: 0,
+            bump: page_bump,
+        }
+    } else {
+        let data = page_info.try_borrow_data()?;
+        let header = data
+            .get(..VotingSnapshotPage::HEADER_LEN)
+            .ok_or(StakingError::AccountDataTooSmall)?;
+        let page = VotingSnapshotPage::try_from_slice(header)?;
+        if !page.is_initialized() {
+            return Err(StakingError::NotInitialized.into());
+        }
+        page
+    };
+
+    let mut written: u32 = 0;
+    for user_stake_info in account_info_iter {
+        // Load and validate user stake
+        let mut user_stake = load_pool_stake(program_id, user_stake_info, pool_info.key)?;
+
+        if user_stake.amount == 0 {
+            continue;
+        }
+
+        // Weight against the pool's current base_time (in memory only)
+        user_stake.sync_to_pool(&pool)?;
+        let weight = VotingWeight {
+            owner: user_stake.owner,
+            amount: user_stake.amount,
+            weighted_stake: calculate_user_weighted_stake(
+                user_stake.amount,
+                user_stake.exp_start_factor,
+                current_time,
+                pool.base_time,
+                pool.tau_seconds,
+            )?,
+            timestamp: current_time,
+        };
+        snapshot.record(&weight)?;
+        page.push(&mut page_info.try_borrow_mut_data()?, &weight)?;
+        written += 1;
+    }
+
+    borsh::to_writer(&mut &mut snapshot_info.try_borrow_mut_data()?[..], &snapshot)?;
+
+    msg!(
+        "Snapshot {}: {} entries written, {} total (page {})",
+        proposal_id,
+        written,
+        snapshot.entries,
+        page_index
+    );
+
+    Ok(())
+}
@@ -6,18 +6,21 @@ use solana_program::{
     clock::Clock,
     entrypoint::ProgramResult,
     msg,
-    program::{invoke, invoke_signed},
+    program::invoke,
     pubkey::Pubkey,
-    rent::Rent,
-    system_instruction,
     sysvar::Sysvar,
 };
 use spl_token_2022::extension::StateWithExtensions;
 
 use crate::{
-    error::StakingError,
-    math::{exp_time_ratio, wad_mul, MAX_EXP_INPUT, U256, WAD},
-    state::{PoolMetadata, StakingPool, UserStake, STAKE_SEED},
+    error::{ErrorContext, StakingError},
+    history::{next_weight_history, record_checkpoint},
+    loaders::load_pool,
+    math::{wad_mul, U256, WAD},
+    receipt::{mint_receipt, next_receipt_accounts},
+    sponsor::{create_stake_account, next_rent_sponsor},
+    state::{PoolMetadata, StakeAction, StakingPool, UserStake, STAKE_SEED},
+    time::pool_time,
 };
 
 /// Stake tokens into the pool
@@ -31,6 +34,12 @@ use crate::{
 /// 5. `[writable, signer]` User/owner
 /// 6. `[]` System program
 /// 7. `[]` Token 2022 program
+/// 8. `[writable]` Receipt mint (receipt pools only)
+/// 9. `[writable]` User receipt token account (receipt pools only)
+/// 10. `[writable]` Rent sponsor vault (pools with a rent sponsor only);
+///     pays the rent of a new stake account while it holds enough
+/// 11. `[writable]` Weight history (existing positions with history only)
+/// 12. `[writable]` (optional) Pool metadata (new positions; member count)
 pub fn process_stake(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -62,19 +71,7 @@ pub fn process_stake(
     }
 
     // Load and validate pool
-    if pool_info.owner != program_id {
-        return Err(StakingError::InvalidAccountOwner.into());
-    }
-    let mut pool = StakingPool::try_from_slice(&pool_info.try_borrow_data()?)?;
-    if !pool.is_initialized() {
-        return Err(StakingError::NotInitialized.into());
-    }
-
-    // Verify pool PDA
-    let (expected_pool, _) = StakingPool::derive_pda(&pool.mint, program_id);
-    if *pool_info.key != expected_pool {
-        return Err(StakingError::InvalidPDA.into());
-    }
+    let mut pool = load_pool(program_id, pool_info)?;
 
     // Verify mint matches pool
     if pool.mint != *mint_info.key {
@@ -86,6 +83,12 @@ pub fn process_stake(
         return Err(StakingError::InvalidTokenVault.into());
     }
 
+    // Receipt accounts follow the fixed accounts on receipt pools
+    let receipt = next_receipt_accounts(&pool, account_info_iter)?;
+
+    // Pools with a rent sponsor must pass its vault
+    let sponsor_info = next_rent_sponsor(program_id, pool_info.key, &pool, account_info_iter)?;
+
     // Verify user stake PDA
     let (expected_stake, stake_bump) =
         UserStake::derive_pda(pool_info.key, user_info.key, program_id);
@@ -94,61 +97,40 @@ pub fn process_stake(
     }
 
     let clock = Clock::get()?;
-    let current_time = clock.unix_timestamp;
-
-    // Check if pool needs rebasing (sum_stake_exp near overflow)
-    if pool.get_sum_stake_exp().needs_rebase() {
-        return Err(StakingError::PoolRequiresSync.into());
-    }
+    let current_time = pool_time(&pool)?;
 
-    // Calculate exp_start_factor for this stake
-    let time_since_base = current_time.saturating_sub(pool.base_time);
-
-    // Check if time_since_base / tau would overflow exp_wad.
-    // Require SyncPool first if the ratio exceeds MAX_EXP_INPUT.
-    let ratio_wad = (time_since_base as u128)
-        .checked_mul(WAD)
-        .ok_or(StakingError::MathOverflow)?
-        / (pool.tau_seconds as u128);
-    if ratio_wad > MAX_EXP_INPUT {
-        return Err(StakingError::PoolRequiresSync.into());
-    }
+    // Rebase inline if sum_stake_exp is near overflow or time_since_base / tau
+    // would overflow exp_wad (saved with the pool below)
+    pool.rebase_if_needed(current_time)?;
 
-    let exp_start_factor = exp_time_ratio(time_since_base, pool.tau_seconds)?;
+    // Calculate exp_start_factor for this stake (pool creation time while
+    // the bootstrap window is open)
+    let (stake_time, exp_start_factor) = pool.new_stake_start(current_time)?;
 
     // Create or update user stake account
     let is_new_stake = user_stake_info.data_is_empty();
 
-    if is_new_stake {
+    let mut user_stake = if is_new_stake {
         // Check minimum stake amount
         if pool.min_stake_amount > 0 && amount < pool.min_stake_amount {
-            return Err(StakingError::BelowMinimumStake.into());
+            return Err(StakingError::BelowMinimumStake
+                .with_context(ErrorContext::MinimumAmount(pool.min_stake_amount)));
         }
 
-        // Create new user stake account
-        let rent = Rent::get()?;
-        let stake_rent = rent.minimum_balance(UserStake::LEN);
-        let stake_seeds = &[
+        // Create new user stake account (rent from the sponsor if it can pay)
+        let stake_seeds: &[&[u8]] = &[
             STAKE_SEED,
             pool_info.key.as_ref(),
             user_info.key.as_ref(),
             &[stake_bump],
         ];
-
-        invoke_signed(
-            &system_instruction::create_account(
-                user_info.key,
-                user_stake_info.key,
-                stake_rent,
-                UserStake::LEN as u64,
-                program_id,
-            ),
-            &[
-                user_info.clone(),
-                user_stake_info.clone(),
-                system_program_info.clone(),
-            ],
-            &[stake_seeds],
+        let sponsored_rent = create_stake_account(
+            program_id,
+            user_info,
+            user_stake_info,
+            system_program_info,
+            sponsor_info,
+            stake_seeds,
         )?;
 
         // Initialize user stake
@@ -161,33 +143,13 @@ pub fn process_stake(
             stake_bump,
             pool.base_time,
         );
+        user_stake.stake_time = stake_time;
+        user_stake.sponsored_rent = sponsored_rent;
 
-        // Set reward_debt using max weight (amount * WAD) to prevent accessing prior rewards
-        user_stake.reward_debt = wad_mul(
-            (amount as u128).checked_mul(WAD).ok_or(StakingError::MathOverflow)?,
-            pool.acc_reward_per_weighted_share,
-        )?;
-
-        // Track in pool-level aggregate
-        pool.total_reward_debt = pool
-            .total_reward_debt
-            .checked_add(user_stake.reward_debt)
-            .ok_or(StakingError::MathOverflow)?;
-
-        let mut stake_data = user_stake_info.try_borrow_mut_data()?;
-        user_stake.serialize(&mut &mut stake_data[..])?;
+        user_stake.record_action(StakeAction::Stake, clock.slot)?;
 
-        // Update pool sum_stake_exp
-        // sum_stake_exp += amount * exp_start_factor
-        let stake_contribution = wad_mul(
-            (amount as u128).checked_mul(WAD).ok_or(StakingError::MathOverflow)?,
-            exp_start_factor,
-        )?;
-        let new_sum = pool
-            .get_sum_stake_exp()
-            .checked_add(U256::from_u128(stake_contribution))
-            .ok_or(StakingError::MathOverflow)?;
-        pool.set_sum_stake_exp(new_sum);
+        open_position(&mut pool, &mut user_stake)?;
+        user_stake
     } else {
         // Realloc legacy accounts to current size (payer = user)
         UserStake::maybe_realloc(user_stake_info, user_info, Some(system_program_info))?;
@@ -202,17 +164,20 @@ pub fn process_stake(
         }
 
         // Verify ownership
-        if user_stake.owner != *user_info.key {
-            return Err(StakingError::InvalidOwner.into());
-        }
+        user_stake.check_owner(user_info.key)?;
         if user_stake.pool != *pool_info.key {
             return Err(StakingError::InvalidPool.into());
         }
+        user_stake.check_not_frozen()?;
 
         // Block staking while unstake request is pending
-        if user_stake.has_pending_unstake_request() {
+        if user_stake.has_pending_unstake_request(&pool, current_time) {
             return Err(StakingError::PendingUnstakeRequestExists.into());
         }
+        user_stake.clear_expired_unstake_request(&pool, current_time);
+
+        // Block re-staking in the same slot as an unstake
+        user_stake.record_action(StakeAction::Stake, clock.slot)?;
 
         // Check minimum stake amount on new total
         let new_total = user_stake
@@ -220,63 +185,28 @@ pub fn process_stake(
             .checked_add(amount)
             .ok_or(StakingError::MathOverflow)?;
         if pool.min_stake_amount > 0 && new_total < pool.min_stake_amount {
-            return Err(StakingError::BelowMinimumStake.into());
+            let min_top_up = pool.min_stake_amount - user_stake.amount;
+            return Err(StakingError::BelowMinimumStake
+                .with_context(ErrorContext::MinimumAmount(min_top_up)));
         }
 
-        // Lazily adjust exp_start_factor if pool has been rebased
-        user_stake.sync_to_pool(&pool)?;
+        add_to_position(&mut pool, &mut user_stake, amount, current_time)?;
 
-        // Maturity percentage is preserved — it depends only on when the user
-        // first staked, not on amount. exp_start_factor and claimed_rewards_wad
-        // are NOT changed. Only reward_debt gets a fresh snapshot for the new
-        // tokens so they don't earn rewards deposited before this add-stake.
-        let old_reward_debt = user_stake.reward_debt;
-
-        // sum_stake_exp: new tokens use the SAME exp_start_factor (same maturity)
-        let new_contribution = wad_mul(
-            (amount as u128).checked_mul(WAD).ok_or(StakingError::MathOverflow)?,
-            user_stake.exp_start_factor,
-        )?;
-        let new_sum = pool
-            .get_sum_stake_exp()
-            .checked_add(U256::from_u128(new_contribution))
-            .ok_or(StakingError::MathOverflow)?;
-        pool.set_sum_stake_exp(new_sum);
-
-        // reward_debt += fresh snapshot for new tokens only
-        let new_token_debt = wad_mul(
-            (amount as u128).checked_mul(WAD).ok_or(StakingError::MathOverflow)?,
-            pool.acc_reward_per_weighted_share,
-        )?;
-        user_stake.reward_debt = user_stake.reward_debt
-            .checked_add(new_token_debt)
-            .ok_or(StakingError::MathOverflow)?;
-
-        user_stake.amount = new_total;
-        user_stake.last_stake_time = current_time;
-        // exp_start_factor: UNCHANGED — maturity depends only on start time
-        // claimed_rewards_wad: UNCHANGED — pending rewards stay exactly the same
-
-        // Update pool-level aggregate
-        pool.total_reward_debt = pool
-            .total_reward_debt
-            .saturating_sub(old_reward_debt)
-            .checked_add(user_stake.reward_debt)
-            .ok_or(StakingError::MathOverflow)?;
+        // Positions with a weight history must pass it (after the sponsor vault)
+        if let Some(history_info) = next_weight_history(&user_stake, account_info_iter)? {
+            record_checkpoint(program_id, history_info, pool_info.key, &pool, &user_stake, current_time)?;
+        }
 
-        let mut stake_data = user_stake_info.try_borrow_mut_data()?;
-        user_stake.serialize(&mut &mut stake_data[..])?;
-    }
+        user_stake
+    };
 
-    // Update pool total staked
-    pool.total_staked = pool
-        .total_staked
-        .checked_add(amount as u128)
-        .ok_or(StakingError::MathOverflow)?;
+    add_total_staked(&mut pool, amount)?;
 
-    // Save pool state
-    let mut pool_data = pool_info.try_borrow_mut_data()?;
-    pool.serialize(&mut &mut pool_data[..])?;
+    // Every pool and position mutation is done: write each account exactly
+    // once, before any CPI, so the token program (and its transfer hooks)
+    // only ever observe the final state
+    user_stake.save(user_stake_info)?;
+    pool.save(pool_info)?;
 
     // Transfer tokens from user to vault
     // Get decimals from mint for transfer_checked
@@ -304,6 +234,11 @@ pub fn process_stake(
         ],
     )?;
 
+    // Mint receipt tokens 1:1 with the staked amount
+    if let Some(receipt) = &receipt {
+        mint_receipt(&pool, pool_info, receipt, amount)?;
+    }
+
     // Optional metadata account: increment member_count on new stake
     if is_new_stake {
         if let Some(metadata_info) = account_info_iter.next() {
@@ -327,3 +262,175 @@ pub fn process_stake(
 
     Ok(())
 }
+
+/// Pool and position accounting for a position created with `amount`
+/// (already in `user_stake`): its reward_debt snapshots the accumulator at
+/// full weight so it can't reach earlier rewards, and its stake joins
+/// sum_stake_exp.
+pub fn open_position(pool: &mut StakingPool, user_stake: &mut UserStake) -> Result<(), StakingError> {
+    let amount_wad = (user_stake.amount as u128)
+        .checked_mul(WAD)
+        .ok_or(StakingError::MathOverflow)?;
+
+    user_stake.reward_debt = wad_mul(amount_wad, pool.acc_reward_per_weighted_share)?;
+    pool.total_reward_debt = pool
+        .total_reward_debt
+        .checked_add(user_stake.reward_debt)
+        .ok_or(StakingError::MathOverflow)?;
+
+    // sum_stake_exp += amount * exp_start_factor
+    let stake_contribution = wad_mul(amount_wad, user_stake.exp_start_factor)?;
+    let new_sum = pool
+        .get_sum_stake_exp()
+        .checked_add(U256::from_u128(stake_contribution))
+        .ok_or(StakingError::MathOverflow)?;
+    pool.set_sum_stake_exp(new_sum);
+    Ok(())
+}
+
+/// Pool and position accounting for `amount` added to an existing position
+///
+/// Maturity percentage is preserved — it depends only on when the user
+/// first staked, not on amount. exp_start_factor and claimed_rewards_wad
+/// are NOT changed. Only reward_debt gets a fresh snapshot for the new
+/// tokens so they don't earn rewards deposited before this add-stake.
+pub fn add_to_position(
+    pool: &mut StakingPool,
+    user_stake: &mut UserStake,
+    amount: u64,
+    current_time: i64,
+) -> Result<(), StakingError> {
+    let new_total = user_stake
+        .amount
+        .checked_add(amount)
+        .ok_or(StakingError::MathOverflow)?;
+    let amount_wad = (amount as u128)
+        .checked_mul(WAD)
+        .ok_or(StakingError::MathOverflow)?;
+
+    // Lazily adjust exp_start_factor if pool has been rebased
+    user_stake.sync_to_pool(pool)?;
+
+    let old_reward_debt = user_stake.reward_debt;
+
+    // sum_stake_exp: new tokens use the SAME exp_start_factor (same maturity)
+    let new_contribution = wad_mul(amount_wad, user_stake.exp_start_factor)?;
+    let new_sum = pool
+        .get_sum_stake_exp()
+        .checked_add(U256::from_u128(new_contribution))
+        .ok_or(StakingError::MathOverflow)?;
+    pool.set_sum_stake_exp(new_sum);
+
+    // reward_debt += fresh snapshot for new tokens only
+    let new_token_debt = wad_mul(amount_wad, pool.acc_reward_per_weighted_share)?;
+    user_stake.reward_debt = user_stake
+        .reward_debt
+        .checked_add(new_token_debt)
+        .ok_or(StakingError::MathOverflow)?;
+
+    // Only the new tokens restart the pool lock
+    user_stake.record_deposit(pool, amount, current_time);
+    user_stake.amount = new_total;
+    user_stake.last_stake_time = current_time;
+    user_stake.total_deposited = user_stake.total_deposited.saturating_add(amount);
+
+    // Update pool-level aggregate
+    pool.total_reward_debt = pool
+        .total_reward_debt
+        .saturating_sub(old_reward_debt)
+        .checked_add(user_stake.reward_debt)
+        .ok_or(StakingError::MathOverflow)?;
+    Ok(())
+}
+
+/// Add `amount` to total_staked. Once enough stake arrives, idle SOL is no
+/// longer refundable.
+pub fn add_total_staked(pool: &mut StakingPool, amount: u64) -> Result<(), StakingError> {
+    let was_below_threshold = pool.below_distribution_threshold();
+    pool.total_staked = pool
+        .total_staked
+        .checked_add(amount as u128)
+        .ok_or(StakingError::MathOverflow)?;
+
+    if was_below_threshold && !pool.below_distribution_threshold() {
+        pool.undistributed_since = 0;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_pool() -> StakingPool {
+        let mut pool = StakingPool::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            3_600,
+            0,
+            255,
+        );
+        pool.acc_reward_per_weighted_share = 2 * WAD;
+        pool
+    }
+
+    #[test]
+    fn test_open_position() {
+        let mut pool = test_pool();
+        pool.total_reward_debt = 7;
+        let mut user = UserStake::new(Pubkey::new_unique(), Pubkey::new_unique(), 1_000, 0, WAD, 255, 0);
+
+        open_position(&mut pool, &mut user).unwrap();
+
+        // Full-weight snapshot: the new position sees none of the 2 WAD/share
+        assert_eq!(user.reward_debt, 2_000 * WAD);
+        assert_eq!(pool.total_reward_debt, 7 + 2_000 * WAD);
+        assert_eq!(pool.get_sum_stake_exp(), U256::from_u128(1_000 * WAD));
+        // total_staked is added separately
+        assert_eq!(pool.total_staked, 0);
+    }
+
+    #[test]
+    fn test_add_to_position() {
+        let mut pool = test_pool();
+        let mut user = UserStake::new(Pubkey::new_unique(), Pubkey::new_unique(), 1_000, 0, WAD / 2, 255, 0);
+        open_position(&mut pool, &mut user).unwrap();
+        add_total_staked(&mut pool, 1_000).unwrap();
+
+        // Rewards accrue, then the user adds stake
+        pool.acc_reward_per_weighted_share = 3 * WAD;
+        let debt_before = user.reward_debt;
+        add_to_position(&mut pool, &mut user, 500, 100).unwrap();
+        add_total_staked(&mut pool, 500).unwrap();
+
+        // Maturity is kept; only the new tokens get a fresh snapshot
+        assert_eq!(user.exp_start_factor, WAD / 2);
+        assert_eq!(user.reward_debt, debt_before + 1_500 * WAD);
+        assert_eq!(pool.total_reward_debt, user.reward_debt);
+        assert_eq!(pool.get_sum_stake_exp(), U256::from_u128(750 * WAD));
+        assert_eq!(user.amount, 1_500);
+        assert_eq!(user.last_stake_time, 100);
+        assert_eq!(user.total_deposited, 1_500);
+        assert_eq!(pool.total_staked, 1_500);
+    }
+
+    #[test]
+    fn test_add_total_staked() {
+        let mut pool = test_pool();
+        pool.min_distribution_stake = 1_000;
+        pool.undistributed_since = 50;
+
+        // Still below the threshold: idle SOL stays refundable
+        add_total_staked(&mut pool, 999).unwrap();
+        assert_eq!(pool.undistributed_since, 50);
+
+        add_total_staked(&mut pool, 1).unwrap();
+        assert_eq!(pool.total_staked, 1_000);
+        assert_eq!(pool.undistributed_since, 0);
+
+        pool.total_staked = u128::MAX;
+        assert_eq!(add_total_staked(&mut pool, 1), Err(StakingError::MathOverflow));
+    }
+}
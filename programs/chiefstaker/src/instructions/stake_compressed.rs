@@ -0,0 +1,111 @@
+//! Stake compressed instruction - move a position into the pool's merkle tree
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    compression::{append, check_tree_accounts, compressed_leaf},
+    error::StakingError,
+    events::emit_stake_compressed,
+    loaders::{load_pool, load_user_stake},
+    state::StakingPool,
+    time::pool_time,
+};
+
+/// Compress the position: its UserStake is appended to the pool's
+/// compression tree as a leaf, and the stake account is closed with its
+/// rent returned to the owner. The tokens stay staked and keep maturing and
+/// accruing rewards; the position has to be decompressed (DecompressStake)
+/// before it can be claimed from, added to or unstaked. The full position
+/// is logged in a StakeCompressed event for the owner to present on
+/// decompression.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable]` User stake account (closed)
+/// 2. `[writable, signer]` User/owner (receives rent)
+/// 3. `[writable]` Merkle tree (pool's compression tree)
+/// 4. `[]` spl-account-compression program
+/// 5. `[]` spl-noop program
+/// 6. `[]` System program
+pub fn process_stake_compressed(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let user_stake_info = next_account_info(account_info_iter)?;
+    let user_info = next_account_info(account_info_iter)?;
+    let tree_info = next_account_info(account_info_iter)?;
+    let compression_program_info = next_account_info(account_info_iter)?;
+    let noop_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Validate user is signer
+    if !user_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    check_tree_accounts(&pool, tree_info, compression_program_info, noop_info)?;
+
+    // Load and validate user stake
+    let user_stake = load_user_stake(program_id, user_stake_info, pool_info.key, user_info.key)?;
+
+    if !user_stake.is_compressible(&pool, pool_time(&pool)?) {
+        return Err(StakingError::PositionNotCompressible.into());
+    }
+
+    // Grow legacy pools before writing (payer = user)
+    StakingPool::maybe_realloc(pool_info, user_info, Some(system_program_info))?;
+
+    let leaf_index =
+        u32::try_from(pool.compressed_leaf_count).map_err(|_| StakingError::MathOverflow)?;
+    pool.compressed_leaf_count += 1;
+    pool.compressed_staked = pool
+        .compressed_staked
+        .checked_add(user_stake.amount as u128)
+        .ok_or(StakingError::MathOverflow)?;
+    pool.compressed_reward_debt = pool
+        .compressed_reward_debt
+        .checked_add(user_stake.reward_debt)
+        .ok_or(StakingError::MathOverflow)?;
+    pool.save(pool_info)?;
+
+    let position = borsh::to_vec(&user_stake)?;
+    let pool_id_bytes = pool.pool_id.to_le_bytes();
+    let pool_bump = [pool.bump];
+    let pool_seeds: &[&[u8]] = &pool.signer_seeds(&pool_id_bytes, &pool_bump);
+    invoke_signed(
+        &append(tree_info.key, pool_info.key, compressed_leaf(&position)),
+        &[
+            tree_info.clone(),
+            pool_info.clone(),
+            noop_info.clone(),
+            compression_program_info.clone(),
+        ],
+        &[pool_seeds],
+    )?;
+
+    // Close the stake account after the CPI (the owner is not a CPI account)
+    let stake_lamports = user_stake_info.lamports();
+    **user_stake_info.try_borrow_mut_lamports()? = 0;
+    **user_info.try_borrow_mut_lamports()? += stake_lamports;
+    user_stake_info.try_borrow_mut_data()?.fill(0);
+
+    emit_stake_compressed(pool_info.key, leaf_index, &position);
+
+    msg!(
+        "Compressed {} staked tokens into leaf {}, returned {} lamports",
+        user_stake.amount,
+        leaf_index,
+        stake_lamports
+    );
+
+    Ok(())
+}
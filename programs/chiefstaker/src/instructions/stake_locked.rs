@@ -0,0 +1,31 @@
+//! Stake tokens on behalf of a beneficiary as a locked tranche
+
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+use crate::instructions::stake_on_behalf;
+
+/// Stake tokens on behalf of a beneficiary and lock them until `unlock_time`.
+/// The position earns rewards as usual; Unstake and RequestUnstake can only
+/// take tokens not held by unexpired tranches of its lock schedule.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+/// 1. `[writable]` Beneficiary stake account (PDA: ["stake", pool, beneficiary])
+/// 2. `[writable]` Token vault
+/// 3. `[writable]` Staker's token account
+/// 4. `[]` Token mint
+/// 5. `[writable, signer]` Staker — signs, pays rent, provides tokens
+/// 6. `[]` Beneficiary — NOT a signer, receives the locked position
+/// 7. `[]` System program
+/// 8. `[]` Token 2022 program
+/// 9. `[writable]` Lock schedule (PDA: ["lock_schedule", pool, beneficiary])
+/// 10. `[writable]` Receipt mint (receipt pools only)
+/// 11. `[writable]` Beneficiary's receipt token account (receipt pools only)
+pub fn process_stake_locked(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    unlock_time: i64,
+) -> ProgramResult {
+    stake_on_behalf(program_id, accounts, amount, Some(unlock_time))
+}
@@ -15,9 +15,14 @@ use solana_program::{
 use spl_token_2022::extension::StateWithExtensions;
 
 use crate::{
-    error::StakingError,
-    math::{exp_time_ratio, wad_mul, MAX_EXP_INPUT, U256, WAD},
-    state::{PoolMetadata, StakingPool, UserStake, STAKE_SEED},
+    error::{ErrorContext, StakingError},
+    history::{next_weight_history, record_checkpoint},
+    loaders::load_pool,
+    lock::add_lock_tranche,
+    math::{wad_mul, U256, WAD},
+    receipt::{mint_receipt, next_receipt_accounts},
+    state::{LockTranche, PoolMetadata, StakeAction, UserStake, STAKE_SEED},
+    time::pool_time,
 };
 
 /// Stake tokens on behalf of another user (beneficiary)
@@ -32,10 +37,24 @@ use crate::{
 /// 6. `[writable]` Beneficiary (B) — NOT a signer, receives position
 /// 7. `[]` System program
 /// 8. `[]` Token 2022 program
+/// 9. `[writable]` Receipt mint (receipt pools only)
+/// 10. `[writable]` Beneficiary's receipt token account (receipt pools only)
 pub fn process_stake_on_behalf(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     amount: u64,
+) -> ProgramResult {
+    stake_on_behalf(program_id, accounts, amount, None)
+}
+
+/// Shared body of StakeOnBehalf and StakeLocked. With `unlock_time`, the
+/// beneficiary's lock schedule follows the token program and the staked
+/// amount is added to it as a tranche.
+pub fn stake_on_behalf(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    unlock_time: Option<i64>,
 ) -> ProgramResult {
     if amount == 0 {
         return Err(StakingError::ZeroAmount.into());
@@ -52,6 +71,10 @@ pub fn process_stake_on_behalf(
     let beneficiary_info = next_account_info(account_info_iter)?;
     let system_program_info = next_account_info(account_info_iter)?;
     let token_program_info = next_account_info(account_info_iter)?;
+    let lock_schedule_info = match unlock_time {
+        Some(_) => Some(next_account_info(account_info_iter)?),
+        None => None,
+    };
 
     // Validate Token 2022 program
     if *token_program_info.key != spl_token_2022::id() {
@@ -64,19 +87,7 @@ pub fn process_stake_on_behalf(
     }
 
     // Load and validate pool
-    if pool_info.owner != program_id {
-        return Err(StakingError::InvalidAccountOwner.into());
-    }
-    let mut pool = StakingPool::try_from_slice(&pool_info.try_borrow_data()?)?;
-    if !pool.is_initialized() {
-        return Err(StakingError::NotInitialized.into());
-    }
-
-    // Verify pool PDA
-    let (expected_pool, _) = StakingPool::derive_pda(&pool.mint, program_id);
-    if *pool_info.key != expected_pool {
-        return Err(StakingError::InvalidPDA.into());
-    }
+    let mut pool = load_pool(program_id, pool_info)?;
 
     // Verify mint matches pool
     if pool.mint != *mint_info.key {
@@ -88,6 +99,18 @@ pub fn process_stake_on_behalf(
         return Err(StakingError::InvalidTokenVault.into());
     }
 
+    // Receipt accounts follow the fixed accounts on receipt pools
+    let receipt = next_receipt_accounts(&pool, account_info_iter)?;
+    if let Some(receipt) = &receipt {
+        // Receipts go to the position owner, who must burn them to unstake
+        let receipt_data = receipt.token_account.try_borrow_data()?;
+        let receipt_account =
+            StateWithExtensions::<spl_token_2022::state::Account>::unpack(&receipt_data)?;
+        if receipt_account.base.owner != *beneficiary_info.key {
+            return Err(StakingError::InvalidOwner.into());
+        }
+    }
+
     // Verify beneficiary stake PDA (derived from beneficiary, not staker)
     let (expected_stake, stake_bump) =
         UserStake::derive_pda(pool_info.key, beneficiary_info.key, program_id);
@@ -96,27 +119,21 @@ pub fn process_stake_on_behalf(
     }
 
     let clock = Clock::get()?;
-    let current_time = clock.unix_timestamp;
+    let current_time = pool_time(&pool)?;
 
-    // Check if pool needs rebasing (sum_stake_exp near overflow)
-    if pool.get_sum_stake_exp().needs_rebase() {
-        return Err(StakingError::PoolRequiresSync.into());
+    if let Some(unlock_time) = unlock_time {
+        if unlock_time <= current_time {
+            return Err(StakingError::InvalidUnlockTime.into());
+        }
     }
 
-    // Calculate exp_start_factor for this stake
-    let time_since_base = current_time.saturating_sub(pool.base_time);
-
-    // Check if time_since_base / tau would overflow exp_wad.
-    // Require SyncPool first if the ratio exceeds MAX_EXP_INPUT.
-    let ratio_wad = (time_since_base as u128)
-        .checked_mul(WAD)
-        .ok_or(StakingError::MathOverflow)?
-        / (pool.tau_seconds as u128);
-    if ratio_wad > MAX_EXP_INPUT {
-        return Err(StakingError::PoolRequiresSync.into());
-    }
+    // Rebase inline if sum_stake_exp is near overflow or time_since_base / tau
+    // would overflow exp_wad (saved with the pool below)
+    pool.rebase_if_needed(current_time)?;
 
-    let exp_start_factor = exp_time_ratio(time_since_base, pool.tau_seconds)?;
+    // Calculate exp_start_factor for this stake (pool creation time while
+    // the bootstrap window is open)
+    let (stake_time, exp_start_factor) = pool.new_stake_start(current_time)?;
 
     // Create or update beneficiary stake account
     let is_new_stake = beneficiary_stake_info.data_is_empty();
@@ -124,7 +141,8 @@ pub fn process_stake_on_behalf(
     if is_new_stake {
         // Check minimum stake amount
         if pool.min_stake_amount > 0 && amount < pool.min_stake_amount {
-            return Err(StakingError::BelowMinimumStake.into());
+            return Err(StakingError::BelowMinimumStake
+                .with_context(ErrorContext::MinimumAmount(pool.min_stake_amount)));
         }
 
         // Create new beneficiary stake account (staker pays rent)
@@ -163,6 +181,10 @@ pub fn process_stake_on_behalf(
             stake_bump,
             pool.base_time,
         );
+        user_stake.stake_time = stake_time;
+
+        user_stake.record_action(StakeAction::Stake, clock.slot)?;
+        user_stake.has_lock_schedule = lock_schedule_info.is_some();
 
         // Set reward_debt using max weight (amount * WAD) to prevent accessing prior rewards
         user_stake.reward_debt = wad_mul(
@@ -176,8 +198,7 @@ pub fn process_stake_on_behalf(
             .checked_add(user_stake.reward_debt)
             .ok_or(StakingError::MathOverflow)?;
 
-        let mut stake_data = beneficiary_stake_info.try_borrow_mut_data()?;
-        user_stake.serialize(&mut &mut stake_data[..])?;
+        user_stake.save(beneficiary_stake_info)?;
 
         // Update pool sum_stake_exp
         // sum_stake_exp += amount * exp_start_factor
@@ -204,17 +225,20 @@ pub fn process_stake_on_behalf(
         }
 
         // Verify ownership — must belong to the beneficiary
-        if user_stake.owner != *beneficiary_info.key {
-            return Err(StakingError::InvalidOwner.into());
-        }
+        user_stake.check_owner(beneficiary_info.key)?;
         if user_stake.pool != *pool_info.key {
             return Err(StakingError::InvalidPool.into());
         }
+        user_stake.check_not_frozen()?;
 
         // Block staking while unstake request is pending
-        if user_stake.has_pending_unstake_request() {
+        if user_stake.has_pending_unstake_request(&pool, current_time) {
             return Err(StakingError::PendingUnstakeRequestExists.into());
         }
+        user_stake.clear_expired_unstake_request(&pool, current_time);
+
+        // Block re-staking in the same slot as an unstake
+        user_stake.record_action(StakeAction::Stake, clock.slot)?;
 
         // Check minimum stake amount on new total
         let new_total = user_stake
@@ -222,7 +246,9 @@ pub fn process_stake_on_behalf(
             .checked_add(amount)
             .ok_or(StakingError::MathOverflow)?;
         if pool.min_stake_amount > 0 && new_total < pool.min_stake_amount {
-            return Err(StakingError::BelowMinimumStake.into());
+            let min_top_up = pool.min_stake_amount - user_stake.amount;
+            return Err(StakingError::BelowMinimumStake
+                .with_context(ErrorContext::MinimumAmount(min_top_up)));
         }
 
         // Lazily adjust exp_start_factor if pool has been rebased
@@ -253,8 +279,12 @@ pub fn process_stake_on_behalf(
             .checked_add(new_token_debt)
             .ok_or(StakingError::MathOverflow)?;
 
+        // Only the new tokens restart the pool lock
+        user_stake.record_deposit(&pool, amount, current_time);
         user_stake.amount = new_total;
         user_stake.last_stake_time = current_time;
+        user_stake.total_deposited = user_stake.total_deposited.saturating_add(amount);
+        user_stake.has_lock_schedule |= lock_schedule_info.is_some();
         // exp_start_factor: UNCHANGED — maturity depends only on start time
         // claimed_rewards_wad: UNCHANGED — pending rewards stay exactly the same
 
@@ -265,19 +295,42 @@ pub fn process_stake_on_behalf(
             .checked_add(user_stake.reward_debt)
             .ok_or(StakingError::MathOverflow)?;
 
-        let mut stake_data = beneficiary_stake_info.try_borrow_mut_data()?;
-        user_stake.serialize(&mut &mut stake_data[..])?;
+        // Positions with a weight history must pass it (after receipt accounts)
+        if let Some(history_info) = next_weight_history(&user_stake, account_info_iter)? {
+            record_checkpoint(program_id, history_info, pool_info.key, &pool, &user_stake, current_time)?;
+        }
+
+        user_stake.save(beneficiary_stake_info)?;
     }
 
     // Update pool total staked
+    let was_below_threshold = pool.below_distribution_threshold();
     pool.total_staked = pool
         .total_staked
         .checked_add(amount as u128)
         .ok_or(StakingError::MathOverflow)?;
 
+    // Enough stake arrived: idle SOL is no longer refundable
+    if was_below_threshold && !pool.below_distribution_threshold() {
+        pool.undistributed_since = 0;
+    }
+
     // Save pool state
-    let mut pool_data = pool_info.try_borrow_mut_data()?;
-    pool.serialize(&mut &mut pool_data[..])?;
+    pool.save(pool_info)?;
+
+    // Locked stakes: the new tokens become a tranche of the beneficiary's schedule
+    if let (Some(schedule_info), Some(unlock_time)) = (lock_schedule_info, unlock_time) {
+        add_lock_tranche(
+            program_id,
+            schedule_info,
+            pool_info.key,
+            beneficiary_info.key,
+            staker_info,
+            system_program_info,
+            LockTranche { amount, unlock_time },
+            current_time,
+        )?;
+    }
 
     // Transfer tokens from staker to vault (staker signs the transfer)
     let mint_data = mint_info.try_borrow_data()?;
@@ -304,6 +357,11 @@ pub fn process_stake_on_behalf(
         ],
     )?;
 
+    // Mint receipt tokens 1:1 with the staked amount
+    if let Some(receipt) = &receipt {
+        mint_receipt(&pool, pool_info, receipt, amount)?;
+    }
+
     // Optional metadata account: increment member_count on new stake
     if is_new_stake {
         if let Some(metadata_info) = account_info_iter.next() {
@@ -0,0 +1,52 @@
+//! Sweep rounding dust back into the reward accumulator
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::loaders::load_pool;
+
+/// Fold accumulated floor-division dust back into acc_reward_per_weighted_share.
+/// Permissionless crank; a no-op while the dust is too small to move the
+/// accumulator, while there are no stakers, or while rewards are paused.
+///
+/// The dust lamports never left the pool and are already counted in
+/// last_synced_lamports, so no lamports move here.
+///
+/// Accounts:
+/// 0. `[writable]` Pool account
+pub fn process_sweep_dust(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let pool_info = next_account_info(account_info_iter)?;
+
+    // Load and validate pool
+    let mut pool = load_pool(program_id, pool_info)?;
+
+    if pool.total_staked == 0 || pool.rewards_paused {
+        msg!("Dust sweep deferred ({} dust)", pool.accumulated_dust_wad);
+        return Ok(());
+    }
+
+    let dust_before = pool.accumulated_dust_wad;
+    let reward_per_share = pool.sweep_dust()?;
+    if reward_per_share == 0 {
+        msg!("Dust below sweep threshold ({} dust)", dust_before);
+        return Ok(());
+    }
+
+    pool.save(pool_info)?;
+
+    msg!(
+        "Swept dust: reward_per_share +{}, remaining dust {}",
+        reward_per_share,
+        pool.accumulated_dust_wad
+    );
+
+    Ok(())
+}
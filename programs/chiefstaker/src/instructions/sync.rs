@@ -1,25 +1,25 @@
 //! Sync/rebase pool instruction
 
-use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
-    clock::Clock,
     entrypoint::ProgramResult,
     msg,
     pubkey::Pubkey,
-    sysvar::Sysvar,
 };
 
 use crate::{
-    error::StakingError,
-    math::{exp_neg_time_ratio, wad_mul_u256, U256},
-    state::StakingPool,
+    events::emit_pool_rebased,
+    loaders::load_pool,
+    time::pool_time,
 };
 
 /// Sync/rebase the pool to prevent overflow
 /// This shifts base_time forward and scales down sum_stake_exp
 ///
-/// Anyone can call this (permissionless crank)
+/// Anyone can call this (permissionless crank). It only rebases once due:
+/// when sum_stake_exp is near overflow or (now - base_time) / tau exceeds
+/// the pool's rebase threshold. Earlier calls are no-ops, since every
+/// rebase compounds the rounding of its decay factor.
 ///
 /// Accounts:
 /// 0. `[writable]` Pool account
@@ -32,56 +32,39 @@ pub fn process_sync_pool(
     let pool_info = next_account_info(account_info_iter)?;
 
     // Load and validate pool
-    if pool_info.owner != program_id {
-        return Err(StakingError::InvalidAccountOwner.into());
-    }
-    let mut pool = StakingPool::try_from_slice(&pool_info.try_borrow_data()?)?;
-    if !pool.is_initialized() {
-        return Err(StakingError::NotInitialized.into());
-    }
-
-    // Verify pool PDA
-    let (expected_pool, _) = StakingPool::derive_pda(&pool.mint, program_id);
-    if *pool_info.key != expected_pool {
-        return Err(StakingError::InvalidPDA.into());
-    }
-
-    let clock = Clock::get()?;
-    let current_time = clock.unix_timestamp;
+    let mut pool = load_pool(program_id, pool_info)?;
 
-    // Calculate time delta since base_time
-    let time_delta = current_time.saturating_sub(pool.base_time);
+    let current_time = pool_time(&pool)?;
 
-    if time_delta <= 0 {
-        msg!("No time has passed since base_time");
+    if !pool.rebase_due(current_time)? {
+        msg!(
+            "Rebase not due: exp input {} below threshold {}",
+            pool.exp_input_wad(current_time)?,
+            pool.rebase_threshold_wad()
+        );
         return Ok(());
     }
 
-    // Record the original base_time before first rebase so legacy UserStake
-    // accounts can be lazily adjusted (their exp_start_factor is relative
-    // to the initial base_time).
-    if pool.initial_base_time == 0 {
-        pool.initial_base_time = pool.base_time;
-    }
-
-    // Calculate the decay factor: e^(-time_delta / tau)
-    // For very large time_delta (> 87*tau), exp_neg_time_ratio returns 0,
-    // meaning all stakes are fully matured and sum_stake_exp zeroes out.
-    let decay_factor = exp_neg_time_ratio(time_delta, pool.tau_seconds)?;
-
-    // Scale down sum_stake_exp by decay factor
-    // new_sum_stake_exp = old_sum_stake_exp * decay_factor / WAD
-    let sum_stake_exp = pool.get_sum_stake_exp();
-    let decay_u256 = U256::from_u128(decay_factor);
-    let new_sum_stake_exp = wad_mul_u256(sum_stake_exp, decay_u256)?;
+    let old_base_time = pool.base_time;
+    let old_sum_stake_exp = pool.get_sum_stake_exp();
 
-    // Update pool state
-    pool.set_sum_stake_exp(new_sum_stake_exp);
-    pool.base_time = current_time;
+    // Shift base_time forward and scale down sum_stake_exp by e^(-time_delta / tau)
+    let Some(decay_factor) = pool.rebase(current_time)? else {
+        msg!("No time has passed since base_time");
+        return Ok(());
+    };
 
     // Save pool state
-    let mut pool_data = pool_info.try_borrow_mut_data()?;
-    pool.serialize(&mut &mut pool_data[..])?;
+    pool.save(pool_info)?;
+
+    emit_pool_rebased(
+        pool_info.key,
+        old_base_time,
+        pool.base_time,
+        decay_factor,
+        &old_sum_stake_exp,
+        &pool.get_sum_stake_exp(),
+    );
 
     msg!(
         "Synced pool: base_time updated to {}, sum_stake_exp reduced by factor {}",
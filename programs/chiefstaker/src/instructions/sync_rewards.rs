@@ -3,10 +3,8 @@
 //! This allows external sources (like pump.fun) to send SOL directly
 //! to the pool PDA, and anyone can call this to distribute it.
 
-use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
-    clock::Clock,
     entrypoint::ProgramResult,
     msg,
     pubkey::Pubkey,
@@ -15,16 +13,36 @@ use solana_program::{
 };
 
 use crate::{
-    error::StakingError,
-    math::{wad_div, WAD},
-    state::StakingPool,
+    distribution_history::{load_history_page, next_distribution_history, record_distribution},
+    events::emit_pool_synced,
+    introspection::{next_instructions_sysvar, prior_transfers_into, reject_prior_stake},
+    loaders::load_pool,
+    matching::{draw_match, next_matching_vault},
+    state::{DistributionHistory, StakingPool},
+    time::pool_time,
 };
 
 /// Sync rewards that were sent directly to the pool account
 /// This is a permissionless crank that anyone can call
 ///
+/// With the instructions sysvar, system transfers into the pool earlier in
+/// the same transaction are attributed to their sender in the PoolSynced
+/// event, and are distributed even while total_staked is below
+/// min_distribution_stake (as long as anything is staked). Other deferred
+/// lamports stay pending. So does everything that arrives within
+/// min_sync_interval_seconds of the last distribution.
+///
+/// Pools with a distribution history checkpoint every distribution; while
+/// the page for the next checkpoint doesn't exist (AddDistributionHistoryPage
+/// creates it), new rewards stay pending.
+///
 /// Accounts:
 /// 0. `[writable]` Pool account
+/// 1. `[writable]` Matching vault (pools with reward matching only)
+/// 2. `[writable]` Current distribution history page (pools with a
+///    distribution history only)
+/// 3. `[]` (optional) Instructions sysvar; required on pools with
+///    stake_guard_required
 pub fn process_sync_rewards(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -33,23 +51,17 @@ pub fn process_sync_rewards(
     let pool_info = next_account_info(account_info_iter)?;
 
     // Load and validate pool
-    if pool_info.owner != program_id {
-        return Err(StakingError::InvalidAccountOwner.into());
-    }
-    let mut pool = StakingPool::try_from_slice(&pool_info.try_borrow_data()?)?;
-    if !pool.is_initialized() {
-        return Err(StakingError::NotInitialized.into());
-    }
+    let mut pool = load_pool(program_id, pool_info)?;
 
-    // Verify pool PDA
-    let (expected_pool, _) = StakingPool::derive_pda(&pool.mint, program_id);
-    if *pool_info.key != expected_pool {
-        return Err(StakingError::InvalidPDA.into());
-    }
+    // Pools with reward matching must pass their matching vault
+    let vault_info = next_matching_vault(program_id, pool_info.key, &pool, account_info_iter)?;
+    // Pools with a distribution history must pass the current page
+    let history_info = next_distribution_history(&pool, account_info_iter)?;
+    // Pools with stake_guard_required must pass the instructions sysvar
+    let instructions_sysvar = next_instructions_sysvar(&pool, account_info_iter)?;
 
     let rent = Rent::get()?;
-    let clock = Clock::get()?;
-    let current_time = clock.unix_timestamp;
+    let current_time = pool_time(&pool)?;
 
     // Calculate how much SOL is available for rewards
     let pool_lamports = pool_info.lamports();
@@ -61,47 +73,150 @@ pub fn process_sync_rewards(
     // New rewards = current balance - what we knew about
     let new_rewards = current_available.saturating_sub(last_known);
 
+    // Deferred rewards are still recorded as undistributed
+    let recorded = pool.record_undistributed(pool_info, &rent);
+
     if new_rewards == 0 {
+        if recorded {
+            pool.save(pool_info)?;
+        }
         msg!("No new rewards to sync");
         return Ok(());
     }
 
-    // Denominator: total_staked * WAD (max weight, not time-varying)
-    let total_staked_wad = (pool.total_staked as u128)
-        .checked_mul(WAD)
-        .ok_or(StakingError::MathOverflow)?;
+    if pool.rewards_paused {
+        // Leave rewards pending until distribution resumes
+        if recorded {
+            pool.save(pool_info)?;
+        }
+        msg!("Rewards deferred: {} new lamports, rewards paused", new_rewards);
+        return Ok(());
+    }
 
-    if total_staked_wad == 0 {
-        // No stakers to distribute to. Leave rewards pending.
+    if pool.distribution_too_soon(current_time) {
+        // Rate limit: dust syncs can't churn the accumulator every slot
+        if recorded {
+            pool.save(pool_info)?;
+        }
         msg!(
-            "Rewards deferred: {} new lamports, no stakers",
+            "Rewards deferred: {} new lamports, last distribution at {}",
             new_rewards,
+            pool.last_distribution_time,
         );
         return Ok(());
     }
 
-    // Calculate reward per share using max weight denominator
-    let amount_wad = (new_rewards as u128)
-        .checked_mul(WAD)
-        .ok_or(StakingError::MathOverflow)?;
-    let reward_per_share = wad_div(amount_wad, total_staked_wad)?;
+    // Direct transfers into the pool earlier in this transaction
+    let direct_transfer = match instructions_sysvar {
+        Some(instructions_sysvar) => prior_transfers_into(instructions_sysvar, pool_info.key)?,
+        None => None,
+    };
+
+    let new_rewards = if !pool.below_distribution_threshold() {
+        new_rewards
+    } else if let Some((_, transferred)) = direct_transfer.filter(|_| pool.total_staked > 0) {
+        // Below min_distribution_stake, but the sender chose to distribute
+        // what it just sent; anything else stays pending
+        transferred.min(new_rewards)
+    } else {
+        // No stakers (or less than min_distribution_stake) to distribute to.
+        // Leave rewards pending.
+        if pool.mark_undistributed(current_time) || recorded {
+            pool.save(pool_info)?;
+        }
+        msg!(
+            "Rewards deferred: {} new lamports, total_staked {} below threshold",
+            new_rewards,
+            pool.total_staked,
+        );
+        return Ok(());
+    };
+    let leaves_pending = current_available - last_known > new_rewards;
+
+    // Every distribution must be checkpointed: without the page, wait
+    let history = match history_info {
+        Some(page_info) => match load_history_page(program_id, page_info, pool_info.key, &pool)? {
+            Some(history) => Some((page_info, history)),
+            None => {
+                if recorded {
+                    pool.save(pool_info)?;
+                }
+                msg!(
+                    "Rewards deferred: {} new lamports, distribution history page {} not created",
+                    new_rewards,
+                    DistributionHistory::page_of(pool.distribution_checkpoints),
+                );
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+
+    // Sandwich guard: refuse to distribute if this transaction already
+    // staked into the pool
+    if let Some(instructions_sysvar) = instructions_sysvar {
+        reject_prior_stake(instructions_sysvar, program_id, pool_info.key)?;
+    }
+
+    // Legacy pools grow so new accounting fields fit. There is no payer here,
+    // so the larger rent-exempt minimum is funded from the new rewards.
+    let Some(migration_rent) = StakingPool::realloc_from_undistributed(pool_info, new_rewards)?
+    else {
+        if recorded {
+            pool.save(pool_info)?;
+        }
+        msg!(
+            "Rewards deferred: {} new lamports do not cover pool migration rent",
+            new_rewards,
+        );
+        return Ok(());
+    };
+    let new_rewards = new_rewards - migration_rent;
 
-    // Update accumulator
-    pool.acc_reward_per_weighted_share = pool
-        .acc_reward_per_weighted_share
-        .checked_add(reward_per_share)
-        .ok_or(StakingError::MathOverflow)?;
+    // Matching vaults add their share; the match is already in the pool
+    // balance, so it is counted as synced below
+    let matched = match vault_info {
+        Some(vault_info) => draw_match(vault_info, pool_info, new_rewards)?,
+        None => 0,
+    };
+    let synced = new_rewards + matched;
+
+    let old_acc_rps = pool.acc_reward_per_weighted_share;
+    let old_fees_owed = pool.protocol_fees_owed;
+
+    // Calculate reward per share using max weight denominator and update the accumulator
+    let reward_per_share = pool.distribute_rewards(synced)?;
+    pool.vest_distribution(current_time, reward_per_share);
+    pool.record_reward_rate(current_time, reward_per_share)?;
 
     pool.last_update_time = current_time;
-    pool.last_synced_lamports = current_available;
+    pool.last_distribution_time = current_time;
+    pool.last_synced_lamports = last_known + synced;
+    if !leaves_pending {
+        pool.undistributed_since = 0;
+    }
+    pool.record_undistributed(pool_info, &rent);
+    if let Some((page_info, mut history)) = history {
+        record_distribution(page_info, &mut history, &mut pool, current_time, synced)?;
+    }
 
     // Save pool state
-    let mut pool_data = pool_info.try_borrow_mut_data()?;
-    pool.serialize(&mut &mut pool_data[..])?;
+    pool.save(pool_info)?;
+
+    emit_pool_synced(
+        pool_info.key,
+        synced,
+        pool.protocol_fees_owed - old_fees_owed,
+        old_acc_rps,
+        pool.acc_reward_per_weighted_share,
+        pool.base_time,
+        &direct_transfer.map_or(Pubkey::default(), |(depositor, _)| depositor),
+    );
 
     msg!(
-        "Synced {} lamports of new rewards, reward_per_share: {}",
-        new_rewards,
+        "Synced {} lamports of new rewards ({} matched), reward_per_share: {}",
+        synced,
+        matched,
         reward_per_share
     );
 
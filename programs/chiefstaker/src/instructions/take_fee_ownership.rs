@@ -7,7 +7,6 @@
 //! Prerequisite: the fee sharing authority must already have been transferred
 //! to the pool PDA off-chain via `transfer_fee_sharing_authority`.
 
-use borsh::BorshDeserialize;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
@@ -20,7 +19,7 @@ use solana_program::{
 
 use crate::{
     error::StakingError,
-    state::{StakingPool, POOL_SEED},
+    loaders::load_pool,
 };
 
 /// pfee program: pfeeUxB6jkeY1Hxd7CsFCAjcbHA9rWtchMGdZ6VojVZ
@@ -40,7 +39,8 @@ const REVOKE_FEE_SHARING_AUTHORITY_DISC: [u8; 8] = [18, 233, 158, 39, 185, 207,
 /// Take fee ownership: set pool as sole fee recipient and revoke authority.
 ///
 /// Accounts (18):
-///  0. `[]`  pool — Pool PDA ["pool", mint], CPI signer
+///  0. `[]`  pool — Pool PDA ["pool", mint] (["pool", mint, pool_id] for
+///     pool_id > 0), CPI signer
 ///  1. `[]`  mint — must match pool.mint
 ///  2. `[]`  pfee_program
 ///  3. `[]`  pfee_event_authority — PDA on pfee: ["__event_authority"]
@@ -84,19 +84,7 @@ pub fn process_take_fee_ownership(
     let coin_creator_vault_ata_info = next_account_info(account_info_iter)?;
 
     // ── Validate pool ───────────────────────────────────────────────────────
-    if pool_info.owner != program_id {
-        return Err(StakingError::InvalidAccountOwner.into());
-    }
-    let pool = StakingPool::try_from_slice(&pool_info.try_borrow_data()?)?;
-    if !pool.is_initialized() {
-        return Err(StakingError::NotInitialized.into());
-    }
-
-    // Verify pool PDA
-    let (expected_pool, _) = StakingPool::derive_pda(&pool.mint, program_id);
-    if *pool_info.key != expected_pool {
-        return Err(StakingError::InvalidPDA.into());
-    }
+    let pool = load_pool(program_id, pool_info)?;
 
     // Verify mint matches pool
     if pool.mint != *mint_info.key {
@@ -109,7 +97,9 @@ pub fn process_take_fee_ownership(
     }
 
     // Pool PDA signing seeds
-    let pool_seeds: &[&[u8]] = &[POOL_SEED, pool.mint.as_ref(), &[pool.bump]];
+    let pool_id_bytes = pool.pool_id.to_le_bytes();
+    let pool_bump = [pool.bump];
+    let pool_seeds: &[&[u8]] = &pool.signer_seeds(&pool_id_bytes, &pool_bump);
 
     // ── CPI 1: update_fee_shares ────────────────────────────────────────────
     // Data: 8-byte discriminator + Vec<Shareholder> with 1 entry {pool_pda, 10000u16}
@@ -0,0 +1,117 @@
+//! Tokenize position instruction
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    error::StakingError,
+    loaders::{load_pool, load_user_stake},
+    position_nft::{
+        create_position_mint, mint_position_nft, position_metadata, ASSOCIATED_TOKEN_PROGRAM_ID,
+    },
+    state::UserStake,
+    time::pool_time,
+};
+
+/// Mint a Token 2022 NFT for the position and hand control of it to the
+/// NFT's holder. The NFT's metadata records the pool, amount and stake
+/// time. Until the holder redeems it (RedeemPosition), the position is
+/// frozen: no owner instruction works on it, so the metadata stays
+/// accurate while the NFT trades. Rewards keep accruing to the position.
+///
+/// Only positions passing UserStake::is_tokenizable can be tokenized, and
+/// not on pools with a receipt mint, whose receipts couldn't follow the
+/// NFT.
+///
+/// Accounts:
+/// 0. `[]` Pool account
+/// 1. `[writable]` User stake account
+/// 2. `[writable, signer]` User/owner (pays rent for the mint and token
+///    account)
+/// 3. `[writable]` Position NFT mint (PDA: ["position_nft", user_stake])
+/// 4. `[writable]` Owner's associated token account for the NFT
+/// 5. `[]` Token 2022 program
+/// 6. `[]` Associated token account program
+/// 7. `[]` System program
+pub fn process_tokenize_position(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let pool_info = next_account_info(account_info_iter)?;
+    let user_stake_info = next_account_info(account_info_iter)?;
+    let user_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let token_account_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let ata_program_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Validate user is signer
+    if !user_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    if *token_program_info.key != spl_token_2022::id() {
+        return Err(StakingError::InvalidTokenProgram.into());
+    }
+    if *ata_program_info.key != ASSOCIATED_TOKEN_PROGRAM_ID {
+        return Err(StakingError::InvalidTokenProgram.into());
+    }
+
+    // Load and validate pool
+    let pool = load_pool(program_id, pool_info)?;
+
+    // Load and validate user stake
+    let mut user_stake =
+        load_user_stake(program_id, user_stake_info, pool_info.key, user_info.key)?;
+
+    let current_time = pool_time(&pool)?;
+    if pool.has_receipt_mint() || !user_stake.is_tokenizable(&pool, current_time) {
+        return Err(StakingError::PositionNotTokenizable.into());
+    }
+
+    let metadata = position_metadata(pool_info.key, &pool, mint_info.key, &user_stake)?;
+    create_position_mint(
+        program_id,
+        &pool,
+        pool_info,
+        user_stake_info,
+        mint_info,
+        user_info,
+        system_program_info,
+        &Rent::get()?,
+        &metadata,
+    )?;
+    mint_position_nft(
+        &pool,
+        pool_info,
+        mint_info,
+        user_info,
+        token_account_info,
+        token_program_info,
+        ata_program_info,
+        system_program_info,
+    )?;
+
+    // Realloc legacy accounts to current size (payer = user)
+    UserStake::maybe_realloc(user_stake_info, user_info, Some(system_program_info))?;
+
+    user_stake.position_mint = *mint_info.key;
+    user_stake.save(user_stake_info)?;
+
+    msg!(
+        "Tokenized position of {} tokens as NFT {}",
+        user_stake.amount,
+        mint_info.key
+    );
+
+    Ok(())
+}
@@ -1,6 +1,5 @@
 //! Transfer authority instruction
 
-use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
@@ -9,25 +8,55 @@ use solana_program::{
 };
 
 use crate::{
-    error::StakingError,
-    state::StakingPool,
+    authority_log::{next_authority_log, record_authority_actions},
+    error::{ErrorContext, StakingError},
+    loaders::load_pool,
+    state::{AuthorityAction, AuthorityLogEntry, StakingPool},
+    time::pool_time,
 };
 
 /// Transfer pool authority to a new address
 /// Setting new_authority to Pubkey::default() renounces authority (irreversible)
 ///
+/// Nobody can lower the pool's settings after a renounce, so it is refused
+/// while lock_duration_seconds or unstake_cooldown_seconds exceeds
+/// StakingPool::MAX_RENOUNCE_SAFE_SECONDS (see RenounceAuthorityUnsafe).
+///
 /// Accounts:
 /// 0. `[writable]` Pool account
-/// 1. `[signer]` Current authority
+/// 1. `[signer]` Current authority (`[writable]` on pools with an authority log)
+/// 2. `[]` System program (pools with an authority log only)
+/// 3. `[writable]` Authority log (pools with an authority log only)
 pub fn process_transfer_authority(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     new_authority: Pubkey,
+) -> ProgramResult {
+    set_authority(program_id, accounts, new_authority, false)
+}
+
+/// Renounce pool authority whatever its lock and cooldown settings: the
+/// explicit opt-in to freezing settings above the renounce-safe bound
+///
+/// Accounts: same as TransferAuthority
+pub fn process_renounce_authority_unsafe(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    set_authority(program_id, accounts, Pubkey::default(), true)
+}
+
+fn set_authority(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    new_authority: Pubkey,
+    allow_unsafe_settings: bool,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
 
     let pool_info = next_account_info(account_info_iter)?;
     let authority_info = next_account_info(account_info_iter)?;
+    let system_program_info = account_info_iter.next();
 
     // Validate authority is signer
     if !authority_info.is_signer {
@@ -35,19 +64,7 @@ pub fn process_transfer_authority(
     }
 
     // Load and validate pool
-    if pool_info.owner != program_id {
-        return Err(StakingError::InvalidAccountOwner.into());
-    }
-    let mut pool = StakingPool::try_from_slice(&pool_info.try_borrow_data()?)?;
-    if !pool.is_initialized() {
-        return Err(StakingError::NotInitialized.into());
-    }
-
-    // Verify pool PDA
-    let (expected_pool, _) = StakingPool::derive_pda(&pool.mint, program_id);
-    if *pool_info.key != expected_pool {
-        return Err(StakingError::InvalidPDA.into());
-    }
+    let mut pool = load_pool(program_id, pool_info)?;
 
     // Check authority is not already renounced
     if pool.is_authority_renounced() {
@@ -59,12 +76,39 @@ pub fn process_transfer_authority(
         return Err(StakingError::InvalidAuthority.into());
     }
 
+    // Renouncing freezes the settings: refuse to freeze abusive ones
+    if new_authority == Pubkey::default() && !pool.renounce_safe() {
+        if !allow_unsafe_settings {
+            msg!(
+                "Renounce refused: lock_duration {}s, unstake_cooldown {}s",
+                pool.lock_duration_seconds,
+                pool.unstake_cooldown_seconds,
+            );
+            return Err(StakingError::UnsafeRenounceSettings
+                .with_context(ErrorContext::MaximumAmount(StakingPool::MAX_RENOUNCE_SAFE_SECONDS)));
+        }
+        msg!("Renouncing with unsafe settings (explicit override)");
+    }
+
+    // Pools with an authority log must pass it (after the system program)
+    let log_info = next_authority_log(&pool, account_info_iter)?;
+
+    if let Some(log_info) = log_info {
+        let entry = AuthorityLogEntry::pubkey(
+            pool_time(&pool)?,
+            *authority_info.key,
+            AuthorityAction::Authority,
+            pool.authority,
+            new_authority,
+        );
+        record_authority_actions(program_id, log_info, pool_info.key, authority_info, system_program_info, &[entry])?;
+    }
+
     // Transfer authority
     pool.authority = new_authority;
 
     // Save pool state
-    let mut pool_data = pool_info.try_borrow_mut_data()?;
-    pool.serialize(&mut &mut pool_data[..])?;
+    pool.save(pool_info)?;
 
     if new_authority == Pubkey::default() {
         msg!("Authority renounced (irreversible)");
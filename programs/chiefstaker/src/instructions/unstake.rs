@@ -1,50 +1,68 @@
 //! Unstake tokens instruction
 
-use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     clock::Clock,
     entrypoint::ProgramResult,
     msg,
     program::invoke_signed,
+    program_error::ProgramError,
     pubkey::Pubkey,
     sysvar::Sysvar,
 };
 use spl_token_2022::extension::StateWithExtensions;
 
 use crate::{
-    error::StakingError,
+    error::{ErrorContext, StakingError},
     events::{emit_reward_payout, RewardPayoutType},
+    guardian::check_guardian_signature,
+    loaders::{load_pool, load_user_stake},
     math::{calculate_user_weighted_stake, wad_div, wad_mul, U256, WAD},
-    state::{StakingPool, UserStake, POOL_SEED},
+    history::{next_weight_history, record_checkpoint},
+    lock::{check_unlocked, next_lock_schedule},
+    memo::{invoke_memo, next_memo_program},
+    receipt::{burn_receipt, next_receipt_accounts},
+    recipient::{next_reward_payee, RewardPayee},
+    state::{StakeAction, StakingPool, UserStake},
+    time::pool_time,
 };
 
-/// Shared unstake logic used by both process_unstake and process_complete_unstake.
-/// Handles: reward claiming, pool math updates (sum_stake_exp, total_staked),
-/// reward_debt recalculation, and token transfer.
-///
-/// Assumes all account validation has been done by the caller.
-pub fn execute_unstake<'a>(
-    _program_id: &Pubkey,
+/// Take the unstake fee destination from `iter` if the pool charges a fee.
+/// Pools without a fee consume nothing.
+pub fn next_fee_destination<'a, 'b, I>(
+    pool: &StakingPool,
+    iter: &mut I,
+) -> Result<Option<&'b AccountInfo<'a>>, ProgramError>
+where
+    I: Iterator<Item = &'b AccountInfo<'a>>,
+{
+    if !pool.has_unstake_fee() {
+        return Ok(None);
+    }
+    let fee_destination_info = next_account_info(iter)?;
+    if *fee_destination_info.key != pool.unstake_fee_destination {
+        return Err(StakingError::InvalidFeeDestination.into());
+    }
+    Ok(Some(fee_destination_info))
+}
+
+/// Remove `amount` from the position: pending rewards are computed against
+/// the pool's available balance and the pool/user math (sum_stake_exp,
+/// total_staked, reward_debt) updated. Rewards still vesting move to the
+/// position's vesting fragment, as do all rewards of a frozen position.
+/// Returns the reward lamports to pay; `last_synced_lamports` already
+/// accounts for them but the caller must move them out of the pool.
+pub fn settle_unstake(
     pool: &mut StakingPool,
     user_stake: &mut UserStake,
-    pool_info: &AccountInfo<'a>,
-    user_stake_info: &AccountInfo<'a>,
-    token_vault_info: &AccountInfo<'a>,
-    user_token_info: &AccountInfo<'a>,
-    mint_info: &AccountInfo<'a>,
-    user_info: &AccountInfo<'a>,
+    pool_info: &AccountInfo,
     amount: u64,
     current_time: i64,
-    system_program_info: Option<&AccountInfo<'a>>,
-) -> ProgramResult {
-
+) -> Result<u64, ProgramError> {
     // Capture old reward_debt for total_reward_debt bookkeeping
     let old_reward_debt = user_stake.reward_debt;
 
-    // Calculate pending rewards (but defer SOL transfer until after token CPI,
-    // because the Solana runtime verifies CPI account balances and user_info
-    // is not a CPI account)
+    // Calculate pending rewards (the caller moves the lamports)
     let mut reward_transfer_amount: u64 = 0;
 
     let user_weighted = calculate_user_weighted_stake(
@@ -58,6 +76,14 @@ pub fn execute_unstake<'a>(
     // Track unpaid rewards (WAD-scaled) to carry forward in reward_debt
     let mut unpaid_rewards_wad: u128 = 0;
 
+    // The vested part of an earlier vesting fragment is paid with the rest
+    // (fragment lamports are tracked with the residuals)
+    let mut pending = user_stake.release_vested_rewards(current_time);
+    pool.pay_residual((pending / WAD) as u64);
+
+    // Rewards of distributions still vesting go to the vesting fragment
+    let mut unvested_rewards_wad: u128 = 0;
+
     if user_weighted > 0 && pool.acc_reward_per_weighted_share > 0 {
         // Full entitlement: user_weighted * (acc_rps - snapshot)
         let amount_wad = (user_stake.amount as u128)
@@ -66,30 +92,43 @@ pub fn execute_unstake<'a>(
         let snapshot = wad_div(user_stake.reward_debt, amount_wad)?;
         let delta_rps = pool.acc_reward_per_weighted_share.saturating_sub(snapshot);
         let full_entitlement = wad_mul(user_weighted, delta_rps)?;
+        let vested_delta_rps = pool.vested_acc_rps(current_time).saturating_sub(snapshot);
+        let vested_entitlement = wad_mul(user_weighted, vested_delta_rps)?;
         // Subtract already-claimed amount (frequency-independent)
-        let pending = full_entitlement.saturating_sub(user_stake.claimed_rewards_wad);
+        let owed = full_entitlement.saturating_sub(user_stake.claimed_rewards_wad);
+        let vested_owed = vested_entitlement.saturating_sub(user_stake.claimed_rewards_wad);
+        unvested_rewards_wad = owed - vested_owed;
+        pending = pending.saturating_add(vested_owed);
+    }
 
-        if pending > 0 {
-            let pending_lamports = pending / WAD;
+    // Frozen positions get their principal only; their rewards stay owed in
+    // the vesting fragment, paid by claims once the position is unfrozen
+    if user_stake.rewards_frozen {
+        unvested_rewards_wad = unvested_rewards_wad.saturating_add(pending);
+        pending = 0;
+    }
+
+    if pending > 0 {
+        let pending_lamports = pool.payout_lamports(pending);
 
-            if pending_lamports > 0 {
-                let pool_lamports = pool_info.lamports();
-                let rent_exempt_minimum = solana_program::rent::Rent::get()?
-                    .minimum_balance(pool_info.data_len());
+        if pending_lamports > 0 {
+            let pool_lamports = pool_info.lamports();
+            let rent_exempt_minimum = solana_program::rent::Rent::get()?
+                .minimum_balance(pool_info.data_len());
 
-                let available_rewards = pool_lamports.saturating_sub(rent_exempt_minimum);
-                reward_transfer_amount = pending_lamports.min(available_rewards as u128) as u64;
+            // Protocol fees owed to the treasury are not rewards, nor are
+            // lamports reserved for residual claimants
+            let available_rewards =
+                pool.claimable_lamports(pool_lamports, rent_exempt_minimum, false);
+            reward_transfer_amount = pending_lamports.min(available_rewards as u128) as u64;
 
-                // Track unpaid portion so it remains claimable later
-                let paid_wad = (reward_transfer_amount as u128)
-                    .checked_mul(WAD)
-                    .ok_or(StakingError::MathOverflow)?;
-                unpaid_rewards_wad = pending.saturating_sub(paid_wad);
+            // Track unpaid portion so it remains claimable later
+            let paid_wad = pool.settle_payout(pending, reward_transfer_amount);
+            unpaid_rewards_wad = pending.saturating_sub(paid_wad);
 
-                // Pre-update last_synced_lamports (actual SOL transfer deferred to after CPI)
-                if reward_transfer_amount > 0 {
-                    pool.last_synced_lamports = pool.last_synced_lamports.saturating_sub(reward_transfer_amount);
-                }
+            // Pre-update last_synced_lamports (actual SOL transfer deferred to after CPI)
+            if reward_transfer_amount > 0 {
+                pool.last_synced_lamports = pool.last_synced_lamports.saturating_sub(reward_transfer_amount);
             }
         }
     }
@@ -118,6 +157,7 @@ pub fn execute_unstake<'a>(
         .amount
         .checked_sub(amount)
         .ok_or(StakingError::MathUnderflow)?;
+    user_stake.total_withdrawn = user_stake.total_withdrawn.saturating_add(amount);
 
     // Recalculate reward debt for remaining stake
     if user_stake.amount > 0 {
@@ -145,7 +185,7 @@ pub fn execute_unstake<'a>(
         // Remove old debt from total_reward_debt but do NOT add the residual.
         // Residual debts are tracked separately in total_residual_unpaid because
         // the user's amount is 0 (no allocation in total_staked * acc_rps), and
-        // including them in total_reward_debt would break FixTotalRewardDebt.
+        // including them in total_reward_debt would break the stranded-rewards formula.
         pool.total_reward_debt = pool
             .total_reward_debt
             .saturating_sub(old_reward_debt);
@@ -157,23 +197,58 @@ pub fn execute_unstake<'a>(
             .ok_or(StakingError::MathOverflow)?;
     }
 
+    // Unvested rewards keep vesting on the position, partial or full unstake
+    user_stake.add_vesting_rewards(current_time, unvested_rewards_wad, pool.vesting_end);
+    pool.total_residual_unpaid = pool
+        .total_residual_unpaid
+        .checked_add((unvested_rewards_wad / WAD) as u64)
+        .ok_or(StakingError::MathOverflow)?;
+
     // Increment cumulative rewards counter
     if reward_transfer_amount > 0 {
         user_stake.total_rewards_claimed = user_stake.total_rewards_claimed.saturating_add(reward_transfer_amount);
     }
 
+    Ok(reward_transfer_amount)
+}
+
+/// Shared unstake logic used by both process_unstake and process_complete_unstake.
+/// Handles: reward claiming, pool math updates (sum_stake_exp, total_staked),
+/// reward_debt recalculation, and token transfer (less any unstake fee,
+/// which goes to `fee_destination_info`), preceded by a memo when
+/// `memo_program_info` is given.
+///
+/// Assumes all account validation has been done by the caller.
+#[allow(clippy::too_many_arguments)]
+pub fn execute_unstake<'a>(
+    _program_id: &Pubkey,
+    pool: &mut StakingPool,
+    user_stake: &mut UserStake,
+    pool_info: &AccountInfo<'a>,
+    user_stake_info: &AccountInfo<'a>,
+    token_vault_info: &AccountInfo<'a>,
+    user_token_info: &AccountInfo<'a>,
+    mint_info: &AccountInfo<'a>,
+    user_info: &AccountInfo<'a>,
+    payee: &RewardPayee<'a, '_>,
+    fee_destination_info: Option<&AccountInfo<'a>>,
+    memo_program_info: Option<&AccountInfo<'a>>,
+    amount: u64,
+    current_time: i64,
+    system_program_info: Option<&AccountInfo<'a>>,
+) -> ProgramResult {
+
+    // Settle rewards and pool math now, but defer the SOL transfer until
+    // after the token CPI: the Solana runtime verifies CPI account balances
+    // and the reward payee is not a CPI account
+    let reward_transfer_amount = settle_unstake(pool, user_stake, pool_info, amount, current_time)?;
+
     // Realloc legacy accounts to current size (payer = user)
     UserStake::maybe_realloc(user_stake_info, user_info, system_program_info)?;
 
     // Save states (before CPI — pool data includes pre-updated last_synced_lamports)
-    {
-        let mut pool_data = pool_info.try_borrow_mut_data()?;
-        pool.serialize(&mut &mut pool_data[..])?;
-    }
-    {
-        let mut stake_data = user_stake_info.try_borrow_mut_data()?;
-        user_stake.serialize(&mut &mut stake_data[..])?;
-    }
+    pool.save(pool_info)?;
+    user_stake.save(user_stake_info)?;
 
     // Transfer tokens from vault to user (CPI)
     let mint_data = mint_info.try_borrow_data()?;
@@ -181,8 +256,43 @@ pub fn execute_unstake<'a>(
     let decimals = mint.base.decimals;
     drop(mint_data);
 
-    let pool_seeds = &[POOL_SEED, pool.mint.as_ref(), &[pool.bump]];
+    let pool_id_bytes = pool.pool_id.to_le_bytes();
+    let pool_bump = [pool.bump];
+    let pool_seeds: &[&[u8]] = &pool.signer_seeds(&pool_id_bytes, &pool_bump);
+
+    // Unstake fee: withheld from the user's tokens (capped at MAX_UNSTAKE_FEE_BPS)
+    let fee = match fee_destination_info {
+        Some(fee_destination_info) => {
+            let fee = pool.unstake_fee(amount);
+            if fee > 0 {
+                invoke_signed(
+                    &spl_token_2022::instruction::transfer_checked(
+                        &spl_token_2022::id(),
+                        token_vault_info.key,
+                        mint_info.key,
+                        fee_destination_info.key,
+                        pool_info.key,
+                        &[],
+                        fee,
+                        decimals,
+                    )?,
+                    &[
+                        token_vault_info.clone(),
+                        mint_info.clone(),
+                        fee_destination_info.clone(),
+                        pool_info.clone(),
+                    ],
+                    &[pool_seeds],
+                )?;
+                msg!("Unstake fee: {} tokens", fee);
+            }
+            fee
+        }
+        None => 0,
+    };
 
+    // Token accounts requiring memos get one right before the transfer
+    invoke_memo(memo_program_info, "chiefstaker: unstake")?;
     invoke_signed(
         &spl_token_2022::instruction::transfer_checked(
             &spl_token_2022::id(),
@@ -191,7 +301,7 @@ pub fn execute_unstake<'a>(
             user_token_info.key,
             pool_info.key,
             &[],
-            amount,
+            amount - fee,
             decimals,
         )?,
         &[
@@ -204,10 +314,10 @@ pub fn execute_unstake<'a>(
     )?;
 
     // Transfer SOL rewards AFTER token CPI to avoid CPI balance check failure
-    // (pool_info is a CPI account but user_info is not)
+    // (pool_info is a CPI account but the payee accounts are not)
     if reward_transfer_amount > 0 {
         **pool_info.try_borrow_mut_lamports()? -= reward_transfer_amount;
-        **user_info.try_borrow_mut_lamports()? += reward_transfer_amount;
+        payee.credit(reward_transfer_amount)?;
         msg!("Claimed {} lamports in rewards", reward_transfer_amount);
         emit_reward_payout(pool_info.key, user_info.key, reward_transfer_amount, RewardPayoutType::Unstake);
     }
@@ -217,7 +327,8 @@ pub fn execute_unstake<'a>(
     Ok(())
 }
 
-/// Unstake tokens from the pool (direct unstake when cooldown is 0)
+/// Unstake tokens from the pool (direct unstake when cooldown is 0).
+/// `amount` None (UnstakeAll) unstakes the whole position as of execution.
 ///
 /// Accounts:
 /// 0. `[writable]` Pool account
@@ -227,12 +338,23 @@ pub fn execute_unstake<'a>(
 /// 4. `[]` Token mint
 /// 5. `[writable, signer]` User/owner
 /// 6. `[]` Token 2022 program
+/// 7. `[writable]` Receipt mint (receipt pools only)
+/// 8. `[writable]` User receipt token account (receipt pools only, burned from)
+/// 9. `[writable]` Unstake fee destination (pools with an unstake fee only)
+/// 10. `[writable]` Weight history (positions with history only)
+/// 11. `[]` Payout split, then `[writable]` each of its destinations in
+///     order (positions with a payout split only); otherwise `[writable]`
+///     Reward recipient (positions with a declared recipient only)
+/// 12. `[]` Lock schedule (positions with lock tranches only)
+/// 13. `[signer]` Guardian (positions with a guardian only)
+/// 14. `[]` Memo program (user token accounts requiring memos only)
+/// 15. `[]` (optional) System program (legacy account reallocation)
 pub fn process_unstake(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    amount: u64,
+    amount: Option<u64>,
 ) -> ProgramResult {
-    if amount == 0 {
+    if amount == Some(0) {
         return Err(StakingError::ZeroAmount.into());
     }
 
@@ -257,24 +379,10 @@ pub fn process_unstake(
     }
 
     // Load and validate pool
-    if pool_info.owner != program_id {
-        return Err(StakingError::InvalidAccountOwner.into());
-    }
-    let mut pool = StakingPool::try_from_slice(&pool_info.try_borrow_data()?)?;
-    if !pool.is_initialized() {
-        return Err(StakingError::NotInitialized.into());
-    }
-
-    // Verify pool PDA
-    let (expected_pool, _) = StakingPool::derive_pda(&pool.mint, program_id);
-    if *pool_info.key != expected_pool {
-        return Err(StakingError::InvalidPDA.into());
-    }
+    let mut pool = load_pool(program_id, pool_info)?;
 
-    // Check if pool needs rebasing
-    if pool.get_sum_stake_exp().needs_rebase() {
-        return Err(StakingError::PoolRequiresSync.into());
-    }
+    // Rebase inline if sum_stake_exp is near overflow (saved with the pool below)
+    pool.rebase_if_needed(pool_time(&pool)?)?;
 
     // If pool has a cooldown, reject direct unstake
     if pool.unstake_cooldown_seconds > 0 {
@@ -292,54 +400,64 @@ pub fn process_unstake(
     }
 
     // Load and validate user stake
-    if user_stake_info.owner != program_id {
-        return Err(StakingError::InvalidAccountOwner.into());
-    }
-    let mut user_stake = UserStake::try_from_slice(&user_stake_info.try_borrow_data()?)?;
-    if !user_stake.is_initialized() {
-        return Err(StakingError::NotInitialized.into());
-    }
+    let mut user_stake =
+        load_user_stake(program_id, user_stake_info, pool_info.key, user_info.key)?;
 
-    // Verify ownership
-    if user_stake.owner != *user_info.key {
-        return Err(StakingError::InvalidOwner.into());
-    }
-    if user_stake.pool != *pool_info.key {
-        return Err(StakingError::InvalidPool.into());
-    }
-
-    // Verify user stake PDA
-    let (expected_stake, _) =
-        UserStake::derive_pda(pool_info.key, user_info.key, program_id);
-    if *user_stake_info.key != expected_stake {
-        return Err(StakingError::InvalidPDA.into());
+    // UnstakeAll takes whatever is staked now, not a client-cached amount
+    let amount = amount.unwrap_or(user_stake.amount);
+    if amount == 0 {
+        return Err(StakingError::ZeroAmount.into());
     }
 
     // Check sufficient balance
     if user_stake.amount < amount {
-        return Err(StakingError::InsufficientStakeBalance.into());
+        return Err(StakingError::InsufficientStakeBalance
+            .with_context(ErrorContext::AvailableAmount(user_stake.amount)));
     }
 
+    let clock = Clock::get()?;
+    let current_time = pool_time(&pool)?;
+
     // Block if pending unstake request
-    if user_stake.has_pending_unstake_request() {
+    if user_stake.has_pending_unstake_request(&pool, current_time) {
         return Err(StakingError::PendingUnstakeRequestExists.into());
     }
+    user_stake.clear_expired_unstake_request(&pool, current_time);
 
     // Lazily adjust exp_start_factor if pool has been rebased
     user_stake.sync_to_pool(&pool)?;
 
-    let clock = Clock::get()?;
-    let current_time = clock.unix_timestamp;
-
-    // Check lock duration
-    if pool.lock_duration_seconds > 0 {
-        let last_stake = user_stake.effective_last_stake_time();
-        let elapsed = current_time.saturating_sub(last_stake).max(0) as u64;
-        if elapsed < pool.lock_duration_seconds {
-            return Err(StakingError::StakeLocked.into());
-        }
+    // Block unstaking in the same slot as a stake
+    user_stake.record_action(StakeAction::Unstake, clock.slot)?;
+
+    // Check lock duration (pool lock on recent deposits, and any ExtendLock)
+    user_stake.check_lock(&pool, amount, current_time)?;
+
+    // Receipt pools: burn the matching receipt tokens before releasing stake
+    if let Some(receipt) = next_receipt_accounts(&pool, account_info_iter)? {
+        burn_receipt(&receipt, user_info, amount)?;
+    }
+
+    // Pools with an unstake fee must pass the fee destination
+    let fee_destination_info = next_fee_destination(&pool, account_info_iter)?;
+
+    // Positions with a weight history must pass it
+    let history_info = next_weight_history(&user_stake, account_info_iter)?;
+
+    // Rewards go to the payout split or declared recipient, if any, instead of the owner
+    let payee = next_reward_payee(program_id, &user_stake, user_info, account_info_iter)?;
+
+    // Positions with lock tranches may only unstake their unlocked balance
+    if let Some(schedule_info) = next_lock_schedule(&user_stake, account_info_iter)? {
+        check_unlocked(program_id, schedule_info, pool_info.key, &user_stake, amount, current_time)?;
     }
 
+    // Guarded positions need the guardian to co-sign
+    check_guardian_signature(&user_stake, account_info_iter)?;
+
+    // User token accounts requiring memos must pass the memo program
+    let memo_program_info = next_memo_program(user_token_info, account_info_iter)?;
+
     // Optional trailing system program for legacy account reallocation
     let system_program_info = account_info_iter.next();
 
@@ -354,8 +472,51 @@ pub fn process_unstake(
         user_token_info,
         mint_info,
         user_info,
+        &payee,
+        fee_destination_info,
+        memo_program_info,
         amount,
         current_time,
         system_program_info,
-    )
+    )?;
+
+    if let Some(history_info) = history_info {
+        record_checkpoint(program_id, history_info, pool_info.key, &pool, &user_stake, current_time)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settle_unstake_frozen() {
+        let key = Pubkey::new_unique();
+        let mut pool = StakingPool::new(key, key, key, key, 86_400, 0, 255);
+        pool.total_staked = 1_000;
+        pool.acc_reward_per_weighted_share = WAD;
+        let mut user_stake = UserStake::new(key, key, 1_000, 0, WAD, 255, 0);
+        user_stake.rewards_frozen = true;
+
+        let mut lamports = 0u64;
+        let mut data = vec![];
+        let pool_info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &key, false, 0);
+
+        // Principal leaves, rewards stay owed in the (vested) fragment
+        let paid = settle_unstake(&mut pool, &mut user_stake, &pool_info, 400, 86_400).unwrap();
+        assert_eq!(paid, 0);
+        assert_eq!(user_stake.amount, 600);
+        assert_eq!(pool.total_staked, 600);
+        assert!(user_stake.vesting_rewards_wad / WAD > 600);
+        assert_eq!(user_stake.vested_rewards_wad(86_400), user_stake.vesting_rewards_wad);
+        assert_eq!(pool.total_residual_unpaid, (user_stake.vesting_rewards_wad / WAD) as u64);
+
+        // Nothing is owed twice: the remaining stake starts from the current acc_rps
+        let paid = settle_unstake(&mut pool, &mut user_stake, &pool_info, 600, 86_400).unwrap();
+        assert_eq!(paid, 0);
+        assert_eq!(user_stake.amount, 0);
+        assert_eq!(pool.total_residual_unpaid, (user_stake.vesting_rewards_wad / WAD) as u64);
+    }
 }
@@ -0,0 +1,91 @@
+//! Update the program-wide config account
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::{ErrorContext, StakingError},
+    state::ProgramConfig,
+};
+
+/// Change ProgramConfig settings (config admin only). `None` leaves a
+/// setting unchanged. A non-zero protocol fee needs a fee treasury; pools
+/// pick up fee changes on their next CollectProtocolFees.
+///
+/// Accounts:
+/// 0. `[writable]` Program config (PDA: ["program_config"])
+/// 1. `[signer]` Config admin
+pub fn process_update_program_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    admin: Option<Pubkey>,
+    upgrade_authority: Option<Pubkey>,
+    protocol_fee_bps: Option<u16>,
+    fee_treasury: Option<Pubkey>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let config_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    // Validate admin is signer
+    if !admin_info.is_signer {
+        return Err(StakingError::MissingRequiredSigner.into());
+    }
+
+    // Load and validate config
+    if config_info.owner != program_id {
+        return Err(StakingError::InvalidAccountOwner.into());
+    }
+    let (expected_config, _) = ProgramConfig::derive_pda(program_id);
+    if *config_info.key != expected_config {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    let mut config = ProgramConfig::try_from_slice(&config_info.try_borrow_data()?)?;
+    if !config.is_initialized() {
+        return Err(StakingError::NotInitialized.into());
+    }
+
+    // Verify admin
+    if config.admin != *admin_info.key {
+        return Err(StakingError::InvalidAuthority.into());
+    }
+
+    if let Some(bps) = protocol_fee_bps {
+        if bps > ProgramConfig::MAX_PROTOCOL_FEE_BPS {
+            return Err(StakingError::SettingExceedsMaximum.with_context(
+                ErrorContext::MaximumAmount(ProgramConfig::MAX_PROTOCOL_FEE_BPS as u64),
+            ));
+        }
+        config.protocol_fee_bps = bps;
+    }
+    if let Some(treasury) = fee_treasury {
+        config.fee_treasury = treasury;
+    }
+    if config.protocol_fee_bps > 0 && config.fee_treasury == Pubkey::default() {
+        return Err(StakingError::InvalidFeeTreasury.into());
+    }
+    if let Some(upgrade_authority) = upgrade_authority {
+        config.upgrade_authority = upgrade_authority;
+    }
+    if let Some(admin) = admin {
+        config.admin = admin;
+    }
+
+    config.serialize(&mut &mut config_info.try_borrow_mut_data()?[..])?;
+
+    msg!(
+        "Program config updated: protocol fee {} bps to {}, upgrade authority {}, admin {}",
+        config.protocol_fee_bps,
+        config.fee_treasury,
+        config.upgrade_authority,
+        config.admin,
+    );
+
+    Ok(())
+}
@@ -1,6 +1,5 @@
 //! Update pool settings instruction
 
-use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
@@ -9,26 +8,31 @@ use solana_program::{
 };
 
 use crate::{
-    error::StakingError,
-    state::StakingPool,
+    authority_log::{next_authority_log, record_authority_actions},
+    error::{ErrorContext, StakingError},
+    loaders::load_pool,
+    state::{AuthorityAction, AuthorityLogEntry},
+    time::pool_time,
 };
 
 /// Maximum lock duration: 365 days. Prevents authority from trapping stakers indefinitely.
-const MAX_LOCK_DURATION_SECONDS: u64 = 365 * 24 * 60 * 60;
+pub const MAX_LOCK_DURATION_SECONDS: u64 = 365 * 24 * 60 * 60;
 
 /// Maximum unstake cooldown: 30 days.
-const MAX_UNSTAKE_COOLDOWN_SECONDS: u64 = 30 * 24 * 60 * 60;
+pub const MAX_UNSTAKE_COOLDOWN_SECONDS: u64 = 30 * 24 * 60 * 60;
 
 /// Maximum min_stake_amount: 10^15 base units.
 /// Prevents authority from setting it so high that new staking is effectively blocked.
 /// (10^15 = 1M tokens at 9 decimals, generous for any realistic mint.)
-const MAX_MIN_STAKE_AMOUNT: u64 = 1_000_000_000_000_000;
+pub const MAX_MIN_STAKE_AMOUNT: u64 = 1_000_000_000_000_000;
 
 /// Update pool settings (authority only)
 ///
 /// Accounts:
 /// 0. `[writable]` Pool account
-/// 1. `[signer]` Authority
+/// 1. `[signer]` Authority (`[writable]` on pools with an authority log)
+/// 2. `[]` System program (pools with an authority log only)
+/// 3. `[writable]` Authority log (pools with an authority log only)
 pub fn process_update_pool_settings(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -40,6 +44,7 @@ pub fn process_update_pool_settings(
 
     let pool_info = next_account_info(account_info_iter)?;
     let authority_info = next_account_info(account_info_iter)?;
+    let system_program_info = account_info_iter.next();
 
     // Validate authority is signer
     if !authority_info.is_signer {
@@ -47,19 +52,7 @@ pub fn process_update_pool_settings(
     }
 
     // Load and validate pool
-    if pool_info.owner != program_id {
-        return Err(StakingError::InvalidAccountOwner.into());
-    }
-    let mut pool = StakingPool::try_from_slice(&pool_info.try_borrow_data()?)?;
-    if !pool.is_initialized() {
-        return Err(StakingError::NotInitialized.into());
-    }
-
-    // Verify pool PDA
-    let (expected_pool, _) = StakingPool::derive_pda(&pool.mint, program_id);
-    if *pool_info.key != expected_pool {
-        return Err(StakingError::InvalidPDA.into());
-    }
+    let mut pool = load_pool(program_id, pool_info)?;
 
     // Check authority is not renounced
     if pool.is_authority_renounced() {
@@ -71,32 +64,65 @@ pub fn process_update_pool_settings(
         return Err(StakingError::InvalidAuthority.into());
     }
 
+    // Pools with an authority log must pass it (after the system program)
+    let log_info = next_authority_log(&pool, account_info_iter)?;
+
+    let now = pool_time(&pool)?;
+    let mut changes = Vec::new();
+
     // Apply settings (with caps to prevent authority abuse)
     if let Some(val) = min_stake_amount {
         if val > MAX_MIN_STAKE_AMOUNT {
-            return Err(StakingError::SettingExceedsMaximum.into());
+            return Err(StakingError::SettingExceedsMaximum
+                .with_context(ErrorContext::MaximumAmount(MAX_MIN_STAKE_AMOUNT)));
         }
+        changes.push(AuthorityLogEntry::integer(
+            now,
+            *authority_info.key,
+            AuthorityAction::MinStakeAmount,
+            pool.min_stake_amount,
+            val,
+        ));
         pool.min_stake_amount = val;
         msg!("Updated min_stake_amount to {}", val);
     }
     if let Some(val) = lock_duration_seconds {
         if val > MAX_LOCK_DURATION_SECONDS {
-            return Err(StakingError::SettingExceedsMaximum.into());
+            return Err(StakingError::SettingExceedsMaximum
+                .with_context(ErrorContext::MaximumAmount(MAX_LOCK_DURATION_SECONDS)));
         }
+        changes.push(AuthorityLogEntry::integer(
+            now,
+            *authority_info.key,
+            AuthorityAction::LockDuration,
+            pool.lock_duration_seconds,
+            val,
+        ));
         pool.lock_duration_seconds = val;
         msg!("Updated lock_duration_seconds to {}", val);
     }
     if let Some(val) = unstake_cooldown_seconds {
         if val > MAX_UNSTAKE_COOLDOWN_SECONDS {
-            return Err(StakingError::SettingExceedsMaximum.into());
+            return Err(StakingError::SettingExceedsMaximum
+                .with_context(ErrorContext::MaximumAmount(MAX_UNSTAKE_COOLDOWN_SECONDS)));
         }
+        changes.push(AuthorityLogEntry::integer(
+            now,
+            *authority_info.key,
+            AuthorityAction::UnstakeCooldown,
+            pool.unstake_cooldown_seconds,
+            val,
+        ));
         pool.unstake_cooldown_seconds = val;
         msg!("Updated unstake_cooldown_seconds to {}", val);
     }
 
+    if let Some(log_info) = log_info {
+        record_authority_actions(program_id, log_info, pool_info.key, authority_info, system_program_info, &changes)?;
+    }
+
     // Save pool state
-    let mut pool_data = pool_info.try_borrow_mut_data()?;
-    pool.serialize(&mut &mut pool_data[..])?;
+    pool.save(pool_info)?;
 
     msg!("Pool settings updated");
     Ok(())
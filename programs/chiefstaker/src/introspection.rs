@@ -0,0 +1,319 @@
+//! Transaction introspection via the instructions sysvar
+//!
+//! Used to refuse reward distribution when the same transaction already
+//! staked into the pool, closing atomic "stake, then deposit" bundles on
+//! pools that require the sysvar (stake_guard_required), and to attribute
+//! direct SOL transfers that precede a SyncRewards.
+
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::Instruction,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program,
+    sysvar::{
+        self,
+        instructions::{load_current_index_checked, load_instruction_at_checked},
+    },
+};
+
+use crate::{error::StakingError, state::StakingPool, StakingInstruction};
+
+/// Take the optional trailing instructions sysvar of a distribution from
+/// `iter`. Pools with stake_guard_required must pass it: a missing account,
+/// or any other account in its place, fails with InstructionsSysvarRequired.
+pub fn next_instructions_sysvar<'a, 'b, I>(
+    pool: &StakingPool,
+    iter: &mut I,
+) -> Result<Option<&'b AccountInfo<'a>>, ProgramError>
+where
+    I: Iterator<Item = &'b AccountInfo<'a>>,
+{
+    match iter.next() {
+        Some(info) if *info.key == sysvar::instructions::ID => Ok(Some(info)),
+        _ if pool.stake_guard_required => Err(StakingError::InstructionsSysvarRequired.into()),
+        account => Ok(account),
+    }
+}
+
+/// Whether `ix` is a chiefstaker instruction that grows a position in
+/// `pool`: Stake, StakeOnBehalf, StakeLocked, or MigrateStake into it
+pub fn is_stake_into_pool(program_id: &Pubkey, pool: &Pubkey, ix: &Instruction) -> bool {
+    if ix.program_id != *program_id {
+        return false;
+    }
+    // Any wire format the entrypoint accepts: sighash, compact or legacy
+    let pool_index = match StakingInstruction::unpack(&ix.data) {
+        Ok(StakingInstruction::Stake { .. })
+        | Ok(StakingInstruction::StakeOnBehalf { .. })
+        | Ok(StakingInstruction::StakeLocked { .. }) => 0,
+        // Target pool is account 3
+        Ok(StakingInstruction::MigrateStake { .. }) => 3,
+        _ => return false,
+    };
+    ix.accounts.get(pool_index).map(|meta| meta.pubkey == *pool).unwrap_or(false)
+}
+
+/// Fail if any instruction before the current one stakes into `pool`.
+///
+/// `instructions_sysvar` must be the instructions sysvar account;
+/// anything else is rejected by the sysvar loaders.
+pub fn reject_prior_stake(
+    instructions_sysvar: &AccountInfo,
+    program_id: &Pubkey,
+    pool: &Pubkey,
+) -> ProgramResult {
+    let current_index = load_current_index_checked(instructions_sysvar)?;
+    for index in 0..current_index {
+        let ix = load_instruction_at_checked(index as usize, instructions_sysvar)?;
+        if is_stake_into_pool(program_id, pool, &ix) {
+            msg!("Stake into this pool at instruction {} precedes distribution", index);
+            return Err(StakingError::StakeBeforeDistribution.into());
+        }
+    }
+    Ok(())
+}
+
+/// Source and lamports of `ix` if it is a system program Transfer into `pool`
+pub fn system_transfer_into(pool: &Pubkey, ix: &Instruction) -> Option<(Pubkey, u64)> {
+    if ix.program_id != system_program::ID {
+        return None;
+    }
+    // SystemInstruction::Transfer: u32 LE tag 2, then lamports as u64 LE;
+    // accounts are [from, to]
+    if ix.data.len() != 12 || ix.data[..4] != 2u32.to_le_bytes() {
+        return None;
+    }
+    let lamports = u64::from_le_bytes(ix.data[4..12].try_into().ok()?);
+    match ix.accounts.as_slice() {
+        [from, to, ..] if to.pubkey == *pool => Some((from.pubkey, lamports)),
+        _ => None,
+    }
+}
+
+/// Total lamports sent to `pool` by system transfers before the current
+/// instruction, with the source of the first such transfer. None if there
+/// are none.
+pub fn prior_transfers_into(
+    instructions_sysvar: &AccountInfo,
+    pool: &Pubkey,
+) -> Result<Option<(Pubkey, u64)>, ProgramError> {
+    let current_index = load_current_index_checked(instructions_sysvar)?;
+    let mut found: Option<(Pubkey, u64)> = None;
+    for index in 0..current_index {
+        let ix = load_instruction_at_checked(index as usize, instructions_sysvar)?;
+        if let Some((from, lamports)) = system_transfer_into(pool, &ix) {
+            found = Some(match found {
+                Some((first, total)) => (first, total.saturating_add(lamports)),
+                None => (from, lamports),
+            });
+        }
+    }
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::instruction::AccountMeta;
+
+    fn ix(program_id: Pubkey, pool: Pubkey, data: &StakingInstruction) -> Instruction {
+        Instruction {
+            program_id,
+            accounts: vec![AccountMeta::new(pool, false)],
+            data: borsh::to_vec(data).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_is_stake_into_pool() {
+        let program_id = crate::id();
+        let pool = Pubkey::new_unique();
+        let other_pool = Pubkey::new_unique();
+
+        let stake = StakingInstruction::Stake { amount: 1 };
+        assert!(is_stake_into_pool(&program_id, &pool, &ix(program_id, pool, &stake)));
+
+        // Different pool, different program, or non-stake instruction
+        assert!(!is_stake_into_pool(&program_id, &pool, &ix(program_id, other_pool, &stake)));
+        assert!(!is_stake_into_pool(&program_id, &pool, &ix(Pubkey::new_unique(), pool, &stake)));
+        let claim = StakingInstruction::ClaimRewards;
+        assert!(!is_stake_into_pool(&program_id, &pool, &ix(program_id, pool, &claim)));
+    }
+
+    #[test]
+    fn test_is_stake_into_pool_on_behalf() {
+        let program_id = crate::id();
+        let pool = Pubkey::new_unique();
+
+        let on_behalf = StakingInstruction::StakeOnBehalf { amount: 1 };
+        assert!(is_stake_into_pool(&program_id, &pool, &ix(program_id, pool, &on_behalf)));
+        let elsewhere = ix(program_id, Pubkey::new_unique(), &on_behalf);
+        assert!(!is_stake_into_pool(&program_id, &pool, &elsewhere));
+    }
+
+    #[test]
+    fn test_is_stake_into_pool_locked() {
+        let program_id = crate::id();
+        let pool = Pubkey::new_unique();
+
+        let locked = StakingInstruction::StakeLocked { amount: 1, unlock_time: 1_800_000_000 };
+        assert!(is_stake_into_pool(&program_id, &pool, &ix(program_id, pool, &locked)));
+        let elsewhere = ix(program_id, Pubkey::new_unique(), &locked);
+        assert!(!is_stake_into_pool(&program_id, &pool, &elsewhere));
+    }
+
+    #[test]
+    fn test_is_stake_into_pool_migrate() {
+        let program_id = crate::id();
+        let source = Pubkey::new_unique();
+        let target = Pubkey::new_unique();
+
+        // Source pool, source stake, source vault, then the target pool
+        let migrate = Instruction {
+            program_id,
+            accounts: [source, Pubkey::new_unique(), Pubkey::new_unique(), target]
+                .into_iter()
+                .map(|key| AccountMeta::new(key, false))
+                .collect(),
+            data: StakingInstruction::MigrateStake { target_pool: target }.pack(),
+        };
+        assert!(is_stake_into_pool(&program_id, &target, &migrate));
+
+        // Moving out of a pool doesn't grow a position there
+        assert!(!is_stake_into_pool(&program_id, &source, &migrate));
+
+        // Too few accounts to name a target
+        let mut truncated = migrate.clone();
+        truncated.accounts.truncate(3);
+        assert!(!is_stake_into_pool(&program_id, &target, &truncated));
+    }
+
+    /// Run reject_prior_stake as the last of `instructions`
+    fn reject_prior_stake_in(instructions: &[Instruction], pool: &Pubkey) -> ProgramResult {
+        use solana_program::sysvar::instructions::{
+            construct_instructions_data, store_current_index, BorrowedAccountMeta,
+            BorrowedInstruction,
+        };
+
+        let borrowed: Vec<BorrowedInstruction> = instructions
+            .iter()
+            .map(|ix| BorrowedInstruction {
+                program_id: &ix.program_id,
+                accounts: ix
+                    .accounts
+                    .iter()
+                    .map(|meta| BorrowedAccountMeta {
+                        pubkey: &meta.pubkey,
+                        is_signer: meta.is_signer,
+                        is_writable: meta.is_writable,
+                    })
+                    .collect(),
+                data: &ix.data,
+            })
+            .collect();
+        let mut data = construct_instructions_data(&borrowed);
+        store_current_index(&mut data, instructions.len() as u16 - 1);
+
+        let key = solana_program::sysvar::instructions::ID;
+        let owner = solana_program::sysvar::ID;
+        let mut lamports = 0;
+        let sysvar =
+            AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        reject_prior_stake(&sysvar, &crate::id(), pool)
+    }
+
+    #[test]
+    fn test_reject_prior_stake_any_encoding() {
+        let program_id = crate::id();
+        let pool = Pubkey::new_unique();
+        let stake = StakingInstruction::Stake { amount: 1_000 };
+        let deposit = ix(program_id, pool, &StakingInstruction::DepositRewards { amount: 1 });
+
+        let encodings = [
+            borsh::to_vec(&stake).unwrap(),
+            stake.pack(),
+            stake.pack_compact().unwrap(),
+        ];
+        for data in encodings {
+            let prior = Instruction {
+                program_id,
+                accounts: vec![AccountMeta::new(pool, false)],
+                data,
+            };
+            assert_eq!(
+                reject_prior_stake_in(&[prior, deposit.clone()], &pool),
+                Err(StakingError::StakeBeforeDistribution.into())
+            );
+        }
+
+        // A stake into another pool, or none at all, passes
+        let elsewhere = ix(program_id, Pubkey::new_unique(), &stake);
+        assert_eq!(reject_prior_stake_in(&[elsewhere, deposit.clone()], &pool), Ok(()));
+        assert_eq!(reject_prior_stake_in(&[deposit], &pool), Ok(()));
+    }
+
+    #[test]
+    fn test_next_instructions_sysvar() {
+        let mut pool = crate::state::StakingPool::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            86_400,
+            0,
+            255,
+        );
+        let sysvar_key = sysvar::instructions::ID;
+        let other_key = Pubkey::new_unique();
+        let owner = sysvar::ID;
+        let (mut lamports_a, mut lamports_b) = (0, 0);
+        let (mut data_a, mut data_b) = (vec![], vec![]);
+        let sysvar_info = AccountInfo::new(
+            &sysvar_key, false, false, &mut lamports_a, &mut data_a, &owner, false, 0,
+        );
+        let other_info = AccountInfo::new(
+            &other_key, false, false, &mut lamports_b, &mut data_b, &owner, false, 0,
+        );
+        let required: ProgramError = StakingError::InstructionsSysvarRequired.into();
+
+        // Optional by default: anything (or nothing) is handed through
+        for accounts in [vec![], vec![sysvar_info.clone()], vec![other_info.clone()]] {
+            let next = next_instructions_sysvar(&pool, &mut accounts.iter()).unwrap();
+            assert_eq!(next.map(|info| *info.key), accounts.first().map(|info| *info.key));
+        }
+
+        // Required: only the sysvar itself passes
+        pool.stake_guard_required = true;
+        let accounts = [sysvar_info];
+        let next = next_instructions_sysvar(&pool, &mut accounts.iter()).unwrap();
+        assert_eq!(next.map(|info| *info.key), Some(sysvar_key));
+        assert_eq!(next_instructions_sysvar(&pool, &mut [].iter()).unwrap_err(), required);
+        let accounts = [other_info];
+        assert_eq!(next_instructions_sysvar(&pool, &mut accounts.iter()).unwrap_err(), required);
+    }
+
+    #[test]
+    fn test_system_transfer_into() {
+        let pool = Pubkey::new_unique();
+        let sender = Pubkey::new_unique();
+
+        let transfer = solana_program::system_instruction::transfer(&sender, &pool, 42);
+        assert_eq!(system_transfer_into(&pool, &transfer), Some((sender, 42)));
+
+        // Transfer elsewhere, out of the pool, or another system instruction
+        let elsewhere = solana_program::system_instruction::transfer(&sender, &Pubkey::new_unique(), 42);
+        assert_eq!(system_transfer_into(&pool, &elsewhere), None);
+        let outgoing = solana_program::system_instruction::transfer(&pool, &sender, 42);
+        assert_eq!(system_transfer_into(&pool, &outgoing), None);
+        let assign = solana_program::system_instruction::assign(&pool, &sender);
+        assert_eq!(system_transfer_into(&pool, &assign), None);
+
+        // Same layout under another program id
+        let mut foreign = transfer.clone();
+        foreign.program_id = Pubkey::new_unique();
+        assert_eq!(system_transfer_into(&pool, &foreign), None);
+    }
+}
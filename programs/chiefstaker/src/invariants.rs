@@ -0,0 +1,209 @@
+//! Shadow accounting checks for the `strict-invariants` feature.
+//!
+//! When the feature is compiled in, every successful instruction re-derives
+//! pool aggregates from the accounts it was given and aborts with
+//! InvariantViolation on mismatch. Tests can pass every stake account of a
+//! pool as extra trailing accounts to get the full check. Without the feature
+//! nothing here runs, so mainnet compute costs are unchanged.
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, pubkey::Pubkey,
+};
+use spl_token_2022::extension::StateWithExtensions;
+
+use crate::{
+    error::StakingError,
+    math::{wad_mul, U256, WAD},
+    state::{StakingPool, UserStake, POOL_DISCRIMINATOR, USER_STAKE_DISCRIMINATOR},
+};
+
+/// Check `pool` against the stakes of it that are present (`stakes` must
+/// all belong to the pool) and, if present, its token vault balance.
+///
+/// Always: present stake amounts sum to at most total_staked, and the vault
+/// holds at least total_staked. When the present stakes cover total_staked
+/// (every staker is present): their reward_debt sums to total_reward_debt
+/// exactly, and their rebased contributions to sum_stake_exp within rounding
+/// (2 units per stake plus 1 ppb, as rebases floor the pool and each stake
+/// separately).
+pub fn check_pool(
+    pool: &StakingPool,
+    stakes: &[UserStake],
+    vault_amount: Option<u64>,
+) -> Result<(), StakingError> {
+    let mut total_amount: u128 = 0;
+    let mut total_debt: u128 = 0;
+    let mut total_contribution = U256::from_u128(0);
+
+    for stake in stakes {
+        total_amount = total_amount
+            .checked_add(stake.amount as u128)
+            .ok_or(StakingError::MathOverflow)?;
+        if stake.amount == 0 {
+            // reward_debt holds the unpaid residual, tracked in total_residual_unpaid
+            continue;
+        }
+        total_debt = total_debt
+            .checked_add(stake.reward_debt)
+            .ok_or(StakingError::MathOverflow)?;
+
+        let mut synced = stake.clone();
+        synced.sync_to_pool(pool)?;
+        let contribution = wad_mul(
+            (synced.amount as u128).checked_mul(WAD).ok_or(StakingError::MathOverflow)?,
+            synced.exp_start_factor,
+        )?;
+        total_contribution = total_contribution
+            .checked_add(U256::from_u128(contribution))
+            .ok_or(StakingError::MathOverflow)?;
+    }
+
+    if total_amount > pool.total_staked {
+        msg!("Invariant: stakes hold {} > total_staked {}", total_amount, pool.total_staked);
+        return Err(StakingError::InvariantViolation);
+    }
+    if let Some(vault_amount) = vault_amount {
+        if (vault_amount as u128) < pool.total_staked {
+            msg!("Invariant: vault holds {} < total_staked {}", vault_amount, pool.total_staked);
+            return Err(StakingError::InvariantViolation);
+        }
+    }
+
+    // The rest needs every staker of the pool
+    if total_amount != pool.total_staked {
+        return Ok(());
+    }
+
+    if total_debt != pool.total_reward_debt {
+        msg!(
+            "Invariant: stakes owe {} != total_reward_debt {}",
+            total_debt,
+            pool.total_reward_debt
+        );
+        return Err(StakingError::InvariantViolation);
+    }
+
+    let sum_stake_exp = pool.get_sum_stake_exp();
+    let diff = if sum_stake_exp > total_contribution {
+        sum_stake_exp - total_contribution
+    } else {
+        total_contribution - sum_stake_exp
+    };
+    let tolerance = U256::from_u128(2 * stakes.len() as u128)
+        .checked_add(sum_stake_exp / U256::from_u128(1_000_000_000))
+        .ok_or(StakingError::MathOverflow)?;
+    if diff > tolerance {
+        msg!("Invariant: stakes' sum_stake_exp differs from the pool's beyond rounding");
+        return Err(StakingError::InvariantViolation);
+    }
+
+    Ok(())
+}
+
+/// Run [`check_pool`] for every pool among `accounts`
+pub fn check(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    for (i, pool_info) in accounts.iter().enumerate() {
+        // Skip duplicates of an account already checked
+        if accounts[..i].iter().any(|a| a.key == pool_info.key) {
+            continue;
+        }
+        if !has_discriminator(program_id, pool_info, &POOL_DISCRIMINATOR) {
+            continue;
+        }
+        let pool = StakingPool::try_from_slice(&pool_info.try_borrow_data()?)?;
+        // The invariants are those of the exponential engine
+        if pool.uses_age_buckets() {
+            continue;
+        }
+
+        let mut stakes = Vec::new();
+        let mut vault_amount = None;
+        for (j, info) in accounts.iter().enumerate() {
+            if accounts[..j].iter().any(|a| a.key == info.key) {
+                continue;
+            }
+            if *info.key == pool.token_vault {
+                let data = info.try_borrow_data()?;
+                let vault = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?;
+                vault_amount = Some(vault.base.amount);
+            } else if has_discriminator(program_id, info, &USER_STAKE_DISCRIMINATOR) {
+                let stake = UserStake::try_from_slice(&info.try_borrow_data()?)?;
+                if stake.pool == *pool_info.key {
+                    stakes.push(stake);
+                }
+            }
+        }
+
+        check_pool(&pool, &stakes, vault_amount)?;
+    }
+    Ok(())
+}
+
+fn has_discriminator(program_id: &Pubkey, info: &AccountInfo, discriminator: &[u8; 8]) -> bool {
+    info.owner == program_id
+        && info
+            .try_borrow_data()
+            .map(|data| data.get(..8) == Some(discriminator.as_slice()))
+            .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool_with(stakes: &[UserStake]) -> StakingPool {
+        let mut pool = StakingPool::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::default(),
+            Pubkey::new_unique(),
+            2592000,
+            1_000,
+            255,
+        );
+        let mut sum = U256::from_u128(0);
+        for s in stakes {
+            pool.total_staked += s.amount as u128;
+            pool.total_reward_debt += s.reward_debt;
+            sum = sum
+                .checked_add(U256::from_u128(
+                    wad_mul(s.amount as u128 * WAD, s.exp_start_factor).unwrap(),
+                ))
+                .unwrap();
+        }
+        pool.set_sum_stake_exp(sum);
+        pool
+    }
+
+    #[test]
+    fn test_check_pool() {
+        let pool_key = Pubkey::new_unique();
+        let mut a = UserStake::new(Pubkey::new_unique(), pool_key, 1_000, 1_000, WAD, 255, 1_000);
+        a.reward_debt = 5 * WAD;
+        let b = UserStake::new(Pubkey::new_unique(), pool_key, 3_000, 1_000, 2 * WAD, 255, 1_000);
+        let stakes = [a.clone(), b.clone()];
+        let mut pool = pool_with(&stakes);
+
+        assert_eq!(check_pool(&pool, &stakes, Some(4_000)), Ok(()));
+        // Partial sets only get the bound checks
+        assert_eq!(check_pool(&pool, &stakes[..1], None), Ok(()));
+        assert_eq!(
+            check_pool(&pool, &stakes, Some(3_999)),
+            Err(StakingError::InvariantViolation)
+        );
+
+        pool.total_reward_debt += 1;
+        assert_eq!(check_pool(&pool, &stakes, None), Err(StakingError::InvariantViolation));
+        assert_eq!(check_pool(&pool, &stakes[1..], None), Ok(()));
+        pool.total_reward_debt -= 1;
+
+        let sum = pool.get_sum_stake_exp();
+        pool.set_sum_stake_exp(sum.checked_add(U256::from_u128(WAD)).unwrap());
+        assert_eq!(check_pool(&pool, &stakes, None), Err(StakingError::InvariantViolation));
+        pool.set_sum_stake_exp(sum);
+
+        pool.total_staked -= 1;
+        assert_eq!(check_pool(&pool, &stakes, None), Err(StakingError::InvariantViolation));
+    }
+}
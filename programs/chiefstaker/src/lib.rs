@@ -9,228 +9,49 @@
 //! - At τ: weight ≈ 63% of max
 //! - At 3τ: weight ≈ 95% of max
 
-use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
-    account_info::AccountInfo, entrypoint, entrypoint::ProgramResult, msg,
+    account_info::AccountInfo, entrypoint::ProgramResult, msg,
     program_error::ProgramError, pubkey::Pubkey,
 };
 
+pub mod age_buckets;
+pub mod authority_log;
+pub mod compression;
+pub mod depositors;
+pub mod distribution_history;
 pub mod error;
 pub mod events;
+pub mod guardian;
+pub mod history;
 pub mod instructions;
+pub mod introspection;
+pub mod invariants;
+pub mod loaders;
+pub mod lock;
+pub mod matching;
 pub mod math;
+pub mod memo;
+pub mod oracle;
+pub mod position_nft;
+pub mod rebate;
+pub mod receipt;
+pub mod recipient;
+pub mod session;
+pub mod sponsor;
 pub mod state;
+pub mod time;
+pub mod upgrade;
+pub mod version;
 
 use instructions::*;
 
-// Declare program ID - matches target/deploy/chiefstaker-keypair.json
-solana_program::declare_id!("3Ecf8gyRURyrBtGHS1XAVXyQik5PqgDch4VkxrH4ECcr");
-
-/// Program instructions
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
-pub enum StakingInstruction {
-    /// Initialize a new staking pool for a Token 2022 mint
-    ///
-    /// Accounts:
-    /// 0. `[writable]` Pool account (PDA: ["pool", mint])
-    /// 1. `[]` Token mint (Token 2022)
-    /// 2. `[writable]` Token vault (PDA: ["token_vault", pool])
-    /// 3. `[writable, signer]` Authority/payer
-    /// 4. `[]` System program
-    /// 5. `[]` Token 2022 program
-    /// 6. `[]` Rent sysvar
-    InitializePool {
-        /// Time constant in seconds (e.g., 2592000 for 30 days)
-        tau_seconds: u64,
-    },
-
-    /// Stake tokens into the pool
-    ///
-    /// Accounts:
-    /// 0. `[writable]` Pool account
-    /// 1. `[writable]` User stake account (PDA: ["stake", pool, owner])
-    /// 2. `[writable]` Token vault
-    /// 3. `[writable]` User token account
-    /// 4. `[]` Token mint
-    /// 5. `[writable, signer]` User/owner
-    /// 6. `[]` System program
-    /// 7. `[]` Token 2022 program
-    Stake {
-        /// Amount of tokens to stake
-        amount: u64,
-    },
-
-    /// Unstake tokens from the pool
-    ///
-    /// Accounts:
-    /// 0. `[writable]` Pool account
-    /// 1. `[writable]` User stake account
-    /// 2. `[writable]` Token vault
-    /// 3. `[writable]` User token account
-    /// 4. `[]` Token mint
-    /// 5. `[writable, signer]` User/owner
-    /// 6. `[]` Token 2022 program
-    Unstake {
-        /// Amount of tokens to unstake
-        amount: u64,
-    },
-
-    /// Claim accumulated SOL rewards
-    ///
-    /// Accounts:
-    /// 0. `[writable]` Pool account (holds SOL rewards)
-    /// 1. `[writable]` User stake account
-    /// 2. `[writable, signer]` User/owner
-    ClaimRewards,
-
-    /// Deposit SOL rewards into the pool (permissionless)
-    ///
-    /// Accounts:
-    /// 0. `[writable]` Pool account (receives SOL)
-    /// 1. `[writable, signer]` Depositor
-    /// 2. `[]` System program
-    DepositRewards {
-        /// Amount of lamports to deposit
-        amount: u64,
-    },
-
-    /// Sync/rebase the pool to prevent overflow (permissionless crank)
-    ///
-    /// Accounts:
-    /// 0. `[writable]` Pool account
-    SyncPool,
-
-    /// Sync rewards sent directly to the pool (permissionless crank)
-    /// Use this when SOL is sent directly to the pool PDA (e.g., from pump.fun)
-    ///
-    /// Accounts:
-    /// 0. `[writable]` Pool account
-    SyncRewards,
-
-    /// Update pool settings (authority only)
-    ///
-    /// Accounts:
-    /// 0. `[writable]` Pool account
-    /// 1. `[signer]` Authority
-    UpdatePoolSettings {
-        min_stake_amount: Option<u64>,
-        lock_duration_seconds: Option<u64>,
-        unstake_cooldown_seconds: Option<u64>,
-    },
-
-    /// Transfer pool authority to a new address
-    /// Setting to Pubkey::default() renounces authority (irreversible)
-    ///
-    /// Accounts:
-    /// 0. `[writable]` Pool account
-    /// 1. `[signer]` Current authority
-    TransferAuthority {
-        new_authority: Pubkey,
-    },
-
-    /// Request unstake - starts cooldown period (tokens remain staked)
-    ///
-    /// Accounts:
-    /// 0. `[writable]` Pool account
-    /// 1. `[writable]` User stake account
-    /// 2. `[signer]` User/owner
-    RequestUnstake {
-        amount: u64,
-    },
-
-    /// Complete unstake after cooldown elapsed
-    ///
-    /// Accounts (same as Unstake):
-    /// 0. `[writable]` Pool account
-    /// 1. `[writable]` User stake account
-    /// 2. `[writable]` Token vault
-    /// 3. `[writable]` User token account
-    /// 4. `[]` Token mint
-    /// 5. `[writable, signer]` User/owner
-    /// 6. `[]` Token 2022 program
-    CompleteUnstake,
-
-    /// Cancel a pending unstake request
-    ///
-    /// Accounts:
-    /// 0. `[]` Pool account
-    /// 1. `[writable]` User stake account
-    /// 2. `[signer]` User/owner
-    CancelUnstakeRequest,
-
-    /// Close a zero-balance user stake account to reclaim rent
-    ///
-    /// Accounts:
-    /// 0. `[]` Pool account
-    /// 1. `[writable]` User stake account
-    /// 2. `[writable, signer]` User/owner
-    CloseStakeAccount,
-
-    /// DEPRECATED: Slot 13 reserved for ABI compatibility (was FixTotalRewardDebt).
-    /// Always returns InvalidInstruction.
-    DeprecatedFixTotalRewardDebt {
-        _unused: u128,
-    },
-
-    /// Set (create or update) pool metadata for explorer display (permissionless)
-    ///
-    /// Derives name from Token 2022 mint metadata extension, tags are fixed.
-    /// Creates the metadata PDA if it doesn't exist.
-    ///
-    /// Accounts:
-    /// 0. `[]` Pool account
-    /// 1. `[writable]` Metadata PDA (["metadata", pool])
-    /// 2. `[]` Token mint
-    /// 3. `[writable, signer]` Payer
-    /// 4. `[]` System program
-    SetPoolMetadata,
-
-    /// Take fee ownership: set pool as sole fee recipient and revoke authority (permissionless)
-    ///
-    /// Prerequisite: fee sharing authority must already be transferred to the pool PDA.
-    /// This instruction sets the pool PDA as the sole shareholder (100% / 10000 bps)
-    /// and then revokes the fee sharing authority, making it irreversible.
-    ///
-    /// Accounts:
-    /// 0. `[]` Pool account (PDA: ["pool", mint])
-    /// 1. `[]` Token mint
-    /// 2. `[]` pfee program
-    /// 3. `[]` pfee event authority
-    /// 4. `[]` pump global
-    /// 5. `[writable]` sharing config
-    /// 6. `[]` bonding curve
-    /// 7. `[writable]` pump creator vault
-    /// 8. `[]` system program
-    /// 9. `[]` pump program
-    /// 10. `[]` pump event authority
-    /// 11. `[]` pump AMM program
-    /// 12. `[]` AMM event authority
-    /// 13. `[]` wSOL mint
-    /// 14. `[]` token program
-    /// 15. `[]` associated token program
-    /// 16. `[writable]` coin creator vault authority
-    /// 17. `[writable]` coin creator vault ATA
-    TakeFeeOwnership,
-
-    /// Stake tokens on behalf of another user (beneficiary)
-    ///
-    /// Accounts:
-    /// 0. `[writable]` Pool account
-    /// 1. `[writable]` Beneficiary stake account (PDA: ["stake", pool, beneficiary])
-    /// 2. `[writable]` Token vault
-    /// 3. `[writable]` Staker's token account
-    /// 4. `[]` Token mint
-    /// 5. `[writable, signer]` Staker (payer + token source)
-    /// 6. `[writable]` Beneficiary (receives position + auto-claimed rewards)
-    /// 7. `[]` System program
-    /// 8. `[]` Token 2022 program
-    StakeOnBehalf {
-        amount: u64,
-    },
-}
+// Program ID, instruction enum and error codes live in chiefstaker-interface
+// so other programs can CPI without depending on this crate
+pub use chiefstaker_interface::{check_id, id, instruction::StakingInstruction, ID};
+pub use version::{PROGRAM_FEATURES, PROGRAM_VERSION};
 
 #[cfg(not(feature = "no-entrypoint"))]
-entrypoint!(process_instruction);
+solana_program::entrypoint!(process_instruction);
 
 #[cfg(not(feature = "no-entrypoint"))]
 use solana_security_txt::security_txt;
@@ -256,30 +77,51 @@ pub fn process_instruction(
     }
 
     // Deserialize instruction
-    let instruction = StakingInstruction::try_from_slice(instruction_data)
-        .map_err(|_| ProgramError::InvalidInstructionData)?;
+    let instruction = StakingInstruction::unpack(instruction_data)?;
+
+    // Profiling builds: log the compute budget around the handler
+    #[cfg(feature = "cu-trace")]
+    let cu_at_entry = solana_program::compute_units::sol_remaining_compute_units();
+    #[cfg(feature = "cu-trace")]
+    msg!("CU entry: {} remaining", cu_at_entry);
 
     // Dispatch to appropriate handler
-    match instruction {
+    let result = match instruction {
         StakingInstruction::InitializePool { tau_seconds } => {
             msg!("Instruction: InitializePool (tau={}s)", tau_seconds);
-            process_initialize_pool(program_id, accounts, tau_seconds)
+            process_initialize_pool(program_id, accounts, tau_seconds, 0, None, false)
         }
         StakingInstruction::Stake { amount } => {
             msg!("Instruction: Stake (amount={})", amount);
-            process_stake(program_id, accounts, amount)
+            if age_buckets::is_bucketed_pool(program_id, accounts) {
+                process_stake_bucketed(program_id, accounts, amount)
+            } else {
+                process_stake(program_id, accounts, amount)
+            }
         }
         StakingInstruction::Unstake { amount } => {
             msg!("Instruction: Unstake (amount={})", amount);
-            process_unstake(program_id, accounts, amount)
+            if age_buckets::is_bucketed_pool(program_id, accounts) {
+                process_unstake_bucketed(program_id, accounts, Some(amount))
+            } else {
+                process_unstake(program_id, accounts, Some(amount))
+            }
         }
         StakingInstruction::ClaimRewards => {
             msg!("Instruction: ClaimRewards");
-            process_claim_rewards(program_id, accounts)
+            if age_buckets::is_bucketed_pool(program_id, accounts) {
+                process_claim_bucketed(program_id, accounts)
+            } else {
+                process_claim_rewards(program_id, accounts)
+            }
         }
         StakingInstruction::DepositRewards { amount } => {
             msg!("Instruction: DepositRewards (amount={})", amount);
-            process_deposit_rewards(program_id, accounts, amount)
+            if age_buckets::is_bucketed_pool(program_id, accounts) {
+                process_deposit_bucketed(program_id, accounts, amount)
+            } else {
+                process_deposit_rewards(program_id, accounts, amount)
+            }
         }
         StakingInstruction::SyncPool => {
             msg!("Instruction: SyncPool");
@@ -287,7 +129,11 @@ pub fn process_instruction(
         }
         StakingInstruction::SyncRewards => {
             msg!("Instruction: SyncRewards");
-            process_sync_rewards(program_id, accounts)
+            if age_buckets::is_bucketed_pool(program_id, accounts) {
+                process_sync_bucketed(program_id, accounts)
+            } else {
+                process_sync_rewards(program_id, accounts)
+            }
         }
         StakingInstruction::UpdatePoolSettings {
             min_stake_amount,
@@ -309,7 +155,7 @@ pub fn process_instruction(
         }
         StakingInstruction::RequestUnstake { amount } => {
             msg!("Instruction: RequestUnstake (amount={})", amount);
-            process_request_unstake(program_id, accounts, amount)
+            process_request_unstake(program_id, accounts, Some(amount))
         }
         StakingInstruction::CompleteUnstake => {
             msg!("Instruction: CompleteUnstake");
@@ -339,12 +185,394 @@ pub fn process_instruction(
             msg!("Instruction: StakeOnBehalf (amount={})", amount);
             process_stake_on_behalf(program_id, accounts, amount)
         }
+        StakingInstruction::SetRewardsPaused { paused } => {
+            msg!("Instruction: SetRewardsPaused (paused={})", paused);
+            process_set_rewards_paused(program_id, accounts, paused)
+        }
+        StakingInstruction::SweepDust => {
+            msg!("Instruction: SweepDust");
+            process_sweep_dust(program_id, accounts)
+        }
+        StakingInstruction::GetUserPosition => {
+            msg!("Instruction: GetUserPosition");
+            process_get_user_position(program_id, accounts)
+        }
+        StakingInstruction::CheckSyncNeeded { min_undistributed_lamports } => {
+            msg!("Instruction: CheckSyncNeeded");
+            process_check_sync_needed(program_id, accounts, min_undistributed_lamports)
+        }
+        StakingInstruction::InitializePoolWithId { tau_seconds, pool_id } => {
+            msg!("Instruction: InitializePoolWithId (tau={}s, pool_id={})", tau_seconds, pool_id);
+            process_initialize_pool(program_id, accounts, tau_seconds, pool_id, None, false)
+        }
+        StakingInstruction::InitWeightHistory { capacity } => {
+            msg!("Instruction: InitWeightHistory (capacity={})", capacity);
+            process_init_weight_history(program_id, accounts, capacity)
+        }
+        StakingInstruction::GetAverageWeightedStake { from, to } => {
+            msg!("Instruction: GetAverageWeightedStake ({}..{})", from, to);
+            process_get_average_weighted_stake(program_id, accounts, from, to)
+        }
+        StakingInstruction::SetRewardRecipient { recipient } => {
+            msg!("Instruction: SetRewardRecipient");
+            process_set_reward_recipient(program_id, accounts, recipient)
+        }
+        StakingInstruction::ClaimRewardsWrapped => {
+            msg!("Instruction: ClaimRewardsWrapped");
+            process_claim_rewards_wrapped(program_id, accounts)
+        }
+        StakingInstruction::SetRefundPolicy {
+            refund_address,
+            refund_after_seconds,
+        } => {
+            msg!("Instruction: SetRefundPolicy");
+            process_set_refund_policy(program_id, accounts, refund_address, refund_after_seconds)
+        }
+        StakingInstruction::RefundUndistributed => {
+            msg!("Instruction: RefundUndistributed");
+            process_refund_undistributed(program_id, accounts)
+        }
+        StakingInstruction::SetDistributionThreshold { min_total_staked } => {
+            msg!("Instruction: SetDistributionThreshold");
+            process_set_distribution_threshold(program_id, accounts, min_total_staked)
+        }
+        StakingInstruction::SetUnstakeFee { fee_bps } => {
+            msg!("Instruction: SetUnstakeFee ({} bps)", fee_bps);
+            process_set_unstake_fee(program_id, accounts, fee_bps)
+        }
+        StakingInstruction::GetUserStats => {
+            msg!("Instruction: GetUserStats");
+            process_get_user_stats(program_id, accounts)
+        }
+        StakingInstruction::StakeLocked { amount, unlock_time } => {
+            msg!("Instruction: StakeLocked ({} until {})", amount, unlock_time);
+            process_stake_locked(program_id, accounts, amount, unlock_time)
+        }
+        StakingInstruction::InitAuthorityLog => {
+            msg!("Instruction: InitAuthorityLog");
+            process_init_authority_log(program_id, accounts)
+        }
+        StakingInstruction::PreviewRecoverStranded => {
+            msg!("Instruction: PreviewRecoverStranded");
+            process_preview_recover_stranded(program_id, accounts)
+        }
+        StakingInstruction::DepositRewardsSplit { amount, schedule } => {
+            msg!("Instruction: DepositRewardsSplit ({} in {} shares)", amount, schedule.len());
+            process_deposit_rewards_split(program_id, accounts, amount, schedule)
+        }
+        StakingInstruction::ReleasePendingDistributions => {
+            msg!("Instruction: ReleasePendingDistributions");
+            process_release_pending_distributions(program_id, accounts)
+        }
+        StakingInstruction::AssertPoolHealthy => {
+            msg!("Instruction: AssertPoolHealthy");
+            process_assert_pool_healthy(program_id, accounts)
+        }
+        StakingInstruction::SetUnstakeRequestExpiry { expiry_seconds } => {
+            msg!("Instruction: SetUnstakeRequestExpiry ({}s)", expiry_seconds);
+            process_set_unstake_request_expiry(program_id, accounts, expiry_seconds)
+        }
+        StakingInstruction::SetPayoutSplit { destinations } => {
+            msg!("Instruction: SetPayoutSplit ({} destinations)", destinations.len());
+            process_set_payout_split(program_id, accounts, destinations)
+        }
+        StakingInstruction::MigrateStake { target_pool } => {
+            msg!("Instruction: MigrateStake (to {})", target_pool);
+            process_migrate_stake(program_id, accounts, target_pool)
+        }
+        StakingInstruction::InitProgramConfig { upgrade_authority } => {
+            msg!("Instruction: InitProgramConfig");
+            process_init_program_config(program_id, accounts, upgrade_authority)
+        }
+        StakingInstruction::AssertUpgradeAuthority => {
+            msg!("Instruction: AssertUpgradeAuthority");
+            process_assert_upgrade_authority(program_id, accounts)
+        }
+        StakingInstruction::UpdateProgramConfig {
+            admin,
+            upgrade_authority,
+            protocol_fee_bps,
+            fee_treasury,
+        } => {
+            msg!("Instruction: UpdateProgramConfig");
+            process_update_program_config(
+                program_id,
+                accounts,
+                admin,
+                upgrade_authority,
+                protocol_fee_bps,
+                fee_treasury,
+            )
+        }
+        StakingInstruction::CollectProtocolFees => {
+            msg!("Instruction: CollectProtocolFees");
+            process_collect_protocol_fees(program_id, accounts)
+        }
+        StakingInstruction::RecountRewardDebt { cursor } => {
+            msg!("Instruction: RecountRewardDebt");
+            process_recount_reward_debt(program_id, accounts, cursor)
+        }
+        StakingInstruction::SetRoundingMode { mode } => {
+            msg!("Instruction: SetRoundingMode ({})", mode);
+            process_set_rounding_mode(program_id, accounts, mode)
+        }
+        StakingInstruction::SetDepositorAllowlist { depositors } => {
+            msg!("Instruction: SetDepositorAllowlist ({} depositors)", depositors.len());
+            process_set_depositor_allowlist(program_id, accounts, depositors)
+        }
+        StakingInstruction::ExtendLock { additional_seconds } => {
+            msg!("Instruction: ExtendLock (additional={}s)", additional_seconds);
+            process_extend_lock(program_id, accounts, additional_seconds)
+        }
+        StakingInstruction::SetLockBoost { boost_bps } => {
+            msg!("Instruction: SetLockBoost ({} bps)", boost_bps);
+            process_set_lock_boost(program_id, accounts, boost_bps)
+        }
+        StakingInstruction::SetMaturityBonus { bonus_bps } => {
+            msg!("Instruction: SetMaturityBonus ({} bps)", bonus_bps);
+            process_set_maturity_bonus(program_id, accounts, bonus_bps)
+        }
+        StakingInstruction::ClaimMaturityBonus => {
+            msg!("Instruction: ClaimMaturityBonus");
+            process_claim_maturity_bonus(program_id, accounts)
+        }
+        StakingInstruction::SetGuardian { guardian } => {
+            msg!("Instruction: SetGuardian");
+            process_set_guardian(program_id, accounts, guardian)
+        }
+        StakingInstruction::ClearGuardian => {
+            msg!("Instruction: ClearGuardian");
+            process_clear_guardian(program_id, accounts)
+        }
+        StakingInstruction::SetBootstrapPeriod { period_seconds } => {
+            msg!("Instruction: SetBootstrapPeriod ({}s)", period_seconds);
+            process_set_bootstrap_period(program_id, accounts, period_seconds)
+        }
+        StakingInstruction::SetRewardMatching { match_bps } => {
+            msg!("Instruction: SetRewardMatching ({} bps)", match_bps);
+            process_set_reward_matching(program_id, accounts, match_bps)
+        }
+        StakingInstruction::InitializePoolIfNeeded { tau_seconds, pool_id } => {
+            msg!("Instruction: InitializePoolIfNeeded (tau={}s, pool_id={})", tau_seconds, pool_id);
+            process_initialize_pool_if_needed(program_id, accounts, tau_seconds, pool_id)
+        }
+        StakingInstruction::InitializePoolWithAuthority { tau_seconds, authority } => {
+            msg!("Instruction: InitializePoolWithAuthority (tau={}s)", tau_seconds);
+            process_initialize_pool(program_id, accounts, tau_seconds, 0, Some(authority), false)
+        }
+        StakingInstruction::UnstakeAll => {
+            msg!("Instruction: UnstakeAll");
+            if age_buckets::is_bucketed_pool(program_id, accounts) {
+                process_unstake_bucketed(program_id, accounts, None)
+            } else {
+                process_unstake(program_id, accounts, None)
+            }
+        }
+        StakingInstruction::RequestUnstakeAll => {
+            msg!("Instruction: RequestUnstakeAll");
+            process_request_unstake(program_id, accounts, None)
+        }
+        StakingInstruction::InitCompressionTree { max_depth, max_buffer_size } => {
+            msg!("Instruction: InitCompressionTree (depth={}, buffer={})", max_depth, max_buffer_size);
+            process_init_compression_tree(program_id, accounts, max_depth, max_buffer_size)
+        }
+        StakingInstruction::StakeCompressed => {
+            msg!("Instruction: StakeCompressed");
+            process_stake_compressed(program_id, accounts)
+        }
+        StakingInstruction::DecompressStake { root, leaf_index, position } => {
+            msg!("Instruction: DecompressStake (leaf={})", leaf_index);
+            process_decompress_stake(program_id, accounts, root, leaf_index, &position)
+        }
+        StakingInstruction::SetRentSponsor { enabled } => {
+            msg!("Instruction: SetRentSponsor ({})", enabled);
+            process_set_rent_sponsor(program_id, accounts, enabled)
+        }
+        StakingInstruction::SetMinSyncInterval { interval_seconds } => {
+            msg!("Instruction: SetMinSyncInterval ({}s)", interval_seconds);
+            process_set_min_sync_interval(program_id, accounts, interval_seconds)
+        }
+        StakingInstruction::SkimStakeAccountLamports => {
+            msg!("Instruction: SkimStakeAccountLamports");
+            process_skim_stake_account_lamports(program_id, accounts)
+        }
+        StakingInstruction::SetWeightTiers { thresholds } => {
+            msg!("Instruction: SetWeightTiers ({} thresholds)", thresholds.len());
+            process_set_weight_tiers(program_id, accounts, thresholds)
+        }
+        StakingInstruction::GetWeightTier => {
+            msg!("Instruction: GetWeightTier");
+            process_get_weight_tier(program_id, accounts)
+        }
+        StakingInstruction::SetClaimVesting { vesting_seconds } => {
+            msg!("Instruction: SetClaimVesting ({}s)", vesting_seconds);
+            process_set_claim_vesting(program_id, accounts, vesting_seconds)
+        }
+        StakingInstruction::GetProgramLimits => {
+            msg!("Instruction: GetProgramLimits");
+            process_get_program_limits()
+        }
+        StakingInstruction::GetVersion => {
+            msg!("Instruction: GetVersion");
+            process_get_version()
+        }
+        StakingInstruction::SetPriceOracle => {
+            msg!("Instruction: SetPriceOracle");
+            process_set_price_oracle(program_id, accounts)
+        }
+        StakingInstruction::GetPoolStats => {
+            msg!("Instruction: GetPoolStats");
+            process_get_pool_stats(program_id, accounts)
+        }
+        StakingInstruction::SetClaimRebate { rebate_lamports } => {
+            msg!("Instruction: SetClaimRebate");
+            process_set_claim_rebate(program_id, accounts, rebate_lamports)
+        }
+        StakingInstruction::FundGasRebates { amount } => {
+            msg!("Instruction: FundGasRebates");
+            process_fund_gas_rebates(program_id, accounts, amount)
+        }
+        StakingInstruction::SetClaimBurn { burn_bps } => {
+            msg!("Instruction: SetClaimBurn");
+            process_set_claim_burn(program_id, accounts, burn_bps)
+        }
+        StakingInstruction::TokenizePosition => {
+            msg!("Instruction: TokenizePosition");
+            process_tokenize_position(program_id, accounts)
+        }
+        StakingInstruction::RedeemPosition => {
+            msg!("Instruction: RedeemPosition");
+            process_redeem_position(program_id, accounts)
+        }
+        StakingInstruction::SetResidualsFirst { enabled } => {
+            msg!("Instruction: SetResidualsFirst");
+            process_set_residuals_first(program_id, accounts, enabled)
+        }
+        StakingInstruction::ClaimWithSession { expires_at } => {
+            msg!("Instruction: ClaimWithSession");
+            process_claim_with_session(program_id, accounts, expires_at)
+        }
+        StakingInstruction::SetMinClaimLamports { min_claim_lamports } => {
+            msg!("Instruction: SetMinClaimLamports");
+            process_set_min_claim_lamports(program_id, accounts, min_claim_lamports)
+        }
+        StakingInstruction::InitDistributionHistory => {
+            msg!("Instruction: InitDistributionHistory");
+            process_init_distribution_history(program_id, accounts)
+        }
+        StakingInstruction::AddDistributionHistoryPage => {
+            msg!("Instruction: AddDistributionHistoryPage");
+            process_add_distribution_history_page(program_id, accounts)
+        }
+        StakingInstruction::ClosePoolMetadata => {
+            msg!("Instruction: ClosePoolMetadata");
+            process_close_pool_metadata(program_id, accounts)
+        }
+        StakingInstruction::CloseStakeAccountArchived => {
+            msg!("Instruction: CloseStakeAccountArchived");
+            process_close_stake_account_archived(program_id, accounts)
+        }
+        StakingInstruction::CloseArchived => {
+            msg!("Instruction: CloseArchived");
+            process_close_archived(program_id, accounts)
+        }
+        StakingInstruction::SetDepositorTracking { enabled } => {
+            msg!("Instruction: SetDepositorTracking");
+            process_set_depositor_tracking(program_id, accounts, enabled)
+        }
+        StakingInstruction::SetRebaseThreshold { rebase_threshold_bps } => {
+            msg!("Instruction: SetRebaseThreshold");
+            process_set_rebase_threshold(program_id, accounts, rebase_threshold_bps)
+        }
+        StakingInstruction::ExitPool => {
+            msg!("Instruction: ExitPool");
+            process_exit_pool(program_id, accounts)
+        }
+        StakingInstruction::MigrateVault => {
+            msg!("Instruction: MigrateVault");
+            process_migrate_vault(program_id, accounts)
+        }
+        StakingInstruction::SnapshotVotingWeights { proposal_id } => {
+            msg!("Instruction: SnapshotVotingWeights");
+            process_snapshot_voting_weights(program_id, accounts, proposal_id)
+        }
+        StakingInstruction::SetLockExemptAmount { lock_exempt_amount } => {
+            msg!("Instruction: SetLockExemptAmount");
+            process_set_lock_exempt_amount(program_id, accounts, lock_exempt_amount)
+        }
+        StakingInstruction::ReconcileSyncedLamports => {
+            msg!("Instruction: ReconcileSyncedLamports");
+            process_reconcile_synced_lamports(program_id, accounts)
+        }
+        StakingInstruction::SetStakeFrozen { frozen } => {
+            msg!("Instruction: SetStakeFrozen");
+            process_set_stake_frozen(program_id, accounts, frozen)
+        }
+        StakingInstruction::ClonePoolConfig { source_pool, tau_seconds, pool_id } => {
+            msg!("Instruction: ClonePoolConfig");
+            process_clone_pool_config(program_id, accounts, &source_pool, tau_seconds, pool_id)
+        }
+        StakingInstruction::SetStakeGuardRequired { required } => {
+            msg!("Instruction: SetStakeGuardRequired");
+            process_set_stake_guard_required(program_id, accounts, required)
+        }
+        StakingInstruction::InitializeBucketedPool { pool_id, bucket_seconds, weights_bps } => {
+            msg!(
+                "Instruction: InitializeBucketedPool (pool_id={}, {} buckets of {}s)",
+                pool_id,
+                weights_bps.len(),
+                bucket_seconds
+            );
+            process_initialize_bucketed_pool(
+                program_id,
+                accounts,
+                pool_id,
+                bucket_seconds,
+                weights_bps,
+            )
+        }
+        StakingInstruction::RollAgeBuckets => {
+            msg!("Instruction: RollAgeBuckets");
+            process_roll_age_buckets(program_id, accounts)
+        }
+        StakingInstruction::RenounceAuthorityUnsafe => {
+            msg!("Instruction: RenounceAuthorityUnsafe");
+            process_renounce_authority_unsafe(program_id, accounts)
+        }
+        StakingInstruction::InitializePoolBeforeMint { tau_seconds, pool_id } => {
+            msg!(
+                "Instruction: InitializePoolBeforeMint (tau={}s, pool_id={})",
+                tau_seconds,
+                pool_id
+            );
+            process_initialize_pool(program_id, accounts, tau_seconds, pool_id, None, true)
+        }
+    };
+
+    #[cfg(feature = "cu-trace")]
+    {
+        let cu_at_exit = solana_program::compute_units::sol_remaining_compute_units();
+        msg!(
+            "CU exit: {} remaining, handler consumed {} ({})",
+            cu_at_exit,
+            cu_at_entry.saturating_sub(cu_at_exit),
+            if result.is_ok() { "ok" } else { "failed" }
+        );
+    }
+
+    // Audit builds: re-derive pool aggregates from the accounts present
+    #[cfg(feature = "strict-invariants")]
+    if result.is_ok() {
+        invariants::check(program_id, accounts)?;
     }
+
+    result
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use borsh::BorshDeserialize;
 
     #[test]
     fn test_instruction_serialization() {
@@ -377,4 +605,89 @@ mod tests {
             _ => panic!("Wrong instruction type"),
         }
     }
+
+    #[test]
+    fn test_user_position() {
+        use crate::math::{U256, WAD};
+        use crate::state::{StakingPool, UserStake};
+
+        let tau = 3_600u64;
+        let mut pool = StakingPool::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            tau,
+            0,
+            255,
+        );
+        // Two equal stakers, both staked at base_time
+        pool.total_staked = 2_000;
+        pool.set_sum_stake_exp(U256::from_u128(2_000 * WAD));
+        pool.acc_reward_per_weighted_share = 2 * WAD;
+        pool.lock_duration_seconds = 7_200;
+        pool.unstake_cooldown_seconds = 600;
+
+        let mut user = UserStake::new(Pubkey::new_unique(), Pubkey::new_unique(), 1_000, 0, WAD, 255, 0);
+        user.unstake_request_amount = 400;
+        user.unstake_request_time = tau as i64 - 100;
+
+        let position = user_position(&pool, &mut user, tau as i64).unwrap();
+        let within = |value: u128, expected: u128| value.abs_diff(expected) < expected / 1_000;
+
+        // maturity = 1 - e^-1, projected = 1 - e^-2
+        assert!(within(position.maturity_wad, 632_120_558_828_557_678));
+        assert!(within(position.weighted_stake, 632_120_558_828_557_678 * 1_000));
+        assert!(within(position.projected_weighted_stake, 864_664_716_763_387_308 * 1_000));
+        assert!(within(position.pool_share_wad, WAD / 2));
+        // pending = weighted * acc_rps = ~1264 lamports
+        assert!((1_263..=1_265).contains(&position.pending_rewards));
+        assert_eq!(position.lock_remaining_seconds, 3_600);
+        assert_eq!(position.unstake_request_amount, 400);
+        assert_eq!(position.cooldown_remaining_seconds, 500);
+
+        // Residual rewards after a full unstake
+        let mut closed = UserStake::new(Pubkey::new_unique(), Pubkey::new_unique(), 0, 0, 0, 255, 0);
+        closed.reward_debt = 42 * WAD;
+        let position = user_position(&pool, &mut closed, tau as i64).unwrap();
+        assert_eq!(position.pending_rewards, 42);
+        assert_eq!(position.weighted_stake, 0);
+        assert_eq!(position.lock_remaining_seconds, 0);
+    }
+
+    #[test]
+    fn test_sync_flags() {
+        use crate::math::{REBASE_THRESHOLD, U256};
+        use crate::state::StakingPool;
+
+        let tau = 1_000u64;
+        let mut pool = StakingPool::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            tau,
+            0,
+            255,
+        );
+        assert_eq!(sync_flags(&pool, 1_000, 0, 0).unwrap(), 0);
+
+        // 37.8 tau is the warning threshold
+        assert_eq!(sync_flags(&pool, 37_800, 0, 0).unwrap(), 0);
+        assert_eq!(sync_flags(&pool, 37_801, 0, 0).unwrap(), SYNC_FLAG_RATIO_NEAR_LIMIT);
+
+        assert_eq!(sync_flags(&pool, 1_000, 500, 500).unwrap(), 0);
+        assert_eq!(sync_flags(&pool, 1_000, 501, 500).unwrap(), SYNC_FLAG_UNDISTRIBUTED);
+
+        // A configured rebase threshold replaces it
+        pool.rebase_threshold_bps = 200_000;
+        assert_eq!(sync_flags(&pool, 20_000, 0, 0).unwrap(), 0);
+        assert_eq!(sync_flags(&pool, 20_001, 0, 0).unwrap(), SYNC_FLAG_RATIO_NEAR_LIMIT);
+
+        pool.set_sum_stake_exp(REBASE_THRESHOLD.checked_add(U256::from_u128(1)).unwrap());
+        assert_eq!(
+            sync_flags(&pool, 40_000, 1, 0).unwrap(),
+            SYNC_FLAG_NEEDS_REBASE | SYNC_FLAG_RATIO_NEAR_LIMIT | SYNC_FLAG_UNDISTRIBUTED
+        );
+    }
 }
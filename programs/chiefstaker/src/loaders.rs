@@ -0,0 +1,227 @@
+//! Typed account loaders: owner, discriminator and PDA checks in one call
+
+use borsh::BorshDeserialize;
+use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{
+    error::StakingError,
+    state::{StakingPool, UserStake},
+};
+
+/// Load the pool at `pool_info`: owned by the program, initialized, at the
+/// PDA of its mint and pool_id, and weighted by the exponential. Pools of
+/// the AgeBuckets engine fail with UnsupportedWeightEngine: they only
+/// support the handlers that load them with [`load_bucketed_pool`].
+pub fn load_pool(program_id: &Pubkey, pool_info: &AccountInfo) -> Result<StakingPool, ProgramError> {
+    let pool = load_pool_any_engine(program_id, pool_info)?;
+    if pool.uses_age_buckets() {
+        return Err(StakingError::UnsupportedWeightEngine.into());
+    }
+    Ok(pool)
+}
+
+/// Load the pool at `pool_info` like [`load_pool`], requiring the
+/// AgeBuckets engine instead
+pub fn load_bucketed_pool(
+    program_id: &Pubkey,
+    pool_info: &AccountInfo,
+) -> Result<StakingPool, ProgramError> {
+    let pool = load_pool_any_engine(program_id, pool_info)?;
+    if !pool.uses_age_buckets() {
+        return Err(StakingError::UnsupportedWeightEngine.into());
+    }
+    Ok(pool)
+}
+
+/// Load the pool at `pool_info` like [`load_pool`], whatever its weight
+/// engine, for handlers that don't touch weights or rewards per share
+pub fn load_pool_any_engine(
+    program_id: &Pubkey,
+    pool_info: &AccountInfo,
+) -> Result<StakingPool, ProgramError> {
+    if pool_info.owner != program_id {
+        return Err(StakingError::InvalidAccountOwner.into());
+    }
+    let pool = StakingPool::try_from_slice(&pool_info.try_borrow_data()?)?;
+    if !pool.is_initialized() {
+        return Err(StakingError::NotInitialized.into());
+    }
+
+    let (expected_pool, _) = StakingPool::derive_pda_with_id(&pool.mint, pool.pool_id, program_id);
+    if *pool_info.key != expected_pool {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    Ok(pool)
+}
+
+/// Load `owner`'s stake in the pool at `pool_key`: owned by the program,
+/// initialized, held (untokenized) by `owner`, in that pool, and at its
+/// ["stake", pool, owner] PDA.
+pub fn load_user_stake(
+    program_id: &Pubkey,
+    user_stake_info: &AccountInfo,
+    pool_key: &Pubkey,
+    owner: &Pubkey,
+) -> Result<UserStake, ProgramError> {
+    load_stake(program_id, user_stake_info, pool_key, Some(owner))
+}
+
+/// Load any stake in the pool at `pool_key`, whoever owns it: owned by the
+/// program, initialized, in that pool, and at the PDA of its owner. For
+/// cranks and views that act on other owners' positions.
+pub fn load_pool_stake(
+    program_id: &Pubkey,
+    user_stake_info: &AccountInfo,
+    pool_key: &Pubkey,
+) -> Result<UserStake, ProgramError> {
+    load_stake(program_id, user_stake_info, pool_key, None)
+}
+
+fn load_stake(
+    program_id: &Pubkey,
+    user_stake_info: &AccountInfo,
+    pool_key: &Pubkey,
+    owner: Option<&Pubkey>,
+) -> Result<UserStake, ProgramError> {
+    if user_stake_info.owner != program_id {
+        return Err(StakingError::InvalidAccountOwner.into());
+    }
+    let user_stake = UserStake::try_from_slice(&user_stake_info.try_borrow_data()?)?;
+    if !user_stake.is_initialized() {
+        return Err(StakingError::NotInitialized.into());
+    }
+    if let Some(owner) = owner {
+        user_stake.check_owner(owner)?;
+    }
+    if user_stake.pool != *pool_key {
+        return Err(StakingError::InvalidPool.into());
+    }
+
+    let (expected_stake, _) = UserStake::derive_pda(pool_key, &user_stake.owner, program_id);
+    if *user_stake_info.key != expected_stake {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    Ok(user_stake)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::WAD;
+    use crate::state::WeightEngine;
+
+    #[test]
+    fn test_loaders() {
+        let program_id = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let (pool_key, pool_bump) = StakingPool::derive_pda_with_id(&mint, 0, &program_id);
+        let (stake_key, stake_bump) = UserStake::derive_pda(&pool_key, &owner, &program_id);
+
+        let pool = StakingPool::new(
+            mint,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            86_400,
+            0,
+            pool_bump,
+        );
+        let mut pool_lamports = 0u64;
+        let mut pool_data = borsh::to_vec(&pool).unwrap();
+        let pool_info = AccountInfo::new(
+            &pool_key, false, true, &mut pool_lamports, &mut pool_data, &program_id, false, 0,
+        );
+        assert_eq!(load_pool(&program_id, &pool_info).unwrap().mint, mint);
+
+        let other = Pubkey::new_unique();
+        assert_eq!(
+            load_pool(&other, &pool_info).unwrap_err(),
+            StakingError::InvalidAccountOwner.into()
+        );
+
+        let stake = UserStake::new(owner, pool_key, 100, 0, WAD, stake_bump, 0);
+        let mut stake_lamports = 0u64;
+        let mut stake_data = borsh::to_vec(&stake).unwrap();
+        let stake_info = AccountInfo::new(
+            &stake_key, false, true, &mut stake_lamports, &mut stake_data, &program_id, false, 0,
+        );
+        assert_eq!(
+            load_user_stake(&program_id, &stake_info, &pool_key, &owner).unwrap().amount,
+            100
+        );
+        assert_eq!(load_pool_stake(&program_id, &stake_info, &pool_key).unwrap().owner, owner);
+        assert_eq!(
+            load_user_stake(&program_id, &stake_info, &pool_key, &other).unwrap_err(),
+            StakingError::InvalidOwner.into()
+        );
+        assert_eq!(
+            load_pool_stake(&program_id, &stake_info, &other).unwrap_err(),
+            StakingError::InvalidPool.into()
+        );
+
+        // A stake at someone else's address is rejected even if its fields match
+        let mut misplaced_lamports = 0u64;
+        let mut misplaced_data = borsh::to_vec(&stake).unwrap();
+        let misplaced_info = AccountInfo::new(
+            &other, false, true, &mut misplaced_lamports, &mut misplaced_data, &program_id, false,
+            0,
+        );
+        assert_eq!(
+            load_pool_stake(&program_id, &misplaced_info, &pool_key).unwrap_err(),
+            StakingError::InvalidPDA.into()
+        );
+
+        // A pool at the wrong address is rejected the same way
+        let mut wrong_lamports = 0u64;
+        let mut wrong_data = borsh::to_vec(&pool).unwrap();
+        let wrong_info = AccountInfo::new(
+            &other, false, true, &mut wrong_lamports, &mut wrong_data, &program_id, false, 0,
+        );
+        assert_eq!(
+            load_pool(&program_id, &wrong_info).unwrap_err(),
+            StakingError::InvalidPDA.into()
+        );
+    }
+
+    #[test]
+    fn test_pool_engines() {
+        let program_id = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let (pool_key, pool_bump) = StakingPool::derive_pda_with_id(&mint, 0, &program_id);
+        let mut pool = StakingPool::new(
+            mint,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            86_400,
+            0,
+            pool_bump,
+        );
+        pool.weight_engine = WeightEngine::AgeBuckets as u8;
+        let mut lamports = 0u64;
+        let mut data = borsh::to_vec(&pool).unwrap();
+        let pool_info = AccountInfo::new(
+            &pool_key, false, true, &mut lamports, &mut data, &program_id, false, 0,
+        );
+
+        // Bucketed pools only load where the handler supports them
+        assert_eq!(
+            load_pool(&program_id, &pool_info).unwrap_err(),
+            StakingError::UnsupportedWeightEngine.into()
+        );
+        assert!(load_bucketed_pool(&program_id, &pool_info).is_ok());
+        assert!(load_pool_any_engine(&program_id, &pool_info).is_ok());
+        let accounts = std::slice::from_ref(&pool_info);
+        assert!(crate::age_buckets::is_bucketed_pool(&program_id, accounts));
+
+        pool_info.try_borrow_mut_data().unwrap()[StakingPool::WEIGHT_ENGINE_OFFSET] =
+            WeightEngine::Exponential as u8;
+        assert!(load_pool(&program_id, &pool_info).is_ok());
+        assert_eq!(
+            load_bucketed_pool(&program_id, &pool_info).unwrap_err(),
+            StakingError::UnsupportedWeightEngine.into()
+        );
+        assert!(!crate::age_buckets::is_bucketed_pool(&program_id, &[pool_info]));
+    }
+}
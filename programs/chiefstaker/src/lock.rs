@@ -0,0 +1,164 @@
+//! Lock tranches for positions with a UserLockSchedule account
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    error::{ErrorContext, StakingError},
+    state::{
+        LockTranche, UserLockSchedule, UserStake, LOCK_SCHEDULE_DISCRIMINATOR, LOCK_SCHEDULE_SEED,
+    },
+};
+
+/// Take the UserLockSchedule account from `iter` if the position has one.
+/// Positions without lock tranches consume nothing.
+pub fn next_lock_schedule<'a, 'b, I>(
+    user_stake: &UserStake,
+    iter: &mut I,
+) -> Result<Option<&'b AccountInfo<'a>>, ProgramError>
+where
+    I: Iterator<Item = &'b AccountInfo<'a>>,
+{
+    if !user_stake.has_lock_schedule {
+        return Ok(None);
+    }
+    next_account_info(iter).map(Some)
+}
+
+/// Load `schedule_info` as the lock schedule of `owner` in `pool_key`
+fn load_schedule(
+    program_id: &Pubkey,
+    schedule_info: &AccountInfo,
+    pool_key: &Pubkey,
+    owner: &Pubkey,
+) -> Result<UserLockSchedule, ProgramError> {
+    if schedule_info.owner != program_id {
+        return Err(StakingError::InvalidAccountOwner.into());
+    }
+    let (expected_schedule, _) = UserLockSchedule::derive_pda(pool_key, owner, program_id);
+    if *schedule_info.key != expected_schedule {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    let data = schedule_info.try_borrow_data()?;
+    let header = data
+        .get(..UserLockSchedule::HEADER_LEN)
+        .ok_or(StakingError::AccountDataTooSmall)?;
+    let schedule = UserLockSchedule::try_from_slice(header)?;
+    if !schedule.is_initialized() {
+        return Err(StakingError::NotInitialized.into());
+    }
+    Ok(schedule)
+}
+
+/// Reject taking `amount` out of the position if it would dip into tokens
+/// still held by lock tranches at `current_time`
+pub fn check_unlocked(
+    program_id: &Pubkey,
+    schedule_info: &AccountInfo,
+    pool_key: &Pubkey,
+    user_stake: &UserStake,
+    amount: u64,
+    current_time: i64,
+) -> ProgramResult {
+    let schedule = load_schedule(program_id, schedule_info, pool_key, &user_stake.owner)?;
+    let locked = schedule.locked_amount(&schedule_info.try_borrow_data()?, current_time)?;
+    let unlocked = user_stake.amount.saturating_sub(locked);
+    if amount > unlocked {
+        return Err(StakingError::InsufficientUnlockedStake
+            .with_context(ErrorContext::AvailableAmount(unlocked)));
+    }
+    Ok(())
+}
+
+/// Append `tranche` to the lock schedule of `owner`, creating the account or
+/// growing it as needed (payer funds the rent). Unlocked tranches are pruned.
+#[allow(clippy::too_many_arguments)]
+pub fn add_lock_tranche<'a>(
+    program_id: &Pubkey,
+    schedule_info: &AccountInfo<'a>,
+    pool_key: &Pubkey,
+    owner: &Pubkey,
+    payer_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    tranche: LockTranche,
+    current_time: i64,
+) -> ProgramResult {
+    let rent = Rent::get()?;
+
+    let (mut schedule, existing) = if schedule_info.data_is_empty() {
+        let (expected_schedule, schedule_bump) =
+            UserLockSchedule::derive_pda(pool_key, owner, program_id);
+        if *schedule_info.key != expected_schedule {
+            return Err(StakingError::InvalidPDA.into());
+        }
+
+        let space = UserLockSchedule::space(1);
+        let schedule_seeds = &[
+            LOCK_SCHEDULE_SEED,
+            pool_key.as_ref(),
+            owner.as_ref(),
+            &[schedule_bump],
+        ];
+        invoke_signed(
+            &system_instruction::create_account(
+                payer_info.key,
+                schedule_info.key,
+                rent.minimum_balance(space),
+                space as u64,
+                program_id,
+            ),
+            &[
+                payer_info.clone(),
+                schedule_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[schedule_seeds],
+        )?;
+
+        let schedule = UserLockSchedule {
+            discriminator: LOCK_SCHEDULE_DISCRIMINATOR,
+            pool: *pool_key,
+            owner: *owner,
+            count: 0,
+            bump: schedule_bump,
+        };
+        (schedule, Vec::new())
+    } else {
+        let schedule = load_schedule(program_id, schedule_info, pool_key, owner)?;
+        let existing = schedule.tranches(&schedule_info.try_borrow_data()?)?;
+        (schedule, existing)
+    };
+
+    let tranches = UserLockSchedule::with_tranche(&existing, tranche, current_time)
+        .map_err(|e| e.with_context(ErrorContext::MaximumAmount(UserLockSchedule::MAX_TRANCHES as u64)))?;
+
+    // Grow the account if the pruned schedule still doesn't fit
+    let space = UserLockSchedule::space(tranches.len());
+    if schedule_info.data_len() < space {
+        let rent_delta = rent
+            .minimum_balance(space)
+            .saturating_sub(schedule_info.lamports());
+        if rent_delta > 0 {
+            invoke(
+                &system_instruction::transfer(payer_info.key, schedule_info.key, rent_delta),
+                &[
+                    payer_info.clone(),
+                    schedule_info.clone(),
+                    system_program_info.clone(),
+                ],
+            )?;
+        }
+        schedule_info.realloc(space, false)?;
+    }
+
+    schedule.write(&mut schedule_info.try_borrow_mut_data()?, &tranches)
+}
@@ -0,0 +1,69 @@
+//! Reward matching for pools with a MatchingVault account
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    error::StakingError,
+    state::{MatchingVault, StakingPool},
+};
+
+/// Take the MatchingVault account from `iter` if the pool matches rewards
+/// and validate it. Pools without matching consume nothing.
+pub fn next_matching_vault<'a: 'b, 'b, I>(
+    program_id: &Pubkey,
+    pool_key: &Pubkey,
+    pool: &StakingPool,
+    iter: &mut I,
+) -> Result<Option<&'b AccountInfo<'a>>, ProgramError>
+where
+    I: Iterator<Item = &'b AccountInfo<'a>>,
+{
+    if !pool.has_matching_vault {
+        return Ok(None);
+    }
+    let vault_info = next_account_info(iter)?;
+    if vault_info.owner != program_id {
+        return Err(StakingError::InvalidAccountOwner.into());
+    }
+    let (expected_vault, _) = MatchingVault::derive_pda(pool_key, program_id);
+    if *vault_info.key != expected_vault {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    let vault = MatchingVault::try_from_slice(&vault_info.try_borrow_data()?)?;
+    if !vault.is_initialized() {
+        return Err(StakingError::NotInitialized.into());
+    }
+    Ok(Some(vault_info))
+}
+
+/// Move the match for distributing `lamports` from the vault into the pool
+/// and return it. An empty vault matches nothing rather than failing, so
+/// distribution never depends on the authority keeping it funded.
+pub fn draw_match(
+    vault_info: &AccountInfo,
+    pool_info: &AccountInfo,
+    lamports: u64,
+) -> Result<u64, ProgramError> {
+    let mut vault = MatchingVault::try_from_slice(&vault_info.try_borrow_data()?)?;
+    let available = vault_info
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(MatchingVault::LEN));
+    let matched = vault.match_for(lamports, available);
+    if matched == 0 {
+        return Ok(0);
+    }
+
+    **vault_info.try_borrow_mut_lamports()? -= matched;
+    **pool_info.try_borrow_mut_lamports()? += matched;
+
+    vault.total_matched = vault.total_matched.saturating_add(matched);
+    vault.serialize(&mut &mut vault_info.try_borrow_mut_data()?[..])?;
+    Ok(matched)
+}
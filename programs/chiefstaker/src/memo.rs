@@ -0,0 +1,137 @@
+//! Memos for token payouts into accounts with the MemoTransfer extension
+
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    program_error::ProgramError,
+};
+use spl_token_2022::{
+    extension::{memo_transfer::memo_required, StateWithExtensions},
+    state::Account,
+};
+
+use crate::error::StakingError;
+
+/// Take the memo program from `iter` if `destination_info` is a token
+/// account that requires incoming transfer memos. Other destinations
+/// consume nothing.
+pub fn next_memo_program<'a, 'b, I>(
+    destination_info: &AccountInfo,
+    iter: &mut I,
+) -> Result<Option<&'b AccountInfo<'a>>, ProgramError>
+where
+    I: Iterator<Item = &'b AccountInfo<'a>>,
+{
+    let requires_memo = {
+        let data = destination_info.try_borrow_data()?;
+        let account = StateWithExtensions::<Account>::unpack(&data)?;
+        memo_required(&account)
+    };
+    if !requires_memo {
+        return Ok(None);
+    }
+    let memo_program_info = iter.next().ok_or_else(|| {
+        msg!("Token account {} requires memos; pass the memo program", destination_info.key);
+        ProgramError::from(StakingError::MemoProgramRequired)
+    })?;
+    if *memo_program_info.key != spl_memo::id() {
+        return Err(StakingError::MemoProgramRequired.into());
+    }
+    Ok(Some(memo_program_info))
+}
+
+/// Log `memo` through the memo program when one was taken for the
+/// destination. Must be the CPI right before the token transfer, which
+/// Token 2022 checks for a memo as its previous sibling instruction.
+pub fn invoke_memo(memo_program_info: Option<&AccountInfo>, memo: &str) -> ProgramResult {
+    if let Some(memo_program_info) = memo_program_info {
+        invoke(
+            &spl_memo::build_memo(memo.as_bytes(), &[]),
+            std::slice::from_ref(memo_program_info),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::{program_option::COption, pubkey::Pubkey};
+    use spl_token_2022::{
+        extension::{
+            memo_transfer::MemoTransfer, BaseStateWithExtensionsMut, ExtensionType,
+            StateWithExtensionsMut,
+        },
+        state::AccountState,
+    };
+
+    /// A Token 2022 account, with the MemoTransfer extension if `memos` is set
+    fn token_account(memos: Option<bool>) -> Vec<u8> {
+        let extensions: &[ExtensionType] =
+            if memos.is_some() { &[ExtensionType::MemoTransfer] } else { &[] };
+        let len = ExtensionType::try_calculate_account_len::<Account>(extensions).unwrap();
+        let mut data = vec![0u8; len];
+        let mut state = StateWithExtensionsMut::<Account>::unpack_uninitialized(&mut data).unwrap();
+        state.base = Account {
+            mint: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            amount: 0,
+            delegate: COption::None,
+            state: AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        };
+        state.pack_base();
+        if let Some(required) = memos {
+            let extension = state.init_extension::<MemoTransfer>(true).unwrap();
+            extension.require_incoming_transfer_memos = required.into();
+        }
+        state.init_account_type().unwrap();
+        data
+    }
+
+    #[test]
+    fn test_next_memo_program() {
+        let key = Pubkey::new_unique();
+        let owner = spl_token_2022::id();
+        let memo_id = spl_memo::id();
+        let system_id = solana_program::system_program::id();
+        let (mut a, mut b) = (0u64, 0u64);
+        let mut memo_data = vec![];
+        let mut system_data = vec![];
+        let memo_info =
+            AccountInfo::new(&memo_id, false, false, &mut a, &mut memo_data, &key, true, 0);
+        let system_info =
+            AccountInfo::new(&system_id, false, false, &mut b, &mut system_data, &key, true, 0);
+
+        for (memos, requires_memo) in [(None, false), (Some(false), false), (Some(true), true)] {
+            let mut lamports = 0u64;
+            let mut data = token_account(memos);
+            let destination =
+                AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+
+            // Nothing is consumed unless the account requires memos
+            let accounts = [memo_info.clone()];
+            let mut iter = accounts.iter();
+            let taken = next_memo_program(&destination, &mut iter).unwrap();
+            assert_eq!(taken.is_some(), requires_memo);
+            assert_eq!(iter.len(), if requires_memo { 0 } else { 1 });
+
+            if requires_memo {
+                let missing: [AccountInfo; 0] = [];
+                assert_eq!(
+                    next_memo_program(&destination, &mut missing.iter()).unwrap_err(),
+                    StakingError::MemoProgramRequired.into()
+                );
+                let wrong = [system_info.clone()];
+                assert_eq!(
+                    next_memo_program(&destination, &mut wrong.iter()).unwrap_err(),
+                    StakingError::MemoProgramRequired.into()
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,238 @@
+//! Price oracle accounts for USD-denominated pool statistics
+//!
+//! Two formats are read, by hand, to avoid pulling the oracle SDKs into
+//! the program: Pyth `PriceUpdateV2` accounts (pull oracle receiver, fully
+//! verified updates only) and Switchboard On-Demand `PullFeedAccountData`
+//! feeds. The owner program selects the format. Prices are normalized to
+//! WAD-scaled USD per whole token.
+
+use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey, pubkey::Pubkey};
+
+use crate::{
+    error::StakingError,
+    math::{U256, WAD},
+};
+
+/// Pyth pull oracle receiver program (owner of PriceUpdateV2 accounts)
+pub const PYTH_RECEIVER_ID: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+
+/// Switchboard On-Demand program (mainnet)
+pub const SWITCHBOARD_ON_DEMAND_ID: Pubkey = pubkey!("SBondMDrcV3K4kxZR1HNVT7osZxAHVHgYXL5Ze1oMUv");
+
+/// Switchboard On-Demand program (devnet)
+pub const SWITCHBOARD_ON_DEMAND_DEVNET_ID: Pubkey =
+    pubkey!("Aio4gaXjXzJNVLtzwtNVmSqGKpANtXhybbkhtAC94ji2");
+
+/// sha256("account:PriceUpdateV2")[..8]
+const PRICE_UPDATE_V2_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+/// sha256("account:PullFeedAccountData")[..8]
+const PULL_FEED_DISCRIMINATOR: [u8; 8] = [196, 27, 108, 196, 10, 215, 219, 40];
+
+/// PriceUpdateV2: discriminator + write authority, then the verification
+/// level (Borsh enum: 0 = Partial { num_signatures: u8 }, 1 = Full)
+const PYTH_VERIFICATION_OFFSET: usize = 40;
+const PYTH_VERIFICATION_FULL: u8 = 1;
+
+/// PriceFeedMessage after a Full verification level: feed id (32), then
+/// price (i64), conf (u64), exponent (i32), publish_time (i64)
+const PYTH_PRICE_OFFSET: usize = 41 + 32;
+const PYTH_EXPONENT_OFFSET: usize = PYTH_PRICE_OFFSET + 16;
+const PYTH_PUBLISH_TIME_OFFSET: usize = PYTH_EXPONENT_OFFSET + 4;
+
+/// PullFeedAccountData: discriminator + 32 submissions (64 bytes each) +
+/// authority, queue, feed_hash + initialized_at, permissions, max_variance
+/// + min_responses (u32) + name (32) + 4 bytes, then last_update_timestamp
+const SWITCHBOARD_TIMESTAMP_OFFSET: usize = 2216;
+
+/// CurrentResult.value (i128, 18 decimals), after lut_slot and 32 reserved
+const SWITCHBOARD_VALUE_OFFSET: usize = 2264;
+
+/// Lamports per SOL
+const LAMPORTS_PER_SOL: u128 = 1_000_000_000;
+
+/// A normalized oracle price
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OraclePrice {
+    /// USD per whole token, WAD-scaled
+    pub price_wad: u128,
+    /// Unix timestamp of the price
+    pub publish_time: i64,
+}
+
+/// Check that `owner` is an oracle program whose accounts can be read
+pub fn is_oracle_program(owner: &Pubkey) -> bool {
+    *owner == PYTH_RECEIVER_ID
+        || *owner == SWITCHBOARD_ON_DEMAND_ID
+        || *owner == SWITCHBOARD_ON_DEMAND_DEVNET_ID
+}
+
+/// Read the price of an oracle account, in the format of its owner program
+pub fn read_price(oracle_info: &AccountInfo) -> Result<OraclePrice, ProgramError> {
+    let data = oracle_info.try_borrow_data()?;
+    let price = if *oracle_info.owner == PYTH_RECEIVER_ID {
+        parse_pyth_price_update(&data)?
+    } else if is_oracle_program(oracle_info.owner) {
+        parse_switchboard_pull_feed(&data)?
+    } else {
+        return Err(StakingError::InvalidOracle.into());
+    };
+    Ok(price)
+}
+
+fn read_array<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N], StakingError> {
+    data.get(offset..offset + N)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(StakingError::InvalidOracle)
+}
+
+/// Price of a Pyth PriceUpdateV2 account. Partially verified updates are
+/// rejected.
+pub fn parse_pyth_price_update(data: &[u8]) -> Result<OraclePrice, StakingError> {
+    if read_array::<8>(data, 0)? != PRICE_UPDATE_V2_DISCRIMINATOR
+        || read_array::<1>(data, PYTH_VERIFICATION_OFFSET)?[0] != PYTH_VERIFICATION_FULL
+    {
+        return Err(StakingError::InvalidOracle);
+    }
+    let price = i64::from_le_bytes(read_array(data, PYTH_PRICE_OFFSET)?);
+    let exponent = i32::from_le_bytes(read_array(data, PYTH_EXPONENT_OFFSET)?);
+    let publish_time = i64::from_le_bytes(read_array(data, PYTH_PUBLISH_TIME_OFFSET)?);
+    if price <= 0 {
+        return Err(StakingError::InvalidOracle);
+    }
+
+    // price * 10^exponent, rescaled to 18 decimals
+    let scale = 18i32
+        .checked_add(exponent)
+        .filter(|scale| (-18..=36).contains(scale))
+        .ok_or(StakingError::InvalidOracle)?;
+    let price_wad = if scale >= 0 {
+        (price as u128).checked_mul(10u128.pow(scale as u32))
+    } else {
+        Some(price as u128 / 10u128.pow(scale.unsigned_abs()))
+    }
+    .filter(|price_wad| *price_wad > 0)
+    .ok_or(StakingError::InvalidOracle)?;
+
+    Ok(OraclePrice { price_wad, publish_time })
+}
+
+/// Price of a Switchboard On-Demand pull feed (its result already has 18
+/// decimals)
+pub fn parse_switchboard_pull_feed(data: &[u8]) -> Result<OraclePrice, StakingError> {
+    if read_array::<8>(data, 0)? != PULL_FEED_DISCRIMINATOR {
+        return Err(StakingError::InvalidOracle);
+    }
+    let value = i128::from_le_bytes(read_array(data, SWITCHBOARD_VALUE_OFFSET)?);
+    let publish_time = i64::from_le_bytes(read_array(data, SWITCHBOARD_TIMESTAMP_OFFSET)?);
+    if value <= 0 {
+        return Err(StakingError::InvalidOracle);
+    }
+    Ok(OraclePrice {
+        price_wad: value as u128,
+        publish_time,
+    })
+}
+
+/// WAD-scaled USD value of `amount` raw units at `price_wad` per whole token
+pub fn usd_value_wad(amount: u128, decimals: u8, price_wad: u128) -> Option<u128> {
+    let value = U256::from_u128(amount).checked_mul(U256::from_u128(price_wad))?
+        / U256::from(10u64).checked_pow(U256::from(decimals))?;
+    value.to_u128()
+}
+
+/// Annual percentage yield in basis points, from a reward rate of
+/// `reward_rate_wad` lamports per raw token unit per year (WAD-scaled), the
+/// SOL price and the token price (both WAD-scaled USD per whole unit)
+pub fn usd_apy_bps(
+    reward_rate_wad: u128,
+    decimals: u8,
+    sol_price_wad: u128,
+    token_price_wad: u128,
+) -> Option<u64> {
+    if token_price_wad == 0 {
+        return None;
+    }
+    // USD earned per whole token per year over USD per whole token, in bps
+    let numerator = U256::from_u128(reward_rate_wad)
+        .checked_mul(U256::from(10u64).checked_pow(U256::from(decimals))?)?
+        .checked_mul(U256::from_u128(sol_price_wad))?
+        .checked_mul(U256::from(10_000u64))?;
+    let denominator = U256::from_u128(WAD)
+        .checked_mul(U256::from_u128(LAMPORTS_PER_SOL))?
+        .checked_mul(U256::from_u128(token_price_wad))?;
+    let bps = numerator / denominator;
+    Some(bps.to_u128()?.min(u64::MAX as u128) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pyth_update(verification: &[u8], price: i64, exponent: i32, publish_time: i64) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&PRICE_UPDATE_V2_DISCRIMINATOR);
+        data.extend_from_slice(&[7u8; 32]); // write authority
+        data.extend_from_slice(verification);
+        data.extend_from_slice(&[9u8; 32]); // feed id
+        data.extend_from_slice(&price.to_le_bytes());
+        data.extend_from_slice(&1_000u64.to_le_bytes()); // conf
+        data.extend_from_slice(&exponent.to_le_bytes());
+        data.extend_from_slice(&publish_time.to_le_bytes());
+        data.extend_from_slice(&[0u8; 40]); // prev publish time, ema, posted slot
+        data
+    }
+
+    #[test]
+    fn test_parse_pyth_price_update() {
+        // $1.50 with 8 decimals
+        let data = pyth_update(&[1], 150_000_000, -8, 1_700_000_000);
+        let price = parse_pyth_price_update(&data).unwrap();
+        assert_eq!(price.price_wad, 3 * WAD / 2);
+        assert_eq!(price.publish_time, 1_700_000_000);
+
+        // Partially verified updates are rejected
+        let data = pyth_update(&[0, 3], 150_000_000, -8, 1_700_000_000);
+        assert_eq!(parse_pyth_price_update(&data), Err(StakingError::InvalidOracle));
+
+        // Non-positive prices and truncated accounts too
+        let data = pyth_update(&[1], 0, -8, 1_700_000_000);
+        assert_eq!(parse_pyth_price_update(&data), Err(StakingError::InvalidOracle));
+        let data = pyth_update(&[1], 150_000_000, -8, 1_700_000_000);
+        assert_eq!(parse_pyth_price_update(&data[..90]), Err(StakingError::InvalidOracle));
+
+        // Exponents below -18 truncate; too small to represent fails
+        let data = pyth_update(&[1], 5, -20, 0);
+        assert_eq!(parse_pyth_price_update(&data), Err(StakingError::InvalidOracle));
+        let data = pyth_update(&[1], 500, -20, 0);
+        assert_eq!(parse_pyth_price_update(&data).unwrap().price_wad, 5);
+    }
+
+    #[test]
+    fn test_parse_switchboard_pull_feed() {
+        let mut data = vec![0u8; 3208];
+        data[..8].copy_from_slice(&PULL_FEED_DISCRIMINATOR);
+        data[SWITCHBOARD_VALUE_OFFSET..SWITCHBOARD_VALUE_OFFSET + 16]
+            .copy_from_slice(&(25 * WAD as i128).to_le_bytes());
+        data[SWITCHBOARD_TIMESTAMP_OFFSET..SWITCHBOARD_TIMESTAMP_OFFSET + 8]
+            .copy_from_slice(&1_700_000_000i64.to_le_bytes());
+        let price = parse_switchboard_pull_feed(&data).unwrap();
+        assert_eq!(price.price_wad, 25 * WAD);
+        assert_eq!(price.publish_time, 1_700_000_000);
+
+        data[..8].copy_from_slice(&PRICE_UPDATE_V2_DISCRIMINATOR);
+        assert_eq!(parse_switchboard_pull_feed(&data), Err(StakingError::InvalidOracle));
+    }
+
+    #[test]
+    fn test_usd_figures() {
+        // 1,000 tokens (6 decimals) at $1.50
+        assert_eq!(usd_value_wad(1_000_000_000, 6, 3 * WAD / 2), Some(1_500 * WAD));
+
+        // 0.01 SOL per whole token per year, SOL at $150, token at $1.50:
+        // $1.50 earned on $1.50 staked = 100%
+        let rate_wad = 10 * WAD; // lamports per raw unit per year
+        assert_eq!(usd_apy_bps(rate_wad, 6, 150 * WAD, 3 * WAD / 2), Some(10_000));
+        assert_eq!(usd_apy_bps(rate_wad, 6, 150 * WAD, 0), None);
+    }
+}
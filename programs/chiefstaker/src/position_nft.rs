@@ -0,0 +1,327 @@
+//! Position NFTs: a Token 2022 mint of supply 1 per tokenized stake
+//! (TokenizePosition), owned by the pool PDA, whose token metadata carries
+//! the position's pool, amount and start time. The position is frozen
+//! while tokenized, so the metadata never goes stale; RedeemPosition burns
+//! the NFT and hands the position to its holder.
+
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction, system_program,
+};
+use spl_token_2022::extension::ExtensionType;
+use spl_token_metadata_interface::state::{Field, TokenMetadata};
+
+use crate::{
+    error::StakingError,
+    instructions::set_metadata::URL_PREFIX,
+    state::{StakingPool, UserStake, POSITION_NFT_SEED},
+};
+
+/// spl-associated-token-account program
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+/// CreateIdempotent instruction of the associated token account program
+const CREATE_IDEMPOTENT: u8 = 1;
+
+pub const POSITION_NFT_NAME: &str = "ChiefStaker Position";
+pub const POSITION_NFT_SYMBOL: &str = "CSPOS";
+
+/// Token metadata of the NFT at `mint` for `user_stake`, with the pool
+/// PDA as update authority
+pub fn position_metadata(
+    pool_key: &Pubkey,
+    pool: &StakingPool,
+    mint: &Pubkey,
+    user_stake: &UserStake,
+) -> Result<TokenMetadata, ProgramError> {
+    Ok(TokenMetadata {
+        update_authority: Some(*pool_key).try_into()?,
+        mint: *mint,
+        name: POSITION_NFT_NAME.to_string(),
+        symbol: POSITION_NFT_SYMBOL.to_string(),
+        uri: format!("{}{}", URL_PREFIX, pool.mint),
+        additional_metadata: vec![
+            ("pool".to_string(), pool_key.to_string()),
+            ("amount".to_string(), user_stake.amount.to_string()),
+            ("stake_time".to_string(), user_stake.stake_time.to_string()),
+        ],
+    })
+}
+
+/// Associated token account of `wallet` for the Token 2022 `mint`
+pub fn position_token_address(wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[wallet.as_ref(), spl_token_2022::id().as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Create the NFT mint at ["position_nft", user_stake] with `metadata`
+/// stored in the mint itself (metadata pointer to self), the pool PDA as
+/// mint, metadata update and close authority, and 0 decimals. The payer
+/// funds the mint at its final size: Token 2022 grows it for the metadata
+/// but doesn't collect the extra rent.
+#[allow(clippy::too_many_arguments)]
+pub fn create_position_mint<'a>(
+    program_id: &Pubkey,
+    pool: &StakingPool,
+    pool_info: &AccountInfo<'a>,
+    user_stake_info: &AccountInfo<'a>,
+    mint_info: &AccountInfo<'a>,
+    payer_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    rent: &Rent,
+    metadata: &TokenMetadata,
+) -> ProgramResult {
+    let (expected_mint, mint_bump) =
+        UserStake::derive_position_mint_pda(user_stake_info.key, program_id);
+    if *mint_info.key != expected_mint {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    let mint_seeds: &[&[u8]] = &[POSITION_NFT_SEED, user_stake_info.key.as_ref(), &[mint_bump]];
+
+    let mint_size = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+        ExtensionType::MetadataPointer,
+        ExtensionType::MintCloseAuthority,
+    ])?;
+    let full_size = mint_size + metadata.tlv_size_of()?;
+
+    invoke_signed(
+        &system_instruction::create_account(
+            payer_info.key,
+            mint_info.key,
+            rent.minimum_balance(full_size),
+            mint_size as u64,
+            &spl_token_2022::id(),
+        ),
+        &[
+            payer_info.clone(),
+            mint_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[mint_seeds],
+    )?;
+
+    invoke(
+        &spl_token_2022::extension::metadata_pointer::instruction::initialize(
+            &spl_token_2022::id(),
+            mint_info.key,
+            Some(*pool_info.key),
+            Some(*mint_info.key),
+        )?,
+        std::slice::from_ref(mint_info),
+    )?;
+    invoke(
+        &spl_token_2022::instruction::initialize_mint_close_authority(
+            &spl_token_2022::id(),
+            mint_info.key,
+            Some(pool_info.key),
+        )?,
+        std::slice::from_ref(mint_info),
+    )?;
+    invoke(
+        &spl_token_2022::instruction::initialize_mint2(
+            &spl_token_2022::id(),
+            mint_info.key,
+            pool_info.key,
+            None,
+            0,
+        )?,
+        std::slice::from_ref(mint_info),
+    )?;
+
+    let pool_id_bytes = pool.pool_id.to_le_bytes();
+    let pool_bump = [pool.bump];
+    let pool_seeds: &[&[u8]] = &pool.signer_seeds(&pool_id_bytes, &pool_bump);
+
+    invoke_signed(
+        &spl_token_metadata_interface::instruction::initialize(
+            &spl_token_2022::id(),
+            mint_info.key,
+            pool_info.key,
+            mint_info.key,
+            pool_info.key,
+            metadata.name.clone(),
+            metadata.symbol.clone(),
+            metadata.uri.clone(),
+        ),
+        &[mint_info.clone(), pool_info.clone()],
+        &[pool_seeds],
+    )?;
+    for (key, value) in &metadata.additional_metadata {
+        invoke_signed(
+            &spl_token_metadata_interface::instruction::update_field(
+                &spl_token_2022::id(),
+                mint_info.key,
+                pool_info.key,
+                Field::Key(key.clone()),
+                value.clone(),
+            ),
+            &[mint_info.clone(), pool_info.clone()],
+            &[pool_seeds],
+        )?;
+    }
+    Ok(())
+}
+
+/// Create the owner's associated token account for the NFT, mint the
+/// single token into it and drop the mint authority, so the supply stays
+/// fixed at one
+#[allow(clippy::too_many_arguments)]
+pub fn mint_position_nft<'a>(
+    pool: &StakingPool,
+    pool_info: &AccountInfo<'a>,
+    mint_info: &AccountInfo<'a>,
+    owner_info: &AccountInfo<'a>,
+    token_account_info: &AccountInfo<'a>,
+    token_program_info: &AccountInfo<'a>,
+    ata_program_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    if *token_account_info.key != position_token_address(owner_info.key, mint_info.key) {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    invoke(
+        &Instruction {
+            program_id: ASSOCIATED_TOKEN_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*owner_info.key, true),
+                AccountMeta::new(*token_account_info.key, false),
+                AccountMeta::new_readonly(*owner_info.key, false),
+                AccountMeta::new_readonly(*mint_info.key, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(spl_token_2022::id(), false),
+            ],
+            data: vec![CREATE_IDEMPOTENT],
+        },
+        &[
+            owner_info.clone(),
+            token_account_info.clone(),
+            mint_info.clone(),
+            system_program_info.clone(),
+            token_program_info.clone(),
+            ata_program_info.clone(),
+        ],
+    )?;
+
+    let pool_id_bytes = pool.pool_id.to_le_bytes();
+    let pool_bump = [pool.bump];
+    let pool_seeds: &[&[u8]] = &pool.signer_seeds(&pool_id_bytes, &pool_bump);
+
+    invoke_signed(
+        &spl_token_2022::instruction::mint_to(
+            &spl_token_2022::id(),
+            mint_info.key,
+            token_account_info.key,
+            pool_info.key,
+            &[],
+            1,
+        )?,
+        &[
+            mint_info.clone(),
+            token_account_info.clone(),
+            pool_info.clone(),
+        ],
+        &[pool_seeds],
+    )?;
+    invoke_signed(
+        &spl_token_2022::instruction::set_authority(
+            &spl_token_2022::id(),
+            mint_info.key,
+            None,
+            spl_token_2022::instruction::AuthorityType::MintTokens,
+            pool_info.key,
+            &[],
+        )?,
+        &[mint_info.clone(), pool_info.clone()],
+        &[pool_seeds],
+    )
+}
+
+/// Burn the NFT from the holder's token account (the holder signs), then
+/// close the emptied mint, returning its rent to the holder
+pub fn burn_position_nft<'a>(
+    pool: &StakingPool,
+    pool_info: &AccountInfo<'a>,
+    mint_info: &AccountInfo<'a>,
+    holder_info: &AccountInfo<'a>,
+    token_account_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    invoke(
+        &spl_token_2022::instruction::burn(
+            &spl_token_2022::id(),
+            token_account_info.key,
+            mint_info.key,
+            holder_info.key,
+            &[],
+            1,
+        )?,
+        &[
+            token_account_info.clone(),
+            mint_info.clone(),
+            holder_info.clone(),
+        ],
+    )?;
+
+    let pool_id_bytes = pool.pool_id.to_le_bytes();
+    let pool_bump = [pool.bump];
+    let pool_seeds: &[&[u8]] = &pool.signer_seeds(&pool_id_bytes, &pool_bump);
+
+    invoke_signed(
+        &spl_token_2022::instruction::close_account(
+            &spl_token_2022::id(),
+            mint_info.key,
+            holder_info.key,
+            pool_info.key,
+            &[],
+        )?,
+        &[mint_info.clone(), holder_info.clone(), pool_info.clone()],
+        &[pool_seeds],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position_metadata() {
+        let pool_key = Pubkey::new_unique();
+        let pool = StakingPool::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            2592000,
+            0,
+            255,
+        );
+        let mut user_stake = UserStake::new(Pubkey::new_unique(), pool_key, 0, 0, 0, 255, 0);
+        user_stake.amount = 1_500_000;
+        user_stake.stake_time = 1_700_000_000;
+        let mint = Pubkey::new_unique();
+
+        let metadata = position_metadata(&pool_key, &pool, &mint, &user_stake).unwrap();
+        assert_eq!(Option::<Pubkey>::from(metadata.update_authority), Some(pool_key));
+        assert_eq!(metadata.mint, mint);
+        assert_eq!(metadata.uri, format!("{}{}", URL_PREFIX, pool.mint));
+        assert_eq!(
+            metadata.additional_metadata,
+            vec![
+                ("pool".to_string(), pool_key.to_string()),
+                ("amount".to_string(), "1500000".to_string()),
+                ("stake_time".to_string(), "1700000000".to_string()),
+            ]
+        );
+        assert!(metadata.tlv_size_of().unwrap() > 0);
+    }
+}
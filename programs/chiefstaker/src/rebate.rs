@@ -0,0 +1,69 @@
+//! Claim gas rebates for pools with a GasRebateVault account
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    error::StakingError,
+    state::{GasRebateVault, StakingPool},
+};
+
+/// Take the GasRebateVault account from `iter` if the pool pays claim
+/// rebates and validate it. Pools without rebates consume nothing.
+pub fn next_gas_rebate_vault<'a: 'b, 'b, I>(
+    program_id: &Pubkey,
+    pool_key: &Pubkey,
+    pool: &StakingPool,
+    iter: &mut I,
+) -> Result<Option<&'b AccountInfo<'a>>, ProgramError>
+where
+    I: Iterator<Item = &'b AccountInfo<'a>>,
+{
+    if !pool.has_gas_rebate_vault {
+        return Ok(None);
+    }
+    let vault_info = next_account_info(iter)?;
+    if vault_info.owner != program_id {
+        return Err(StakingError::InvalidAccountOwner.into());
+    }
+    let (expected_vault, _) = GasRebateVault::derive_pda(pool_key, program_id);
+    if *vault_info.key != expected_vault {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    let vault = GasRebateVault::try_from_slice(&vault_info.try_borrow_data()?)?;
+    if !vault.is_initialized() {
+        return Err(StakingError::NotInitialized.into());
+    }
+    Ok(Some(vault_info))
+}
+
+/// Move the rebate for a claim paying `payout` lamports from the vault to
+/// `claimer_info` and return it. An empty vault pays nothing rather than
+/// failing, so claims never depend on the vault staying funded.
+pub fn pay_gas_rebate(
+    vault_info: &AccountInfo,
+    claimer_info: &AccountInfo,
+    payout: u64,
+) -> Result<u64, ProgramError> {
+    let mut vault = GasRebateVault::try_from_slice(&vault_info.try_borrow_data()?)?;
+    let available = vault_info
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(GasRebateVault::LEN));
+    let rebate = vault.rebate_for(payout, available);
+    if rebate == 0 {
+        return Ok(0);
+    }
+
+    **vault_info.try_borrow_mut_lamports()? -= rebate;
+    **claimer_info.try_borrow_mut_lamports()? += rebate;
+
+    vault.record_rebate(rebate);
+    vault.serialize(&mut &mut vault_info.try_borrow_mut_data()?[..])?;
+    Ok(rebate)
+}
@@ -0,0 +1,194 @@
+//! Receipt token: a Token 2022 mint owned by the pool PDA, minted 1:1 on
+//! stake and burned on unstake, so staked positions are transferable.
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+};
+
+use crate::{
+    error::StakingError,
+    state::{StakingPool, RECEIPT_MINT_SEED},
+};
+
+/// Receipt mint and the user's receipt token account
+pub struct ReceiptAccounts<'a, 'b> {
+    pub mint: &'b AccountInfo<'a>,
+    pub token_account: &'b AccountInfo<'a>,
+}
+
+/// Take the receipt accounts from `iter` if the pool issues receipts.
+/// Pools without a receipt mint consume nothing.
+pub fn next_receipt_accounts<'a, 'b, I>(
+    pool: &StakingPool,
+    iter: &mut I,
+) -> Result<Option<ReceiptAccounts<'a, 'b>>, ProgramError>
+where
+    I: Iterator<Item = &'b AccountInfo<'a>>,
+{
+    if !pool.has_receipt_mint() {
+        return Ok(None);
+    }
+    let mint = next_account_info(iter)?;
+    let token_account = next_account_info(iter)?;
+    if *mint.key != pool.receipt_mint {
+        return Err(StakingError::InvalidReceiptMint.into());
+    }
+    Ok(Some(ReceiptAccounts { mint, token_account }))
+}
+
+/// Create and initialize the receipt mint at ["receipt_mint", pool] with
+/// the pool PDA as mint authority and no freeze authority.
+pub fn create_receipt_mint<'a>(
+    program_id: &Pubkey,
+    pool_info: &AccountInfo<'a>,
+    receipt_mint_info: &AccountInfo<'a>,
+    payer_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    rent: &Rent,
+    decimals: u8,
+) -> ProgramResult {
+    let (expected_mint, mint_bump) =
+        StakingPool::derive_receipt_mint_pda(pool_info.key, program_id);
+    if *receipt_mint_info.key != expected_mint {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    let mint_seeds: &[&[u8]] = &[RECEIPT_MINT_SEED, pool_info.key.as_ref(), &[mint_bump]];
+
+    let mint_size = spl_token_2022::extension::ExtensionType::try_calculate_account_len::<
+        spl_token_2022::state::Mint,
+    >(&[])?;
+
+    invoke_signed(
+        &system_instruction::create_account(
+            payer_info.key,
+            receipt_mint_info.key,
+            rent.minimum_balance(mint_size),
+            mint_size as u64,
+            &spl_token_2022::id(),
+        ),
+        &[
+            payer_info.clone(),
+            receipt_mint_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[mint_seeds],
+    )?;
+
+    invoke(
+        &spl_token_2022::instruction::initialize_mint2(
+            &spl_token_2022::id(),
+            receipt_mint_info.key,
+            pool_info.key,
+            None,
+            decimals,
+        )?,
+        std::slice::from_ref(receipt_mint_info),
+    )
+}
+
+/// Mint `amount` receipt tokens, signed by the pool PDA
+pub fn mint_receipt<'a>(
+    pool: &StakingPool,
+    pool_info: &AccountInfo<'a>,
+    receipt: &ReceiptAccounts<'a, '_>,
+    amount: u64,
+) -> ProgramResult {
+    let pool_id_bytes = pool.pool_id.to_le_bytes();
+    let pool_bump = [pool.bump];
+    let pool_seeds: &[&[u8]] = &pool.signer_seeds(&pool_id_bytes, &pool_bump);
+
+    invoke_signed(
+        &spl_token_2022::instruction::mint_to(
+            &spl_token_2022::id(),
+            receipt.mint.key,
+            receipt.token_account.key,
+            pool_info.key,
+            &[],
+            amount,
+        )?,
+        &[
+            receipt.mint.clone(),
+            receipt.token_account.clone(),
+            pool_info.clone(),
+        ],
+        &[pool_seeds],
+    )
+}
+
+/// Burn `amount` receipt tokens from the owner's receipt account
+pub fn burn_receipt<'a>(
+    receipt: &ReceiptAccounts<'a, '_>,
+    owner_info: &AccountInfo<'a>,
+    amount: u64,
+) -> ProgramResult {
+    invoke(
+        &spl_token_2022::instruction::burn(
+            &spl_token_2022::id(),
+            receipt.token_account.key,
+            receipt.mint.key,
+            owner_info.key,
+            &[],
+            amount,
+        )?,
+        &[
+            receipt.token_account.clone(),
+            receipt.mint.clone(),
+            owner_info.clone(),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_receipt_accounts() {
+        let owner = crate::id();
+        let (mint_key, other_key, account_key) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut l1, mut l2, mut l3) = (0u64, 0u64, 0u64);
+        let (mut d1, mut d2, mut d3) = ([0u8; 0], [0u8; 0], [0u8; 0]);
+        let mint = AccountInfo::new(&mint_key, false, true, &mut l1, &mut d1, &owner, false, 0);
+        let other = AccountInfo::new(&other_key, false, true, &mut l2, &mut d2, &owner, false, 0);
+        let account = AccountInfo::new(&account_key, false, true, &mut l3, &mut d3, &owner, false, 0);
+
+        let mut pool = StakingPool::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            2592000,
+            0,
+            255,
+        );
+
+        // No receipt mint: nothing consumed
+        let accounts = [mint.clone(), account.clone()];
+        let iter = &mut accounts.iter();
+        assert!(next_receipt_accounts(&pool, iter).unwrap().is_none());
+        assert_eq!(iter.count(), 2);
+
+        pool.receipt_mint = mint_key;
+        let iter = &mut accounts.iter();
+        let receipt = next_receipt_accounts(&pool, iter).unwrap().unwrap();
+        assert_eq!(*receipt.token_account.key, account_key);
+
+        let wrong = [other, account.clone()];
+        assert_eq!(
+            next_receipt_accounts(&pool, &mut wrong.iter()).err(),
+            Some(StakingError::InvalidReceiptMint.into())
+        );
+        let short = [mint];
+        assert_eq!(
+            next_receipt_accounts(&pool, &mut short.iter()).err(),
+            Some(ProgramError::NotEnoughAccountKeys)
+        );
+    }
+}
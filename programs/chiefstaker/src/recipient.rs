@@ -0,0 +1,190 @@
+//! Reward recipient: where claim/unstake reward lamports are credited.
+//! Positions owned by program PDAs can declare a separate recipient account,
+//! and any position can split its payouts across several accounts.
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::StakingError,
+    state::{PayoutSplit, UserStake},
+};
+
+/// Accounts credited with a position's reward lamports
+pub enum RewardPayee<'a, 'b> {
+    /// The owner, the declared recipient or a wSOL account
+    Single(&'b AccountInfo<'a>),
+    /// Payout split destinations, in split order
+    Split(Box<PayoutSplit>, Vec<&'b AccountInfo<'a>>),
+}
+
+impl RewardPayee<'_, '_> {
+    /// Credit `amount` lamports the caller has already debited from the pool
+    pub fn credit(&self, amount: u64) -> ProgramResult {
+        match self {
+            RewardPayee::Single(info) => {
+                **info.try_borrow_mut_lamports()? += amount;
+            }
+            RewardPayee::Split(split, infos) => {
+                for (info, share) in infos.iter().zip(split.shares(amount)) {
+                    **info.try_borrow_mut_lamports()? += share;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Take the payout split account and its destinations from `iter` if the
+/// position has a split, otherwise fall back to [`next_reward_recipient`].
+pub fn next_reward_payee<'a, 'b, I>(
+    program_id: &Pubkey,
+    user_stake: &UserStake,
+    owner_info: &'b AccountInfo<'a>,
+    iter: &mut I,
+) -> Result<RewardPayee<'a, 'b>, ProgramError>
+where
+    I: Iterator<Item = &'b AccountInfo<'a>>,
+{
+    if !user_stake.has_payout_split {
+        return next_reward_recipient(user_stake, owner_info, iter).map(RewardPayee::Single);
+    }
+
+    let split_info = next_account_info(iter)?;
+    if split_info.owner != program_id {
+        return Err(StakingError::InvalidAccountOwner.into());
+    }
+    let (expected_split, _) =
+        PayoutSplit::derive_pda(&user_stake.pool, &user_stake.owner, program_id);
+    if *split_info.key != expected_split {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    let split = PayoutSplit::try_from_slice(&split_info.try_borrow_data()?)?;
+    if !split.is_initialized() {
+        return Err(StakingError::NotInitialized.into());
+    }
+
+    let mut infos = Vec::with_capacity(split.active().len());
+    for destination in split.active() {
+        let info = next_account_info(iter)?;
+        if *info.key != destination.recipient {
+            return Err(StakingError::InvalidRewardRecipient.into());
+        }
+        infos.push(info);
+    }
+    Ok(RewardPayee::Split(Box::new(split), infos))
+}
+
+/// Take the declared reward recipient from `iter` if the position has one,
+/// otherwise pay `owner_info`. Positions without a recipient consume nothing.
+pub fn next_reward_recipient<'a, 'b, I>(
+    user_stake: &UserStake,
+    owner_info: &'b AccountInfo<'a>,
+    iter: &mut I,
+) -> Result<&'b AccountInfo<'a>, ProgramError>
+where
+    I: Iterator<Item = &'b AccountInfo<'a>>,
+{
+    if !user_stake.has_reward_recipient() {
+        return Ok(owner_info);
+    }
+    let recipient_info = next_account_info(iter)?;
+    if *recipient_info.key != user_stake.reward_recipient {
+        return Err(StakingError::InvalidRewardRecipient.into());
+    }
+    Ok(recipient_info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::pubkey::Pubkey;
+
+    #[test]
+    fn test_next_reward_recipient() {
+        let program = crate::id();
+        let (owner_key, recipient_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut l1, mut l2) = (0u64, 0u64);
+        let (mut d1, mut d2) = ([0u8; 0], [0u8; 0]);
+        let owner = AccountInfo::new(&owner_key, true, true, &mut l1, &mut d1, &program, false, 0);
+        let recipient =
+            AccountInfo::new(&recipient_key, false, true, &mut l2, &mut d2, &program, false, 0);
+
+        let mut user_stake = UserStake::new(owner_key, Pubkey::default(), 1, 0, 0, 255, 0);
+
+        // No recipient: owner is paid, nothing consumed
+        let accounts = [recipient.clone()];
+        let iter = &mut accounts.iter();
+        assert_eq!(*next_reward_recipient(&user_stake, &owner, iter).unwrap().key, owner_key);
+        assert_eq!(iter.count(), 1);
+
+        user_stake.reward_recipient = recipient_key;
+        let paid = next_reward_recipient(&user_stake, &owner, &mut accounts.iter()).unwrap();
+        assert_eq!(*paid.key, recipient_key);
+
+        let wrong = [owner.clone()];
+        assert_eq!(
+            next_reward_recipient(&user_stake, &owner, &mut wrong.iter()).err(),
+            Some(StakingError::InvalidRewardRecipient.into())
+        );
+        assert_eq!(
+            next_reward_recipient(&user_stake, &owner, &mut [].iter()).err(),
+            Some(ProgramError::NotEnoughAccountKeys)
+        );
+    }
+    #[test]
+    fn test_next_reward_payee() {
+        let program = crate::id();
+        let (owner_key, a_key, b_key) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let pool_key = Pubkey::new_unique();
+        let (split_key, bump) = PayoutSplit::derive_pda(&pool_key, &owner_key, &program);
+
+        let mut split = PayoutSplit {
+            discriminator: crate::state::PAYOUT_SPLIT_DISCRIMINATOR,
+            pool: pool_key,
+            owner: owner_key,
+            count: 0,
+            bump,
+            destinations: Default::default(),
+        };
+        split.set_destinations(&[(a_key, 7_000), (b_key, 3_000)]).unwrap();
+        let mut split_data = borsh::to_vec(&split).unwrap();
+
+        let (mut l0, mut l1, mut l2, mut l3) = (0u64, 0u64, 0u64, 0u64);
+        let (mut d0, mut d2, mut d3) = ([0u8; 0], [0u8; 0], [0u8; 0]);
+        let owner = AccountInfo::new(&owner_key, true, true, &mut l0, &mut d0, &program, false, 0);
+        let split_info =
+            AccountInfo::new(&split_key, false, false, &mut l1, &mut split_data, &program, false, 0);
+        let a = AccountInfo::new(&a_key, false, true, &mut l2, &mut d2, &program, false, 0);
+        let b = AccountInfo::new(&b_key, false, true, &mut l3, &mut d3, &program, false, 0);
+
+        let mut user_stake = UserStake::new(owner_key, pool_key, 1, 0, 0, 255, 0);
+
+        // No split: falls back to the owner
+        let payee = next_reward_payee(&program, &user_stake, &owner, &mut [].iter()).unwrap();
+        assert!(matches!(payee, RewardPayee::Single(info) if *info.key == owner_key));
+
+        user_stake.has_payout_split = true;
+        let accounts = [split_info.clone(), a.clone(), b.clone()];
+        let payee = next_reward_payee(&program, &user_stake, &owner, &mut accounts.iter()).unwrap();
+        payee.credit(1_001).unwrap();
+        assert_eq!((a.lamports(), b.lamports()), (700, 301));
+
+        // Destinations must be passed in split order
+        let swapped = [split_info.clone(), b.clone(), a.clone()];
+        assert_eq!(
+            next_reward_payee(&program, &user_stake, &owner, &mut swapped.iter()).err(),
+            Some(StakingError::InvalidRewardRecipient.into())
+        );
+        let wrong_split = [a.clone(), a.clone(), b.clone()];
+        assert_eq!(
+            next_reward_payee(&program, &user_stake, &owner, &mut wrong_split.iter()).err(),
+            Some(StakingError::InvalidPDA.into())
+        );
+    }
+}
@@ -0,0 +1,181 @@
+//! Claim sessions: an owner signs an off-chain message delegating reward
+//! claims on one pool to a hot key until an expiry (ClaimWithSession).
+//!
+//! The owner's ed25519 signature is checked by an Ed25519 program
+//! instruction earlier in the same transaction; this module only confirms,
+//! through the instructions sysvar, that such an instruction signed the
+//! expected message with the owner's key.
+
+use solana_program::{
+    account_info::AccountInfo,
+    ed25519_program,
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+};
+
+use crate::error::{ErrorContext, StakingError};
+
+/// Domain prefix of session messages, so the signature can't be replayed
+/// as anything else the owner signs
+pub const SESSION_MESSAGE_PREFIX: &[u8] = b"chiefstaker claim session v1\n";
+
+/// Length of a session message: prefix, pool, session key, expiry
+pub const SESSION_MESSAGE_LEN: usize = SESSION_MESSAGE_PREFIX.len() + 32 + 32 + 8;
+
+/// Longest a session may last (7 days)
+pub const MAX_SESSION_SECONDS: i64 = 7 * 86400;
+
+/// Ed25519SignatureOffsets: seven u16 fields per signature
+const SIGNATURE_OFFSETS_LEN: usize = 14;
+/// Offsets start after the signature count and a padding byte
+const SIGNATURE_OFFSETS_START: usize = 2;
+/// Instruction index meaning "the Ed25519 instruction itself"
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Message the owner signs to let `session_key` claim on `pool` until
+/// `expires_at` (unix timestamp)
+pub fn session_message(pool: &Pubkey, session_key: &Pubkey, expires_at: i64) -> Vec<u8> {
+    let mut message = Vec::with_capacity(SESSION_MESSAGE_LEN);
+    message.extend_from_slice(SESSION_MESSAGE_PREFIX);
+    message.extend_from_slice(pool.as_ref());
+    message.extend_from_slice(session_key.as_ref());
+    message.extend_from_slice(&expires_at.to_le_bytes());
+    message
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<usize> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]) as usize)
+}
+
+/// Whether Ed25519 program instruction data `data` verifies a signature by
+/// `signer` over exactly `message`. Only signatures whose key, signature
+/// and message all live in the instruction itself count; ones pointing into
+/// other instructions are skipped.
+pub fn ed25519_signs(data: &[u8], signer: &Pubkey, message: &[u8]) -> bool {
+    let count = match data.first() {
+        Some(&count) => count as usize,
+        None => return false,
+    };
+    (0..count).any(|i| {
+        let start = SIGNATURE_OFFSETS_START + i * SIGNATURE_OFFSETS_LEN;
+        let field = |n: usize| read_u16(data, start + 2 * n);
+        let (
+            Some(_signature_offset),
+            Some(signature_ix),
+            Some(pubkey_offset),
+            Some(pubkey_ix),
+            Some(message_offset),
+            Some(message_size),
+            Some(message_ix),
+        ) = (field(0), field(1), field(2), field(3), field(4), field(5), field(6))
+        else {
+            return false;
+        };
+        let current = CURRENT_INSTRUCTION as usize;
+        if signature_ix != current || pubkey_ix != current || message_ix != current {
+            return false;
+        }
+        data.get(pubkey_offset..pubkey_offset + 32) == Some(signer.as_ref())
+            && data.get(message_offset..message_offset + message_size) == Some(message)
+    })
+}
+
+/// Check that `session_key` may claim for `owner` on `pool`: the session
+/// hasn't expired, isn't longer than MAX_SESSION_SECONDS, and an Ed25519
+/// instruction before the current one verified the owner's signature of
+/// the session message.
+///
+/// `instructions_sysvar` must be the instructions sysvar account;
+/// anything else is rejected by the sysvar loaders.
+pub fn verify_claim_session(
+    instructions_sysvar: &AccountInfo,
+    pool: &Pubkey,
+    owner: &Pubkey,
+    session_key: &Pubkey,
+    expires_at: i64,
+    now: i64,
+) -> ProgramResult {
+    if now >= expires_at {
+        return Err(StakingError::SessionExpired.into());
+    }
+    if expires_at - now > MAX_SESSION_SECONDS {
+        return Err(StakingError::SettingExceedsMaximum
+            .with_context(ErrorContext::MaximumAmount(MAX_SESSION_SECONDS as u64)));
+    }
+
+    let message = session_message(pool, session_key, expires_at);
+    let current_index = load_current_index_checked(instructions_sysvar)?;
+    for index in 0..current_index {
+        let ix = load_instruction_at_checked(index as usize, instructions_sysvar)?;
+        if ix.program_id == ed25519_program::id() && ed25519_signs(&ix.data, owner, &message) {
+            return Ok(());
+        }
+    }
+    msg!("No Ed25519 instruction signs the claim session of {}", owner);
+    Err(StakingError::InvalidSessionProof.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ed25519 instruction data for one signature with everything inline
+    fn ed25519_data(signer: &Pubkey, message: &[u8], ix_index: u16) -> Vec<u8> {
+        let pubkey_offset = (SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_LEN) as u16;
+        let signature_offset = pubkey_offset + 32;
+        let message_offset = signature_offset + 64;
+        let mut data = vec![1, 0];
+        for field in [
+            signature_offset,
+            ix_index,
+            pubkey_offset,
+            ix_index,
+            message_offset,
+            message.len() as u16,
+            ix_index,
+        ] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+        data.extend_from_slice(signer.as_ref());
+        data.extend_from_slice(&[7u8; 64]);
+        data.extend_from_slice(message);
+        data
+    }
+
+    #[test]
+    fn test_session_message() {
+        let pool = Pubkey::new_unique();
+        let session_key = Pubkey::new_unique();
+        let message = session_message(&pool, &session_key, 1_700_000_000);
+        assert_eq!(message.len(), SESSION_MESSAGE_LEN);
+        assert!(message.starts_with(SESSION_MESSAGE_PREFIX));
+        assert_ne!(message, session_message(&pool, &session_key, 1_700_000_001));
+        assert_ne!(message, session_message(&session_key, &pool, 1_700_000_000));
+    }
+
+    #[test]
+    fn test_ed25519_signs() {
+        let owner = Pubkey::new_unique();
+        let message = session_message(&Pubkey::new_unique(), &Pubkey::new_unique(), 100);
+        let data = ed25519_data(&owner, &message, CURRENT_INSTRUCTION);
+
+        assert!(ed25519_signs(&data, &owner, &message));
+        // Another signer or message
+        assert!(!ed25519_signs(&data, &Pubkey::new_unique(), &message));
+        assert!(!ed25519_signs(&data, &owner, &message[1..]));
+        // Key or message taken from another instruction
+        assert!(!ed25519_signs(&ed25519_data(&owner, &message, 0), &owner, &message));
+        // No signatures, or offsets past the end
+        let mut empty = data.clone();
+        empty[0] = 0;
+        assert!(!ed25519_signs(&empty, &owner, &message));
+        assert!(!ed25519_signs(&data[..data.len() - 1], &owner, &message));
+        assert!(!ed25519_signs(&[], &owner, &message));
+        let mut overcounted = data.clone();
+        overcounted[0] = 2;
+        assert!(ed25519_signs(&overcounted, &owner, &message));
+    }
+}
@@ -0,0 +1,119 @@
+//! Rent sponsorship for pools with a RentSponsorVault account
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    error::StakingError,
+    state::{RentSponsorVault, StakingPool, UserStake},
+};
+
+/// Take the RentSponsorVault account from `iter` if the pool sponsors rent
+/// and validate it. Pools without a sponsor consume nothing.
+pub fn next_rent_sponsor<'a: 'b, 'b, I>(
+    program_id: &Pubkey,
+    pool_key: &Pubkey,
+    pool: &StakingPool,
+    iter: &mut I,
+) -> Result<Option<&'b AccountInfo<'a>>, ProgramError>
+where
+    I: Iterator<Item = &'b AccountInfo<'a>>,
+{
+    if !pool.has_rent_sponsor {
+        return Ok(None);
+    }
+    let vault_info = next_account_info(iter)?;
+    if vault_info.owner != program_id {
+        return Err(StakingError::InvalidAccountOwner.into());
+    }
+    let (expected_vault, _) = RentSponsorVault::derive_pda(pool_key, program_id);
+    if *vault_info.key != expected_vault {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    let vault = RentSponsorVault::try_from_slice(&vault_info.try_borrow_data()?)?;
+    if !vault.is_initialized() {
+        return Err(StakingError::NotInitialized.into());
+    }
+    Ok(Some(vault_info))
+}
+
+/// Create the stake account `stake_info` (PDA `seeds`). The rent comes
+/// from the sponsor vault if one is passed and holds enough above its own
+/// rent, otherwise from `payer`. Returns the lamports sponsored (0 when
+/// the payer paid).
+pub fn create_stake_account<'a>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    stake_info: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    sponsor_info: Option<&AccountInfo<'a>>,
+    seeds: &[&[u8]],
+) -> Result<u64, ProgramError> {
+    let rent = Rent::get()?;
+    let stake_rent = rent.minimum_balance(UserStake::LEN);
+
+    if let Some(vault_info) = sponsor_info {
+        let available = vault_info
+            .lamports()
+            .saturating_sub(rent.minimum_balance(RentSponsorVault::LEN));
+        let required = stake_rent.saturating_sub(stake_info.lamports());
+        if available >= required {
+            // The vault is program-owned, so it can't fund create_account:
+            // allocate and assign first, then move the rent over
+            invoke_signed(
+                &system_instruction::allocate(stake_info.key, UserStake::LEN as u64),
+                &[stake_info.clone(), system_program.clone()],
+                &[seeds],
+            )?;
+            invoke_signed(
+                &system_instruction::assign(stake_info.key, program_id),
+                &[stake_info.clone(), system_program.clone()],
+                &[seeds],
+            )?;
+            **vault_info.try_borrow_mut_lamports()? -= required;
+            **stake_info.try_borrow_mut_lamports()? += required;
+
+            let mut vault = RentSponsorVault::try_from_slice(&vault_info.try_borrow_data()?)?;
+            vault.record_sponsored(required);
+            vault.serialize(&mut &mut vault_info.try_borrow_mut_data()?[..])?;
+            return Ok(required);
+        }
+    }
+
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            stake_info.key,
+            stake_rent,
+            UserStake::LEN as u64,
+            program_id,
+        ),
+        &[payer.clone(), stake_info.clone(), system_program.clone()],
+        &[seeds],
+    )?;
+    Ok(0)
+}
+
+/// Move the sponsored share (up to `sponsored_rent`) of a closing stake
+/// account's `lamports` back to the vault. Returns the lamports returned.
+pub fn return_sponsored_rent(
+    vault_info: &AccountInfo,
+    sponsored_rent: u64,
+    lamports: u64,
+) -> Result<u64, ProgramError> {
+    let returned = sponsored_rent.min(lamports);
+    **vault_info.try_borrow_mut_lamports()? += returned;
+
+    let mut vault = RentSponsorVault::try_from_slice(&vault_info.try_borrow_data()?)?;
+    vault.record_returned(returned);
+    vault.serialize(&mut &mut vault_info.try_borrow_mut_data()?[..])?;
+    Ok(returned)
+}
@@ -1,26 +1,80 @@
 //! Account state structures for the staking program
 
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::{account_info::AccountInfo, pubkey::Pubkey, sysvar::Sysvar};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, incinerator, pubkey::Pubkey,
+    rent::Rent, sysvar::Sysvar,
+};
 
-use crate::error::StakingError;
-use crate::math::{exp_neg_time_ratio, wad_mul, U256};
+use chiefstaker_core::buckets::{self, AgeBucketSchedule, MAX_AGE_BUCKETS};
+use chiefstaker_core::state::{credit_ray_accumulator, pending_rewards_wad, rebase_exp_start_factor};
 
-/// Seed prefixes for PDAs
-pub const POOL_SEED: &[u8] = b"pool";
-pub const STAKE_SEED: &[u8] = b"stake";
-pub const TOKEN_VAULT_SEED: &[u8] = b"token_vault";
-pub const METADATA_SEED: &[u8] = b"metadata";
+use crate::error::{ErrorContext, StakingError};
+use crate::math::{
+    exp_neg_time_ratio, exp_time_ratio, wad_mul, wad_mul_u256, MAX_EXP_INPUT,
+    RAY_PER_WAD, REBASE_WARN_EXP_INPUT, U256, WAD, WAD_U256,
+};
+
+/// Payout rounding, shared with off-chain consumers through chiefstaker-core
+pub use chiefstaker_core::state::RoundingMode;
 
+/// Seed prefixes for PDAs
+pub use chiefstaker_interface::pda::{
+    AGE_BUCKETS_SEED, AGE_BUCKET_HISTORY_SEED, AUTHORITY_LOG_SEED, BONUS_BUDGET_SEED, DEBT_RECOUNT_SEED, DEPOSITOR_ALLOWLIST_SEED,
+    DISTRIBUTION_HISTORY_SEED, GAS_REBATE_SEED, LOCK_SCHEDULE_SEED, MATCHING_VAULT_SEED, METADATA_SEED, PAYOUT_SPLIT_SEED,
+    PENDING_DISTRIBUTIONS_SEED, POOL_SEED, POSITION_NFT_SEED, PROGRAM_CONFIG_SEED,
+    DEPOSITOR_RECORD_SEED, RECEIPT_MINT_SEED, RENT_SPONSOR_SEED, STAKE_ARCHIVE_SEED, STAKE_SEED, TOKEN_VAULT_SEED,
+    VOTING_SNAPSHOT_PAGE_SEED, VOTING_SNAPSHOT_SEED, WEIGHT_HISTORY_SEED, WEIGHT_TIERS_SEED,
+};
 
 /// Account discriminators
 pub const POOL_DISCRIMINATOR: [u8; 8] = [0xc7, 0x5f, 0x7e, 0x2d, 0x3b, 0x1a, 0x9c, 0x4e];
 pub const USER_STAKE_DISCRIMINATOR: [u8; 8] = [0xa3, 0x8b, 0x5d, 0x2f, 0x7c, 0x4a, 0x1e, 0x9d];
 pub const METADATA_DISCRIMINATOR: [u8; 8] = [0xd4, 0x2a, 0x8f, 0x6b, 0x51, 0x3c, 0xe7, 0x90];
+pub const WEIGHT_HISTORY_DISCRIMINATOR: [u8; 8] = [0xd5, 0xa4, 0x56, 0x05, 0x24, 0xfd, 0x9a, 0xb8];
+pub const LOCK_SCHEDULE_DISCRIMINATOR: [u8; 8] = [0x6e, 0x1f, 0xb3, 0x92, 0x0c, 0x47, 0xd8, 0x25];
+pub const AUTHORITY_LOG_DISCRIMINATOR: [u8; 8] = [0x3a, 0xc8, 0x71, 0xe4, 0x09, 0x5b, 0xf2, 0x6d];
+pub const PENDING_DISTRIBUTIONS_DISCRIMINATOR: [u8; 8] = [0xb5, 0x76, 0xe5, 0x0f, 0xb1, 0x1b, 0xd1, 0x3f];
+pub const PAYOUT_SPLIT_DISCRIMINATOR: [u8; 8] = [0x4c, 0x93, 0x2e, 0xa7, 0x5d, 0x18, 0xf6, 0x0b];
+pub const PROGRAM_CONFIG_DISCRIMINATOR: [u8; 8] = [0x91, 0x0e, 0x6a, 0xd2, 0x37, 0xc4, 0x58, 0xbf];
+pub const DEBT_RECOUNT_DISCRIMINATOR: [u8; 8] = [0x2d, 0xb8, 0x64, 0xf1, 0x0a, 0x97, 0x5e, 0xc3];
+pub const DEPOSITOR_ALLOWLIST_DISCRIMINATOR: [u8; 8] = [0x7a, 0x05, 0xd9, 0x3e, 0xc1, 0x68, 0x24, 0xb6];
+pub const BONUS_BUDGET_DISCRIMINATOR: [u8; 8] = [0x64, 0xb7, 0x24, 0xdf, 0x29, 0xee, 0x51, 0xe0];
+pub const MATCHING_VAULT_DISCRIMINATOR: [u8; 8] = [0xcd, 0x7e, 0x7f, 0x77, 0xd4, 0x85, 0x9b, 0xee];
+pub const RENT_SPONSOR_DISCRIMINATOR: [u8; 8] = [0x80, 0xde, 0x53, 0x7f, 0x25, 0xc3, 0x3b, 0xe0];
+pub const WEIGHT_TIERS_DISCRIMINATOR: [u8; 8] = [0xc4, 0xde, 0x8b, 0x62, 0x62, 0x0a, 0x5d, 0x9a];
+pub const GAS_REBATE_DISCRIMINATOR: [u8; 8] = [0x75, 0xf8, 0xd0, 0x07, 0x44, 0x5c, 0x5a, 0xd9];
+pub const DISTRIBUTION_HISTORY_DISCRIMINATOR: [u8; 8] = [0x1b, 0xe9, 0x42, 0xa6, 0x8d, 0x30, 0xc5, 0x7f];
+pub const STAKE_ARCHIVE_DISCRIMINATOR: [u8; 8] = [0x5e, 0x0d, 0xa1, 0x73, 0xc8, 0x2b, 0x94, 0xf6];
+pub const DEPOSITOR_RECORD_DISCRIMINATOR: [u8; 8] = [0xc3, 0x61, 0x2e, 0x9a, 0x07, 0xd4, 0x58, 0xb2];
+pub const VOTING_SNAPSHOT_DISCRIMINATOR: [u8; 8] = [0x8e, 0x27, 0xf4, 0x1b, 0x6a, 0xd0, 0x93, 0x5c];
+pub const VOTING_SNAPSHOT_PAGE_DISCRIMINATOR: [u8; 8] = [0x19, 0xb6, 0x4d, 0xe2, 0x85, 0x3f, 0x70, 0xca];
+pub const AGE_BUCKETS_DISCRIMINATOR: [u8; 8] = [0x62, 0x0f, 0xd7, 0x3c, 0xa9, 0x44, 0xe1, 0x58];
+pub const AGE_BUCKET_HISTORY_DISCRIMINATOR: [u8; 8] = [0xe6, 0x31, 0x8a, 0x5f, 0x0d, 0xb2, 0x77, 0x9e];
+
+/// Zeroed bytes at the end of StakingPool for future fields
+pub const POOL_RESERVED_LEN: usize = 249;
+
+/// Zeroed bytes at the end of UserStake for future fields
+pub const USER_STAKE_RESERVED_LEN: usize = 73;
+
+/// Recent deposits tracked per position for the pool lock
+pub const RECENT_DEPOSIT_SLOTS: usize = 4;
+
+// A field appended after `reserved` instead of carved out of it grows the
+// account and breaks the frozen sizes
+const _: () = assert!(
+    StakingPool::LEN == StakingPool::ACCOUNT_SIZE,
+    "StakingPool size changed: take new fields from `reserved`"
+);
+const _: () = assert!(
+    UserStake::LEN == UserStake::ACCOUNT_SIZE,
+    "UserStake size changed: take new fields from `reserved`"
+);
 
 /// Staking pool state account
-/// PDA: ["pool", mint]
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+/// PDA: ["pool", mint] for pool_id 0, ["pool", mint, pool_id (u64 LE)] otherwise
+#[derive(BorshSerialize, Debug, Clone)]
 pub struct StakingPool {
     /// Discriminator for account type identification
     pub discriminator: [u8; 8],
@@ -52,7 +106,9 @@ pub struct StakingPool {
     /// All exp_start_factors are relative to this time
     pub base_time: i64,
 
-    /// Accumulated reward per weighted share (scaled by 10^18)
+    /// Accumulated reward per weighted share (scaled by 10^18). Positions
+    /// settle against it; the accumulator itself is kept at 10^27 with
+    /// acc_rps_ray_remainder (see acc_rps_ray)
     pub acc_reward_per_weighted_share: u128,
 
     /// Last time rewards were updated
@@ -79,17 +135,266 @@ pub struct StakingPool {
 
     /// Sum of all active users' reward_debt values.
     /// Maintained incrementally by stake/unstake/claim instructions.
-    /// Used by FixTotalRewardDebt to compute stranded rewards from pool state alone.
-    /// Starts at 0 for existing pools (bootstraps conservatively — under-recovery is safe).
+    /// Used to compute stranded rewards from pool state alone.
+    /// Starts at 0 for existing pools (bootstraps conservatively — under-recovery is safe);
+    /// RecountRewardDebt rebuilds it from the stake accounts.
     pub total_reward_debt: u128,
 
     /// Total lamports owed to residual claimants (users who fully unstaked
     /// but couldn't be fully paid because the pool lacked SOL).
     /// Tracked separately from `total_reward_debt` because residual users have
     /// amount=0 (no allocation in `total_staked * acc_rps`), so including their
-    /// debt in `total_reward_debt` would break the FixTotalRewardDebt formula.
+    /// debt in `total_reward_debt` would break the stranded-rewards formula.
     /// Starts at 0 for existing pools (binary-compatible with old `_reserved3`).
     pub total_residual_unpaid: u64,
+
+    /// When true, DepositRewards/SyncRewards accept SOL but leave it
+    /// undistributed (acc_rps frozen). Claims of already-accrued rewards
+    /// still work. Toggled by the authority via SetRewardsPaused.
+    /// Defaults to false for legacy pools (field absent).
+    pub rewards_paused: bool,
+
+    /// Reward value lost to floor division when crediting the accumulator
+    /// (lamports × WAD). The lamports are still in the pool; SweepDust folds
+    /// this back into acc_reward_per_weighted_share once it is large enough.
+    pub accumulated_dust_wad: u128,
+
+    /// Distinguishes multiple pools for the same mint. Pool 0 lives at the
+    /// original ["pool", mint] address; other ids at ["pool", mint, pool_id].
+    /// Defaults to 0 for legacy pools (field absent).
+    pub pool_id: u64,
+
+    /// Token 2022 receipt mint (PDA: ["receipt_mint", pool]), minted 1:1 on
+    /// stake and burned on unstake. Default pubkey when the pool was created
+    /// without receipts (including all legacy pools).
+    pub receipt_mint: Pubkey,
+
+    /// Where RefundUndistributed sends SOL left idle in a pool without
+    /// stakers. Set by the authority via SetRefundPolicy; default = disabled.
+    pub refund_address: Pubkey,
+
+    /// How long undistributed SOL must sit idle before it can be refunded
+    /// (0 = disabled)
+    pub refund_after_seconds: u64,
+
+    /// When undistributed SOL started waiting for stakers (0 = not waiting).
+    /// Only tracked while a refund policy is set.
+    pub undistributed_since: i64,
+
+    /// Deposits and syncs are deferred while total_staked is below this
+    /// (raw token units), so a dust stake can't capture a whole deposit.
+    /// Set via SetDistributionThreshold; 0 for legacy pools (any stake).
+    pub min_distribution_stake: u64,
+
+    /// Share of unstaked tokens (basis points, at most MAX_UNSTAKE_FEE_BPS)
+    /// sent to `unstake_fee_destination` instead of the user. 0 = no fee.
+    pub unstake_fee_bps: u16,
+
+    /// Token account (pool mint) receiving unstake fees. Set via SetUnstakeFee.
+    pub unstake_fee_destination: Pubkey,
+
+    /// Whether an AuthorityLog account exists. When set, every authority
+    /// instruction must pass it (after the system program) and appends to it.
+    pub has_authority_log: bool,
+
+    /// Incremented on every write, so indexers consuming account updates
+    /// can order them and detect missed writes. Stays 0 until a legacy
+    /// pool is grown to the current size.
+    pub state_nonce: u64,
+
+    /// Grace window (seconds) after the cooldown during which an unstake
+    /// request can be completed; afterwards it lapses and the stake resumes
+    /// normal status. Set via SetUnstakeRequestExpiry; 0 = never expires.
+    pub unstake_request_expiry_seconds: u64,
+
+    /// Protocol fee (basis points) taken from each reward distribution.
+    /// Snapshot of ProgramConfig.protocol_fee_bps, refreshed by
+    /// CollectProtocolFees. 0 for legacy pools.
+    pub protocol_fee_bps: u16,
+
+    /// Protocol fee lamports held by the pool until CollectProtocolFees
+    /// sends them to the treasury. Counted in last_synced_lamports.
+    pub protocol_fees_owed: u64,
+
+    /// How reward payouts round WAD-scaled entitlements to whole lamports
+    /// (see [`RoundingMode`]). Set via SetRoundingMode; 0 (floor) for
+    /// legacy pools.
+    pub rounding_mode: u8,
+
+    /// Whether DepositRewards/DepositRewardsSplit only accept depositors
+    /// listed in a DepositorAllowlist account, which they must then pass.
+    /// Set via SetDepositorAllowlist; false (permissionless) for legacy pools.
+    pub has_depositor_allowlist: bool,
+
+    /// Age credited by ExtendLock, in basis points of the added lock time
+    /// (10000 = one second older per second locked). At most
+    /// MAX_LOCK_BOOST_BPS. Set via SetLockBoost; 0 disables ExtendLock.
+    pub lock_boost_bps: u16,
+
+    /// One-time bonus paid from the BonusBudget when a position reaches
+    /// 3 tau of age, in basis points of its lifetime claimed rewards.
+    /// Set via SetMaturityBonus; 0 (no bonus) for legacy pools.
+    pub maturity_bonus_bps: u16,
+
+    /// Window after pool creation during which new positions are weighted
+    /// as if staked at creation. At most MAX_BOOTSTRAP_PERIOD_SECONDS.
+    /// Set via SetBootstrapPeriod; 0 (none) for legacy pools.
+    pub bootstrap_period_seconds: u64,
+
+    /// Whether distributions are matched from a MatchingVault account, which
+    /// DepositRewards and SyncRewards must then pass.
+    /// Set via SetRewardMatching; false for legacy pools.
+    pub has_matching_vault: bool,
+
+    /// Concurrent merkle tree (spl-account-compression) holding compressed
+    /// positions, with the pool PDA as tree authority.
+    /// Set once via InitCompressionTree; default (none) for legacy pools.
+    pub compression_tree: Pubkey,
+
+    /// Leaves appended to the compression tree so far, which is also the
+    /// index of the next compressed position
+    pub compressed_leaf_count: u64,
+
+    /// Tokens held by compressed positions (included in total_staked)
+    pub compressed_staked: u128,
+
+    /// reward_debt of compressed positions (included in total_reward_debt)
+    pub compressed_reward_debt: u128,
+
+    /// Whether new stake accounts get their rent from a RentSponsorVault
+    /// account, which Stake and sponsored CloseStakeAccount must then pass.
+    /// Set via SetRentSponsor; false for legacy pools.
+    pub has_rent_sponsor: bool,
+
+    /// Minimum seconds between reward distributions; SOL arriving sooner is
+    /// left pending for the next one. Set via SetMinSyncInterval; 0 disables.
+    pub min_sync_interval_seconds: u64,
+
+    /// Unix timestamp of the last DepositRewards/SyncRewards that distributed
+    /// (0 if none since the interval fields were added)
+    pub last_distribution_time: i64,
+
+    /// Seconds over which each distribution vests to stakers; claims only
+    /// see the vested part of acc_reward_per_weighted_share. Set via
+    /// SetClaimVesting; 0 (instant) for legacy pools.
+    pub claim_vesting_seconds: u64,
+
+    /// Part of acc_reward_per_weighted_share still vesting at vesting_start;
+    /// it vests linearly until vesting_end
+    pub vesting_acc_rps: u128,
+
+    /// Start of the current vesting schedule (last vesting distribution)
+    pub vesting_start: i64,
+
+    /// End of the current vesting schedule
+    pub vesting_end: i64,
+
+    /// Pyth or Switchboard price account for the pool mint, used by
+    /// GetPoolStats for USD figures. Set via SetPriceOracle; default
+    /// pubkey = none.
+    pub price_oracle: Pubkey,
+
+    /// Whether claims get a gas rebate from a GasRebateVault account, which
+    /// claims must then pass. Set via SetClaimRebate; false for legacy pools.
+    pub has_gas_rebate_vault: bool,
+
+    /// Share of each reward claim (basis points, at most
+    /// MAX_CLAIM_BURN_BPS) sent to `claim_burn_destination` instead of the
+    /// claimer. Set via SetClaimBurn; 0 disables.
+    pub claim_burn_bps: u16,
+
+    /// Account receiving the burned share of claims: the incinerator. Set
+    /// via SetClaimBurn.
+    pub claim_burn_destination: Pubkey,
+
+    /// Lamports held above rent and last_synced_lamports (not yet credited
+    /// to the accumulator) as of the last deposit, sync or refund, computed
+    /// against the account's actual rent-exempt minimum. 0 for legacy pools
+    /// until they grow.
+    pub undistributed_lamports: u64,
+
+    /// When set, new rewards first cover total_residual_unpaid not yet
+    /// earmarked (residual_reserved), and only the rest moves the
+    /// accumulator. Set via SetResidualsFirst; false for legacy pools.
+    pub residuals_first: bool,
+
+    /// Lamports set aside for residual claimants by residuals_first. Only
+    /// residual claims may spend them; paying residuals releases them.
+    pub residual_reserved: u64,
+
+    /// Smallest claim paid out (at most MAX_MIN_CLAIM_LAMPORTS); smaller
+    /// claims are deferred and keep accruing. Residual claims are exempt.
+    /// Set via SetMinClaimLamports; 0 (no minimum) for legacy pools.
+    pub min_claim_lamports: u64,
+
+    /// Whether every distribution is checkpointed in DistributionHistory
+    /// pages, which DepositRewards and SyncRewards must then pass. Set via
+    /// InitDistributionHistory; false for legacy pools.
+    pub has_distribution_history: bool,
+
+    /// Distribution checkpoints written so far; the next one goes to page
+    /// distribution_checkpoints / DistributionHistory::CAPACITY
+    pub distribution_checkpoints: u64,
+
+    /// acc_reward_per_weighted_share increments of past distributions, each
+    /// decayed by e^(-age / REWARD_RATE_WINDOW_SECONDS) as of
+    /// reward_rate_time (see trailing_reward_rate). 0 for legacy pools.
+    pub reward_rate_sum: u128,
+
+    /// When reward_rate_sum was last updated
+    pub reward_rate_time: i64,
+
+    /// Whether DepositRewards and DepositRewardsSplit add each deposit to
+    /// the depositor's DepositorRecord, which they must then pass. Set via
+    /// SetDepositorTracking; false for legacy pools.
+    pub track_depositors: bool,
+
+    /// (now - base_time) / tau, in basis points of tau, past which SyncPool
+    /// rebases; below it SyncPool is a no-op unless sum_stake_exp needs a
+    /// rebase. Set via SetRebaseThreshold; 0 (REBASE_WARN_EXP_INPUT) for
+    /// legacy pools.
+    pub rebase_threshold_bps: u32,
+
+    /// Digits of the accumulator below acc_reward_per_weighted_share's
+    /// WAD scale (RAY units, < RAY_PER_WAD), carried between distributions
+    /// so small ones to a large total_staked aren't floored away. 0 for
+    /// legacy pools, whose accumulator was kept at WAD.
+    pub acc_rps_ray_remainder: u64,
+
+    /// Number of MigrateVault runs: token_vault is the PDA of this
+    /// generation (see derive_token_vault_pda_for_generation). 0 for pools
+    /// still on their original vault.
+    pub vault_generation: u32,
+
+    /// Principal the pool lock never holds: lock_duration_seconds only
+    /// applies to the part of a position above it, so positions of at most
+    /// this many tokens are never locked by the pool. Set via
+    /// SetLockExemptAmount; 0 (lock everything) for legacy pools.
+    pub lock_exempt_amount: u64,
+
+    /// When set, DepositRewards and SyncRewards must pass the instructions
+    /// sysvar, so a stake earlier in the same transaction can't be skipped
+    /// past the sandwich guard by leaving it out. Set via
+    /// SetStakeGuardRequired; false (sysvar optional) for legacy pools.
+    pub stake_guard_required: bool,
+
+    /// WAD-scaled lamports that fund round-ups under the nearest rounding
+    /// modes: payouts rounded down add their fraction, payouts rounded up
+    /// draw theirs, and round-ups it can't cover pay the floor. Held in the
+    /// pool balance and owed to no position. 0 for legacy pools.
+    pub rounding_reserve_wad: u128,
+
+    /// Weighting engine (see [`WeightEngine`]), fixed at initialization:
+    /// pools created by InitializeBucketedPool weight stake by age bucket
+    /// (state in the AgeBuckets account) instead of the exponential.
+    /// 0 (exponential) for legacy pools.
+    pub weight_engine: u8,
+
+    /// Space for future fields. A new field takes its bytes from the front
+    /// of this array (shrinking POOL_RESERVED_LEN by its size), so LEN and
+    /// every existing offset stay put; it reads as zero on existing pools,
+    /// so its zero value must mean "unset". Always written as zeros.
+    pub reserved: [u8; POOL_RESERVED_LEN],
 }
 
 impl StakingPool {
@@ -112,7 +417,125 @@ impl StakingPool {
         8 +  // unstake_cooldown_seconds
         8 +  // initial_base_time
         16 + // total_reward_debt
-        8;   // total_residual_unpaid
+        8 +  // total_residual_unpaid
+        1 +  // rewards_paused
+        16 + // accumulated_dust_wad
+        8 +  // pool_id
+        32 + // receipt_mint
+        32 + // refund_address
+        8 +  // refund_after_seconds
+        8 +  // undistributed_since
+        8 +  // min_distribution_stake
+        2 +  // unstake_fee_bps
+        32 + // unstake_fee_destination
+        1 +  // has_authority_log
+        8 +  // state_nonce
+        8 +  // unstake_request_expiry_seconds
+        2 +  // protocol_fee_bps
+        8 +  // protocol_fees_owed
+        1 +  // rounding_mode
+        1 +  // has_depositor_allowlist
+        2 +  // lock_boost_bps
+        2 +  // maturity_bonus_bps
+        8 +  // bootstrap_period_seconds
+        1 +  // has_matching_vault
+        32 + // compression_tree
+        8 +  // compressed_leaf_count
+        16 + // compressed_staked
+        16 + // compressed_reward_debt
+        1 +  // has_rent_sponsor
+        8 +  // min_sync_interval_seconds
+        8 +  // last_distribution_time
+        8 +  // claim_vesting_seconds
+        16 + // vesting_acc_rps
+        8 +  // vesting_start
+        8 +  // vesting_end
+        32 + // price_oracle
+        1 +  // has_gas_rebate_vault
+        2 +  // claim_burn_bps
+        32 + // claim_burn_destination
+        8 +  // undistributed_lamports
+        1 +  // residuals_first
+        8 +  // residual_reserved
+        8 +  // min_claim_lamports
+        1 +  // has_distribution_history
+        8 +  // distribution_checkpoints
+        16 + // reward_rate_sum
+        8 +  // reward_rate_time
+        1 +  // track_depositors
+        4 +  // rebase_threshold_bps
+        8 +  // acc_rps_ray_remainder
+        4 +  // vault_generation
+        8 +  // lock_exempt_amount
+        1 +  // stake_guard_required
+        16 + // rounding_reserve_wad
+        1 +  // weight_engine
+        POOL_RESERVED_LEN; // reserved
+
+    /// Allocated size of current pool accounts, frozen: new fields are
+    /// carved out of `reserved` instead of growing the account
+    pub const ACCOUNT_SIZE: usize = 1024;
+
+    /// Legacy account size (before rewards_paused was added)
+    pub const LEGACY_LEN: usize = 289;
+
+    /// Upper bound on unstake_fee_bps (5%)
+    pub const MAX_UNSTAKE_FEE_BPS: u16 = 500;
+
+    /// Upper bound on lock_boost_bps (age never runs ahead of the lock)
+    pub const MAX_LOCK_BOOST_BPS: u16 = 10_000;
+
+    /// Upper bound on maturity_bonus_bps (100% of lifetime claims)
+    pub const MAX_MATURITY_BONUS_BPS: u16 = 10_000;
+
+    /// Age, in multiples of tau, at which a position earns the maturity
+    /// bonus (weight is ~95% of the maximum)
+    pub const MATURITY_TAU_MULTIPLE: u64 = 3;
+
+    /// Upper bound on bootstrap_period_seconds (30 days)
+    pub const MAX_BOOTSTRAP_PERIOD_SECONDS: u64 = 30 * 24 * 60 * 60;
+
+    /// Upper bound on MatchingVault::match_bps (1:1 match)
+    pub const MAX_MATCH_BPS: u16 = 10_000;
+
+    /// Upper bound on min_sync_interval_seconds (1 day)
+    pub const MAX_MIN_SYNC_INTERVAL_SECONDS: u64 = 24 * 60 * 60;
+
+    /// Upper bound on claim_vesting_seconds (30 days)
+    pub const MAX_CLAIM_VESTING_SECONDS: u64 = 30 * 24 * 60 * 60;
+
+    /// Upper bound on GasRebateVault::rebate_lamports (20 signatures' base fee)
+    pub const MAX_CLAIM_REBATE_LAMPORTS: u64 = 100_000;
+
+    /// Upper bound on claim_burn_bps (a tenth of each claim)
+    pub const MAX_CLAIM_BURN_BPS: u16 = 1_000;
+
+    /// Longest lock_duration_seconds / unstake_cooldown_seconds a pool may
+    /// keep when its authority is renounced without an override (7 days)
+    pub const MAX_RENOUNCE_SAFE_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+    /// Time constant of the trailing reward rate (7 days)
+    pub const REWARD_RATE_WINDOW_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+    /// Upper bound on min_claim_lamports (0.1 SOL)
+    pub const MAX_MIN_CLAIM_LAMPORTS: u64 = 100_000_000;
+
+    /// Upper bound on rebase_threshold_bps (MAX_EXP_INPUT, 42 tau)
+    pub const MAX_REBASE_THRESHOLD_BPS: u32 = (MAX_EXP_INPUT / (WAD / 10_000)) as u32;
+
+    /// Byte offset of `mint`, for getProgramAccounts memcmp filters
+    /// (enumerate all pools of a mint: discriminator at 0 + mint at 8)
+    pub const MINT_OFFSET: usize = 8;
+
+    /// Byte offset of `authority` (after token_vault and reward_vault).
+    /// Fields up to here are in every pool version, so the offsets hold for
+    /// legacy accounts too; new fields are only ever appended.
+    pub const AUTHORITY_OFFSET: usize = 104;
+
+    /// Byte offset of `weight_engine`, so the entrypoint can route a pool to
+    /// its engine without deserializing it. Legacy pools are shorter and
+    /// use the exponential.
+    pub const WEIGHT_ENGINE_OFFSET: usize = 774;
 
     /// Create a new staking pool
     pub fn new(
@@ -144,6 +567,59 @@ impl StakingPool {
             initial_base_time: 0,
             total_reward_debt: 0,
             total_residual_unpaid: 0,
+            rewards_paused: false,
+            accumulated_dust_wad: 0,
+            pool_id: 0,
+            receipt_mint: Pubkey::default(),
+            refund_address: Pubkey::default(),
+            refund_after_seconds: 0,
+            undistributed_since: 0,
+            min_distribution_stake: 0,
+            unstake_fee_bps: 0,
+            unstake_fee_destination: Pubkey::default(),
+            has_authority_log: false,
+            state_nonce: 0,
+            unstake_request_expiry_seconds: 0,
+            protocol_fee_bps: 0,
+            protocol_fees_owed: 0,
+            rounding_mode: 0,
+            has_depositor_allowlist: false,
+            lock_boost_bps: 0,
+            maturity_bonus_bps: 0,
+            bootstrap_period_seconds: 0,
+            has_matching_vault: false,
+            compression_tree: Pubkey::default(),
+            compressed_leaf_count: 0,
+            compressed_staked: 0,
+            compressed_reward_debt: 0,
+            has_rent_sponsor: false,
+            min_sync_interval_seconds: 0,
+            last_distribution_time: 0,
+            claim_vesting_seconds: 0,
+            vesting_acc_rps: 0,
+            vesting_start: 0,
+            vesting_end: 0,
+            price_oracle: Pubkey::default(),
+            has_gas_rebate_vault: false,
+            claim_burn_bps: 0,
+            claim_burn_destination: Pubkey::default(),
+            undistributed_lamports: 0,
+            residuals_first: false,
+            residual_reserved: 0,
+            min_claim_lamports: 0,
+            has_distribution_history: false,
+            distribution_checkpoints: 0,
+            reward_rate_sum: 0,
+            reward_rate_time: 0,
+            track_depositors: false,
+            rebase_threshold_bps: 0,
+            acc_rps_ray_remainder: 0,
+            vault_generation: 0,
+            lock_exempt_amount: 0,
+            stake_guard_required: false,
+            rounding_reserve_wad: 0,
+            weight_engine: WeightEngine::Exponential as u8,
+            reserved: [0u8; POOL_RESERVED_LEN],
         }
     }
 
@@ -157,443 +633,5578 @@ impl StakingPool {
         self.sum_stake_exp = value.to_le_bytes();
     }
 
+    /// Unix timestamp of pool creation: the base_time before the first
+    /// rebase
+    pub fn creation_time(&self) -> i64 {
+        if self.initial_base_time != 0 {
+            self.initial_base_time
+        } else {
+            self.base_time
+        }
+    }
+
+    /// stake_time and exp_start_factor for a position opened at
+    /// `current_time`. Inside the bootstrap window it counts as staked at
+    /// pool creation, so early stakers all start level with the first one.
+    pub fn new_stake_start(&self, current_time: i64) -> Result<(i64, u128), StakingError> {
+        let created = self.creation_time();
+        let bootstrap_end = created
+            .saturating_add(self.bootstrap_period_seconds.min(i64::MAX as u64) as i64);
+        let stake_time = if current_time < bootstrap_end { created } else { current_time };
+
+        // e^((stake_time - base_time)/tau); below WAD if a rebase has
+        // already moved base_time past creation
+        let exp_start_factor = if stake_time >= self.base_time {
+            exp_time_ratio(stake_time - self.base_time, self.tau_seconds)?
+        } else {
+            exp_neg_time_ratio(self.base_time - stake_time, self.tau_seconds)?
+        };
+        Ok((stake_time, exp_start_factor))
+    }
+
     /// Check if pool is initialized
     pub fn is_initialized(&self) -> bool {
         self.discriminator == POOL_DISCRIMINATOR
     }
 
+    /// Whether the pool weights stake by age bucket instead of the
+    /// exponential
+    pub fn uses_age_buckets(&self) -> bool {
+        self.weight_engine == WeightEngine::AgeBuckets as u8
+    }
+
     /// Check if authority has been renounced (set to default/zero pubkey)
     pub fn is_authority_renounced(&self) -> bool {
         self.authority == Pubkey::default()
     }
 
-    /// Derive pool PDA
+    /// Check if the pool has a registered price oracle
+    pub fn has_price_oracle(&self) -> bool {
+        self.price_oracle != Pubkey::default()
+    }
+
+    /// Check if positions can be compressed into a merkle tree
+    pub fn has_compression_tree(&self) -> bool {
+        self.compression_tree != Pubkey::default()
+    }
+
+    /// Derive pool PDA (pool_id 0)
     pub fn derive_pda(mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
         Pubkey::find_program_address(&[POOL_SEED, mint.as_ref()], program_id)
     }
 
+    /// Derive the PDA of pool `pool_id` for `mint`
+    pub fn derive_pda_with_id(mint: &Pubkey, pool_id: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+        if pool_id == 0 {
+            return Self::derive_pda(mint, program_id);
+        }
+        Pubkey::find_program_address(
+            &[POOL_SEED, mint.as_ref(), &pool_id.to_le_bytes()],
+            program_id,
+        )
+    }
+
+    /// Pool PDA signer seeds. `pool_id_bytes` and `bump` are caller-owned
+    /// buffers (pool_id.to_le_bytes() and [bump]) that the seeds borrow.
+    pub fn signer_seeds<'a>(
+        &'a self,
+        pool_id_bytes: &'a [u8; 8],
+        bump: &'a [u8; 1],
+    ) -> Vec<&'a [u8]> {
+        if self.pool_id == 0 {
+            vec![POOL_SEED, self.mint.as_ref(), bump]
+        } else {
+            vec![POOL_SEED, self.mint.as_ref(), pool_id_bytes, bump]
+        }
+    }
+
     /// Derive token vault PDA
     pub fn derive_token_vault_pda(pool: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
         Pubkey::find_program_address(&[TOKEN_VAULT_SEED, pool.as_ref()], program_id)
     }
 
-}
-
-/// User stake account
-/// PDA: ["stake", pool, owner]
-#[derive(BorshSerialize, Debug, Clone)]
-pub struct UserStake {
-    /// Discriminator for account type identification
-    pub discriminator: [u8; 8],
+    /// Derive the token vault PDA of `generation`: the original vault for
+    /// 0, ["token_vault", pool, generation (u32 LE)] after MigrateVault
+    pub fn derive_token_vault_pda_for_generation(
+        pool: &Pubkey,
+        generation: u32,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        if generation == 0 {
+            return Self::derive_token_vault_pda(pool, program_id);
+        }
+        Pubkey::find_program_address(
+            &[TOKEN_VAULT_SEED, pool.as_ref(), &generation.to_le_bytes()],
+            program_id,
+        )
+    }
 
-    /// Owner of this stake
-    pub owner: Pubkey,
+    /// Derive receipt mint PDA
+    pub fn derive_receipt_mint_pda(pool: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[RECEIPT_MINT_SEED, pool.as_ref()], program_id)
+    }
 
-    /// Pool this stake belongs to
-    pub pool: Pubkey,
+    /// Check if the pool issues receipt tokens
+    pub fn has_receipt_mint(&self) -> bool {
+        self.receipt_mint != Pubkey::default()
+    }
 
-    /// Amount of tokens staked
-    pub amount: u64,
+    /// Check if too little is staked to distribute rewards (no stakers, or
+    /// total_staked below min_distribution_stake)
+    pub fn below_distribution_threshold(&self) -> bool {
+        self.total_staked == 0 || self.total_staked < self.min_distribution_stake as u128
+    }
 
-    /// Unix timestamp when stake began
-    pub stake_time: i64,
+    /// Check if unstakes pay a fee (and must pass the fee destination)
+    pub fn has_unstake_fee(&self) -> bool {
+        self.unstake_fee_bps > 0 && self.unstake_fee_destination != Pubkey::default()
+    }
 
-    /// e^((stake_time - base_time) / tau) at time of staking, WAD-scaled
-    /// Used to track contribution to sum_stake_exp
-    pub exp_start_factor: u128,
+    /// Check if claims burn a share (and must pass the burn destination).
+    /// Only the incinerator burns: any other stored destination is ignored.
+    pub fn has_claim_burn(&self) -> bool {
+        self.claim_burn_bps > 0 && self.claim_burn_destination == incinerator::id()
+    }
 
-    /// Reward debt encoding an acc_rps snapshot for pending reward calculation.
-    /// Encodes: reward_debt = wad_mul(amount * WAD, snapshot_acc_rps).
-    /// Pending = user_weighted * (current_acc_rps - snapshot_acc_rps).
-    /// When amount == 0 (post-full-unstake), reinterpreted as unclaimed WAD-scaled rewards.
-    pub reward_debt: u128,
+    /// Lamports burned out of a `amount` lamport claim, rounded down and
+    /// capped at MAX_CLAIM_BURN_BPS regardless of the stored setting
+    pub fn claim_burn(&self, amount: u64) -> u64 {
+        if !self.has_claim_burn() {
+            return 0;
+        }
+        let bps = self.claim_burn_bps.min(Self::MAX_CLAIM_BURN_BPS) as u128;
+        (amount as u128 * bps / 10_000) as u64
+    }
 
-    /// PDA bump seed
-    pub bump: u8,
+    /// Whether a claim of `pending_lamports` is below min_claim_lamports and
+    /// should be deferred. Residual claims are never deferred: a fully
+    /// unstaked position has nothing left accruing to reach the minimum.
+    pub fn defers_claim(&self, pending_lamports: u128, residual_claim: bool) -> bool {
+        !residual_claim && pending_lamports < self.min_claim_lamports as u128
+    }
 
-    /// Pending unstake request amount (0 = no pending request)
-    pub unstake_request_amount: u64,
+    /// Whether lock_duration_seconds and unstake_cooldown_seconds are both
+    /// within MAX_RENOUNCE_SAFE_SECONDS, so freezing them by renouncing the
+    /// authority can't trap stakers for long
+    pub fn renounce_safe(&self) -> bool {
+        self.lock_duration_seconds <= Self::MAX_RENOUNCE_SAFE_SECONDS
+            && self.unstake_cooldown_seconds <= Self::MAX_RENOUNCE_SAFE_SECONDS
+    }
 
-    /// Timestamp when unstake was requested
-    pub unstake_request_time: i64,
+    /// Fee withheld from unstaking `amount` tokens, rounded down and capped
+    /// at MAX_UNSTAKE_FEE_BPS regardless of the stored setting
+    pub fn unstake_fee(&self, amount: u64) -> u64 {
+        if !self.has_unstake_fee() {
+            return 0;
+        }
+        let bps = self.unstake_fee_bps.min(Self::MAX_UNSTAKE_FEE_BPS) as u128;
+        (amount as u128 * bps / 10_000) as u64
+    }
 
-    /// Timestamp of most recent stake deposit (for lock duration checks)
-    /// Falls back to stake_time when 0 (for existing accounts)
-    pub last_stake_time: i64,
+    /// Protocol fee taken from a distribution of `lamports`, capped at
+    /// ProgramConfig::MAX_PROTOCOL_FEE_BPS regardless of the snapshot
+    pub fn protocol_fee(&self, lamports: u64) -> u64 {
+        let bps = self.protocol_fee_bps.min(ProgramConfig::MAX_PROTOCOL_FEE_BPS) as u128;
+        (lamports as u128 * bps / 10_000) as u64
+    }
 
-    /// Pool base_time when exp_start_factor was last calibrated.
-    /// 0 = legacy account (pre-rebase-aware); treated as matching the pool's
-    /// initial_base_time or current base_time if no rebase has occurred.
-    pub base_time_snapshot: i64,
+    /// Rounding applied to reward payouts (unknown values fall back to floor)
+    pub fn rounding(&self) -> RoundingMode {
+        RoundingMode::from_u8(self.rounding_mode).unwrap_or(RoundingMode::Floor)
+    }
 
-    /// Cumulative SOL rewards claimed by this user (lamports).
-    /// Defaults to 0 for legacy 153-byte accounts (populated on first realloc).
-    pub total_rewards_claimed: u64,
+    /// Lamports paid for `pending_wad` rewards under the pool's rounding
+    /// mode, with round-ups funded from rounding_reserve_wad
+    pub fn payout_lamports(&self, pending_wad: u128) -> u128 {
+        self.rounding().to_lamports_funded(pending_wad, self.rounding_reserve_wad)
+    }
 
-    /// Cumulative WAD-scaled rewards already paid out for the current position.
-    /// Used to make claims frequency-independent: pending = full_entitlement - claimed_rewards_wad.
-    /// Reset to 0 on stake (add-more) and unstake (partial/full) when the position is restructured.
-    /// Defaults to 0 for existing accounts (correct: first claim gets full pending).
-    pub claimed_rewards_wad: u128,
-}
+    /// Record a payout of `paid` lamports for `pending_wad` rewards,
+    /// moving its rounding difference through rounding_reserve_wad.
+    /// Returns the WAD-scaled rewards it settles (see [`RoundingMode::settle`]).
+    pub fn settle_payout(&mut self, pending_wad: u128, paid: u64) -> u128 {
+        self.rounding().settle(pending_wad, paid as u128, &mut self.rounding_reserve_wad)
+    }
 
-impl UserStake {
-    /// Size of the account in bytes
-    pub const LEN: usize = 8 +  // discriminator
-        32 + // owner
-        32 + // pool
-        8 +  // amount
-        8 +  // stake_time
-        16 + // exp_start_factor
-        16 + // reward_debt
-        1 +  // bump
-        8 +  // unstake_request_amount
-        8 +  // unstake_request_time
-        8 +  // last_stake_time
-        8 +  // base_time_snapshot
-        8 +  // total_rewards_claimed
-        16;  // claimed_rewards_wad
+    /// Check if idle undistributed SOL can be refunded
+    pub fn has_refund_policy(&self) -> bool {
+        self.refund_after_seconds > 0 && self.refund_address != Pubkey::default()
+    }
 
-    /// Legacy account size (before claimed_rewards_wad was added)
-    pub const LEGACY_LEN: usize = Self::LEN - 16;
+    /// Whether a distribution at `current_time` comes within
+    /// min_sync_interval_seconds of the last one (and must be deferred)
+    pub fn distribution_too_soon(&self, current_time: i64) -> bool {
+        self.min_sync_interval_seconds > 0
+            && self.last_distribution_time != 0
+            && current_time.saturating_sub(self.last_distribution_time)
+                < self.min_sync_interval_seconds as i64
+    }
 
-    /// Create a new user stake
-    pub fn new(
-        owner: Pubkey,
-        pool: Pubkey,
-        amount: u64,
-        stake_time: i64,
-        exp_start_factor: u128,
-        bump: u8,
-        base_time_snapshot: i64,
-    ) -> Self {
-        Self {
-            discriminator: USER_STAKE_DISCRIMINATOR,
-            owner,
-            pool,
-            amount,
-            stake_time,
-            exp_start_factor,
-            reward_debt: 0,
-            bump,
-            unstake_request_amount: 0,
-            unstake_request_time: 0,
-            last_stake_time: stake_time,
-            base_time_snapshot,
-            total_rewards_claimed: 0,
-            claimed_rewards_wad: 0,
+    /// Start the idle-refund timer for SOL deferred for lack of stake.
+    /// Returns true if the pool changed (a policy is set and the timer
+    /// wasn't running).
+    pub fn mark_undistributed(&mut self, current_time: i64) -> bool {
+        if !self.has_refund_policy() || self.undistributed_since != 0 {
+            return false;
         }
+        self.undistributed_since = current_time;
+        true
     }
 
-    /// Check if stake is initialized
-    pub fn is_initialized(&self) -> bool {
-        self.discriminator == USER_STAKE_DISCRIMINATOR
+    /// Exponent input (current_time - base_time) / tau, WAD-scaled.
+    /// Stakes fail with PoolRequiresSync once this exceeds MAX_EXP_INPUT.
+    pub fn exp_input_wad(&self, current_time: i64) -> Result<u128, StakingError> {
+        let time_since_base = current_time.saturating_sub(self.base_time).max(0);
+        Ok((time_since_base as u128)
+            .checked_mul(WAD)
+            .ok_or(StakingError::MathOverflow)?
+            / (self.tau_seconds as u128))
     }
 
-    /// Derive user stake PDA
-    pub fn derive_pda(pool: &Pubkey, owner: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
-        Pubkey::find_program_address(&[STAKE_SEED, pool.as_ref(), owner.as_ref()], program_id)
+    /// Shift base_time to `current_time` and scale sum_stake_exp down by
+    /// e^(-(current_time - base_time)/tau). User stakes catch up lazily via
+    /// `UserStake::sync_to_pool`. Returns the decay factor, or None if no
+    /// time has passed since base_time.
+    pub fn rebase(&mut self, current_time: i64) -> Result<Option<u128>, StakingError> {
+        let time_delta = current_time.saturating_sub(self.base_time);
+        if time_delta <= 0 {
+            return Ok(None);
+        }
+
+        // Record the original base_time before first rebase so legacy UserStake
+        // accounts can be lazily adjusted (their exp_start_factor is relative
+        // to the initial base_time).
+        if self.initial_base_time == 0 {
+            self.initial_base_time = self.base_time;
+        }
+
+        // For very large time_delta (> 87*tau), exp_neg_time_ratio returns 0,
+        // meaning all stakes are fully matured and sum_stake_exp zeroes out.
+        let decay_factor = exp_neg_time_ratio(time_delta, self.tau_seconds)?;
+
+        // new_sum_stake_exp = old_sum_stake_exp * decay_factor / WAD
+        let new_sum_stake_exp =
+            wad_mul_u256(self.get_sum_stake_exp(), U256::from_u128(decay_factor))?;
+        self.set_sum_stake_exp(new_sum_stake_exp);
+        self.base_time = current_time;
+
+        Ok(Some(decay_factor))
     }
 
-    /// Get the effective last stake time (falls back to stake_time for existing accounts)
-    pub fn effective_last_stake_time(&self) -> i64 {
-        if self.last_stake_time != 0 {
-            self.last_stake_time
-        } else {
-            self.stake_time
+    /// Exponent input (WAD-scaled) past which SyncPool rebases
+    pub fn rebase_threshold_wad(&self) -> u128 {
+        if self.rebase_threshold_bps == 0 {
+            return REBASE_WARN_EXP_INPUT;
         }
+        self.rebase_threshold_bps as u128 * (WAD / 10_000)
     }
 
-    /// Check if there is a pending unstake request
-    pub fn has_pending_unstake_request(&self) -> bool {
-        self.unstake_request_amount > 0
+    /// Whether SyncPool should rebase at `current_time`: sum_stake_exp is
+    /// near overflow, or the exponent input is past rebase_threshold_wad.
+    /// Each rebase compounds the rounding of its decay factor, so rebasing
+    /// only when due keeps sum_stake_exp closer to its exact value.
+    pub fn rebase_due(&self, current_time: i64) -> Result<bool, StakingError> {
+        Ok(self.get_sum_stake_exp().needs_rebase()
+            || self.exp_input_wad(current_time)? > self.rebase_threshold_wad())
     }
 
-    /// Lazily adjust exp_start_factor when pool has been rebased.
-    /// Must be called before any calculation that uses exp_start_factor.
-    /// Returns true if an adjustment was made.
-    pub fn sync_to_pool(&mut self, pool: &StakingPool) -> Result<bool, StakingError> {
-        if self.base_time_snapshot == pool.base_time {
+    /// Rebase in place when sum_stake_exp is near overflow or the exponent
+    /// input exceeds MAX_EXP_INPUT, instead of failing with PoolRequiresSync.
+    /// Returns true if a rebase happened; the caller must save the pool.
+    pub fn rebase_if_needed(&mut self, current_time: i64) -> Result<bool, StakingError> {
+        if !self.get_sum_stake_exp().needs_rebase()
+            && self.exp_input_wad(current_time)? <= MAX_EXP_INPUT
+        {
             return Ok(false);
         }
+        Ok(self.rebase(current_time)?.is_some())
+    }
 
-        if self.base_time_snapshot == 0 {
-            // Legacy account (created before rebase-aware upgrade)
-            if pool.initial_base_time == 0 {
-                // No rebase has occurred since upgrade — exp_start_factor is still
-                // relative to the current pool.base_time, so no adjustment needed.
-                self.base_time_snapshot = pool.base_time;
-                return Ok(true);
+    /// Like `rebase_if_needed`, but leaves the pool untouched if the rebase
+    /// fails. For claims, which only read base_time and acc_rps (never
+    /// sum_stake_exp) and so must not be gated on the rebase succeeding.
+    /// Returns true if a rebase happened; the caller must save the pool.
+    pub fn rebase_if_possible(&mut self, current_time: i64) -> bool {
+        let mut rebased = self.clone();
+        match rebased.rebase_if_needed(current_time) {
+            Ok(true) => {
+                *self = rebased;
+                true
             }
-            // A rebase has occurred — adjust from the original base_time
-            let delta = pool.base_time.saturating_sub(pool.initial_base_time);
-            if delta > 0 {
-                let adjustment = exp_neg_time_ratio(delta, pool.tau_seconds)?;
-                self.exp_start_factor = wad_mul(self.exp_start_factor, adjustment)?;
-            }
-            self.base_time_snapshot = pool.base_time;
-            return Ok(true);
+            _ => false,
         }
+    }
 
-        // Standard case: adjust from the snapshot's base_time to the current one
-        let delta = pool.base_time.saturating_sub(self.base_time_snapshot);
-        if delta > 0 {
-            let adjustment = exp_neg_time_ratio(delta, pool.tau_seconds)?;
-            self.exp_start_factor = wad_mul(self.exp_start_factor, adjustment)?;
-        }
-        self.base_time_snapshot = pool.base_time;
-        Ok(true)
+    /// Upper bound on synced lamports still owed to active stakers:
+    /// total_staked * acc_rps - total_reward_debt, rounded up. Claims are not
+    /// tracked at pool level, so already-claimed rewards are still counted.
+    pub fn max_active_obligation(&self) -> Result<u64, StakingError> {
+        let entitlement_wad = U256::from_u128(self.total_staked)
+            .checked_mul(U256::from_u128(self.acc_reward_per_weighted_share))
+            .ok_or(StakingError::MathOverflow)?;
+        let owed_wad = entitlement_wad.saturating_sub(U256::from_u128(self.total_reward_debt));
+        let owed = (owed_wad + WAD_U256 - 1) / WAD_U256;
+        Ok(owed.to_u128().unwrap_or(u128::MAX).min(u64::MAX as u128) as u64)
     }
-}
 
-impl BorshDeserialize for UserStake {
-    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
-        let discriminator = <[u8; 8]>::deserialize_reader(reader)?;
-        let owner = Pubkey::deserialize_reader(reader)?;
-        let pool = Pubkey::deserialize_reader(reader)?;
-        let amount = u64::deserialize_reader(reader)?;
-        let stake_time = i64::deserialize_reader(reader)?;
-        let exp_start_factor = u128::deserialize_reader(reader)?;
-        let reward_debt = u128::deserialize_reader(reader)?;
+    /// Synced lamports owed to nobody (rewards forfeited by unstaking before
+    /// full maturity): last_synced_lamports minus the active obligation,
+    /// residuals and the earmarked balances (protocol fees owed, the
+    /// residual reserve and the rounding reserve). A lower bound, since the
+    /// obligation over-counts.
+    pub fn stranded_lamports(&self) -> Result<u64, StakingError> {
+        let rounding_reserve = (self.rounding_reserve_wad / WAD).min(u64::MAX as u128) as u64;
+        Ok(self
+            .last_synced_lamports
+            .saturating_sub(self.max_active_obligation()?)
+            .saturating_sub(self.total_residual_unpaid)
+            .saturating_sub(self.protocol_fees_owed)
+            .saturating_sub(self.residual_reserved)
+            .saturating_sub(rounding_reserve))
+    }
+
+    /// Lamports held above rent and last_synced_lamports, i.e. not yet
+    /// credited to the accumulator.
+    pub fn undistributed_lamports(&self, pool_lamports: u64, rent_exempt_minimum: u64) -> u64 {
+        pool_lamports
+            .saturating_sub(rent_exempt_minimum)
+            .saturating_sub(self.last_synced_lamports)
+    }
+
+    /// Lower last_synced_lamports to the balance above rent if it drifted
+    /// past it (lamports that left, or a larger rent-exempt minimum, without
+    /// bookkeeping). Never raises it, and leaves undistributed_lamports at
+    /// zero, so no lamports become new rewards. Returns the amount removed.
+    pub fn reconcile_synced_lamports(&mut self, pool_lamports: u64, rent_exempt_minimum: u64) -> u64 {
+        let available = pool_lamports.saturating_sub(rent_exempt_minimum);
+        let excess = self.last_synced_lamports.saturating_sub(available);
+        self.last_synced_lamports -= excess;
+        excess
+    }
+
+    /// Store undistributed_lamports for the pool `account` (with its
+    /// current balance and size). Legacy pools too small for the field are
+    /// left alone. Returns whether the stored value changed.
+    pub fn record_undistributed(&mut self, account: &AccountInfo, rent: &Rent) -> bool {
+        if account.data_len() < Self::LEN {
+            return false;
+        }
+        let undistributed = self.undistributed_lamports(
+            account.lamports(),
+            rent.minimum_balance(account.data_len()),
+        );
+        let changed = undistributed != self.undistributed_lamports;
+        self.undistributed_lamports = undistributed;
+        changed
+    }
+
+    /// Credit `lamports` of new rewards to the accumulator.
+    /// Denominator is total_staked * WAD (max weight, not time-varying).
+    /// Returns the reward_per_share increment.
+    pub fn distribute_rewards(&mut self, lamports: u64) -> Result<u128, StakingError> {
+        // The protocol fee stays in the pool, owed to the treasury
+        let fee = self.protocol_fee(lamports);
+        self.protocol_fees_owed = self
+            .protocol_fees_owed
+            .checked_add(fee)
+            .ok_or(StakingError::MathOverflow)?;
+        // Residual claimants are covered before the accumulator grows
+        let earmarked = self.earmark_residuals(lamports - fee);
+        let amount_wad = ((lamports - fee - earmarked) as u128)
+            .checked_mul(WAD)
+            .ok_or(StakingError::MathOverflow)?;
+        self.credit_accumulator(amount_wad)
+    }
+
+    /// With residuals_first, set aside up to `lamports` for the part of
+    /// total_residual_unpaid not already in residual_reserved. Returns the
+    /// lamports set aside.
+    pub fn earmark_residuals(&mut self, lamports: u64) -> u64 {
+        if !self.residuals_first {
+            return 0;
+        }
+        let earmarked = self
+            .total_residual_unpaid
+            .saturating_sub(self.residual_reserved)
+            .min(lamports);
+        self.residual_reserved += earmarked;
+        earmarked
+    }
+
+    /// Record `lamports` of residuals (or vesting fragments) paid out: they
+    /// leave total_residual_unpaid and are drawn from residual_reserved
+    /// first
+    pub fn pay_residual(&mut self, lamports: u64) {
+        self.total_residual_unpaid = self.total_residual_unpaid.saturating_sub(lamports);
+        self.residual_reserved = self
+            .residual_reserved
+            .saturating_sub(lamports)
+            .min(self.total_residual_unpaid);
+    }
+
+    /// Pool lamports a claim can pay from: the balance above rent, less
+    /// protocol fees owed to the treasury and, unless the claim is a
+    /// residual one, the lamports reserved for residual claimants
+    pub fn claimable_lamports(
+        &self,
+        pool_lamports: u64,
+        rent_exempt_minimum: u64,
+        residual_claim: bool,
+    ) -> u64 {
+        let reserved = if residual_claim { 0 } else { self.residual_reserved };
+        pool_lamports
+            .saturating_sub(rent_exempt_minimum)
+            .saturating_sub(self.protocol_fees_owed)
+            .saturating_sub(reserved)
+    }
+
+    /// Start vesting a distribution's `reward_per_share` (just credited to
+    /// the accumulator) over claim_vesting_seconds. What is still vesting
+    /// from earlier distributions joins it, so the schedule restarts from
+    /// `current_time`. No-op for pools without claim vesting.
+    pub fn vest_distribution(&mut self, current_time: i64, reward_per_share: u128) {
+        if self.claim_vesting_seconds == 0 || reward_per_share == 0 {
+            return;
+        }
+        self.vesting_acc_rps = self
+            .unvested_acc_rps(current_time)
+            .saturating_add(reward_per_share);
+        self.vesting_start = current_time;
+        self.vesting_end = current_time.saturating_add(self.claim_vesting_seconds as i64);
+    }
+
+    /// Fold a distribution's `reward_per_share` (just credited to the
+    /// accumulator) into the trailing reward rate
+    pub fn record_reward_rate(
+        &mut self,
+        current_time: i64,
+        reward_per_share: u128,
+    ) -> Result<(), StakingError> {
+        self.reward_rate_sum = self
+            .decayed_reward_rate_sum(current_time)?
+            .saturating_add(reward_per_share);
+        self.reward_rate_time = current_time;
+        Ok(())
+    }
+
+    /// reward_rate_sum decayed to `current_time`
+    fn decayed_reward_rate_sum(&self, current_time: i64) -> Result<u128, StakingError> {
+        let elapsed = current_time.saturating_sub(self.reward_rate_time).max(0);
+        let decay = exp_neg_time_ratio(elapsed, Self::REWARD_RATE_WINDOW_SECONDS)?;
+        Ok(wad_mul(self.reward_rate_sum, decay)?)
+    }
+
+    /// Trailing reward rate at `current_time`: the exponentially weighted
+    /// moving average (time constant REWARD_RATE_WINDOW_SECONDS) of
+    /// acc_reward_per_weighted_share growth, in WAD-scaled lamports per
+    /// fully weighted raw token unit per day. Pools younger than the window
+    /// are averaged over their lifetime only.
+    pub fn trailing_reward_rate(&self, current_time: i64) -> Result<u128, StakingError> {
+        let window = Self::REWARD_RATE_WINDOW_SECONDS;
+        let age = current_time.saturating_sub(self.creation_time()).max(0);
+        let coverage = WAD - exp_neg_time_ratio(age, window)?;
+        if coverage == 0 {
+            return Ok(0);
+        }
+        // A constant rate r per second decays to a sum of r * window * coverage
+        let rate = U256::from_u128(self.decayed_reward_rate_sum(current_time)?)
+            * U256::from(86_400u64)
+            * U256::from_u128(WAD)
+            / (U256::from(window) * U256::from_u128(coverage));
+        Ok(rate.to_u128().unwrap_or(u128::MAX))
+    }
+
+    /// Part of acc_reward_per_weighted_share not yet vested at `current_time`
+    pub fn unvested_acc_rps(&self, current_time: i64) -> u128 {
+        linear_unvested(self.vesting_acc_rps, self.vesting_start, self.vesting_end, current_time)
+    }
+
+    /// acc_reward_per_weighted_share as claims see it at `current_time`
+    pub fn vested_acc_rps(&self, current_time: i64) -> u128 {
+        self.acc_reward_per_weighted_share
+            .saturating_sub(self.unvested_acc_rps(current_time))
+    }
+
+    /// Fold accumulated rounding dust back into the accumulator.
+    /// Returns the reward_per_share increment (0 if the dust is still too
+    /// small to move the accumulator).
+    pub fn sweep_dust(&mut self) -> Result<u128, StakingError> {
+        let dust = core::mem::take(&mut self.accumulated_dust_wad);
+        self.credit_accumulator(dust)
+    }
+
+    /// acc_rps += amount_wad / (total_staked * WAD) at RAY scale, carrying
+    /// the part lost to floor division into accumulated_dust_wad. Returns
+    /// the increment of acc_reward_per_weighted_share (WAD digits only).
+    fn credit_accumulator(&mut self, amount_wad: u128) -> Result<u128, StakingError> {
+        let acc_before = self.acc_reward_per_weighted_share;
+        let dust = credit_ray_accumulator(
+            &mut self.acc_reward_per_weighted_share,
+            &mut self.acc_rps_ray_remainder,
+            amount_wad,
+            self.total_staked,
+        )?;
+        self.accumulated_dust_wad = self.accumulated_dust_wad.saturating_add(dust);
+
+        Ok(self.acc_reward_per_weighted_share - acc_before)
+    }
+
+    /// The reward accumulator at RAY (10^27) scale:
+    /// acc_reward_per_weighted_share * RAY_PER_WAD + acc_rps_ray_remainder
+    pub fn acc_rps_ray(&self) -> U256 {
+        U256::from_u128(self.acc_reward_per_weighted_share) * U256::from_u128(RAY_PER_WAD)
+            + U256::from_u128(self.acc_rps_ray_remainder as u128)
+    }
+
+    /// Grow a legacy pool to current LEN, paying the larger rent-exempt
+    /// minimum out of the pool's own undistributed lamports.
+    /// Returns the lamports consumed, or None (no-op) if `undistributed`
+    /// can't cover it. Returns Some(0) if the pool is already full size.
+    pub fn realloc_from_undistributed(
+        account: &AccountInfo,
+        undistributed: u64,
+    ) -> Result<Option<u64>, solana_program::program_error::ProgramError> {
+        if account.data_len() >= Self::LEN {
+            return Ok(Some(0));
+        }
+
+        let rent = solana_program::rent::Rent::get()?;
+        let rent_delta = rent
+            .minimum_balance(Self::LEN)
+            .saturating_sub(rent.minimum_balance(account.data_len()));
+        if rent_delta > undistributed {
+            return Ok(None);
+        }
+
+        account.realloc(Self::LEN, false)?;
+        Ok(Some(rent_delta))
+    }
+
+    /// Serialize into the pool account.
+    ///
+    /// Legacy pools are smaller than LEN. Writing into one is allowed as long
+    /// as every field that doesn't fit still holds its default (zero) value;
+    /// otherwise the pool must first be grown with `maybe_realloc`.
+    /// Bumps `state_nonce` when the account is large enough to hold it.
+    pub fn save(&mut self, account: &AccountInfo) -> ProgramResult {
+        if account.data_len() >= Self::LEN {
+            self.state_nonce = self.state_nonce.wrapping_add(1);
+        }
+        let bytes = borsh::to_vec(self)?;
+        let mut data = account.try_borrow_mut_data()?;
+        let fit = bytes.len().min(data.len());
+        if bytes[fit..].iter().any(|b| *b != 0) {
+            return Err(StakingError::AccountDataTooSmall.into());
+        }
+        data[..fit].copy_from_slice(&bytes[..fit]);
+        Ok(())
+    }
+
+    /// Realloc a legacy pool account to current LEN.
+    /// Transfers the additional rent from payer so the SOL available for
+    /// rewards (lamports above rent-exempt minimum) is unchanged.
+    /// No-op if account is already at or above current LEN.
+    pub fn maybe_realloc<'a>(
+        account: &AccountInfo<'a>,
+        payer: &AccountInfo<'a>,
+        system_program: Option<&AccountInfo<'a>>,
+    ) -> ProgramResult {
+        if account.data_len() >= Self::LEN {
+            return Ok(());
+        }
+
+        let rent = solana_program::rent::Rent::get()?;
+        let rent_delta = rent
+            .minimum_balance(Self::LEN)
+            .saturating_sub(rent.minimum_balance(account.data_len()));
+
+        if rent_delta > 0 {
+            let sys_prog = system_program.ok_or(StakingError::MissingSystemProgram)?;
+            solana_program::program::invoke(
+                &solana_program::system_instruction::transfer(payer.key, account.key, rent_delta),
+                &[payer.clone(), account.clone(), sys_prog.clone()],
+            )?;
+        }
+
+        account.realloc(Self::LEN, false)?;
+
+        Ok(())
+    }
+}
+
+impl BorshDeserialize for StakingPool {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let discriminator = <[u8; 8]>::deserialize_reader(reader)?;
+        let mint = Pubkey::deserialize_reader(reader)?;
+        let token_vault = Pubkey::deserialize_reader(reader)?;
+        let reward_vault = Pubkey::deserialize_reader(reader)?;
+        let authority = Pubkey::deserialize_reader(reader)?;
+        let total_staked = u128::deserialize_reader(reader)?;
+        let sum_stake_exp = <[u8; 32]>::deserialize_reader(reader)?;
+        let tau_seconds = u64::deserialize_reader(reader)?;
+        let base_time = i64::deserialize_reader(reader)?;
+        let acc_reward_per_weighted_share = u128::deserialize_reader(reader)?;
+        let last_update_time = i64::deserialize_reader(reader)?;
         let bump = u8::deserialize_reader(reader)?;
-        let unstake_request_amount = u64::deserialize_reader(reader)?;
-        let unstake_request_time = i64::deserialize_reader(reader)?;
-        let last_stake_time = i64::deserialize_reader(reader)?;
-        let base_time_snapshot = i64::deserialize_reader(reader)?;
+        let last_synced_lamports = u64::deserialize_reader(reader)?;
+        let min_stake_amount = u64::deserialize_reader(reader)?;
+        let lock_duration_seconds = u64::deserialize_reader(reader)?;
+        let unstake_cooldown_seconds = u64::deserialize_reader(reader)?;
+        let initial_base_time = i64::deserialize_reader(reader)?;
+        let total_reward_debt = u128::deserialize_reader(reader)?;
+        let total_residual_unpaid = u64::deserialize_reader(reader)?;
 
         // New fields — may not be present in legacy accounts
-        let total_rewards_claimed = u64::deserialize_reader(reader).unwrap_or(0);
-        let claimed_rewards_wad = u128::deserialize_reader(reader).unwrap_or(0);
+        let rewards_paused = bool::deserialize_reader(reader).unwrap_or(false);
+        let accumulated_dust_wad = u128::deserialize_reader(reader).unwrap_or(0);
+        let pool_id = u64::deserialize_reader(reader).unwrap_or(0);
+        let receipt_mint = Pubkey::deserialize_reader(reader).unwrap_or_default();
+        let refund_address = Pubkey::deserialize_reader(reader).unwrap_or_default();
+        let refund_after_seconds = u64::deserialize_reader(reader).unwrap_or(0);
+        let undistributed_since = i64::deserialize_reader(reader).unwrap_or(0);
+        let min_distribution_stake = u64::deserialize_reader(reader).unwrap_or(0);
+        let unstake_fee_bps = u16::deserialize_reader(reader).unwrap_or(0);
+        let unstake_fee_destination = Pubkey::deserialize_reader(reader).unwrap_or_default();
+        let has_authority_log = bool::deserialize_reader(reader).unwrap_or(false);
+        let state_nonce = u64::deserialize_reader(reader).unwrap_or(0);
+        let unstake_request_expiry_seconds = u64::deserialize_reader(reader).unwrap_or(0);
+        let protocol_fee_bps = u16::deserialize_reader(reader).unwrap_or(0);
+        let protocol_fees_owed = u64::deserialize_reader(reader).unwrap_or(0);
+        let rounding_mode = u8::deserialize_reader(reader).unwrap_or(0);
+        let has_depositor_allowlist = bool::deserialize_reader(reader).unwrap_or(false);
+        let lock_boost_bps = u16::deserialize_reader(reader).unwrap_or(0);
+        let maturity_bonus_bps = u16::deserialize_reader(reader).unwrap_or(0);
+        let bootstrap_period_seconds = u64::deserialize_reader(reader).unwrap_or(0);
+        let has_matching_vault = bool::deserialize_reader(reader).unwrap_or(false);
+        let compression_tree = Pubkey::deserialize_reader(reader).unwrap_or_default();
+        let compressed_leaf_count = u64::deserialize_reader(reader).unwrap_or(0);
+        let compressed_staked = u128::deserialize_reader(reader).unwrap_or(0);
+        let compressed_reward_debt = u128::deserialize_reader(reader).unwrap_or(0);
+        let has_rent_sponsor = bool::deserialize_reader(reader).unwrap_or(false);
+        let min_sync_interval_seconds = u64::deserialize_reader(reader).unwrap_or(0);
+        let last_distribution_time = i64::deserialize_reader(reader).unwrap_or(0);
+        let claim_vesting_seconds = u64::deserialize_reader(reader).unwrap_or(0);
+        let vesting_acc_rps = u128::deserialize_reader(reader).unwrap_or(0);
+        let vesting_start = i64::deserialize_reader(reader).unwrap_or(0);
+        let vesting_end = i64::deserialize_reader(reader).unwrap_or(0);
+        let price_oracle = Pubkey::deserialize_reader(reader).unwrap_or_default();
+        let has_gas_rebate_vault = bool::deserialize_reader(reader).unwrap_or(false);
+        let claim_burn_bps = u16::deserialize_reader(reader).unwrap_or(0);
+        let claim_burn_destination = Pubkey::deserialize_reader(reader).unwrap_or_default();
+        let undistributed_lamports = u64::deserialize_reader(reader).unwrap_or(0);
+        let residuals_first = bool::deserialize_reader(reader).unwrap_or(false);
+        let residual_reserved = u64::deserialize_reader(reader).unwrap_or(0);
+        let min_claim_lamports = u64::deserialize_reader(reader).unwrap_or(0);
+        let has_distribution_history = bool::deserialize_reader(reader).unwrap_or(false);
+        let distribution_checkpoints = u64::deserialize_reader(reader).unwrap_or(0);
+        let reward_rate_sum = u128::deserialize_reader(reader).unwrap_or(0);
+        let reward_rate_time = i64::deserialize_reader(reader).unwrap_or(0);
+        let track_depositors = bool::deserialize_reader(reader).unwrap_or(false);
+        let rebase_threshold_bps = u32::deserialize_reader(reader).unwrap_or(0);
+        let acc_rps_ray_remainder = u64::deserialize_reader(reader).unwrap_or(0);
+        let vault_generation = u32::deserialize_reader(reader).unwrap_or(0);
+        let lock_exempt_amount = u64::deserialize_reader(reader).unwrap_or(0);
+        let stake_guard_required = bool::deserialize_reader(reader).unwrap_or(false);
+        let rounding_reserve_wad = u128::deserialize_reader(reader).unwrap_or(0);
+        let weight_engine = u8::deserialize_reader(reader).unwrap_or(0);
+        let reserved =
+            <[u8; POOL_RESERVED_LEN]>::deserialize_reader(reader).unwrap_or([0u8; POOL_RESERVED_LEN]);
 
         Ok(Self {
             discriminator,
+            mint,
+            token_vault,
+            reward_vault,
+            authority,
+            total_staked,
+            sum_stake_exp,
+            tau_seconds,
+            base_time,
+            acc_reward_per_weighted_share,
+            last_update_time,
+            bump,
+            last_synced_lamports,
+            min_stake_amount,
+            lock_duration_seconds,
+            unstake_cooldown_seconds,
+            initial_base_time,
+            total_reward_debt,
+            total_residual_unpaid,
+            rewards_paused,
+            accumulated_dust_wad,
+            pool_id,
+            receipt_mint,
+            refund_address,
+            refund_after_seconds,
+            undistributed_since,
+            min_distribution_stake,
+            unstake_fee_bps,
+            unstake_fee_destination,
+            has_authority_log,
+            state_nonce,
+            unstake_request_expiry_seconds,
+            protocol_fee_bps,
+            protocol_fees_owed,
+            rounding_mode,
+            has_depositor_allowlist,
+            lock_boost_bps,
+            maturity_bonus_bps,
+            bootstrap_period_seconds,
+            has_matching_vault,
+            compression_tree,
+            compressed_leaf_count,
+            compressed_staked,
+            compressed_reward_debt,
+            has_rent_sponsor,
+            min_sync_interval_seconds,
+            last_distribution_time,
+            claim_vesting_seconds,
+            vesting_acc_rps,
+            vesting_start,
+            vesting_end,
+            price_oracle,
+            has_gas_rebate_vault,
+            claim_burn_bps,
+            claim_burn_destination,
+            undistributed_lamports,
+            residuals_first,
+            residual_reserved,
+            min_claim_lamports,
+            has_distribution_history,
+            distribution_checkpoints,
+            reward_rate_sum,
+            reward_rate_time,
+            track_depositors,
+            rebase_threshold_bps,
+            acc_rps_ray_remainder,
+            vault_generation,
+            lock_exempt_amount,
+            stake_guard_required,
+            rounding_reserve_wad,
+            weight_engine,
+            reserved,
+        })
+    }
+}
+
+/// Part of `amount` not yet vested at `current_time` on a linear schedule
+/// from `start` to `end`
+fn linear_unvested(amount: u128, start: i64, end: i64, current_time: i64) -> u128 {
+    if amount == 0 || current_time >= end || end <= start {
+        return 0;
+    }
+    let remaining = (end - current_time.max(start)) as u128;
+    let duration = (end - start) as u128;
+    (U256::from_u128(amount) * U256::from_u128(remaining) / U256::from_u128(duration))
+        .to_u128()
+        .unwrap_or(amount)
+}
+
+/// User stake account
+/// PDA: ["stake", pool, owner]
+#[derive(BorshSerialize, Debug, Clone)]
+pub struct UserStake {
+    /// Discriminator for account type identification
+    pub discriminator: [u8; 8],
+
+    /// Owner of this stake
+    pub owner: Pubkey,
+
+    /// Pool this stake belongs to
+    pub pool: Pubkey,
+
+    /// Amount of tokens staked
+    pub amount: u64,
+
+    /// Unix timestamp when stake began
+    pub stake_time: i64,
+
+    /// e^((stake_time - base_time) / tau) at time of staking, WAD-scaled
+    /// Used to track contribution to sum_stake_exp
+    pub exp_start_factor: u128,
+
+    /// Reward debt encoding an acc_rps snapshot for pending reward calculation.
+    /// Encodes: reward_debt = wad_mul(amount * WAD, snapshot_acc_rps).
+    /// Pending = user_weighted * (current_acc_rps - snapshot_acc_rps).
+    /// When amount == 0 (post-full-unstake), reinterpreted as unclaimed WAD-scaled rewards.
+    pub reward_debt: u128,
+
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Pending unstake request amount (0 = no pending request)
+    pub unstake_request_amount: u64,
+
+    /// Timestamp when unstake was requested
+    pub unstake_request_time: i64,
+
+    /// Timestamp of most recent stake deposit (for lock duration checks)
+    /// Falls back to stake_time when 0 (for existing accounts)
+    pub last_stake_time: i64,
+
+    /// Pool base_time when exp_start_factor was last calibrated.
+    /// 0 = legacy account (pre-rebase-aware); treated as matching the pool's
+    /// initial_base_time or current base_time if no rebase has occurred.
+    pub base_time_snapshot: i64,
+
+    /// Cumulative SOL rewards claimed by this user (lamports).
+    /// Defaults to 0 for legacy 153-byte accounts (populated on first realloc).
+    pub total_rewards_claimed: u64,
+
+    /// Cumulative WAD-scaled rewards already paid out for the current position.
+    /// Used to make claims frequency-independent: pending = full_entitlement - claimed_rewards_wad.
+    /// Reset to 0 on stake (add-more) and unstake (partial/full) when the position is restructured.
+    /// Defaults to 0 for existing accounts (correct: first claim gets full pending).
+    pub claimed_rewards_wad: u128,
+
+    /// Slot of the most recent stake or unstake/request (flash-stake guard).
+    /// Defaults to 0 for legacy accounts.
+    pub last_action_slot: u64,
+
+    /// Kind of the action recorded in `last_action_slot` (see [`StakeAction`])
+    pub last_action_kind: u8,
+
+    /// Whether a WeightHistory account exists for this position. When set,
+    /// every stake/unstake must pass it so the history stays complete.
+    /// Defaults to false for legacy accounts.
+    pub has_weight_history: bool,
+
+    /// Account that receives reward lamports instead of the owner, for
+    /// program-owned (PDA) owners that can't hold raw lamport credits.
+    /// Pubkey::default() pays the owner. Defaults to default for legacy accounts.
+    pub reward_recipient: Pubkey,
+
+    /// Unix timestamp when this stake account was created.
+    /// 0 for legacy accounts (falls back to stake_time).
+    pub first_stake_time: i64,
+
+    /// Lifetime tokens staked into this position (raw units).
+    /// Only counts stakes made after the account was upgraded.
+    pub total_deposited: u64,
+
+    /// Lifetime tokens unstaked from this position (raw units, before any
+    /// unstake fee). Only counts unstakes made after the account was upgraded.
+    pub total_withdrawn: u64,
+
+    /// Whether a UserLockSchedule account exists for this position. When set,
+    /// Unstake and RequestUnstake must pass it so locked tranches are enforced.
+    /// Defaults to false for legacy accounts.
+    pub has_lock_schedule: bool,
+
+    /// Incremented on every write, so indexers consuming account updates
+    /// can order them and detect missed writes. 0 for legacy accounts.
+    pub state_nonce: u64,
+
+    /// Whether reward payouts are split per a PayoutSplit account. When set,
+    /// the split replaces the declared reward recipient on claim and unstake.
+    /// Defaults to false for legacy accounts.
+    pub has_payout_split: bool,
+
+    /// Unix timestamp before which the position can't be unstaked, set by
+    /// ExtendLock on top of the pool's lock_duration_seconds.
+    /// 0 (no extra lock) for legacy accounts.
+    pub lock_until: i64,
+
+    /// Whether ClaimMaturityBonus has paid this position.
+    /// Defaults to false for legacy accounts.
+    pub maturity_bonus_paid: bool,
+
+    /// Co-signer required to move principal out (Unstake, CompleteUnstake,
+    /// MigrateStake). Default pubkey = none (legacy accounts).
+    pub guardian: Pubkey,
+
+    /// When a ClearGuardian without the guardian's signature may complete.
+    /// 0 = no removal pending.
+    pub guardian_removal_time: i64,
+
+    /// Account rent paid by the pool's RentSponsorVault, which
+    /// CloseStakeAccount returns to it. 0 (owner-paid) for legacy accounts.
+    pub sponsored_rent: u64,
+
+    /// Rewards (WAD-scaled) that were still vesting when the position was
+    /// unstaked from, left at vesting_start; they vest linearly until
+    /// vesting_end and are paid by claims. Counted in the pool's
+    /// total_residual_unpaid.
+    pub vesting_rewards_wad: u128,
+
+    /// Start of the vesting fragment's schedule
+    pub vesting_start: i64,
+
+    /// End of the vesting fragment's schedule
+    pub vesting_end: i64,
+
+    /// Top-ups still under the pool's lock_duration_seconds, so only they
+    /// stay locked instead of the whole position. Empty (all zero) until
+    /// the first top-up; then the pool lock covers the whole position from
+    /// the last stake, as for legacy accounts.
+    pub recent_deposits: [DepositTranche; RECENT_DEPOSIT_SLOTS],
+
+    /// Position NFT mint while the position is tokenized (TokenizePosition):
+    /// the NFT holder, not `owner`, controls it until RedeemPosition.
+    /// Pubkey::default() = not tokenized.
+    pub position_mint: Pubkey,
+
+    /// Set by the pool authority (SetStakeFrozen): the position can't claim,
+    /// take new stake or migrate, and unstakes return principal only,
+    /// keeping rewards owed until it is unfrozen. False for legacy accounts.
+    pub rewards_frozen: bool,
+
+    /// Space for future fields, carved from the front like
+    /// StakingPool::reserved. Always written as zeros.
+    pub reserved: [u8; USER_STAKE_RESERVED_LEN],
+}
+
+/// Tokens added to a position at `time`, locked by the pool until
+/// `time + lock_duration_seconds`
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DepositTranche {
+    /// Deposited token amount (raw units)
+    pub amount: u64,
+
+    /// Unix timestamp of the deposit
+    pub time: i64,
+}
+
+/// Weighting engine of a pool (StakingPool::weight_engine)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum WeightEngine {
+    /// `amount * (1 - e^(-age/tau))`, continuous
+    Exponential = 0,
+    /// Fixed weight per age bucket, rolled by RollAgeBuckets
+    AgeBuckets = 1,
+}
+
+/// Position-changing actions tracked for the same-slot guard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum StakeAction {
+    None = 0,
+    Stake = 1,
+    Unstake = 2,
+}
+
+impl UserStake {
+    /// Size of the account in bytes
+    pub const LEN: usize = 8 +  // discriminator
+        32 + // owner
+        32 + // pool
+        8 +  // amount
+        8 +  // stake_time
+        16 + // exp_start_factor
+        16 + // reward_debt
+        1 +  // bump
+        8 +  // unstake_request_amount
+        8 +  // unstake_request_time
+        8 +  // last_stake_time
+        8 +  // base_time_snapshot
+        8 +  // total_rewards_claimed
+        16 + // claimed_rewards_wad
+        8 +  // last_action_slot
+        1 +  // last_action_kind
+        1 +  // has_weight_history
+        32 + // reward_recipient
+        8 +  // first_stake_time
+        8 +  // total_deposited
+        8 +  // total_withdrawn
+        1 +  // has_lock_schedule
+        8 +  // state_nonce
+        1 +  // has_payout_split
+        8 +  // lock_until
+        1 +  // maturity_bonus_paid
+        32 + // guardian
+        8 +  // guardian_removal_time
+        8 +  // sponsored_rent
+        16 + // vesting_rewards_wad
+        8 +  // vesting_start
+        8 +  // vesting_end
+        16 * RECENT_DEPOSIT_SLOTS + // recent_deposits
+        32 + // position_mint
+        1 +  // rewards_frozen
+        USER_STAKE_RESERVED_LEN; // reserved
+
+    /// Allocated size of current stake accounts, frozen like
+    /// StakingPool::ACCOUNT_SIZE
+    pub const ACCOUNT_SIZE: usize = 512;
+
+    /// Legacy account size (before claimed_rewards_wad was added)
+    pub const LEGACY_LEN: usize = 161;
+
+    /// Byte offset of `owner`, for getProgramAccounts memcmp filters
+    pub const OWNER_OFFSET: usize = 8;
+
+    /// Byte offset of `pool`, for getProgramAccounts memcmp filters
+    pub const POOL_OFFSET: usize = 40;
+
+    /// Delay before ClearGuardian removes a guardian that didn't co-sign
+    pub const GUARDIAN_REMOVAL_DELAY_SECONDS: i64 = 7 * 24 * 60 * 60;
+
+    /// Create a new user stake
+    pub fn new(
+        owner: Pubkey,
+        pool: Pubkey,
+        amount: u64,
+        stake_time: i64,
+        exp_start_factor: u128,
+        bump: u8,
+        base_time_snapshot: i64,
+    ) -> Self {
+        Self {
+            discriminator: USER_STAKE_DISCRIMINATOR,
             owner,
             pool,
             amount,
             stake_time,
             exp_start_factor,
-            reward_debt,
+            reward_debt: 0,
             bump,
-            unstake_request_amount,
-            unstake_request_time,
-            last_stake_time,
+            unstake_request_amount: 0,
+            unstake_request_time: 0,
+            last_stake_time: stake_time,
             base_time_snapshot,
-            total_rewards_claimed,
-            claimed_rewards_wad,
-        })
+            total_rewards_claimed: 0,
+            claimed_rewards_wad: 0,
+            last_action_slot: 0,
+            last_action_kind: StakeAction::None as u8,
+            has_weight_history: false,
+            reward_recipient: Pubkey::default(),
+            first_stake_time: stake_time,
+            total_deposited: amount,
+            total_withdrawn: 0,
+            has_lock_schedule: false,
+            state_nonce: 0,
+            has_payout_split: false,
+            lock_until: 0,
+            maturity_bonus_paid: false,
+            guardian: Pubkey::default(),
+            guardian_removal_time: 0,
+            sponsored_rent: 0,
+            vesting_rewards_wad: 0,
+            vesting_start: 0,
+            vesting_end: 0,
+            recent_deposits: [DepositTranche::default(); RECENT_DEPOSIT_SLOTS],
+            position_mint: Pubkey::default(),
+            rewards_frozen: false,
+            reserved: [0u8; USER_STAKE_RESERVED_LEN],
+        }
+    }
+
+    /// Check if stake is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.discriminator == USER_STAKE_DISCRIMINATOR
+    }
+
+    /// Check if rewards are routed to a declared recipient instead of the owner
+    pub fn has_reward_recipient(&self) -> bool {
+        self.reward_recipient != Pubkey::default()
+    }
+
+    /// Derive user stake PDA
+    pub fn derive_pda(pool: &Pubkey, owner: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[STAKE_SEED, pool.as_ref(), owner.as_ref()], program_id)
+    }
+
+    /// Derive the position NFT mint PDA of the stake at `user_stake`
+    pub fn derive_position_mint_pda(user_stake: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[POSITION_NFT_SEED, user_stake.as_ref()], program_id)
+    }
+
+    /// Get the effective last stake time (falls back to stake_time for existing accounts)
+    pub fn effective_last_stake_time(&self) -> i64 {
+        if self.last_stake_time != 0 {
+            self.last_stake_time
+        } else {
+            self.stake_time
+        }
+    }
+
+    /// Get the effective account creation time (falls back to stake_time for legacy accounts)
+    pub fn effective_first_stake_time(&self) -> i64 {
+        if self.first_stake_time != 0 {
+            self.first_stake_time
+        } else {
+            self.stake_time
+        }
+    }
+
+    /// Seconds until the whole position can be unstaked: the later of the
+    /// pool's lock_duration_seconds after the last stake and `lock_until`
+    pub fn lock_remaining_seconds(&self, pool: &StakingPool, current_time: i64) -> u64 {
+        let pool_lock_end = self
+            .effective_last_stake_time()
+            .saturating_add(pool.lock_duration_seconds.min(i64::MAX as u64) as i64);
+        pool_lock_end
+            .max(self.lock_until)
+            .saturating_sub(current_time)
+            .max(0) as u64
+    }
+
+    /// Tokens that can leave the position at `current_time`: none before
+    /// `lock_until`, otherwise all but the deposits still under the pool's
+    /// lock_duration_seconds. Without tracked deposits the pool lock holds
+    /// the whole position from the last stake. The pool lock never holds
+    /// the first `lock_exempt_amount` tokens.
+    pub fn unlocked_amount(&self, pool: &StakingPool, current_time: i64) -> u64 {
+        if current_time < self.lock_until {
+            return 0;
+        }
+        let lock = pool.lock_duration_seconds.min(i64::MAX as u64) as i64;
+        let locked = if self.recent_deposits.iter().all(|deposit| deposit.amount == 0) {
+            if self.effective_last_stake_time().saturating_add(lock) > current_time {
+                self.amount
+            } else {
+                0
+            }
+        } else {
+            self.recent_deposits
+                .iter()
+                .filter(|deposit| deposit.time.saturating_add(lock) > current_time)
+                .fold(0u64, |sum, deposit| sum.saturating_add(deposit.amount))
+        };
+        let locked = locked.min(self.amount.saturating_sub(pool.lock_exempt_amount));
+        self.amount.saturating_sub(locked)
+    }
+
+    /// Reject taking `amount` out of the position while it is locked.
+    /// Fully locked positions fail with StakeLocked and the time left;
+    /// partly unlocked ones with InsufficientUnlockedStake and the amount
+    /// that can leave.
+    pub fn check_lock(&self, pool: &StakingPool, amount: u64, current_time: i64) -> ProgramResult {
+        let unlocked = self.unlocked_amount(pool, current_time);
+        if amount <= unlocked {
+            return Ok(());
+        }
+        if unlocked == 0 {
+            let remaining = self.lock_remaining_seconds(pool, current_time);
+            return Err(StakingError::StakeLocked.with_context(ErrorContext::RemainingSeconds(remaining)));
+        }
+        Err(StakingError::InsufficientUnlockedStake.with_context(ErrorContext::AvailableAmount(unlocked)))
+    }
+
+    /// Track a top-up of `amount` at `current_time` so the pool lock only
+    /// holds the new tokens. Call before adding it to the position. Expired
+    /// deposits are dropped; a still-locked untracked balance is recorded
+    /// at the last stake time first. When every slot is taken the top-up
+    /// joins the newest deposit, which is re-locked from now.
+    pub fn record_deposit(&mut self, pool: &StakingPool, amount: u64, current_time: i64) {
+        if self.amount == 0 {
+            // The pool lock after this stake covers the whole position
+            self.recent_deposits = [DepositTranche::default(); RECENT_DEPOSIT_SLOTS];
+            return;
+        }
+        let lock = pool.lock_duration_seconds.min(i64::MAX as u64) as i64;
+        for deposit in self.recent_deposits.iter_mut() {
+            if deposit.time.saturating_add(lock) <= current_time {
+                *deposit = DepositTranche::default();
+            }
+        }
+        if self.recent_deposits.iter().all(|deposit| deposit.amount == 0) {
+            let last_stake_time = self.effective_last_stake_time();
+            if last_stake_time.saturating_add(lock) > current_time {
+                self.recent_deposits[0] = DepositTranche {
+                    amount: self.amount,
+                    time: last_stake_time,
+                };
+            }
+        }
+
+        let slot = self
+            .recent_deposits
+            .iter()
+            .position(|deposit| deposit.amount > 0 && deposit.time == current_time)
+            .or_else(|| self.recent_deposits.iter().position(|deposit| deposit.amount == 0))
+            .unwrap_or_else(|| {
+                (0..RECENT_DEPOSIT_SLOTS)
+                    .max_by_key(|&i| self.recent_deposits[i].time)
+                    .unwrap_or(0)
+            });
+        let deposit = &mut self.recent_deposits[slot];
+        deposit.amount = deposit.amount.saturating_add(amount);
+        deposit.time = current_time;
+    }
+
+    /// Treat the position as `bonus_seconds` older: scale exp_start_factor
+    /// by e^(-bonus/tau), move stake_time back and take the difference out
+    /// of the pool's sum_stake_exp. The position must be synced to the pool.
+    pub fn apply_age_bonus(
+        &mut self,
+        pool: &mut StakingPool,
+        bonus_seconds: u64,
+    ) -> Result<(), StakingError> {
+        let bonus = bonus_seconds.min(i64::MAX as u64) as i64;
+        let decay = exp_neg_time_ratio(bonus, pool.tau_seconds)?;
+        let new_factor = wad_mul(self.exp_start_factor, decay)?;
+
+        // sum_stake_exp holds amount * exp_start_factor for every position
+        let amount_wad = (self.amount as u128)
+            .checked_mul(WAD)
+            .ok_or(StakingError::MathOverflow)?;
+        let removed = wad_mul(amount_wad, self.exp_start_factor)?
+            .saturating_sub(wad_mul(amount_wad, new_factor)?);
+        let new_sum = pool
+            .get_sum_stake_exp()
+            .saturating_sub(U256::from_u128(removed));
+        pool.set_sum_stake_exp(new_sum);
+
+        self.exp_start_factor = new_factor;
+        self.stake_time = self.stake_time.saturating_sub(bonus);
+        Ok(())
+    }
+
+    /// Seconds until the position is MATURITY_TAU_MULTIPLE tau old (0 once
+    /// it is). Age counts from stake_time, so it includes ExtendLock bonuses.
+    pub fn maturity_remaining_seconds(&self, pool: &StakingPool, current_time: i64) -> u64 {
+        let maturity_age = pool
+            .tau_seconds
+            .saturating_mul(StakingPool::MATURITY_TAU_MULTIPLE)
+            .min(i64::MAX as u64) as i64;
+        self.stake_time
+            .saturating_add(maturity_age)
+            .saturating_sub(current_time)
+            .max(0) as u64
+    }
+
+    /// Whether principal moves need a guardian co-signature
+    pub fn has_guardian(&self) -> bool {
+        self.guardian != Pubkey::default()
+    }
+
+    /// Whether the position is held as an NFT (TokenizePosition)
+    pub fn is_tokenized(&self) -> bool {
+        self.position_mint != Pubkey::default()
+    }
+
+    /// Fail if the pool authority froze the position's rewards
+    pub fn check_not_frozen(&self) -> ProgramResult {
+        if self.rewards_frozen {
+            return Err(StakingError::PositionFrozen.into());
+        }
+        Ok(())
+    }
+
+    /// Check that `owner` controls the position. A tokenized position is
+    /// controlled by its NFT holder, so every owner action is refused
+    /// until the NFT is redeemed.
+    pub fn check_owner(&self, owner: &Pubkey) -> ProgramResult {
+        if self.owner != *owner {
+            return Err(StakingError::InvalidOwner.into());
+        }
+        if self.is_tokenized() {
+            return Err(StakingError::PositionTokenized.into());
+        }
+        Ok(())
+    }
+
+    /// Whether the position can be tokenized: like is_compressible, it
+    /// holds tokens with no pending unstake request, companion accounts,
+    /// guardian, sponsored rent or vesting fragment, none of which could
+    /// follow the NFT to a new holder, and it has no reward recipient that
+    /// would keep collecting the buyer's rewards
+    pub fn is_tokenizable(&self, pool: &StakingPool, current_time: i64) -> bool {
+        self.is_compressible(pool, current_time)
+            && !self.has_reward_recipient()
+            && !self.is_tokenized()
+    }
+
+    /// Whether the position can move into the pool's compression tree: it
+    /// holds tokens, has no pending unstake request, no companion accounts
+    /// (weight history, lock schedule, payout split) or guardian that a
+    /// compressed leaf couldn't keep in step, and its rent was not
+    /// sponsored (compression refunds the rent to the owner), and it has no
+    /// rewards vesting outside the accumulator
+    pub fn is_compressible(&self, pool: &StakingPool, current_time: i64) -> bool {
+        self.amount > 0
+            && !self.has_pending_unstake_request(pool, current_time)
+            && !self.has_weight_history
+            && !self.has_lock_schedule
+            && !self.has_payout_split
+            && !self.has_guardian()
+            && self.sponsored_rent == 0
+            && self.vesting_rewards_wad == 0
+    }
+
+    /// Vesting fragment rewards (WAD-scaled) vested at `current_time`
+    pub fn vested_rewards_wad(&self, current_time: i64) -> u128 {
+        self.vesting_rewards_wad - linear_unvested(
+            self.vesting_rewards_wad,
+            self.vesting_start,
+            self.vesting_end,
+            current_time,
+        )
+    }
+
+    /// Take the vested part of the fragment out of it (the caller pays it);
+    /// the rest keeps vesting from `current_time`. Returns it, WAD-scaled.
+    pub fn release_vested_rewards(&mut self, current_time: i64) -> u128 {
+        let vested = self.vested_rewards_wad(current_time);
+        self.vesting_rewards_wad -= vested;
+        self.vesting_start = current_time;
+        if self.vesting_rewards_wad == 0 {
+            self.vesting_start = 0;
+            self.vesting_end = 0;
+        }
+        vested
+    }
+
+    /// Add unvested rewards (WAD-scaled) to the fragment, vesting by
+    /// `vesting_end` or the fragment's own end, whichever is later. Call
+    /// release_vested_rewards first, so nothing already vested re-locks.
+    pub fn add_vesting_rewards(&mut self, current_time: i64, rewards_wad: u128, vesting_end: i64) {
+        if rewards_wad == 0 {
+            return;
+        }
+        self.vesting_rewards_wad = self.vesting_rewards_wad.saturating_add(rewards_wad);
+        self.vesting_start = current_time;
+        self.vesting_end = self.vesting_end.max(vesting_end).max(current_time);
+    }
+
+    /// Maturity bonus owed at `bonus_bps`: a share of lifetime claims
+    pub fn maturity_bonus(&self, bonus_bps: u16) -> u64 {
+        (self.total_rewards_claimed as u128 * bonus_bps as u128 / 10_000) as u64
+    }
+
+    /// Check if there is a pending unstake request at `current_time`.
+    /// Requests that outlived the pool's grace window no longer count.
+    pub fn has_pending_unstake_request(&self, pool: &StakingPool, current_time: i64) -> bool {
+        self.unstake_request_amount > 0 && !self.unstake_request_expired(pool, current_time)
+    }
+
+    /// Whether the stored request lapsed: it could only be completed for
+    /// `unstake_request_expiry_seconds` after its cooldown elapsed.
+    /// Never true on pools without an expiry.
+    pub fn unstake_request_expired(&self, pool: &StakingPool, current_time: i64) -> bool {
+        if self.unstake_request_amount == 0 || pool.unstake_request_expiry_seconds == 0 {
+            return false;
+        }
+        let window = pool
+            .unstake_cooldown_seconds
+            .saturating_add(pool.unstake_request_expiry_seconds);
+        let elapsed = current_time.saturating_sub(self.unstake_request_time).max(0) as u64;
+        elapsed >= window
+    }
+
+    /// Drop a lapsed request so the stored fields match its lazy status
+    pub fn clear_expired_unstake_request(&mut self, pool: &StakingPool, current_time: i64) {
+        if self.unstake_request_expired(pool, current_time) {
+            self.unstake_request_amount = 0;
+            self.unstake_request_time = 0;
+        }
+    }
+
+    /// Record a stake or unstake at `slot`, rejecting it if the opposite
+    /// action already happened in the same slot. Prevents stake+unstake
+    /// round trips inside one transaction bundle.
+    pub fn record_action(&mut self, action: StakeAction, slot: u64) -> Result<(), StakingError> {
+        let opposite = match action {
+            StakeAction::Stake => StakeAction::Unstake,
+            StakeAction::Unstake => StakeAction::Stake,
+            StakeAction::None => return Ok(()),
+        };
+        if self.last_action_slot == slot && self.last_action_kind == opposite as u8 {
+            return Err(StakingError::SameSlotAction);
+        }
+        self.last_action_slot = slot;
+        self.last_action_kind = action as u8;
+        Ok(())
+    }
+
+    /// Lazily adjust exp_start_factor when pool has been rebased.
+    /// Must be called before any calculation that uses exp_start_factor.
+    /// Returns true if an adjustment was made.
+    pub fn sync_to_pool(&mut self, pool: &StakingPool) -> Result<bool, StakingError> {
+        if self.base_time_snapshot == pool.base_time {
+            return Ok(false);
+        }
+
+        if self.base_time_snapshot == 0 {
+            // Legacy account (created before rebase-aware upgrade)
+            if pool.initial_base_time == 0 {
+                // No rebase has occurred since upgrade — exp_start_factor is still
+                // relative to the current pool.base_time, so no adjustment needed.
+                self.base_time_snapshot = pool.base_time;
+                return Ok(true);
+            }
+            // A rebase has occurred — adjust from the original base_time
+            self.exp_start_factor = rebase_exp_start_factor(
+                self.exp_start_factor,
+                pool.initial_base_time,
+                pool.base_time,
+                pool.tau_seconds,
+            )?;
+            self.base_time_snapshot = pool.base_time;
+            return Ok(true);
+        }
+
+        // Standard case: adjust from the snapshot's base_time to the current one
+        self.exp_start_factor = rebase_exp_start_factor(
+            self.exp_start_factor,
+            self.base_time_snapshot,
+            pool.base_time,
+            pool.tau_seconds,
+        )?;
+        self.base_time_snapshot = pool.base_time;
+        Ok(true)
+    }
+
+    /// WAD-scaled rewards earned but not yet claimed, given the user's
+    /// current weighted stake: user_weighted * (acc_rps - snapshot) - claimed.
+    /// Only meaningful while amount > 0 (residuals live in reward_debt).
+    pub fn pending_rewards_wad(
+        &self,
+        user_weighted: u128,
+        acc_reward_per_weighted_share: u128,
+    ) -> Result<u128, StakingError> {
+        Ok(pending_rewards_wad(
+            self.amount,
+            self.reward_debt,
+            self.claimed_rewards_wad,
+            user_weighted,
+            acc_reward_per_weighted_share,
+        )?)
+    }
+}
+
+impl BorshDeserialize for UserStake {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let discriminator = <[u8; 8]>::deserialize_reader(reader)?;
+        let owner = Pubkey::deserialize_reader(reader)?;
+        let pool = Pubkey::deserialize_reader(reader)?;
+        let amount = u64::deserialize_reader(reader)?;
+        let stake_time = i64::deserialize_reader(reader)?;
+        let exp_start_factor = u128::deserialize_reader(reader)?;
+        let reward_debt = u128::deserialize_reader(reader)?;
+        let bump = u8::deserialize_reader(reader)?;
+        let unstake_request_amount = u64::deserialize_reader(reader)?;
+        let unstake_request_time = i64::deserialize_reader(reader)?;
+        let last_stake_time = i64::deserialize_reader(reader)?;
+        let base_time_snapshot = i64::deserialize_reader(reader)?;
+
+        // New fields — may not be present in legacy accounts
+        let total_rewards_claimed = u64::deserialize_reader(reader).unwrap_or(0);
+        let claimed_rewards_wad = u128::deserialize_reader(reader).unwrap_or(0);
+        let last_action_slot = u64::deserialize_reader(reader).unwrap_or(0);
+        let last_action_kind = u8::deserialize_reader(reader).unwrap_or(0);
+        let has_weight_history = bool::deserialize_reader(reader).unwrap_or(false);
+        let reward_recipient = Pubkey::deserialize_reader(reader).unwrap_or_default();
+        let first_stake_time = i64::deserialize_reader(reader).unwrap_or(0);
+        let total_deposited = u64::deserialize_reader(reader).unwrap_or(0);
+        let total_withdrawn = u64::deserialize_reader(reader).unwrap_or(0);
+        let has_lock_schedule = bool::deserialize_reader(reader).unwrap_or(false);
+        let state_nonce = u64::deserialize_reader(reader).unwrap_or(0);
+        let has_payout_split = bool::deserialize_reader(reader).unwrap_or(false);
+        let lock_until = i64::deserialize_reader(reader).unwrap_or(0);
+        let maturity_bonus_paid = bool::deserialize_reader(reader).unwrap_or(false);
+        let guardian = Pubkey::deserialize_reader(reader).unwrap_or_default();
+        let guardian_removal_time = i64::deserialize_reader(reader).unwrap_or(0);
+        let sponsored_rent = u64::deserialize_reader(reader).unwrap_or(0);
+        let vesting_rewards_wad = u128::deserialize_reader(reader).unwrap_or(0);
+        let vesting_start = i64::deserialize_reader(reader).unwrap_or(0);
+        let vesting_end = i64::deserialize_reader(reader).unwrap_or(0);
+        let recent_deposits =
+            <[DepositTranche; RECENT_DEPOSIT_SLOTS]>::deserialize_reader(reader).unwrap_or_default();
+        let position_mint = Pubkey::deserialize_reader(reader).unwrap_or_default();
+        let rewards_frozen = bool::deserialize_reader(reader).unwrap_or(false);
+        let reserved = <[u8; USER_STAKE_RESERVED_LEN]>::deserialize_reader(reader)
+            .unwrap_or([0u8; USER_STAKE_RESERVED_LEN]);
+
+        Ok(Self {
+            discriminator,
+            owner,
+            pool,
+            amount,
+            stake_time,
+            exp_start_factor,
+            reward_debt,
+            bump,
+            unstake_request_amount,
+            unstake_request_time,
+            last_stake_time,
+            base_time_snapshot,
+            total_rewards_claimed,
+            claimed_rewards_wad,
+            last_action_slot,
+            last_action_kind,
+            has_weight_history,
+            reward_recipient,
+            first_stake_time,
+            total_deposited,
+            total_withdrawn,
+            has_lock_schedule,
+            state_nonce,
+            has_payout_split,
+            lock_until,
+            maturity_bonus_paid,
+            guardian,
+            guardian_removal_time,
+            sponsored_rent,
+            vesting_rewards_wad,
+            vesting_start,
+            vesting_end,
+            recent_deposits,
+            position_mint,
+            rewards_frozen,
+            reserved,
+        })
+    }
+}
+
+impl UserStake {
+    /// Bump `state_nonce` and serialize into the stake account
+    pub fn save(&mut self, account: &AccountInfo) -> ProgramResult {
+        self.state_nonce = self.state_nonce.wrapping_add(1);
+        self.serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+        Ok(())
+    }
+
+    /// Realloc account to current LEN if it's a legacy (smaller) account.
+    /// Transfers additional rent from payer to the account via system program CPI.
+    /// No-op if account is already at or above current LEN.
+    pub fn maybe_realloc<'a>(
+        account: &AccountInfo<'a>,
+        payer: &AccountInfo<'a>,
+        system_program: Option<&AccountInfo<'a>>,
+    ) -> Result<(), solana_program::program_error::ProgramError> {
+        if account.data_len() >= Self::LEN {
+            return Ok(());
+        }
+
+        // Only the shortfall is charged, so owners that can't pay via the
+        // system program (program-owned PDAs) can prefund the account instead
+        let rent = solana_program::rent::Rent::get()?;
+        let new_rent = rent.minimum_balance(Self::LEN);
+        let rent_delta = new_rent.saturating_sub(account.lamports());
+
+        if rent_delta > 0 {
+            let sys_prog = system_program
+                .ok_or(StakingError::MissingSystemProgram)?;
+            solana_program::program::invoke(
+                &solana_program::system_instruction::transfer(
+                    payer.key,
+                    account.key,
+                    rent_delta,
+                ),
+                &[payer.clone(), account.clone(), sys_prog.clone()],
+            )?;
+        }
+
+        account.realloc(Self::LEN, false)?;
+
+        Ok(())
+    }
+}
+
+/// Pool metadata account for explorer display
+/// PDA: ["metadata", pool]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct PoolMetadata {
+    /// Discriminator for account type identification
+    pub discriminator: [u8; 8],
+
+    /// Back-reference to staking pool
+    pub pool: Pubkey,
+
+    /// Actual byte length of name
+    pub name_len: u8,
+
+    /// UTF-8 name, zero-padded
+    pub name: [u8; 64],
+
+    /// Number of active tags (max 8)
+    pub num_tags: u8,
+
+    /// Byte length of each tag
+    pub tag_lengths: [u8; 8],
+
+    /// UTF-8 tags, zero-padded
+    pub tags: [[u8; 32]; 8],
+
+    /// Actual byte length of url
+    pub url_len: u8,
+
+    /// UTF-8 URL, zero-padded
+    pub url: [u8; 128],
+
+    /// Active staker count
+    pub member_count: u64,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl PoolMetadata {
+    /// Size of the account in bytes
+    pub const LEN: usize = 8 +  // discriminator
+        32 + // pool
+        1 +  // name_len
+        64 + // name
+        1 +  // num_tags
+        8 +  // tag_lengths
+        256 + // tags (8 * 32)
+        1 +  // url_len
+        128 + // url
+        8 +  // member_count
+        1;   // bump
+
+    /// Derive metadata PDA
+    pub fn derive_pda(pool: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[METADATA_SEED, pool.as_ref()], program_id)
+    }
+
+    /// Check if metadata is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.discriminator == METADATA_DISCRIMINATOR
+    }
+}
+
+/// Per-position weighted-stake history for external protocols
+/// PDA: ["weight_history", pool, owner]
+///
+/// Fixed header followed by `capacity` checkpoints. Checkpoints are written
+/// in order to slot `count % capacity`; once `count > capacity` the oldest
+/// are overwritten, so the retained window is the last `capacity` entries.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct WeightHistory {
+    /// Discriminator for account type identification
+    pub discriminator: [u8; 8],
+
+    /// Back-reference to staking pool
+    pub pool: Pubkey,
+
+    /// Owner of the tracked position
+    pub owner: Pubkey,
+
+    /// Maximum number of retained checkpoints
+    pub capacity: u16,
+
+    /// Total checkpoints ever written
+    pub count: u64,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+/// Position state right after a stake or unstake
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WeightCheckpoint {
+    /// Unix timestamp of the action
+    pub timestamp: i64,
+
+    /// Staked amount after the action
+    pub amount: u64,
+
+    /// Weighted stake after the action (WAD-scaled)
+    pub weighted_stake: u128,
+}
+
+impl WeightCheckpoint {
+    /// Size of one checkpoint in bytes
+    pub const LEN: usize = 8 + 8 + 16;
+
+    /// Weighted stake at `time >= timestamp`, assuming no action in between:
+    /// w(t) = amount - (amount - w0) * e^(-(t - t0)/tau)
+    pub fn weighted_at(&self, time: i64, tau: u64) -> Result<u128, StakingError> {
+        let amount_wad = (self.amount as u128)
+            .checked_mul(WAD)
+            .ok_or(StakingError::MathOverflow)?;
+        let immature = amount_wad.saturating_sub(self.weighted_stake);
+        let decay = exp_neg_time_ratio(time.saturating_sub(self.timestamp), tau)?;
+        Ok(amount_wad.saturating_sub(wad_mul(immature, decay)?))
+    }
+
+    /// Integral of the weighted stake over [from, to] within this segment
+    /// (WAD-scaled token-seconds):
+    /// amount*(to-from) - (amount - w(from)) * tau * (1 - e^(-(to-from)/tau))
+    pub fn integral(&self, from: i64, to: i64, tau: u64) -> Result<U256, StakingError> {
+        if to <= from {
+            return Ok(U256::zero());
+        }
+        let amount_wad = (self.amount as u128)
+            .checked_mul(WAD)
+            .ok_or(StakingError::MathOverflow)?;
+        let immature = amount_wad.saturating_sub(self.weighted_at(from, tau)?);
+        let one_minus_decay = WAD.saturating_sub(exp_neg_time_ratio(to - from, tau)?);
+        let lost = U256::from_u128(wad_mul(immature, one_minus_decay)?)
+            .checked_mul(U256::from(tau))
+            .ok_or(StakingError::MathOverflow)?;
+        Ok(U256::from_u128(amount_wad)
+            .checked_mul(U256::from((to - from) as u64))
+            .ok_or(StakingError::MathOverflow)?
+            .saturating_sub(lost))
+    }
+}
+
+impl WeightHistory {
+    /// Size of the header in bytes
+    pub const HEADER_LEN: usize = 8 +  // discriminator
+        32 + // pool
+        32 + // owner
+        2 +  // capacity
+        8 +  // count
+        1;   // bump
+
+    /// Maximum capacity (keeps the account within one allocation)
+    pub const MAX_CAPACITY: u16 = 256;
+
+    /// Account size for `capacity` checkpoints
+    pub const fn space(capacity: u16) -> usize {
+        Self::HEADER_LEN + capacity as usize * WeightCheckpoint::LEN
+    }
+
+    /// Derive weight history PDA
+    pub fn derive_pda(pool: &Pubkey, owner: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[WEIGHT_HISTORY_SEED, pool.as_ref(), owner.as_ref()],
+            program_id,
+        )
+    }
+
+    /// Check if history is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.discriminator == WEIGHT_HISTORY_DISCRIMINATOR
+    }
+
+    /// Number of checkpoints currently retained
+    pub fn retained(&self) -> u64 {
+        self.count.min(self.capacity as u64)
+    }
+
+    /// Read the `n`-th retained checkpoint, oldest first
+    pub fn get(&self, data: &[u8], n: u64) -> Option<WeightCheckpoint> {
+        if n >= self.retained() {
+            return None;
+        }
+        let seq = self.count - self.retained() + n;
+        let offset = Self::HEADER_LEN + (seq % self.capacity as u64) as usize * WeightCheckpoint::LEN;
+        WeightCheckpoint::try_from_slice(data.get(offset..offset + WeightCheckpoint::LEN)?).ok()
+    }
+
+    /// Append a checkpoint and persist the header
+    pub fn push(&mut self, data: &mut [u8], checkpoint: &WeightCheckpoint) -> ProgramResult {
+        let slot = (self.count % self.capacity as u64) as usize;
+        let offset = Self::HEADER_LEN + slot * WeightCheckpoint::LEN;
+        let mut entry = data
+            .get_mut(offset..offset + WeightCheckpoint::LEN)
+            .ok_or(StakingError::AccountDataTooSmall)?;
+        checkpoint.serialize(&mut entry)?;
+        self.count = self.count.saturating_add(1);
+        self.serialize(&mut &mut data[..Self::HEADER_LEN])?;
+        Ok(())
+    }
+
+    /// Time-weighted average weighted stake over [from, to] (WAD-scaled).
+    /// `from` must not precede the oldest retained checkpoint; before the
+    /// first ever checkpoint the position did not exist and counts as 0.
+    pub fn average_weighted_stake(
+        &self,
+        data: &[u8],
+        tau: u64,
+        from: i64,
+        to: i64,
+    ) -> Result<u128, StakingError> {
+        if to <= from {
+            return Err(StakingError::InvalidInstruction);
+        }
+        let retained = self.retained();
+        let oldest = self.get(data, 0).ok_or(StakingError::HistoryWindowExceeded)?;
+        if self.count > retained && from < oldest.timestamp {
+            return Err(StakingError::HistoryWindowExceeded);
+        }
+
+        let mut total = U256::zero();
+        for n in 0..retained {
+            let checkpoint = self.get(data, n).ok_or(StakingError::AccountDataTooSmall)?;
+            let segment_end = match self.get(data, n + 1) {
+                Some(next) => next.timestamp,
+                None => i64::MAX,
+            };
+            let start = from.max(checkpoint.timestamp);
+            let end = to.min(segment_end);
+            total = total
+                .checked_add(checkpoint.integral(start, end, tau)?)
+                .ok_or(StakingError::MathOverflow)?;
+        }
+        (total / U256::from((to - from) as u64))
+            .to_u128()
+            .ok_or(StakingError::MathOverflow)
+    }
+}
+
+/// Lock tranches of a single position (team/investor cliffs)
+/// PDA: ["lock_schedule", pool, owner]
+///
+/// Fixed header followed by `count` tranches. The account grows as tranches
+/// are added; tranches whose unlock_time has passed are pruned at that point.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct UserLockSchedule {
+    /// Discriminator for account type identification
+    pub discriminator: [u8; 8],
+
+    /// Back-reference to staking pool
+    pub pool: Pubkey,
+
+    /// Owner of the locked position
+    pub owner: Pubkey,
+
+    /// Number of stored tranches
+    pub count: u16,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+/// Tokens of a position that can't be unstaked before `unlock_time`
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockTranche {
+    /// Locked token amount (raw units)
+    pub amount: u64,
+
+    /// Unix timestamp from which the tranche is unlocked
+    pub unlock_time: i64,
+}
+
+impl LockTranche {
+    /// Size of one tranche in bytes
+    pub const LEN: usize = 8 + 8;
+
+    /// Whether the tranche still holds its tokens at `time`
+    pub fn is_locked(&self, time: i64) -> bool {
+        time < self.unlock_time
+    }
+}
+
+impl UserLockSchedule {
+    /// Size of the header in bytes
+    pub const HEADER_LEN: usize = 8 +  // discriminator
+        32 + // pool
+        32 + // owner
+        2 +  // count
+        1;   // bump
+
+    /// Maximum number of simultaneously locked tranches
+    pub const MAX_TRANCHES: usize = 32;
+
+    /// Account size for `count` tranches
+    pub const fn space(count: usize) -> usize {
+        Self::HEADER_LEN + count * LockTranche::LEN
+    }
+
+    /// Derive lock schedule PDA
+    pub fn derive_pda(pool: &Pubkey, owner: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[LOCK_SCHEDULE_SEED, pool.as_ref(), owner.as_ref()],
+            program_id,
+        )
+    }
+
+    /// Check if schedule is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.discriminator == LOCK_SCHEDULE_DISCRIMINATOR
+    }
+
+    /// Read all stored tranches
+    pub fn tranches(&self, data: &[u8]) -> Result<Vec<LockTranche>, StakingError> {
+        let body = data
+            .get(Self::HEADER_LEN..Self::space(self.count as usize))
+            .ok_or(StakingError::AccountDataTooSmall)?;
+        body.chunks_exact(LockTranche::LEN)
+            .map(|chunk| LockTranche::try_from_slice(chunk).map_err(|_| StakingError::InvalidInstruction))
+            .collect()
+    }
+
+    /// Total tokens still locked at `time`
+    pub fn locked_amount(&self, data: &[u8], time: i64) -> Result<u64, StakingError> {
+        Ok(self
+            .tranches(data)?
+            .iter()
+            .filter(|t| t.is_locked(time))
+            .fold(0u64, |sum, t| sum.saturating_add(t.amount)))
+    }
+
+    /// Tranches still locked at `time` plus `tranche`, or LockScheduleFull
+    pub fn with_tranche(
+        tranches: &[LockTranche],
+        tranche: LockTranche,
+        time: i64,
+    ) -> Result<Vec<LockTranche>, StakingError> {
+        let mut kept: Vec<LockTranche> =
+            tranches.iter().copied().filter(|t| t.is_locked(time)).collect();
+        if kept.len() >= Self::MAX_TRANCHES {
+            return Err(StakingError::LockScheduleFull);
+        }
+        kept.push(tranche);
+        Ok(kept)
+    }
+
+    /// Replace the stored tranches and persist the header
+    pub fn write(&mut self, data: &mut [u8], tranches: &[LockTranche]) -> ProgramResult {
+        let body = data
+            .get_mut(Self::HEADER_LEN..Self::space(tranches.len()))
+            .ok_or(StakingError::AccountDataTooSmall)?;
+        for (chunk, tranche) in body.chunks_exact_mut(LockTranche::LEN).zip(tranches) {
+            tranche.serialize(&mut &mut chunk[..])?;
+        }
+        self.count = tranches.len() as u16;
+        self.serialize(&mut &mut data[..Self::HEADER_LEN])?;
+        Ok(())
+    }
+}
+
+/// Append-only record of authority actions on a pool
+/// PDA: ["authority_log", pool]
+///
+/// Fixed header followed by `count` entries. The account grows by one entry
+/// per recorded change; entries are never rewritten.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct AuthorityLog {
+    /// Discriminator for account type identification
+    pub discriminator: [u8; 8],
+
+    /// Back-reference to staking pool
+    pub pool: Pubkey,
+
+    /// Total entries written
+    pub count: u64,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+/// Pool setting changed by an authority action (see [`AuthorityLogEntry`])
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum AuthorityAction {
+    MinStakeAmount = 0,
+    LockDuration = 1,
+    UnstakeCooldown = 2,
+    Authority = 3,
+    RewardsPaused = 4,
+    RefundAddress = 5,
+    RefundAfterSeconds = 6,
+    DistributionThreshold = 7,
+    UnstakeFeeBps = 8,
+    UnstakeFeeDestination = 9,
+    UnstakeRequestExpiry = 10,
+    RoundingMode = 11,
+    DepositorAllowlist = 12,
+    LockBoostBps = 13,
+    MaturityBonusBps = 14,
+    BootstrapPeriod = 15,
+    MatchBps = 16,
+    CompressionTree = 17,
+    RentSponsor = 18,
+    MinSyncInterval = 19,
+    WeightTiers = 20,
+    ClaimVesting = 21,
+    PriceOracle = 22,
+    ClaimRebate = 23,
+    ClaimBurnBps = 24,
+    ClaimBurnDestination = 25,
+    ResidualsFirst = 26,
+    MinClaimLamports = 27,
+    DistributionHistory = 28,
+    PoolMetadata = 29,
+    DepositorTracking = 30,
+    RebaseThreshold = 31,
+    VaultMigration = 32,
+    LockExemptAmount = 33,
+    /// Per position: each value is the stake account while frozen, else default
+    StakeFreeze = 34,
+    StakeGuardRequired = 35,
+}
+
+/// One changed setting. Integers and flags are stored little-endian in the
+/// first 8 bytes of the value (rest zero); pubkeys use all 32 bytes.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuthorityLogEntry {
+    /// Unix timestamp of the action
+    pub timestamp: i64,
+
+    /// Authority that signed the action
+    pub authority: Pubkey,
+
+    /// Changed setting (see [`AuthorityAction`])
+    pub action: u8,
+
+    /// Value before the action
+    pub old_value: [u8; 32],
+
+    /// Value after the action
+    pub new_value: [u8; 32],
+}
+
+impl AuthorityLogEntry {
+    /// Size of one entry in bytes
+    pub const LEN: usize = 8 + 32 + 1 + 32 + 32;
+
+    /// Entry for an integer (or flag) setting
+    pub fn integer(
+        timestamp: i64,
+        authority: Pubkey,
+        action: AuthorityAction,
+        old: u64,
+        new: u64,
+    ) -> Self {
+        let encode = |v: u64| {
+            let mut value = [0u8; 32];
+            value[..8].copy_from_slice(&v.to_le_bytes());
+            value
+        };
+        Self {
+            timestamp,
+            authority,
+            action: action as u8,
+            old_value: encode(old),
+            new_value: encode(new),
+        }
+    }
+
+    /// Entry for a pubkey setting
+    pub fn pubkey(
+        timestamp: i64,
+        authority: Pubkey,
+        action: AuthorityAction,
+        old: Pubkey,
+        new: Pubkey,
+    ) -> Self {
+        Self {
+            timestamp,
+            authority,
+            action: action as u8,
+            old_value: old.to_bytes(),
+            new_value: new.to_bytes(),
+        }
+    }
+}
+
+impl AuthorityLog {
+    /// Size of the header in bytes
+    pub const HEADER_LEN: usize = 8 +  // discriminator
+        32 + // pool
+        8 +  // count
+        1;   // bump
+
+    /// Account size for `count` entries
+    pub const fn space(count: u64) -> usize {
+        Self::HEADER_LEN + count as usize * AuthorityLogEntry::LEN
+    }
+
+    /// Derive authority log PDA
+    pub fn derive_pda(pool: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[AUTHORITY_LOG_SEED, pool.as_ref()], program_id)
+    }
+
+    /// Check if log is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.discriminator == AUTHORITY_LOG_DISCRIMINATOR
+    }
+
+    /// Read the `n`-th entry, oldest first
+    pub fn get(&self, data: &[u8], n: u64) -> Option<AuthorityLogEntry> {
+        if n >= self.count {
+            return None;
+        }
+        let offset = Self::space(n);
+        AuthorityLogEntry::try_from_slice(data.get(offset..offset + AuthorityLogEntry::LEN)?).ok()
+    }
+
+    /// Append entries (the account must already have room) and persist the header
+    pub fn append(&mut self, data: &mut [u8], entries: &[AuthorityLogEntry]) -> ProgramResult {
+        for entry in entries {
+            let offset = Self::space(self.count);
+            let mut slot = data
+                .get_mut(offset..offset + AuthorityLogEntry::LEN)
+                .ok_or(StakingError::AccountDataTooSmall)?;
+            entry.serialize(&mut slot)?;
+            self.count = self.count.saturating_add(1);
+        }
+        self.serialize(&mut &mut data[..Self::HEADER_LEN])?;
+        Ok(())
+    }
+}
+
+/// One page of a pool's distribution checkpoints
+/// PDA: ["distribution_history", pool, page (u32 LE)]
+///
+/// Fixed header followed by CAPACITY checkpoints, filled in order. Pages
+/// are never rewritten, so together they keep acc_reward_per_weighted_share
+/// after every distribution since InitDistributionHistory: enough to
+/// recompute any past entitlement after its stake account is gone.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct DistributionHistory {
+    /// Discriminator for account type identification
+    pub discriminator: [u8; 8],
+
+    /// Back-reference to staking pool
+    pub pool: Pubkey,
+
+    /// Page index
+    pub page: u32,
+
+    /// Checkpoints written to this page
+    pub count: u16,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+/// Pool accumulator right after a distribution
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DistributionCheckpoint {
+    /// Unix timestamp of the distribution
+    pub timestamp: i64,
+
+    /// acc_reward_per_weighted_share after the distribution
+    pub acc_reward_per_weighted_share: u128,
+
+    /// Lamports distributed (before the protocol fee)
+    pub lamports: u64,
+}
+
+impl DistributionCheckpoint {
+    /// Size of one checkpoint in bytes
+    pub const LEN: usize = 8 + 16 + 8;
+}
+
+impl DistributionHistory {
+    /// Size of the header in bytes
+    pub const HEADER_LEN: usize = 8 +  // discriminator
+        32 + // pool
+        4 +  // page
+        2 +  // count
+        1;   // bump
+
+    /// Checkpoints per page
+    pub const CAPACITY: u16 = 128;
+
+    /// Account size of a page
+    pub const LEN: usize = Self::HEADER_LEN + Self::CAPACITY as usize * DistributionCheckpoint::LEN;
+
+    /// Derive the PDA of page `page`
+    pub fn derive_pda(pool: &Pubkey, page: u32, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[DISTRIBUTION_HISTORY_SEED, pool.as_ref(), &page.to_le_bytes()],
+            program_id,
+        )
+    }
+
+    /// Page receiving a pool's next checkpoint after `checkpoints` written
+    pub fn page_of(checkpoints: u64) -> u32 {
+        (checkpoints / Self::CAPACITY as u64).min(u32::MAX as u64) as u32
+    }
+
+    /// Check if page is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.discriminator == DISTRIBUTION_HISTORY_DISCRIMINATOR
+    }
+
+    /// Read the `n`-th checkpoint of this page
+    pub fn get(&self, data: &[u8], n: u16) -> Option<DistributionCheckpoint> {
+        if n >= self.count {
+            return None;
+        }
+        let offset = Self::HEADER_LEN + n as usize * DistributionCheckpoint::LEN;
+        DistributionCheckpoint::try_from_slice(data.get(offset..offset + DistributionCheckpoint::LEN)?)
+            .ok()
+    }
+
+    /// Append a checkpoint and persist the header
+    pub fn push(&mut self, data: &mut [u8], checkpoint: &DistributionCheckpoint) -> ProgramResult {
+        if self.count >= Self::CAPACITY {
+            return Err(StakingError::AccountDataTooSmall.into());
+        }
+        let offset = Self::HEADER_LEN + self.count as usize * DistributionCheckpoint::LEN;
+        let mut entry = data
+            .get_mut(offset..offset + DistributionCheckpoint::LEN)
+            .ok_or(StakingError::AccountDataTooSmall)?;
+        checkpoint.serialize(&mut entry)?;
+        self.count += 1;
+        self.serialize(&mut &mut data[..Self::HEADER_LEN])?;
+        Ok(())
+    }
+}
+
+/// SOL rewards staged for timed release into a pool
+/// PDA: ["pending_distributions", pool]
+///
+/// Fixed header followed by `count` tranches. The account holds the staged
+/// lamports on top of its rent; released tranches are removed.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct PendingDistributions {
+    /// Discriminator for account type identification
+    pub discriminator: [u8; 8],
+
+    /// Back-reference to staking pool
+    pub pool: Pubkey,
+
+    /// Number of stored tranches
+    pub count: u16,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+/// Lamports that become distributable at `release_time`
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PendingTranche {
+    /// Unix timestamp from which the tranche can be released
+    pub release_time: i64,
+
+    /// Staged lamports
+    pub amount: u64,
+}
+
+impl PendingTranche {
+    /// Size of one tranche in bytes
+    pub const LEN: usize = 8 + 8;
+
+    /// Whether the tranche can be released at `time`
+    pub fn is_due(&self, time: i64) -> bool {
+        time >= self.release_time
+    }
+}
+
+impl PendingDistributions {
+    /// Size of the header in bytes
+    pub const HEADER_LEN: usize = 8 +  // discriminator
+        32 + // pool
+        2 +  // count
+        1;   // bump
+
+    /// Maximum number of staged tranches
+    pub const MAX_TRANCHES: usize = 64;
+
+    /// Account size for `count` tranches
+    pub const fn space(count: usize) -> usize {
+        Self::HEADER_LEN + count * PendingTranche::LEN
+    }
+
+    /// Derive pending distributions PDA
+    pub fn derive_pda(pool: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[PENDING_DISTRIBUTIONS_SEED, pool.as_ref()], program_id)
+    }
+
+    /// Check if account is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.discriminator == PENDING_DISTRIBUTIONS_DISCRIMINATOR
+    }
+
+    /// Read all stored tranches
+    pub fn tranches(&self, data: &[u8]) -> Result<Vec<PendingTranche>, StakingError> {
+        let body = data
+            .get(Self::HEADER_LEN..Self::space(self.count as usize))
+            .ok_or(StakingError::AccountDataTooSmall)?;
+        body.chunks_exact(PendingTranche::LEN)
+            .map(|chunk| PendingTranche::try_from_slice(chunk).map_err(|_| StakingError::InvalidInstruction))
+            .collect()
+    }
+
+    /// Split `amount` into tranches released `offset_seconds` after `time`.
+    /// Shares must be non-zero and sum to 10000 bps; rounding dust goes to
+    /// the last entry and tranches that round to zero are dropped.
+    pub fn split_schedule(
+        amount: u64,
+        schedule: &[(u64, u16)],
+        time: i64,
+    ) -> Result<Vec<PendingTranche>, StakingError> {
+        let total_bps = schedule
+            .iter()
+            .try_fold(0u32, |sum, &(_, bps)| (bps > 0).then_some(sum + bps as u32));
+        if schedule.is_empty() || total_bps != Some(10_000) {
+            return Err(StakingError::InvalidDistributionSchedule);
+        }
+
+        let mut remaining = amount;
+        let mut tranches = Vec::with_capacity(schedule.len());
+        for (i, &(offset_seconds, bps)) in schedule.iter().enumerate() {
+            let share = if i + 1 == schedule.len() {
+                remaining
+            } else {
+                (amount as u128 * bps as u128 / 10_000) as u64
+            };
+            remaining -= share;
+            let release_time = i64::try_from(offset_seconds)
+                .ok()
+                .and_then(|offset| time.checked_add(offset))
+                .ok_or(StakingError::MathOverflow)?;
+            if share > 0 {
+                tranches.push(PendingTranche { release_time, amount: share });
+            }
+        }
+        Ok(tranches)
+    }
+
+    /// Total lamports of the tranches due at `time`, and the tranches left
+    pub fn take_due(tranches: &[PendingTranche], time: i64) -> (u64, Vec<PendingTranche>) {
+        let (due, kept): (Vec<PendingTranche>, Vec<PendingTranche>) =
+            tranches.iter().partition(|t| t.is_due(time));
+        let released = due.iter().fold(0u64, |sum, t| sum.saturating_add(t.amount));
+        (released, kept)
+    }
+
+    /// Replace the stored tranches and persist the header
+    pub fn write(&mut self, data: &mut [u8], tranches: &[PendingTranche]) -> ProgramResult {
+        let body = data
+            .get_mut(Self::HEADER_LEN..Self::space(tranches.len()))
+            .ok_or(StakingError::AccountDataTooSmall)?;
+        for (chunk, tranche) in body.chunks_exact_mut(PendingTranche::LEN).zip(tranches) {
+            tranche.serialize(&mut &mut chunk[..])?;
+        }
+        self.count = tranches.len() as u16;
+        self.serialize(&mut &mut data[..Self::HEADER_LEN])?;
+        Ok(())
+    }
+}
+
+/// Reward payout split of a single position
+/// PDA: ["payout_split", pool, owner]
+///
+/// Fixed-size: the first `count` destinations are in use.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct PayoutSplit {
+    /// Discriminator for account type identification
+    pub discriminator: [u8; 8],
+
+    /// Back-reference to staking pool
+    pub pool: Pubkey,
+
+    /// Owner of the position
+    pub owner: Pubkey,
+
+    /// Number of destinations in use
+    pub count: u8,
+
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Destinations and their shares in bps
+    pub destinations: [PayoutDestination; PayoutSplit::MAX_DESTINATIONS],
+}
+
+/// Account credited with `bps` / 10000 of each reward payout
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PayoutDestination {
+    /// Account receiving the share
+    pub recipient: Pubkey,
+
+    /// Share in basis points
+    pub bps: u16,
+}
+
+impl PayoutSplit {
+    /// Maximum number of destinations
+    pub const MAX_DESTINATIONS: usize = 4;
+
+    /// Size of the account in bytes
+    pub const LEN: usize = 8 +  // discriminator
+        32 + // pool
+        32 + // owner
+        1 +  // count
+        1 +  // bump
+        Self::MAX_DESTINATIONS * (32 + 2); // destinations
+
+    /// Derive payout split PDA
+    pub fn derive_pda(pool: &Pubkey, owner: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[PAYOUT_SPLIT_SEED, pool.as_ref(), owner.as_ref()],
+            program_id,
+        )
+    }
+
+    /// Check if split is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.discriminator == PAYOUT_SPLIT_DISCRIMINATOR
+    }
+
+    /// Destinations in use
+    pub fn active(&self) -> &[PayoutDestination] {
+        &self.destinations[..(self.count as usize).min(Self::MAX_DESTINATIONS)]
+    }
+
+    /// Replace the destinations. There must be 1-4 distinct, non-default
+    /// recipients with non-zero shares summing to 10000 bps.
+    pub fn set_destinations(&mut self, destinations: &[(Pubkey, u16)]) -> Result<(), StakingError> {
+        let total_bps = destinations
+            .iter()
+            .try_fold(0u32, |sum, &(_, bps)| (bps > 0).then_some(sum + bps as u32));
+        let distinct = destinations.iter().enumerate().all(|(i, (key, _))| {
+            *key != Pubkey::default() && destinations[..i].iter().all(|(other, _)| other != key)
+        });
+        if destinations.is_empty()
+            || destinations.len() > Self::MAX_DESTINATIONS
+            || total_bps != Some(10_000)
+            || !distinct
+        {
+            return Err(StakingError::InvalidPayoutSplit);
+        }
+
+        self.destinations = [PayoutDestination::default(); Self::MAX_DESTINATIONS];
+        for (slot, &(recipient, bps)) in self.destinations.iter_mut().zip(destinations) {
+            *slot = PayoutDestination { recipient, bps };
+        }
+        self.count = destinations.len() as u8;
+        Ok(())
+    }
+
+    /// Lamports of `amount` owed to each active destination, in order.
+    /// Rounding dust goes to the last destination.
+    pub fn shares(&self, amount: u64) -> Vec<u64> {
+        let active = self.active();
+        let mut remaining = amount;
+        active
+            .iter()
+            .enumerate()
+            .map(|(i, d)| {
+                let share = if i + 1 == active.len() {
+                    remaining
+                } else {
+                    (amount as u128 * d.bps as u128 / 10_000) as u64
+                };
+                remaining -= share;
+                share
+            })
+            .collect()
+    }
+}
+
+/// Depositors allowed to fund a pool's rewards
+/// PDA: ["depositor_allowlist", pool]
+///
+/// Fixed-size: the first `count` depositors are in use.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct DepositorAllowlist {
+    /// Discriminator for account type identification
+    pub discriminator: [u8; 8],
+
+    /// Back-reference to staking pool
+    pub pool: Pubkey,
+
+    /// Number of depositors in use
+    pub count: u8,
+
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Allowed depositor keys
+    pub depositors: [Pubkey; DepositorAllowlist::MAX_DEPOSITORS],
+}
+
+impl DepositorAllowlist {
+    /// Maximum number of depositors
+    pub const MAX_DEPOSITORS: usize = 8;
+
+    /// Size of the account in bytes
+    pub const LEN: usize = 8 +  // discriminator
+        32 + // pool
+        1 +  // count
+        1 +  // bump
+        Self::MAX_DEPOSITORS * 32; // depositors
+
+    /// Derive depositor allowlist PDA
+    pub fn derive_pda(pool: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[DEPOSITOR_ALLOWLIST_SEED, pool.as_ref()], program_id)
+    }
+
+    /// Check if allowlist is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.discriminator == DEPOSITOR_ALLOWLIST_DISCRIMINATOR
+    }
+
+    /// Depositors in use
+    pub fn active(&self) -> &[Pubkey] {
+        &self.depositors[..(self.count as usize).min(Self::MAX_DEPOSITORS)]
+    }
+
+    /// Whether `depositor` may deposit
+    pub fn contains(&self, depositor: &Pubkey) -> bool {
+        self.active().contains(depositor)
+    }
+
+    /// Replace the depositors. There must be 1-8 distinct, non-default keys.
+    pub fn set_depositors(&mut self, depositors: &[Pubkey]) -> Result<(), StakingError> {
+        let distinct = depositors.iter().enumerate().all(|(i, key)| {
+            *key != Pubkey::default() && !depositors[..i].contains(key)
+        });
+        if depositors.is_empty() || depositors.len() > Self::MAX_DEPOSITORS || !distinct {
+            return Err(StakingError::InvalidDepositorAllowlist);
+        }
+
+        self.depositors = [Pubkey::default(); Self::MAX_DEPOSITORS];
+        self.depositors[..depositors.len()].copy_from_slice(depositors);
+        self.count = depositors.len() as u8;
+        Ok(())
+    }
+}
+
+/// Weighted-stake thresholds for partner fee tiers
+/// PDA: ["weight_tiers", pool]
+///
+/// Fixed-size: the first `count` thresholds are in use. A position is in
+/// tier N when its weighted stake reaches N thresholds (tier 0 below all).
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct WeightTiers {
+    /// Discriminator for account type identification
+    pub discriminator: [u8; 8],
+
+    /// Back-reference to staking pool
+    pub pool: Pubkey,
+
+    /// Number of thresholds in use
+    pub count: u8,
+
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Weighted stake thresholds (raw token units), strictly increasing
+    pub thresholds: [u64; WeightTiers::MAX_TIERS],
+}
+
+impl WeightTiers {
+    /// Maximum number of thresholds
+    pub const MAX_TIERS: usize = 8;
+
+    /// Size of the account in bytes
+    pub const LEN: usize = 8 +  // discriminator
+        32 + // pool
+        1 +  // count
+        1 +  // bump
+        Self::MAX_TIERS * 8; // thresholds
+
+    /// Derive weight tiers PDA
+    pub fn derive_pda(pool: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[WEIGHT_TIERS_SEED, pool.as_ref()], program_id)
+    }
+
+    /// Check if the tiers are initialized
+    pub fn is_initialized(&self) -> bool {
+        self.discriminator == WEIGHT_TIERS_DISCRIMINATOR
+    }
+
+    /// Thresholds in use
+    pub fn active(&self) -> &[u64] {
+        &self.thresholds[..(self.count as usize).min(Self::MAX_TIERS)]
+    }
+
+    /// Tier of a weighted stake (raw token units): thresholds reached
+    pub fn tier_of(&self, weighted_stake: u64) -> u8 {
+        self.active().iter().take_while(|&&t| weighted_stake >= t).count() as u8
+    }
+
+    /// Replace the thresholds. There must be 1-8, nonzero and strictly
+    /// increasing.
+    pub fn set_thresholds(&mut self, thresholds: &[u64]) -> Result<(), StakingError> {
+        let increasing = thresholds.windows(2).all(|pair| pair[0] < pair[1]);
+        if thresholds.is_empty()
+            || thresholds.len() > Self::MAX_TIERS
+            || thresholds[0] == 0
+            || !increasing
+        {
+            return Err(StakingError::InvalidWeightTiers);
+        }
+
+        self.thresholds = [0; Self::MAX_TIERS];
+        self.thresholds[..thresholds.len()].copy_from_slice(thresholds);
+        self.count = thresholds.len() as u8;
+        Ok(())
+    }
+}
+
+/// Lamports set aside by the authority for maturity bonuses
+/// PDA: ["bonus_budget", pool]
+///
+/// Funded with plain SOL transfers; everything above the rent-exempt
+/// minimum is available to ClaimMaturityBonus.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct BonusBudget {
+    /// Discriminator for account type identification
+    pub discriminator: [u8; 8],
+
+    /// Back-reference to staking pool
+    pub pool: Pubkey,
+
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Lamports paid out as maturity bonuses
+    pub total_paid: u64,
+}
+
+impl BonusBudget {
+    /// Size of the account in bytes
+    pub const LEN: usize = 8 +  // discriminator
+        32 + // pool
+        1 +  // bump
+        8;   // total_paid
+
+    /// Derive bonus budget PDA
+    pub fn derive_pda(pool: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[BONUS_BUDGET_SEED, pool.as_ref()], program_id)
+    }
+
+    /// Check if budget is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.discriminator == BONUS_BUDGET_DISCRIMINATOR
+    }
+}
+
+/// Lamports set aside by the authority to match reward distributions
+/// PDA: ["matching_vault", pool]
+///
+/// Funded with plain SOL transfers; everything above the rent-exempt
+/// minimum is available for matching.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct MatchingVault {
+    /// Discriminator for account type identification
+    pub discriminator: [u8; 8],
+
+    /// Back-reference to staking pool
+    pub pool: Pubkey,
+
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Match per distributed lamport, in basis points
+    pub match_bps: u16,
+
+    /// Lamports moved into the pool as matches
+    pub total_matched: u64,
+}
+
+impl MatchingVault {
+    /// Size of the account in bytes
+    pub const LEN: usize = 8 +  // discriminator
+        32 + // pool
+        1 +  // bump
+        2 +  // match_bps
+        8;   // total_matched
+
+    /// Derive matching vault PDA
+    pub fn derive_pda(pool: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[MATCHING_VAULT_SEED, pool.as_ref()], program_id)
+    }
+
+    /// Check if vault is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.discriminator == MATCHING_VAULT_DISCRIMINATOR
+    }
+
+    /// Match owed for distributing `lamports`, capped at what the vault
+    /// holds above rent (`available`)
+    pub fn match_for(&self, lamports: u64, available: u64) -> u64 {
+        let owed = (lamports as u128 * self.match_bps as u128 / 10_000) as u64;
+        owed.min(available)
+    }
+}
+
+/// Lamports set aside by the authority to pay new stakers' account rent
+/// PDA: ["rent_sponsor", pool]
+///
+/// Funded with plain SOL transfers; everything above the rent-exempt
+/// minimum is available. Rent of sponsored stake accounts returns here
+/// when they are closed.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct RentSponsorVault {
+    /// Discriminator for account type identification
+    pub discriminator: [u8; 8],
+
+    /// Back-reference to staking pool
+    pub pool: Pubkey,
+
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Stake accounts whose rent the vault currently covers
+    pub sponsored_accounts: u64,
+
+    /// Lamports currently lent out as stake account rent
+    pub sponsored_lamports: u64,
+}
+
+impl RentSponsorVault {
+    /// Size of the account in bytes
+    pub const LEN: usize = 8 +  // discriminator
+        32 + // pool
+        1 +  // bump
+        8 +  // sponsored_accounts
+        8;   // sponsored_lamports
+
+    /// Derive rent sponsor vault PDA
+    pub fn derive_pda(pool: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[RENT_SPONSOR_SEED, pool.as_ref()], program_id)
+    }
+
+    /// Check if vault is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.discriminator == RENT_SPONSOR_DISCRIMINATOR
+    }
+
+    /// Record `lamports` of rent paid for a new stake account
+    pub fn record_sponsored(&mut self, lamports: u64) {
+        self.sponsored_accounts = self.sponsored_accounts.saturating_add(1);
+        self.sponsored_lamports = self.sponsored_lamports.saturating_add(lamports);
+    }
+
+    /// Record `lamports` of rent returned by a closed stake account
+    pub fn record_returned(&mut self, lamports: u64) {
+        self.sponsored_accounts = self.sponsored_accounts.saturating_sub(1);
+        self.sponsored_lamports = self.sponsored_lamports.saturating_sub(lamports);
+    }
+}
+
+/// Lamports set aside to refund claimers' transaction fees
+/// PDA: ["gas_rebate", pool]
+///
+/// Funded through FundGasRebates (or plain SOL transfers, which are not
+/// counted in total_funded); everything above the rent-exempt minimum is
+/// available. Rebates only ever come from here, never from the pool.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct GasRebateVault {
+    /// Discriminator for account type identification
+    pub discriminator: [u8; 8],
+
+    /// Back-reference to staking pool
+    pub pool: Pubkey,
+
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Lamports added to each qualifying claim
+    pub rebate_lamports: u64,
+
+    /// Lamports received through FundGasRebates
+    pub total_funded: u64,
+
+    /// Lamports paid out as rebates
+    pub total_rebated: u64,
+
+    /// Number of rebates paid
+    pub rebates_paid: u64,
+}
+
+impl GasRebateVault {
+    /// Size of the account in bytes
+    pub const LEN: usize = 8 +  // discriminator
+        32 + // pool
+        1 +  // bump
+        8 +  // rebate_lamports
+        8 +  // total_funded
+        8 +  // total_rebated
+        8;   // rebates_paid
+
+    /// Derive gas rebate vault PDA
+    pub fn derive_pda(pool: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[GAS_REBATE_SEED, pool.as_ref()], program_id)
+    }
+
+    /// Check if vault is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.discriminator == GAS_REBATE_DISCRIMINATOR
+    }
+
+    /// Rebate for a claim paying `payout` lamports, given what the vault
+    /// holds above rent (`available`). Claims smaller than the rebate get
+    /// none, so dust claims can't drain the vault; a short vault pays
+    /// nothing rather than a partial rebate.
+    pub fn rebate_for(&self, payout: u64, available: u64) -> u64 {
+        if self.rebate_lamports == 0 || payout < self.rebate_lamports || available < self.rebate_lamports {
+            return 0;
+        }
+        self.rebate_lamports
+    }
+
+    /// Record a rebate of `lamports`
+    pub fn record_rebate(&mut self, lamports: u64) {
+        self.total_rebated = self.total_rebated.saturating_add(lamports);
+        self.rebates_paid = self.rebates_paid.saturating_add(1);
+    }
+}
+
+/// Program-wide settings, created once by the deployer
+/// PDA: ["program_config"]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct ProgramConfig {
+    /// Discriminator for account type identification
+    pub discriminator: [u8; 8],
+
+    /// Governance key allowed to change the config
+    pub admin: Pubkey,
+
+    /// Expected upgrade authority of the program; Pubkey::default() means
+    /// the program is expected to be immutable
+    pub upgrade_authority: Pubkey,
+
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Protocol fee (basis points, at most MAX_PROTOCOL_FEE_BPS) taken from
+    /// every pool's reward distributions. 0 = no fee.
+    pub protocol_fee_bps: u16,
+
+    /// Account receiving collected protocol fees
+    pub fee_treasury: Pubkey,
+}
+
+impl ProgramConfig {
+    /// Size of the account in bytes
+    pub const LEN: usize = 8 +  // discriminator
+        32 + // admin
+        32 + // upgrade_authority
+        1 +  // bump
+        2 +  // protocol_fee_bps
+        32;  // fee_treasury
+
+    /// Upper bound on protocol_fee_bps (10%)
+    pub const MAX_PROTOCOL_FEE_BPS: u16 = 1_000;
+
+    /// Derive program config PDA
+    pub fn derive_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[PROGRAM_CONFIG_SEED], program_id)
+    }
+
+    /// Check if config is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.discriminator == PROGRAM_CONFIG_DISCRIMINATOR
+    }
+}
+
+/// Progress of a paged RecountRewardDebt over a pool's stake accounts
+/// PDA: ["debt_recount", pool]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct DebtRecount {
+    /// Discriminator for account type identification
+    pub discriminator: [u8; 8],
+
+    /// Back-reference to staking pool
+    pub pool: Pubkey,
+
+    /// PDA bump seed
+    pub bump: u8,
+
+    /// Stake accounts counted so far; the next page must pass this cursor
+    pub cursor: u64,
+
+    /// Last stake account counted. Pages must list accounts in strictly
+    /// ascending key order, so none can be counted twice.
+    pub last_key: Pubkey,
+
+    /// Sum of amount over the stakes counted so far
+    pub counted_staked: u128,
+
+    /// Sum of reward_debt over the stakes counted so far
+    pub counted_debt: u128,
+
+    /// Pool total_staked when the recount started
+    pub snapshot_staked: u128,
+
+    /// Pool total_reward_debt when the recount started
+    pub snapshot_debt: u128,
+}
+
+impl DebtRecount {
+    /// Size of the account in bytes
+    pub const LEN: usize = 8 +  // discriminator
+        32 + // pool
+        1 +  // bump
+        8 +  // cursor
+        32 + // last_key
+        16 + // counted_staked
+        16 + // counted_debt
+        16 + // snapshot_staked
+        16;  // snapshot_debt
+
+    /// Most stake accounts accepted per page
+    pub const MAX_PAGE: usize = 24;
+
+    /// Derive debt recount PDA
+    pub fn derive_pda(pool: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[DEBT_RECOUNT_SEED, pool.as_ref()], program_id)
+    }
+
+    /// Check if recount is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.discriminator == DEBT_RECOUNT_DISCRIMINATOR
+    }
+
+    /// Start over against the pool's current totals. Compressed positions
+    /// have no stake account to pass, so their totals count from the start.
+    pub fn restart(&mut self, pool: &StakingPool) {
+        self.cursor = 0;
+        self.last_key = Pubkey::default();
+        self.counted_staked = pool.compressed_staked;
+        self.counted_debt = pool.compressed_reward_debt;
+        self.snapshot_staked = pool.total_staked;
+        self.snapshot_debt = pool.total_reward_debt;
+    }
+
+    /// Add one stake account (with key `key`) of the pool to the count.
+    /// Residual positions (amount 0) are skipped: their debt is tracked in
+    /// total_residual_unpaid.
+    pub fn count(&mut self, key: &Pubkey, stake: &UserStake) -> Result<(), StakingError> {
+        if self.cursor > 0 && key <= &self.last_key {
+            return Err(StakingError::RecountOutOfSync);
+        }
+        self.cursor += 1;
+        self.last_key = *key;
+        if stake.amount == 0 {
+            return Ok(());
+        }
+        self.counted_staked = self
+            .counted_staked
+            .checked_add(stake.amount as u128)
+            .ok_or(StakingError::MathOverflow)?;
+        self.counted_debt = self
+            .counted_debt
+            .checked_add(stake.reward_debt)
+            .ok_or(StakingError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Whether every staked token of the pool has been counted. Fails if
+    /// the pool's totals moved since the recount started (a stake or
+    /// unstake happened in between) or more was counted than is staked.
+    pub fn is_complete(&self, pool: &StakingPool) -> Result<bool, StakingError> {
+        if pool.total_staked != self.snapshot_staked
+            || pool.total_reward_debt != self.snapshot_debt
+            || self.counted_staked > pool.total_staked
+        {
+            return Err(StakingError::RecountOutOfSync);
+        }
+        Ok(self.counted_staked == pool.total_staked)
+    }
+}
+
+/// Trace of an owner's closed positions in a pool, kept on request by
+/// CloseStakeAccountArchived. PDA: ["stake_archive", pool, owner]
+///
+/// Closing forgives sub-lamport rewards and erases the position's lifetime
+/// stats; the archive keeps both, summed over every archived close, until
+/// CloseArchived reclaims its rent.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct StakeArchive {
+    /// Discriminator for account type identification
+    pub discriminator: [u8; 8],
+
+    /// Back-reference to staking pool
+    pub pool: Pubkey,
+
+    /// Owner of the archived positions
+    pub owner: Pubkey,
+
+    /// Positions archived
+    pub closures: u32,
+
+    /// Earliest first_stake_time (stake_time for legacy positions) archived
+    pub first_stake_time: i64,
+
+    /// Unix timestamp of the latest archived close
+    pub last_closed_at: i64,
+
+    /// Lifetime tokens staked (UserStake::total_deposited), summed
+    pub total_deposited: u64,
+
+    /// Lifetime tokens unstaked (UserStake::total_withdrawn), summed
+    pub total_withdrawn: u64,
+
+    /// Lamports claimed (UserStake::total_rewards_claimed), summed
+    pub total_rewards_claimed: u64,
+
+    /// Sub-lamport residual and vesting rewards forgiven at close (WAD-scaled)
+    pub forgiven_rewards_wad: u128,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl StakeArchive {
+    /// Size of the account in bytes
+    pub const LEN: usize = 8 +  // discriminator
+        32 + // pool
+        32 + // owner
+        4 +  // closures
+        8 +  // first_stake_time
+        8 +  // last_closed_at
+        8 +  // total_deposited
+        8 +  // total_withdrawn
+        8 +  // total_rewards_claimed
+        16 + // forgiven_rewards_wad
+        1;   // bump
+
+    /// Derive the archive PDA of `owner` in `pool`
+    pub fn derive_pda(pool: &Pubkey, owner: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[STAKE_ARCHIVE_SEED, pool.as_ref(), owner.as_ref()], program_id)
+    }
+
+    /// Empty archive, before its first close is recorded
+    pub fn new(pool: Pubkey, owner: Pubkey, bump: u8) -> Self {
+        Self {
+            discriminator: STAKE_ARCHIVE_DISCRIMINATOR,
+            pool,
+            owner,
+            closures: 0,
+            first_stake_time: 0,
+            last_closed_at: 0,
+            total_deposited: 0,
+            total_withdrawn: 0,
+            total_rewards_claimed: 0,
+            forgiven_rewards_wad: 0,
+            bump,
+        }
+    }
+
+    /// Check if archive is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.discriminator == STAKE_ARCHIVE_DISCRIMINATOR
+    }
+
+    /// Add the position being closed at `closed_at`
+    pub fn record(&mut self, stake: &UserStake, closed_at: i64) {
+        let first_stake_time = if stake.first_stake_time != 0 {
+            stake.first_stake_time
+        } else {
+            stake.stake_time
+        };
+        self.first_stake_time = if self.closures == 0 {
+            first_stake_time
+        } else {
+            self.first_stake_time.min(first_stake_time)
+        };
+        self.closures = self.closures.saturating_add(1);
+        self.last_closed_at = closed_at;
+        self.total_deposited = self.total_deposited.saturating_add(stake.total_deposited);
+        self.total_withdrawn = self.total_withdrawn.saturating_add(stake.total_withdrawn);
+        self.total_rewards_claimed = self
+            .total_rewards_claimed
+            .saturating_add(stake.total_rewards_claimed);
+        self.forgiven_rewards_wad = self
+            .forgiven_rewards_wad
+            .saturating_add(stake.reward_debt)
+            .saturating_add(stake.vesting_rewards_wad);
+    }
+}
+
+/// Cumulative reward contributions of one depositor to a pool, kept while
+/// the pool tracks depositors. PDA: ["depositor_record", pool, depositor]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DepositorRecord {
+    /// Discriminator for account type identification
+    pub discriminator: [u8; 8],
+
+    /// Back-reference to staking pool
+    pub pool: Pubkey,
+
+    /// Signer of the recorded deposits
+    pub depositor: Pubkey,
+
+    /// Lamports deposited (DepositRewards and DepositRewardsSplit), lifetime
+    pub total_deposited: u64,
+
+    /// Deposits recorded
+    pub deposit_count: u64,
+
+    /// Unix timestamp of the first recorded deposit
+    pub first_deposit_time: i64,
+
+    /// Unix timestamp of the latest recorded deposit
+    pub last_deposit_time: i64,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl DepositorRecord {
+    /// Size of the account in bytes
+    pub const LEN: usize = 8 +  // discriminator
+        32 + // pool
+        32 + // depositor
+        8 +  // total_deposited
+        8 +  // deposit_count
+        8 +  // first_deposit_time
+        8 +  // last_deposit_time
+        1;   // bump
+
+    /// Derive the record PDA of `depositor` in `pool`
+    pub fn derive_pda(pool: &Pubkey, depositor: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[DEPOSITOR_RECORD_SEED, pool.as_ref(), depositor.as_ref()], program_id)
+    }
+
+    /// Empty record, before its first deposit
+    pub fn new(pool: Pubkey, depositor: Pubkey, bump: u8) -> Self {
+        Self {
+            discriminator: DEPOSITOR_RECORD_DISCRIMINATOR,
+            pool,
+            depositor,
+            total_deposited: 0,
+            deposit_count: 0,
+            first_deposit_time: 0,
+            last_deposit_time: 0,
+            bump,
+        }
+    }
+
+    /// Check if record is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.discriminator == DEPOSITOR_RECORD_DISCRIMINATOR
+    }
+
+    /// Add a deposit of `amount` lamports at `current_time`
+    pub fn record(&mut self, amount: u64, current_time: i64) {
+        if self.deposit_count == 0 {
+            self.first_deposit_time = current_time;
+        }
+        self.deposit_count = self.deposit_count.saturating_add(1);
+        self.total_deposited = self.total_deposited.saturating_add(amount);
+        self.last_deposit_time = current_time;
+    }
+}
+
+/// Weighted stakes of a pool's stakers for one off-protocol proposal,
+/// written by SnapshotVotingWeights. PDA: ["voting_snapshot", pool,
+/// proposal_id (u64 LE)]
+///
+/// Entries live in VotingSnapshotPage accounts, in strictly increasing
+/// owner order across all pages, so each staker appears at most once.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct VotingSnapshot {
+    /// Discriminator for account type identification
+    pub discriminator: [u8; 8],
+
+    /// Back-reference to staking pool
+    pub pool: Pubkey,
+
+    /// Caller-chosen proposal identifier
+    pub proposal_id: u64,
+
+    /// Unix timestamp of the first snapshot call
+    pub created_at: i64,
+
+    /// Entries written across all pages
+    pub entries: u32,
+
+    /// Sum of the entries' weighted stakes (WAD-scaled)
+    pub total_weighted_stake: u128,
+
+    /// Owner of the latest entry; the next must sort after it
+    pub last_owner: Pubkey,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl VotingSnapshot {
+    /// Size of the account in bytes
+    pub const LEN: usize = 8 +  // discriminator
+        32 + // pool
+        8 +  // proposal_id
+        8 +  // created_at
+        4 +  // entries
+        16 + // total_weighted_stake
+        32 + // last_owner
+        1;   // bump
+
+    /// Derive the snapshot PDA of `proposal_id` in `pool`
+    pub fn derive_pda(pool: &Pubkey, proposal_id: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[VOTING_SNAPSHOT_SEED, pool.as_ref(), &proposal_id.to_le_bytes()],
+            program_id,
+        )
+    }
+
+    /// Empty snapshot, created at `current_time`
+    pub fn new(pool: Pubkey, proposal_id: u64, current_time: i64, bump: u8) -> Self {
+        Self {
+            discriminator: VOTING_SNAPSHOT_DISCRIMINATOR,
+            pool,
+            proposal_id,
+            created_at: current_time,
+            entries: 0,
+            total_weighted_stake: 0,
+            last_owner: Pubkey::default(),
+            bump,
+        }
+    }
+
+    /// Check if snapshot is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.discriminator == VOTING_SNAPSHOT_DISCRIMINATOR
+    }
+
+    /// Page receiving the next entry
+    pub fn current_page(&self) -> u32 {
+        self.entries / VotingSnapshotPage::CAPACITY as u32
+    }
+
+    /// Count `weight` as the next entry. Owners must be strictly increasing
+    /// (which also rejects the default pubkey as a first owner).
+    pub fn record(&mut self, weight: &VotingWeight) -> ProgramResult {
+        if weight.owner <= self.last_owner {
+            return Err(StakingError::SnapshotOutOfOrder.into());
+        }
+        self.entries = self.entries.checked_add(1).ok_or(StakingError::MathOverflow)?;
+        self.total_weighted_stake = self
+            .total_weighted_stake
+            .checked_add(weight.weighted_stake)
+            .ok_or(StakingError::MathOverflow)?;
+        self.last_owner = weight.owner;
+        Ok(())
+    }
+}
+
+/// One page of a VotingSnapshot's entries
+/// PDA: ["voting_snapshot_page", snapshot, page (u32 LE)]
+///
+/// Fixed header followed by CAPACITY entries, filled in order.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct VotingSnapshotPage {
+    /// Discriminator for account type identification
+    pub discriminator: [u8; 8],
+
+    /// Back-reference to the snapshot
+    pub snapshot: Pubkey,
+
+    /// Page index
+    pub page: u32,
+
+    /// Entries written to this page
+    pub count: u16,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+/// A staker's position when it was snapshotted
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VotingWeight {
+    /// Position owner
+    pub owner: Pubkey,
+
+    /// Tokens staked
+    pub amount: u64,
+
+    /// Time-weighted stake (WAD-scaled)
+    pub weighted_stake: u128,
+
+    /// Unix timestamp the weight was computed at
+    pub timestamp: i64,
+}
+
+impl VotingWeight {
+    /// Size of one entry in bytes
+    pub const LEN: usize = 32 + 8 + 16 + 8;
+}
+
+impl VotingSnapshotPage {
+    /// Size of the header in bytes
+    pub const HEADER_LEN: usize = 8 +  // discriminator
+        32 + // snapshot
+        4 +  // page
+        2 +  // count
+        1;   // bump
+
+    /// Entries per page
+    pub const CAPACITY: u16 = 64;
+
+    /// Account size of a page
+    pub const LEN: usize = Self::HEADER_LEN + Self::CAPACITY as usize * VotingWeight::LEN;
+
+    /// Derive the PDA of page `page` of `snapshot`
+    pub fn derive_pda(snapshot: &Pubkey, page: u32, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[VOTING_SNAPSHOT_PAGE_SEED, snapshot.as_ref(), &page.to_le_bytes()],
+            program_id,
+        )
+    }
+
+    /// Check if page is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.discriminator == VOTING_SNAPSHOT_PAGE_DISCRIMINATOR
+    }
+
+    /// Read the `n`-th entry of this page
+    pub fn get(&self, data: &[u8], n: u16) -> Option<VotingWeight> {
+        if n >= self.count {
+            return None;
+        }
+        let offset = Self::HEADER_LEN + n as usize * VotingWeight::LEN;
+        VotingWeight::try_from_slice(data.get(offset..offset + VotingWeight::LEN)?).ok()
+    }
+
+    /// Append an entry and persist the header
+    pub fn push(&mut self, data: &mut [u8], weight: &VotingWeight) -> ProgramResult {
+        if self.count >= Self::CAPACITY {
+            return Err(StakingError::AccountDataTooSmall.into());
+        }
+        let offset = Self::HEADER_LEN + self.count as usize * VotingWeight::LEN;
+        let mut entry = data
+            .get_mut(offset..offset + VotingWeight::LEN)
+            .ok_or(StakingError::AccountDataTooSmall)?;
+        weight.serialize(&mut entry)?;
+        self.count += 1;
+        self.serialize(&mut &mut data[..Self::HEADER_LEN])?;
+        Ok(())
+    }
+}
+
+/// Age-bucket state of a pool with the AgeBuckets weight engine
+/// PDA: ["age_buckets", pool]
+///
+/// Mirrors chiefstaker_core::buckets::BucketedPool, minus the epoch-start
+/// history, which lives in AgeBucketHistory pages.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AgeBuckets {
+    /// Discriminator for account type identification
+    pub discriminator: [u8; 8],
+
+    /// Back-reference to staking pool
+    pub pool: Pubkey,
+
+    /// Length of one age bucket (epoch) in seconds
+    pub bucket_seconds: u64,
+
+    /// Number of buckets in use (K)
+    pub bucket_count: u8,
+
+    /// Weight of each bucket in bps, youngest first; the last one in use
+    /// applies to all older stake. Entries past bucket_count are 0.
+    pub weights_bps: [u16; MAX_AGE_BUCKETS],
+
+    /// Start of epoch 0
+    pub start_time: i64,
+
+    /// Current epoch (bucket periods rolled since start_time)
+    pub epoch: u64,
+
+    /// Staked amount per age bucket, youngest first
+    pub amounts: [u64; MAX_AGE_BUCKETS],
+
+    /// Accumulated WAD lamports per unit of weight (amount * weight_bps)
+    pub acc_per_weight: u128,
+
+    /// acc_per_weight at the start of the current epoch (also the newest
+    /// history entry), so positions of this epoch need no history page
+    pub epoch_acc: u128,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl AgeBuckets {
+    /// Size of the account in bytes
+    pub const LEN: usize = 8 +  // discriminator
+        32 + // pool
+        8 +  // bucket_seconds
+        1 +  // bucket_count
+        2 * MAX_AGE_BUCKETS + // weights_bps
+        8 +  // start_time
+        8 +  // epoch
+        8 * MAX_AGE_BUCKETS + // amounts
+        16 + // acc_per_weight
+        16 + // epoch_acc
+        1;   // bump
+
+    /// State of a new pool following `schedule` (already validated), with
+    /// epoch 0 starting at `start_time`
+    pub fn new(pool: Pubkey, schedule: &AgeBucketSchedule, start_time: i64, bump: u8) -> Self {
+        let mut weights_bps = [0u16; MAX_AGE_BUCKETS];
+        weights_bps[..schedule.len()].copy_from_slice(&schedule.weights_bps);
+        Self {
+            discriminator: AGE_BUCKETS_DISCRIMINATOR,
+            pool,
+            bucket_seconds: schedule.bucket_seconds,
+            bucket_count: schedule.len() as u8,
+            weights_bps,
+            start_time,
+            epoch: 0,
+            amounts: [0; MAX_AGE_BUCKETS],
+            acc_per_weight: 0,
+            epoch_acc: 0,
+            bump,
+        }
+    }
+
+    /// Derive the PDA of `pool`'s age buckets
+    pub fn derive_pda(pool: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[AGE_BUCKETS_SEED, pool.as_ref()], program_id)
+    }
+
+    /// Check if account is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.discriminator == AGE_BUCKETS_DISCRIMINATOR
+    }
+
+    /// Weights of the buckets in use
+    pub fn weights(&self) -> &[u16] {
+        &self.weights_bps[..self.bucket_count as usize]
+    }
+
+    /// Epoch containing `time`
+    pub fn epoch_at(&self, time: i64) -> u64 {
+        let elapsed = time.saturating_sub(self.start_time).max(0) as u64;
+        elapsed / self.bucket_seconds
+    }
+
+    /// Whether the buckets are rolled up to the epoch containing `time`
+    pub fn is_rolled(&self, time: i64) -> bool {
+        self.epoch >= self.epoch_at(time)
+    }
+
+    /// Total weight: sum of bucket amounts times their weight (bps)
+    pub fn total_weight(&self) -> u128 {
+        buckets::total_weight(&self.amounts, self.weights())
+    }
+
+    /// Bucket holding the stake of a position that entered at `entry_epoch`
+    pub fn bucket_of(&self, entry_epoch: u64) -> usize {
+        let age = self.epoch.saturating_sub(entry_epoch);
+        (age as usize).min(self.bucket_count as usize - 1)
+    }
+
+    /// Move every cohort one bucket older and start the next epoch
+    pub fn roll_one(&mut self) {
+        let count = self.bucket_count as usize;
+        buckets::roll_amounts(&mut self.amounts[..count], 1);
+        self.epoch += 1;
+        self.epoch_acc = self.acc_per_weight;
+    }
+
+    /// Distribute `lamports` over the current weight. Returns false, leaving
+    /// the rewards undistributed, when nothing is staked.
+    pub fn distribute(&mut self, lamports: u64) -> Result<bool, StakingError> {
+        let total_weight = self.total_weight();
+        if total_weight == 0 {
+            return Ok(false);
+        }
+        self.acc_per_weight = self
+            .acc_per_weight
+            .checked_add(buckets::acc_increment(lamports, total_weight)?)
+            .ok_or(StakingError::MathOverflow)?;
+        Ok(true)
+    }
+
+    /// Serialize into `account`
+    pub fn save(&self, account: &AccountInfo) -> ProgramResult {
+        self.serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+        Ok(())
+    }
+}
+
+/// acc_per_weight of a bucketed pool at the start of each epoch
+/// PDA: ["age_bucket_history", pool, page (u32 LE)]
+///
+/// Fixed header followed by CAPACITY u128 entries, entry `n` of page `p`
+/// holding epoch `p * CAPACITY + n`. RollAgeBuckets appends one per epoch
+/// and pages are never rewritten.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct AgeBucketHistory {
+    /// Discriminator for account type identification
+    pub discriminator: [u8; 8],
+
+    /// Back-reference to staking pool
+    pub pool: Pubkey,
+
+    /// Page index
+    pub page: u32,
+
+    /// Entries written to this page
+    pub count: u16,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl AgeBucketHistory {
+    /// Size of the header in bytes
+    pub const HEADER_LEN: usize = 8 +  // discriminator
+        32 + // pool
+        4 +  // page
+        2 +  // count
+        1;   // bump
+
+    /// Epochs per page
+    pub const CAPACITY: u16 = 64;
+
+    /// Account size of a page
+    pub const LEN: usize = Self::HEADER_LEN + Self::CAPACITY as usize * 16;
+
+    /// Derive the PDA of page `page`
+    pub fn derive_pda(pool: &Pubkey, page: u32, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[AGE_BUCKET_HISTORY_SEED, pool.as_ref(), &page.to_le_bytes()],
+            program_id,
+        )
+    }
+
+    /// Page holding the start of `epoch`
+    pub fn page_of(epoch: u64) -> u32 {
+        (epoch / Self::CAPACITY as u64).min(u32::MAX as u64) as u32
+    }
+
+    /// Check if page is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.discriminator == AGE_BUCKET_HISTORY_DISCRIMINATOR
+    }
+
+    /// acc_per_weight at the start of `epoch`, if this page has it
+    pub fn get(&self, data: &[u8], epoch: u64) -> Option<u128> {
+        let n = epoch.checked_sub(self.page as u64 * Self::CAPACITY as u64)?;
+        if n >= self.count as u64 {
+            return None;
+        }
+        let offset = Self::HEADER_LEN + n as usize * 16;
+        Some(u128::from_le_bytes(data.get(offset..offset + 16)?.try_into().ok()?))
+    }
+
+    /// Append the next epoch's entry and persist the header
+    pub fn push(&mut self, data: &mut [u8], acc_per_weight: u128) -> ProgramResult {
+        if self.count >= Self::CAPACITY {
+            return Err(StakingError::AccountDataTooSmall.into());
+        }
+        let offset = Self::HEADER_LEN + self.count as usize * 16;
+        data.get_mut(offset..offset + 16)
+            .ok_or(StakingError::AccountDataTooSmall)?
+            .copy_from_slice(&acc_per_weight.to_le_bytes());
+        self.count += 1;
+        self.serialize(&mut &mut data[..Self::HEADER_LEN])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::RAY;
+
+    #[test]
+    fn test_pool_size() {
+        // Verify the calculated size matches actual serialized size
+        let pool = StakingPool::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            2592000,
+            0,
+            255,
+        );
+        let serialized = borsh::to_vec(&pool).unwrap();
+        assert_eq!(serialized.len(), StakingPool::LEN);
+    }
+
+    #[test]
+    fn test_pool_pda_with_id() {
+        let program_id = crate::id();
+        let mint = Pubkey::new_unique();
+
+        // Pool 0 keeps the original address
+        assert_eq!(
+            StakingPool::derive_pda_with_id(&mint, 0, &program_id),
+            StakingPool::derive_pda(&mint, &program_id)
+        );
+        let (pool_1, bump_1) = StakingPool::derive_pda_with_id(&mint, 1, &program_id);
+        let (pool_2, _) = StakingPool::derive_pda_with_id(&mint, 2, &program_id);
+        assert_ne!(pool_1, StakingPool::derive_pda(&mint, &program_id).0);
+        assert_ne!(pool_1, pool_2);
+
+        // Signer seeds reproduce the address
+        let mut pool = StakingPool::new(mint, mint, mint, mint, 2592000, 0, bump_1);
+        pool.pool_id = 1;
+        let id_bytes = pool.pool_id.to_le_bytes();
+        let bump = [pool.bump];
+        let seeds = pool.signer_seeds(&id_bytes, &bump);
+        assert_eq!(Pubkey::create_program_address(&seeds, &program_id).unwrap(), pool_1);
+
+        // The interface crate derives the same addresses
+        use chiefstaker_interface::pda;
+        assert_eq!(pda::find_pool_address(&mint, 1), (pool_1, bump_1));
+        assert_eq!(
+            pda::find_pool_address(&mint, 0),
+            StakingPool::derive_pda(&mint, &program_id)
+        );
+        let owner = Pubkey::new_unique();
+        assert_eq!(
+            pda::find_stake_address(&pool_1, &owner),
+            UserStake::derive_pda(&pool_1, &owner, &program_id)
+        );
+        assert_eq!(
+            pda::find_weight_history_address(&pool_1, &owner),
+            WeightHistory::derive_pda(&pool_1, &owner, &program_id)
+        );
+        assert_eq!(
+            pda::find_lock_schedule_address(&pool_1, &owner),
+            UserLockSchedule::derive_pda(&pool_1, &owner, &program_id)
+        );
+        assert_eq!(
+            StakingPool::derive_token_vault_pda_for_generation(&pool_1, 0, &program_id),
+            StakingPool::derive_token_vault_pda(&pool_1, &program_id)
+        );
+        let (vault_1, _) = StakingPool::derive_token_vault_pda_for_generation(&pool_1, 1, &program_id);
+        assert_ne!(vault_1, StakingPool::derive_token_vault_pda(&pool_1, &program_id).0);
+        assert_eq!(
+            pda::find_migrated_token_vault_address(&pool_1, 1),
+            StakingPool::derive_token_vault_pda_for_generation(&pool_1, 1, &program_id)
+        );
+        assert_eq!(
+            pda::find_authority_log_address(&pool_1),
+            AuthorityLog::derive_pda(&pool_1, &program_id)
+        );
+    }
+
+    #[test]
+    fn test_pool_legacy_deserialize() {
+        let mut pool = StakingPool::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::default(),
+            Pubkey::new_unique(),
+            2592000,
+            1_700_000_000,
+            254,
+        );
+        pool.total_residual_unpaid = 77;
+        let full = borsh::to_vec(&pool).unwrap();
+
+        let legacy = StakingPool::try_from_slice(&full[..StakingPool::LEGACY_LEN]).unwrap();
+        assert_eq!(legacy.total_residual_unpaid, 77);
+        assert!(!legacy.rewards_paused);
+
+        pool.rewards_paused = true;
+        let full = borsh::to_vec(&pool).unwrap();
+        assert!(StakingPool::try_from_slice(&full).unwrap().rewards_paused);
+        assert!(!legacy.has_refund_policy());
+    }
+
+    #[test]
+    fn test_residuals_first() {
+        let mut pool = StakingPool::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::default(),
+            Pubkey::new_unique(),
+            1_000,
+            0,
+            255,
+        );
+        pool.total_staked = 1_000;
+        pool.total_residual_unpaid = 300;
+
+        // Off: residuals compete with new accrual
+        assert_eq!(pool.distribute_rewards(1_000).unwrap(), WAD);
+        assert_eq!(pool.residual_reserved, 0);
+
+        // On: the uncovered residuals come off the top
+        pool.residuals_first = true;
+        assert_eq!(pool.distribute_rewards(1_000).unwrap(), WAD * 7 / 10);
+        assert_eq!(pool.residual_reserved, 300);
+        // Already covered residuals aren't earmarked twice
+        assert_eq!(pool.distribute_rewards(1_000).unwrap(), WAD);
+        // A smaller distribution is fully earmarked
+        pool.total_residual_unpaid = 500;
+        assert_eq!(pool.distribute_rewards(100).unwrap(), 0);
+        assert_eq!(pool.residual_reserved, 400);
+
+        // Only residual claims can spend the reserve
+        assert_eq!(pool.claimable_lamports(10_500, 100, false), 10_000);
+        assert_eq!(pool.claimable_lamports(10_500, 100, true), 10_400);
+
+        // Paying residuals releases the reserve
+        pool.pay_residual(150);
+        assert_eq!((pool.total_residual_unpaid, pool.residual_reserved), (350, 250));
+        pool.pay_residual(1_000);
+        assert_eq!((pool.total_residual_unpaid, pool.residual_reserved), (0, 0));
+    }
+
+    #[test]
+    fn test_stranded_lamports() {
+        let mut pool = StakingPool::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::default(),
+            Pubkey::new_unique(),
+            1_000,
+            0,
+            255,
+        );
+        // 1000 tokens staked at snapshot 0, then 1 lamport per token distributed
+        pool.total_staked = 1_000;
+        pool.acc_reward_per_weighted_share = WAD;
+        pool.last_synced_lamports = 1_000;
+        assert_eq!(pool.max_active_obligation().unwrap(), 1_000);
+        assert_eq!(pool.stranded_lamports().unwrap(), 0);
+
+        // Half the stake left early, forfeiting 300 of its 500 lamports:
+        // 200 paid out, 300 remain synced but owed to nobody
+        pool.total_staked = 500;
+        pool.last_synced_lamports = 800;
+        assert_eq!(pool.max_active_obligation().unwrap(), 500);
+        assert_eq!(pool.stranded_lamports().unwrap(), 300);
+
+        // Residuals are still owed
+        pool.total_residual_unpaid = 100;
+        assert_eq!(pool.stranded_lamports().unwrap(), 200);
+
+        // So are the treasury's fees, and the reserves are earmarked
+        pool.protocol_fees_owed = 50;
+        assert_eq!(pool.stranded_lamports().unwrap(), 150);
+        pool.residual_reserved = 40;
+        pool.rounding_reserve_wad = 10 * WAD + WAD / 2;
+        assert_eq!(pool.stranded_lamports().unwrap(), 100);
+        pool.protocol_fees_owed = 1_000;
+        assert_eq!(pool.stranded_lamports().unwrap(), 0);
+
+        // Obligations round up
+        pool.total_reward_debt = WAD / 2;
+        assert_eq!(pool.max_active_obligation().unwrap(), 500);
+    }
+
+    #[test]
+    fn test_reconcile_synced_lamports() {
+        let mut pool = StakingPool::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::new_unique(),
+            1_000,
+            0,
+            255,
+        );
+        pool.last_synced_lamports = 1_000;
+
+        // In step with the balance, or behind it (pending rewards): untouched
+        assert_eq!(pool.reconcile_synced_lamports(2_000 + 1_000, 2_000), 0);
+        assert_eq!(pool.reconcile_synced_lamports(2_000 + 1_500, 2_000), 0);
+        assert_eq!(pool.last_synced_lamports, 1_000);
+
+        // Rent grew by 300 without bookkeeping: clamped, nothing to distribute
+        assert_eq!(pool.reconcile_synced_lamports(2_000 + 1_000, 2_300), 300);
+        assert_eq!(pool.last_synced_lamports, 700);
+        assert_eq!(pool.undistributed_lamports(2_000 + 1_000, 2_300), 0);
+
+        // Below rent: nothing is synced
+        assert_eq!(pool.reconcile_synced_lamports(1_000, 2_000), 700);
+        assert_eq!(pool.last_synced_lamports, 0);
+    }
+
+    #[test]
+    fn test_unstake_fee() {
+        let mut pool = StakingPool::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::default(),
+            Pubkey::new_unique(),
+            2592000,
+            1_700_000_000,
+            254,
+        );
+        pool.unstake_fee_bps = 250;
+        assert_eq!(pool.unstake_fee(1_000_000), 0); // no destination
+
+        pool.unstake_fee_destination = Pubkey::new_unique();
+        assert_eq!(pool.unstake_fee(1_000_000), 25_000);
+        assert_eq!(pool.unstake_fee(39), 0); // rounds down
+        assert_eq!(pool.unstake_fee(u64::MAX), (u64::MAX as u128 * 250 / 10_000) as u64);
+
+        // Stored values above the cap are clamped
+        pool.unstake_fee_bps = 10_000;
+        assert_eq!(pool.unstake_fee(1_000_000), 50_000);
+    }
+
+    #[test]
+    fn test_claim_burn() {
+        let mut pool = StakingPool::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::default(),
+            Pubkey::new_unique(),
+            2592000,
+            1_700_000_000,
+            254,
+        );
+        pool.claim_burn_bps = 1_000;
+        assert!(!pool.has_claim_burn());
+        assert_eq!(pool.claim_burn(1_000_000), 0); // no destination
+
+        pool.claim_burn_destination = solana_program::incinerator::id();
+        assert_eq!(pool.claim_burn(1_000_000), 100_000);
+        assert_eq!(pool.claim_burn(9), 0); // rounds down, in the claimer's favor
+
+        // Stored values above the cap are clamped
+        pool.claim_burn_bps = 10_000;
+        assert_eq!(pool.claim_burn(1_000_000), 100_000);
+
+        // A destination that isn't the incinerator never receives a share
+        pool.claim_burn_destination = Pubkey::new_unique();
+        assert!(!pool.has_claim_burn());
+        assert_eq!(pool.claim_burn(1_000_000), 0);
+    }
+
+    #[test]
+    fn test_defers_claim() {
+        let mut pool = StakingPool::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::default(),
+            Pubkey::new_unique(),
+            2592000,
+            1_700_000_000,
+            254,
+        );
+        assert!(!pool.defers_claim(1, false));
+
+        pool.min_claim_lamports = 10_000;
+        assert!(pool.defers_claim(9_999, false));
+        assert!(!pool.defers_claim(10_000, false));
+        // Residual claims are paid whatever their size
+        assert!(!pool.defers_claim(1, true));
+    }
+
+    #[test]
+    fn test_trailing_reward_rate() {
+        let mut pool = StakingPool::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::default(),
+            Pubkey::new_unique(),
+            2592000,
+            0,
+            254,
+        );
+        assert_eq!(pool.trailing_reward_rate(0).unwrap(), 0);
+
+        // WAD of accumulator growth every hour: 24 WAD a day
+        let close = |rate: u128, expected: u128| rate.abs_diff(expected) < expected / 100;
+        let mut time = 0;
+        for _ in 0..24 {
+            time += 3600;
+            pool.record_reward_rate(time, WAD).unwrap();
+        }
+        // A young pool averages over its lifetime
+        assert!(close(pool.trailing_reward_rate(time).unwrap(), 24 * WAD));
+        for _ in 0..24 * 56 {
+            time += 3600;
+            pool.record_reward_rate(time, WAD).unwrap();
+        }
+        assert!(close(pool.trailing_reward_rate(time).unwrap(), 24 * WAD));
+
+        // A week without distributions decays the rate by e
+        let week = StakingPool::REWARD_RATE_WINDOW_SECONDS as i64;
+        assert!(close(pool.trailing_reward_rate(time + week).unwrap(), 24 * WAD * 1000 / 2718));
+    }
+
+    #[test]
+    fn test_renounce_safe() {
+        let mut pool = StakingPool::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::default(),
+            Pubkey::new_unique(),
+            2592000,
+            1_700_000_000,
+            254,
+        );
+        assert!(pool.renounce_safe());
+
+        pool.lock_duration_seconds = StakingPool::MAX_RENOUNCE_SAFE_SECONDS;
+        pool.unstake_cooldown_seconds = StakingPool::MAX_RENOUNCE_SAFE_SECONDS;
+        assert!(pool.renounce_safe());
+        pool.unstake_cooldown_seconds += 1;
+        assert!(!pool.renounce_safe());
+        pool.unstake_cooldown_seconds = 0;
+        pool.lock_duration_seconds += 1;
+        assert!(!pool.renounce_safe());
+    }
+
+    #[test]
+    fn test_distribution_threshold() {
+        let mut pool = StakingPool::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::default(),
+            Pubkey::new_unique(),
+            2592000,
+            1_700_000_000,
+            254,
+        );
+        assert!(pool.below_distribution_threshold());
+        pool.total_staked = 1;
+        assert!(!pool.below_distribution_threshold());
+
+        pool.min_distribution_stake = 1_000_000_000;
+        assert!(pool.below_distribution_threshold());
+        pool.total_staked = 1_000_000_000;
+        assert!(!pool.below_distribution_threshold());
+    }
+
+    #[test]
+    fn test_min_sync_interval() {
+        let mut pool = StakingPool::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::default(),
+            Pubkey::new_unique(),
+            2592000,
+            1_700_000_000,
+            254,
+        );
+        // Disabled by default, and never before a first distribution
+        pool.last_distribution_time = 1_700_000_000;
+        assert!(!pool.distribution_too_soon(1_700_000_000));
+        pool.min_sync_interval_seconds = 60;
+        assert!(pool.distribution_too_soon(1_700_000_000));
+        assert!(pool.distribution_too_soon(1_700_000_059));
+        assert!(!pool.distribution_too_soon(1_700_000_060));
+        pool.last_distribution_time = 0;
+        assert!(!pool.distribution_too_soon(1_700_000_000));
+    }
+
+    #[test]
+    fn test_refund_timer() {
+        let mut pool = StakingPool::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::default(),
+            Pubkey::new_unique(),
+            2592000,
+            1_700_000_000,
+            254,
+        );
+
+        // No policy: the timer never starts
+        assert!(!pool.mark_undistributed(100));
+        assert_eq!(pool.undistributed_since, 0);
+
+        pool.refund_address = Pubkey::new_unique();
+        assert!(!pool.has_refund_policy());
+        pool.refund_after_seconds = 86_400;
+        assert!(pool.mark_undistributed(100));
+        assert_eq!(pool.undistributed_since, 100);
+
+        // Already running: keeps the original start
+        assert!(!pool.mark_undistributed(200));
+        assert_eq!(pool.undistributed_since, 100);
+
+        let full = borsh::to_vec(&pool).unwrap();
+        assert_eq!(StakingPool::try_from_slice(&full).unwrap().undistributed_since, 100);
+    }
+
+    #[test]
+    fn test_lazy_rebase_accuracy() {
+        let tau = 1_000u64;
+        let base = 1_000i64;
+        let amount = 1_000_000_000u64;
+        let mut pool = StakingPool::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            tau,
+            base,
+            255,
+        );
+        pool.total_staked = amount as u128;
+        pool.set_sum_stake_exp(U256::from_u128(amount as u128 * WAD));
+        let user = UserStake::new(Pubkey::default(), Pubkey::default(), amount, base, WAD, 255, base);
+
+        // Crank every 0.01 tau for 30 tau: eagerly, and gated by rebase_due
+        let mut eager = pool.clone();
+        let mut lazy = pool.clone();
+        let mut lazy_rebases = 0;
+        let mut now = base;
+        for _ in 0..3_000 {
+            now += tau as i64 / 100;
+            eager.rebase(now).unwrap();
+            if lazy.rebase_due(now).unwrap() {
+                lazy.rebase(now).unwrap();
+                lazy_rebases += 1;
+            }
+        }
+        assert_eq!(lazy_rebases, 0);
+        assert!(lazy.rebase_due(base + 38 * tau as i64).unwrap());
+        lazy.rebase(now).unwrap();
+
+        // A position syncs in one step; the pool sum should match it
+        let drift = |pool: &StakingPool| {
+            let mut user = user.clone();
+            user.sync_to_pool(pool).unwrap();
+            let exact = U256::from_u128(amount as u128 * user.exp_start_factor);
+            let sum = pool.get_sum_stake_exp();
+            if sum > exact { sum - exact } else { exact - sum }
+        };
+        assert!(drift(&eager) > U256::from_u128(0));
+        assert_eq!(drift(&lazy), U256::from_u128(0));
+
+        // A configured threshold rebases sooner
+        pool.rebase_threshold_bps = 10_000;
+        assert!(!pool.rebase_due(base + tau as i64).unwrap());
+        assert!(pool.rebase_due(base + tau as i64 + 1).unwrap());
+    }
+
+    #[test]
+    fn test_inline_rebase() {
+        use crate::math::{calculate_total_weighted_stake, calculate_user_weighted_stake};
+
+        let tau = 1_000u64;
+        let base = 1_000i64;
+        let mut pool = StakingPool::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            tau,
+            base,
+            255,
+        );
+        pool.total_staked = 1_000;
+        pool.set_sum_stake_exp(U256::from_u128(1_000 * WAD));
+        let mut user = UserStake::new(Pubkey::default(), Pubkey::default(), 1_000, base, WAD, 255, base);
+
+        // Within MAX_EXP_INPUT and below the rebase threshold: untouched
+        assert!(!pool.rebase_if_needed(base + 10 * tau as i64).unwrap());
+        assert_eq!(pool.base_time, base);
+
+        // Past MAX_EXP_INPUT: rebased in place
+        let now = base + 43 * tau as i64;
+        assert!(pool.rebase_if_needed(now).unwrap());
+        assert_eq!(pool.base_time, now);
+        assert_eq!(pool.initial_base_time, base);
+        assert_eq!(pool.exp_input_wad(now).unwrap(), 0);
+
+        // Weights are unchanged by the rebase once the user syncs
+        assert!(user.sync_to_pool(&pool).unwrap());
+        let user_weight = calculate_user_weighted_stake(
+            user.amount, user.exp_start_factor, now, pool.base_time, tau,
+        ).unwrap();
+        let total_weight = calculate_total_weighted_stake(
+            pool.total_staked, &pool.get_sum_stake_exp(), now, pool.base_time, tau,
+        ).unwrap();
+        assert_eq!(user_weight, total_weight);
+        assert!(user_weight > 999 * WAD);
+
+        // No time passed: nothing to do
+        assert_eq!(pool.rebase(now).unwrap(), None);
+
+        // A rebase that can't be computed leaves the pool as it was for claims
+        let later = now + tau as i64;
+        let mut overflowing = pool.clone();
+        overflowing.set_sum_stake_exp(U256::MAX);
+        assert!(overflowing.clone().rebase_if_needed(later).is_err());
+        assert!(!overflowing.rebase_if_possible(later));
+        assert_eq!(overflowing.base_time, now);
+        assert_eq!(overflowing.get_sum_stake_exp(), U256::MAX);
+
+        assert!(!pool.rebase_if_possible(later));
+        let later = now + 43 * tau as i64;
+        assert!(pool.rebase_if_possible(later));
+        assert_eq!(pool.base_time, later);
+    }
+
+    #[test]
+    fn test_distribution_dust_sweep() {
+        let mut pool = StakingPool::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            2592000,
+            0,
+            255,
+        );
+        // 7,000 tokens of 9 decimals staked: each lamport credits
+        // 142_857_142_857_142 RAY units per token and leaves 6e12 RAY units
+        // (6,000 WAD units) to floor division
+        pool.total_staked = 7_000_000_000_000;
+        let rps = pool.distribute_rewards(1).unwrap();
+        assert_eq!(rps, 142_857);
+        assert_eq!(pool.acc_rps_ray_remainder, 142_857_142);
+        let dust_after_one = pool.accumulated_dust_wad;
+        assert_eq!(dust_after_one, 6_000);
+
+        for _ in 0..5 {
+            pool.distribute_rewards(1).unwrap();
+        }
+        assert_eq!(pool.accumulated_dust_wad, 6 * dust_after_one);
+
+        // Total value = credited (acc_rps_ray * total_staked) + dust
+        let total_value = |pool: &StakingPool| {
+            pool.acc_rps_ray() * U256::from_u128(pool.total_staked)
+                + U256::from_u128(pool.accumulated_dust_wad * RAY_PER_WAD)
+        };
+        assert_eq!(total_value(&pool), U256::from_u128(6 * RAY));
+
+        // The dust only moves the carried digits here
+        let acc_before = pool.acc_reward_per_weighted_share;
+        let swept = pool.sweep_dust().unwrap();
+        assert_eq!(swept, 0);
+        assert_eq!(pool.acc_reward_per_weighted_share, acc_before);
+        assert_eq!(pool.accumulated_dust_wad, 1_000);
+        assert_eq!(total_value(&pool), U256::from_u128(6 * RAY));
+    }
+
+    #[test]
+    fn test_pool_save_into_legacy_account() {
+        let key = Pubkey::new_unique();
+        let owner = crate::id();
+        let mut lamports = 0u64;
+        let mut data = vec![0u8; StakingPool::LEGACY_LEN];
+        let info = AccountInfo::new(
+            &key, false, true, &mut lamports, &mut data, &owner, false, 0,
+        );
+
+        let mut pool = StakingPool::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            key,
+            Pubkey::new_unique(),
+            2592000,
+            0,
+            255,
+        );
+        // Trailing fields at default: truncated write succeeds, and the
+        // state nonce isn't bumped since it doesn't fit
+        pool.save(&info).unwrap();
+        let reloaded = StakingPool::try_from_slice(&info.try_borrow_data().unwrap()).unwrap();
+        assert_eq!(reloaded.mint, pool.mint);
+        assert_eq!(pool.state_nonce, 0);
+
+        // Non-default trailing field needs a realloc first
+        pool.rewards_paused = true;
+        assert_eq!(
+            pool.save(&info),
+            Err(StakingError::AccountDataTooSmall.into())
+        );
+    }
+
+    #[test]
+    fn test_record_undistributed() {
+        let rent = Rent::default();
+        let key = Pubkey::new_unique();
+        let owner = crate::id();
+        let mut pool = StakingPool::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            key,
+            Pubkey::new_unique(),
+            2592000,
+            0,
+            255,
+        );
+        pool.last_synced_lamports = 1_000;
+
+        // Measured against the rent of the account's actual size
+        let mut lamports = rent.minimum_balance(StakingPool::LEN) + 1_500;
+        let mut data = vec![0u8; StakingPool::LEN];
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+        assert!(pool.record_undistributed(&info, &rent));
+        assert_eq!(pool.undistributed_lamports, 500);
+        assert!(!pool.record_undistributed(&info, &rent));
+
+        // Legacy pools have no room for it
+        let mut legacy_lamports = rent.minimum_balance(StakingPool::LEGACY_LEN) + 1_700;
+        let mut legacy_data = vec![0u8; StakingPool::LEGACY_LEN];
+        let legacy = AccountInfo::new(
+            &key, false, true, &mut legacy_lamports, &mut legacy_data, &owner, false, 0,
+        );
+        pool.undistributed_lamports = 0;
+        assert!(!pool.record_undistributed(&legacy, &rent));
+        assert_eq!(pool.undistributed_lamports, 0);
+    }
+
+    #[test]
+    fn test_state_nonce_bumped_on_save() {
+        let key = Pubkey::new_unique();
+        let owner = crate::id();
+        let mut lamports = 0u64;
+        let mut data = vec![0u8; StakingPool::LEN];
+        let info = AccountInfo::new(
+            &key, false, true, &mut lamports, &mut data, &owner, false, 0,
+        );
+
+        let mut pool = StakingPool::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            key,
+            Pubkey::new_unique(),
+            2592000,
+            0,
+            255,
+        );
+        pool.save(&info).unwrap();
+        pool.save(&info).unwrap();
+        let reloaded = StakingPool::try_from_slice(&info.try_borrow_data().unwrap()).unwrap();
+        assert_eq!(reloaded.state_nonce, 2);
+
+        let mut stake_lamports = 0u64;
+        let mut stake_data = vec![0u8; UserStake::LEN];
+        let stake_info = AccountInfo::new(
+            &key, false, true, &mut stake_lamports, &mut stake_data, &owner, false, 0,
+        );
+        let mut user_stake = UserStake::new(key, key, 100, 0, WAD, 255, 0);
+        user_stake.save(&stake_info).unwrap();
+        let reloaded = UserStake::try_from_slice(&stake_info.try_borrow_data().unwrap()).unwrap();
+        assert_eq!(reloaded.state_nonce, 1);
+    }
+
+    #[test]
+    fn test_pool_metadata_size() {
+        let metadata = PoolMetadata {
+            discriminator: METADATA_DISCRIMINATOR,
+            pool: Pubkey::default(),
+            name_len: 0,
+            name: [0u8; 64],
+            num_tags: 0,
+            tag_lengths: [0u8; 8],
+            tags: [[0u8; 32]; 8],
+            url_len: 0,
+            url: [0u8; 128],
+            member_count: 0,
+            bump: 255,
+        };
+        let serialized = borsh::to_vec(&metadata).unwrap();
+        assert_eq!(serialized.len(), PoolMetadata::LEN);
+        assert_eq!(PoolMetadata::LEN, 508);
+    }
+
+    #[test]
+    fn test_user_stake_size() {
+        let stake = UserStake::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            1000,
+            12345,
+            1_000_000_000_000_000_000,
+            255,
+            12345,
+        );
+        let serialized = borsh::to_vec(&stake).unwrap();
+        assert_eq!(serialized.len(), UserStake::LEN);
+        assert_eq!(UserStake::LEN, UserStake::ACCOUNT_SIZE);
+        assert_eq!(UserStake::LEGACY_LEN, 161);
+    }
+
+    /// Pool with every field set to bytes of its position, so the golden
+    /// dump shows where each field lives
+    fn layout_sample_pool() -> StakingPool {
+        StakingPool {
+            discriminator: POOL_DISCRIMINATOR,
+            mint: Pubkey::new_from_array([1; 32]),
+            token_vault: Pubkey::new_from_array([2; 32]),
+            reward_vault: Pubkey::new_from_array([3; 32]),
+            authority: Pubkey::new_from_array([4; 32]),
+            total_staked: u128::from_le_bytes([5; 16]),
+            sum_stake_exp: [6; 32],
+            tau_seconds: u64::from_le_bytes([7; 8]),
+            base_time: i64::from_le_bytes([8; 8]),
+            acc_reward_per_weighted_share: u128::from_le_bytes([9; 16]),
+            last_update_time: i64::from_le_bytes([10; 8]),
+            bump: 11,
+            last_synced_lamports: u64::from_le_bytes([12; 8]),
+            min_stake_amount: u64::from_le_bytes([13; 8]),
+            lock_duration_seconds: u64::from_le_bytes([14; 8]),
+            unstake_cooldown_seconds: u64::from_le_bytes([15; 8]),
+            initial_base_time: i64::from_le_bytes([16; 8]),
+            total_reward_debt: u128::from_le_bytes([17; 16]),
+            total_residual_unpaid: u64::from_le_bytes([18; 8]),
+            rewards_paused: true,
+            accumulated_dust_wad: u128::from_le_bytes([20; 16]),
+            pool_id: u64::from_le_bytes([21; 8]),
+            receipt_mint: Pubkey::new_from_array([22; 32]),
+            refund_address: Pubkey::new_from_array([23; 32]),
+            refund_after_seconds: u64::from_le_bytes([24; 8]),
+            undistributed_since: i64::from_le_bytes([25; 8]),
+            min_distribution_stake: u64::from_le_bytes([26; 8]),
+            unstake_fee_bps: u16::from_le_bytes([27; 2]),
+            unstake_fee_destination: Pubkey::new_from_array([28; 32]),
+            has_authority_log: true,
+            state_nonce: u64::from_le_bytes([30; 8]),
+            unstake_request_expiry_seconds: u64::from_le_bytes([31; 8]),
+            protocol_fee_bps: u16::from_le_bytes([32; 2]),
+            protocol_fees_owed: u64::from_le_bytes([33; 8]),
+            rounding_mode: 34,
+            has_depositor_allowlist: true,
+            lock_boost_bps: u16::from_le_bytes([36; 2]),
+            maturity_bonus_bps: u16::from_le_bytes([37; 2]),
+            bootstrap_period_seconds: u64::from_le_bytes([38; 8]),
+            has_matching_vault: true,
+            compression_tree: Pubkey::new_from_array([40; 32]),
+            compressed_leaf_count: u64::from_le_bytes([41; 8]),
+            compressed_staked: u128::from_le_bytes([42; 16]),
+            compressed_reward_debt: u128::from_le_bytes([43; 16]),
+            has_rent_sponsor: true,
+            min_sync_interval_seconds: u64::from_le_bytes([45; 8]),
+            last_distribution_time: i64::from_le_bytes([46; 8]),
+            claim_vesting_seconds: u64::from_le_bytes([47; 8]),
+            vesting_acc_rps: u128::from_le_bytes([48; 16]),
+            vesting_start: i64::from_le_bytes([49; 8]),
+            vesting_end: i64::from_le_bytes([50; 8]),
+            price_oracle: Pubkey::new_from_array([51; 32]),
+            has_gas_rebate_vault: true,
+            claim_burn_bps: u16::from_le_bytes([53; 2]),
+            claim_burn_destination: Pubkey::new_from_array([54; 32]),
+            undistributed_lamports: u64::from_le_bytes([55; 8]),
+            residuals_first: true,
+            residual_reserved: u64::from_le_bytes([57; 8]),
+            min_claim_lamports: u64::from_le_bytes([58; 8]),
+            has_distribution_history: true,
+            distribution_checkpoints: u64::from_le_bytes([59; 8]),
+            reward_rate_sum: u128::from_le_bytes([60; 16]),
+            reward_rate_time: i64::from_le_bytes([61; 8]),
+            track_depositors: true,
+            rebase_threshold_bps: u32::from_le_bytes([62; 4]),
+            acc_rps_ray_remainder: u64::from_le_bytes([63; 8]),
+            vault_generation: u32::from_le_bytes([64; 4]),
+            lock_exempt_amount: u64::from_le_bytes([65; 8]),
+            stake_guard_required: true,
+            rounding_reserve_wad: u128::from_le_bytes([67; 16]),
+            weight_engine: 68,
+            reserved: [0u8; POOL_RESERVED_LEN],
+        }
+    }
+
+    /// UserStake counterpart of layout_sample_pool
+    fn layout_sample_stake() -> UserStake {
+        UserStake {
+            discriminator: USER_STAKE_DISCRIMINATOR,
+            owner: Pubkey::new_from_array([1; 32]),
+            pool: Pubkey::new_from_array([2; 32]),
+            amount: u64::from_le_bytes([3; 8]),
+            stake_time: i64::from_le_bytes([4; 8]),
+            exp_start_factor: u128::from_le_bytes([5; 16]),
+            reward_debt: u128::from_le_bytes([6; 16]),
+            bump: 7,
+            unstake_request_amount: u64::from_le_bytes([8; 8]),
+            unstake_request_time: i64::from_le_bytes([9; 8]),
+            last_stake_time: i64::from_le_bytes([10; 8]),
+            base_time_snapshot: i64::from_le_bytes([11; 8]),
+            total_rewards_claimed: u64::from_le_bytes([12; 8]),
+            claimed_rewards_wad: u128::from_le_bytes([13; 16]),
+            last_action_slot: u64::from_le_bytes([14; 8]),
+            last_action_kind: 15,
+            has_weight_history: true,
+            reward_recipient: Pubkey::new_from_array([17; 32]),
+            first_stake_time: i64::from_le_bytes([18; 8]),
+            total_deposited: u64::from_le_bytes([19; 8]),
+            total_withdrawn: u64::from_le_bytes([20; 8]),
+            has_lock_schedule: true,
+            state_nonce: u64::from_le_bytes([22; 8]),
+            has_payout_split: true,
+            lock_until: i64::from_le_bytes([24; 8]),
+            maturity_bonus_paid: true,
+            guardian: Pubkey::new_from_array([26; 32]),
+            guardian_removal_time: i64::from_le_bytes([27; 8]),
+            sponsored_rent: u64::from_le_bytes([28; 8]),
+            vesting_rewards_wad: u128::from_le_bytes([29; 16]),
+            vesting_start: i64::from_le_bytes([30; 8]),
+            vesting_end: i64::from_le_bytes([31; 8]),
+            recent_deposits: [DepositTranche {
+                amount: u64::from_le_bytes([32; 8]),
+                time: i64::from_le_bytes([33; 8]),
+            }; RECENT_DEPOSIT_SLOTS],
+            position_mint: Pubkey::new_from_array([34; 32]),
+            rewards_frozen: true,
+            reserved: [0u8; USER_STAKE_RESERVED_LEN],
+        }
+    }
+
+    /// Hex dump, 16 bytes per line, prefixed with the offset
+    fn hex_dump(bytes: &[u8]) -> String {
+        bytes
+            .chunks(16)
+            .enumerate()
+            .map(|(line, chunk)| {
+                let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+                format!("{:04x}: {}\n", line * 16, hex.join(" "))
+            })
+            .collect()
+    }
+
+    /// Compare `bytes` with the golden dump in testdata/. Run the tests with
+    /// UPDATE_GOLDEN=1 to regenerate it after an intended layout change.
+    fn check_golden_layout(name: &str, bytes: &[u8]) {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata")
+            .join(format!("{}.hex", name));
+        let dump = hex_dump(bytes);
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::write(&path, &dump).unwrap();
+            return;
+        }
+        let golden = std::fs::read_to_string(&path)
+            .unwrap_or_else(|_| panic!("missing {}; run with UPDATE_GOLDEN=1", path.display()));
+        assert!(
+            golden == dump,
+            "{} layout changed; existing accounts would be misread. New fields \
+             must come out of `reserved`; regenerate with UPDATE_GOLDEN=1 only \
+             if no existing offset moved",
+            name
+        );
+    }
+
+    #[test]
+    fn test_pool_golden_layout() {
+        let pool = layout_sample_pool();
+        let data = borsh::to_vec(&pool).unwrap();
+        assert_eq!(data.len(), StakingPool::ACCOUNT_SIZE);
+        assert!(data[StakingPool::LEN - POOL_RESERVED_LEN..].iter().all(|b| *b == 0));
+        assert_eq!(data[StakingPool::WEIGHT_ENGINE_OFFSET], pool.weight_engine);
+        check_golden_layout("staking_pool", &data);
+
+        // Reserved space round-trips, and pools sized before it existed
+        // read it as zeros
+        let round_trip = StakingPool::try_from_slice(&data).unwrap();
+        assert_eq!(borsh::to_vec(&round_trip).unwrap(), data);
+        let pre_reserved =
+            StakingPool::try_from_slice(&data[..StakingPool::LEN - POOL_RESERVED_LEN]).unwrap();
+        assert_eq!(pre_reserved.reserved, [0u8; POOL_RESERVED_LEN]);
+        assert_eq!(pre_reserved.price_oracle, pool.price_oracle);
+        assert!(pre_reserved.has_gas_rebate_vault);
+    }
+
+    #[test]
+    fn test_user_stake_golden_layout() {
+        let stake = layout_sample_stake();
+        let data = borsh::to_vec(&stake).unwrap();
+        assert_eq!(data.len(), UserStake::ACCOUNT_SIZE);
+        assert!(data[UserStake::LEN - USER_STAKE_RESERVED_LEN..].iter().all(|b| *b == 0));
+        check_golden_layout("user_stake", &data);
+
+        let round_trip = UserStake::try_from_slice(&data).unwrap();
+        assert_eq!(borsh::to_vec(&round_trip).unwrap(), data);
+        let pre_reserved =
+            UserStake::try_from_slice(&data[..UserStake::LEN - USER_STAKE_RESERVED_LEN]).unwrap();
+        assert_eq!(pre_reserved.reserved, [0u8; USER_STAKE_RESERVED_LEN]);
+        assert_eq!(pre_reserved.vesting_end, stake.vesting_end);
+    }
+
+    #[test]
+    fn test_filter_offsets() {
+        let (mint, authority) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pool = StakingPool::new(
+            mint,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            authority,
+            86_400,
+            0,
+            255,
+        );
+        let data = borsh::to_vec(&pool).unwrap();
+        assert_eq!(data[..8], POOL_DISCRIMINATOR);
+        assert_eq!(data[StakingPool::MINT_OFFSET..][..32], mint.to_bytes());
+        assert_eq!(data[StakingPool::AUTHORITY_OFFSET..][..32], authority.to_bytes());
+
+        let (owner, pool_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let stake = UserStake::new(owner, pool_key, 1, 0, WAD, 255, 0);
+        let data = borsh::to_vec(&stake).unwrap();
+        assert_eq!(data[..8], USER_STAKE_DISCRIMINATOR);
+        assert_eq!(data[UserStake::OWNER_OFFSET..][..32], owner.to_bytes());
+        assert_eq!(data[UserStake::POOL_OFFSET..][..32], pool_key.to_bytes());
+    }
+
+    #[test]
+    fn test_user_stake_legacy_deserialize() {
+        // Create a new stake and serialize it
+        let stake = UserStake::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            1000,
+            12345,
+            1_000_000_000_000_000_000,
+            255,
+            12345,
+        );
+        let full = borsh::to_vec(&stake).unwrap();
+
+        // Truncate to legacy 161 bytes (no claimed_rewards_wad)
+        let legacy = &full[..UserStake::LEGACY_LEN];
+
+        // Deserialize should succeed with claimed_rewards_wad defaulting to 0
+        let deserialized = UserStake::try_from_slice(legacy).unwrap();
+        assert_eq!(deserialized.amount, 1000);
+        assert_eq!(deserialized.total_rewards_claimed, 0);
+        assert_eq!(deserialized.claimed_rewards_wad, 0);
+        assert_eq!(deserialized.bump, 255);
+
+        // Very old 153-byte accounts (no total_rewards_claimed or claimed_rewards_wad)
+        let very_old = &full[..153];
+        let deserialized_old = UserStake::try_from_slice(very_old).unwrap();
+        assert_eq!(deserialized_old.amount, 1000);
+        assert_eq!(deserialized_old.total_rewards_claimed, 0);
+        assert_eq!(deserialized_old.claimed_rewards_wad, 0);
+
+        // 177-byte accounts (no same-slot guard fields)
+        let pre_guard = &full[..177];
+        let deserialized_pre_guard = UserStake::try_from_slice(pre_guard).unwrap();
+        assert_eq!(deserialized_pre_guard.last_action_slot, 0);
+        assert_eq!(deserialized_pre_guard.last_action_kind, StakeAction::None as u8);
+
+        // 186-byte accounts (no weight history flag)
+        let pre_history = UserStake::try_from_slice(&full[..186]).unwrap();
+        assert!(!pre_history.has_weight_history);
+
+        // 187-byte accounts (no reward recipient)
+        let pre_recipient = UserStake::try_from_slice(&full[..187]).unwrap();
+        assert!(!pre_recipient.has_reward_recipient());
+
+        // 219-byte accounts (no lifetime stats)
+        let pre_stats = UserStake::try_from_slice(&full[..219]).unwrap();
+        assert_eq!(pre_stats.first_stake_time, 0);
+        assert_eq!(pre_stats.effective_first_stake_time(), 12345);
+        assert_eq!(pre_stats.total_deposited, 0);
+
+        // 243-byte accounts (no lock schedule flag)
+        let pre_lock = UserStake::try_from_slice(&full[..243]).unwrap();
+        assert!(!pre_lock.has_lock_schedule);
+
+        // 262-byte accounts (no guardian)
+        let pre_guardian = UserStake::try_from_slice(&full[..262]).unwrap();
+        assert!(!pre_guardian.has_guardian());
+        assert_eq!(pre_guardian.guardian_removal_time, 0);
+
+        // Full deserialization should also work
+        let deserialized_full = UserStake::try_from_slice(&full).unwrap();
+        assert_eq!(deserialized_full.total_rewards_claimed, 0);
+        assert_eq!(deserialized_full.claimed_rewards_wad, 0);
+    }
+
+    #[test]
+    fn test_weight_history() {
+        let tau = 1_000u64;
+        let capacity = 3u16;
+        let mut data = vec![0u8; WeightHistory::space(capacity)];
+        let mut history = WeightHistory {
+            discriminator: WEIGHT_HISTORY_DISCRIMINATOR,
+            pool: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            capacity,
+            count: 0,
+            bump: 255,
+        };
+        let checkpoint = |timestamp, amount, weighted_stake| WeightCheckpoint {
+            timestamp,
+            amount,
+            weighted_stake,
+        };
+        let within = |value: u128, expected: u128| value.abs_diff(expected) < expected / 1_000;
+
+        // Fresh 100-token stake at t=0: average over one tau is 100 * e^-1
+        history.push(&mut data, &checkpoint(0, 100, 0)).unwrap();
+        let avg = history.average_weighted_stake(&data, tau, 0, 1_000).unwrap();
+        assert!(within(avg, 36_787_944_117_144_232_160), "avg {}", avg);
+
+        // Full unstake at t=1000 halves the average over [0, 2000]
+        history.push(&mut data, &checkpoint(1_000, 0, 0)).unwrap();
+        let avg = history.average_weighted_stake(&data, tau, 0, 2_000).unwrap();
+        assert!(within(avg, 18_393_972_058_572_116_080), "avg {}", avg);
+
+        // Before the first checkpoint the position did not exist
+        let avg = history.average_weighted_stake(&data, tau, -1_000, 1_000).unwrap();
+        assert!(within(avg, 18_393_972_058_572_116_080), "avg {}", avg);
+
+        // Header persisted with the entries
+        let stored = WeightHistory::try_from_slice(&data[..WeightHistory::HEADER_LEN]).unwrap();
+        assert_eq!(stored.count, 2);
+
+        // Wrap around: oldest checkpoint is overwritten
+        history.push(&mut data, &checkpoint(2_000, 50, 0)).unwrap();
+        history.push(&mut data, &checkpoint(3_000, 50, 10 * WAD)).unwrap();
+        assert_eq!(history.retained(), 3);
+        assert_eq!(history.get(&data, 0).unwrap().timestamp, 1_000);
+        assert_eq!(history.get(&data, 2).unwrap().weighted_stake, 10 * WAD);
+        assert!(history.get(&data, 3).is_none());
+        assert_eq!(
+            history.average_weighted_stake(&data, tau, 0, 3_000),
+            Err(StakingError::HistoryWindowExceeded)
+        );
+        assert!(history.average_weighted_stake(&data, tau, 1_000, 4_000).is_ok());
+    }
+
+    #[test]
+    fn test_lock_schedule() {
+        let tranche = |amount, unlock_time| LockTranche { amount, unlock_time };
+        let mut schedule = UserLockSchedule {
+            discriminator: LOCK_SCHEDULE_DISCRIMINATOR,
+            pool: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            count: 0,
+            bump: 255,
+        };
+
+        // Staggered cliffs: 100 unlock at t=1000, 50 at t=2000
+        let tranches = UserLockSchedule::with_tranche(&[], tranche(100, 1_000), 0).unwrap();
+        let tranches = UserLockSchedule::with_tranche(&tranches, tranche(50, 2_000), 0).unwrap();
+        let mut data = vec![0u8; UserLockSchedule::space(tranches.len())];
+        schedule.write(&mut data, &tranches).unwrap();
+
+        let stored = UserLockSchedule::try_from_slice(&data[..UserLockSchedule::HEADER_LEN]).unwrap();
+        assert!(stored.is_initialized());
+        assert_eq!(stored.count, 2);
+        assert_eq!(stored.locked_amount(&data, 999).unwrap(), 150);
+        assert_eq!(stored.locked_amount(&data, 1_000).unwrap(), 50);
+        assert_eq!(stored.locked_amount(&data, 2_000).unwrap(), 0);
+
+        // Adding after the first cliff prunes it
+        let tranches = UserLockSchedule::with_tranche(
+            &stored.tranches(&data).unwrap(),
+            tranche(10, 3_000),
+            1_500,
+        )
+        .unwrap();
+        assert_eq!(tranches, vec![tranche(50, 2_000), tranche(10, 3_000)]);
+
+        // Too small an account for the tranches
+        assert!(schedule.write(&mut data[..UserLockSchedule::space(1)], &tranches).is_err());
+
+        // Full schedule
+        let full = vec![tranche(1, 10_000); UserLockSchedule::MAX_TRANCHES];
+        assert_eq!(
+            UserLockSchedule::with_tranche(&full, tranche(1, 10_000), 0),
+            Err(StakingError::LockScheduleFull)
+        );
+    }
+
+    #[test]
+    fn test_authority_log() {
+        let authority = Pubkey::new_unique();
+        let mut log = AuthorityLog {
+            discriminator: AUTHORITY_LOG_DISCRIMINATOR,
+            pool: Pubkey::new_unique(),
+            count: 0,
+            bump: 255,
+        };
+        let entries = [
+            AuthorityLogEntry::integer(100, authority, AuthorityAction::UnstakeFeeBps, 0, 250),
+            AuthorityLogEntry::pubkey(100, authority, AuthorityAction::Authority, authority, Pubkey::default()),
+        ];
+        let mut data = vec![0u8; AuthorityLog::space(2)];
+        log.append(&mut data, &entries).unwrap();
+
+        let stored = AuthorityLog::try_from_slice(&data[..AuthorityLog::HEADER_LEN]).unwrap();
+        assert!(stored.is_initialized());
+        assert_eq!(stored.count, 2);
+        let fee = stored.get(&data, 0).unwrap();
+        assert_eq!(fee.action, AuthorityAction::UnstakeFeeBps as u8);
+        assert_eq!(u64::from_le_bytes(fee.new_value[..8].try_into().unwrap()), 250);
+        assert_eq!(stored.get(&data, 1).unwrap().old_value, authority.to_bytes());
+        assert!(stored.get(&data, 2).is_none());
+
+        // No room for a third entry
+        assert!(log.append(&mut data, &entries[..1]).is_err());
+    }
+
+    #[test]
+    fn test_depositor_record() {
+        let mut record = DepositorRecord::new(Pubkey::new_unique(), Pubkey::new_unique(), 253);
+        record.record(1_000, 50);
+        record.record(u64::MAX, 80);
+        assert_eq!(record.deposit_count, 2);
+        assert_eq!(record.total_deposited, u64::MAX);
+        assert_eq!(record.first_deposit_time, 50);
+        assert_eq!(record.last_deposit_time, 80);
+        assert_eq!(borsh::to_vec(&record).unwrap().len(), DepositorRecord::LEN);
+    }
+
+    #[test]
+    fn test_voting_snapshot() {
+        let mut snapshot = VotingSnapshot::new(Pubkey::new_unique(), 7, 1_000, 254);
+        assert_eq!(borsh::to_vec(&snapshot).unwrap().len(), VotingSnapshot::LEN);
+        let mut page = VotingSnapshotPage {
+            discriminator: VOTING_SNAPSHOT_PAGE_DISCRIMINATOR,
+            snapshot: Pubkey::new_unique(),
+            page: 0,
+            count: 0,
+            bump: 253,
+        };
+        let mut data = vec![0u8; VotingSnapshotPage::LEN];
+
+        // Owners must strictly increase, across calls and pages
+        let weight = |n: u8| VotingWeight {
+            owner: Pubkey::new_from_array([n; 32]),
+            amount: n as u64 * 100,
+            weighted_stake: n as u128 * WAD,
+            timestamp: 1_000,
+        };
+        for n in 1..=VotingSnapshotPage::CAPACITY as u8 {
+            snapshot.record(&weight(n)).unwrap();
+            page.push(&mut data, &weight(n)).unwrap();
+        }
+        let capacity = VotingSnapshotPage::CAPACITY as u128;
+        assert_eq!(snapshot.entries, VotingSnapshotPage::CAPACITY as u32);
+        assert_eq!(snapshot.total_weighted_stake, capacity * (capacity + 1) / 2 * WAD);
+        assert_eq!(snapshot.current_page(), 1);
+        assert!(snapshot.record(&weight(VotingSnapshotPage::CAPACITY as u8)).is_err());
+        assert!(snapshot.record(&weight(1)).is_err());
+
+        let stored =
+            VotingSnapshotPage::try_from_slice(&data[..VotingSnapshotPage::HEADER_LEN]).unwrap();
+        assert!(stored.is_initialized());
+        assert_eq!(stored.get(&data, 0), Some(weight(1)));
+        assert!(stored.get(&data, VotingSnapshotPage::CAPACITY).is_none());
+
+        // A full page takes no more
+        assert!(page.push(&mut data, &weight(200)).is_err());
+    }
+
+    #[test]
+    fn test_stake_archive() {
+        let pool = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut stake = UserStake::new(owner, pool, 0, 2_000, WAD, 255, 0);
+        stake.total_deposited = 500;
+        stake.total_withdrawn = 500;
+        stake.total_rewards_claimed = 70;
+        stake.reward_debt = WAD / 3;
+
+        let mut archive = StakeArchive::new(pool, owner, 254);
+        archive.record(&stake, 9_000);
+        stake.first_stake_time = 1_000;
+        stake.vesting_rewards_wad = WAD / 4;
+        archive.record(&stake, 12_000);
+
+        assert_eq!(archive.closures, 2);
+        assert_eq!(archive.first_stake_time, 1_000);
+        assert_eq!(archive.last_closed_at, 12_000);
+        assert_eq!(archive.total_deposited, 1_000);
+        assert_eq!(archive.total_rewards_claimed, 140);
+        assert_eq!(archive.forgiven_rewards_wad, WAD * 2 / 3 + WAD / 4);
+        assert_eq!(borsh::to_vec(&archive).unwrap().len(), StakeArchive::LEN);
+    }
+
+    #[test]
+    fn test_distribution_history() {
+        assert_eq!(DistributionHistory::page_of(0), 0);
+        assert_eq!(DistributionHistory::page_of(127), 0);
+        assert_eq!(DistributionHistory::page_of(128), 1);
+
+        let mut page = DistributionHistory {
+            discriminator: DISTRIBUTION_HISTORY_DISCRIMINATOR,
+            pool: Pubkey::new_unique(),
+            page: 0,
+            count: 0,
+            bump: 255,
+        };
+        let mut data = vec![0u8; DistributionHistory::LEN];
+        for n in 0..DistributionHistory::CAPACITY {
+            let checkpoint = DistributionCheckpoint {
+                timestamp: 1_700_000_000 + n as i64,
+                acc_reward_per_weighted_share: (n as u128 + 1) * WAD,
+                lamports: 1_000,
+            };
+            page.push(&mut data, &checkpoint).unwrap();
+        }
+
+        let stored = DistributionHistory::try_from_slice(&data[..DistributionHistory::HEADER_LEN]).unwrap();
+        assert!(stored.is_initialized());
+        assert_eq!(stored.count, DistributionHistory::CAPACITY);
+        let last = stored.get(&data, DistributionHistory::CAPACITY - 1).unwrap();
+        assert_eq!(last.acc_reward_per_weighted_share, 128 * WAD);
+        assert!(stored.get(&data, DistributionHistory::CAPACITY).is_none());
+
+        // A full page takes no more
+        assert!(page.push(&mut data, &last).is_err());
+    }
+
+    #[test]
+    fn test_same_slot_action_guard() {
+        let mut stake = UserStake::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            1000,
+            12345,
+            1_000_000_000_000_000_000,
+            255,
+            12345,
+        );
+        stake.record_action(StakeAction::Stake, 100).unwrap();
+        // Adding more in the same slot is fine
+        stake.record_action(StakeAction::Stake, 100).unwrap();
+        // Unstake in the same slot is rejected
+        assert_eq!(
+            stake.record_action(StakeAction::Unstake, 100),
+            Err(StakingError::SameSlotAction)
+        );
+        // Next slot is fine, and blocks a re-stake in that slot
+        stake.record_action(StakeAction::Unstake, 101).unwrap();
+        assert_eq!(
+            stake.record_action(StakeAction::Stake, 101),
+            Err(StakingError::SameSlotAction)
+        );
+        stake.record_action(StakeAction::Stake, 102).unwrap();
+    }
+
+    #[test]
+    fn test_user_stake_total_rewards_roundtrip() {
+        let mut stake = UserStake::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            1000,
+            12345,
+            1_000_000_000_000_000_000,
+            255,
+            12345,
+        );
+        stake.total_rewards_claimed = 999_999;
+        stake.claimed_rewards_wad = 42_000_000_000_000_000_000;
+        let serialized = borsh::to_vec(&stake).unwrap();
+        let deserialized = UserStake::try_from_slice(&serialized).unwrap();
+        assert_eq!(deserialized.total_rewards_claimed, 999_999);
+        assert_eq!(deserialized.claimed_rewards_wad, 42_000_000_000_000_000_000);
+    }
+
+    #[test]
+    fn test_unstake_request_expiry() {
+        let mut pool = StakingPool::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            2592000,
+            0,
+            255,
+        );
+        pool.unstake_cooldown_seconds = 100;
+        let mut stake = UserStake::new(Pubkey::default(), Pubkey::default(), 1_000, 0, WAD, 255, 0);
+        stake.unstake_request_amount = 400;
+        stake.unstake_request_time = 1_000;
+
+        // No expiry configured: pending forever
+        assert!(stake.has_pending_unstake_request(&pool, i64::MAX));
+
+        // Completable until cooldown + grace has passed
+        pool.unstake_request_expiry_seconds = 50;
+        assert!(stake.has_pending_unstake_request(&pool, 1_149));
+        assert!(!stake.unstake_request_expired(&pool, 1_149));
+        assert!(stake.unstake_request_expired(&pool, 1_150));
+        assert!(!stake.has_pending_unstake_request(&pool, 1_150));
+
+        stake.clear_expired_unstake_request(&pool, 1_149);
+        assert_eq!(stake.unstake_request_amount, 400);
+        stake.clear_expired_unstake_request(&pool, 1_150);
+        assert_eq!((stake.unstake_request_amount, stake.unstake_request_time), (0, 0));
+        assert!(!stake.unstake_request_expired(&pool, 1_150));
+    }
+
+    #[test]
+    fn test_pending_distributions() {
+        let tranche = |release_time, amount| PendingTranche { release_time, amount };
+
+        // A week of emissions: 1/7 per day, dust to the last day
+        let schedule: Vec<(u64, u16)> = (0..7)
+            .map(|day| (day * 86_400, if day == 6 { 1_432 } else { 1_428 }))
+            .collect();
+        let tranches = PendingDistributions::split_schedule(1_000_001, &schedule, 100).unwrap();
+        assert_eq!(tranches.len(), 7);
+        assert_eq!(tranches[0], tranche(100, 142_800));
+        assert_eq!(tranches[6], tranche(100 + 6 * 86_400, 143_201));
+        assert_eq!(tranches.iter().map(|t| t.amount).sum::<u64>(), 1_000_001);
+
+        // Shares must be non-empty, non-zero and sum to 100%
+        for bad in [vec![], vec![(0, 5_000)], vec![(0, 10_000), (10, 0)], vec![(0, 9_000), (1, 2_000)]] {
+            assert_eq!(
+                PendingDistributions::split_schedule(100, &bad, 0),
+                Err(StakingError::InvalidDistributionSchedule)
+            );
+        }
+        assert_eq!(
+            PendingDistributions::split_schedule(100, &[(u64::MAX, 10_000)], 0),
+            Err(StakingError::MathOverflow)
+        );
+        // Tranches rounding to zero are dropped
+        let tiny = PendingDistributions::split_schedule(1, &[(0, 5_000), (10, 5_000)], 0).unwrap();
+        assert_eq!(tiny, vec![tranche(10, 1)]);
+
+        // Release the first two days
+        let (released, kept) = PendingDistributions::take_due(&tranches, 100 + 86_400);
+        assert_eq!(released, 2 * 142_800);
+        assert_eq!(kept.len(), 5);
+
+        let mut pending = PendingDistributions {
+            discriminator: PENDING_DISTRIBUTIONS_DISCRIMINATOR,
+            pool: Pubkey::new_unique(),
+            count: 0,
+            bump: 255,
+        };
+        let mut data = vec![0u8; PendingDistributions::space(tranches.len())];
+        pending.write(&mut data, &tranches).unwrap();
+        pending.write(&mut data, &kept).unwrap();
+        let stored =
+            PendingDistributions::try_from_slice(&data[..PendingDistributions::HEADER_LEN]).unwrap();
+        assert!(stored.is_initialized());
+        assert_eq!(stored.tranches(&data).unwrap(), kept);
+    }
+
+    #[test]
+    fn test_payout_split() {
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut split = PayoutSplit {
+            discriminator: PAYOUT_SPLIT_DISCRIMINATOR,
+            pool: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            count: 0,
+            bump: 255,
+            destinations: Default::default(),
+        };
+        assert_eq!(borsh::to_vec(&split).unwrap().len(), PayoutSplit::LEN);
+
+        // Dust goes to the last destination
+        split.set_destinations(&[(a, 5_000), (b, 3_333), (c, 1_667)]).unwrap();
+        assert_eq!(split.active().len(), 3);
+        assert_eq!(split.shares(1_000_001), vec![500_000, 333_300, 166_701]);
+        assert_eq!(split.shares(1), vec![0, 0, 1]);
+
+        // Replacing with fewer destinations clears the unused slots
+        split.set_destinations(&[(b, 10_000)]).unwrap();
+        assert_eq!(split.active(), &[PayoutDestination { recipient: b, bps: 10_000 }]);
+        assert_eq!(split.destinations[1], PayoutDestination::default());
+
+        let d = Pubkey::new_unique();
+        for bad in [
+            vec![],
+            vec![(a, 9_999)],
+            vec![(a, 10_000), (b, 0)],
+            vec![(a, 5_000), (a, 5_000)],
+            vec![(Pubkey::default(), 10_000)],
+            vec![(a, 2_000), (b, 2_000), (c, 2_000), (d, 2_000), (Pubkey::new_unique(), 2_000)],
+        ] {
+            assert_eq!(split.set_destinations(&bad), Err(StakingError::InvalidPayoutSplit));
+        }
+        assert_eq!(split.active().len(), 1);
+    }
+
+    #[test]
+    fn test_depositor_allowlist() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut allowlist = DepositorAllowlist {
+            discriminator: DEPOSITOR_ALLOWLIST_DISCRIMINATOR,
+            pool: Pubkey::new_unique(),
+            count: 0,
+            bump: 255,
+            depositors: Default::default(),
+        };
+        assert_eq!(borsh::to_vec(&allowlist).unwrap().len(), DepositorAllowlist::LEN);
+        assert!(!allowlist.contains(&Pubkey::default()));
+
+        allowlist.set_depositors(&[a, b]).unwrap();
+        assert!(allowlist.contains(&a) && allowlist.contains(&b));
+
+        // Replacing with fewer depositors clears the unused slots
+        allowlist.set_depositors(&[b]).unwrap();
+        assert!(!allowlist.contains(&a));
+        assert_eq!(allowlist.depositors[1], Pubkey::default());
+
+        let nine: Vec<Pubkey> = (0..9).map(|_| Pubkey::new_unique()).collect();
+        for bad in [vec![], vec![a, a], vec![Pubkey::default()], nine] {
+            assert_eq!(
+                allowlist.set_depositors(&bad),
+                Err(StakingError::InvalidDepositorAllowlist)
+            );
+        }
+        assert_eq!(allowlist.active(), &[b]);
+    }
+
+    #[test]
+    fn test_weight_tiers() {
+        let mut tiers = WeightTiers {
+            discriminator: WEIGHT_TIERS_DISCRIMINATOR,
+            pool: Pubkey::new_unique(),
+            count: 0,
+            bump: 255,
+            thresholds: [0; WeightTiers::MAX_TIERS],
+        };
+        assert_eq!(borsh::to_vec(&tiers).unwrap().len(), WeightTiers::LEN);
+        assert_eq!(tiers.tier_of(u64::MAX), 0);
+
+        tiers.set_thresholds(&[100, 1_000, 10_000]).unwrap();
+        assert_eq!(tiers.tier_of(0), 0);
+        assert_eq!(tiers.tier_of(99), 0);
+        assert_eq!(tiers.tier_of(100), 1);
+        assert_eq!(tiers.tier_of(9_999), 2);
+        assert_eq!(tiers.tier_of(u64::MAX), 3);
+
+        // Replacing with fewer thresholds clears the unused slots
+        tiers.set_thresholds(&[500]).unwrap();
+        assert_eq!(tiers.thresholds[1], 0);
+        assert_eq!(tiers.tier_of(10_000), 1);
+
+        let nine: Vec<u64> = (1..=9).collect();
+        for bad in [vec![], vec![0, 1], vec![5, 5], vec![5, 4], nine] {
+            assert_eq!(tiers.set_thresholds(&bad), Err(StakingError::InvalidWeightTiers));
+        }
+        assert_eq!(tiers.active(), &[500]);
+    }
+
+    #[test]
+    fn test_lock_extension() {
+        use crate::math::{calculate_user_weighted_stake, calculate_weight};
+        let tau = 86_400u64;
+        let mut pool = StakingPool::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            tau,
+            0,
+            255,
+        );
+        pool.lock_duration_seconds = 3_600;
+
+        // Staked at t=1000 with base_time 0
+        let factor = exp_time_ratio(1_000, tau).unwrap();
+        let mut stake = UserStake::new(Pubkey::default(), Pubkey::default(), 1_000, 1_000, factor, 255, 0);
+        pool.total_staked = 1_000;
+        pool.set_sum_stake_exp(U256::from_u128(wad_mul(1_000 * WAD, factor).unwrap()));
+
+        // Pool lock runs from the last stake; lock_until only extends it
+        assert_eq!(stake.lock_remaining_seconds(&pool, 2_000), 2_600);
+        stake.lock_until = 10_000;
+        assert_eq!(stake.lock_remaining_seconds(&pool, 2_000), 8_000);
+        assert_eq!(stake.lock_remaining_seconds(&pool, 20_000), 0);
+
+        // A 1-day age bonus weighs the same as staking a day earlier
+        let now = 50_000;
+        stake.apply_age_bonus(&mut pool, tau).unwrap();
+        assert_eq!(stake.stake_time, 1_000 - tau as i64);
+        let boosted = calculate_user_weighted_stake(1_000, stake.exp_start_factor, now, 0, tau).unwrap();
+        let expected = calculate_weight(1_000, now - stake.stake_time, tau).unwrap();
+        assert!(boosted.abs_diff(expected) < WAD / 1_000);
+
+        // The pool sum tracks the new factor
+        let sum = pool.get_sum_stake_exp().to_u128().unwrap();
+        assert!(sum.abs_diff(wad_mul(1_000 * WAD, stake.exp_start_factor).unwrap()) <= 1);
     }
-}
 
-impl UserStake {
-    /// Realloc account to current LEN if it's a legacy (smaller) account.
-    /// Transfers additional rent from payer to the account via system program CPI.
-    /// No-op if account is already at or above current LEN.
-    pub fn maybe_realloc<'a>(
-        account: &AccountInfo<'a>,
-        payer: &AccountInfo<'a>,
-        system_program: Option<&AccountInfo<'a>>,
-    ) -> Result<(), solana_program::program_error::ProgramError> {
-        if account.data_len() >= Self::LEN {
-            return Ok(());
-        }
+    #[test]
+    fn test_recent_deposit_lock() {
+        let mut pool = StakingPool::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            86_400,
+            0,
+            255,
+        );
+        pool.lock_duration_seconds = 1_000;
+        let top_up = |stake: &mut UserStake, pool: &StakingPool, amount: u64, time: i64| {
+            stake.record_deposit(pool, amount, time);
+            stake.amount += amount;
+            stake.last_stake_time = time;
+        };
 
-        let rent = solana_program::rent::Rent::get()?;
-        let new_rent = rent.minimum_balance(Self::LEN);
-        let old_rent = rent.minimum_balance(account.data_len());
-        let rent_delta = new_rent.saturating_sub(old_rent);
+        // Untracked positions are locked as a whole after the last stake
+        let mut stake = UserStake::new(Pubkey::default(), Pubkey::default(), 1_000, 0, WAD, 255, 0);
+        assert_eq!(stake.unlocked_amount(&pool, 999), 0);
+        assert_eq!(stake.unlocked_amount(&pool, 1_000), 1_000);
 
-        if rent_delta > 0 {
-            let sys_prog = system_program
-                .ok_or(StakingError::MissingSystemProgram)?;
-            solana_program::program::invoke(
-                &solana_program::system_instruction::transfer(
-                    payer.key,
-                    account.key,
-                    rent_delta,
-                ),
-                &[payer.clone(), account.clone(), sys_prog.clone()],
-            )?;
+        // A small top-up only re-locks itself
+        top_up(&mut stake, &pool, 10, 5_000);
+        assert_eq!(stake.unlocked_amount(&pool, 5_000), 1_000);
+        assert!(stake.check_lock(&pool, 1_000, 5_000).is_ok());
+        assert_eq!(
+            stake.check_lock(&pool, 1_001, 5_000),
+            Err(StakingError::InsufficientUnlockedStake
+                .with_context(ErrorContext::AvailableAmount(1_000)))
+        );
+        assert_eq!(stake.unlocked_amount(&pool, 6_000), 1_010);
+
+        // A top-up while the previous stake is still locked records it too
+        let mut stake = UserStake::new(Pubkey::default(), Pubkey::default(), 1_000, 0, WAD, 255, 0);
+        top_up(&mut stake, &pool, 10, 500);
+        assert_eq!(stake.unlocked_amount(&pool, 500), 0);
+        assert_eq!(
+            stake.check_lock(&pool, 1, 500),
+            Err(StakingError::StakeLocked.with_context(ErrorContext::RemainingSeconds(1_000)))
+        );
+        assert_eq!(stake.unlocked_amount(&pool, 1_000), 1_000);
+        assert_eq!(stake.unlocked_amount(&pool, 1_500), 1_010);
+
+        // Once the slots are full, top-ups join the newest deposit
+        for (i, time) in [2_000, 2_100, 2_200, 2_300, 2_400].into_iter().enumerate() {
+            top_up(&mut stake, &pool, 100 + i as u64, time);
         }
+        assert_eq!(stake.recent_deposits.iter().map(|d| d.amount).sum::<u64>(), 510);
+        assert_eq!(stake.recent_deposits.iter().map(|d| d.time).max(), Some(2_400));
+        // 103 + 104 share the 2_400 slot
+        assert_eq!(stake.unlocked_amount(&pool, 3_250), 1_520 - 207);
+        assert_eq!(stake.unlocked_amount(&pool, 3_400), 1_520);
 
-        account.realloc(Self::LEN, false)?;
+        // lock_until still holds everything; emptied positions start over
+        stake.lock_until = 10_000;
+        assert_eq!(stake.unlocked_amount(&pool, 9_999), 0);
+        stake.amount = 0;
+        top_up(&mut stake, &pool, 50, 20_000);
+        assert!(stake.recent_deposits.iter().all(|d| d.amount == 0));
+        assert_eq!(stake.unlocked_amount(&pool, 20_999), 0);
+        assert_eq!(stake.unlocked_amount(&pool, 21_000), 50);
 
-        Ok(())
+        // The pool lock only holds principal above lock_exempt_amount
+        pool.lock_exempt_amount = 100;
+        assert_eq!(stake.unlocked_amount(&pool, 20_000), 50);
+        top_up(&mut stake, &pool, 1_000, 20_500);
+        assert_eq!(stake.unlocked_amount(&pool, 20_500), 100);
+        assert_eq!(
+            stake.check_lock(&pool, 101, 20_500),
+            Err(StakingError::InsufficientUnlockedStake
+                .with_context(ErrorContext::AvailableAmount(100)))
+        );
+        assert_eq!(stake.unlocked_amount(&pool, 21_500), 1_050);
+        stake.lock_until = 30_000;
+        assert_eq!(stake.unlocked_amount(&pool, 29_999), 0);
     }
-}
 
-/// Pool metadata account for explorer display
-/// PDA: ["metadata", pool]
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
-pub struct PoolMetadata {
-    /// Discriminator for account type identification
-    pub discriminator: [u8; 8],
+    #[test]
+    fn test_maturity_bonus() {
+        let pool = StakingPool::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            1_000,
+            0,
+            255,
+        );
+        let mut stake = UserStake::new(Pubkey::default(), Pubkey::default(), 1_000, 500, WAD, 255, 0);
 
-    /// Back-reference to staking pool
-    pub pool: Pubkey,
+        // Mature at stake_time + 3 tau
+        assert_eq!(stake.maturity_remaining_seconds(&pool, 500), 3_000);
+        assert_eq!(stake.maturity_remaining_seconds(&pool, 3_499), 1);
+        assert_eq!(stake.maturity_remaining_seconds(&pool, 3_500), 0);
+        assert_eq!(stake.maturity_remaining_seconds(&pool, 1_000_000), 0);
 
-    /// Actual byte length of name
-    pub name_len: u8,
+        // Share of lifetime claims, rounded down
+        stake.total_rewards_claimed = 12_345;
+        assert_eq!(stake.maturity_bonus(1_000), 1_234);
+        assert_eq!(stake.maturity_bonus(StakingPool::MAX_MATURITY_BONUS_BPS), 12_345);
+        stake.total_rewards_claimed = u64::MAX;
+        assert_eq!(stake.maturity_bonus(10_000), u64::MAX);
 
-    /// UTF-8 name, zero-padded
-    pub name: [u8; 64],
+        let budget = BonusBudget {
+            discriminator: BONUS_BUDGET_DISCRIMINATOR,
+            pool: Pubkey::default(),
+            bump: 255,
+            total_paid: 0,
+        };
+        assert_eq!(borsh::to_vec(&budget).unwrap().len(), BonusBudget::LEN);
+    }
 
-    /// Number of active tags (max 8)
-    pub num_tags: u8,
+    #[test]
+    fn test_bootstrap_period() {
+        let tau = 1_000u64;
+        let mut pool = StakingPool::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            tau,
+            10_000,
+            255,
+        );
 
-    /// Byte length of each tag
-    pub tag_lengths: [u8; 8],
+        // No window: stakes start now
+        let (time, factor) = pool.new_stake_start(10_500).unwrap();
+        assert_eq!(time, 10_500);
+        assert_eq!(factor, exp_time_ratio(500, tau).unwrap());
 
-    /// UTF-8 tags, zero-padded
-    pub tags: [[u8; 32]; 8],
+        // Inside the window stakes start at creation, with factor WAD
+        pool.bootstrap_period_seconds = 3_600;
+        assert_eq!(pool.new_stake_start(10_500).unwrap(), (10_000, WAD));
+        assert_eq!(pool.new_stake_start(13_599).unwrap(), (10_000, WAD));
+        assert_eq!(pool.new_stake_start(13_600).unwrap().0, 13_600);
 
-    /// Actual byte length of url
-    pub url_len: u8,
+        // After a rebase, creation lies before base_time
+        pool.rebase(12_000).unwrap();
+        assert_eq!(pool.creation_time(), 10_000);
+        let (time, factor) = pool.new_stake_start(12_500).unwrap();
+        assert_eq!(time, 10_000);
+        assert_eq!(factor, exp_neg_time_ratio(2_000, tau).unwrap());
+    }
 
-    /// UTF-8 URL, zero-padded
-    pub url: [u8; 128],
+    #[test]
+    fn test_reward_matching() {
+        let mut vault = MatchingVault {
+            discriminator: MATCHING_VAULT_DISCRIMINATOR,
+            pool: Pubkey::default(),
+            bump: 255,
+            match_bps: 5_000,
+            total_matched: 0,
+        };
+        assert_eq!(borsh::to_vec(&vault).unwrap().len(), MatchingVault::LEN);
 
-    /// Active staker count
-    pub member_count: u64,
+        // Half of the distribution, capped by what the vault holds
+        assert_eq!(vault.match_for(1_001, u64::MAX), 500);
+        assert_eq!(vault.match_for(1_000, 200), 200);
+        assert_eq!(vault.match_for(1_000, 0), 0);
 
-    /// PDA bump seed
-    pub bump: u8,
-}
+        // 1:1 at the maximum, without overflow
+        vault.match_bps = StakingPool::MAX_MATCH_BPS;
+        assert_eq!(vault.match_for(u64::MAX, u64::MAX), u64::MAX);
+    }
 
-impl PoolMetadata {
-    /// Size of the account in bytes
-    pub const LEN: usize = 8 +  // discriminator
-        32 + // pool
-        1 +  // name_len
-        64 + // name
-        1 +  // num_tags
-        8 +  // tag_lengths
-        256 + // tags (8 * 32)
-        1 +  // url_len
-        128 + // url
-        8 +  // member_count
-        1;   // bump
+    #[test]
+    fn test_rent_sponsor_vault() {
+        let mut vault = RentSponsorVault {
+            discriminator: RENT_SPONSOR_DISCRIMINATOR,
+            pool: Pubkey::default(),
+            bump: 255,
+            sponsored_accounts: 0,
+            sponsored_lamports: 0,
+        };
+        assert_eq!(borsh::to_vec(&vault).unwrap().len(), RentSponsorVault::LEN);
 
-    /// Derive metadata PDA
-    pub fn derive_pda(pool: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
-        Pubkey::find_program_address(&[METADATA_SEED, pool.as_ref()], program_id)
+        vault.record_sponsored(2_000);
+        vault.record_sponsored(2_000);
+        vault.record_returned(2_000);
+        assert_eq!(vault.sponsored_accounts, 1);
+        assert_eq!(vault.sponsored_lamports, 2_000);
+        vault.record_returned(2_000);
+        assert_eq!((vault.sponsored_accounts, vault.sponsored_lamports), (0, 0));
     }
 
-    /// Check if metadata is initialized
-    pub fn is_initialized(&self) -> bool {
-        self.discriminator == METADATA_DISCRIMINATOR
-    }
-}
+    #[test]
+    fn test_gas_rebate_vault() {
+        let mut vault = GasRebateVault {
+            discriminator: GAS_REBATE_DISCRIMINATOR,
+            pool: Pubkey::default(),
+            bump: 255,
+            rebate_lamports: 5_000,
+            total_funded: 0,
+            total_rebated: 0,
+            rebates_paid: 0,
+        };
+        assert_eq!(borsh::to_vec(&vault).unwrap().len(), GasRebateVault::LEN);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        assert_eq!(vault.rebate_for(10_000, 1_000_000), 5_000);
+        assert_eq!(vault.rebate_for(5_000, 5_000), 5_000);
+        // Dust claims and short vaults get nothing
+        assert_eq!(vault.rebate_for(4_999, 1_000_000), 0);
+        assert_eq!(vault.rebate_for(10_000, 4_999), 0);
+
+        vault.record_rebate(5_000);
+        vault.record_rebate(5_000);
+        assert_eq!((vault.total_rebated, vault.rebates_paid), (10_000, 2));
+
+        vault.rebate_lamports = 0;
+        assert_eq!(vault.rebate_for(10_000, 1_000_000), 0);
+    }
 
     #[test]
-    fn test_pool_size() {
-        // Verify the calculated size matches actual serialized size
-        let pool = StakingPool::new(
+    fn test_protocol_fee() {
+        let mut pool = StakingPool::new(
             Pubkey::default(),
             Pubkey::default(),
             Pubkey::default(),
             Pubkey::default(),
-            2592000,
+            86_400,
             0,
             255,
         );
-        let serialized = borsh::to_vec(&pool).unwrap();
-        assert_eq!(serialized.len(), StakingPool::LEN);
+        pool.total_staked = 1;
+
+        // No fee until the config snapshot is set
+        pool.distribute_rewards(1_000).unwrap();
+        assert_eq!(pool.protocol_fees_owed, 0);
+
+        // 2.5%: the fee is held for the treasury, the rest credited
+        pool.protocol_fee_bps = 250;
+        let acc_before = pool.acc_reward_per_weighted_share;
+        let rps = pool.distribute_rewards(1_000).unwrap();
+        assert_eq!(pool.protocol_fees_owed, 25);
+        assert_eq!(pool.acc_reward_per_weighted_share, acc_before + rps);
+        assert_eq!(rps, 975 * WAD);
+
+        // Capped at MAX_PROTOCOL_FEE_BPS whatever the snapshot says
+        pool.protocol_fee_bps = 10_000;
+        assert_eq!(pool.protocol_fee(1_000), 100);
     }
 
     #[test]
-    fn test_pool_metadata_size() {
-        let metadata = PoolMetadata {
-            discriminator: METADATA_DISCRIMINATOR,
+    fn test_debt_recount() {
+        let mut pool = StakingPool::new(
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            86_400,
+            0,
+            255,
+        );
+        pool.total_staked = 300;
+        pool.total_reward_debt = 7; // drifted
+
+        let stake = |amount: u64, reward_debt: u128| {
+            let mut s = UserStake::new(Pubkey::default(), Pubkey::default(), amount, 0, 0, 255, 0);
+            s.reward_debt = reward_debt;
+            s
+        };
+        let mut keys: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        keys.sort();
+
+        let mut recount = DebtRecount {
+            discriminator: DEBT_RECOUNT_DISCRIMINATOR,
             pool: Pubkey::default(),
-            name_len: 0,
-            name: [0u8; 64],
-            num_tags: 0,
-            tag_lengths: [0u8; 8],
-            tags: [[0u8; 32]; 8],
-            url_len: 0,
-            url: [0u8; 128],
-            member_count: 0,
             bump: 255,
+            cursor: 0,
+            last_key: Pubkey::default(),
+            counted_staked: 0,
+            counted_debt: 0,
+            snapshot_staked: 0,
+            snapshot_debt: 0,
         };
-        let serialized = borsh::to_vec(&metadata).unwrap();
-        assert_eq!(serialized.len(), PoolMetadata::LEN);
-        assert_eq!(PoolMetadata::LEN, 508);
+        assert_eq!(borsh::to_vec(&recount).unwrap().len(), DebtRecount::LEN);
+        recount.restart(&pool);
+
+        // Page 1; a repeated or out-of-order key is rejected
+        recount.count(&keys[0], &stake(100, 40)).unwrap();
+        recount.count(&keys[2], &stake(0, 999)).unwrap(); // residual, skipped
+        assert_eq!(recount.count(&keys[1], &stake(100, 1)), Err(StakingError::RecountOutOfSync));
+        assert_eq!(recount.count(&keys[2], &stake(100, 1)), Err(StakingError::RecountOutOfSync));
+        assert_eq!(recount.is_complete(&pool), Ok(false));
+
+        // Page 2 covers total_staked
+        recount.count(&keys[3], &stake(200, 60)).unwrap();
+        assert_eq!(recount.cursor, 3);
+        assert_eq!(recount.is_complete(&pool), Ok(true));
+        assert_eq!(recount.counted_debt, 100);
+
+        // A stake between pages invalidates the recount
+        pool.total_staked += 50;
+        assert_eq!(recount.is_complete(&pool), Err(StakingError::RecountOutOfSync));
+
+        // Compressed positions are counted up front
+        pool.compressed_staked = 50;
+        pool.compressed_reward_debt = 5;
+        recount.restart(&pool);
+        recount.count(&keys[0], &stake(100, 40)).unwrap();
+        recount.count(&keys[3], &stake(200, 60)).unwrap();
+        assert_eq!(recount.is_complete(&pool), Ok(true));
+        assert_eq!(recount.counted_debt, 105);
     }
 
     #[test]
-    fn test_user_stake_size() {
-        let stake = UserStake::new(
+    fn test_is_compressible() {
+        let pool = StakingPool::new(
             Pubkey::default(),
             Pubkey::default(),
-            1000,
-            12345,
-            1_000_000_000_000_000_000,
+            Pubkey::default(),
+            Pubkey::default(),
+            86_400,
+            0,
             255,
-            12345,
         );
-        let serialized = borsh::to_vec(&stake).unwrap();
-        assert_eq!(serialized.len(), UserStake::LEN);
-        assert_eq!(UserStake::LEN, 177);
-        assert_eq!(UserStake::LEGACY_LEN, 161);
+        let stake = UserStake::new(Pubkey::default(), Pubkey::default(), 100, 0, WAD, 255, 0);
+        assert!(stake.is_compressible(&pool, 1_000));
+
+        // Residual positions close instead
+        let mut s = stake.clone();
+        s.amount = 0;
+        assert!(!s.is_compressible(&pool, 1_000));
+
+        let mut s = stake.clone();
+        s.unstake_request_amount = 10;
+        s.unstake_request_time = 900;
+        assert!(!s.is_compressible(&pool, 1_000));
+
+        // Companion accounts and guardians stay with live positions
+        let mut s = stake.clone();
+        s.has_weight_history = true;
+        assert!(!s.is_compressible(&pool, 1_000));
+        let mut s = stake.clone();
+        s.has_lock_schedule = true;
+        assert!(!s.is_compressible(&pool, 1_000));
+        let mut s = stake.clone();
+        s.has_payout_split = true;
+        assert!(!s.is_compressible(&pool, 1_000));
+        let mut s = stake.clone();
+        s.guardian = Pubkey::new_unique();
+        assert!(!s.is_compressible(&pool, 1_000));
+
+        // Sponsored rent isn't the owner's to take back
+        let mut s = stake.clone();
+        s.sponsored_rent = 2_000;
+        assert!(!s.is_compressible(&pool, 1_000));
+        let mut s = stake;
+        s.vesting_rewards_wad = WAD;
+        assert!(!s.is_compressible(&pool, 1_000));
     }
 
     #[test]
-    fn test_user_stake_legacy_deserialize() {
-        // Create a new stake and serialize it
-        let stake = UserStake::new(
+    fn test_tokenized_position() {
+        let pool = StakingPool::new(
             Pubkey::default(),
             Pubkey::default(),
-            1000,
-            12345,
-            1_000_000_000_000_000_000,
+            Pubkey::default(),
+            Pubkey::default(),
+            86_400,
+            0,
             255,
-            12345,
         );
-        let full = borsh::to_vec(&stake).unwrap();
-
-        // Truncate to legacy 161 bytes (no claimed_rewards_wad)
-        let legacy = &full[..UserStake::LEGACY_LEN];
-
-        // Deserialize should succeed with claimed_rewards_wad defaulting to 0
-        let deserialized = UserStake::try_from_slice(legacy).unwrap();
-        assert_eq!(deserialized.amount, 1000);
-        assert_eq!(deserialized.total_rewards_claimed, 0);
-        assert_eq!(deserialized.claimed_rewards_wad, 0);
-        assert_eq!(deserialized.bump, 255);
+        let owner = Pubkey::new_unique();
+        let mut stake = UserStake::new(owner, Pubkey::default(), 100, 0, WAD, 255, 0);
+        assert!(stake.is_tokenizable(&pool, 1_000));
+        assert_eq!(stake.check_owner(&owner), Ok(()));
+        assert_eq!(
+            stake.check_owner(&Pubkey::new_unique()),
+            Err(StakingError::InvalidOwner.into())
+        );
 
-        // Very old 153-byte accounts (no total_rewards_claimed or claimed_rewards_wad)
-        let very_old = &full[..153];
-        let deserialized_old = UserStake::try_from_slice(very_old).unwrap();
-        assert_eq!(deserialized_old.amount, 1000);
-        assert_eq!(deserialized_old.total_rewards_claimed, 0);
-        assert_eq!(deserialized_old.claimed_rewards_wad, 0);
+        // A reward recipient would keep the buyer's rewards
+        let mut s = stake.clone();
+        s.reward_recipient = Pubkey::new_unique();
+        assert!(!s.is_tokenizable(&pool, 1_000));
+        let mut s = stake.clone();
+        s.has_payout_split = true;
+        assert!(!s.is_tokenizable(&pool, 1_000));
 
-        // Full 177-byte deserialization should also work
-        let deserialized_full = UserStake::try_from_slice(&full).unwrap();
-        assert_eq!(deserialized_full.total_rewards_claimed, 0);
-        assert_eq!(deserialized_full.claimed_rewards_wad, 0);
+        // Once tokenized, the owner loses control until redemption
+        stake.position_mint = Pubkey::new_unique();
+        assert!(stake.is_tokenized());
+        assert!(!stake.is_tokenizable(&pool, 1_000));
+        assert_eq!(
+            stake.check_owner(&owner),
+            Err(StakingError::PositionTokenized.into())
+        );
+        assert_eq!(
+            stake.check_owner(&Pubkey::new_unique()),
+            Err(StakingError::InvalidOwner.into())
+        );
     }
 
     #[test]
-    fn test_user_stake_total_rewards_roundtrip() {
-        let mut stake = UserStake::new(
-            Pubkey::default(),
+    fn test_claim_vesting() {
+        let mut pool = StakingPool::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
             Pubkey::default(),
-            1000,
-            12345,
-            1_000_000_000_000_000_000,
-            255,
-            12345,
+            Pubkey::new_unique(),
+            2592000,
+            1_700_000_000,
+            254,
         );
-        stake.total_rewards_claimed = 999_999;
-        stake.claimed_rewards_wad = 42_000_000_000_000_000_000;
-        let serialized = borsh::to_vec(&stake).unwrap();
-        let deserialized = UserStake::try_from_slice(&serialized).unwrap();
-        assert_eq!(deserialized.total_rewards_claimed, 999_999);
-        assert_eq!(deserialized.claimed_rewards_wad, 42_000_000_000_000_000_000);
+        pool.total_staked = 1_000;
+
+        // Without vesting, distributions are claimable at once
+        let rps = pool.distribute_rewards(1_000).unwrap();
+        pool.vest_distribution(1_000, rps);
+        assert_eq!(pool.vested_acc_rps(1_000), pool.acc_reward_per_weighted_share);
+
+        // With vesting, each distribution vests linearly
+        pool.claim_vesting_seconds = 100;
+        let before = pool.acc_reward_per_weighted_share;
+        let rps = pool.distribute_rewards(1_000).unwrap();
+        pool.vest_distribution(1_000, rps);
+        assert_eq!(pool.vested_acc_rps(1_000), before);
+        assert_eq!(pool.vested_acc_rps(1_050), before + rps / 2);
+        assert_eq!(pool.vested_acc_rps(1_100), pool.acc_reward_per_weighted_share);
+
+        // A new distribution restarts the schedule with what is left
+        pool.vest_distribution(1_050, rps);
+        assert_eq!(pool.unvested_acc_rps(1_050), rps / 2 + rps);
+        assert_eq!(pool.vesting_end, 1_150);
+        assert_eq!(pool.unvested_acc_rps(1_150), 0);
+    }
+
+    #[test]
+    fn test_vesting_fragment() {
+        let mut stake = UserStake::new(Pubkey::new_unique(), Pubkey::new_unique(), 100, 0, WAD, 255, 0);
+        assert_eq!(stake.release_vested_rewards(1_000), 0);
+
+        stake.add_vesting_rewards(1_000, 100 * WAD, 1_100);
+        assert_eq!(stake.vested_rewards_wad(1_000), 0);
+        assert_eq!(stake.vested_rewards_wad(1_025), 25 * WAD);
+        assert_eq!(stake.release_vested_rewards(1_025), 25 * WAD);
+        assert_eq!(stake.vested_rewards_wad(1_025), 0);
+
+        // The rest keeps vesting until the same end
+        assert_eq!(stake.vested_rewards_wad(1_050), 25 * WAD);
+
+        // Added rewards never shorten the schedule
+        stake.release_vested_rewards(1_050);
+        stake.add_vesting_rewards(1_050, 50 * WAD, 1_060);
+        assert_eq!(stake.vesting_end, 1_100);
+        assert_eq!(stake.release_vested_rewards(1_200), 100 * WAD);
+        assert_eq!((stake.vesting_rewards_wad, stake.vesting_start, stake.vesting_end), (0, 0, 0));
+    }
+
+    #[test]
+    fn test_rounding_mode() {
+        let cases = [
+            // (wad, floor, half up, half even)
+            (2 * WAD + WAD / 2 - 1, 2, 2, 2),
+            (2 * WAD + WAD / 2, 2, 3, 2),
+            (3 * WAD + WAD / 2, 3, 4, 4),
+            (2 * WAD + WAD / 2 + 1, 2, 3, 3),
+            (WAD - 1, 0, 1, 1),
+            (0, 0, 0, 0),
+        ];
+        for (wad, floor, half_up, half_even) in cases {
+            assert_eq!(RoundingMode::Floor.to_lamports(wad), floor);
+            assert_eq!(RoundingMode::HalfUp.to_lamports(wad), half_up);
+            assert_eq!(RoundingMode::HalfEven.to_lamports(wad), half_even);
+        }
+        assert_eq!(RoundingMode::from_u8(3), None);
+    }
+
+    #[test]
+    fn test_rounding_conservation() {
+        // Mature stakes of 1, 2 and 4 tokens claim after every deposit; a
+        // deposit of 10 lamports splits into sevenths
+        for mode in [RoundingMode::Floor, RoundingMode::HalfUp, RoundingMode::HalfEven] {
+            let mut pool = StakingPool::new(
+                Pubkey::default(),
+                Pubkey::default(),
+                Pubkey::default(),
+                Pubkey::default(),
+                86_400,
+                0,
+                255,
+            );
+            pool.rounding_mode = mode as u8;
+            pool.total_staked = 7;
+            let mut users: Vec<UserStake> = [1u64, 2, 4]
+                .iter()
+                .map(|&amount| UserStake::new(Pubkey::default(), Pubkey::default(), amount, 0, WAD, 255, 0))
+                .collect();
+            let mut paid = [0u128; 3];
+            let mut deposited = 0u128;
+
+            for round in 0..200u64 {
+                let lamports = 10 + round % 3;
+                pool.distribute_rewards(lamports).unwrap();
+                deposited += lamports as u128;
+
+                for (user, paid) in users.iter_mut().zip(paid.iter_mut()) {
+                    let weighted = user.amount as u128 * WAD;
+                    let pending = user
+                        .pending_rewards_wad(weighted, pool.acc_reward_per_weighted_share)
+                        .unwrap();
+                    let payout = pool.payout_lamports(pending);
+                    user.claimed_rewards_wad += pool.settle_payout(pending, payout as u64);
+                    *paid += payout;
+
+                    // Floor never pays ahead and leaves under a lamport
+                    // owed; the nearest modes settle the entitlement exactly
+                    let entitlement = wad_mul(weighted, pool.acc_reward_per_weighted_share).unwrap();
+                    if mode == RoundingMode::Floor {
+                        assert!(*paid * WAD <= entitlement);
+                        assert!(*paid * WAD + WAD > entitlement);
+                    } else {
+                        assert_eq!(user.claimed_rewards_wad, entitlement);
+                    }
+                }
+
+                // Payouts never exceed deposits: every settled reward was
+                // either paid or is still held in the rounding reserve, and
+                // no more is settled than was credited at WAD (excluding
+                // dust and the carried RAY digits)
+                let total: u128 = paid.iter().sum();
+                let settled: u128 = users.iter().map(|user| user.claimed_rewards_wad).sum();
+                let credited_wad = pool.acc_reward_per_weighted_share * pool.total_staked;
+                assert!(credited_wad + pool.accumulated_dust_wad <= deposited * WAD);
+                assert_eq!(total * WAD + pool.rounding_reserve_wad, settled);
+                assert!(settled <= credited_wad);
+                assert!(total <= deposited);
+                let covered = (total + users.len() as u128) * WAD + pool.rounding_reserve_wad;
+                assert!(covered > credited_wad);
+                if mode == RoundingMode::Floor {
+                    assert_eq!(pool.rounding_reserve_wad, 0);
+                }
+            }
+        }
     }
 }
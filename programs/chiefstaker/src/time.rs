@@ -0,0 +1,66 @@
+//! Clock reads for handlers working on a pool
+//!
+//! The cluster's unix_timestamp is a stake-weighted estimate and can step
+//! backwards. Accumulator and weight math assume time never precedes what
+//! the pool already recorded, so handlers take the time through
+//! [`pool_time`] instead of reading the Clock sysvar directly.
+
+use solana_program::{clock::Clock, msg, program_error::ProgramError, sysvar::Sysvar};
+
+use crate::{error::StakingError, state::StakingPool};
+
+/// How far the clock may lag the pool's recorded time before handlers
+/// refuse to run (5 minutes); smaller lags are clamped
+pub const MAX_CLOCK_REGRESSION_SECONDS: i64 = 300;
+
+/// Current time for `pool`: the Clock sysvar's unix_timestamp, see
+/// [`clamp_time`]
+pub fn pool_time(pool: &StakingPool) -> Result<i64, ProgramError> {
+    Ok(clamp_time(pool, Clock::get()?.unix_timestamp)?)
+}
+
+/// `now`, raised to the latest time `pool` recorded (last_update_time or
+/// base_time) so ages and elapsed times are never negative and
+/// last_update_time only moves forward. A clock more than
+/// MAX_CLOCK_REGRESSION_SECONDS behind is rejected.
+pub fn clamp_time(pool: &StakingPool, now: i64) -> Result<i64, StakingError> {
+    let recorded = pool.last_update_time.max(pool.base_time);
+    if now < recorded.saturating_sub(MAX_CLOCK_REGRESSION_SECONDS) {
+        msg!("Clock at {} is behind the pool's recorded time {}", now, recorded);
+        return Err(StakingError::ClockWentBackwards);
+    }
+    Ok(now.max(recorded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::pubkey::Pubkey;
+
+    #[test]
+    fn test_clamp_time() {
+        let mut pool = StakingPool::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::default(),
+            Pubkey::new_unique(),
+            2592000,
+            1_000,
+            254,
+        );
+        pool.last_update_time = 5_000;
+
+        assert_eq!(clamp_time(&pool, 6_000), Ok(6_000));
+        // Small regressions are clamped to the recorded time
+        assert_eq!(clamp_time(&pool, 4_900), Ok(5_000));
+        assert_eq!(clamp_time(&pool, 5_000 - MAX_CLOCK_REGRESSION_SECONDS), Ok(5_000));
+        assert_eq!(
+            clamp_time(&pool, 4_999 - MAX_CLOCK_REGRESSION_SECONDS),
+            Err(StakingError::ClockWentBackwards)
+        );
+
+        // A rebase records base_time too
+        pool.base_time = 8_000;
+        assert_eq!(clamp_time(&pool, 7_900), Ok(8_000));
+    }
+}
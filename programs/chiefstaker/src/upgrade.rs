@@ -0,0 +1,75 @@
+//! Upgrade authority lookup from the program's ProgramData account
+
+use solana_program::{
+    account_info::AccountInfo, bpf_loader_upgradeable, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::error::StakingError;
+
+/// Bincode tag of `UpgradeableLoaderState::ProgramData`
+const PROGRAM_DATA_TAG: u32 = 3;
+
+/// Offset of the `Option<Pubkey>` upgrade authority (tag u32 + slot u64)
+const AUTHORITY_OFFSET: usize = 4 + 8;
+
+/// Upgrade authority recorded in ProgramData account data. Immutable
+/// programs report Pubkey::default().
+pub fn parse_upgrade_authority(data: &[u8]) -> Result<Pubkey, StakingError> {
+    let tag = data
+        .get(..4)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        .ok_or(StakingError::AccountDataTooSmall)?;
+    if tag != PROGRAM_DATA_TAG {
+        return Err(StakingError::InvalidAccountOwner);
+    }
+    match data.get(AUTHORITY_OFFSET) {
+        Some(0) => Ok(Pubkey::default()),
+        Some(1) => data
+            .get(AUTHORITY_OFFSET + 1..AUTHORITY_OFFSET + 33)
+            .map(|b| Pubkey::new_from_array(b.try_into().unwrap()))
+            .ok_or(StakingError::AccountDataTooSmall),
+        Some(_) => Err(StakingError::InvalidInstruction),
+        None => Err(StakingError::AccountDataTooSmall),
+    }
+}
+
+/// Validate `programdata_info` as the ProgramData account of `program_id`
+/// and return its current upgrade authority
+pub fn current_upgrade_authority(
+    program_id: &Pubkey,
+    programdata_info: &AccountInfo,
+) -> Result<Pubkey, ProgramError> {
+    if *programdata_info.owner != bpf_loader_upgradeable::id() {
+        return Err(StakingError::InvalidAccountOwner.into());
+    }
+    if *programdata_info.key != bpf_loader_upgradeable::get_program_data_address(program_id) {
+        return Err(StakingError::InvalidPDA.into());
+    }
+    Ok(parse_upgrade_authority(&programdata_info.try_borrow_data()?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_upgrade_authority() {
+        let authority = Pubkey::new_unique();
+        let mut data = vec![0u8; 45];
+        data[..4].copy_from_slice(&PROGRAM_DATA_TAG.to_le_bytes());
+        data[4..12].copy_from_slice(&1234u64.to_le_bytes());
+        data[12] = 1;
+        data[13..45].copy_from_slice(authority.as_ref());
+        assert_eq!(parse_upgrade_authority(&data), Ok(authority));
+
+        // Immutable program
+        data[12] = 0;
+        assert_eq!(parse_upgrade_authority(&data), Ok(Pubkey::default()));
+
+        // Program (tag 2) account instead of ProgramData
+        data[..4].copy_from_slice(&2u32.to_le_bytes());
+        assert_eq!(parse_upgrade_authority(&data), Err(StakingError::InvalidAccountOwner));
+        assert_eq!(parse_upgrade_authority(&data[..3]), Err(StakingError::AccountDataTooSmall));
+    }
+}
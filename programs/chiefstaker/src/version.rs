@@ -0,0 +1,189 @@
+//! Program version and feature bitmap
+//!
+//! Clients read these through GetVersion (or PoolInitialized events) to
+//! gate newer instructions on what a cluster actually runs. Feature bits
+//! are only ever appended; a bit keeps its meaning once released.
+
+/// Program version (major, minor, patch), taken from the crate version
+pub const PROGRAM_VERSION: [u16; 3] = [
+    parse_version(env!("CARGO_PKG_VERSION_MAJOR")),
+    parse_version(env!("CARGO_PKG_VERSION_MINOR")),
+    parse_version(env!("CARGO_PKG_VERSION_PATCH")),
+];
+
+const fn parse_version(s: &str) -> u16 {
+    let bytes = s.as_bytes();
+    let mut value = 0u16;
+    let mut i = 0;
+    while i < bytes.len() {
+        value = value * 10 + (bytes[i] - b'0') as u16;
+        i += 1;
+    }
+    value
+}
+
+/// Instruction data accepts Anchor-style 8-byte sighash discriminators
+pub const FEATURE_SIGHASH_DISCRIMINATORS: u64 = 1 << 0;
+/// SetUnstakeRequestExpiry
+pub const FEATURE_UNSTAKE_REQUEST_EXPIRY: u64 = 1 << 1;
+/// SetPayoutSplit
+pub const FEATURE_PAYOUT_SPLIT: u64 = 1 << 2;
+/// MigrateStake
+pub const FEATURE_MIGRATE_STAKE: u64 = 1 << 3;
+/// ProgramConfig, upgrade authority checks and the protocol fee
+pub const FEATURE_PROGRAM_CONFIG: u64 = 1 << 4;
+/// SetDepositorAllowlist
+pub const FEATURE_DEPOSITOR_ALLOWLIST: u64 = 1 << 5;
+/// ExtendLock and SetLockBoost
+pub const FEATURE_LOCK_BOOST: u64 = 1 << 6;
+/// SetMaturityBonus and ClaimMaturityBonus
+pub const FEATURE_MATURITY_BONUS: u64 = 1 << 7;
+/// SetGuardian and ClearGuardian
+pub const FEATURE_GUARDIAN: u64 = 1 << 8;
+/// SetBootstrapPeriod
+pub const FEATURE_BOOTSTRAP_PERIOD: u64 = 1 << 9;
+/// SetRewardMatching
+pub const FEATURE_REWARD_MATCHING: u64 = 1 << 10;
+/// InitializePoolIfNeeded and InitializePoolWithAuthority
+pub const FEATURE_POOL_CREATION_OPTIONS: u64 = 1 << 11;
+/// UnstakeAll and RequestUnstakeAll
+pub const FEATURE_UNSTAKE_ALL: u64 = 1 << 12;
+/// InitCompressionTree, StakeCompressed and DecompressStake
+pub const FEATURE_COMPRESSION: u64 = 1 << 13;
+/// SetRentSponsor
+pub const FEATURE_RENT_SPONSOR: u64 = 1 << 14;
+/// SetMinSyncInterval
+pub const FEATURE_MIN_SYNC_INTERVAL: u64 = 1 << 15;
+/// SkimStakeAccountLamports
+pub const FEATURE_STAKE_ACCOUNT_SKIM: u64 = 1 << 16;
+/// SetWeightTiers and GetWeightTier
+pub const FEATURE_WEIGHT_TIERS: u64 = 1 << 17;
+/// SetClaimVesting
+pub const FEATURE_CLAIM_VESTING: u64 = 1 << 18;
+/// GetProgramLimits
+pub const FEATURE_PROGRAM_LIMITS: u64 = 1 << 19;
+/// GetVersion and the PoolInitialized event
+pub const FEATURE_VERSION: u64 = 1 << 20;
+/// SetPriceOracle and GetPoolStats
+pub const FEATURE_PRICE_ORACLE: u64 = 1 << 21;
+/// SetClaimRebate and FundGasRebates
+pub const FEATURE_CLAIM_REBATE: u64 = 1 << 22;
+/// SetClaimBurn
+pub const FEATURE_CLAIM_BURN: u64 = 1 << 23;
+/// TokenizePosition and RedeemPosition
+pub const FEATURE_POSITION_NFT: u64 = 1 << 24;
+/// SetResidualsFirst
+pub const FEATURE_RESIDUALS_FIRST: u64 = 1 << 25;
+/// ClaimWithSession
+pub const FEATURE_CLAIM_SESSION: u64 = 1 << 26;
+/// Compact instruction layout for Stake, Unstake and ClaimRewards
+pub const FEATURE_COMPACT_INSTRUCTIONS: u64 = 1 << 27;
+/// SetMinClaimLamports
+pub const FEATURE_MIN_CLAIM: u64 = 1 << 28;
+/// InitDistributionHistory and AddDistributionHistoryPage
+pub const FEATURE_DISTRIBUTION_HISTORY: u64 = 1 << 29;
+/// ClosePoolMetadata
+pub const FEATURE_CLOSE_POOL_METADATA: u64 = 1 << 30;
+/// Renounce safety checks and RenounceAuthorityUnsafe
+pub const FEATURE_SAFE_RENOUNCE: u64 = 1 << 31;
+/// CloseStakeAccountArchived and CloseArchived
+pub const FEATURE_STAKE_ARCHIVE: u64 = 1 << 32;
+/// SetDepositorTracking and DepositorRecord accounts
+pub const FEATURE_DEPOSITOR_RECORDS: u64 = 1 << 33;
+/// SetRebaseThreshold; SyncPool only rebases once due
+pub const FEATURE_LAZY_REBASE: u64 = 1 << 34;
+/// ExitPool (unstake, claim and close in one instruction)
+pub const FEATURE_EXIT_POOL: u64 = 1 << 35;
+/// MigrateVault and StakingPool::vault_generation
+pub const FEATURE_VAULT_MIGRATION: u64 = 1 << 36;
+/// SnapshotVotingWeights, VotingSnapshot and VotingSnapshotPage accounts
+pub const FEATURE_VOTING_SNAPSHOTS: u64 = 1 << 37;
+/// SetLockExemptAmount; the pool lock only holds principal above it
+pub const FEATURE_LOCK_EXEMPT_AMOUNT: u64 = 1 << 38;
+/// ReconcileSyncedLamports and the SyncedLamportsReconciled event
+pub const FEATURE_RECONCILE_SYNCED: u64 = 1 << 39;
+/// Unstake memos for token accounts requiring them (MemoTransfer extension)
+pub const FEATURE_MEMO_TRANSFER: u64 = 1 << 40;
+/// SetStakeFrozen, UserStake::rewards_frozen and the StakeFreezeSet event
+pub const FEATURE_STAKE_FREEZE: u64 = 1 << 41;
+/// ClonePoolConfig
+pub const FEATURE_CLONE_POOL_CONFIG: u64 = 1 << 42;
+/// SetStakeGuardRequired and StakingPool::stake_guard_required
+pub const FEATURE_STAKE_GUARD_REQUIRED: u64 = 1 << 43;
+/// InitializeBucketedPool, RollAgeBuckets and the AgeBuckets weight engine
+pub const FEATURE_AGE_BUCKETS: u64 = 1 << 44;
+/// InitializePoolBeforeMint
+pub const FEATURE_POOL_BEFORE_MINT: u64 = 1 << 45;
+
+/// Features this build supports
+pub const PROGRAM_FEATURES: u64 = FEATURE_SIGHASH_DISCRIMINATORS
+    | FEATURE_UNSTAKE_REQUEST_EXPIRY
+    | FEATURE_PAYOUT_SPLIT
+    | FEATURE_MIGRATE_STAKE
+    | FEATURE_PROGRAM_CONFIG
+    | FEATURE_DEPOSITOR_ALLOWLIST
+    | FEATURE_LOCK_BOOST
+    | FEATURE_MATURITY_BONUS
+    | FEATURE_GUARDIAN
+    | FEATURE_BOOTSTRAP_PERIOD
+    | FEATURE_REWARD_MATCHING
+    | FEATURE_POOL_CREATION_OPTIONS
+    | FEATURE_UNSTAKE_ALL
+    | FEATURE_COMPRESSION
+    | FEATURE_RENT_SPONSOR
+    | FEATURE_MIN_SYNC_INTERVAL
+    | FEATURE_STAKE_ACCOUNT_SKIM
+    | FEATURE_WEIGHT_TIERS
+    | FEATURE_CLAIM_VESTING
+    | FEATURE_PROGRAM_LIMITS
+    | FEATURE_VERSION
+    | FEATURE_PRICE_ORACLE
+    | FEATURE_CLAIM_REBATE
+    | FEATURE_CLAIM_BURN
+    | FEATURE_POSITION_NFT
+    | FEATURE_RESIDUALS_FIRST
+    | FEATURE_CLAIM_SESSION
+    | FEATURE_COMPACT_INSTRUCTIONS
+    | FEATURE_MIN_CLAIM
+    | FEATURE_DISTRIBUTION_HISTORY
+    | FEATURE_CLOSE_POOL_METADATA
+    | FEATURE_SAFE_RENOUNCE
+    | FEATURE_STAKE_ARCHIVE
+    | FEATURE_DEPOSITOR_RECORDS
+    | FEATURE_LAZY_REBASE
+    | FEATURE_EXIT_POOL
+    | FEATURE_VAULT_MIGRATION
+    | FEATURE_VOTING_SNAPSHOTS
+    | FEATURE_LOCK_EXEMPT_AMOUNT
+    | FEATURE_RECONCILE_SYNCED
+    | FEATURE_MEMO_TRANSFER
+    | FEATURE_STAKE_FREEZE
+    | FEATURE_CLONE_POOL_CONFIG
+    | FEATURE_STAKE_GUARD_REQUIRED
+    | FEATURE_AGE_BUCKETS
+    | FEATURE_POOL_BEFORE_MINT;
+
+/// Check whether `features` (from GetVersion) includes all of `required`
+pub fn has_features(features: u64, required: u64) -> bool {
+    features & required == required
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_program_version() {
+        assert_eq!(
+            PROGRAM_VERSION,
+            [
+                env!("CARGO_PKG_VERSION_MAJOR").parse::<u16>().unwrap(),
+                env!("CARGO_PKG_VERSION_MINOR").parse::<u16>().unwrap(),
+                env!("CARGO_PKG_VERSION_PATCH").parse::<u16>().unwrap(),
+            ]
+        );
+        assert_eq!(PROGRAM_FEATURES.count_ones(), 46);
+        assert!(has_features(PROGRAM_FEATURES, FEATURE_VERSION | FEATURE_COMPRESSION));
+        assert!(!has_features(FEATURE_VERSION, FEATURE_VERSION | FEATURE_COMPRESSION));
+    }
+}
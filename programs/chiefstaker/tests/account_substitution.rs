@@ -0,0 +1,646 @@
+//! Account substitution attacks
+//!
+//! Every row runs one instruction through `process_instruction` with a
+//! single account swapped for a look-alike (another pool's stake or vault,
+//! a spoofed metadata PDA, the wrong token program, a stake owned by
+//! another program or user) and asserts the specific StakingError its
+//! validation promises. The accounts live in memory and the Clock and Rent
+//! sysvars are stubbed, so the suite needs no validator; CPIs fail, so a
+//! row that gets past validation shows up as CPI_UNAVAILABLE instead of
+//! passing silently.
+
+use std::sync::Once;
+
+use chiefstaker::{
+    error::StakingError,
+    math::WAD,
+    process_instruction,
+    state::{PoolMetadata, StakingPool, UserStake, METADATA_DISCRIMINATOR},
+    StakingInstruction,
+};
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::{ProgramResult, SUCCESS},
+    instruction::Instruction,
+    program_error::ProgramError,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    pubkey,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_program,
+    sysvar,
+};
+
+const NOW: i64 = 1_700_000_000;
+const CPI_UNAVAILABLE: ProgramError = ProgramError::Custom(u32::MAX);
+const LEGACY_TOKEN_PROGRAM: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_log_data(&self, _fields: &[&[u8]]) {}
+
+    fn sol_invoke_signed(
+        &self,
+        _instruction: &Instruction,
+        _account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        Err(CPI_UNAVAILABLE)
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock { unix_timestamp: NOW, ..Clock::default() };
+        unsafe { *(var_addr as *mut Clock) = clock };
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+}
+
+fn install_stubs() {
+    static STUBS: Once = Once::new();
+    STUBS.call_once(|| {
+        set_syscall_stubs(Box::new(Stubs));
+    });
+}
+
+struct TestAccount {
+    key: Pubkey,
+    owner: Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+    is_signer: bool,
+    is_writable: bool,
+}
+
+impl TestAccount {
+    fn new(key: Pubkey, owner: Pubkey, data: Vec<u8>) -> Self {
+        let lamports = Rent::default().minimum_balance(data.len());
+        Self { key, owner, lamports, data, is_signer: false, is_writable: true }
+    }
+
+    /// A key with no data, e.g. a program or a wallet
+    fn key(key: Pubkey) -> Self {
+        Self::new(key, system_program::id(), vec![])
+    }
+
+    fn signer(mut self) -> Self {
+        self.is_signer = true;
+        self.lamports = 1_000_000_000;
+        self
+    }
+
+    fn owned_by(mut self, owner: Pubkey) -> Self {
+        self.owner = owner;
+        self
+    }
+}
+
+fn run(instruction: StakingInstruction, accounts: &mut [TestAccount]) -> ProgramResult {
+    install_stubs();
+    let infos: Vec<AccountInfo> = accounts
+        .iter_mut()
+        .map(|a| {
+            AccountInfo::new(
+                &a.key, a.is_signer, a.is_writable, &mut a.lamports, &mut a.data, &a.owner, false,
+                0,
+            )
+        })
+        .collect();
+    process_instruction(&chiefstaker::id(), &infos, &instruction.pack())
+}
+
+fn assert_rejected(
+    case: &str,
+    instruction: StakingInstruction,
+    accounts: &mut [TestAccount],
+    expected: StakingError,
+) {
+    assert_eq!(run(instruction, accounts), Err(expected.into()), "{case}");
+}
+
+/// Two pools of one mint (ids 0 and 1) with their authority, and a user
+/// with a 100-token position in each
+struct World {
+    mint: Pubkey,
+    authority: Pubkey,
+    user: Pubkey,
+    pool_a: Pubkey,
+    pool_b: Pubkey,
+    vault_a: Pubkey,
+    vault_b: Pubkey,
+}
+
+impl World {
+    fn new() -> Self {
+        let program_id = chiefstaker::id();
+        let mint = Pubkey::new_unique();
+        let (pool_a, _) = StakingPool::derive_pda_with_id(&mint, 0, &program_id);
+        let (pool_b, _) = StakingPool::derive_pda_with_id(&mint, 1, &program_id);
+        Self {
+            mint,
+            authority: Pubkey::new_unique(),
+            user: Pubkey::new_unique(),
+            pool_a,
+            pool_b,
+            vault_a: StakingPool::derive_token_vault_pda(&pool_a, &program_id).0,
+            vault_b: StakingPool::derive_token_vault_pda(&pool_b, &program_id).0,
+        }
+    }
+
+    fn pool_state(&self, pool: &Pubkey) -> StakingPool {
+        let (pool_id, vault) =
+            if *pool == self.pool_a { (0, self.vault_a) } else { (1, self.vault_b) };
+        let (_, bump) = StakingPool::derive_pda_with_id(&self.mint, pool_id, &chiefstaker::id());
+        let mut state =
+            StakingPool::new(self.mint, vault, *pool, self.authority, 86_400, NOW - 1_000, bump);
+        state.pool_id = pool_id;
+        state
+    }
+
+    fn pool(&self, pool: &Pubkey) -> TestAccount {
+        self.pool_with(pool, |_| {})
+    }
+
+    fn pool_with(&self, pool: &Pubkey, configure: impl FnOnce(&mut StakingPool)) -> TestAccount {
+        let mut state = self.pool_state(pool);
+        configure(&mut state);
+        TestAccount::new(*pool, chiefstaker::id(), borsh::to_vec(&state).unwrap())
+    }
+
+    /// Pool with an unstake cooldown, as RequestUnstake requires
+    fn cooldown_pool(&self, pool: &Pubkey) -> TestAccount {
+        self.pool_with(pool, |state| state.unstake_cooldown_seconds = 600)
+    }
+
+    fn stake_state(&self, pool: &Pubkey, owner: &Pubkey, amount: u64) -> UserStake {
+        let (_, bump) = UserStake::derive_pda(pool, owner, &chiefstaker::id());
+        UserStake::new(*owner, *pool, amount, NOW - 1_000, WAD, bump, NOW - 1_000)
+    }
+
+    fn stake_key(&self, pool: &Pubkey, owner: &Pubkey) -> Pubkey {
+        UserStake::derive_pda(pool, owner, &chiefstaker::id()).0
+    }
+
+    fn stake(&self, pool: &Pubkey, amount: u64) -> TestAccount {
+        let data = borsh::to_vec(&self.stake_state(pool, &self.user, amount)).unwrap();
+        TestAccount::new(self.stake_key(pool, &self.user), chiefstaker::id(), data)
+    }
+
+    fn metadata_key(&self, pool: &Pubkey) -> Pubkey {
+        PoolMetadata::derive_pda(pool, &chiefstaker::id()).0
+    }
+
+    fn metadata(&self, pool: &Pubkey, member_count: u64) -> TestAccount {
+        let (key, bump) = PoolMetadata::derive_pda(pool, &chiefstaker::id());
+        let metadata = PoolMetadata {
+            discriminator: METADATA_DISCRIMINATOR,
+            pool: *pool,
+            name_len: 4,
+            name: {
+                let mut name = [0u8; 64];
+                name[..4].copy_from_slice(b"Pool");
+                name
+            },
+            num_tags: 0,
+            tag_lengths: [0; 8],
+            tags: [[0; 32]; 8],
+            url_len: 0,
+            url: [0; 128],
+            member_count,
+            bump,
+        };
+        TestAccount::new(key, chiefstaker::id(), borsh::to_vec(&metadata).unwrap())
+    }
+
+    fn token_account(&self, key: Pubkey) -> TestAccount {
+        TestAccount::key(key).owned_by(spl_token_2022::id())
+    }
+
+    fn mint_account(&self) -> TestAccount {
+        TestAccount::key(self.mint).owned_by(spl_token_2022::id())
+    }
+
+    fn user(&self) -> TestAccount {
+        TestAccount::key(self.user).signer()
+    }
+
+    fn authority(&self) -> TestAccount {
+        TestAccount::key(self.authority).signer()
+    }
+
+    /// Stake accounts: pool, user stake, vault, user token, mint, user,
+    /// system program, token program
+    fn stake_accounts(
+        &self,
+        pool: TestAccount,
+        stake: TestAccount,
+        vault: Pubkey,
+    ) -> Vec<TestAccount> {
+        vec![
+            pool,
+            stake,
+            self.token_account(vault),
+            self.token_account(Pubkey::new_unique()),
+            self.mint_account(),
+            self.user(),
+            TestAccount::key(system_program::id()),
+            TestAccount::key(spl_token_2022::id()),
+        ]
+    }
+
+    /// Unstake accounts: pool, user stake, vault, user token, mint, user,
+    /// token program
+    fn unstake_accounts(
+        &self,
+        pool: TestAccount,
+        stake: TestAccount,
+        vault: Pubkey,
+    ) -> Vec<TestAccount> {
+        vec![
+            pool,
+            stake,
+            self.token_account(vault),
+            self.token_account(Pubkey::new_unique()),
+            self.mint_account(),
+            self.user(),
+            TestAccount::key(spl_token_2022::id()),
+        ]
+    }
+}
+
+#[test]
+fn test_mismatched_pools() {
+    let w = World::new();
+    let (a, b) = (&w.pool_a, &w.pool_b);
+
+    // The user's pool B stake, offered to pool A
+    assert_rejected(
+        "unstake",
+        StakingInstruction::Unstake { amount: 10 },
+        &mut w.unstake_accounts(w.pool(a), w.stake(b, 100), w.vault_a),
+        StakingError::InvalidPool,
+    );
+    assert_rejected(
+        "claim",
+        StakingInstruction::ClaimRewards,
+        &mut [w.pool(a), w.stake(b, 100), w.user()],
+        StakingError::InvalidPool,
+    );
+    assert_rejected(
+        "request unstake",
+        StakingInstruction::RequestUnstake { amount: 10 },
+        &mut [w.cooldown_pool(a), w.stake(b, 100), w.user()],
+        StakingError::InvalidPool,
+    );
+    assert_rejected(
+        "extend lock",
+        StakingInstruction::ExtendLock { additional_seconds: 60 },
+        &mut [w.pool_with(a, |state| state.lock_boost_bps = 5_000), w.stake(b, 100), w.user()],
+        StakingError::InvalidPool,
+    );
+    assert_rejected(
+        "close stake",
+        StakingInstruction::CloseStakeAccount,
+        &mut [w.pool(a), w.stake(b, 0), w.user()],
+        StakingError::InvalidPool,
+    );
+    assert_rejected(
+        "position view",
+        StakingInstruction::GetUserPosition,
+        &mut [w.pool(a), w.stake(b, 100)],
+        StakingError::InvalidPool,
+    );
+    assert_rejected(
+        "freeze",
+        StakingInstruction::SetStakeFrozen { frozen: true },
+        &mut [w.pool(a), w.stake(b, 100), w.authority()],
+        StakingError::InvalidPool,
+    );
+
+    // A stake carrying pool B's data at pool A's stake address
+    let mut misplaced = w.stake(b, 100);
+    misplaced.key = w.stake_key(a, &w.user);
+    assert_rejected(
+        "claim, misplaced stake",
+        StakingInstruction::ClaimRewards,
+        &mut [w.pool(a), misplaced, w.user()],
+        StakingError::InvalidPool,
+    );
+
+    // Pool A's data at another address, or owned by another program
+    let mut spoofed = w.pool(a);
+    spoofed.key = Pubkey::new_unique();
+    assert_rejected(
+        "claim, spoofed pool",
+        StakingInstruction::ClaimRewards,
+        &mut [spoofed, w.stake(a, 100), w.user()],
+        StakingError::InvalidPDA,
+    );
+    assert_rejected(
+        "deposit, foreign pool",
+        StakingInstruction::DepositRewards { amount: 1_000 },
+        &mut [
+            w.pool(a).owned_by(Pubkey::new_unique()),
+            w.user(),
+            TestAccount::key(system_program::id()),
+        ],
+        StakingError::InvalidAccountOwner,
+    );
+}
+
+#[test]
+fn test_stolen_vaults() {
+    let w = World::new();
+    let a = &w.pool_a;
+    let stake_key = w.stake_key(a, &w.user);
+
+    // Pool B's vault, or any token account, in place of pool A's
+    for vault in [w.vault_b, Pubkey::new_unique()] {
+        assert_rejected(
+            "stake",
+            StakingInstruction::Stake { amount: 10 },
+            &mut w.stake_accounts(w.pool(a), TestAccount::key(stake_key), vault),
+            StakingError::InvalidTokenVault,
+        );
+        assert_rejected(
+            "unstake",
+            StakingInstruction::Unstake { amount: 10 },
+            &mut w.unstake_accounts(w.pool(a), w.stake(a, 100), vault),
+            StakingError::InvalidTokenVault,
+        );
+        assert_rejected(
+            "migrate vault",
+            StakingInstruction::MigrateVault,
+            &mut [
+                w.pool(a),
+                w.mint_account(),
+                w.token_account(vault),
+                TestAccount::key(Pubkey::new_unique()),
+                w.authority(),
+                TestAccount::key(system_program::id()),
+                TestAccount::key(spl_token_2022::id()),
+            ],
+            StakingError::InvalidTokenVault,
+        );
+    }
+
+    // The vault is checked against the pool, not just the mint
+    let mut other_mint = w.stake_accounts(w.pool(a), TestAccount::key(stake_key), w.vault_a);
+    other_mint[4] = TestAccount::key(Pubkey::new_unique()).owned_by(spl_token_2022::id());
+    assert_rejected(
+        "stake, other mint",
+        StakingInstruction::Stake { amount: 10 },
+        &mut other_mint,
+        StakingError::InvalidPoolMint,
+    );
+}
+
+#[test]
+fn test_spoofed_metadata() {
+    let w = World::new();
+    let (a, b) = (&w.pool_a, &w.pool_b);
+
+    // Pool B's metadata, or pool A's address owned by another program
+    assert_rejected(
+        "close metadata, other pool's",
+        StakingInstruction::ClosePoolMetadata,
+        &mut [w.pool(a), w.authority(), w.metadata(b, 1)],
+        StakingError::InvalidPDA,
+    );
+    assert_rejected(
+        "close metadata, foreign",
+        StakingInstruction::ClosePoolMetadata,
+        &mut [w.pool(a), w.authority(), w.metadata(a, 1).owned_by(Pubkey::new_unique())],
+        StakingError::InvalidAccountOwner,
+    );
+
+    // Cloning pool A into a new pool id copies only pool A's metadata
+    let program_id = chiefstaker::id();
+    let (new_pool, _) = StakingPool::derive_pda_with_id(&w.mint, 2, &program_id);
+    let clone_accounts = |source_metadata: TestAccount| {
+        vec![
+            TestAccount::key(new_pool),
+            w.mint_account(),
+            TestAccount::key(StakingPool::derive_token_vault_pda(&new_pool, &program_id).0),
+            w.authority(),
+            TestAccount::key(system_program::id()),
+            TestAccount::key(spl_token_2022::id()),
+            TestAccount::key(sysvar::rent::id()),
+            w.pool(a),
+            source_metadata,
+            TestAccount::key(w.metadata_key(&new_pool)),
+        ]
+    };
+    let clone = || StakingInstruction::ClonePoolConfig {
+        source_pool: w.pool_a,
+        tau_seconds: 604_800,
+        pool_id: 2,
+    };
+    assert_rejected(
+        "clone, other pool's metadata",
+        clone(),
+        &mut clone_accounts(w.metadata(b, 1)),
+        StakingError::InvalidPDA,
+    );
+    assert_rejected(
+        "clone, foreign metadata",
+        clone(),
+        &mut clone_accounts(w.metadata(a, 1).owned_by(Pubkey::new_unique())),
+        StakingError::InvalidAccountOwner,
+    );
+    let mut wrong_pool = w.metadata(b, 1);
+    wrong_pool.key = w.metadata_key(a);
+    assert_rejected(
+        "clone, metadata of another pool at the PDA",
+        clone(),
+        &mut clone_accounts(wrong_pool),
+        StakingError::InvalidPool,
+    );
+    let mut other_source = clone_accounts(w.metadata(a, 1));
+    other_source[7] = w.pool(b);
+    assert_rejected(
+        "clone, source pool swapped",
+        clone(),
+        &mut other_source,
+        StakingError::InvalidPool,
+    );
+
+    // CloseStakeAccount only decrements the pool's own metadata; a spoofed
+    // one is left alone
+    let mut spoofed = w.metadata(b, 5);
+    spoofed.key = Pubkey::new_unique();
+    for (metadata, expected_count) in [(w.metadata(b, 5), 5), (spoofed, 5), (w.metadata(a, 5), 4)] {
+        let mut accounts = [w.pool(a), w.stake(a, 0), w.user(), metadata];
+        run(StakingInstruction::CloseStakeAccount, &mut accounts).unwrap();
+        let metadata = borsh::from_slice::<PoolMetadata>(&accounts[3].data).unwrap();
+        assert_eq!(metadata.member_count, expected_count);
+        assert_eq!(accounts[1].lamports, 0);
+    }
+}
+
+#[test]
+fn test_wrong_token_programs() {
+    let w = World::new();
+    let a = &w.pool_a;
+    let stake_key = w.stake_key(a, &w.user);
+
+    for token_program in [LEGACY_TOKEN_PROGRAM, system_program::id()] {
+        let mut stake = w.stake_accounts(w.pool(a), TestAccount::key(stake_key), w.vault_a);
+        stake[7] = TestAccount::key(token_program);
+        assert_rejected(
+            "stake",
+            StakingInstruction::Stake { amount: 10 },
+            &mut stake,
+            StakingError::InvalidTokenProgram,
+        );
+
+        let mut unstake = w.unstake_accounts(w.pool(a), w.stake(a, 100), w.vault_a);
+        unstake[6] = TestAccount::key(token_program);
+        assert_rejected(
+            "unstake",
+            StakingInstruction::Unstake { amount: 10 },
+            &mut unstake,
+            StakingError::InvalidTokenProgram,
+        );
+
+        let (new_pool, _) = StakingPool::derive_pda_with_id(&w.mint, 0, &chiefstaker::id());
+        assert_rejected(
+            "initialize",
+            StakingInstruction::InitializePool { tau_seconds: 86_400 },
+            &mut [
+                TestAccount::key(new_pool),
+                w.mint_account(),
+                TestAccount::key(w.vault_a),
+                w.authority(),
+                TestAccount::key(system_program::id()),
+                TestAccount::key(token_program),
+                TestAccount::key(sysvar::rent::id()),
+            ],
+            StakingError::InvalidTokenProgram,
+        );
+
+        assert_rejected(
+            "migrate vault",
+            StakingInstruction::MigrateVault,
+            &mut [
+                w.pool(a),
+                w.mint_account(),
+                w.token_account(w.vault_a),
+                TestAccount::key(Pubkey::new_unique()),
+                w.authority(),
+                TestAccount::key(system_program::id()),
+                TestAccount::key(token_program),
+            ],
+            StakingError::InvalidTokenProgram,
+        );
+    }
+
+    // A mint owned by the legacy token program can't back a pool
+    let (new_pool, _) = StakingPool::derive_pda_with_id(&w.mint, 3, &chiefstaker::id());
+    assert_rejected(
+        "initialize, legacy mint",
+        StakingInstruction::InitializePoolWithId { tau_seconds: 86_400, pool_id: 3 },
+        &mut [
+            TestAccount::key(new_pool),
+            w.mint_account().owned_by(LEGACY_TOKEN_PROGRAM),
+            TestAccount::key(StakingPool::derive_token_vault_pda(&new_pool, &chiefstaker::id()).0),
+            w.authority(),
+            TestAccount::key(system_program::id()),
+            TestAccount::key(spl_token_2022::id()),
+            TestAccount::key(sysvar::rent::id()),
+        ],
+        StakingError::InvalidMintProgram,
+    );
+}
+
+#[test]
+fn test_foreign_stakes() {
+    let w = World::new();
+    let a = &w.pool_a;
+    let foreign = || w.stake(a, 100).owned_by(Pubkey::new_unique());
+
+    // A stake account owned by another program
+    assert_rejected(
+        "unstake",
+        StakingInstruction::Unstake { amount: 10 },
+        &mut w.unstake_accounts(w.pool(a), foreign(), w.vault_a),
+        StakingError::InvalidAccountOwner,
+    );
+    assert_rejected(
+        "claim",
+        StakingInstruction::ClaimRewards,
+        &mut [w.pool(a), foreign(), w.user()],
+        StakingError::InvalidAccountOwner,
+    );
+    assert_rejected(
+        "request unstake",
+        StakingInstruction::RequestUnstake { amount: 10 },
+        &mut [w.cooldown_pool(a), foreign(), w.user()],
+        StakingError::InvalidAccountOwner,
+    );
+    assert_rejected(
+        "close stake",
+        StakingInstruction::CloseStakeAccount,
+        &mut [w.pool(a), w.stake(a, 0).owned_by(Pubkey::new_unique()), w.user()],
+        StakingError::InvalidAccountOwner,
+    );
+    assert_rejected(
+        "position view",
+        StakingInstruction::GetUserPosition,
+        &mut [w.pool(a), foreign()],
+        StakingError::InvalidAccountOwner,
+    );
+    assert_rejected(
+        "freeze",
+        StakingInstruction::SetStakeFrozen { frozen: true },
+        &mut [w.pool(a), foreign(), w.authority()],
+        StakingError::InvalidAccountOwner,
+    );
+
+    // The user's stake, signed for by someone else
+    let thief = || TestAccount::key(Pubkey::new_unique()).signer();
+    assert_rejected(
+        "claim, other signer",
+        StakingInstruction::ClaimRewards,
+        &mut [w.pool(a), w.stake(a, 100), thief()],
+        StakingError::InvalidOwner,
+    );
+    assert_rejected(
+        "request unstake, other signer",
+        StakingInstruction::RequestUnstake { amount: 10 },
+        &mut [w.cooldown_pool(a), w.stake(a, 100), thief()],
+        StakingError::InvalidOwner,
+    );
+    assert_rejected(
+        "close stake, other signer",
+        StakingInstruction::CloseStakeAccount,
+        &mut [w.pool(a), w.stake(a, 0), thief()],
+        StakingError::InvalidOwner,
+    );
+    let mut unstake = w.unstake_accounts(w.pool(a), w.stake(a, 100), w.vault_a);
+    unstake[5] = thief();
+    assert_rejected(
+        "unstake, other signer",
+        StakingInstruction::Unstake { amount: 10 },
+        &mut unstake,
+        StakingError::InvalidOwner,
+    );
+
+    // Topping up someone else's position: the stake PDA follows the signer
+    let mut stake = w.stake_accounts(w.pool(a), w.stake(a, 100), w.vault_a);
+    stake[5] = thief();
+    assert_rejected(
+        "stake, other signer",
+        StakingInstruction::Stake { amount: 10 },
+        &mut stake,
+        StakingError::InvalidPDA,
+    );
+}
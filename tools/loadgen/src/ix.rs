@@ -0,0 +1,110 @@
+//! Instruction builders for the handful of instructions the load uses
+
+use chiefstaker_interface::{instruction::StakingInstruction, ID};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+
+fn instruction(accounts: Vec<AccountMeta>, ix: StakingInstruction) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts,
+        data: ix.pack(),
+    }
+}
+
+pub fn initialize_pool(
+    pool: &Pubkey,
+    mint: &Pubkey,
+    vault: &Pubkey,
+    authority: &Pubkey,
+    tau_seconds: u64,
+) -> Instruction {
+    instruction(
+        vec![
+            AccountMeta::new(*pool, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(spl_token_2022::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::ID, false),
+        ],
+        StakingInstruction::InitializePool { tau_seconds },
+    )
+}
+
+pub fn stake(
+    pool: &Pubkey,
+    user_stake: &Pubkey,
+    vault: &Pubkey,
+    user_token: &Pubkey,
+    mint: &Pubkey,
+    user: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    instruction(
+        vec![
+            AccountMeta::new(*pool, false),
+            AccountMeta::new(*user_stake, false),
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(*user_token, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(spl_token_2022::id(), false),
+        ],
+        StakingInstruction::Stake { amount },
+    )
+}
+
+pub fn unstake(
+    pool: &Pubkey,
+    user_stake: &Pubkey,
+    vault: &Pubkey,
+    user_token: &Pubkey,
+    mint: &Pubkey,
+    user: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    instruction(
+        vec![
+            AccountMeta::new(*pool, false),
+            AccountMeta::new(*user_stake, false),
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(*user_token, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(spl_token_2022::id(), false),
+        ],
+        StakingInstruction::Unstake { amount },
+    )
+}
+
+pub fn claim(pool: &Pubkey, user_stake: &Pubkey, user: &Pubkey) -> Instruction {
+    instruction(
+        vec![
+            AccountMeta::new(*pool, false),
+            AccountMeta::new(*user_stake, false),
+            AccountMeta::new(*user, true),
+        ],
+        StakingInstruction::ClaimRewards,
+    )
+}
+
+pub fn deposit_rewards(pool: &Pubkey, depositor: &Pubkey, amount: u64) -> Instruction {
+    instruction(
+        vec![
+            AccountMeta::new(*pool, false),
+            AccountMeta::new(*depositor, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        StakingInstruction::DepositRewards { amount },
+    )
+}
+
+pub fn sync_pool(pool: &Pubkey) -> Instruction {
+    instruction(vec![AccountMeta::new(*pool, false)], StakingInstruction::SyncPool)
+}
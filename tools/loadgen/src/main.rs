@@ -0,0 +1,394 @@
+//! ChiefStaker load generator
+//!
+//! Spins up a local bank (solana-program-test) with the program and Token
+//! 2022 loaded, creates a mint and a pool, then drives thousands of
+//! randomized stake / claim / unstake / deposit / sync transactions from a
+//! set of users while moving the clock forward by random amounts (with
+//! occasional jumps of many tau to force rebases). Every `--report-every`
+//! operations, and at the end, it re-reads the pool and every position and
+//! prints an invariant report; the exit code is 1 if any check failed.
+//!
+//! Usage:
+//!   cargo run --manifest-path tools/loadgen/Cargo.toml --release -- \
+//!       [--users N] [--ops N] [--seed N] [--tau SECONDS] [--report-every N]
+
+mod ix;
+mod report;
+
+use std::{collections::BTreeMap, process::ExitCode};
+
+use borsh::BorshDeserialize;
+use chiefstaker::state::{StakingPool, UserStake};
+use chiefstaker_interface::{
+    error::StakingError,
+    pda::{find_pool_address, find_stake_address, find_token_vault_address},
+    ID,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    clock::Clock,
+    compute_budget::ComputeBudgetInstruction,
+    instruction::{Instruction, InstructionError},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::{Transaction, TransactionError},
+};
+use spl_token_2022::state::{Account as TokenAccount, Mint};
+
+use crate::report::{Report, Snapshot};
+
+/// Tokens minted to each user (6 decimals)
+const USER_TOKENS: u64 = 1_000_000_000_000;
+
+/// SOL given to each user for stake account rent
+const USER_LAMPORTS: u64 = 10_000_000_000;
+
+struct Args {
+    users: usize,
+    ops: u64,
+    seed: u64,
+    tau: u64,
+    report_every: u64,
+}
+
+impl Args {
+    fn parse() -> Result<Self, String> {
+        let mut args = Args {
+            users: 16,
+            ops: 2_000,
+            seed: 1,
+            tau: 3_600,
+            report_every: 500,
+        };
+        let mut iter = std::env::args().skip(1);
+        while let Some(flag) = iter.next() {
+            let value = iter.next().ok_or(format!("{} needs a value", flag))?;
+            let number = value
+                .parse::<u64>()
+                .map_err(|_| format!("{}: not a number: {}", flag, value))?;
+            match flag.as_str() {
+                "--users" => args.users = number as usize,
+                "--ops" => args.ops = number,
+                "--seed" => args.seed = number,
+                "--tau" => args.tau = number,
+                "--report-every" => args.report_every = number.max(1),
+                _ => return Err(format!("unknown flag {}", flag)),
+            }
+        }
+        if args.users == 0 || args.tau == 0 {
+            return Err("--users and --tau must be positive".to_string());
+        }
+        Ok(args)
+    }
+}
+
+struct User {
+    keypair: Keypair,
+    token_account: Pubkey,
+    stake: Pubkey,
+    staked: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Op {
+    Stake,
+    Claim,
+    Unstake,
+    Deposit,
+    Sync,
+}
+
+struct Harness {
+    ctx: ProgramTestContext,
+    slot: u64,
+    now: i64,
+    nonce: u64,
+}
+
+impl Harness {
+    /// Send `ixs` signed by the payer and `signers`. A program error comes
+    /// back as `Err(Some(code))`, anything else as `Err(None)`.
+    async fn send(&mut self, ixs: &[Instruction], signers: &[&Keypair]) -> Result<(), Option<u32>> {
+        // A distinct compute price keeps otherwise identical transactions
+        // from being deduplicated within one blockhash
+        self.nonce += 1;
+        let mut all = vec![ComputeBudgetInstruction::set_compute_unit_price(self.nonce)];
+        all.extend_from_slice(ixs);
+        let mut keys: Vec<&Keypair> = vec![&self.ctx.payer];
+        keys.extend_from_slice(signers);
+        let tx = Transaction::new_signed_with_payer(
+            &all,
+            Some(&self.ctx.payer.pubkey()),
+            &keys,
+            self.ctx.last_blockhash,
+        );
+        match self.ctx.banks_client.process_transaction(tx).await {
+            Ok(()) => Ok(()),
+            Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+                _,
+                InstructionError::Custom(code),
+            ))) => Err(Some(code)),
+            Err(_) => Err(None),
+        }
+    }
+
+    /// Move to the next slot and `seconds` later
+    async fn advance(&mut self, seconds: i64) {
+        self.slot += 1;
+        self.now += seconds;
+        self.ctx.warp_to_slot(self.slot).expect("warp");
+        let mut clock: Clock = self.ctx.banks_client.get_sysvar().await.expect("clock");
+        clock.unix_timestamp = self.now;
+        self.ctx.set_sysvar(&clock);
+    }
+
+    async fn account_data(&mut self, key: &Pubkey) -> Option<(u64, Vec<u8>)> {
+        self.ctx
+            .banks_client
+            .get_account(*key)
+            .await
+            .expect("get_account")
+            .map(|a| (a.lamports, a.data))
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = match Args::parse() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("loadgen: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+    let mut rng = StdRng::seed_from_u64(args.seed);
+
+    let program_test = ProgramTest::new("chiefstaker", ID, processor!(chiefstaker::process_instruction));
+    let mut ctx = program_test.start_with_context().await;
+    let mut clock: Clock = ctx.banks_client.get_sysvar().await.expect("clock");
+    let mut h = Harness {
+        slot: clock.slot.max(1),
+        now: clock.unix_timestamp,
+        nonce: 0,
+        ctx,
+    };
+    clock.unix_timestamp = h.now;
+    h.ctx.set_sysvar(&clock);
+    let rent = h.ctx.banks_client.get_rent().await.expect("rent");
+    let payer = h.ctx.payer.pubkey();
+
+    // Mint and pool
+    let mint = Keypair::new();
+    let (pool, _) = find_pool_address(&mint.pubkey(), 0);
+    let (vault, _) = find_token_vault_address(&pool);
+    h.send(
+        &[
+            system_instruction::create_account(
+                &payer,
+                &mint.pubkey(),
+                rent.minimum_balance(Mint::LEN),
+                Mint::LEN as u64,
+                &spl_token_2022::id(),
+            ),
+            spl_token_2022::instruction::initialize_mint2(
+                &spl_token_2022::id(),
+                &mint.pubkey(),
+                &payer,
+                None,
+                6,
+            )
+            .expect("initialize_mint2"),
+            ix::initialize_pool(&pool, &mint.pubkey(), &vault, &payer, args.tau),
+        ],
+        &[&mint],
+    )
+    .await
+    .expect("create mint and pool");
+
+    // Users with tokens and SOL
+    let mut users = Vec::with_capacity(args.users);
+    for _ in 0..args.users {
+        let keypair = Keypair::new();
+        let token_account = Keypair::new();
+        h.send(
+            &[
+                system_instruction::transfer(&payer, &keypair.pubkey(), USER_LAMPORTS),
+                system_instruction::create_account(
+                    &payer,
+                    &token_account.pubkey(),
+                    rent.minimum_balance(TokenAccount::LEN),
+                    TokenAccount::LEN as u64,
+                    &spl_token_2022::id(),
+                ),
+                spl_token_2022::instruction::initialize_account3(
+                    &spl_token_2022::id(),
+                    &token_account.pubkey(),
+                    &mint.pubkey(),
+                    &keypair.pubkey(),
+                )
+                .expect("initialize_account3"),
+                spl_token_2022::instruction::mint_to(
+                    &spl_token_2022::id(),
+                    &mint.pubkey(),
+                    &token_account.pubkey(),
+                    &payer,
+                    &[],
+                    USER_TOKENS,
+                )
+                .expect("mint_to"),
+            ],
+            &[&token_account],
+        )
+        .await
+        .expect("create user");
+        let (stake, _) = find_stake_address(&pool, &keypair.pubkey());
+        users.push(User {
+            keypair,
+            token_account: token_account.pubkey(),
+            stake,
+            staked: 0,
+        });
+    }
+
+    let mut outcomes: BTreeMap<(Op, String), u64> = BTreeMap::new();
+    let mut deposited: u64 = 0;
+    let mut rebases: u64 = 0;
+    let mut last_base_time = None;
+    let mut previous_acc_rps = 0u128;
+    let mut failed = false;
+
+    for op_index in 1..=args.ops {
+        // Mostly short steps, occasionally many tau at once to force a rebase
+        let step = if rng.gen_ratio(1, 100) {
+            rng.gen_range(5 * args.tau..=40 * args.tau)
+        } else {
+            rng.gen_range(0..=args.tau / 8)
+        };
+        h.advance(step as i64).await;
+
+        let op = match rng.gen_range(0..100) {
+            0..=29 => Op::Stake,
+            30..=54 => Op::Claim,
+            55..=74 => Op::Unstake,
+            75..=92 => Op::Deposit,
+            _ => Op::Sync,
+        };
+        let user = &mut users[rng.gen_range(0..args.users)];
+        let result = match op {
+            Op::Stake => {
+                let amount = rng.gen_range(1..=(USER_TOKENS - user.staked) / 4 + 1);
+                let ix = ix::stake(
+                    &pool,
+                    &user.stake,
+                    &vault,
+                    &user.token_account,
+                    &mint.pubkey(),
+                    &user.keypair.pubkey(),
+                    amount,
+                );
+                let result = h.send(&[ix], &[&user.keypair]).await;
+                if result.is_ok() {
+                    user.staked += amount;
+                }
+                result
+            }
+            Op::Claim => {
+                let ix = ix::claim(&pool, &user.stake, &user.keypair.pubkey());
+                h.send(&[ix], &[&user.keypair]).await
+            }
+            Op::Unstake => {
+                let amount = if user.staked == 0 || rng.gen_ratio(1, 4) {
+                    user.staked
+                } else {
+                    rng.gen_range(1..=user.staked)
+                };
+                let ix = ix::unstake(
+                    &pool,
+                    &user.stake,
+                    &vault,
+                    &user.token_account,
+                    &mint.pubkey(),
+                    &user.keypair.pubkey(),
+                    amount,
+                );
+                let result = h.send(&[ix], &[&user.keypair]).await;
+                if result.is_ok() {
+                    user.staked -= amount;
+                }
+                result
+            }
+            Op::Deposit => {
+                // Wide range so both dust and large deposits occur
+                let amount = 10u64.pow(rng.gen_range(0..10)) * rng.gen_range(1..10);
+                let result = h.send(&[ix::deposit_rewards(&pool, &payer, amount)], &[]).await;
+                if result.is_ok() {
+                    deposited += amount;
+                }
+                result
+            }
+            Op::Sync => h.send(&[ix::sync_pool(&pool)], &[]).await,
+        };
+        let outcome = match result {
+            Ok(()) => "ok".to_string(),
+            Err(Some(code)) => match StakingError::try_from(code) {
+                Ok(e) => format!("{:?}", e),
+                Err(_) => format!("custom {}", code),
+            },
+            Err(None) => "other error".to_string(),
+        };
+        *outcomes.entry((op, outcome)).or_default() += 1;
+
+        if op_index % args.report_every == 0 || op_index == args.ops {
+            let (pool_lamports, pool_data) = h.account_data(&pool).await.expect("pool");
+            let pool_state = StakingPool::try_from_slice(&pool_data).expect("pool state");
+            if last_base_time.is_some_and(|t| t != pool_state.base_time) {
+                rebases += 1;
+            }
+            last_base_time = Some(pool_state.base_time);
+
+            let mut stakes = Vec::new();
+            for user in &users {
+                if let Some((_, data)) = h.account_data(&user.stake).await {
+                    stakes.push(UserStake::try_from_slice(&data).expect("stake state"));
+                }
+            }
+            let (_, vault_data) = h.account_data(&vault).await.expect("vault");
+            let vault_amount = TokenAccount::unpack_from_slice(&vault_data[..TokenAccount::LEN])
+                .expect("vault state")
+                .amount;
+
+            let report = Report::new(
+                op_index,
+                &Snapshot {
+                    pool: &pool_state,
+                    stakes: &stakes,
+                    vault_amount,
+                    pool_lamports,
+                    rent_exempt_minimum: rent.minimum_balance(pool_data.len()),
+                    deposited,
+                    previous_acc_rps,
+                },
+            );
+            previous_acc_rps = pool_state.acc_reward_per_weighted_share;
+            failed |= !report.is_ok();
+            print!("{}", report);
+        }
+    }
+
+    println!("--- operations (seed {}, {} users, tau {}s) ---", args.seed, args.users, args.tau);
+    for ((op, outcome), count) in &outcomes {
+        println!("  {:<8} {:<32} {}", format!("{:?}", op), outcome, count);
+    }
+    println!("  base_time changes seen at report points: {}", rebases);
+
+    if failed {
+        println!("FAILED: invariant violations above");
+        ExitCode::from(1)
+    } else {
+        println!("PASSED");
+        ExitCode::SUCCESS
+    }
+}
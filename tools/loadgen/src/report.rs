@@ -0,0 +1,138 @@
+//! Invariant report over the pool and every position in it
+
+use std::fmt;
+
+use chiefstaker::{
+    invariants::check_pool,
+    math::{WAD, U256},
+    state::{StakingPool, UserStake},
+};
+
+/// Accounting snapshot taken between operations
+pub struct Report {
+    pub ops: u64,
+    pub lines: Vec<String>,
+    pub violations: Vec<String>,
+}
+
+/// Chain-side values the report is computed from
+pub struct Snapshot<'a> {
+    pub pool: &'a StakingPool,
+    pub stakes: &'a [UserStake],
+    pub vault_amount: u64,
+    pub pool_lamports: u64,
+    pub rent_exempt_minimum: u64,
+    pub deposited: u64,
+    pub previous_acc_rps: u128,
+}
+
+impl Report {
+    pub fn new(ops: u64, snap: &Snapshot) -> Self {
+        let mut report = Report {
+            ops,
+            lines: Vec::new(),
+            violations: Vec::new(),
+        };
+        let pool = snap.pool;
+
+        // Aggregates the program maintains incrementally vs. the positions
+        if let Err(e) = check_pool(pool, snap.stakes, Some(snap.vault_amount)) {
+            report.violations.push(format!("check_pool: {}", e));
+        }
+
+        // Every deposited lamport is either still in the pool or was paid out
+        let paid: u128 = snap.stakes.iter().map(|s| s.total_rewards_claimed as u128).sum();
+        let held = snap.pool_lamports.saturating_sub(snap.rent_exempt_minimum) as u128;
+        report.lines.push(format!(
+            "lamports: deposited {} = paid {} + held {} ({})",
+            snap.deposited,
+            paid,
+            held,
+            if paid + held == snap.deposited as u128 { "ok" } else { "MISMATCH" }
+        ));
+        if paid + held != snap.deposited as u128 {
+            report.violations.push(format!(
+                "conservation: deposited {} != paid {} + held {}",
+                snap.deposited, paid, held
+            ));
+        }
+
+        // Everything still owed at full weight must be covered, up to one
+        // lamport of rounding per position
+        let owed_wad = snap.stakes.iter().try_fold(U256::from_u128(0), |sum, s| {
+            let owed = if s.amount == 0 {
+                s.reward_debt
+            } else {
+                s.pending_rewards_wad(s.amount as u128 * WAD, pool.acc_reward_per_weighted_share)
+                    .ok()?
+            };
+            sum.checked_add(U256::from_u128(owed))
+        });
+        match owed_wad {
+            Some(owed_wad) => {
+                let owed = (owed_wad / U256::from_u128(WAD)).to_u128().unwrap_or(u128::MAX);
+                let available = held.saturating_sub(pool.protocol_fees_owed as u128);
+                report.lines.push(format!(
+                    "solvency: owed at full weight {} <= available {} ({} spare)",
+                    owed,
+                    available,
+                    available as i128 - owed as i128
+                ));
+                if owed > available + snap.stakes.len() as u128 {
+                    report.violations.push(format!(
+                        "solvency: owed {} exceeds available {}",
+                        owed, available
+                    ));
+                }
+            }
+            None => report.violations.push("solvency: owed overflows".to_string()),
+        }
+
+        // The accumulator only ever grows (rebases rescale sum_stake_exp only)
+        if pool.acc_reward_per_weighted_share < snap.previous_acc_rps {
+            report.violations.push(format!(
+                "accumulator went backwards: {} -> {}",
+                snap.previous_acc_rps, pool.acc_reward_per_weighted_share
+            ));
+        }
+
+        report.lines.push(format!(
+            "pool: staked {} by {} positions, acc_rps {}, base_time {}, dust {} wad",
+            pool.total_staked,
+            snap.stakes.iter().filter(|s| s.amount > 0).count(),
+            pool.acc_reward_per_weighted_share,
+            pool.base_time,
+            pool.accumulated_dust_wad
+        ));
+        report.lines.push(format!(
+            "debt: total_reward_debt {}, residual unpaid {}, stranded {}",
+            pool.total_reward_debt,
+            pool.total_residual_unpaid,
+            pool.stranded_lamports().unwrap_or(0)
+        ));
+
+        report
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "--- after {} ops: {} ---",
+            self.ops,
+            if self.is_ok() { "OK" } else { "VIOLATIONS" }
+        )?;
+        for line in &self.lines {
+            writeln!(f, "  {}", line)?;
+        }
+        for violation in &self.violations {
+            writeln!(f, "  !! {}", violation)?;
+        }
+        Ok(())
+    }
+}